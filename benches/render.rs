@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rustdash::bench::synthetic_state;
+use rustdash::ui::{truncate_message, wrap_message};
+
+fn bench_message_layout(c: &mut Criterion) {
+    let short = "GET /api/items/42 status=200 took=12ms";
+    let long = "request id=1234 path=/api/items/42 status=200 took=12ms ".repeat(40);
+
+    let mut group = c.benchmark_group("message_layout");
+    for width in [80usize, 160, 300] {
+        group.bench_with_input(BenchmarkId::new("truncate_short", width), &width, |b, &w| {
+            b.iter(|| truncate_message(black_box(short), w))
+        });
+        group.bench_with_input(BenchmarkId::new("truncate_long", width), &width, |b, &w| {
+            b.iter(|| truncate_message(black_box(&long), w))
+        });
+        group.bench_with_input(BenchmarkId::new("wrap_long", width), &width, |b, &w| {
+            b.iter(|| wrap_message(black_box(&long), w))
+        });
+    }
+    group.finish();
+}
+
+fn bench_visible_window(c: &mut Criterion) {
+    let mut group = c.benchmark_group("visible_window");
+    for log_count in [1_000usize, 100_000] {
+        let mut state = synthetic_state(log_count, 0);
+        group.bench_with_input(BenchmarkId::from_parameter(log_count), &log_count, |b, &n| {
+            let mut offset = 0;
+            b.iter(|| {
+                offset = (offset + 7) % n;
+                state.log_scroll_offset = offset;
+                state.update_visible_logs_with_height(black_box(50));
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_message_layout, bench_visible_window);
+criterion_main!(benches);
@@ -0,0 +1,145 @@
+//! Maintenance actions against the backends' admin APIs, offered from the
+//! admin menu (`A`) for backends configured with `admin = true`: TSDB
+//! snapshots and tombstone cleanup on Prometheus, delete requests on Loki.
+//!
+//! Nothing runs without an explicit `y` on a confirmation that spells out
+//! what the action does and where.
+
+use crate::audit::AuditEntry;
+use crate::loki::{LogEntry, LokiClient};
+use crate::prometheus::PrometheusClient;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminAction {
+    PrometheusSnapshot,        // POST /api/v1/admin/tsdb/snapshot
+    PrometheusCleanTombstones, // POST /api/v1/admin/tsdb/clean_tombstones
+    // POST /loki/api/v1/delete
+    LokiDelete {
+        selector: String, // Exact stream selector
+        start: i64,       // Unix seconds, inclusive
+        end: i64,
+    },
+}
+
+impl AdminAction {
+    /// Actions offered for the backends that have admin enabled.
+    pub fn available(prometheus_admin: bool) -> Vec<AdminAction> {
+        let mut actions = Vec::new();
+        if prometheus_admin {
+            actions.push(AdminAction::PrometheusSnapshot);
+            actions.push(AdminAction::PrometheusCleanTombstones);
+        }
+        actions
+    }
+
+    /// Delete request for the stream of `logs[selected]`, spanning every
+    /// buffered Loki line of that stream (whole seconds, rounded outwards).
+    /// None for a line from a local file, whose selector Loki may share.
+    pub fn delete_stream(logs: &[LogEntry], selected: usize) -> Option<AdminAction> {
+        let log = logs.get(selected).filter(|log| !log.local)?;
+        let selector = &log.stream;
+        if selector.is_empty() || selector == "{}" {
+            return None;
+        }
+        let (first, last) = logs
+            .iter()
+            .filter(|log| !log.local && &log.stream == selector)
+            .fold((i64::MAX, i64::MIN), |(first, last), log| (first.min(log.nanos), last.max(log.nanos)));
+        Some(AdminAction::LokiDelete {
+            selector: selector.clone(),
+            start: first.div_euclid(1_000_000_000),
+            end: last.div_euclid(1_000_000_000) + 1,
+        })
+    }
+
+    /// Whether the action removes data for good, which the confirmation calls out.
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, AdminAction::LokiDelete { .. })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            AdminAction::PrometheusSnapshot => "Prometheus: create TSDB snapshot".to_string(),
+            AdminAction::PrometheusCleanTombstones => "Prometheus: clean tombstones".to_string(),
+            AdminAction::LokiDelete { selector, .. } => format!("Loki: delete logs of {}", selector),
+        }
+    }
+
+    /// Audit entry for running the action against `target`, the server URL.
+    pub fn audit_entry(&self, target: &str) -> AuditEntry {
+        match self {
+            AdminAction::PrometheusSnapshot => AuditEntry::new("prometheus_snapshot", target),
+            AdminAction::PrometheusCleanTombstones => AuditEntry::new("prometheus_clean_tombstones", target),
+            AdminAction::LokiDelete { selector, start, end } => AuditEntry::new("loki_delete", target)
+                .param("selector", selector)
+                .param("start", start)
+                .param("end", end),
+        }
+    }
+
+    /// What confirming will do, shown before asking.
+    pub fn warning(&self) -> String {
+        match self {
+            AdminAction::PrometheusSnapshot => {
+                "Writes a copy of all current data to <data-dir>/snapshots on the server. Snapshots use disk space until removed by hand.".to_string()
+            }
+            AdminAction::PrometheusCleanTombstones => {
+                "Rewrites blocks to drop series already deleted through the admin API. Expect extra disk and CPU load while it runs.".to_string()
+            }
+            AdminAction::LokiDelete { selector, start, end } => format!(
+                "Deletes EVERY line of {} logged from {} to {}, not just the ones on screen. Loki removes them once the delete request's cancellation period is over; after that they cannot be recovered.",
+                selector,
+                local_time(*start),
+                local_time(*end),
+            ),
+        }
+    }
+}
+
+/// State of the admin menu while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct AdminMenu {
+    pub actions: Vec<AdminAction>,
+    pub selected: usize,
+    pub confirming: bool, // Waiting for y/n on the selected action
+    pub running: bool,    // Sent and not answered yet
+    pub result: Option<Result<String, String>>, // Outcome of the last action run
+}
+
+impl AdminMenu {
+    pub fn new(actions: Vec<AdminAction>) -> Self {
+        Self { actions, ..Self::default() }
+    }
+
+    pub fn selected_action(&self) -> Option<&AdminAction> {
+        self.actions.get(self.selected)
+    }
+}
+
+fn local_time(unix_seconds: i64) -> String {
+    DateTime::from_timestamp(unix_seconds, 0)
+        .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// Run a confirmed action, returning a line describing what happened.
+pub async fn run(action: &AdminAction, prometheus: Option<&PrometheusClient>, loki: Option<&LokiClient>) -> Result<String> {
+    let prometheus = || prometheus.ok_or_else(|| anyhow!("Prometheus is disabled"));
+    match action {
+        AdminAction::PrometheusSnapshot => {
+            let name = prometheus()?.create_snapshot().await?;
+            Ok(format!("Snapshot created: snapshots/{}", name))
+        }
+        AdminAction::PrometheusCleanTombstones => {
+            prometheus()?.clean_tombstones().await?;
+            Ok("Tombstones cleaned".to_string())
+        }
+        AdminAction::LokiDelete { selector, start, end } => {
+            let loki = loki.ok_or_else(|| anyhow!("Loki is disabled"))?;
+            loki.submit_delete_request(selector, *start, *end).await?;
+            Ok(format!("Delete request for {} submitted", selector))
+        }
+    }
+}
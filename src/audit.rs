@@ -0,0 +1,155 @@
+//! Audit log of what the user changed through the dashboard: admin actions
+//! against the backends (with their outcome), ignore rules added with `x`,
+//! and log filter changes. Each entry is one JSON line appended to
+//! `$XDG_STATE_HOME/rustdash/audit.log`; the file is never rewritten, so it
+//! can be shipped or reviewed like any other log.
+
+use crate::persist;
+use anyhow::{Context, Result};
+use chrono::{Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Entries kept in memory for the recent-actions popup.
+pub const RECENT_ENTRIES: usize = 100;
+
+/// The `[audit]` config section.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,        // Write entries to the file; the popup works either way
+    pub path: Option<String>, // Defaults to audit.log next to the state file
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self { enabled: true, path: None }
+    }
+}
+
+impl AuditConfig {
+    /// The file to append to, if writing is enabled and a location is known.
+    pub fn file(&self) -> Option<PathBuf> {
+        if !self.enabled {
+            return None;
+        }
+        match &self.path {
+            Some(path) => Some(PathBuf::from(path)),
+            None => Some(persist::state_path()?.with_file_name("audit.log")),
+        }
+    }
+}
+
+/// One thing the user did.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AuditEntry {
+    pub time: String,   // RFC 3339, local time with offset
+    pub user: String,   // $USER of the session
+    pub action: String, // e.g. "loki_delete", "ignore_log", "log_filter"
+    pub target: String, // Server URL the action went to, empty for local ones
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub result: String, // What the server answered, or why it failed
+}
+
+impl AuditEntry {
+    pub fn new(action: &str, target: &str) -> Self {
+        Self {
+            time: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            user: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default(),
+            action: action.to_string(),
+            target: target.to_string(),
+            params: BTreeMap::new(),
+            ok: true,
+            result: String::new(),
+        }
+    }
+
+    pub fn param(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Record how the action ended.
+    pub fn outcome<E: std::fmt::Display>(mut self, result: &Result<String, E>) -> Self {
+        match result {
+            Ok(message) => self.result = message.clone(),
+            Err(e) => {
+                self.ok = false;
+                self.result = e.to_string();
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    path: Option<PathBuf>,        // None keeps entries in memory only, e.g. in demo mode
+    recent: VecDeque<AuditEntry>, // Oldest first, at most RECENT_ENTRIES
+}
+
+impl AuditLog {
+    /// Append to `path`, starting with the newest entries already in it so
+    /// the popup shows earlier sessions too.
+    pub fn open(path: PathBuf) -> Self {
+        let mut log = Self { path: None, recent: VecDeque::new() };
+        if let Ok(contents) = fs::read_to_string(&path) {
+            for entry in contents.lines().filter_map(|line| serde_json::from_str(line).ok()) {
+                log.remember(entry);
+            }
+        }
+        log.path = Some(path);
+        log
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Append `entry` to the file. It is kept for the popup even if writing fails.
+    pub fn record(&mut self, entry: AuditEntry) -> Result<()> {
+        let line = serde_json::to_string(&entry)? + "\n";
+        self.remember(entry);
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        // One write per entry, so sessions appending at once don't interleave lines
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Cannot append to {}", path.display()))
+    }
+
+    fn remember(&mut self, entry: AuditEntry) {
+        if self.recent.len() == RECENT_ENTRIES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(entry);
+    }
+
+    /// Kept entries, newest first.
+    pub fn recent(&self) -> impl Iterator<Item = &AuditEntry> {
+        self.recent.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.recent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recent.is_empty()
+    }
+}
@@ -0,0 +1,49 @@
+//! Retry schedule for a backend that stopped answering. Failed refreshes
+//! push the next attempt out exponentially, so an outage doesn't turn every
+//! tick into a timeout, and the start of the outage is kept for the badge
+//! and for backfilling the gap once the backend is back.
+
+use crate::error::ErrorClass;
+use chrono::{DateTime, Duration, Local};
+
+/// Longest wait between attempts, however long the outage.
+const MAX_DELAY_SECONDS: i64 = 60;
+
+#[derive(Debug, Clone, Default)]
+pub struct Backoff {
+    failures: u32,                           // Consecutive failed attempts
+    pub down_since: Option<DateTime<Local>>, // First failure of the current outage
+    pub retry_at: Option<DateTime<Local>>,   // Earliest next scheduled attempt
+    pub cause: Option<ErrorClass>,           // What the last failure was, when known
+}
+
+impl Backoff {
+    pub fn is_down(&self) -> bool {
+        self.down_since.is_some()
+    }
+
+    /// Whether a scheduled refresh at `now` should query the backend. Ticks
+    /// within a second of the retry time count, so jitter doesn't push a
+    /// retry back a whole interval.
+    pub fn ready(&self, now: DateTime<Local>) -> bool {
+        self.retry_at.is_none_or(|at| now + Duration::seconds(1) >= at)
+    }
+
+    /// Record a failed attempt: wait `base_seconds`, doubling with every
+    /// further failure up to a minute (or `base_seconds`, if that is longer).
+    pub fn failure(&mut self, now: DateTime<Local>, base_seconds: u64) {
+        let base = (base_seconds as i64).max(1);
+        let delay = base.saturating_mul(1 << self.failures.min(16)).min(MAX_DELAY_SECONDS.max(base));
+        self.failures += 1;
+        self.down_since.get_or_insert(now);
+        self.retry_at = Some(now + Duration::seconds(delay));
+    }
+
+    /// Record a successful attempt, returning when the outage it ends began.
+    pub fn success(&mut self) -> Option<DateTime<Local>> {
+        self.failures = 0;
+        self.retry_at = None;
+        self.cause = None;
+        self.down_since.take()
+    }
+}
@@ -0,0 +1,152 @@
+use crate::loki::{LevelCounts, LogEntry};
+use crate::prometheus::{MetricsData, UriMetric};
+use crate::frontend::{Frontend, MemoryFrontend};
+use crate::ui::{ActivePanel, AppState};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// System allocator wrapper that counts allocations, so the render benchmark
+/// can report allocations per frame. Install with `#[global_allocator]`.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Whether a [`CountingAllocator`] is installed, i.e. allocations are
+/// being counted at all.
+pub fn counting_allocations() -> bool {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(std::hint::black_box(Box::new(0u64)));
+    ALLOCATIONS.load(Ordering::Relaxed) != before
+}
+
+/// (allocation count, allocated bytes) since process start.
+pub fn allocation_stats() -> (usize, usize) {
+    (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed))
+}
+
+/// Deterministic synthetic state with `log_count` logs and `metric_count` URI rows.
+pub fn synthetic_state(log_count: usize, metric_count: usize) -> AppState {
+    const LEVELS: [&str; 4] = ["INFO", "DEBUG", "WARN", "ERROR"];
+    
+    let all_logs: Vec<LogEntry> = (0..log_count)
+        .map(|i| {
+            // Mix of short lines and long ones that need truncation or wrapping
+            let repeat = if i % 7 == 0 { 12 } else { 1 };
+            LogEntry {
+                timestamp: format!("2025-01-01 {:02}:{:02}:{:02}", (i / 3600) % 24, (i / 60) % 60, i % 60),
+                nanos: 1_735_689_600_000_000_000 + i as i64 * 1_000_000_000,
+                message: format!("request id={} path=/api/items/{} status=200 took={}ms ", i, i % 500, i % 900)
+                    .repeat(repeat),
+                level: LEVELS[i % LEVELS.len()].to_string(),
+                is_new: i + 20 >= log_count,
+                stream: r#"{service_name="bench"}"#.to_string(),
+                source: None,
+                local: false,
+            }
+        })
+        .collect();
+    
+    let uri_metrics = (0..metric_count)
+        .map(|i| UriMetric {
+            uri: format!("/api/v1/service-{}/resource/{}", i % 37, i),
+            avg_duration_ms: ((i * 37) % 1200) as f64 + 0.5,
+            request_count: ((i * 13) % 400) as f64,
+            errors_per_min: 0.0,
+            change_pct: None,
+            bytes_per_sec: None,
+        })
+        .collect();
+    
+    AppState {
+        log_level_counts: LevelCounts::of(&all_logs),
+        all_logs,
+        metrics: Some(MetricsData {
+            http_requests_total: 1234.5,
+            uri_metrics,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        active_panel: ActivePanel::Logs,
+        ..AppState::default()
+    }
+}
+
+/// Render `frames` frames of a synthetic state on an in-memory backend,
+/// scrolling through the logs, and report frame times and allocations.
+pub fn run_render_bench(log_count: usize, metric_count: usize, frames: usize) -> anyhow::Result<String> {
+    let (width, height) = (160, 50);
+    let mut frontend = MemoryFrontend::new(width, height)?;
+    let mut state = synthetic_state(log_count, metric_count);
+    state.last_terminal_height = height;
+    state.last_terminal_width = width;
+    
+    let mut frame_times = Vec::with_capacity(frames);
+    let mut window_times = Vec::with_capacity(frames);
+    let mut frame_allocations = Vec::with_capacity(frames);
+    
+    for frame in 0..frames {
+        // Move the selection like a user holding the arrow keys, expanding every 10th line
+        let selected = log_count.saturating_sub(1 + frame * 3 % log_count.max(1));
+        state.selected_log_index = Some(selected);
+        state.expanded_log_index = if frame % 10 == 0 { Some(selected) } else { None };
+        state.log_scroll_offset = selected.saturating_sub(5);
+        
+        let window_start = Instant::now();
+        state.update_visible_logs_with_height(height);
+        window_times.push(window_start.elapsed());
+        
+        let (allocs_before, _) = allocation_stats();
+        let draw_start = Instant::now();
+        frontend.draw(&state)?;
+        frame_times.push(draw_start.elapsed());
+        frame_allocations.push(allocation_stats().0 - allocs_before);
+    }
+    
+    let mut report = String::new();
+    writeln!(report, "Render benchmark: {} logs, {} metric rows, {} frames at {}x{}",
+        log_count, metric_count, frames, width, height)?;
+    writeln!(report, "draw_ui        {}", summarize(&mut frame_times))?;
+    writeln!(report, "visible window {}", summarize(&mut window_times))?;
+    if counting_allocations() {
+        let total_allocs: usize = frame_allocations.iter().sum();
+        writeln!(report, "allocations    avg {:.0}/frame, max {}/frame",
+            total_allocs as f64 / frames.max(1) as f64,
+            frame_allocations.iter().max().copied().unwrap_or(0))?;
+    } else {
+        writeln!(report, "allocations    not counted (build with --features bench-alloc)")?;
+    }
+    Ok(report)
+}
+
+fn summarize(times: &mut [Duration]) -> String {
+    if times.is_empty() {
+        return "no samples".to_string();
+    }
+    times.sort();
+    let total: Duration = times.iter().sum();
+    let avg = total / times.len() as u32;
+    let p95 = times[(times.len() * 95 / 100).min(times.len() - 1)];
+    format!("min {:?}  avg {:?}  p95 {:?}  max {:?}", times[0], avg, p95, times[times.len() - 1])
+}
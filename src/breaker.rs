@@ -0,0 +1,197 @@
+//! Retries and circuit breaking around every request to a backend, so one
+//! that is flapping neither fails a refresh on a single dropped connection
+//! nor keeps every refresh waiting on it.
+//!
+//! GETs that fail to connect or get a 502/503/504 are retried a few times
+//! with a doubling delay. Each endpoint (URL without its query) counts its
+//! consecutive failures; past a threshold its circuit opens and requests to
+//! it fail at once for a cooldown. After the cooldown requests go through
+//! again, and the first one to fail reopens the circuit straight away.
+
+use crate::query_stats::Backend;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The `[http.breaker]` config section.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BreakerConfig {
+    pub retries: u32,          // Extra attempts for a GET that failed to connect or got a 502/503/504
+    pub retry_delay_ms: u64,   // Wait before the first retry, doubled for each further one
+    pub failures: u32,         // Consecutive failures that open an endpoint's circuit; 0 never opens it
+    pub cooldown_seconds: u64, // How long an open circuit turns requests away
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            retry_delay_ms: 200,
+            failures: 5,
+            cooldown_seconds: 30,
+        }
+    }
+}
+
+/// A request turned away because its endpoint's circuit is open.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{endpoint} is cooling down after {failures} failures, retry in {}s", retry_in.as_secs())]
+pub struct CircuitOpen {
+    pub endpoint: String,
+    pub failures: u32,
+    pub retry_in: Duration,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SendError {
+    #[error(transparent)]
+    Open(#[from] CircuitOpen),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+/// An endpoint whose circuit is open, for the status line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenCircuit {
+    pub backend: Backend,
+    pub endpoint: String, // URL without the query string
+    pub failures: u32,
+    pub retry_in: Duration,
+}
+
+impl OpenCircuit {
+    /// The endpoint's path, e.g. "/api/v1/query".
+    pub fn path(&self) -> String {
+        reqwest::Url::parse(&self.endpoint).map_or_else(|_| self.endpoint.clone(), |url| url.path().to_string())
+    }
+}
+
+#[derive(Debug)]
+struct Circuit {
+    backend: Backend,
+    failures: u32,               // Consecutive, reset by any answer that isn't a failure
+    open_until: Option<Instant>, // Set while the circuit is open, and kept once the cooldown ends
+}
+
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: Mutex::default(),
+        }
+    }
+
+    /// Passes requests through once: no retries, never opens.
+    pub fn disabled() -> Self {
+        Self::new(BreakerConfig { retries: 0, failures: 0, ..BreakerConfig::default() })
+    }
+
+    /// Send `request`, retrying a GET that failed in passing. Fails at once
+    /// while the endpoint's circuit is open.
+    pub async fn send(&self, backend: Backend, request: RequestBuilder) -> Result<Response, SendError> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        let endpoint = endpoint(request.url());
+        let retries = if request.method() == Method::GET { self.config.retries } else { 0 };
+        let mut delay = Duration::from_millis(self.config.retry_delay_ms);
+        let mut attempt = 0;
+        loop {
+            self.check(&endpoint)?;
+            // Bodies of our requests are plain strings, which always clone
+            let result = client.execute(request.try_clone().expect("request body is not a stream")).await;
+            let failed = match &result {
+                Ok(response) => is_failure_status(response.status()),
+                Err(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            };
+            self.record(backend, &endpoint, failed);
+            let retriable = match &result {
+                Ok(response) => is_failure_status(response.status()),
+                Err(e) => e.is_connect(), // A timed out query would only time out again
+            };
+            if !retriable || attempt >= retries {
+                return Ok(result?);
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Turn a request away if `endpoint`'s circuit is open.
+    fn check(&self, endpoint: &str) -> Result<(), CircuitOpen> {
+        let circuits = self.circuits.lock().unwrap();
+        match circuits.get(endpoint) {
+            Some(circuit) => match circuit.open_until.map(|until| until.saturating_duration_since(Instant::now())) {
+                Some(retry_in) if !retry_in.is_zero() => Err(CircuitOpen {
+                    endpoint: endpoint.to_string(),
+                    failures: circuit.failures,
+                    retry_in,
+                }),
+                _ => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Count a request's outcome towards its endpoint's circuit.
+    fn record(&self, backend: Backend, endpoint: &str, failed: bool) {
+        let mut circuits = self.circuits.lock().unwrap();
+        if !failed {
+            circuits.remove(endpoint);
+            return;
+        }
+        let circuit = circuits.entry(endpoint.to_string()).or_insert(Circuit {
+            backend,
+            failures: 0,
+            open_until: None,
+        });
+        circuit.failures += 1;
+        if self.config.failures > 0 && circuit.failures >= self.config.failures {
+            circuit.open_until = Some(Instant::now() + Duration::from_secs(self.config.cooldown_seconds));
+        }
+    }
+
+    /// Endpoints currently turning requests away, soonest to retry first.
+    pub fn open_circuits(&self) -> Vec<OpenCircuit> {
+        let now = Instant::now();
+        let circuits = self.circuits.lock().unwrap();
+        let mut open: Vec<OpenCircuit> = circuits
+            .iter()
+            .filter_map(|(endpoint, circuit)| {
+                let retry_in = circuit.open_until?.checked_duration_since(now).filter(|d| !d.is_zero())?;
+                Some(OpenCircuit {
+                    backend: circuit.backend,
+                    endpoint: endpoint.clone(),
+                    failures: circuit.failures,
+                    retry_in,
+                })
+            })
+            .collect();
+        open.sort_by(|a, b| a.retry_in.cmp(&b.retry_in).then_with(|| a.endpoint.cmp(&b.endpoint)));
+        open
+    }
+}
+
+/// Answers that say the server, or the proxy in front of it, is in trouble.
+fn is_failure_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// The circuit key of a URL: everything but the query string.
+fn endpoint(url: &reqwest::Url) -> String {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.to_string()
+}
@@ -0,0 +1,191 @@
+//! Profile bundles (`rustdash export-profile` / `import-profile`): the
+//! config file and the saved views in one JSON file, so a team can hand its
+//! setup around. The config travels as written, comments included, and
+//! carries the backends, panels, variables, layout, saved log queries and
+//! everything else set there; credentials in it are only references to where
+//! they live (see `secrets`), never their values.
+//!
+//! A config can make rustdash run shell commands (secret sources, log line
+//! commands) and open SSH connections, so importing lists those and asks
+//! before anything is written.
+
+use crate::config::{AuthConfig, Settings};
+use crate::log_actions::LogActionsConfig;
+use crate::persist::PersistedState;
+use crate::saved_views::{self, SavedView};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// Format of the bundles written; newer ones are refused.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// File `export-profile` writes without a path.
+pub const DEFAULT_FILE: &str = "rustdash-profile.json";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub exported_at: String,    // RFC 3339
+    pub config: Option<String>, // config.toml as written; None without one
+    #[serde(default)]
+    pub saved_views: Vec<SavedView>,
+}
+
+/// What importing a bundle changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Installed {
+    pub config: bool,            // The config file was replaced
+    pub backup: Option<PathBuf>, // Where the previous config went
+    pub saved_views: usize,
+}
+
+impl Bundle {
+    pub fn new(config: Option<String>, saved_views: Vec<SavedView>) -> Self {
+        Self { version: BUNDLE_VERSION, exported_at: Local::now().to_rfc3339(), config, saved_views }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Read a bundle, checking its config would load before anything is
+    /// written.
+    pub fn parse(text: &str) -> Result<Self> {
+        let bundle: Bundle = serde_json::from_str(text).context("Not a rustdash profile bundle")?;
+        if bundle.version > BUNDLE_VERSION {
+            anyhow::bail!("Bundle format {} is newer than this rustdash reads ({})", bundle.version, BUNDLE_VERSION);
+        }
+        if let Some(config) = &bundle.config {
+            toml::from_str::<Settings>(config).map_err(|e| anyhow::anyhow!("Invalid config in the bundle: {}", e))?;
+        }
+        Ok(bundle)
+    }
+
+    /// The shell commands the bundle's config would run and the SSH hosts
+    /// it would connect through, one line each. The default log line
+    /// commands ($PAGER and $EDITOR) aren't listed.
+    pub fn review(&self) -> Result<Vec<String>> {
+        let Some(config) = &self.config else { return Ok(Vec::new()) };
+        let settings: Settings = toml::from_str(config)?;
+        let mut lines = Vec::new();
+        let mut auth = vec![("prometheus.auth".to_string(), &settings.prometheus.auth), ("loki.auth".to_string(), &settings.loki.auth)];
+        let mut profiles: Vec<_> = settings.profiles.iter().collect();
+        profiles.sort_by_key(|(name, _)| *name);
+        for (name, profile) in profiles {
+            auth.extend(profile.prometheus_auth.as_ref().map(|config| (format!("profiles.{}.prometheus_auth", name), config)));
+            auth.extend(profile.loki_auth.as_ref().map(|config| (format!("profiles.{}.loki_auth", name), config)));
+        }
+        for (section, config) in auth {
+            let AuthConfig { password, bearer_token, .. } = config;
+            for (key, secret) in [("password", password), ("bearer_token", bearer_token)] {
+                if let Some(command) = secret.as_ref().and_then(|secret| secret.command.as_ref()) {
+                    lines.push(format!("runs `{}` for {}.{}", command, section, key));
+                }
+            }
+        }
+        let defaults = LogActionsConfig::default().commands;
+        for command in &settings.log_actions.commands {
+            if !defaults.iter().any(|default| default.name == command.name && default.command == command.command) {
+                lines.push(format!("runs `{}` as log command \"{}\"", command.command, command.name));
+            }
+        }
+        for (section, host) in [("prometheus", &settings.prometheus.ssh_host), ("loki", &settings.loki.ssh_host)] {
+            if let Some(host) = host {
+                lines.push(format!("connects to SSH host {} for {}", host, section));
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Write the config to `config_path`, keeping the one there as
+    /// `config.toml.<time>.bak`, and add the saved views to `state`,
+    /// replacing views of the same name. An existing backup of that name is
+    /// never overwritten.
+    pub fn install(&self, config_path: &Path, state: &mut PersistedState, now: DateTime<Local>) -> Result<Installed> {
+        let mut backup = None;
+        if let Some(config) = &self.config {
+            if let Some(parent) = config_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if config_path.exists() {
+                let path = config_path.with_extension(format!("toml.{}.bak", now.format("%Y%m%d-%H%M%S")));
+                if path.exists() {
+                    anyhow::bail!("{} already exists; not overwriting it", path.display());
+                }
+                fs::copy(config_path, &path).with_context(|| format!("Couldn't back up {}", config_path.display()))?;
+                backup = Some(path);
+            }
+            fs::write(config_path, config).with_context(|| format!("Couldn't write {}", config_path.display()))?;
+        }
+        for view in &self.saved_views {
+            saved_views::save(&mut state.saved_views, view.clone());
+        }
+        Ok(Installed { config: self.config.is_some(), backup, saved_views: self.saved_views.len() })
+    }
+}
+
+/// Bundle the config file and saved views into `path`.
+pub fn export_profile(path: &Path) -> Result<String> {
+    let config_path = crate::config::config_path();
+    let config = match &config_path {
+        Some(config_path) if config_path.exists() => Some(fs::read_to_string(config_path)?),
+        _ => None,
+    };
+    let bundle = Bundle::new(config, PersistedState::load().saved_views);
+    fs::write(path, bundle.to_json()?).with_context(|| format!("Couldn't write {}", path.display()))?;
+    Ok(format!(
+        "Exported {} and {} saved views to {}",
+        match (&bundle.config, &config_path) {
+            (Some(_), Some(config_path)) => config_path.display().to_string(),
+            _ => "no config file".to_string(),
+        },
+        bundle.saved_views.len(),
+        path.display()
+    ))
+}
+
+/// Install the bundle at `path` over the config file and state file, after
+/// listing what its config runs and asking, unless `yes`.
+pub fn import_profile(path: &Path, yes: bool) -> Result<String> {
+    let text = fs::read_to_string(path).with_context(|| format!("Couldn't read {}", path.display()))?;
+    let bundle = Bundle::parse(&text)?;
+    let config_path = crate::config::config_path().context("Cannot determine config directory")?;
+    let review = bundle.review()?;
+    if !review.is_empty() {
+        println!("The bundle's config:");
+        for line in &review {
+            println!("  {}", line);
+        }
+    }
+    if !yes {
+        if !io::stdin().is_terminal() {
+            anyhow::bail!("Not importing without confirmation; pass --yes to import anyway");
+        }
+        let replaces = match &bundle.config {
+            Some(_) => format!("Replace {} and add", config_path.display()),
+            None => "Add".to_string(),
+        };
+        print!("{} {} saved views? [y/N] ", replaces, bundle.saved_views.len());
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            anyhow::bail!("Import cancelled");
+        }
+    }
+    let mut state = PersistedState::load();
+    let installed = bundle.install(&config_path, &mut state, Local::now())?;
+    state.save()?;
+    let mut summary = vec![format!("Imported {} saved views", installed.saved_views)];
+    if installed.config {
+        summary.push(format!("wrote {}", config_path.display()));
+    }
+    if let Some(backup) = installed.backup {
+        summary.push(format!("previous config kept as {}", backup.display()));
+    }
+    Ok(summary.join(", "))
+}
@@ -0,0 +1,114 @@
+//! Cache for slowly-changing API responses (build info, label names) so the
+//! refresh loop only spends requests on the hot path.
+//!
+//! Entries are served from memory until their TTL runs out, then revalidated
+//! with `If-None-Match` / `If-Modified-Since`; a `304 Not Modified` keeps the
+//! cached body without transferring it again.
+
+use crate::breaker::{CircuitBreaker, SendError};
+use crate::query_stats::Backend;
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct Entry {
+    body: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    expires: Instant,
+}
+
+/// How requests were answered, for diagnostics and tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,        // Served from memory
+    pub revalidated: u64, // 304 Not Modified
+    pub fetched: u64,     // Full response transferred
+}
+
+#[derive(Debug, Default)]
+pub struct StaticCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    stats: Mutex<CacheStats>,
+}
+
+impl StaticCache {
+    /// GET `url` through `breaker`, served from the cache while younger than
+    /// `ttl`. An error status comes back as a reqwest error carrying it.
+    pub async fn get(
+        &self,
+        client: &Client,
+        breaker: &CircuitBreaker,
+        backend: Backend,
+        url: &str,
+        ttl: Duration,
+    ) -> Result<String, SendError> {
+        let validators = {
+            let entries = self.entries.lock().unwrap();
+            match entries.get(url) {
+                Some(entry) if entry.expires > Instant::now() => {
+                    self.stats.lock().unwrap().hits += 1;
+                    return Ok(entry.body.clone());
+                }
+                Some(entry) => (entry.etag.clone(), entry.last_modified.clone()),
+                None => (None, None),
+            }
+        };
+
+        let mut request = client.get(url);
+        if let Some(etag) = &validators.0 {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.1 {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = breaker.send(backend, request).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = self.entries.lock().unwrap().get_mut(url) {
+                entry.expires = Instant::now() + ttl;
+                self.stats.lock().unwrap().revalidated += 1;
+                return Ok(entry.body.clone());
+            }
+        }
+        let response = response.error_for_status()?;
+
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let body = response.text().await?;
+
+        self.entries.lock().unwrap().insert(
+            url.to_string(),
+            Entry {
+                body: body.clone(),
+                etag,
+                last_modified,
+                expires: Instant::now() + ttl,
+            },
+        );
+        self.stats.lock().unwrap().fetched += 1;
+        Ok(body)
+    }
+
+    /// Expire every entry, keeping the validators, so the next request of
+    /// each is a cheap conditional one.
+    pub fn invalidate(&self) {
+        let now = Instant::now();
+        for entry in self.entries.lock().unwrap().values_mut() {
+            entry.expires = now;
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        *self.stats.lock().unwrap()
+    }
+}
@@ -0,0 +1,133 @@
+//! What changed in the metrics table since the refresh before: URIs that
+//! appeared or vanished, and latencies that moved by more than
+//! `ui.change_threshold_pct`. Changed rows are marked in the table and
+//! listed by `K`, so an emerging problem stands out without watching every
+//! number. Rows are only compared within one time range.
+
+use crate::prometheus::UriMetric;
+use chrono::{DateTime, Local};
+use std::cmp::Ordering;
+
+/// Latency moves smaller than this are noise, whatever the percentage.
+pub const MIN_CHANGE_MS: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    New,
+    Vanished,
+    Latency { before_ms: f64, after_ms: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricChange {
+    pub uri: String,
+    pub kind: ChangeKind,
+}
+
+impl MetricChange {
+    /// Latency change in percent; None for rows that came or went.
+    pub fn pct(&self) -> Option<f64> {
+        match self.kind {
+            ChangeKind::Latency { before_ms, after_ms } => Some((after_ms - before_ms) / before_ms * 100.0),
+            _ => None,
+        }
+    }
+
+    /// The row's marker in the table, and the word for it in accessible mode.
+    pub fn marker(&self) -> (&'static str, &'static str) {
+        match self.kind {
+            ChangeKind::New => ("+", "new"),
+            ChangeKind::Vanished => ("-", "gone"),
+            ChangeKind::Latency { before_ms, after_ms } if after_ms > before_ms => ("▲", "slower"),
+            ChangeKind::Latency { .. } => ("▼", "faster"),
+        }
+    }
+
+    /// "+85%  120.0ms → 222.0ms", "new" or "gone".
+    pub fn describe(&self) -> String {
+        match self.kind {
+            ChangeKind::Latency { before_ms, after_ms } => {
+                format!("{:+.0}%  {:.1}ms → {:.1}ms", self.pct().unwrap_or_default(), before_ms, after_ms)
+            }
+            _ => self.marker().1.to_string(),
+        }
+    }
+}
+
+/// Rows of `after` that differ from `before`: new ones first, then latency
+/// changes, biggest first, then the ones that vanished.
+pub fn diff(before: &[UriMetric], after: &[UriMetric], threshold_pct: f64) -> Vec<MetricChange> {
+    let find = |rows: &[UriMetric], uri: &str| rows.iter().find(|row| row.uri == uri).map(|row| row.avg_duration_ms);
+    let mut changes: Vec<MetricChange> = after
+        .iter()
+        .filter_map(|row| {
+            let kind = match find(before, &row.uri) {
+                None => ChangeKind::New,
+                Some(before_ms) => {
+                    let moved = (row.avg_duration_ms - before_ms).abs();
+                    if before_ms <= 0.0 || moved < MIN_CHANGE_MS || moved / before_ms * 100.0 < threshold_pct {
+                        return None;
+                    }
+                    ChangeKind::Latency { before_ms, after_ms: row.avg_duration_ms }
+                }
+            };
+            Some(MetricChange { uri: row.uri.clone(), kind })
+        })
+        .collect();
+    changes.extend(
+        before
+            .iter()
+            .filter(|row| find(after, &row.uri).is_none())
+            .map(|row| MetricChange { uri: row.uri.clone(), kind: ChangeKind::Vanished }),
+    );
+    let rank = |change: &MetricChange| match change.kind {
+        ChangeKind::New => 0,
+        ChangeKind::Latency { .. } => 1,
+        ChangeKind::Vanished => 2,
+    };
+    changes.sort_by(|a, b| {
+        rank(a).cmp(&rank(b)).then_with(|| {
+            let size = |change: &MetricChange| change.pct().map_or(0.0, f64::abs);
+            size(b).partial_cmp(&size(a)).unwrap_or(Ordering::Equal)
+        })
+    });
+    changes
+}
+
+/// The rows of the last refresh, to compare the next one with.
+#[derive(Debug, Clone)]
+struct Baseline {
+    window_minutes: Option<i64>,
+    at: DateTime<Local>,
+    rows: Vec<UriMetric>,
+}
+
+/// Changes between the last two refreshes of the metrics table.
+#[derive(Debug, Clone, Default)]
+pub struct MetricChanges {
+    pub changes: Vec<MetricChange>,
+    pub since: Option<DateTime<Local>>, // When the rows compared against were fetched
+    baseline: Option<Baseline>,
+}
+
+impl MetricChanges {
+    /// Compare a refresh's rows with the last ones; a different time range
+    /// starts over.
+    pub fn update(&mut self, window_minutes: Option<i64>, rows: &[UriMetric], at: DateTime<Local>, threshold_pct: f64) {
+        match self.baseline.take().filter(|baseline| baseline.window_minutes == window_minutes) {
+            Some(baseline) => {
+                self.changes = diff(&baseline.rows, rows, threshold_pct);
+                self.since = Some(baseline.at);
+            }
+            None => {
+                self.changes.clear();
+                self.since = None;
+            }
+        }
+        self.baseline = Some(Baseline { window_minutes, at, rows: rows.to_vec() });
+    }
+
+    pub fn get(&self, uri: &str) -> Option<&MetricChange> {
+        self.changes.iter().find(|change| change.uri == uri)
+    }
+}
@@ -0,0 +1,64 @@
+use clap::{Parser, Subcommand};
+use rustdash::bundle::DEFAULT_FILE;
+use std::path::PathBuf;
+
+/// Terminal-based monitoring dashboard for Prometheus and Loki
+#[derive(Debug, Parser)]
+#[command(name = "rustdash", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Apply the overrides of a `[profiles.<name>]` section from the config file
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Run against a built-in mock Prometheus/Loki with generated data (same as `rustdash demo`)
+    #[arg(long)]
+    pub demo: bool,
+
+    /// Print a plain-text summary to stdout on quit (pipe it into an incident note)
+    #[arg(long)]
+    pub summary: bool,
+
+    /// Backfill logs and the "All" metric history for this long (e.g. 2h, 30m, 1d) before going live
+    #[arg(long, value_name = "DURATION")]
+    pub since: Option<String>,
+
+    /// Serve the session read-only on this address (`7000` is `127.0.0.1:7000`) for `rustdash attach`
+    #[arg(long, value_name = "ADDR")]
+    pub share: Option<String>,
+
+    /// Render a synthetic state (100k logs, 500 metric rows) and report frame times and allocations
+    #[arg(long)]
+    pub bench_render: bool,
+
+    /// Number of frames to render with --bench-render
+    #[arg(long, default_value_t = 500)]
+    pub bench_frames: usize,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run against a built-in mock Prometheus/Loki with generated streaming data
+    Demo,
+    /// Follow a session shared with `--share`, read-only (`q` detaches)
+    Attach {
+        /// Where the session is shared, e.g. 7000 or 10.0.0.5:7000
+        address: String,
+    },
+    /// Write the config file and saved views to one file to share the setup
+    ExportProfile {
+        /// Where to write the bundle
+        #[arg(default_value = DEFAULT_FILE)]
+        path: PathBuf,
+    },
+    /// Install a bundle written by `export-profile` (the current config is kept as config.toml.<time>.bak)
+    ImportProfile {
+        /// The bundle to install
+        path: PathBuf,
+        /// Install without asking, after listing the commands and SSH hosts its config uses
+        #[arg(long)]
+        yes: bool,
+    },
+}
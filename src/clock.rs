@@ -0,0 +1,56 @@
+//! Clock skew between this machine and the servers it queries. Range queries
+//! are anchored on the server's clock, so a drifting laptop neither asks for
+//! a window that ends before the newest data nor one that hasn't happened yet.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::header::DATE;
+use reqwest::Response;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Last measured offset of one server's clock.
+#[derive(Debug, Default)]
+pub struct ClockSkew {
+    measured: Mutex<Option<(f64, Instant)>>, // Seconds the server is ahead, and when that was measured
+}
+
+impl ClockSkew {
+    /// Seconds the server's clock is ahead of ours (negative when behind),
+    /// or None until it has been measured.
+    pub fn seconds(&self) -> Option<f64> {
+        self.measured.lock().unwrap().map(|(seconds, _)| seconds)
+    }
+
+    /// Whether the last measurement is older than `max_age`, or missing.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.measured.lock().unwrap().is_none_or(|(_, at)| at.elapsed() > max_age)
+    }
+
+    /// Record that the server read `server_time` (unix seconds) while a
+    /// request sent at `sent` was in flight, i.e. around the round trip's midpoint.
+    pub fn record(&self, server_time: f64, sent: DateTime<Utc>) {
+        let received = Utc::now();
+        let midpoint = sent + (received - sent) / 2;
+        let seconds = server_time - midpoint.timestamp_millis() as f64 / 1000.0;
+        *self.measured.lock().unwrap() = Some((seconds, Instant::now()));
+    }
+
+    /// Measure from a response's `Date` header, if it has one. The header
+    /// is truncated to the second, so this is only accurate to about half a second.
+    pub fn record_date_header(&self, response: &Response, sent: DateTime<Utc>) {
+        let date = response
+            .headers()
+            .get(DATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+        if let Some(date) = date {
+            self.record(date.timestamp() as f64 + 0.5, sent);
+        }
+    }
+
+    /// The current time on the server's clock, our own until measured.
+    pub fn server_now(&self) -> DateTime<Utc> {
+        let skew = self.seconds().unwrap_or(0.0);
+        Utc::now() + TimeDelta::milliseconds((skew * 1000.0) as i64)
+    }
+}
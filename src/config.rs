@@ -1,48 +1,308 @@
+use crate::audit::AuditConfig;
+use crate::breaker::BreakerConfig;
+use crate::guard::GuardConfig;
+use crate::ignore::IgnoreConfig;
+use crate::layout::LayoutConfig;
+use crate::limiter::MIN_QUERIES_PER_SECOND;
+use crate::log_actions::LogActionsConfig;
+use crate::log_files::LogFilesConfig;
+use crate::log_overlay::LogQuery;
+use crate::panels::PanelConfig;
+use crate::ping::PingConfig;
+use crate::retention::RetentionConfig;
+use crate::secrets::Secret;
+use crate::silence::SilenceRule;
+use crate::slo::SloConfig;
+use crate::status_pages::StatusPageConfig;
+use crate::synthetic::CheckConfig;
+use crate::templates::UriTemplate;
+use crate::text::Truncation;
+use crate::tunnel::validate_ssh_host;
+use crate::uri_errors::UriErrorsConfig;
+use crate::variables::VariableConfig;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::PathBuf};
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct Settings {
     pub prometheus: PrometheusConfig,
     pub loki: LokiConfig,
     pub ui: UiConfig,
+    pub layout: LayoutConfig,               // Grid of the main area; the metrics-over-logs stack without cells
+    pub http: HttpConfig,
+    pub history: HistoryConfig,
+    pub audit: AuditConfig,                 // Where changes made through the dashboard are logged
+    pub profiles: HashMap<String, Profile>, // Named overrides, selected with --profile
+    pub panels: Vec<PanelConfig>,           // Preset panels shown on the panels page
+    pub variables: Vec<VariableConfig>,     // Template variables referenced by the panels as `$name`
+    pub uri_templates: Vec<UriTemplate>,    // Fold raw paths into templates before aggregation
+    pub ignore: IgnoreConfig,               // URIs and log lines hidden from the tables
+    pub silence_alarms: Vec<SilenceRule>,   // Streams expected to keep logging
+    pub bandwidth: BandwidthConfig,         // Optional response size metric
+    pub log_actions: LogActionsConfig,      // Trace and link templates in the log line menu
+    pub uri_errors: UriErrorsConfig,        // Last error per URI in the metrics table
+    pub retention: RetentionConfig,         // How much log and history data is kept in memory
+    pub slow_query_guard: GuardConfig,      // Reducing queries that keep being slow
+    pub log_queries: Vec<LogQuery>,         // Queries merged into the logs panel instead of auto-detecting a stream
+    pub log_files: LogFilesConfig,          // Local files followed into the logs panel
+    pub status_pages: Vec<StatusPageConfig>, // External dependencies' public status pages
+    pub synthetic_checks: Vec<CheckConfig>, // HTTP requests run on an interval and asserted on
+    pub ping: PingConfig,                   // Hosts whose round-trip latency is charted
+    pub slos: Vec<SloConfig>,               // Objectives watched with multi-window burn-rate alerts
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct PrometheusConfig {
+    pub enabled: bool, // false runs without Prometheus: no metrics panel, no metric queries
     pub base_url: String,
     pub timeout_seconds: u64,
+    pub proxy: Option<String>, // http://, https://, socks5:// or socks5h:// proxy URL
+    pub ssh_host: Option<String>, // Reach base_url through an SSH tunnel via this host
+    pub remote_port: Option<u16>, // Port on the remote side, defaults to base_url's port
+    pub max_concurrent_queries: usize, // Queries in flight at once; the rest wait
+    pub max_queries_per_second: Option<f64>, // Optional rate budget for query starts
+    pub admin: bool, // Offer TSDB snapshot and tombstone cleanup in the admin menu (`A`)
+    pub auth: AuthConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct LokiConfig {
+    pub enabled: bool, // false runs without Loki: no logs panel, no log queries
     pub base_url: String,
     pub timeout_seconds: u64,
     pub log_limit: u32,
+    pub tenant_id: Option<String>, // Sent as X-Scope-OrgID for multi-tenant Loki
+    pub proxy: Option<String>,
+    pub ssh_host: Option<String>,
+    pub remote_port: Option<u16>,
+    pub max_concurrent_queries: usize,
+    pub max_queries_per_second: Option<f64>,
+    pub admin: bool, // Offer delete requests for the selected line's stream in the admin menu (`A`)
+    pub auth: AuthConfig,
+}
+
+/// Credentials sent with every request to an endpoint. Values are never
+/// written in the config, only where to read them from.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub username: Option<String>,     // Basic auth user, with `password`
+    pub password: Option<Secret>,
+    pub bearer_token: Option<Secret>, // Sent as `Authorization: Bearer ...` instead of basic auth
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct UiConfig {
     pub refresh_interval_seconds: u64,
     pub log_display_count: usize,
+    pub accessible: bool, // Text indicators instead of color-only signaling and bar charts
+    pub summary_on_exit: bool, // Print a plain-text summary to stdout on quit
+    pub clock_skew_warn_seconds: u64, // Flag a server whose clock is off by more than this
+    pub log_ages: bool, // Start with log ages shown ("12s", "3m"); `t` toggles them
+    pub line_numbers: bool, // Start with the log line-number gutter shown; `#` toggles it
+    pub log_jump_steps: [String; 2], // How far `{`/`}` and `(`/`)` move the log selection in time
+    pub change_markers: bool, // Mark metrics rows that changed since the last refresh
+    pub change_threshold_pct: f64, // Latency change that counts, in percent of the previous value
+    pub max_fps: u32,   // Redraws per second at most; input is still handled as it comes
+    pub poll_interval_ms: u64, // How long each wait for terminal input lasts before the input thread wakes up
+    pub uri_truncation: Truncation, // Where long URIs are cut in the metrics table: "end" or "middle"
+    pub since: Option<String>, // Backfill logs and the "All" history for this long on launch, e.g. "2h"; --since overrides
+}
+
+/// Response size counter behind the bandwidth column and the egress
+/// sparkline. Micrometer doesn't export one, so it's off unless named.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    pub metric: Option<String>, // Counter of response bytes, e.g. `nginx_ingress_controller_response_size_sum`
+    pub uri_label: String,      // Label holding the request path on that metric
+}
+
+impl Default for BandwidthConfig {
+    fn default() -> Self {
+        BandwidthConfig {
+            metric: None,
+            uri_label: "uri".to_string(),
+        }
+    }
+}
+
+/// Client-side metric history behind the "All" time range.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub persist: bool, // Keep the history across sessions in the state directory
+}
+
+/// Per-environment overrides applied on top of the base settings.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Profile {
+    pub prometheus_url: Option<String>,
+    pub loki_url: Option<String>,
+    pub loki_tenant_id: Option<String>,
+    pub prometheus_auth: Option<AuthConfig>, // Replaces [prometheus.auth] as a whole
+    pub loki_auth: Option<AuthConfig>,
+}
+
+/// HTTP client tuning shared by the Prometheus and Loki clients.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub compression: bool,                 // Accept gzip/deflate encoded responses
+    pub pool_max_idle_per_host: usize,     // Idle keep-alive connections kept per host
+    pub pool_idle_timeout_seconds: u64,    // How long an idle connection is kept
+    pub tcp_keepalive_seconds: Option<u64>,
+    pub http2_prior_knowledge: bool,       // Talk HTTP/2 without negotiation (h2c endpoints)
+    pub use_env_proxy: bool,               // Honor HTTP_PROXY/HTTPS_PROXY/NO_PROXY when no proxy is set
+    pub breaker: BreakerConfig,            // Retries and per-endpoint circuit breaking
+}
+
+impl Default for PrometheusConfig {
+    fn default() -> Self {
+        PrometheusConfig {
+            enabled: true,
+            base_url: "http://localhost:9090".to_string(),
+            timeout_seconds: 10,
+            proxy: None,
+            ssh_host: None,
+            remote_port: None,
+            max_concurrent_queries: 8,
+            max_queries_per_second: None,
+            admin: false,
+            auth: AuthConfig::default(),
+        }
+    }
+}
+
+impl Default for LokiConfig {
+    fn default() -> Self {
+        LokiConfig {
+            enabled: true,
+            base_url: "http://localhost:3100".to_string(),
+            timeout_seconds: 10,
+            log_limit: 100,
+            tenant_id: None,
+            proxy: None,
+            ssh_host: None,
+            remote_port: None,
+            max_concurrent_queries: 4,
+            max_queries_per_second: None,
+            admin: false,
+            auth: AuthConfig::default(),
+        }
+    }
 }
 
-impl Default for Settings {
+impl Default for UiConfig {
     fn default() -> Self {
-        Settings {
-            prometheus: PrometheusConfig {
-                base_url: "http://localhost:9090".to_string(),
-                timeout_seconds: 10,
-            },
-            loki: LokiConfig {
-                base_url: "http://localhost:3100".to_string(),
-                timeout_seconds: 10,
-                log_limit: 100,
-            },
-            ui: UiConfig {
-                refresh_interval_seconds: 5,
-                log_display_count: 20,
-            },
+        UiConfig {
+            refresh_interval_seconds: 5,
+            log_display_count: 20,
+            accessible: false,
+            summary_on_exit: false,
+            clock_skew_warn_seconds: 10,
+            log_ages: false,
+            line_numbers: false,
+            log_jump_steps: ["30s".to_string(), "5m".to_string()],
+            change_markers: true,
+            change_threshold_pct: 50.0,
+            max_fps: 30,
+            poll_interval_ms: 250,
+            uri_truncation: Truncation::End,
+            since: None,
         }
     }
 }
 
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            compression: true,
+            pool_max_idle_per_host: 8,
+            pool_idle_timeout_seconds: 90,
+            tcp_keepalive_seconds: Some(60),
+            http2_prior_knowledge: false,
+            use_env_proxy: true,
+            breaker: BreakerConfig::default(),
+        }
+    }
+}
+
+/// Location of the config file: `$XDG_CONFIG_HOME/rustdash/config.toml`,
+/// falling back to `~/.config/rustdash/config.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("rustdash").join("config.toml"))
+}
+
+impl Settings {
+    /// Load settings from the config file. A missing file yields the defaults;
+    /// any omitted key falls back to its default value.
+    pub fn load() -> Result<Self> {
+        match config_path() {
+            Some(path) if path.exists() => {
+                let contents = fs::read_to_string(&path)?;
+                let settings: Settings = toml::from_str(&contents)
+                    .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))?;
+                settings
+                    .validate()
+                    .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))?;
+                Ok(settings)
+            }
+            _ => Ok(Settings::default()),
+        }
+    }
+
+    /// Refuse values that would stall or crash the fetch loop and limiters, or
+    /// be taken for options by `ssh`.
+    pub fn validate(&self) -> Result<()> {
+        if self.ui.refresh_interval_seconds == 0 {
+            bail!("ui.refresh_interval_seconds must be at least 1");
+        }
+        for (section, host) in [("prometheus", &self.prometheus.ssh_host), ("loki", &self.loki.ssh_host)] {
+            if let Some(host) = host {
+                validate_ssh_host(section, host)?;
+            }
+        }
+        for (section, rate) in [("prometheus", self.prometheus.max_queries_per_second), ("loki", self.loki.max_queries_per_second)] {
+            if let Some(rate) = rate.filter(|rate| !(rate.is_finite() && *rate >= MIN_QUERIES_PER_SECOND)) {
+                bail!("{}.max_queries_per_second must be at least {}, got {}", section, MIN_QUERIES_PER_SECOND, rate);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the overrides of the named profile.
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown profile '{}'", name))?;
+        if let Some(url) = profile.prometheus_url {
+            self.prometheus.base_url = url;
+        }
+        if let Some(url) = profile.loki_url {
+            self.loki.base_url = url;
+        }
+        if let Some(tenant_id) = profile.loki_tenant_id {
+            self.loki.tenant_id = Some(tenant_id);
+        }
+        if let Some(auth) = profile.prometheus_auth {
+            self.prometheus.auth = auth;
+        }
+        if let Some(auth) = profile.loki_auth {
+            self.loki.auth = auth;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Character-level diff between two log lines, for spotting the one field
+//! that differs between otherwise identical repeated messages (`d` on a
+//! selected line compares it with the line before).
+
+/// How a run of characters differs between the two lines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Same,
+    Removed, // Only in the older line
+    Added,   // Only in the newer line
+}
+
+/// A run of characters with the same [`Change`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub change: Change,
+    pub text: String,
+}
+
+/// Largest middle section (chars × chars, after the common prefix and
+/// suffix are taken off) compared character by character; anything bigger
+/// is shown as replaced wholesale rather than risk a slow redraw.
+const MAX_CELLS: usize = 1_000_000;
+
+/// Segments turning `old` into `new`: a longest common subsequence of
+/// characters, with the removed and added runs in between.
+pub fn diff(old: &str, new: &str) -> Vec<Segment> {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut changes = vec![Change::Same; prefix];
+    if old_middle.len() * new_middle.len() > MAX_CELLS {
+        changes.extend(std::iter::repeat_n(Change::Removed, old_middle.len()));
+        changes.extend(std::iter::repeat_n(Change::Added, new_middle.len()));
+    } else {
+        changes.extend(lcs_changes(old_middle, new_middle));
+    }
+    changes.extend(std::iter::repeat_n(Change::Same, suffix));
+
+    // Walk both lines along the per-character changes, merging runs
+    let (mut old_chars, mut new_chars) = (old.iter(), new.iter());
+    let mut segments: Vec<Segment> = Vec::new();
+    for change in changes {
+        let c = match change {
+            Change::Removed => old_chars.next(),
+            Change::Added => new_chars.next(),
+            Change::Same => {
+                old_chars.next();
+                new_chars.next()
+            }
+        };
+        let Some(&c) = c else { continue };
+        match segments.last_mut() {
+            Some(last) if last.change == change => last.text.push(c),
+            _ => segments.push(Segment { change, text: c.to_string() }),
+        }
+    }
+    segments
+}
+
+/// The segments of the older line: what both share and what was removed.
+pub fn old_side(segments: &[Segment]) -> impl Iterator<Item = &Segment> {
+    segments.iter().filter(|segment| segment.change != Change::Added)
+}
+
+/// The segments of the newer line: what both share and what was added.
+pub fn new_side(segments: &[Segment]) -> impl Iterator<Item = &Segment> {
+    segments.iter().filter(|segment| segment.change != Change::Removed)
+}
+
+/// Places where the lines differ: runs of removed and/or added characters.
+pub fn change_count(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .enumerate()
+        .filter(|(idx, segment)| {
+            // A removal directly followed by an addition is one replacement
+            segment.change != Change::Same
+                && !(segment.change == Change::Added && *idx > 0 && segments[idx - 1].change == Change::Removed)
+        })
+        .count()
+}
+
+/// Per-character changes from a longest common subsequence table, removals
+/// before additions where both apply.
+fn lcs_changes(old: &[char], new: &[char]) -> Vec<Change> {
+    let width = new.len() + 1;
+    // lengths[i * width + j]: LCS length of old[i..] and new[j..]
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(Change::Same);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            changes.push(Change::Removed);
+            i += 1;
+        } else {
+            changes.push(Change::Added);
+            j += 1;
+        }
+    }
+    changes.extend(std::iter::repeat_n(Change::Removed, old.len() - i));
+    changes.extend(std::iter::repeat_n(Change::Added, new.len() - j));
+    changes
+}
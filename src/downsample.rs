@@ -0,0 +1,58 @@
+//! Point budgets for charts: picking a range query `step` that yields about
+//! one point per column, and thinning series that are denser than that.
+
+/// Steps a range query is rounded up to, in seconds, so consecutive refreshes
+/// of the same window line up on the same timestamps.
+const NICE_STEPS: [i64; 16] = [
+    1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1800, 3600, 7200, 21600, 86400,
+];
+
+/// Smallest nice step that covers `window_seconds` in at most `columns` points.
+pub fn auto_step(window_seconds: i64, columns: usize) -> i64 {
+    let wanted = (window_seconds as f64 / columns.max(1) as f64).ceil() as i64;
+    NICE_STEPS
+        .iter()
+        .copied()
+        .find(|&step| step >= wanted)
+        .unwrap_or_else(|| wanted.max(1))
+}
+
+/// Largest-Triangle-Three-Buckets: reduce `points` to `threshold` points while
+/// keeping the visual shape (peaks and dips survive, unlike plain averaging).
+/// Points must be sorted by x; the first and last are always kept.
+pub fn lttb(points: &[(f64, f64)], threshold: usize) -> Vec<(f64, f64)> {
+    if threshold >= points.len() || threshold < 3 {
+        return points.to_vec();
+    }
+
+    let mut sampled = Vec::with_capacity(threshold);
+    sampled.push(points[0]);
+
+    // Everything between the endpoints is split into threshold - 2 buckets
+    let bucket_size = (points.len() - 2) as f64 / (threshold - 2) as f64;
+    let mut selected = 0;
+    for bucket in 0..threshold - 2 {
+        let start = (bucket as f64 * bucket_size) as usize + 1;
+        let end = ((bucket + 1) as f64 * bucket_size) as usize + 1;
+
+        // The next bucket's average is the third corner of the triangle
+        let next_end = (((bucket + 2) as f64 * bucket_size) as usize + 1).min(points.len());
+        let next = &points[end..next_end.max(end + 1)];
+        let avg_x = next.iter().map(|p| p.0).sum::<f64>() / next.len() as f64;
+        let avg_y = next.iter().map(|p| p.1).sum::<f64>() / next.len() as f64;
+
+        let (ax, ay) = points[selected];
+        let mut best_area = -1.0;
+        for (idx, &(x, y)) in points.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (y - ay) - (ax - x) * (avg_y - ay)).abs();
+            if area > best_area {
+                best_area = area;
+                selected = idx;
+            }
+        }
+        sampled.push(points[selected]);
+    }
+
+    sampled.push(points[points.len() - 1]);
+    sampled
+}
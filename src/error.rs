@@ -0,0 +1,87 @@
+//! Classes of backend errors, shared by the Prometheus and Loki clients, so
+//! callers can tell an outage from bad credentials or a query that asked for
+//! too much without matching on message text.
+//!
+//! The only place messages are still looked at is here: servers report
+//! limits and evaluation timeouts as plain 4xx/5xx bodies.
+
+use reqwest::StatusCode;
+
+/// What kind of failure a request ended in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    Unreachable, // No answer: connection refused, DNS, TLS, proxy
+    Timeout,     // The request or the query's evaluation ran out of time
+    Auth,        // 401/403: credentials missing or rejected
+    Limit,       // The query asked for too much, e.g. Loki's range limit
+    Rejected,    // Any other error answer, e.g. a query that doesn't parse
+    Malformed,   // An answer that couldn't be decoded
+}
+
+impl ErrorClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::Unreachable => "unreachable",
+            ErrorClass::Timeout => "timed out",
+            ErrorClass::Auth => "unauthorized",
+            ErrorClass::Limit => "over a limit",
+            ErrorClass::Rejected => "rejected the query",
+            ErrorClass::Malformed => "sent a bad response",
+        }
+    }
+
+    /// Whether retrying the same request later may succeed on its own.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, ErrorClass::Unreachable | ErrorClass::Timeout)
+    }
+}
+
+/// Class of an error answer with HTTP `status` and body `message`.
+pub fn classify_status(status: StatusCode, message: &str) -> ErrorClass {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ErrorClass::Auth,
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => ErrorClass::Timeout,
+        // A proxy in front with nothing to forward to, or a server shutting down
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => ErrorClass::Unreachable,
+        _ => classify_message(message).unwrap_or(ErrorClass::Rejected),
+    }
+}
+
+/// Class of a server error message, if it says what went wrong.
+pub fn classify_message(message: &str) -> Option<ErrorClass> {
+    if is_limit_error(message) {
+        Some(ErrorClass::Limit)
+    } else if is_timeout_error(message) {
+        Some(ErrorClass::Timeout)
+    } else {
+        None
+    }
+}
+
+/// Whether a server error says the query asked for too much, e.g. Loki's
+/// "the query time range exceeds the limit" or "max entries limit per
+/// query exceeded".
+pub fn is_limit_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    (message.contains("limit") && (message.contains("exceed") || message.contains("reached")))
+        || message.contains("too many")
+        || message.contains("maximum of series")
+}
+
+/// Whether a server error says the query ran out of time, e.g. Prometheus'
+/// "query timed out in expression evaluation" or a proxy's 504.
+fn is_timeout_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("timed out") || message.contains("timeout")
+}
+
+/// Class of a failed reqwest request: its status if the response was turned
+/// into an error, otherwise whether it timed out or never got an answer.
+pub fn classify_request(e: &reqwest::Error) -> ErrorClass {
+    match e.status() {
+        Some(status) => classify_status(status, &e.to_string()),
+        None if e.is_timeout() => ErrorClass::Timeout,
+        None if e.is_decode() => ErrorClass::Malformed,
+        None => ErrorClass::Unreachable,
+    }
+}
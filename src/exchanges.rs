@@ -0,0 +1,157 @@
+//! The last HTTP exchange of every query (`D`): the literal request URL
+//! with its parameters and the raw response body, to see why a panel is
+//! blank without reaching for curl. Headers, and with them any
+//! credentials (reqwest moves those in the URL into one), are left out.
+
+use crate::prometheus;
+use crate::query_stats::Backend;
+use crate::ui::{ActivePanel, AppState};
+use chrono::{DateTime, Local};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Response bodies are kept up to this size.
+pub const MAX_BODY_BYTES: usize = 256 * 1024;
+
+/// One request and what came back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub backend: Backend,
+    pub query: String,
+    pub url: String,         // With its parameters, as sent
+    pub status: Option<u16>, // None when no response came back
+    pub body: String,        // Raw response body, or why there was none
+    pub truncated: bool,     // The body was cut at MAX_BODY_BYTES
+    pub elapsed_ms: f64,
+    pub at: DateTime<Local>,
+}
+
+impl Exchange {
+    /// An exchange for `request`, sent at `at`; None if it can't be built.
+    pub fn new(backend: Backend, query: &str, request: &reqwest::RequestBuilder, at: DateTime<Local>) -> Option<Self> {
+        let request = request.try_clone()?.build().ok()?;
+        Some(Self {
+            backend,
+            query: query.to_string(),
+            url: request.url().to_string(),
+            status: None,
+            body: String::new(),
+            truncated: false,
+            elapsed_ms: 0.0,
+            at,
+        })
+    }
+
+    /// Fill in the response, or the error in place of one.
+    pub fn respond(mut self, status: Option<u16>, body: &str, elapsed_ms: f64) -> Self {
+        let mut end = body.len().min(MAX_BODY_BYTES);
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.status = status;
+        self.body = body[..end].to_string();
+        self.truncated = end < body.len();
+        self.elapsed_ms = elapsed_ms;
+        self
+    }
+
+    /// The URL without its parameters.
+    pub fn endpoint(&self) -> &str {
+        self.url.split('?').next().unwrap_or(&self.url)
+    }
+
+    /// The request's parameters, decoded.
+    pub fn params(&self) -> Vec<(String, String)> {
+        Url::parse(&self.url).map_or_else(|_| Vec::new(), |url| url.query_pairs().into_owned().collect())
+    }
+
+    /// The body pretty-printed if it is JSON, otherwise as it came.
+    pub fn pretty_body(&self) -> String {
+        match serde_json::from_str::<serde_json::Value>(&self.body) {
+            Ok(json) if !self.truncated => serde_json::to_string_pretty(&json).unwrap_or_else(|_| self.body.clone()),
+            _ => self.body.clone(),
+        }
+    }
+
+    /// A curl command sending the same request (without the auth headers).
+    pub fn curl(&self) -> String {
+        let quote = |s: &str| format!("'{}'", s.replace('\'', r"'\''"));
+        let mut command = format!("curl -G {}", quote(self.endpoint()));
+        for (name, value) in self.params() {
+            command.push_str(&format!(" \\\n  --data-urlencode {}", quote(&format!("{}={}", name, value))));
+        }
+        command
+    }
+
+    /// The request as curl and the response, for the clipboard.
+    pub fn to_text(&self) -> String {
+        let status = self.status.map_or("no response".to_string(), |status| format!("HTTP {}", status));
+        let truncated = if self.truncated { " (truncated)" } else { "" };
+        format!("{}\n\n{}{}\n{}\n", self.curl(), status, truncated, self.pretty_body())
+    }
+}
+
+/// The latest exchange per query, shared by the clients that record them and the UI.
+#[derive(Debug, Default)]
+pub struct ExchangeLog {
+    last: Mutex<HashMap<(Backend, String), Exchange>>,
+}
+
+impl ExchangeLog {
+    pub fn record(&self, exchange: Exchange) {
+        self.last.lock().unwrap().insert((exchange.backend, exchange.query.clone()), exchange);
+    }
+
+    pub fn get(&self, backend: Backend, query: &str) -> Option<Exchange> {
+        self.last.lock().unwrap().get(&(backend, query.to_string())).cloned()
+    }
+
+    /// Every query of `backend` (or of both), newest exchange first.
+    pub fn newest(&self, backend: Option<Backend>) -> Vec<(Backend, String)> {
+        let last = self.last.lock().unwrap();
+        let mut exchanges: Vec<&Exchange> = last.values().filter(|exchange| backend.is_none_or(|backend| exchange.backend == backend)).collect();
+        exchanges.sort_by(|a, b| b.at.cmp(&a.at).then_with(|| a.query.cmp(&b.query)));
+        exchanges.into_iter().map(|exchange| (exchange.backend, exchange.query.clone())).collect()
+    }
+}
+
+/// The debug popup while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct DebugPopup {
+    pub queries: Vec<(Backend, String)>, // The focused panel's own queries first
+    pub selected: usize,
+    pub scroll_offset: usize, // Lines of the response scrolled past
+}
+
+impl DebugPopup {
+    /// The exchanges behind what has focus: the history chart, the selected
+    /// panel of the panels page, or the focused panel, its own queries first.
+    pub fn open(state: &AppState) -> Self {
+        let log = &state.exchanges;
+        let (backend, own): (Option<Backend>, Vec<String>) = if let Some(drill_in) = &state.drill_in {
+            (Some(Backend::Prometheus), drill_in.history.as_ref().map(|history| history.queries.clone()).unwrap_or_default())
+        } else if let Some(page) = &state.panels_page {
+            (Some(Backend::Prometheus), state.panels.get(page.selected).map(|panel| vec![panel.query.clone()]).unwrap_or_default())
+        } else {
+            match state.active_panel {
+                ActivePanel::Metrics => {
+                    (Some(Backend::Prometheus), prometheus::uri_metric_queries(&state.metrics_time_range.to_prometheus_range()).to_vec())
+                }
+                ActivePanel::Logs => (Some(Backend::Loki), Vec::new()),
+                ActivePanel::None => (None, Vec::new()),
+            }
+        };
+        let mut queries: Vec<(Backend, String)> = own
+            .into_iter()
+            .filter(|query| log.get(Backend::Prometheus, query).is_some())
+            .map(|query| (Backend::Prometheus, query))
+            .collect();
+        for query in log.newest(backend) {
+            if !queries.contains(&query) {
+                queries.push(query);
+            }
+        }
+        Self { queries, ..Self::default() }
+    }
+}
@@ -0,0 +1,141 @@
+//! Run one Prometheus query against several endpoints (e.g. one per region)
+//! and merge the answers, either keeping each endpoint's series apart or
+//! summing matching series across endpoints.
+
+use crate::prometheus::{PrometheusClient, PrometheusData, PrometheusResponse, PrometheusResult};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Label added to each series in [`Aggregate::Separate`] mode. Names starting
+/// with `__` are reserved by Prometheus, so it can't clash with a real label.
+pub const ENDPOINT_LABEL: &str = "__endpoint__";
+
+/// How the answers of several endpoints are combined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    #[default]
+    Separate, // One series per endpoint, tagged with ENDPOINT_LABEL
+    Sum,      // Series with the same labels are added up point by point
+}
+
+/// A set of named endpoints queried together.
+#[derive(Debug, Clone)]
+pub struct Fanout {
+    endpoints: Vec<(String, PrometheusClient)>,
+    aggregate: Aggregate,
+}
+
+impl Fanout {
+    pub fn new(aggregate: Aggregate) -> Self {
+        Self {
+            endpoints: Vec::new(),
+            aggregate,
+        }
+    }
+
+    pub fn endpoint(mut self, name: &str, client: PrometheusClient) -> Self {
+        self.endpoints.push((name.to_string(), client));
+        self
+    }
+
+    /// Range query on every endpoint concurrently, merged per the aggregate
+    /// mode. Endpoints that fail are reported as warnings; only when all of
+    /// them fail is the query an error.
+    pub async fn query_range(&self, query: &str, start: &str, end: &str, step: &str) -> Result<PrometheusResponse> {
+        let mut tasks = tokio::task::JoinSet::new();
+        for (idx, (_, client)) in self.endpoints.iter().enumerate() {
+            let client = client.clone();
+            let (query, start, end, step) = (query.to_string(), start.to_string(), end.to_string(), step.to_string());
+            tasks.spawn(async move { (idx, client.query_range(&query, &start, &end, &step).await.map_err(Into::into)) });
+        }
+
+        let mut responses: Vec<Option<Result<PrometheusResponse>>> = self.endpoints.iter().map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            let (idx, response) = joined?;
+            responses[idx] = Some(response);
+        }
+
+        merge(
+            self.endpoints
+                .iter()
+                .zip(responses)
+                .filter_map(|((name, _), response)| Some((name.clone(), response?)))
+                .collect(),
+            self.aggregate,
+        )
+    }
+}
+
+/// Combine per-endpoint responses, in endpoint order.
+pub fn merge(responses: Vec<(String, Result<PrometheusResponse>)>, aggregate: Aggregate) -> Result<PrometheusResponse> {
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    let mut results = Vec::new();
+    let mut result_type = String::new();
+
+    for (name, response) in responses {
+        match response {
+            Ok(response) => {
+                warnings.extend(response.warnings.into_iter().map(|w| format!("{}: {}", name, w)));
+                result_type = response.data.result_type;
+                for mut result in response.data.result {
+                    result.metric.insert(ENDPOINT_LABEL.to_string(), name.clone());
+                    results.push(result);
+                }
+            }
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    if results.is_empty() && !errors.is_empty() {
+        return Err(anyhow::anyhow!(errors.join("; ")));
+    }
+    warnings.extend(errors);
+
+    if aggregate == Aggregate::Sum {
+        results = sum_series(results);
+    }
+
+    Ok(PrometheusResponse {
+        status: "success".to_string(),
+        data: PrometheusData { result_type, result: results, stats: None },
+        error_type: None,
+        error: None,
+        warnings,
+    })
+}
+
+/// Sorted label pairs of a series, and its points keyed by millisecond
+/// timestamp so equal timestamps from different servers line up.
+type SeriesKey = Vec<(String, String)>;
+type Points = BTreeMap<i64, (f64, f64)>;
+
+/// Add up series whose labels match once the endpoint label is dropped.
+fn sum_series(results: Vec<PrometheusResult>) -> Vec<PrometheusResult> {
+    let mut sums: BTreeMap<SeriesKey, Points> = BTreeMap::new();
+    for result in results {
+        let mut labels: Vec<(String, String)> = result
+            .metric
+            .into_iter()
+            .filter(|(name, _)| name != ENDPOINT_LABEL)
+            .collect();
+        labels.sort();
+        let points = sums.entry(labels).or_default();
+        for (t, value) in result.values.unwrap_or_default() {
+            if let Ok(value) = value.parse::<f64>() {
+                let entry = points.entry((t * 1000.0).round() as i64).or_insert((t, 0.0));
+                entry.1 += value;
+            }
+        }
+    }
+
+    sums.into_iter()
+        .map(|(labels, points)| PrometheusResult {
+            metric: labels.into_iter().collect::<HashMap<_, _>>(),
+            value: None,
+            values: Some(points.into_values().map(|(t, v)| (t, v.to_string())).collect()),
+        })
+        .collect()
+}
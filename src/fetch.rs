@@ -0,0 +1,684 @@
+use crate::downsample::{auto_step, lttb};
+use crate::error::ErrorClass;
+use crate::history::UriTotals;
+use crate::log_actions::LogFilter;
+use crate::loki::{LevelCounts, LogEntry, LokiClient, LokiError};
+use crate::panels::{self, PanelConfig, PanelData};
+use crate::prometheus::{MetricsData, PrometheusClient, PrometheusError, Ranking, ServerInfo, ServiceEdge, UriHistory};
+use crate::retention;
+use crate::silence::SilenceRule;
+use crate::slo::{self, BurnRates};
+use crate::triage::Alert;
+use crate::ui::AppState;
+use crate::uri_errors;
+use crate::variables::{self, Variable};
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// History snapshots fetched to fill the gap an outage left.
+const BACKFILL_POINTS: usize = 120;
+
+/// Recent error lines searched for each URI's last error.
+const ERROR_LOG_LIMIT: u32 = 200;
+
+/// Which sources a refresh fetches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefreshScope {
+    All,
+    Metrics, // Prometheus: the table, drill-in, service map and panels
+    Logs,    // Loki
+}
+
+impl RefreshScope {
+    pub fn includes_metrics(self) -> bool {
+        self != RefreshScope::Logs
+    }
+
+    pub fn includes_logs(self) -> bool {
+        self != RefreshScope::Metrics
+    }
+}
+
+/// Fetch metrics and logs once and merge them into the app state, marking
+/// new logs and preserving the user's scroll position and selection. A backend
+/// without a client isn't configured and is skipped entirely.
+pub async fn refresh(
+    app_state: &Mutex<AppState>,
+    prometheus_client: Option<&PrometheusClient>,
+    loki_client: Option<&LokiClient>,
+    log_limit: u32,
+    panel_configs: &[PanelConfig],
+) {
+    refresh_scope(app_state, prometheus_client, loki_client, log_limit, panel_configs, RefreshScope::All).await;
+}
+
+/// Like [`refresh`], limited to the sources in `scope`.
+pub async fn refresh_scope(
+    app_state: &Mutex<AppState>,
+    prometheus_client: Option<&PrometheusClient>,
+    loki_client: Option<&LokiClient>,
+    log_limit: u32,
+    panel_configs: &[PanelConfig],
+    scope: RefreshScope,
+) {
+    let loki_configured = loki_client.is_some();
+    let prometheus_client = prometheus_client.filter(|_| scope.includes_metrics());
+    let loki_client = loki_client.filter(|_| scope.includes_logs());
+    // Get current time range, ranking and drill-in target from state
+    let (time_range_str, ranking, window_minutes, drill_in_uri, service_map_open, about_open, chart_columns, counter_views, slo_configs) = {
+        let state = app_state.lock().await;
+        (
+            state.metrics_time_range.to_prometheus_range(),
+            state.metrics_ranking,
+            state.metrics_time_range.as_minutes(), // None charts "All" from the recorded history
+            state.drill_in.as_ref().map(|d| d.uri.clone()),
+            state.service_map.is_some(),
+            state.about.is_some(),
+            // The drill-in chart spans the width minus borders and y-axis labels
+            usize::from(state.last_terminal_width.saturating_sub(10)).max(20),
+            state.counter_views.clone(),
+            state.slos.iter().map(|slo| slo.config.clone()).collect::<Vec<_>>(),
+        )
+    };
+
+    // A manual refresh also revalidates build info and label names
+    if std::mem::take(&mut app_state.lock().await.refresh_static) {
+        if let Some(client) = prometheus_client {
+            client.static_cache().invalidate();
+        }
+        if let Some(client) = loki_client {
+            client.static_cache().invalidate();
+        }
+    }
+    
+    // Mark the sources in flight for the panel spinners, and note where the
+    // data of a backend that is down ends, to backfill once it answers again
+    let (metrics_gap_start, logs_gap_start) = {
+        let mut state = app_state.lock().await;
+        let now = Local::now();
+        if prometheus_client.is_some() {
+            state.metrics_fetch_started = Some(now);
+        }
+        if loki_client.is_some() {
+            state.logs_fetch_started = Some(now);
+        }
+        (
+            state.metrics_backoff.is_down().then(|| state.history.latest()).flatten(),
+            state.logs_backoff.is_down().then(|| state.all_logs.last().map(|log| log.nanos)).flatten(),
+        )
+    };
+    
+    // Fetch metrics and logs concurrently, applying each as soon as it arrives
+    // so a slow backend doesn't hold up the other
+    tokio::join!(
+        async {
+            let Some(client) = prometheus_client else { return };
+            let variables = refresh_variables(app_state, client).await;
+            let panel_configs = variables::expand(panel_configs, &variables);
+            let results = tokio::join!(
+                client.get_metrics(&time_range_str, ranking),
+                async {
+                    match (&drill_in_uri, window_minutes) {
+                        (Some(uri), Some(minutes)) => Some(client.get_uri_history(uri, minutes, chart_columns).await),
+                        _ => None,
+                    }
+                },
+                async {
+                    if service_map_open {
+                        Some(client.get_service_graph(&time_range_str).await)
+                    } else {
+                        None
+                    }
+                },
+                async {
+                    let mut data = Vec::with_capacity(panel_configs.len());
+                    for (idx, (config, repeat)) in panel_configs.iter().enumerate() {
+                        let panel = panels::fetch_panel(client, config, counter_views.get(&idx).copied()).await;
+                        data.push(match repeat {
+                            Some((entry, value)) => panel.repeated_for(*entry, value),
+                            None => panel,
+                        });
+                    }
+                    data
+                },
+                client.get_uri_totals(),
+                client.get_build_info(),
+                client.get_clock_skew(),
+                async {
+                    if about_open {
+                        Some(client.get_server_info().await)
+                    } else {
+                        None
+                    }
+                },
+                async {
+                    let mut burn_rates = Vec::with_capacity(slo_configs.len());
+                    for config in &slo_configs {
+                        burn_rates.push(slo::get_burn_rates(client, config).await);
+                    }
+                    burn_rates
+                }
+            );
+            // Back after an outage: sample the counters over the gap
+            let mut backfill = Vec::new();
+            if let Some(after) = metrics_gap_start.filter(|_| is_reachable(&results.0)) {
+                let (after, end) = (after as i64, Local::now().timestamp());
+                let step = auto_step(end - after, BACKFILL_POINTS);
+                backfill = client.get_uri_totals_range(after + step, end, step).await.unwrap_or_default();
+            }
+            let mut state = app_state.lock().await;
+            for (timestamp, totals) in backfill {
+                state.history.record(timestamp, totals);
+            }
+            apply_prometheus(&mut state, results, window_minutes, ranking, chart_columns, drill_in_uri, Local::now());
+            state.metrics_loading = false; // Clear loading state
+            state.metrics_fetch_started = None;
+        },
+        async {
+            let Some(client) = loki_client else {
+                // Without Loki followed local files are the only source
+                let mut state = app_state.lock().await;
+                if scope.includes_logs() && state.log_files.is_some() {
+                    let (mut logs, filter) = (Vec::new(), state.log_filter.clone());
+                    merge_log_files(&mut state, &mut logs, &filter, log_limit);
+                    apply_logs(&mut state, Ok(logs), Local::now());
+                }
+                return;
+            };
+            let (filter, submitted, uri_errors_enabled, log_lines, log_queries) = {
+                let state = app_state.lock().await;
+                let submitted = state.log_query_bar.as_ref().and_then(|bar| bar.submitted.clone());
+                (state.log_filter.clone(), submitted, state.uri_error_pattern.is_some(), state.log_retention.entries, state.log_queries.clone())
+            };
+            // A query submitted from the query bar is tried in place of the filter
+            let filter = submitted.as_deref().map_or(filter, LogFilter::query);
+            let mut logs = if let Some(query) = &filter.query {
+                client.tail(query, log_limit).await
+            } else if filter.is_active() {
+                client.get_filtered_logs(filter.stream.as_deref(), filter.pattern.as_deref(), log_limit).await
+            } else if !log_queries.is_empty() {
+                client.get_overlay_logs(&log_queries, log_limit).await
+            } else {
+                client.get_recent_logs(log_limit).await
+            };
+            // Back after an outage: fetch what was logged in between (of the
+            // unfiltered stream; a filtered view just shows what it gets)
+            let logs_gap_start = logs_gap_start.filter(|_| !filter.is_active());
+            if let (Some(after), Ok(recent)) = (logs_gap_start, logs.as_mut()) {
+                if let Some(before) = recent.first().map(|log| log.nanos).filter(|&before| before > after) {
+                    if let Ok(mut missed) = client.get_logs_between(after, before, log_lines as u32).await {
+                        missed.append(recent);
+                        *recent = missed;
+                    }
+                }
+            }
+            // Log volume histogram for the last 30 minutes, one bucket per minute
+            let volume = client.get_log_volume(30, 1).await.unwrap_or_default();
+            let version = client.get_build_info().await.ok();
+            // Recent errors for the metrics table's "Last error" column
+            let errors = if uri_errors_enabled {
+                client.get_error_logs(ERROR_LOG_LIMIT).await.ok()
+            } else {
+                None
+            };
+            // One single-line query per stream that is expected to keep logging
+            let rules: Vec<SilenceRule> = app_state.lock().await.silence_alarms.iter().map(|a| a.rule.clone()).collect();
+            let mut silence = Vec::with_capacity(rules.len());
+            for rule in &rules {
+                silence.push(client.has_logs_since(&rule.selector, rule.minutes).await);
+            }
+            let mut state = app_state.lock().await;
+            apply_silence(&mut state, silence, Local::now());
+            state.log_volume = volume;
+            state.loki_version = version.or(state.loki_version.take());
+            state.loki_clock_skew = client.clock_skew().or(state.loki_clock_skew);
+            if let Some(mut errors) = errors {
+                errors.retain(|log| log.level == "ERROR");
+                state.error_logs = errors;
+            }
+            if let Some(query) = &submitted {
+                settle_submitted_query(&mut state, query, &logs);
+            }
+            if let Ok(logs) = logs.as_mut() {
+                merge_log_files(&mut state, logs, &filter, log_limit);
+            }
+            // Lines fetched for a filter changed in the meantime belong to the old view
+            if state.log_filter == filter {
+                apply_logs(&mut state, logs, Local::now());
+            }
+            state.logs_fetch_started = None;
+        }
+    );
+    
+    let mut state = app_state.lock().await;
+    let now = Local::now();
+    if let (Some(pattern), Some(metrics)) = (&state.uri_error_pattern, &state.metrics) {
+        let uris = metrics.uri_metrics.iter().map(|m| m.uri.as_str());
+        state.uri_errors = uri_errors::latest_errors(uris, &state.error_logs, pattern);
+    }
+    if scope.includes_metrics() {
+        state.metrics_loading = false; // Also when Prometheus isn't configured
+    }
+    if !loki_configured {
+        state.status = "Connected".to_string();
+    }
+    state.last_fetch = now.format("%Y-%m-%d %H:%M:%S").to_string();
+}
+
+/// Before going live, fill the "All" history with counter samples and the
+/// log buffer with the newest lines of the last `seconds`, so a dashboard
+/// opened mid-incident starts with that much context.
+pub async fn backfill(
+    app_state: &Mutex<AppState>,
+    prometheus_client: Option<&PrometheusClient>,
+    loki_client: Option<&LokiClient>,
+    seconds: i64,
+) {
+    let (log_lines, log_queries) = {
+        let mut state = app_state.lock().await;
+        state.status = format!("Backfilling the last {}...", humanize_duration(seconds));
+        (state.log_retention.entries, state.log_queries.clone())
+    };
+    let end = Local::now().timestamp();
+    let (totals, logs) = tokio::join!(
+        async {
+            let client = prometheus_client?;
+            let step = auto_step(seconds, BACKFILL_POINTS);
+            client.get_uri_totals_range(end - seconds, end, step).await.ok()
+        },
+        async {
+            let client = loki_client?;
+            if !log_queries.is_empty() {
+                return client.get_overlay_logs_since(&log_queries, seconds, log_lines as u32).await.ok();
+            }
+            // Settle on the stream to follow first, as a refresh would
+            client.get_recent_logs(1).await.ok()?;
+            client.get_logs_since(seconds, log_lines as u32).await.ok()
+        },
+    );
+
+    let mut state = app_state.lock().await;
+    let samples = totals.as_ref().map_or(0, Vec::len);
+    for (timestamp, totals) in totals.unwrap_or_default() {
+        state.history.record(timestamp, totals);
+    }
+    let lines = logs.as_ref().map_or(0, Vec::len);
+    if let Some(logs) = logs {
+        apply_logs(&mut state, Ok(logs), Local::now());
+    }
+    state.status = format!(
+        "Backfilled the last {}: {} log lines, {} history samples",
+        humanize_duration(seconds),
+        lines,
+        samples
+    );
+}
+
+/// Look up the template variables' values (cached like other label
+/// lookups) and return the variables to resolve the panels with. A
+/// selection picked while the lookup ran is kept for the next refresh.
+async fn refresh_variables(app_state: &Mutex<AppState>, client: &PrometheusClient) -> Vec<Variable> {
+    let mut refreshed = app_state.lock().await.variables.clone();
+    let before: Vec<Option<String>> = refreshed.iter().map(|variable| variable.selected.clone()).collect();
+    variables::load_values(client, &mut refreshed).await;
+    let mut state = app_state.lock().await;
+    for ((variable, refreshed), before) in state.variables.iter_mut().zip(&refreshed).zip(before) {
+        let picked = (variable.selected != before).then(|| variable.selected.clone());
+        *variable = refreshed.clone();
+        if let Some(selected) = picked {
+            variable.selected = selected;
+        }
+    }
+    refreshed
+}
+
+/// "2h", "90m" or "45s": the largest unit that divides `seconds` evenly.
+fn humanize_duration(seconds: i64) -> String {
+    match seconds {
+        s if s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Whether Prometheus answered: some queries may fail on a healthy server,
+/// but every query failing with nothing to show means it is unreachable.
+fn is_reachable(metrics_result: &Result<MetricsData, PrometheusError>) -> bool {
+    matches!(metrics_result, Ok(metrics) if metrics.errors.is_empty() || !metrics.uri_metrics.is_empty())
+}
+
+type PrometheusResults = (
+    Result<MetricsData, PrometheusError>,
+    Option<Result<UriHistory, PrometheusError>>,
+    Option<Result<Vec<ServiceEdge>, PrometheusError>>,
+    Vec<PanelData>,
+    Result<HashMap<String, UriTotals>, PrometheusError>,
+    Result<String, PrometheusError>,
+    Result<f64, PrometheusError>,
+    Option<Result<ServerInfo, PrometheusError>>,
+    Vec<Result<BurnRates, PrometheusError>>,
+);
+
+fn apply_prometheus(
+    state: &mut AppState,
+    (metrics_result, history_result, service_graph_result, panel_data, totals, version, clock_skew, server_info, burn_rates): PrometheusResults,
+    window_minutes: Option<i64>,
+    ranking: Ranking,
+    chart_columns: usize,
+    drill_in_uri: Option<String>,
+    now: DateTime<Local>,
+) {
+    if let Ok(version) = version {
+        state.prometheus_version = Some(version);
+    }
+    if let Ok(clock_skew) = clock_skew {
+        state.prometheus_clock_skew = Some(clock_skew);
+    }
+    // The query for the totals runs every time; what it ran into is the outage's cause
+    let cause = totals.as_ref().err().map(PrometheusError::class);
+    if let Ok(totals) = totals {
+        state.history.record(now.timestamp_millis() as f64 / 1000.0, totals);
+    }
+    // Lifetime counters say nothing about this session; show what was observed
+    let metrics_result = metrics_result.map(|mut metrics| {
+        if window_minutes.is_none() {
+            for metric in metrics.uri_metrics.iter_mut() {
+                if let Some((latency_ms, requests_per_min)) = state.history.uri_average(&metric.uri) {
+                    metric.avg_duration_ms = latency_ms;
+                    metric.request_count = requests_per_min;
+                }
+            }
+            ranking.sort(&mut metrics.uri_metrics);
+        }
+        metrics
+    });
+    if is_reachable(&metrics_result) {
+        state.metrics_backoff.success();
+    } else {
+        state.metrics_backoff.failure(now, state.refresh_interval_seconds);
+        state.metrics_backoff.cause = cause;
+    }
+    let mut refreshed = false;
+    match metrics_result {
+        Ok(metrics) if metrics.errors.is_empty() || !metrics.uri_metrics.is_empty() => {
+            refreshed = true;
+            state.metrics = Some(metrics);
+            state.metrics_last_success = Some(now);
+            state.hidden_uri_count = 0;
+        }
+        Ok(failed) => {
+            // Keep showing the last good data, but surface the new errors
+            match state.metrics.as_mut() {
+                Some(previous) if !previous.uri_metrics.is_empty() => previous.errors = failed.errors,
+                _ => state.metrics = Some(failed),
+            }
+        }
+        Err(_) => {}
+    }
+    state.hide_ignored_metrics();
+    if let Some(metrics) = state.metrics.as_ref().filter(|_| refreshed) {
+        state.metric_changes.update(window_minutes, &metrics.uri_metrics, now, state.change_threshold_pct);
+    }
+    state.resolve_selected_uri();
+    state.panels = panel_data;
+    apply_slos(state, burn_rates, now);
+    
+    // "All" is charted from what this session (and, if persisted, earlier ones) observed
+    let history_result = match (window_minutes, state.drill_in.as_ref()) {
+        (None, Some(drill_in)) => {
+            let history = state.history.uri_history(&drill_in.uri);
+            Some(Ok(UriHistory {
+                latency_ms: lttb(&history.latency_ms, chart_columns),
+                requests_per_min: lttb(&history.requests_per_min, chart_columns),
+                queries: Vec::new(),
+            }))
+        }
+        _ => history_result,
+    };
+    
+    // The drill-in may have been closed or switched while we were fetching
+    let time_cursor = state.time_cursor;
+    if let (Some(drill_in), Some(result)) = (state.drill_in.as_mut(), history_result) {
+        if Some(&drill_in.uri) == drill_in_uri.as_ref() {
+            match result {
+                Ok(history) => {
+                    drill_in.history = Some(history);
+                    drill_in.error = None;
+                    // Keep the cursor on the instant under investigation as samples move
+                    drill_in.follow_time_cursor(time_cursor);
+                }
+                Err(e) => drill_in.error = Some(e.to_string()),
+            }
+        }
+    }
+    if let (Some(service_map), Some(result)) = (state.service_map.as_mut(), service_graph_result) {
+        match result {
+            Ok(edges) => {
+                service_map.edges = Some(edges);
+                service_map.error = None;
+            }
+            Err(e) => service_map.error = Some(e.to_string()),
+        }
+    }
+    if let (Some(about), Some(result)) = (state.about.as_mut(), server_info) {
+        match result {
+            Ok(info) => {
+                about.prometheus = Some(info);
+                about.error = None;
+            }
+            Err(e) => about.error = Some(e.to_string()),
+        }
+    }
+}
+
+/// Update the silence alarms, ringing the bell for any that just went off.
+fn apply_silence(state: &mut AppState, results: Vec<Result<bool, LokiError>>, now: DateTime<Local>) {
+    let mut ring = false;
+    for (alarm, result) in state.silence_alarms.iter_mut().zip(results) {
+        match result {
+            // Unless this stream's alerts are ignored for the session
+            Ok(has_logs) if alarm.update(has_logs, now) => {
+                ring |= state.triage.nags(&Alert::Silent(alarm.rule.label().to_string()), alarm.silent_since.unwrap_or(now));
+            }
+            Ok(_) => {}
+            Err(e) => alarm.error = Some(e.to_string()),
+        }
+    }
+    if ring {
+        state.bell = true;
+    }
+}
+
+/// Update the SLOs' burn rates, ringing the bell for any alert that just
+/// went off or escalated.
+fn apply_slos(state: &mut AppState, results: Vec<Result<BurnRates, PrometheusError>>, now: DateTime<Local>) {
+    let mut ring = false;
+    for (slo, result) in state.slos.iter_mut().zip(results) {
+        match result {
+            // Unless this SLO's alerts are ignored for the session
+            Ok(burn_rates) if slo.update(burn_rates, now) => {
+                ring |= state.triage.nags(&Alert::Burning(slo.config.name.clone()), slo.burning_since.unwrap_or(now));
+            }
+            Ok(_) => {}
+            Err(e) => slo.error = Some(e.to_string()),
+        }
+    }
+    if ring {
+        state.bell = true;
+    }
+}
+
+/// Settle a query tried from the query bar: one Loki accepted becomes the
+/// filter and closes the bar, while Loki's error stays under the bar and
+/// the panel keeps what it showed.
+fn settle_submitted_query(state: &mut AppState, query: &str, logs: &Result<Vec<LogEntry>, LokiError>) {
+    // Closed or edited since it was sent
+    let Some(bar) = state.log_query_bar.as_mut().filter(|bar| bar.submitted.as_deref() == Some(query)) else {
+        return;
+    };
+    match logs {
+        Err(e) => bar.reject(e),
+        Ok(_) => {
+            state.log_query_bar = None;
+            state.status = format!("Showing {}", query);
+            state.last_log_query = Some(query.to_string());
+            state.set_log_filter(LogFilter::query(query));
+        }
+    }
+}
+
+/// Add the newest lines of the followed local files that pass `filter` to
+/// `logs`, keeping them in time order.
+fn merge_log_files(state: &mut AppState, logs: &mut Vec<LogEntry>, filter: &LogFilter, log_limit: u32) {
+    let Some(files) = state.log_files.as_mut() else { return };
+    let lines = files.poll(log_limit as usize);
+    if lines.is_empty() {
+        return;
+    }
+    let pattern = state.log_pattern.as_ref();
+    logs.extend(lines.into_iter().filter(|log| filter.matches(log, pattern)));
+    logs.sort_by_key(|log| log.nanos);
+}
+
+/// Merge a log fetch into the state, highlighting new lines and keeping the
+/// user's scroll position and selection. Lines older than the fetch are kept,
+/// up to the state's log retention limit.
+fn apply_logs(state: &mut AppState, logs_result: Result<Vec<LogEntry>, LokiError>, now: DateTime<Local>) {
+    let mut all_logs = match logs_result {
+        Ok(mut logs) => {
+            state.logs_last_success = Some(now);
+            state.logs_backoff.success();
+            let fetched = logs.len();
+            logs.retain(|log| !state.ignore.hides_log(&log.message));
+            state.hidden_log_count = fetched - logs.len();
+            if let Some(flushed) = state.logs_flushed_before {
+                logs.retain(|log| log.nanos > flushed);
+            }
+            // The fetch replaces what we had from its first line on
+            let first = logs.first().map_or(i64::MAX, |log| log.nanos);
+            let mut merged: Vec<LogEntry> = state.all_logs.iter().filter(|log| log.nanos < first).cloned().collect();
+            merged.extend(logs);
+            merged
+        }
+        Err(e) => {
+            // Keep the logs we already have and try again later
+            state.logs_backoff.failure(now, state.refresh_interval_seconds);
+            state.logs_backoff.cause = Some(e.class());
+            state.status = match e.class() {
+                ErrorClass::Auth => format!("Loki rejected the credentials ({}) - check the config", e),
+                class if class.is_transient() => format!("Loki {} - retrying", class.as_str()),
+                class => format!("Loki {}: {}", class.as_str(), e),
+            };
+            return;
+        }
+    };
+    
+    // Drop the oldest lines beyond the retention limit, shifting indices with them
+    let trimmed = retention::logs_over_limit(&all_logs, state.log_retention);
+    all_logs.drain(..trimmed);
+    state.expanded_log_index = state.expanded_log_index.and_then(|idx| idx.checked_sub(trimmed));
+    
+    // Preserve scroll position and selection when updating logs
+    let old_scroll_offset = state.log_scroll_offset.saturating_sub(trimmed);
+    let old_selected_index = state.selected_log_index.map(|idx| idx.saturating_sub(trimmed));
+    let terminal_height = state.last_terminal_height;
+    
+    // Keep track of previous state before processing
+    let old_logs = state.all_logs.split_off(trimmed.min(state.all_logs.len()));
+    let old_fetch_count = state.last_fetch_count.saturating_sub(trimmed);
+    
+    // Process new logs
+    let mut marked_logs = all_logs;
+    let new_count = marked_logs.len();
+    
+    // Initialize all logs as not new
+    for log in marked_logs.iter_mut() {
+        log.is_new = false;
+    }
+    
+    // Handle different cases
+    if !state.has_initial_fetch {
+        // First fetch - don't highlight anything
+        state.has_initial_fetch = true;
+        state.last_fetch_count = new_count;
+        state.status = format!("Connected - Initial: {} logs", new_count);
+    } else if new_count > old_fetch_count {
+        // New logs detected! Highlight only the new ones
+        let new_log_count = new_count - old_fetch_count;
+        
+        // Mark only the NEW logs (at the end of the list)
+        for log in marked_logs.iter_mut().skip(old_fetch_count) {
+            log.is_new = true;
+        }
+        
+        // Clear old highlights when we get truly new logs
+        state.last_fetch_count = new_count;
+        state.status = format!("Connected - {} new logs!", new_log_count);
+    } else {
+        // Same count - preserve existing highlights
+        let mut preserved = 0;
+        
+        // Copy highlight status from old logs if they match
+        for i in 0..new_count.min(old_logs.len()) {
+            if i < old_logs.len() && old_logs[i].is_new {
+                // Same position, same message = preserve highlight
+                if marked_logs[i].message == old_logs[i].message {
+                    marked_logs[i].is_new = true;
+                    preserved += 1;
+                }
+            }
+        }
+        
+        state.last_fetch_count = new_count;
+        if preserved > 0 {
+            state.status = format!("Connected - {} logs highlighted", preserved);
+        } else {
+            state.status = "Connected".to_string();
+        }
+    }
+    
+    // Check if we had new logs
+    let had_new_logs = new_count > old_fetch_count;
+    
+    state.all_logs = marked_logs;
+    state.log_level_counts = LevelCounts::of(&state.all_logs);
+    
+    // Handle scrolling
+    let is_first_load = old_scroll_offset == 0 && old_selected_index.is_none() && state.logs.is_empty();
+    
+    if is_first_load && !state.all_logs.is_empty() && state.resolve_restore_anchors() {
+        // First load after a restart - positioned on the saved anchors
+    } else if is_first_load && !state.all_logs.is_empty() {
+        // First load - scroll to bottom to show latest logs
+        let visible_height = state.get_visible_height(terminal_height);
+        let last_idx = state.all_logs.len().saturating_sub(1);
+        state.log_scroll_offset = last_idx.saturating_sub(visible_height - 1);
+    } else if had_new_logs && old_selected_index.is_none() {
+        // New logs arrived and user isn't selecting - auto-scroll to show them
+        let visible_height = state.get_visible_height(terminal_height);
+        if state.all_logs.len() > visible_height {
+            state.log_scroll_offset = state.all_logs.len() - visible_height;
+        } else {
+            state.log_scroll_offset = 0;
+        }
+    } else if old_selected_index.is_some() {
+        // User has selected something, preserve their position
+        state.log_scroll_offset = old_scroll_offset;
+        state.selected_log_index = old_selected_index;
+        
+        // Validate that the selection is still in bounds
+        if let Some(idx) = state.selected_log_index {
+            if idx >= state.all_logs.len() {
+                state.selected_log_index = Some(state.all_logs.len().saturating_sub(1));
+            }
+        }
+    } else {
+        // No new logs, no selection - keep current position
+        state.log_scroll_offset = old_scroll_offset;
+    }
+    
+    // Update visible logs with the last known terminal height
+    state.update_visible_logs_with_height(terminal_height);
+}
@@ -0,0 +1,58 @@
+//! When a panel row will reach its panel's `forecast` value (e.g. 100 for
+//! disk usage in %), from a least-squares line through the row's trend,
+//! the client-side equivalent of PromQL's `predict_linear`. Shown as a
+//! "full in ~3d" hint next to the row, so capacity runs out on a schedule
+//! instead of by surprise.
+
+use crate::ui::humanize_age;
+
+/// Farther out than this, a straight line says nothing; no hint is shown.
+pub const MAX_HORIZON_SECONDS: f64 = 365.0 * 86400.0;
+
+/// Slope (per second) and intercept of the least-squares line through
+/// `(unix seconds, value)` points; None for fewer than two distinct times.
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|&(t, _)| t).sum::<f64>() / n;
+    let mean_v = points.iter().map(|&(_, v)| v).sum::<f64>() / n;
+    let (covariance, variance) = points.iter().fold((0.0, 0.0), |(covariance, variance), &(t, v)| {
+        (covariance + (t - mean_t) * (v - mean_v), variance + (t - mean_t) * (t - mean_t))
+    });
+    if variance == 0.0 {
+        return None;
+    }
+    let slope = covariance / variance;
+    Some((slope, mean_v - slope * mean_t))
+}
+
+/// Seconds from the last point until the fitted line reaches `target`,
+/// rising or falling towards it from where the trend began; 0 once the row
+/// got there and at least a second while it hasn't, None when it is heading
+/// away, flat, or further off than [`MAX_HORIZON_SECONDS`].
+pub fn time_to_reach(points: &[(f64, f64)], target: f64) -> Option<f64> {
+    let (slope, intercept) = linear_fit(points)?;
+    let (&(first_t, _), &(last_t, last_v)) = (points.first()?, points.last()?);
+    let towards = (target - (slope * first_t + intercept)) * slope;
+    if slope == 0.0 || towards < 0.0 {
+        return None;
+    }
+    let reached = if slope > 0.0 { last_v >= target } else { last_v <= target };
+    if reached {
+        return Some(0.0);
+    }
+    // From where the line ends; a single noisy last sample shouldn't move the hint
+    let seconds = ((target - (slope * last_t + intercept)) / slope).max(1.0);
+    Some(seconds).filter(|&seconds| seconds <= MAX_HORIZON_SECONDS)
+}
+
+/// "full in ~3d", "full in < 1m", or "full now" once reached (0 seconds).
+pub fn describe(seconds: f64) -> String {
+    match seconds {
+        s if s <= 0.0 => "full now".to_string(),
+        s if s < 60.0 => "full in < 1m".to_string(),
+        s => format!("full in ~{}", humanize_age(s as i64)),
+    }
+}
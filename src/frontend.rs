@@ -0,0 +1,148 @@
+//! What the dashboard is drawn through. `AppState` and the renderer
+//! (`ui::draw_ui`) don't know where frames end up; a frontend owns the
+//! screen: taking it over and giving it back, drawing frames, the bell.
+//! [`CrosstermFrontend`] is the terminal; [`MemoryFrontend`] draws into
+//! memory with no terminal at all, for tests and the render benchmark.
+//! Input arrives as crossterm events whatever the frontend, so another one
+//! (a GUI, say) drives the same key handling by producing them.
+
+use crate::ui::{draw_ui, AppState};
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::backend::{Backend, CrosstermBackend, TestBackend};
+use ratatui::layout::Size;
+use ratatui::{CompletedFrame, Terminal};
+use std::io::{self, stdout, IsTerminal, Write};
+
+pub trait Frontend {
+    type Backend: Backend;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend>;
+
+    /// Take over the screen.
+    fn enter(&mut self) -> io::Result<()>;
+
+    /// Give the screen back as it was found.
+    fn leave(&mut self) -> io::Result<()>;
+
+    /// Get the user's attention, e.g. when an alert starts nagging.
+    fn bell(&mut self) -> io::Result<()>;
+
+    /// Draw `state`, returning the frame drawn.
+    fn draw(&mut self, state: &AppState) -> io::Result<CompletedFrame<'_>> {
+        self.terminal().draw(|frame| draw_ui(frame, state))
+    }
+
+    fn size(&mut self) -> io::Result<Size> {
+        self.terminal().size()
+    }
+
+    /// Leave with the cursor showing, for a shell or command to use the screen.
+    fn release(&mut self) -> io::Result<()> {
+        self.leave()?;
+        self.terminal().show_cursor()
+    }
+
+    /// Take the screen back after something else drew on it, and redraw
+    /// everything on the next frame.
+    fn resume(&mut self) -> io::Result<()> {
+        self.enter()?;
+        self.terminal().clear()
+    }
+}
+
+/// Where the prompts and the TUI are drawn. When stdout is piped (e.g. to
+/// capture the exit summary) the interface goes to stderr instead.
+pub fn tui_output() -> Box<dyn Write> {
+    if stdout().is_terminal() {
+        Box::new(stdout())
+    } else {
+        Box::new(io::stderr())
+    }
+}
+
+/// The terminal, in raw mode on the alternate screen while entered.
+pub struct CrosstermFrontend {
+    terminal: Terminal<CrosstermBackend<Box<dyn Write>>>,
+}
+
+impl CrosstermFrontend {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self { terminal: Terminal::new(CrosstermBackend::new(tui_output()))? })
+    }
+}
+
+impl Frontend for CrosstermFrontend {
+    type Backend = CrosstermBackend<Box<dyn Write>>;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend> {
+        &mut self.terminal
+    }
+
+    fn enter(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        execute!(tui_output(), EnterAlternateScreen, EnableMouseCapture)
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        disable_raw_mode()?;
+        execute!(tui_output(), LeaveAlternateScreen, DisableMouseCapture)
+    }
+
+    fn bell(&mut self) -> io::Result<()> {
+        let mut out = tui_output();
+        out.write_all(b"\x07")?;
+        out.flush()
+    }
+}
+
+/// A screen in memory: frames are drawn into a buffer to read back.
+pub struct MemoryFrontend {
+    terminal: Terminal<TestBackend>,
+    pub entered: bool, // Between enter and leave
+    pub bells: usize,  // Rung so far
+}
+
+impl MemoryFrontend {
+    pub fn new(width: u16, height: u16) -> io::Result<Self> {
+        Ok(Self { terminal: Terminal::new(TestBackend::new(width, height))?, entered: false, bells: 0 })
+    }
+
+    /// The screen's rows as text.
+    pub fn lines(&self) -> Vec<String> {
+        let buffer = self.terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal.backend_mut().resize(width, height);
+    }
+}
+
+impl Frontend for MemoryFrontend {
+    type Backend = TestBackend;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend> {
+        &mut self.terminal
+    }
+
+    fn enter(&mut self) -> io::Result<()> {
+        self.entered = true;
+        Ok(())
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        self.entered = false;
+        Ok(())
+    }
+
+    fn bell(&mut self) -> io::Result<()> {
+        self.bells += 1;
+        Ok(())
+    }
+}
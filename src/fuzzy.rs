@@ -0,0 +1,90 @@
+//! Fuzzy matching for completion lists: a candidate matches when the typed
+//! characters appear in it in order, e.g. `hrqtot` finds
+//! `http_requests_total`. Matches that are contiguous or start at word
+//! boundaries rank first.
+
+/// How well a candidate matched, and which of its characters did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub score: i64,
+    pub positions: Vec<usize>, // Char indices into the candidate, ascending
+}
+
+/// Match `pattern` against `candidate`, ignoring case. An empty pattern
+/// matches everything with score 0.
+pub fn score(pattern: &str, candidate: &str) -> Option<Match> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    if pattern.is_empty() {
+        return Some(Match { score: 0, positions: Vec::new() });
+    }
+
+    // A contiguous run beats any scattered match; prefer one at a boundary
+    let runs: Vec<usize> = (0..lower.len().saturating_sub(pattern.len() - 1))
+        .filter(|&start| lower[start..start + pattern.len()] == pattern[..])
+        .collect();
+    let positions: Vec<usize> = match runs.iter().find(|&&start| is_boundary(&chars, start)).or(runs.first()) {
+        Some(&start) => (start..start + pattern.len()).collect(),
+        None => subsequence(&pattern, &lower, &chars, true).or_else(|| subsequence(&pattern, &lower, &chars, false))?,
+    };
+
+    let mut score = 0;
+    for (n, &i) in positions.iter().enumerate() {
+        score += 1;
+        if i == 0 {
+            score += 8;
+        } else if is_boundary(&chars, i) {
+            score += 4;
+        }
+        if n > 0 {
+            let gap = i - positions[n - 1] - 1;
+            score += if gap == 0 { 5 } else { -(gap.min(4) as i64) };
+        }
+    }
+    // Shorter candidates are closer to what was typed
+    score -= (chars.len() / 8) as i64;
+    Some(Match { score, positions })
+}
+
+/// Greedy positions of `pattern` in `lower`. With `boundaries`, a character
+/// that doesn't continue the previous one is taken where a word starts, if
+/// it does anywhere ahead; that can leave too little for the rest.
+fn subsequence(pattern: &[char], lower: &[char], chars: &[char], boundaries: bool) -> Option<Vec<usize>> {
+    let mut positions: Vec<usize> = Vec::with_capacity(pattern.len());
+    let mut from = 0;
+    for c in pattern {
+        let mut found = (from..lower.len()).filter(|&i| lower[i] == *c);
+        let first = found.next()?;
+        let continues = positions.last().is_some_and(|&last| first == last + 1);
+        let next = if boundaries && !continues {
+            std::iter::once(first).chain(found).find(|&i| is_boundary(chars, i)).unwrap_or(first)
+        } else {
+            first
+        };
+        positions.push(next);
+        from = next + 1;
+    }
+    Some(positions)
+}
+
+/// Whether a word starts at `chars[i]`: after `_ : . / -` or a space, or at
+/// a lower-to-upper case change.
+fn is_boundary(chars: &[char], i: usize) -> bool {
+    match i.checked_sub(1).map(|prev| chars[prev]) {
+        None => true,
+        Some(prev) => matches!(prev, '_' | ':' | '.' | '/' | '-' | ' ') || (prev.is_lowercase() && chars[i].is_uppercase()),
+    }
+}
+
+/// The best `limit` matches of `pattern` among `candidates`, best first;
+/// ties go to the shorter, then the alphabetically first candidate.
+pub fn rank<'a>(pattern: &str, candidates: impl IntoIterator<Item = &'a str>, limit: usize) -> Vec<(&'a str, Match)> {
+    let mut matches: Vec<(&str, Match)> = candidates
+        .into_iter()
+        .filter_map(|candidate| Some((candidate, score(pattern, candidate)?)))
+        .collect();
+    matches.sort_by(|(a, x), (b, y)| y.score.cmp(&x.score).then(a.len().cmp(&b.len())).then(a.cmp(b)));
+    matches.truncate(limit);
+    matches
+}
@@ -0,0 +1,47 @@
+//! Go to a log line (`:`): `:1234` selects the 1234th buffered line, as
+//! numbered in the line-number gutter (`#`), and `:12:05:30` or `:12:05` the
+//! line nearest that time of day, on the day of the lines shown.
+
+use crate::loki::LogEntry;
+use crate::text::LineInput;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+
+/// Where `:` jumps to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GotoTarget {
+    Line(usize), // From 1, as in the gutter
+    Time(NaiveTime),
+}
+
+/// Read what was typed after `:`.
+pub fn parse(input: &str) -> Result<GotoTarget, String> {
+    let input = input.trim().trim_start_matches(':');
+    if input.is_empty() {
+        return Err("Type a line number or a time".to_string());
+    }
+    if let Ok(line) = input.parse::<usize>() {
+        return match line {
+            0 => Err("Lines are numbered from 1".to_string()),
+            line => Ok(GotoTarget::Line(line)),
+        };
+    }
+    NaiveTime::parse_from_str(input, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M"))
+        .map(GotoTarget::Time)
+        .map_err(|_| format!("Not a line number or HH:MM[:SS] time: {}", input))
+}
+
+/// `time` (unix seconds) on the local day of `around`, the line the jump
+/// starts from.
+pub fn time_on_day_of(time: NaiveTime, around: &LogEntry) -> Option<f64> {
+    let day = DateTime::from_timestamp_nanos(around.nanos).with_timezone(&Local).date_naive();
+    let at = Local.from_local_datetime(&day.and_time(time)).earliest()?;
+    Some(at.timestamp() as f64)
+}
+
+/// The go-to prompt along the bottom of the logs.
+#[derive(Debug, Clone, Default)]
+pub struct GotoPrompt {
+    pub input: LineInput,
+    pub error: Option<String>, // Why the last Enter didn't jump
+}
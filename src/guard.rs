@@ -0,0 +1,215 @@
+//! Slow query guard: a query that keeps running longer than a threshold,
+//! or that Loki rejects for exceeding one of its limits, is made cheaper
+//! instead of timing out on every refresh. Each reduction halves the time
+//! window of log queries and range selectors (`[1h]` -> `[30m]`) and
+//! doubles the step of range queries.
+//!
+//! Reductions are kept per query text and undone one at a time once the
+//! query has run well within the threshold for a while.
+
+use crate::error::ErrorClass;
+use crate::promql::parse_duration;
+use crate::query_stats::Backend;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Consecutive runs under half the threshold before a reduction is undone.
+const RECOVERY_RUNS: u32 = 10;
+
+/// Windows are never narrowed below this many seconds.
+const MIN_WINDOW_SECONDS: i64 = 60;
+
+/// The `[slow_query_guard]` config section.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GuardConfig {
+    pub enabled: bool,
+    pub slow_seconds: f64,   // A run taking this long (or timing out) counts as slow
+    pub strikes: u32,        // Consecutive slow runs before the query is reduced
+    pub max_reductions: u32, // At most 2^this narrower window or coarser step
+}
+
+impl Default for GuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            slow_seconds: 5.0,
+            strikes: 3,
+            max_reductions: 4,
+        }
+    }
+}
+
+/// How a run of a query ended, as far as the guard cares.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome<'a> {
+    Finished(Duration), // Answered successfully, after this long
+    TimedOut,
+    Rejected(ErrorClass, &'a str), // The server's error, and its message
+}
+
+/// A query currently running reduced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reduction {
+    pub backend: Backend,
+    pub query: String,
+    pub level: u32,     // Window divided, step multiplied by 2^level
+    pub reason: String, // Why it was last reduced
+}
+
+impl Reduction {
+    pub fn factor(&self) -> i64 {
+        factor(self.level)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tracked {
+    level: u32,
+    slow_runs: u32,
+    fast_runs: u32,
+    reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SlowQueryGuard {
+    config: GuardConfig,
+    queries: Mutex<HashMap<(Backend, String), Tracked>>,
+}
+
+impl SlowQueryGuard {
+    pub fn new(config: GuardConfig) -> Self {
+        Self {
+            config,
+            queries: Mutex::default(),
+        }
+    }
+
+    /// Reductions applied to `query`; 0 runs it as written.
+    pub fn level(&self, backend: Backend, query: &str) -> u32 {
+        let queries = self.queries.lock().unwrap();
+        queries.get(&(backend, query.to_string())).map_or(0, |tracked| tracked.level)
+    }
+
+    /// Take a run of `query` into account, reducing it further or easing
+    /// off. Returns the new level when it changed.
+    pub fn observe(&self, backend: Backend, query: &str, outcome: Outcome) -> Option<u32> {
+        if !self.config.enabled {
+            return None;
+        }
+        let slow_after = Duration::from_secs_f64(self.config.slow_seconds.max(0.0));
+        let mut queries = self.queries.lock().unwrap();
+        let tracked = queries.entry((backend, query.to_string())).or_default();
+        let before = tracked.level;
+        let reason = match outcome {
+            Outcome::Rejected(ErrorClass::Limit, message) => Some(format!("hit a limit: {}", message.trim())),
+            Outcome::Rejected(ErrorClass::Timeout, _) => strike(tracked, self.config.strikes, "timed out"),
+            Outcome::Rejected(..) => {
+                tracked.slow_runs = 0;
+                None
+            }
+            Outcome::TimedOut => strike(tracked, self.config.strikes, "timed out"),
+            Outcome::Finished(elapsed) if elapsed >= slow_after => {
+                strike(tracked, self.config.strikes, &format!("slower than {}s", self.config.slow_seconds))
+            }
+            Outcome::Finished(elapsed) => {
+                tracked.slow_runs = 0;
+                if elapsed < slow_after / 2 {
+                    tracked.fast_runs += 1;
+                    if tracked.level > 0 && tracked.fast_runs >= RECOVERY_RUNS {
+                        tracked.level -= 1;
+                        tracked.fast_runs = 0;
+                    }
+                }
+                None
+            }
+        };
+        if let Some(reason) = reason {
+            tracked.level = (tracked.level + 1).min(self.config.max_reductions);
+            tracked.reason = reason;
+        }
+        let after = tracked.level;
+        if after == 0 && tracked.slow_runs == 0 {
+            queries.remove(&(backend, query.to_string()));
+        }
+        (after != before).then_some(after)
+    }
+
+    /// How `query` is currently reduced, if it is.
+    pub fn reduction(&self, backend: Backend, query: &str) -> Option<Reduction> {
+        let queries = self.queries.lock().unwrap();
+        let tracked = queries.get(&(backend, query.to_string())).filter(|tracked| tracked.level > 0)?;
+        Some(Reduction {
+            backend,
+            query: query.to_string(),
+            level: tracked.level,
+            reason: tracked.reason.clone(),
+        })
+    }
+
+    /// Every query running reduced, most reduced first.
+    pub fn reductions(&self) -> Vec<Reduction> {
+        let queries = self.queries.lock().unwrap();
+        let mut reductions: Vec<Reduction> = queries
+            .iter()
+            .filter(|(_, tracked)| tracked.level > 0)
+            .map(|((backend, query), tracked)| Reduction {
+                backend: *backend,
+                query: query.clone(),
+                level: tracked.level,
+                reason: tracked.reason.clone(),
+            })
+            .collect();
+        reductions.sort_by(|a, b| b.level.cmp(&a.level).then_with(|| a.query.cmp(&b.query)));
+        reductions
+    }
+}
+
+/// Count a slow run; the reason to reduce once there were enough in a row.
+fn strike(tracked: &mut Tracked, strikes: u32, reason: &str) -> Option<String> {
+    tracked.fast_runs = 0;
+    tracked.slow_runs += 1;
+    if tracked.slow_runs < strikes.max(1) {
+        return None;
+    }
+    tracked.slow_runs = 0;
+    Some(format!("{} {} times in a row", reason, strikes.max(1)))
+}
+
+pub fn factor(level: u32) -> i64 {
+    1 << level.min(30)
+}
+
+/// `seconds` narrowed `level` times, but not below a minute.
+pub fn narrow_seconds(seconds: i64, level: u32) -> i64 {
+    (seconds / factor(level)).max(MIN_WINDOW_SECONDS.min(seconds))
+}
+
+/// `query` with each range selector (`[1h]`, not subqueries) narrowed
+/// `level` times.
+pub fn narrow_ranges(query: &str, level: u32) -> String {
+    if level == 0 {
+        return query.to_string();
+    }
+    let range = Regex::new(r"\[([0-9smhdw]+)\]").expect("valid regex");
+    range
+        .replace_all(query, |captures: &regex::Captures| match parse_duration(&captures[1]) {
+            Some(seconds) => format!("[{}s]", narrow_seconds(seconds, level)),
+            None => captures[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// A range query step (`60`, `15s` or `1m`) made `level` times coarser.
+pub fn raise_step(step: &str, level: u32) -> String {
+    if level == 0 {
+        return step.to_string();
+    }
+    match step.parse::<f64>().ok().or_else(|| parse_duration(step).map(|seconds| seconds as f64)) {
+        Some(seconds) => format!("{}", seconds * factor(level) as f64),
+        None => step.to_string(),
+    }
+}
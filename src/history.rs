@@ -0,0 +1,199 @@
+//! Client-side record of per-URI request counters, sampled on every refresh.
+//!
+//! The "All" time range charts what rustdash itself has observed rather than
+//! asking Prometheus for a range that may exceed its retention. Snapshots keep
+//! the cumulative counters, so dropping old snapshots to bound memory only
+//! coarsens the history: the difference between any two snapshots is still
+//! the true number of requests (and seconds spent) between them.
+
+use crate::prometheus::UriHistory;
+use crate::retention::{Limit, DEFAULT_HISTORY_SNAPSHOTS};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::mem::size_of;
+use std::{fs, path::PathBuf};
+
+/// Cumulative `http_server_requests_seconds` counters of one URI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct UriTotals {
+    pub count: f64,       // Requests served
+    pub sum_seconds: f64, // Total time spent serving them
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Snapshot {
+    timestamp: f64, // Unix seconds
+    totals: HashMap<String, UriTotals>,
+}
+
+impl Snapshot {
+    /// Approximate heap held by the snapshot.
+    fn bytes(&self) -> usize {
+        // Keys plus a hash table slot each
+        let entry = size_of::<String>() + size_of::<UriTotals>() + 8;
+        size_of::<Snapshot>() + self.totals.keys().map(|uri| uri.capacity() + entry).sum::<usize>()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetricHistory {
+    prometheus_url: String, // Counters from another server don't continue this history
+    snapshots: VecDeque<Snapshot>,
+    #[serde(skip)]
+    limit: Limit, // When the older half is thinned out
+    #[serde(skip)]
+    bytes: usize, // Approximate heap held by the snapshots
+}
+
+impl Default for MetricHistory {
+    fn default() -> Self {
+        Self {
+            prometheus_url: String::new(),
+            snapshots: VecDeque::new(),
+            limit: Limit { entries: DEFAULT_HISTORY_SNAPSHOTS, bytes: None },
+            bytes: 0,
+        }
+    }
+}
+
+/// Location of the history file, next to the UI state:
+/// `$XDG_STATE_HOME/rustdash/history.json`.
+pub fn history_path() -> Option<PathBuf> {
+    Some(crate::persist::state_path()?.with_file_name("history.json"))
+}
+
+impl MetricHistory {
+    pub fn new(prometheus_url: &str) -> Self {
+        Self {
+            prometheus_url: prometheus_url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Keep the history within `limit`, thinning it now if it's already over.
+    pub fn with_limit(mut self, limit: Limit) -> Self {
+        self.limit = limit;
+        self.enforce_limit();
+        self
+    }
+
+    /// Load the saved history for `prometheus_url`, starting a new one if there
+    /// is none, it can't be read, or it was recorded against another server.
+    pub fn load(prometheus_url: &str) -> Self {
+        history_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str::<Self>(&contents).ok())
+            .filter(|history| history.prometheus_url == prometheus_url)
+            .map(|mut history| {
+                history.bytes = history.snapshots.iter().map(Snapshot::bytes).sum();
+                history
+            })
+            .unwrap_or_else(|| Self::new(prometheus_url))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = history_path().ok_or_else(|| anyhow::anyhow!("Cannot determine state directory"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Unix seconds of the oldest snapshot.
+    pub fn since(&self) -> Option<f64> {
+        self.snapshots.front().map(|s| s.timestamp)
+    }
+
+    /// Unix seconds of the newest snapshot.
+    pub fn latest(&self) -> Option<f64> {
+        self.snapshots.back().map(|s| s.timestamp)
+    }
+
+    /// Append the counters read at `timestamp` (unix seconds).
+    pub fn record(&mut self, timestamp: f64, totals: HashMap<String, UriTotals>) {
+        if totals.is_empty() || self.snapshots.back().is_some_and(|last| last.timestamp >= timestamp) {
+            return;
+        }
+        let snapshot = Snapshot { timestamp, totals };
+        self.bytes += snapshot.bytes();
+        self.snapshots.push_back(snapshot);
+        self.enforce_limit();
+    }
+
+    /// Approximate heap held by the recorded snapshots.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    fn enforce_limit(&mut self) {
+        // Three or fewer snapshots can't be thinned; the newest is always kept
+        while self.snapshots.len() > 3 && self.limit.exceeded(self.snapshots.len(), self.bytes) {
+            // Keep every other snapshot of the older half; recent ones stay fine-grained
+            let half = self.snapshots.len() / 2;
+            let mut idx = 0;
+            self.snapshots.retain(|snapshot| {
+                let keep = idx >= half || idx % 2 == 0;
+                if !keep {
+                    self.bytes -= snapshot.bytes();
+                }
+                idx += 1;
+                keep
+            });
+        }
+    }
+
+    /// `(end timestamp, seconds elapsed, requests, seconds spent)` for each
+    /// interval between consecutive snapshots that saw `uri`. Counter resets
+    /// (a restarted service) count from zero, as Prometheus' `increase()` does.
+    fn intervals<'a>(&'a self, uri: &'a str) -> impl Iterator<Item = (f64, f64, f64, f64)> + 'a {
+        let mut previous: Option<(f64, UriTotals)> = None;
+        self.snapshots.iter().filter_map(move |snapshot| {
+            let current = *snapshot.totals.get(uri)?;
+            let interval = previous.map(|(timestamp, before)| {
+                let reset = current.count < before.count;
+                let count = if reset { current.count } else { current.count - before.count };
+                let sum = if reset { current.sum_seconds } else { current.sum_seconds - before.sum_seconds };
+                (snapshot.timestamp, snapshot.timestamp - timestamp, count, sum)
+            });
+            previous = Some((snapshot.timestamp, current));
+            interval
+        })
+    }
+
+    /// Latency and request rate of `uri` between consecutive snapshots, stamped
+    /// at the end of each interval.
+    pub fn uri_history(&self, uri: &str) -> UriHistory {
+        let mut history = UriHistory::default();
+        for (timestamp, elapsed, count, sum) in self.intervals(uri) {
+            history.requests_per_min.push((timestamp, count / elapsed * 60.0));
+            if count > 0.0 {
+                history.latency_ms.push((timestamp, sum / count * 1000.0));
+            }
+        }
+        history
+    }
+
+    /// Average latency (ms) and requests per minute of `uri` over everything
+    /// recorded, or None until two snapshots have seen it.
+    pub fn uri_average(&self, uri: &str) -> Option<(f64, f64)> {
+        let (elapsed, count, sum) = self
+            .intervals(uri)
+            .fold((0.0, 0.0, 0.0), |acc, (_, e, c, s)| (acc.0 + e, acc.1 + c, acc.2 + s));
+        if elapsed <= 0.0 {
+            return None;
+        }
+        let latency_ms = if count > 0.0 { sum / count * 1000.0 } else { 0.0 };
+        Some((latency_ms, count / elapsed * 60.0))
+    }
+}
@@ -0,0 +1,77 @@
+use crate::config::{AuthConfig, HttpConfig};
+use anyhow::{Context, Result};
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Proxy};
+use std::time::Duration;
+
+/// Build an HTTP client with the configured compression and connection pooling.
+/// `default_headers` are sent with every request. An explicit `proxy` is used
+/// for all requests (NO_PROXY still applies); otherwise the proxy environment
+/// variables are honored unless disabled.
+pub fn build_client(
+    http: &HttpConfig,
+    timeout_seconds: u64,
+    default_headers: HeaderMap,
+    proxy: Option<&str>,
+) -> Result<Client> {
+    let mut builder = Client::builder()
+        .default_headers(default_headers)
+        .timeout(Duration::from_secs(timeout_seconds))
+        .gzip(http.compression)
+        .deflate(http.compression)
+        .pool_max_idle_per_host(http.pool_max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(http.pool_idle_timeout_seconds))
+        .tcp_keepalive(http.tcp_keepalive_seconds.map(Duration::from_secs));
+    
+    match proxy {
+        Some(url) => {
+            let proxy = Proxy::all(url)
+                .map_err(|e| anyhow::anyhow!("Invalid proxy '{}': {}", url, e))?
+                .no_proxy(reqwest::NoProxy::from_env());
+            builder = builder.proxy(proxy);
+        }
+        None if !http.use_env_proxy => builder = builder.no_proxy(),
+        None => {}
+    }
+    
+    if http.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    
+    Ok(builder.build()?)
+}
+
+/// Headers for Loki requests: `X-Scope-OrgID` when a tenant is configured.
+pub fn loki_headers(tenant_id: Option<&str>) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    if let Some(tenant_id) = tenant_id {
+        let value = HeaderValue::from_str(tenant_id)
+            .map_err(|_| anyhow::anyhow!("Invalid Loki tenant id '{}'", tenant_id))?;
+        headers.insert("X-Scope-OrgID", value);
+    }
+    Ok(headers)
+}
+
+/// `Authorization` header for an endpoint's credentials, resolving their
+/// secrets: a bearer token if set, otherwise basic auth. Empty when none
+/// are configured.
+pub fn auth_headers(auth: &AuthConfig) -> Result<HeaderMap> {
+    let value = match (&auth.bearer_token, &auth.username) {
+        (Some(token), _) => format!("Bearer {}", token.resolve().context("bearer_token")?),
+        (None, Some(username)) => {
+            let password = match &auth.password {
+                Some(password) => password.resolve().context("password")?,
+                None => String::new(),
+            };
+            let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+            format!("Basic {}", credentials)
+        }
+        (None, None) => return Ok(HeaderMap::new()),
+    };
+    let mut value = HeaderValue::from_str(&value).context("Credentials are not a valid header value")?;
+    value.set_sensitive(true); // Kept out of debug output
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, value);
+    Ok(headers)
+}
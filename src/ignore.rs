@@ -0,0 +1,99 @@
+//! Ignore lists for noisy endpoints and log lines (health checks, scrapes of
+//! `/metrics`, access logs), applied before rows reach the tables.
+//!
+//! Patterns come from the `[ignore]` config section or are added at runtime
+//! with `x`; `H` momentarily shows what they hide.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct IgnoreConfig {
+    pub uris: Vec<String>, // Regexes matched against the whole URI (after templating)
+    pub logs: Vec<String>, // Regexes searched for in log lines
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreList {
+    uris: Vec<Regex>,
+    logs: Vec<Regex>,
+    added: IgnoreConfig, // Patterns added at runtime, persisted with the UI state
+    pub show_hidden: bool, // Temporarily bypass the list
+}
+
+fn compile(pattern: &str, anchored: bool) -> Result<Regex> {
+    let source = if anchored { format!("^(?:{})$", pattern) } else { pattern.to_string() };
+    Regex::new(&source).with_context(|| format!("Invalid ignore pattern '{}'", pattern))
+}
+
+impl IgnoreList {
+    pub fn new(config: &IgnoreConfig) -> Result<Self> {
+        let mut list = Self::default();
+        for pattern in &config.uris {
+            list.uris.push(compile(pattern, true)?);
+        }
+        for pattern in &config.logs {
+            list.logs.push(compile(pattern, false)?);
+        }
+        Ok(list)
+    }
+
+    /// Patterns added at runtime, to be restored with [`IgnoreList::extend`].
+    pub fn added(&self) -> &IgnoreConfig {
+        &self.added
+    }
+
+    /// Add previously saved runtime patterns; invalid ones are skipped.
+    pub fn extend(&mut self, added: IgnoreConfig) {
+        for pattern in added.uris {
+            self.add_uri_pattern(pattern);
+        }
+        for pattern in added.logs {
+            self.add_log_pattern(pattern);
+        }
+    }
+
+    fn add_uri_pattern(&mut self, pattern: String) {
+        if let (Ok(regex), false) = (compile(&pattern, true), self.added.uris.contains(&pattern)) {
+            self.uris.push(regex);
+            self.added.uris.push(pattern);
+        }
+    }
+
+    fn add_log_pattern(&mut self, pattern: String) {
+        if let (Ok(regex), false) = (compile(&pattern, false), self.added.logs.contains(&pattern)) {
+            self.logs.push(regex);
+            self.added.logs.push(pattern);
+        }
+    }
+
+    /// Hide exactly this URI.
+    pub fn ignore_uri(&mut self, uri: &str) {
+        self.add_uri_pattern(regex::escape(uri));
+    }
+
+    /// Hide lines shaped like `message` (see [`similar_pattern`]).
+    /// Returns the pattern added.
+    pub fn ignore_log(&mut self, message: &str) -> String {
+        let pattern = similar_pattern(message);
+        self.add_log_pattern(pattern.clone());
+        pattern
+    }
+
+    pub fn hides_uri(&self, uri: &str) -> bool {
+        !self.show_hidden && self.uris.iter().any(|regex| regex.is_match(uri))
+    }
+
+    pub fn hides_log(&self, message: &str) -> bool {
+        !self.show_hidden && self.logs.iter().any(|regex| regex.is_match(message))
+    }
+}
+
+/// Regex for lines shaped like `message`: the same text with any numbers,
+/// so `GET /health 200 in 3ms` also matches `GET /health 200 in 12ms`.
+pub fn similar_pattern(message: &str) -> String {
+    let digits = Regex::new(r"\d+").expect("valid regex");
+    digits.replace_all(&regex::escape(message.trim()), r"\d+").into_owned()
+}
@@ -0,0 +1,59 @@
+//! Value inspection (`v`): a detail box for the selected metrics row or
+//! drill-in chart point with its exact values, when they were evaluated and
+//! the queries behind them. It also lists the series the row adds up, with
+//! their full label sets, since `sum by (uri)` hides which instances
+//! contributed.
+
+use crate::prometheus::SeriesValue;
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+
+/// How often the inspected row is checked for a new lookup.
+pub const POLL_MILLIS: u64 = 300;
+
+/// Inspection while it is on.
+#[derive(Debug, Clone, Default)]
+pub struct Inspection {
+    pub uri: String,                      // What `series` belong to
+    pub series: Option<Vec<SeriesValue>>, // None until the lookup returns
+    pub error: Option<String>,
+    pub looked_up: Option<DateTime<Local>>, // When the last lookup started
+}
+
+impl Inspection {
+    /// Start a lookup for `uri` if another row was selected or the metrics
+    /// refreshed (at `refreshed`) since the last one. Returns whether to run it.
+    pub fn start(&mut self, uri: &str, refreshed: Option<DateTime<Local>>, now: DateTime<Local>) -> bool {
+        let current = self.looked_up.is_some_and(|at| refreshed.is_none_or(|refreshed| refreshed <= at));
+        if self.uri == uri && current {
+            return false;
+        }
+        if self.uri != uri {
+            self.uri = uri.to_string();
+            self.series = None;
+            self.error = None;
+        }
+        self.looked_up = Some(now);
+        true
+    }
+
+    /// Keep a lookup's result, unless another row was selected meanwhile.
+    pub fn finish(&mut self, uri: &str, result: Result<Vec<SeriesValue>, String>) {
+        if self.uri != uri {
+            return;
+        }
+        match result {
+            Ok(series) => {
+                self.series = Some(series);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+/// A label set as PromQL writes it: `{instance="10.0.0.1:8080", job="api"}`.
+pub fn format_labels(labels: &BTreeMap<String, String>) -> String {
+    let pairs: Vec<String> = labels.iter().map(|(name, value)| format!("{}={:?}", name, value)).collect();
+    format!("{{{}}}", pairs.join(", "))
+}
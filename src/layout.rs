@@ -0,0 +1,139 @@
+//! Grid layout of the dashboard's main area (`[layout]`): rows and columns
+//! sized by weight, cells spanning several of them with minimum sizes, each
+//! showing the metrics table, the logs or one of the panels. Side-by-side
+//! metrics and logs or a 2x2 grid of stats are a few lines of config;
+//! without cells the metrics table sits above the logs as always.
+
+use ratatui::layout::Rect;
+use serde::{Deserialize, Serialize};
+
+/// The `[layout]` section.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub rows: Vec<u16>,    // Relative heights; rows without one weigh 1
+    pub columns: Vec<u16>, // Relative widths; columns without one weigh 1
+    pub cells: Vec<Cell>,  // Empty keeps the metrics-over-logs stack
+}
+
+/// One `[[layout.cells]]` entry.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Cell {
+    pub show: CellContent,
+    #[serde(default)]
+    pub row: usize, // Top row, from 0
+    #[serde(default)]
+    pub column: usize, // Left column, from 0
+    #[serde(default = "default_span")]
+    pub row_span: usize,
+    #[serde(default = "default_span")]
+    pub column_span: usize,
+    #[serde(default)]
+    pub min_height: u16, // Its rows together get at least this many lines
+    #[serde(default)]
+    pub min_width: u16,
+}
+
+fn default_span() -> usize {
+    1
+}
+
+/// What a cell shows: `show = "metrics"`, `"logs"` or `{ panel = 0 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellContent {
+    Metrics,
+    Logs,
+    Panel(usize), // Position on the panels page
+}
+
+impl LayoutConfig {
+    pub fn is_grid(&self) -> bool {
+        !self.cells.is_empty()
+    }
+
+    /// Where each cell goes in `area`, in config order.
+    pub fn areas(&self, area: Rect) -> Vec<(CellContent, Rect)> {
+        let rows = tracks(
+            area.height,
+            &self.rows,
+            self.cells.iter().map(|cell| (cell.row, cell.row_span.max(1), cell.min_height)),
+        );
+        let columns = tracks(
+            area.width,
+            &self.columns,
+            self.cells.iter().map(|cell| (cell.column, cell.column_span.max(1), cell.min_width)),
+        );
+        self.cells
+            .iter()
+            .map(|cell| {
+                let (y, height) = span(&rows, cell.row, cell.row_span.max(1));
+                let (x, width) = span(&columns, cell.column, cell.column_span.max(1));
+                (cell.show, Rect::new(area.x + x, area.y + y, width, height))
+            })
+            .collect()
+    }
+
+    /// Where the first cell showing `content` goes in `area`.
+    pub fn area_of(&self, content: CellContent, area: Rect) -> Option<Rect> {
+        self.areas(area).into_iter().find(|(shown, _)| *shown == content).map(|(_, rect)| rect)
+    }
+}
+
+/// Sizes of the tracks (rows or columns) of `total` lines or columns. Cells
+/// are (first track, span, minimum size); a spanning cell's minimum is
+/// shared by its tracks.
+fn tracks(total: u16, weights: &[u16], cells: impl Iterator<Item = (usize, usize, u16)>) -> Vec<u16> {
+    let cells: Vec<_> = cells.collect();
+    let count = cells.iter().map(|(start, span, _)| start + span).chain([weights.len()]).max().unwrap_or(0);
+    let mut mins = vec![0; count];
+    for (start, span, min) in cells {
+        let share = min.div_ceil(span as u16);
+        for track in &mut mins[start..start + span] {
+            *track = (*track).max(share);
+        }
+    }
+    let weights: Vec<u16> = (0..count).map(|track| weights.get(track).copied().unwrap_or(1)).collect();
+    split(total, &weights, &mins)
+}
+
+/// Split `total` by `weights`, no part below its minimum: a part whose
+/// share falls short keeps its minimum and the others share what is left.
+/// When even the minimums don't fit, by weight alone.
+pub fn split(total: u16, weights: &[u16], mins: &[u16]) -> Vec<u16> {
+    let total = u32::from(total);
+    let fits = mins.iter().map(|&min| u32::from(min)).sum::<u32>() <= total;
+    let mut floored = vec![false; weights.len()]; // Held at their minimum
+    loop {
+        let floor_total: u32 = (0..weights.len()).filter(|&part| floored[part]).map(|part| u32::from(mins[part])).sum();
+        let rest = total - floor_total;
+        let weight_total: u32 = (0..weights.len()).filter(|&part| !floored[part]).map(|part| u32::from(weights[part])).sum();
+        // Cumulative shares, so the parts add up to exactly `rest`
+        let (mut weight_so_far, mut handed_out) = (0, 0);
+        let mut sizes = Vec::with_capacity(weights.len());
+        for (part, &weight) in weights.iter().enumerate() {
+            if floored[part] {
+                sizes.push(mins[part]);
+                continue;
+            }
+            weight_so_far += u32::from(weight);
+            let upto = (rest * weight_so_far).checked_div(weight_total).unwrap_or(0);
+            sizes.push((upto - handed_out) as u16);
+            handed_out = upto;
+        }
+        let short: Vec<usize> = (0..weights.len()).filter(|&part| fits && !floored[part] && sizes[part] < mins[part]).collect();
+        if short.is_empty() {
+            return sizes;
+        }
+        for part in short {
+            floored[part] = true;
+        }
+    }
+}
+
+/// Offset and size of `span` tracks from `start`.
+fn span(tracks: &[u16], start: usize, span: usize) -> (u16, u16) {
+    let offset = tracks[..start.min(tracks.len())].iter().sum();
+    let size = tracks.iter().skip(start).take(span).sum();
+    (offset, size)
+}
@@ -0,0 +1,66 @@
+pub mod admin;
+pub mod audit;
+pub mod backoff;
+pub mod bench;
+pub mod breaker;
+pub mod bundle;
+pub mod cache;
+pub mod changes;
+pub mod clock;
+pub mod config;
+pub mod diff;
+pub mod downsample;
+pub mod error;
+pub mod exchanges;
+pub mod fanout;
+pub mod fetch;
+pub mod forecast;
+pub mod frontend;
+pub mod fuzzy;
+pub mod goto;
+pub mod guard;
+pub mod history;
+pub mod http;
+pub mod ignore;
+pub mod inspect;
+pub mod layout;
+pub mod limiter;
+pub mod logql;
+pub mod log_actions;
+pub mod log_files;
+pub mod log_query;
+pub mod log_overlay;
+pub mod loki;
+pub mod metrics_export;
+pub mod mock;
+pub mod modal;
+pub mod notes;
+pub mod palette;
+pub mod panels;
+pub mod persist;
+pub mod ping;
+pub mod prometheus;
+pub mod retention;
+pub mod saved_views;
+pub mod secrets;
+pub mod share;
+pub mod promql;
+pub mod query_editor;
+pub mod query_stats;
+pub mod report;
+pub mod silence;
+pub mod slo;
+pub mod status_pages;
+pub mod summary;
+pub mod synthetic;
+pub mod table_text;
+pub mod system;
+pub mod tcp;
+pub mod templates;
+pub mod text;
+pub mod triage;
+pub mod tunnel;
+pub mod ui;
+pub mod undo;
+pub mod uri_errors;
+pub mod variables;
@@ -0,0 +1,50 @@
+//! Per-backend budget for outgoing queries: a cap on queries in flight and an
+//! optional rate limit. Excess queries wait their turn instead of piling onto
+//! a shared server when many panels or a short refresh interval are enabled.
+
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// Lowest accepted `max_queries_per_second`: one query every 100 seconds.
+pub const MIN_QUERIES_PER_SECOND: f64 = 0.01;
+
+#[derive(Debug)]
+pub struct QueryLimiter {
+    permits: Semaphore,
+    interval: Option<Duration>, // Minimum spacing between query starts
+    next_start: Mutex<Instant>, // Earliest start for the next query
+}
+
+impl QueryLimiter {
+    /// At most `max_concurrent` queries in flight, started no faster than
+    /// `max_per_second` when set.
+    pub fn new(max_concurrent: usize, max_per_second: Option<f64>) -> Self {
+        Self {
+            permits: Semaphore::new(max_concurrent.clamp(1, Semaphore::MAX_PERMITS)),
+            interval: max_per_second
+                .filter(|rate| *rate > 0.0)
+                .and_then(|rate| Duration::try_from_secs_f64(1.0 / rate).ok()),
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(Semaphore::MAX_PERMITS, None)
+    }
+
+    /// Wait for a slot; the query may run while the permit is held.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self.permits.acquire().await.expect("limiter semaphore is never closed");
+        if let Some(interval) = self.interval {
+            let start = {
+                let mut next_start = self.next_start.lock().await;
+                let start = (*next_start).max(Instant::now());
+                *next_start = start + interval;
+                start
+            };
+            tokio::time::sleep_until(start).await;
+        }
+        permit
+    }
+}
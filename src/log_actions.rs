@@ -0,0 +1,397 @@
+//! Actions on a single log line, offered from the context menu (`a`) on the
+//! selected line: copy variants, narrowing the logs panel to the line's
+//! stream or to lines like it, hiding similar lines, bookmarks, and opening
+//! the line's trace or a configured URL template in the browser, and
+//! piping the line or the whole buffer into a pager, editor or other command.
+
+use crate::ignore;
+use crate::loki::LogEntry;
+use crate::promql::quote;
+use crate::triage::TriageState;
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The `[log_actions]` config section.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct LogActionsConfig {
+    pub trace_url: Option<String>, // e.g. "http://tempo:3200/trace/{trace_id}"; offers "Open trace" on lines with a trace id
+    pub links: Vec<LogLink>,       // Extra "Open ..." entries
+    pub commands: Vec<LogCommand>, // Commands the line or the buffer can be piped into; $PAGER and $EDITOR by default
+}
+
+impl Default for LogActionsConfig {
+    fn default() -> Self {
+        Self {
+            trace_url: None,
+            links: Vec::new(),
+            commands: vec![
+                LogCommand {
+                    name: "pager".to_string(),
+                    command: "${PAGER:-less}".to_string(),
+                },
+                LogCommand {
+                    name: "editor".to_string(),
+                    command: "${EDITOR:-vi} {file}".to_string(),
+                },
+            ],
+        }
+    }
+}
+
+/// One `[[log_actions.commands]]` entry, run through the shell with the
+/// terminal handed over until it exits. The raw log lines go to its stdin,
+/// or, if the command mentions `{file}`, into a temporary file whose path
+/// replaces it (for editors).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogCommand {
+    pub name: String,
+    pub command: String,
+}
+
+/// One `[[log_actions.links]]` entry. The URL may use the placeholders
+/// `{message}`, `{level}`, `{stream}`, `{timestamp}` (RFC 3339), `{nanos}`
+/// and `{trace_id}`, which are filled in percent-encoded.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogLink {
+    pub name: String,
+    pub url: String,
+}
+
+/// What the logs panel is narrowed to, set from the context menu or the
+/// query bar.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LogFilter {
+    pub stream: Option<String>,  // Exact stream selector to query instead of the auto-detected one
+    pub pattern: Option<String>, // Line filter regex (`|~`)
+    pub query: Option<String>,   // Whole LogQL query from the query bar; stream and pattern are then unset
+}
+
+impl LogFilter {
+    /// Show what `query` returns.
+    pub fn query(query: &str) -> Self {
+        Self { query: Some(query.to_string()), ..Self::default() }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.stream.is_some() || self.pattern.is_some() || self.query.is_some()
+    }
+
+    /// The line filter compiled, to check local lines with. None without
+    /// one, or when it doesn't compile.
+    pub fn compile_pattern(&self) -> Option<Regex> {
+        Regex::new(self.pattern.as_ref()?).ok()
+    }
+
+    /// Whether a line from a local file passes, checked here as Loki never
+    /// sees it; `pattern` is the line filter compiled. A LogQL query from the
+    /// query bar can't be, and hides them all, as does a line filter that
+    /// doesn't compile.
+    pub fn matches(&self, log: &LogEntry, pattern: Option<&Regex>) -> bool {
+        self.query.is_none()
+            && self.stream.as_ref().is_none_or(|stream| *stream == log.stream)
+            && (self.pattern.is_none() || pattern.is_some_and(|regex| regex.is_match(&log.message)))
+    }
+
+    /// The filter as a LogQL query, to start the query bar from. None while
+    /// it has no stream of its own to write the query on.
+    pub fn logql(&self) -> Option<String> {
+        match (&self.query, &self.stream, &self.pattern) {
+            (Some(query), _, _) => Some(query.clone()),
+            (None, Some(stream), Some(pattern)) => Some(format!("{} |~ {}", stream, quote(pattern))),
+            (None, Some(stream), None) => Some(stream.clone()),
+            (None, None, _) => None,
+        }
+    }
+
+    /// Short description for the logs panel title.
+    pub fn describe(&self) -> String {
+        if let Some(query) = &self.query {
+            return query.clone();
+        }
+        match (&self.stream, &self.pattern) {
+            (Some(stream), Some(pattern)) => format!("{} |~ {}", stream, pattern),
+            (Some(stream), None) => stream.clone(),
+            (None, Some(pattern)) => format!("|~ {}", pattern),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogAction {
+    CopyLine,        // "[LEVEL] message", like `c`
+    CopyMessage,     // The raw line only
+    CopyWithContext, // Timestamp, stream and raw line
+    CopyStream,      // The stream selector
+    FilterStream(String),
+    ClearStreamFilter,
+    HideSimilar,           // Add the line's shape to the ignore list, like `x`
+    SearchSimilar(String), // Query Loki for lines matching this pattern
+    ClearSearch,
+    Bookmark,
+    RemoveBookmark,
+    OpenUrl { label: String, url: String },
+    // Hand the terminal to a command fed the line, or every buffered line
+    RunCommand { name: String, command: String, whole_buffer: bool },
+    Triage(Option<TriageState>), // Of lines like this one; None clears it
+}
+
+impl LogAction {
+    /// Actions offered for `log`, given the current filter and whether the
+    /// line is bookmarked.
+    pub fn available(log: &LogEntry, filter: &LogFilter, bookmarked: bool, config: &LogActionsConfig) -> Vec<LogAction> {
+        let mut actions = vec![
+            LogAction::CopyLine,
+            LogAction::CopyMessage,
+            LogAction::CopyWithContext,
+        ];
+        let has_stream = !log.stream.is_empty() && log.stream != "{}";
+        if has_stream {
+            actions.push(LogAction::CopyStream);
+        }
+        // Both query Loki, which would read a local file's selector as its own
+        if filter.stream.is_some() {
+            actions.push(LogAction::ClearStreamFilter);
+        } else if has_stream && !log.local {
+            actions.push(LogAction::FilterStream(log.stream.clone()));
+        }
+        actions.push(LogAction::HideSimilar);
+        if filter.pattern.is_some() {
+            actions.push(LogAction::ClearSearch);
+        } else if !log.local {
+            actions.push(LogAction::SearchSimilar(ignore::similar_pattern(&log.message)));
+        }
+        actions.push(if bookmarked { LogAction::RemoveBookmark } else { LogAction::Bookmark });
+        if let (Some(template), Some(id)) = (&config.trace_url, trace_id(&log.message)) {
+            actions.push(LogAction::OpenUrl {
+                label: format!("Open trace {}", id),
+                url: expand_template(template, log),
+            });
+        }
+        for link in &config.links {
+            actions.push(LogAction::OpenUrl {
+                label: format!("Open {}", link.name),
+                url: expand_template(&link.url, log),
+            });
+        }
+        for whole_buffer in [false, true] {
+            for command in &config.commands {
+                actions.push(LogAction::RunCommand {
+                    name: command.name.clone(),
+                    command: command.command.clone(),
+                    whole_buffer,
+                });
+            }
+        }
+        actions
+    }
+
+    /// Triage states to move lines like one in `current` to.
+    pub fn triage(current: Option<TriageState>) -> Vec<LogAction> {
+        let mut actions: Vec<LogAction> =
+            TriageState::ALL.into_iter().filter(|state| Some(*state) != current).map(|state| LogAction::Triage(Some(state))).collect();
+        if current.is_some() {
+            actions.push(LogAction::Triage(None));
+        }
+        actions
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            LogAction::CopyLine => "Copy line".to_string(),
+            LogAction::CopyMessage => "Copy message only".to_string(),
+            LogAction::CopyWithContext => "Copy with timestamp and stream".to_string(),
+            LogAction::CopyStream => "Copy stream selector".to_string(),
+            LogAction::FilterStream(stream) => format!("Show only {}", stream),
+            LogAction::ClearStreamFilter => "Show all streams".to_string(),
+            LogAction::HideSimilar => "Hide lines like this".to_string(),
+            LogAction::SearchSimilar(_) => "Search logs for lines like this".to_string(),
+            LogAction::ClearSearch => "Clear search".to_string(),
+            LogAction::Bookmark => "Bookmark line".to_string(),
+            LogAction::RemoveBookmark => "Remove bookmark".to_string(),
+            LogAction::OpenUrl { label, .. } => label.clone(),
+            LogAction::RunCommand { name, whole_buffer: false, .. } => format!("Pipe line to {}", name),
+            LogAction::RunCommand { name, whole_buffer: true, .. } => format!("Pipe buffer to {}", name),
+            LogAction::Triage(Some(TriageState::Acknowledged)) => "Acknowledge lines like this".to_string(),
+            LogAction::Triage(Some(TriageState::Investigating)) => "Mark lines like this as investigating".to_string(),
+            LogAction::Triage(Some(TriageState::Ignored)) => "Ignore lines like this for the session".to_string(),
+            LogAction::Triage(None) => "Clear the triage of lines like this".to_string(),
+        }
+    }
+
+    /// Clipboard text for the copy actions.
+    pub fn copy_text(&self, log: &LogEntry) -> Option<String> {
+        match self {
+            LogAction::CopyLine => Some(format!("[{}] {}", log.level, log.message)),
+            LogAction::CopyMessage => Some(log.message.clone()),
+            LogAction::CopyWithContext => Some(format!("{} {} {}", rfc3339(log.nanos), log.stream, log.message)),
+            LogAction::CopyStream => Some(log.stream.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// State of the log line context menu while it is open.
+#[derive(Debug, Clone)]
+pub struct LogActionMenu {
+    pub log_index: usize, // Index into `all_logs` of the line the menu is for
+    pub actions: Vec<LogAction>,
+    pub selected: usize,
+}
+
+impl LogActionMenu {
+    pub fn new(log_index: usize, actions: Vec<LogAction>) -> Self {
+        Self {
+            log_index,
+            actions,
+            selected: 0,
+        }
+    }
+
+    pub fn selected_action(&self) -> Option<&LogAction> {
+        self.actions.get(self.selected)
+    }
+}
+
+/// Trace id in a log line: a `trace_id=`/`traceId:`-style field or a W3C
+/// `traceparent`, 16 to 32 hex digits.
+pub fn trace_id(message: &str) -> Option<String> {
+    let field = Regex::new(r#"(?i)trace[_-]?id["']?\s*[:=]\s*["']?([0-9a-f]{16,32})\b"#).expect("valid regex");
+    let traceparent = Regex::new(r"\b[0-9a-f]{2}-([0-9a-f]{32})-[0-9a-f]{16}-[0-9a-f]{2}\b").expect("valid regex");
+    field
+        .captures(message)
+        .or_else(|| traceparent.captures(message))
+        .map(|captures| captures[1].to_lowercase())
+}
+
+/// Fill a URL template's placeholders from `log`, percent-encoded.
+pub fn expand_template(template: &str, log: &LogEntry) -> String {
+    template
+        .replace("{message}", &percent_encode(&log.message))
+        .replace("{level}", &percent_encode(&log.level))
+        .replace("{stream}", &percent_encode(&log.stream))
+        .replace("{timestamp}", &percent_encode(&rfc3339(log.nanos)))
+        .replace("{nanos}", &log.nanos.to_string())
+        .replace("{trace_id}", &trace_id(&log.message).unwrap_or_default())
+}
+
+fn rfc3339(nanos: i64) -> String {
+    DateTime::from_timestamp_nanos(nanos).to_rfc3339()
+}
+
+/// Encode everything but RFC 3986 unreserved characters.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Open `url` with the desktop's default handler, without waiting for it.
+pub fn open_url(url: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Could not start a browser")?;
+    // Reap it in the background so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Run `command` through the shell with `input` on its stdin (or in the
+/// `{file}` temporary file), inheriting the terminal, and wait for it.
+/// `stdout` is where its output goes, i.e. wherever the TUI is drawn.
+pub fn run_command(command: &str, input: &str, stdout: Stdio) -> Result<()> {
+    let file = command.contains("{file}").then(|| write_temp_file(input)).transpose()?;
+    let command_line = match &file {
+        Some(path) => command.replace("{file}", &shell_quote(&path.to_string_lossy())),
+        None => command.to_string(),
+    };
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let mut child = shell
+        .arg(&command_line)
+        .stdin(if file.is_some() { Stdio::inherit() } else { Stdio::piped() })
+        .stdout(stdout)
+        .spawn()
+        .with_context(|| format!("Could not run {}", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A pager quitting early closes the pipe; that's not an error
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let status = child.wait()?;
+    if let Some(path) = &file {
+        let _ = fs::remove_file(path);
+    }
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", command, status);
+    }
+    Ok(())
+}
+
+/// Write `input` to a new file in the temporary directory, readable only by
+/// us. The file must not exist yet, so a planted file or symlink under a
+/// guessed name is never written through.
+fn write_temp_file(input: &str) -> Result<PathBuf> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.subsec_nanos());
+    for attempt in 0..16 {
+        let path = std::env::temp_dir().join(format!("rustdash-{}-{}.log", std::process::id(), seed.wrapping_add(attempt)));
+        match options.open(&path) {
+            Ok(mut file) => {
+                if let Err(e) = file.write_all(input.as_bytes()) {
+                    // Don't leave a partial copy of the lines behind
+                    drop(file);
+                    let _ = fs::remove_file(&path);
+                    return Err(e).with_context(|| format!("Could not write {}", path.display()));
+                }
+                return Ok(path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("Could not create {}", path.display())),
+        }
+    }
+    anyhow::bail!("Could not create a temporary file in {}", std::env::temp_dir().display())
+}
+
+fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", value)
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
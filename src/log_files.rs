@@ -0,0 +1,270 @@
+//! Local log files as a log source, for apps that only write to disk. The
+//! files (or globs) of the `[log_files]` config section are followed like
+//! `tail -F`, through rotation and truncation, and their lines merged into
+//! the logs panel under a `{filename="..."}` stream. Their lines are marked
+//! local, so actions that query or delete in Loki aren't offered on them:
+//! promtail may ship the same file under the very same selector.
+
+use crate::logql::StreamSelector;
+use crate::loki::{extract_log_level, LogEntry};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// How far from its end a file is first read, for context.
+pub const BACKLOG_BYTES: u64 = 64 * 1024;
+/// Most of a file read in one poll; the rest waits for the next one.
+pub const MAX_READ_BYTES: u64 = 1024 * 1024;
+/// Longest line kept; the rest of a longer one is dropped.
+pub const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// The `[log_files]` config section.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LogFilesConfig {
+    pub paths: Vec<String>, // Files to follow; `*` and `?` in the file name match several
+}
+
+/// Device and inode, which tell a rotated file from its replacement. Not
+/// available off Unix, where only truncation is noticed.
+type FileId = Option<(u64, u64)>;
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> FileId {
+    None
+}
+
+/// One followed file.
+#[derive(Debug)]
+struct Tail {
+    file: File,
+    id: FileId,
+    offset: u64,          // Bytes read so far
+    partial: Vec<u8>,     // An unfinished last line, completed by a later read
+    skip_first: bool,     // Started mid-file or cut a long line: drop up to the next newline
+    stream: String,       // `{filename="..."}`
+    last_nanos: i64,      // Keeps the file's lines in order when timestamps tie
+}
+
+impl Tail {
+    fn open(path: &Path, start: u64) -> std::io::Result<Self> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let offset = start.min(metadata.len());
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(Self {
+            file,
+            id: file_id(&metadata),
+            offset,
+            partial: Vec::new(),
+            skip_first: offset > 0,
+            stream: StreamSelector::new().eq("filename", &path.display().to_string()).to_string(),
+            last_nanos: 0,
+        })
+    }
+
+    /// Read up to MAX_READ_BYTES more of the file, adding its complete lines
+    /// to `out`.
+    fn read(&mut self, out: &mut Vec<LogEntry>, now: DateTime<Local>) {
+        // Shorter than what was read: truncated in place, start over
+        if self.file.metadata().is_ok_and(|metadata| metadata.len() < self.offset)
+            && self.file.seek(SeekFrom::Start(0)).is_ok()
+        {
+            self.offset = 0;
+            self.partial.clear();
+            self.skip_first = false;
+        }
+        if let Ok(read) = (&mut self.file).take(MAX_READ_BYTES).read_to_end(&mut self.partial) {
+            self.offset += read as u64;
+        }
+        if self.skip_first {
+            match self.partial.iter().position(|&b| b == b'\n') {
+                Some(end) => {
+                    self.partial.drain(..=end);
+                    self.skip_first = false;
+                }
+                None => {
+                    self.partial.clear();
+                    return;
+                }
+            }
+        }
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            self.push_line(&line[..line.len().min(MAX_LINE_BYTES)], out, now);
+        }
+        // A line with no end in sight: keep its start, drop the rest as it comes
+        if self.partial.len() > MAX_LINE_BYTES {
+            let line: Vec<u8> = self.partial.drain(..).take(MAX_LINE_BYTES).collect();
+            self.push_line(&line, out, now);
+            self.skip_first = true;
+        }
+    }
+
+    fn push_line(&mut self, line: &[u8], out: &mut Vec<LogEntry>, now: DateTime<Local>) {
+        let message = String::from_utf8_lossy(line).trim_end_matches(['\n', '\r']).to_string();
+        if message.trim().is_empty() {
+            return;
+        }
+        let nanos = leading_timestamp(&message)
+            .unwrap_or_else(|| now.timestamp_nanos_opt().unwrap_or_default())
+            .max(self.last_nanos + 1);
+        self.last_nanos = nanos;
+        out.push(LogEntry {
+            timestamp: DateTime::from_timestamp_nanos(nanos).format("%Y-%m-%d %H:%M:%S").to_string(),
+            nanos,
+            level: extract_log_level(&message),
+            message,
+            is_new: false,
+            stream: self.stream.clone(),
+            source: None,
+            local: true,
+        });
+    }
+}
+
+/// The followed files and their newest lines.
+#[derive(Debug, Default)]
+pub struct LogFiles {
+    patterns: Vec<String>,
+    tails: HashMap<PathBuf, Tail>,
+    rotated: HashMap<(u64, u64), u64>, // Offsets reached in files rotated away, should a glob match them
+    lines: Vec<LogEntry>,              // Oldest first
+}
+
+impl LogFiles {
+    /// `None` when no paths are configured.
+    pub fn new(config: &LogFilesConfig) -> Option<Self> {
+        (!config.paths.is_empty()).then(|| Self { patterns: config.paths.clone(), ..Self::default() })
+    }
+
+    /// Read what was written since the last poll, and return the newest
+    /// `limit` lines of all files, oldest first.
+    pub fn poll(&mut self, limit: usize) -> Vec<LogEntry> {
+        let now = Local::now();
+        let paths = self.expand();
+        for path in &paths {
+            if self.tails.contains_key(path) {
+                continue;
+            }
+            let Ok(id) = fs::metadata(path).map(|metadata| file_id(&metadata)) else { continue };
+            // Already followed under another name, e.g. `app.log*` after a rename
+            if id.is_some() && self.tails.values().any(|tail| tail.id == id) {
+                continue;
+            }
+            let start = match id.and_then(|id| self.rotated.get(&id)) {
+                Some(&offset) => offset,
+                None => fs::metadata(path).map_or(0, |metadata| metadata.len().saturating_sub(BACKLOG_BYTES)),
+            };
+            if let Ok(mut tail) = Tail::open(path, start) {
+                tail.skip_first &= id.is_none_or(|id| !self.rotated.contains_key(&id));
+                self.tails.insert(path.clone(), tail);
+            }
+        }
+
+        let mut fresh = Vec::new();
+        for (path, tail) in self.tails.iter_mut() {
+            // Finish the file we have open, then switch to a new one at its path
+            tail.read(&mut fresh, now);
+            let replaced = fs::metadata(path).is_ok_and(|metadata| file_id(&metadata) != tail.id);
+            if let Some(new) = replaced.then(|| Tail::open(path, 0).ok()).flatten() {
+                if let Some(id) = tail.id {
+                    self.rotated.insert(id, tail.offset);
+                }
+                *tail = Tail { last_nanos: tail.last_nanos, ..new };
+                tail.read(&mut fresh, now);
+            }
+        }
+        // Stop following files that are gone, remembering how far they were read
+        let rotated = &mut self.rotated;
+        self.tails.retain(|path, tail| {
+            let exists = path.exists();
+            if let Some(id) = tail.id.filter(|_| !exists) {
+                rotated.insert(id, tail.offset);
+            }
+            exists
+        });
+
+        self.lines.extend(fresh);
+        self.lines.sort_by_key(|log| log.nanos);
+        let excess = self.lines.len().saturating_sub(limit);
+        self.lines.drain(..excess);
+        self.lines.clone()
+    }
+
+    /// The existing files the configured paths match, wildcards expanded.
+    fn expand(&self) -> BTreeSet<PathBuf> {
+        let mut paths = BTreeSet::new();
+        for pattern in &self.patterns {
+            let path = Path::new(pattern);
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            if !name.contains(['*', '?']) {
+                if path.is_file() {
+                    paths.insert(path.to_path_buf());
+                }
+                continue;
+            }
+            let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            let Ok(entries) = fs::read_dir(dir) else { continue };
+            paths.extend(
+                entries
+                    .flatten()
+                    .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+                    .filter(|entry| entry.file_name().to_str().is_some_and(|file| wildcard(name, file)))
+                    .map(|entry| entry.path()),
+            );
+        }
+        paths
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` is any run of characters and
+/// `?` any one character.
+pub fn wildcard(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    let mut star = None; // Where the last `*` was, and the name position it covers up to
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // Let the `*` take one more character
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Unix nanoseconds of the timestamp a line starts with: RFC 3339, or
+/// `2024-05-01 12:00:00` (optionally with fractions) in local time.
+fn leading_timestamp(line: &str) -> Option<i64> {
+    let line = line.trim_start_matches('[');
+    let token = line.split([' ', ']']).next()?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(token) {
+        return time.timestamp_nanos_opt();
+    }
+    let (time, _) = NaiveDateTime::parse_and_remainder(line, "%Y-%m-%d %H:%M:%S%.f").ok()?;
+    Local.from_local_datetime(&time).earliest()?.timestamp_nanos_opt()
+}
@@ -0,0 +1,36 @@
+//! Several LogQL queries shown as one timeline, e.g. an app's own logs
+//! interleaved with the ingress access log in front of it. Each line keeps
+//! the index of the query it came from, which the logs panel shows as a
+//! colored chip.
+
+use crate::loki::LogEntry;
+use serde::{Deserialize, Serialize};
+
+/// Most queries overlaid at once; beyond this the chips stop being easy to
+/// tell apart.
+pub const MAX_LOG_QUERIES: usize = 6;
+
+/// One `[[log_queries]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogQuery {
+    pub name: String,  // Shown in each line's chip
+    pub query: String, // LogQL log query, e.g. `{app="checkout"} |= "order"`
+}
+
+/// Merge each query's lines (oldest first) into one chronological list,
+/// tagging every line with the index of its query. Lines logged at the same
+/// instant keep the queries' order.
+pub fn merge(results: Vec<Vec<LogEntry>>) -> Vec<LogEntry> {
+    let mut merged: Vec<LogEntry> = results
+        .into_iter()
+        .enumerate()
+        .flat_map(|(source, logs)| {
+            logs.into_iter().map(move |log| LogEntry {
+                source: Some(source),
+                ..log
+            })
+        })
+        .collect();
+    merged.sort_by_key(|log| log.nanos);
+    merged
+}
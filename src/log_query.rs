@@ -0,0 +1,488 @@
+//! LogQL query bar (`/`): a log query typed by hand narrows the logs panel,
+//! highlighted as it is typed. Submitting checks it locally first (stream
+//! selector matchers, each pipeline stage, quoting and the regexes used) and
+//! then tries it against Loki; only a query Loki accepts replaces the panel's
+//! filter. Either kind of error is shown under the bar, pointing at the
+//! offending part of the query, rather than leaving an empty panel.
+
+use crate::loki::LokiError;
+use crate::text::LineInput;
+use regex::Regex;
+use std::fmt;
+use std::ops::Range;
+
+/// Words with a meaning of their own in a log pipeline.
+const KEYWORDS: &[&str] = &[
+    "json", "logfmt", "regexp", "pattern", "unpack", "line_format", "label_format", "drop", "keep", "decolorize", "and",
+    "or", "unwrap", "by", "without",
+];
+
+/// What a piece of a query is, for highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Punct,    // { } ( ) [ ] ,
+    Name,     // A label
+    Keyword,  // A parser or stage name, `and`, `or`, a `--flag`
+    Operator, // |= != |~ !~ | = =~ == > >= < <= and arithmetic
+    String,   // "..." or `...`
+    Unclosed, // A string missing its closing quote
+    Number,   // 500, 1.5, 5m, 10KB
+    Invalid,  // Nothing LogQL knows
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize, // Byte range in the query
+    pub end: usize,
+}
+
+/// Split `query` into tokens; whitespace between them is skipped.
+pub fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let mut take_while = |chars: &mut std::iter::Peekable<std::str::CharIndices>, keep: fn(char) -> bool| {
+            while let Some(&(i, next)) = chars.peek() {
+                if !keep(next) {
+                    break;
+                }
+                chars.next();
+                end = i + next.len_utf8();
+            }
+        };
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '"' | '`' => {
+                let (mut escaped, mut closed) = (false, false);
+                for (i, next) in chars.by_ref() {
+                    end = i + next.len_utf8();
+                    if escaped {
+                        escaped = false;
+                    } else if next == '\\' && c == '"' {
+                        escaped = true;
+                    } else if next == c {
+                        closed = true;
+                        break;
+                    }
+                }
+                if closed { TokenKind::String } else { TokenKind::Unclosed }
+            }
+            '{' | '}' | '(' | ')' | '[' | ']' | ',' => TokenKind::Punct,
+            '|' | '!' | '=' | '>' | '<' => {
+                if let Some(&(i, next)) = chars.peek() {
+                    if matches!((c, next), ('|' | '!', '=' | '~') | ('=', '~' | '=') | ('>' | '<', '=')) {
+                        chars.next();
+                        end = i + 1;
+                    }
+                }
+                if c == '!' && end == start + 1 { TokenKind::Invalid } else { TokenKind::Operator }
+            }
+            '-' if chars.peek().is_some_and(|&(_, next)| next == '-') => {
+                take_while(&mut chars, |next| next == '-' || next.is_ascii_alphanumeric() || next == '_');
+                TokenKind::Keyword
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => TokenKind::Operator,
+            c if c.is_ascii_digit() => {
+                take_while(&mut chars, |next| next.is_ascii_alphanumeric() || next == '.');
+                TokenKind::Number
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                take_while(&mut chars, |next| next.is_ascii_alphanumeric() || next == '_');
+                if KEYWORDS.contains(&&query[start..end]) { TokenKind::Keyword } else { TokenKind::Name }
+            }
+            _ => TokenKind::Invalid,
+        };
+        tokens.push(Token { kind, start, end });
+    }
+    tokens
+}
+
+/// Why a query can't run, and where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub message: String,
+    pub at: Option<Range<usize>>, // Byte range to point at; empty at the end of the query
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Check that `query` is a log query Loki would run: a stream selector
+/// with at least one matcher that can't match an empty value, followed by
+/// line filters and pipeline stages.
+pub fn check(query: &str) -> Result<(), SyntaxError> {
+    let mut parser = Parser { query, tokens: tokenize(query), at: 0 };
+    parser.log_query()
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    tokens: Vec<Token>,
+    at: usize, // Next token
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.at).copied()
+    }
+
+    fn text(&self, token: Token) -> &str {
+        &self.query[token.start..token.end]
+    }
+
+    /// Whether the next token is `text`, taking it if so.
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.peek().is_some_and(|token| self.text(token) == text && token.kind != TokenKind::String);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    /// An error at the next token, or at the end of the query.
+    fn error(&self, message: impl Into<String>) -> SyntaxError {
+        let at = match self.peek() {
+            Some(token) => token.start..token.end,
+            None => self.query.len()..self.query.len(),
+        };
+        SyntaxError { message: message.into(), at: Some(at) }
+    }
+
+    fn error_at(&self, token: Token, message: impl Into<String>) -> SyntaxError {
+        SyntaxError { message: message.into(), at: Some(token.start..token.end) }
+    }
+
+    /// The next token if it is of `kind`.
+    fn expect(&mut self, kind: TokenKind, message: &str) -> Result<Token, SyntaxError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Unclosed => Err(self.error_at(token, "missing closing quote")),
+            Some(token) if token.kind == kind => {
+                self.at += 1;
+                Ok(token)
+            }
+            _ => Err(self.error(message)),
+        }
+    }
+
+    /// The next token, which must be a string, unquoted.
+    fn string(&mut self, message: &str) -> Result<(Token, String), SyntaxError> {
+        let token = self.expect(TokenKind::String, message)?;
+        let value = unquote(self.text(token)).map_err(|e| self.error_at(token, e))?;
+        Ok((token, value))
+    }
+
+    /// The next token, a string holding a valid regex.
+    fn regex(&mut self, message: &str) -> Result<(Token, String), SyntaxError> {
+        let (token, pattern) = self.string(message)?;
+        if let Err(e) = Regex::new(&pattern) {
+            let reason = e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+            return Err(self.error_at(token, format!("invalid regex: {}", reason)));
+        }
+        Ok((token, pattern))
+    }
+
+    fn log_query(&mut self) -> Result<(), SyntaxError> {
+        match self.peek() {
+            None => return Err(self.error("empty query")),
+            Some(token) if token.kind == TokenKind::Name || token.kind == TokenKind::Keyword => {
+                return Err(self.error_at(
+                    token,
+                    "metric queries can't be shown as log lines; start with a stream selector such as {app=\"api\"}",
+                ));
+            }
+            Some(_) if !self.eat("{") => return Err(self.error("a log query starts with a stream selector such as {app=\"api\"}")),
+            Some(_) => {}
+        }
+        self.selector()?;
+        while self.peek().is_some() {
+            self.stage()?;
+        }
+        Ok(())
+    }
+
+    /// The matchers of a stream selector, after its `{`.
+    fn selector(&mut self) -> Result<(), SyntaxError> {
+        let open = self.at - 1;
+        let mut selective = false;
+        if self.eat("}") {
+            return Err(self.error_at(self.tokens[open], "the stream selector needs at least one matcher"));
+        }
+        loop {
+            self.expect(TokenKind::Name, "expected a label name")?;
+            let op = self.peek().map(|token| self.text(token).to_string()).unwrap_or_default();
+            if !matches!(op.as_str(), "=" | "!=" | "=~" | "!~") {
+                return Err(self.error("expected =, !=, =~ or !~ after the label"));
+            }
+            self.at += 1;
+            let value = if op.ends_with('~') {
+                self.regex("expected a quoted regex")?.1
+            } else {
+                self.string("expected a quoted value")?.1
+            };
+            // Loki refuses selectors that would match every stream
+            selective |= match op.as_str() {
+                "=" => !value.is_empty(),
+                "=~" => Regex::new(&format!("^(?:{})$", value)).is_ok_and(|re| !re.is_match("")),
+                _ => false,
+            };
+            if self.eat("}") {
+                break;
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected , or } after the matcher"));
+            }
+        }
+        if !selective {
+            return Err(SyntaxError {
+                message: "the selector needs a matcher that can't match an empty value, such as app=\"api\" or app=~\".+\"".to_string(),
+                at: Some(self.tokens[open].start..self.tokens[self.at - 1].end),
+            });
+        }
+        Ok(())
+    }
+
+    /// One line filter or `|` stage.
+    fn stage(&mut self) -> Result<(), SyntaxError> {
+        let token = self.peek().expect("called with tokens left");
+        match self.text(token) {
+            "|=" | "!=" | "|~" | "!~" if token.kind == TokenKind::Operator => {
+                self.at += 1;
+                let regex = self.text(token).ends_with('~');
+                loop {
+                    if !regex && self.eat("ip") {
+                        self.expect_text("(")?;
+                        self.string("expected a quoted address or range")?;
+                        self.expect_text(")")?;
+                    } else if regex {
+                        self.regex("expected a quoted regex after the line filter")?;
+                    } else {
+                        self.string("expected a quoted string after the line filter")?;
+                    }
+                    if !self.eat("or") {
+                        return Ok(());
+                    }
+                }
+            }
+            "|" if token.kind == TokenKind::Operator => {
+                self.at += 1;
+                self.pipe_stage()
+            }
+            _ => Err(self.error("expected a line filter (|=, !=, |~, !~) or | and a stage")),
+        }
+    }
+
+    fn expect_text(&mut self, text: &str) -> Result<(), SyntaxError> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {}", text)))
+        }
+    }
+
+    /// What follows a `|`.
+    fn pipe_stage(&mut self) -> Result<(), SyntaxError> {
+        let Some(token) = self.peek() else {
+            return Err(self.error("expected a parser, formatter or label filter after |"));
+        };
+        if token.kind == TokenKind::Name {
+            return self.label_filters();
+        }
+        if self.eat("(") {
+            self.label_filters()?;
+            return self.expect_text(")");
+        }
+        self.at += 1;
+        match self.text(token) {
+            "json" | "logfmt" if token.kind == TokenKind::Keyword => {
+                while self.peek().is_some_and(|next| next.kind == TokenKind::Keyword && self.text(next).starts_with("--")) {
+                    self.at += 1;
+                }
+                // Optional `label="expression"` or `label`, comma-separated
+                while self.peek().is_some_and(|next| next.kind == TokenKind::Name) {
+                    self.at += 1;
+                    if self.eat("=") {
+                        self.string("expected a quoted expression")?;
+                    }
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            "unpack" | "decolorize" => Ok(()),
+            "regexp" => {
+                let (token, pattern) = self.regex("expected a quoted regex with named groups")?;
+                if !pattern.contains("(?P<") && !pattern.contains("(?<") {
+                    return Err(self.error_at(token, "regexp needs a named capture group such as (?P<status>\\d+)"));
+                }
+                Ok(())
+            }
+            "pattern" => {
+                let (token, pattern) = self.string("expected a quoted pattern")?;
+                if !pattern.contains('<') {
+                    return Err(self.error_at(token, "pattern needs a capture such as <status>"));
+                }
+                Ok(())
+            }
+            "line_format" => self.string("expected a quoted template").map(|_| ()),
+            "label_format" => loop {
+                self.expect(TokenKind::Name, "expected a label to set")?;
+                self.expect_text("=")?;
+                match self.peek() {
+                    Some(next) if matches!(next.kind, TokenKind::Name | TokenKind::String) => self.at += 1,
+                    _ => return Err(self.error("expected a quoted template or a label to rename")),
+                }
+                if !self.eat(",") {
+                    return Ok(());
+                }
+            },
+            "drop" | "keep" => loop {
+                self.expect(TokenKind::Name, "expected a label")?;
+                if self.peek().is_some_and(|next| matches!(self.text(next), "=" | "!=" | "=~" | "!~")) {
+                    self.at += 1;
+                    self.string("expected a quoted value")?;
+                }
+                if !self.eat(",") {
+                    return Ok(());
+                }
+            },
+            "unwrap" => Err(self.error_at(token, "unwrap only works in metric queries")),
+            _ => Err(self.error_at(token, "expected a stage or a label filter after |")),
+        }
+    }
+
+    /// `label op value`, chained with `and`, `or` or `,`.
+    fn label_filters(&mut self) -> Result<(), SyntaxError> {
+        loop {
+            let label = self.expect(TokenKind::Name, "expected a label")?;
+            let op = self.peek().map(|token| self.text(token).to_string()).unwrap_or_default();
+            match op.as_str() {
+                "=~" | "!~" => {
+                    self.at += 1;
+                    self.regex("expected a quoted regex")?;
+                }
+                "=" | "!=" | "==" | ">" | ">=" | "<" | "<=" => {
+                    self.at += 1;
+                    match self.peek() {
+                        Some(next) if next.kind == TokenKind::Number => self.at += 1,
+                        Some(next) if matches!(next.kind, TokenKind::String | TokenKind::Unclosed) => {
+                            self.string("expected a value")?;
+                        }
+                        _ => return Err(self.error("expected a quoted value, number, duration or size")),
+                    }
+                }
+                _ => {
+                    return Err(self.error_at(
+                        label,
+                        "expected a stage (json, logfmt, regexp, pattern, line_format, label_format, drop, keep) or a label filter such as status >= 500",
+                    ));
+                }
+            }
+            if !(self.eat("and") || self.eat("or") || self.eat(",")) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The value of a string token: backquoted ones as written, double-quoted
+/// ones with Go's escapes, which is what Loki accepts.
+fn unquote(token: &str) -> Result<String, String> {
+    if let Some(raw) = token.strip_prefix('`') {
+        return Ok(raw.trim_end_matches('`').to_string());
+    }
+    let inner = &token[1..token.len() - 1];
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => value.push('\\'),
+            Some('"') => value.push('"'),
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some(c @ ('a' | 'b' | 'f' | 'v' | 'x' | 'u' | 'U' | '0'..='7')) => {
+                // Rare in log queries; kept as written
+                value.push('\\');
+                value.push(c);
+            }
+            Some(c) => {
+                return Err(format!("invalid escape \\{} in a string; write \\\\{} or use a `backquoted` string", c, c));
+            }
+            None => return Err("string ends with a lone \\".to_string()),
+        }
+    }
+    Ok(value)
+}
+
+/// State of the query bar while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct LogQueryBar {
+    pub input: LineInput,
+    pub error: Option<SyntaxError>, // Why the last submit was refused, locally or by Loki
+    pub submitted: Option<String>,  // Being tried against Loki
+}
+
+impl LogQueryBar {
+    /// Start editing `query`, usually the filter the panel has now.
+    pub fn new(query: &str) -> Self {
+        Self { input: LineInput::new(query), ..Self::default() }
+    }
+
+    /// Forget errors and a pending submit; they were about the old text.
+    pub fn edited(&mut self) {
+        self.error = None;
+        self.submitted = None;
+    }
+
+    /// Check the input; if it passes, it is the query to try against Loki.
+    pub fn submit(&mut self) -> Option<String> {
+        let query = self.input.text.trim().to_string();
+        match check(&query) {
+            Ok(()) => {
+                self.error = None;
+                self.submitted = Some(query.clone());
+                Some(query)
+            }
+            Err(mut e) => {
+                // Positions are into the untrimmed input
+                let offset = self.input.text.len() - self.input.text.trim_start().len();
+                e.at = e.at.map(|at| at.start + offset..at.end + offset);
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Loki refused the submitted query; show why, at the column it names.
+    pub fn reject(&mut self, e: &LokiError) {
+        self.submitted = None;
+        let message = match e {
+            LokiError::Status { message, .. } => message.clone(),
+            e => format!("{}: {}", e.class().as_str(), e),
+        };
+        let at = loki_column(&message).and_then(|column| {
+            let text = &self.input.text;
+            let offset = text.len() - text.trim_start().len();
+            let start = offset + text.trim_start().char_indices().nth(column.checked_sub(1)?)?.0;
+            let token = tokenize(text).into_iter().find(|token| token.start <= start && start < token.end);
+            Some(token.map_or(start..start + 1, |token| token.start..token.end))
+        });
+        self.error = Some(SyntaxError { message: format!("Loki: {}", message), at });
+    }
+}
+
+/// The column in Loki's `parse error at line 1, col 13: ...`, 1-based.
+fn loki_column(message: &str) -> Option<usize> {
+    let rest = &message[message.find("col ")? + 4..];
+    rest[..rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())].parse().ok()
+}
@@ -0,0 +1,170 @@
+//! Small typed builder for the LogQL queries rustdash issues.
+//!
+//! Label values and filter strings are escaped with the same rules as PromQL,
+//! and pipeline stages are appended in order, so runtime filters can be
+//! composed without producing invalid queries.
+//!
+//! ```
+//! use rustdash::logql::StreamSelector;
+//!
+//! let query = StreamSelector::new()
+//!     .eq("app", "api")
+//!     .pipe()
+//!     .json()
+//!     .matches("(?i)timeout");
+//! assert_eq!(query.to_string(), r#"{app="api"} | json |~ "(?i)timeout""#);
+//! ```
+
+use crate::promql::{quote, MatchOp};
+use std::fmt;
+
+/// A log stream selector: `{label="value", ...}`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamSelector {
+    matchers: Vec<(String, MatchOp, String)>,
+}
+
+impl StreamSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn matching(mut self, label: &str, op: MatchOp, value: &str) -> Self {
+        self.matchers.push((label.to_string(), op, value.to_string()));
+        self
+    }
+
+    pub fn eq(self, label: &str, value: &str) -> Self {
+        self.matching(label, MatchOp::Eq, value)
+    }
+
+    pub fn ne(self, label: &str, value: &str) -> Self {
+        self.matching(label, MatchOp::NotEq, value)
+    }
+
+    pub fn re(self, label: &str, pattern: &str) -> Self {
+        self.matching(label, MatchOp::Regex, pattern)
+    }
+
+    pub fn not_re(self, label: &str, pattern: &str) -> Self {
+        self.matching(label, MatchOp::NotRegex, pattern)
+    }
+
+    /// Names of the labels this selector matches on.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.matchers.iter().map(|(label, _, _)| label.as_str())
+    }
+
+    /// Start a log pipeline on this stream.
+    pub fn pipe(self) -> LogQuery {
+        LogQuery::from(self)
+    }
+}
+
+impl fmt::Display for StreamSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let matchers: Vec<String> = self
+            .matchers
+            .iter()
+            .map(|(label, op, value)| format!("{}{}{}", label, op.as_str(), quote(value)))
+            .collect();
+        write!(f, "{{{}}}", matchers.join(", "))
+    }
+}
+
+/// A log query: a stream selector followed by pipeline stages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogQuery {
+    selector: StreamSelector,
+    stages: Vec<String>,
+}
+
+impl From<StreamSelector> for LogQuery {
+    fn from(selector: StreamSelector) -> Self {
+        Self {
+            selector,
+            stages: Vec::new(),
+        }
+    }
+}
+
+impl LogQuery {
+    fn stage(mut self, stage: String) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// `|= "text"`: keep lines containing `text`.
+    pub fn contains(self, text: &str) -> Self {
+        self.stage(format!("|= {}", quote(text)))
+    }
+
+    /// `!= "text"`: drop lines containing `text`.
+    pub fn not_contains(self, text: &str) -> Self {
+        self.stage(format!("!= {}", quote(text)))
+    }
+
+    /// `|~ "regex"`: keep lines matching `regex`.
+    pub fn matches(self, pattern: &str) -> Self {
+        self.stage(format!("|~ {}", quote(pattern)))
+    }
+
+    /// `!~ "regex"`: drop lines matching `regex`.
+    pub fn not_matches(self, pattern: &str) -> Self {
+        self.stage(format!("!~ {}", quote(pattern)))
+    }
+
+    /// `| json`: extract JSON fields as labels.
+    pub fn json(self) -> Self {
+        self.stage("| json".to_string())
+    }
+
+    /// `| label="value"` etc.: filter on an extracted label.
+    pub fn label(self, label: &str, op: MatchOp, value: &str) -> Self {
+        self.stage(format!("| {}{}{}", label, op.as_str(), quote(value)))
+    }
+
+    pub fn selector(&self) -> &StreamSelector {
+        &self.selector
+    }
+}
+
+impl fmt::Display for LogQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.selector)?;
+        for stage in &self.stages {
+            write!(f, " {}", stage)?;
+        }
+        Ok(())
+    }
+}
+
+/// A metric query over log lines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricQuery(String);
+
+impl fmt::Display for MetricQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// `count_over_time(query[range])`: number of lines per range window.
+pub fn count_over_time(query: impl Into<LogQuery>, range: &str) -> MetricQuery {
+    MetricQuery(format!("count_over_time({}[{}])", query.into(), range))
+}
+
+/// `rate(query[range])`: lines per second.
+pub fn rate(query: impl Into<LogQuery>, range: &str) -> MetricQuery {
+    MetricQuery(format!("rate({}[{}])", query.into(), range))
+}
+
+impl MetricQuery {
+    pub fn sum(self) -> MetricQuery {
+        MetricQuery(format!("sum({})", self.0))
+    }
+
+    pub fn sum_by(self, labels: &[&str]) -> MetricQuery {
+        MetricQuery(format!("sum by ({}) ({})", labels.join(", "), self.0))
+    }
+}
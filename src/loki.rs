@@ -1,12 +1,109 @@
-use anyhow::Result;
+use crate::breaker::{CircuitBreaker, CircuitOpen, SendError};
+use crate::cache::StaticCache;
+use crate::clock::ClockSkew;
+use crate::error::{self, ErrorClass};
+use crate::guard::{self, Outcome, SlowQueryGuard};
+use crate::limiter::QueryLimiter;
+use crate::log_overlay::{self, LogQuery};
+use crate::logql::{count_over_time, StreamSelector};
+use crate::promql::quote;
+use crate::exchanges::{Exchange, ExchangeLog};
+use crate::query_stats::{Backend, QueryStats};
 use chrono::DateTime;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long build info is reused before it is revalidated.
+const BUILD_INFO_TTL: Duration = Duration::from_secs(600);
+/// How long label names are reused; new labels only matter when picking a
+/// stream selector, so a few minutes of delay is fine.
+const LABELS_TTL: Duration = Duration::from_secs(300);
+/// How far back recent logs are looked for, unless the slow query guard
+/// narrowed it.
+const RECENT_LOGS_SECONDS: i64 = 1800;
+
+/// Why a Loki request failed.
+#[derive(Debug, thiserror::Error)]
+pub enum LokiError {
+    #[error("HTTP {status}: {message}")]
+    Status { status: StatusCode, message: String }, // Loki's message, e.g. a limit it enforces
+    #[error("HTTP {status}: {message}")]
+    Auth { status: StatusCode, message: String }, // 401/403
+    #[error("timed out: {0}")]
+    Timeout(reqwest::Error),
+    #[error(transparent)]
+    Request(reqwest::Error), // No answer: connection refused, DNS, TLS
+    #[error(transparent)]
+    CircuitOpen(CircuitOpen), // Not sent: the endpoint failed too often lately
+    #[error("invalid response: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("expected a {0} result")]
+    UnexpectedResult(&'static str), // A log query answered with a matrix or the reverse
+    #[error("no active log stream")]
+    NoStream,
+    #[error("query task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+impl LokiError {
+    /// An error answer with HTTP `status` and body `message`.
+    pub fn status(status: StatusCode, message: &str) -> Self {
+        let message = message.trim().to_string();
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => LokiError::Auth { status, message },
+            _ => LokiError::Status { status, message },
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            LokiError::Status { status, message } => error::classify_status(*status, message),
+            LokiError::Auth { .. } => ErrorClass::Auth,
+            LokiError::Timeout(_) => ErrorClass::Timeout,
+            LokiError::Request(e) => error::classify_request(e),
+            LokiError::CircuitOpen(_) => ErrorClass::Unreachable,
+            LokiError::Parse(_) | LokiError::UnexpectedResult(_) => ErrorClass::Malformed,
+            LokiError::NoStream | LokiError::Task(_) => ErrorClass::Rejected,
+        }
+    }
+}
+
+impl From<reqwest::Error> for LokiError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status() {
+            Some(status) => LokiError::status(status, &e.to_string()),
+            None if e.is_timeout() => LokiError::Timeout(e),
+            None => LokiError::Request(e),
+        }
+    }
+}
+
+impl From<SendError> for LokiError {
+    fn from(e: SendError) -> Self {
+        match e {
+            SendError::Open(open) => LokiError::CircuitOpen(open),
+            SendError::Request(e) => e.into(),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, LokiError>;
 
 #[derive(Debug, Clone)]
 pub struct LokiClient {
     client: Client,
     base_url: String,
+    active_selector: Arc<Mutex<Option<StreamSelector>>>, // Stream selector that last returned logs
+    limiter: Arc<QueryLimiter>,
+    static_cache: Arc<StaticCache>, // Build info and label names
+    clock_skew: Arc<ClockSkew>,     // Offset of Loki's clock, from its Date headers
+    query_stats: Arc<QueryStats>,   // Timings of every query sent
+    exchanges: Arc<ExchangeLog>,    // The last request and response of every query
+    guard: Arc<SlowQueryGuard>,     // Narrows the window of recent-log queries that keep being slow
+    breaker: Arc<CircuitBreaker>,   // Retries and cooldowns per endpoint
 }
 
 #[derive(Debug, Deserialize)]
@@ -18,12 +115,36 @@ pub struct LokiResponse {
 
 #[derive(Debug, Deserialize)]
 pub struct LokiData {
-    #[serde(rename = "resultType")]
+    #[serde(flatten)]
+    pub result: LokiResult,
+    pub stats: Option<LokiStats>,
+}
+
+/// Result payload, keyed by `resultType`. Log queries return streams,
+/// metric queries (`count_over_time`, `rate`, ...) return a matrix from
+/// query_range or a vector from instant queries.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "resultType", content = "result", rename_all = "lowercase")]
+pub enum LokiResult {
+    Streams(Vec<LokiStream>),
+    Matrix(Vec<LokiSeries>),
     #[allow(dead_code)]
-    pub result_type: String,
-    pub result: Vec<LokiStream>,
+    Vector(Vec<LokiSample>),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LokiSeries {
     #[allow(dead_code)]
-    pub stats: Option<LokiStats>,
+    pub metric: HashMap<String, String>,
+    pub values: Vec<(f64, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LokiSample {
+    #[allow(dead_code)]
+    pub metric: HashMap<String, String>,
+    #[allow(dead_code)]
+    pub value: (f64, String),
 }
 
 #[derive(Debug, Deserialize)]
@@ -35,27 +156,171 @@ pub struct LokiStream {
 
 #[derive(Debug, Deserialize)]
 pub struct LokiStats {
-    #[allow(dead_code)]
     pub summary: serde_json::Value,
 }
 
+impl LokiStats {
+    /// Seconds Loki spent executing the query.
+    pub fn exec_seconds(&self) -> Option<f64> {
+        self.summary.get("execTime")?.as_f64()
+    }
+}
+
+/// Time range of a log query.
+enum LogRange {
+    Recent(i64),       // The latest seconds of Loki's clock, narrowed by the slow query guard
+    Since(String),     // The latest stretch of Loki's clock, e.g. "30m"
+    Between(i64, i64), // Unix nanoseconds
+}
+
 #[derive(Debug, Clone)]
 pub struct LogEntry {
     #[allow(dead_code)]
     pub timestamp: String,
+    pub nanos: i64, // Loki's timestamp (unix nanoseconds), for ordering and range queries
     pub message: String,
     pub level: String,
     pub is_new: bool,  // Flag to indicate if this log is newly added in the current update
+    pub stream: String, // Exact selector of the line's stream, e.g. `{job="api"}`
+    pub source: Option<usize>, // Index of the overlaid log query it came from, if any
+    pub local: bool, // Read from a `[log_files]` file, so Loki has never seen it
 }
 
 impl LokiClient {
-    pub fn new(base_url: String) -> Self {
+    pub fn new(base_url: String, client: Client) -> Self {
         Self {
-            client: Client::new(),
+            client,
             base_url,
+            active_selector: Arc::new(Mutex::new(None)),
+            limiter: Arc::new(QueryLimiter::unlimited()),
+            static_cache: Arc::default(),
+            clock_skew: Arc::default(),
+            query_stats: Arc::default(),
+            exchanges: Arc::default(),
+            guard: Arc::default(),
+            breaker: Arc::new(CircuitBreaker::disabled()),
         }
     }
 
+    /// Queue queries beyond the limiter's budget.
+    pub fn with_limiter(mut self, limiter: QueryLimiter) -> Self {
+        self.limiter = Arc::new(limiter);
+        self
+    }
+
+    /// Record query timings in `stats`, e.g. one shared with the UI.
+    pub fn with_query_stats(mut self, stats: Arc<QueryStats>) -> Self {
+        self.query_stats = stats;
+        self
+    }
+
+    /// Keep the last request and response of every query in `log`.
+    pub fn with_exchange_log(mut self, log: Arc<ExchangeLog>) -> Self {
+        self.exchanges = log;
+        self
+    }
+
+    /// Reduce queries that keep being slow or hitting Loki's limits.
+    pub fn with_guard(mut self, guard: Arc<SlowQueryGuard>) -> Self {
+        self.guard = guard;
+        self
+    }
+
+    /// Retry requests that fail in passing and stop sending to endpoints
+    /// that keep failing.
+    pub fn with_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    /// Send a query_range request and decode the response, recording how
+    /// long it took, and for `guarded` queries telling the slow query guard.
+    /// Errors carry Loki's message.
+    async fn send_query(&self, query: &str, params: &[(&str, String)], guarded: bool) -> Result<LokiResponse> {
+        let url = format!("{}/loki/api/v1/query_range", self.base_url);
+        let observe = |outcome| {
+            if guarded {
+                self.guard.observe(Backend::Loki, query, outcome);
+            }
+        };
+        let started = Instant::now();
+        let sent = chrono::Utc::now();
+        let request = self.client.get(&url).query(&[("query", query)]).query(params);
+        let exchange = Exchange::new(Backend::Loki, query, &request, chrono::Local::now());
+        let elapsed_ms = || started.elapsed().as_secs_f64() * 1000.0;
+        let response = match self.breaker.send(Backend::Loki, request).await {
+            Ok(response) => response,
+            Err(e) => {
+                let e = LokiError::from(e);
+                if let Some(exchange) = exchange {
+                    self.exchanges.record(exchange.respond(None, &e.to_string(), elapsed_ms()));
+                }
+                if e.class() == ErrorClass::Timeout {
+                    observe(Outcome::TimedOut);
+                }
+                return Err(e);
+            }
+        };
+        self.clock_skew.record_date_header(&response, sent);
+        let status = response.status();
+        let body = response.text().await?;
+        if let Some(exchange) = exchange {
+            self.exchanges.record(exchange.respond(Some(status.as_u16()), &body, elapsed_ms()));
+        }
+        
+        if !status.is_success() {
+            self.query_stats.record(Backend::Loki, query, started.elapsed(), None);
+            let e = LokiError::status(status, &body);
+            if let LokiError::Status { message, .. } | LokiError::Auth { message, .. } = &e {
+                observe(Outcome::Rejected(e.class(), message));
+            }
+            return Err(e);
+        }
+        let loki_response = serde_json::from_str::<LokiResponse>(&body)?;
+        let server_seconds = loki_response.data.stats.as_ref().and_then(LokiStats::exec_seconds);
+        self.query_stats.record(Backend::Loki, query, started.elapsed(), server_seconds);
+        observe(Outcome::Finished(started.elapsed()));
+        Ok(loki_response)
+    }
+
+    /// Loki version, from `/loki/api/v1/status/buildinfo`.
+    pub async fn get_build_info(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct BuildInfo {
+            version: String,
+        }
+
+        let _permit = self.limiter.acquire().await;
+        let url = format!("{}/loki/api/v1/status/buildinfo", self.base_url);
+        let body = self.static_cache.get(&self.client, &self.breaker, Backend::Loki, &url, BUILD_INFO_TTL).await?;
+        Ok(serde_json::from_str::<BuildInfo>(&body)?.version)
+    }
+
+    /// Label names present in recent streams, from `/loki/api/v1/labels`.
+    pub async fn get_label_names(&self) -> Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct LabelsResponse {
+            #[serde(default)]
+            data: Vec<String>,
+        }
+
+        let _permit = self.limiter.acquire().await;
+        let url = format!("{}/loki/api/v1/labels", self.base_url);
+        let body = self.static_cache.get(&self.client, &self.breaker, Backend::Loki, &url, LABELS_TTL).await?;
+        Ok(serde_json::from_str::<LabelsResponse>(&body)?.data)
+    }
+
+    /// Cache of slowly-changing responses, e.g. to expire it on demand.
+    pub fn static_cache(&self) -> &StaticCache {
+        &self.static_cache
+    }
+
+    /// Seconds Loki's clock is ahead of ours (negative when behind), as of
+    /// the last log query; None before the first.
+    pub fn clock_skew(&self) -> Option<f64> {
+        self.clock_skew.seconds()
+    }
+
     #[allow(dead_code)]
     pub async fn query_range(
         &self,
@@ -64,69 +329,197 @@ impl LokiClient {
         end: &str,
         limit: u32,
     ) -> Result<LokiResponse> {
-        let url = format!("{}/loki/api/v1/query_range", self.base_url);
-        let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("query", query),
-                ("start", start),
-                ("end", end),
-                ("limit", &limit.to_string()),
-            ])
-            .send()
-            .await?
-            .json::<LokiResponse>()
-            .await?;
-        
-        Ok(response)
+        let _permit = self.limiter.acquire().await;
+        self.send_query(
+            query,
+            &[("start", start.to_string()), ("end", end.to_string()), ("limit", limit.to_string())],
+            false,
+        )
+        .await
     }
 
     pub async fn tail(&self, query: &str, limit: u32) -> Result<Vec<LogEntry>> {
-        // Use query_range instead of query for log queries
-        let url = format!("{}/loki/api/v1/query_range", self.base_url);
-        
-        // Get logs up to current time, with a smaller time window for more recent logs
-        let end = chrono::Utc::now() + chrono::Duration::minutes(1); // Add buffer for clock skew
-        let start = end - chrono::Duration::minutes(30); // Look back only 30 minutes for recent logs
-        
-        let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("query", query),
-                ("start", &start.timestamp_nanos_opt().unwrap_or(0).to_string()),
-                ("end", &end.timestamp_nanos_opt().unwrap_or(0).to_string()),
-                ("limit", &limit.to_string()),
-                ("direction", "backward"),  // Get newest logs first, then reverse
-            ])
-            .send()
+        // Look back only 30 minutes for recent logs, measured on Loki's own
+        // clock so a skewed local clock can't push the window past the newest lines
+        let range = LogRange::Recent(RECENT_LOGS_SECONDS);
+        // Get newest logs first, then reverse
+        self.query_logs(query, range, limit, "backward").await
+    }
+
+    /// The newest `limit` recent lines of each of `queries`, merged into one
+    /// timeline. Queries that fail are left out; only when all of them fail
+    /// is it an error.
+    pub async fn get_overlay_logs(&self, queries: &[LogQuery], limit: u32) -> Result<Vec<LogEntry>> {
+        self.overlay_logs(queries, None, limit).await
+    }
+
+    /// Like [`LokiClient::get_overlay_logs`], over the last `seconds` on
+    /// Loki's clock, e.g. to start with an hour of context.
+    pub async fn get_overlay_logs_since(&self, queries: &[LogQuery], seconds: i64, limit: u32) -> Result<Vec<LogEntry>> {
+        self.overlay_logs(queries, Some(seconds), limit).await
+    }
+
+    async fn overlay_logs(&self, queries: &[LogQuery], since: Option<i64>, limit: u32) -> Result<Vec<LogEntry>> {
+        let mut tasks = tokio::task::JoinSet::new();
+        for (idx, query) in queries.iter().enumerate() {
+            let (client, query) = (self.clone(), query.query.clone());
+            tasks.spawn(async move {
+                let logs = match since {
+                    Some(seconds) => client.query_logs(&query, LogRange::Since(format!("{}s", seconds)), limit, "backward").await,
+                    None => client.tail(&query, limit).await,
+                };
+                (idx, logs)
+            });
+        }
+        let mut results: Vec<Vec<LogEntry>> = vec![Vec::new(); queries.len()];
+        let mut last_error = None;
+        let mut any_succeeded = false;
+        while let Some(joined) = tasks.join_next().await {
+            match joined? {
+                (idx, Ok(logs)) => {
+                    any_succeeded = true;
+                    results[idx] = logs;
+                }
+                (idx, Err(e)) => {
+                    log::debug!("Log query '{}' failed: {}", queries[idx].name, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if !any_succeeded => Err(e),
+            _ => Ok(log_overlay::merge(results)),
+        }
+    }
+
+    /// Lines of the active stream logged strictly between `after` and `before`
+    /// (unix nanoseconds), oldest first and at most `limit` of them, e.g. to
+    /// fill the gap an outage left.
+    pub async fn get_logs_between(&self, after: i64, before: i64, limit: u32) -> Result<Vec<LogEntry>> {
+        let selector = self
+            .active_selector
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(LokiError::NoStream)?;
+        let mut logs = self
+            .query_logs(&selector.to_string(), LogRange::Between(after, before), limit, "forward")
             .await?;
-        
+        logs.retain(|log| log.nanos > after && log.nanos < before);
+        Ok(logs)
+    }
+
+    /// The newest `limit` lines of the active stream from the last `seconds`
+    /// on Loki's clock, oldest first, e.g. to start with an hour of context.
+    pub async fn get_logs_since(&self, seconds: i64, limit: u32) -> Result<Vec<LogEntry>> {
+        let selector = self
+            .active_selector
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(LokiError::NoStream)?;
+        self.query_logs(&selector.to_string(), LogRange::Since(format!("{}s", seconds)), limit, "backward")
+            .await
+    }
+
+    /// Recent lines of `stream` (an exact selector; the active stream when
+    /// None) that match the line filter regex `pattern`, if any. Unlike
+    /// [`LokiClient::get_recent_logs`] this leaves the active stream alone.
+    pub async fn get_filtered_logs(&self, stream: Option<&str>, pattern: Option<&str>, limit: u32) -> Result<Vec<LogEntry>> {
+        let selector = match stream {
+            Some(stream) => stream.to_string(),
+            None => self
+                .active_selector
+                .lock()
+                .unwrap()
+                .clone()
+                .unwrap_or_else(|| StreamSelector::new().re("service_name", ".+"))
+                .to_string(),
+        };
+        let query = match pattern {
+            Some(pattern) => format!("{} |~ {}", selector, quote(pattern)),
+            None => selector,
+        };
+        self.tail(&query, limit).await
+    }
+
+    /// Ask Loki's compactor to delete the lines matching `query` between
+    /// `start` and `end` (unix seconds). Loki must have deletion enabled; the
+    /// lines go once the request's cancellation period has passed.
+    pub async fn submit_delete_request(&self, query: &str, start: i64, end: i64) -> Result<()> {
+        let _permit = self.limiter.acquire().await;
+        let url = format!("{}/loki/api/v1/delete", self.base_url);
+        let request = self
+            .client
+            .post(&url)
+            .query(&[("query", query), ("start", &start.to_string()), ("end", &end.to_string())]);
+        let response = self.breaker.send(Backend::Loki, request).await?;
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("Loki query failed: {}", error_text));
+            return Err(LokiError::status(response.status(), &response.text().await?));
         }
+        Ok(())
+    }
+
+    /// Whether `selector` produced any line in the last `minutes`.
+    pub async fn has_logs_since(&self, selector: &str, minutes: u64) -> Result<bool> {
+        let logs = self
+            .query_logs(selector, LogRange::Since(format!("{}m", minutes)), 1, "backward")
+            .await?;
+        Ok(!logs.is_empty())
+    }
+
+    /// Log query over `range`, sorted oldest first. `direction` picks which
+    /// end `limit` keeps: "backward" the newest lines, "forward" the oldest.
+    async fn query_logs(&self, query: &str, range: LogRange, limit: u32, direction: &str) -> Result<Vec<LogEntry>> {
+        let _permit = self.limiter.acquire().await;
+        // Use query_range instead of query for log queries
+        let mut params = vec![("limit", limit.to_string()), ("direction", direction.to_string())];
+        let guarded = matches!(range, LogRange::Recent(_));
+        match range {
+            LogRange::Recent(seconds) => {
+                let level = self.guard.level(Backend::Loki, query);
+                params.push(("since", format!("{}s", guard::narrow_seconds(seconds, level))));
+            }
+            LogRange::Since(duration) => params.push(("since", duration)),
+            LogRange::Between(start, end) => params.extend([("start", start.to_string()), ("end", end.to_string())]),
+        }
+        let loki_response = self.send_query(query, &params, guarded).await?;
         
-        let loki_response = response.json::<LokiResponse>().await?;
+        let streams = match loki_response.data.result {
+            LokiResult::Streams(streams) => streams,
+            _ => return Err(LokiError::UnexpectedResult("streams")),
+        };
         
         let mut logs = Vec::new();
         
-        for stream in loki_response.data.result {
+        for stream in streams {
+            let selector = stream
+                .stream
+                .as_object()
+                .into_iter()
+                .flatten()
+                .fold(StreamSelector::new(), |selector, (label, value)| {
+                    selector.eq(label, value.as_str().unwrap_or_default())
+                })
+                .to_string();
             for (timestamp_str, message) in stream.values {
-                let level = self.extract_log_level(&message);
+                let level = extract_log_level(&message);
                 let timestamp = timestamp_str.parse::<i64>().unwrap_or(0);
                 logs.push((timestamp, LogEntry {
                     timestamp: self.format_timestamp(&timestamp_str),
+                    nanos: timestamp,
                     message: message.clone(),
                     level,
                     is_new: false,  // Will be set properly when comparing with previous logs
+                    stream: selector.clone(),
+                    source: None,
+                    local: false,
                 }));
             }
         }
         
         // Sort by timestamp (oldest first, newest last)
-        logs.sort_by(|a, b| a.0.cmp(&b.0));
+        logs.sort_by_key(|(timestamp, _)| *timestamp);
         
         // Extract just the LogEntry values
         let sorted_logs: Vec<LogEntry> = logs.into_iter().map(|(_, entry)| entry).collect();
@@ -137,77 +530,112 @@ impl LokiClient {
     pub async fn get_recent_logs(&self, limit: u32) -> Result<Vec<LogEntry>> {
         // Try different queries in order of preference
         let queries = vec![
-            "{service_name=\"fontory\"}",  // fontory service specifically
-            "{service_name=~\".+\"}",      // Any service_name label
-            "{app=~\".+\"}",               // Any app label
-            "{host=~\".+\"}",              // Any host label
-            "{level=~\".+\"}",             // Any level label
-            "{}",                          // Any logs (might not work on all Loki configs)
+            StreamSelector::new().eq("service_name", "fontory"), // fontory service specifically
+            StreamSelector::new().re("service_name", ".+"),      // Any service_name label
+            StreamSelector::new().re("app", ".+"),               // Any app label
+            StreamSelector::new().re("host", ".+"),              // Any host label
+            StreamSelector::new().re("level", ".+"),             // Any level label
+            StreamSelector::new(),                               // Any logs (might not work on all Loki configs)
         ];
+
+        // Skip selectors on labels Loki doesn't have; each would be a wasted
+        // query on every refresh. Without the label list, try them all.
+        let labels = self.get_label_names().await.ok();
+        let queries = queries.into_iter().filter(|query| match &labels {
+            Some(labels) => query.labels().all(|label| labels.iter().any(|l| l == label)),
+            None => true,
+        });
+        
+        let mut last_error = None;
+        let mut any_succeeded = false;
         
         for query in queries {
-            match self.tail(query, limit).await {
+            match self.tail(&query.to_string(), limit).await {
                 Ok(logs) if !logs.is_empty() => {
+                    *self.active_selector.lock().unwrap() = Some(query);
                     return Ok(logs);
                 }
-                Ok(_) => continue,  // Empty result, try next query
+                Ok(_) => {
+                    any_succeeded = true;
+                    continue;  // Empty result, try next query
+                }
                 Err(e) => {
-                    eprintln!("Query '{}' failed: {}", query, e);
+                    log::debug!("Query '{}' failed: {}", query, e);
+                    last_error = Some(e);
                     continue;
                 }
             }
         }
         
-        // Return dummy logs for testing if no real logs found
-        Ok(vec![
-            LogEntry {
-                timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                message: "No logs found in Loki. Check your Loki configuration.".to_string(),
-                level: "WARN".to_string(),
-                is_new: false,
-            },
-            LogEntry {
-                timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                message: "RustDash is running in demo mode".to_string(),
-                level: "INFO".to_string(),
-                is_new: false,
-            },
-        ])
+        // Loki unreachable or rejecting every query
+        if !any_succeeded {
+            if let Some(e) = last_error {
+                return Err(e);
+            }
+        }
+        
+        // Reachable, but no stream has any lines; the UI shows an empty state
+        Ok(Vec::new())
     }
 
-    #[allow(dead_code)]
-    pub async fn get_error_logs(&self, limit: u32) -> Result<Vec<LogEntry>> {
-        self.tail("{} |= \"error\" or \"ERROR\"", limit).await
+    /// Run a metric LogQL query over a range, returning one series per label set.
+    pub async fn query_metric_range(
+        &self,
+        query: &str,
+        start: i64,
+        end: i64,
+        step_seconds: i64,
+    ) -> Result<Vec<LokiSeries>> {
+        let _permit = self.limiter.acquire().await;
+        let params = [
+            ("start", start.to_string()),
+            ("end", end.to_string()),
+            ("step", step_seconds.to_string()),
+        ];
+        match self.send_query(query, &params, false).await?.data.result {
+            LokiResult::Matrix(series) => Ok(series),
+            _ => Err(LokiError::UnexpectedResult("matrix")),
+        }
     }
 
-    fn extract_log_level(&self, message: &str) -> String {
-        // Check for common log level patterns
-        if message.contains("l=ERROR") || message.contains("[ERROR]") || message.contains(" ERROR ") {
-            return "ERROR".to_string();
-        }
-        if message.contains("l=WARN") || message.contains("[WARN]") || message.contains(" WARN ") {
-            return "WARN".to_string();
-        }
-        if message.contains("l=INFO") || message.contains("[INFO]") || message.contains(" INFO ") {
-            return "INFO".to_string();
-        }
-        if message.contains("l=DEBUG") || message.contains("[DEBUG]") || message.contains(" DEBUG ") {
-            return "DEBUG".to_string();
-        }
+    /// Log volume histogram for the active stream: counts per `step_minutes`
+    /// bucket over the last `window_minutes`, oldest first. Empty buckets are 0.
+    pub async fn get_log_volume(&self, window_minutes: i64, step_minutes: i64) -> Result<Vec<u64>> {
+        let selector = self
+            .active_selector
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(LokiError::NoStream)?;
+        
+        let step = step_minutes * 60;
+        let end = self.clock_skew.server_now().timestamp();
+        let start = end - window_minutes * 60;
+        let query = count_over_time(selector, &format!("{}m", step_minutes)).sum();
         
-        // Fallback to case-insensitive search
-        let message_lower = message.to_lowercase();
-        if message_lower.contains("error") || message_lower.contains("fatal") {
-            "ERROR".to_string()
-        } else if message_lower.contains("warn") {
-            "WARN".to_string()
-        } else if message_lower.contains("info") {
-            "INFO".to_string()
-        } else if message_lower.contains("debug") {
-            "DEBUG".to_string()
-        } else {
-            "INFO".to_string()
+        let bucket_count = (window_minutes / step_minutes).max(1) as usize;
+        let mut buckets = vec![0u64; bucket_count];
+        for series in self.query_metric_range(&query.to_string(), start, end, step).await? {
+            for (timestamp, value) in series.values {
+                let idx = ((timestamp as i64 - start) / step).clamp(0, bucket_count as i64 - 1) as usize;
+                buckets[idx] += value.parse::<f64>().unwrap_or(0.0) as u64;
+            }
         }
+        
+        Ok(buckets)
+    }
+
+    /// Recent lines of the active stream mentioning "error", oldest first.
+    pub async fn get_error_logs(&self, limit: u32) -> Result<Vec<LogEntry>> {
+        // Loki rejects an empty selector, so fall back to any labelled service
+        let selector = self
+            .active_selector
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| StreamSelector::new().re("service_name", ".+"));
+        let query = selector.pipe().matches("(?i)error");
+        self.tail(&query.to_string(), limit).await
     }
 
     fn format_timestamp(&self, timestamp: &str) -> String {
@@ -223,3 +651,58 @@ impl LokiClient {
     }
 }
 
+/// Lines per level in a batch of logs, for the log panel's title.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LevelCounts {
+    pub error: usize,
+    pub warn: usize,
+    pub info: usize,
+    pub debug: usize,
+}
+
+impl LevelCounts {
+    pub fn of(logs: &[LogEntry]) -> Self {
+        let mut counts = Self::default();
+        for log in logs {
+            match log.level.as_str() {
+                "ERROR" => counts.error += 1,
+                "WARN" => counts.warn += 1,
+                "DEBUG" => counts.debug += 1,
+                _ => counts.info += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// The level a line logs at, guessed from its text.
+pub fn extract_log_level(message: &str) -> String {
+    // Check for common log level patterns
+    if message.contains("l=ERROR") || message.contains("[ERROR]") || message.contains(" ERROR ") {
+        return "ERROR".to_string();
+    }
+    if message.contains("l=WARN") || message.contains("[WARN]") || message.contains(" WARN ") {
+        return "WARN".to_string();
+    }
+    if message.contains("l=INFO") || message.contains("[INFO]") || message.contains(" INFO ") {
+        return "INFO".to_string();
+    }
+    if message.contains("l=DEBUG") || message.contains("[DEBUG]") || message.contains(" DEBUG ") {
+        return "DEBUG".to_string();
+    }
+
+    // Fallback to case-insensitive search
+    let message_lower = message.to_lowercase();
+    if message_lower.contains("error") || message_lower.contains("fatal") {
+        "ERROR".to_string()
+    } else if message_lower.contains("warn") {
+        "WARN".to_string()
+    } else if message_lower.contains("info") {
+        "INFO".to_string()
+    } else if message_lower.contains("debug") {
+        "DEBUG".to_string()
+    } else {
+        "INFO".to_string()
+    }
+}
+
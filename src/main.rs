@@ -1,35 +1,72 @@
-mod config;
-mod loki;
-mod prometheus;
-mod ui;
+mod cli;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Local;
+use clap::Parser;
+use cli::{Cli, Command};
 use clipboard::ClipboardProvider;
 use clipboard::ClipboardContext;
-use config::Settings;
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use loki::LokiClient;
-use prometheus::PrometheusClient;
-use ratatui::{
-    backend::{Backend, CrosstermBackend},
-    Terminal,
-};
+use rustdash::admin::{self, AdminAction, AdminMenu};
+use rustdash::audit::{AuditEntry, AuditLog};
+use rustdash::breaker::CircuitBreaker;
+use rustdash::bundle;
+use rustdash::config::Settings;
+use rustdash::exchanges::{DebugPopup, ExchangeLog};
+use rustdash::fetch::{self, RefreshScope};
+use rustdash::frontend::{tui_output, CrosstermFrontend, Frontend};
+use rustdash::goto::{self, GotoPrompt};
+use rustdash::guard::SlowQueryGuard;
+use rustdash::{bench, http, mock::MockBackend, report, summary};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use rustdash::loki::LokiClient;
+use rustdash::history::MetricHistory;
+use rustdash::ignore::IgnoreList;
+use rustdash::inspect::{self, Inspection};
+use rustdash::log_actions::{self, LogAction, LogActionMenu, LogFilter};
+use rustdash::log_files::LogFiles;
+use rustdash::log_overlay::MAX_LOG_QUERIES;
+use rustdash::log_query::LogQueryBar;
+use rustdash::metrics_export::{ExportFormat, MetricsExport};
+use rustdash::modal::ModalKind;
+use rustdash::notes::{self, Note, NotesPad};
+use rustdash::limiter::QueryLimiter;
+use rustdash::palette::{self, Palette, Target};
+use rustdash::persist::PersistedState;
+use rustdash::ping::{self, PingTarget};
+use rustdash::prometheus::PrometheusClient;
+use rustdash::query_editor::{self, EditorRequest, QueryEditor};
+use rustdash::query_stats::QueryStats;
+use rustdash::saved_views::{self, SavedView, SavedViewsPopup};
+use rustdash::share::{self, ShareServer};
+use rustdash::silence::SilenceAlarm;
+use rustdash::slo::{Slo, SloConfig};
+use rustdash::table_text::{self, TableFormat};
+use rustdash::status_pages::{self, DependencyStatus, StatusPageConfig};
+use rustdash::system::{self, SystemSampler, SystemView};
+use rustdash::synthetic::{self, CheckConfig, SyntheticCheck};
+use rustdash::templates::UriTemplates;
+use rustdash::text::LineInput;
+use rustdash::triage::{Alert, TriagePopup, TriageState};
+use rustdash::tunnel::SshTunnel;
+use rustdash::uri_errors;
+use rustdash::variables::{Variable, VariablePicker};
 use std::{
-    io::{self, stdout, Write},
+    io::{self, stdout, IsTerminal, Write},
+    process::Stdio,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tokio::{sync::Mutex, time, sync::mpsc};
-use ui::{draw_ui, ActivePanel, AppState};
+use tokio::{sync::Mutex, time, sync::mpsc, sync::watch};
+use rustdash::ui::{format_time, metrics_columns, panels_page_line_count, panels_page_title_line, AboutBackends, ActivePanel, AppState, AuditPopup, PanelsPage, QueryStatsPopup, ServiceMap, UriDrillIn};
 
-fn prompt_for_input(prompt: &str, default: &str) -> String {
-    print!("{} [default: {}]: ", prompt, default);
-    io::stdout().flush().unwrap();
+// Counts allocations for --bench-render, in builds with the bench-alloc feature
+#[cfg(feature = "bench-alloc")]
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
+
+fn prompt_for_input(out: &mut dyn Write, prompt: &str, default: &str) -> String {
+    write!(out, "{} [default: {}]: ", prompt, default).unwrap();
+    out.flush().unwrap();
     
     let mut input = String::new();
     io::stdin().read_line(&mut input).unwrap();
@@ -42,54 +79,452 @@ fn prompt_for_input(prompt: &str, default: &str) -> String {
     }
 }
 
-fn get_configuration() -> Settings {
-    println!("=== RustDash Configuration ===");
-    println!("Press Enter to use default values.\n");
+fn get_configuration(mut settings: Settings, saved: &PersistedState) -> Settings {
+    let out = &mut *tui_output();
+    writeln!(out, "=== RustDash Configuration ===").unwrap();
+    writeln!(out, "Press Enter to use default values.\n").unwrap();
     
-    let prometheus_url = prompt_for_input(
-        "Enter Prometheus URL",
-        "http://localhost:9090"
-    );
+    // Offer the endpoints from the previous session as defaults,
+    // falling back to the config file values. "none" skips a backend.
+    if settings.prometheus.enabled {
+        let prometheus_url = prompt_for_input(
+            out,
+            "Enter Prometheus URL (or 'none')",
+            saved.prometheus_url.as_deref().unwrap_or(&settings.prometheus.base_url)
+        );
+        settings.prometheus.enabled = !prometheus_url.eq_ignore_ascii_case("none");
+        settings.prometheus.base_url = prometheus_url;
+    }
     
-    let loki_url = prompt_for_input(
-        "Enter Loki URL",
-        "http://localhost:3100"
-    );
+    if settings.loki.enabled {
+        let loki_url = prompt_for_input(
+            out,
+            "Enter Loki URL (or 'none')",
+            saved.loki_url.as_deref().unwrap_or(&settings.loki.base_url)
+        );
+        settings.loki.enabled = !loki_url.eq_ignore_ascii_case("none");
+        settings.loki.base_url = loki_url;
+    }
     
-    println!("\nConfiguration:");
-    println!("  Prometheus: {}", prometheus_url);
-    println!("  Loki: {}", loki_url);
-    println!("\nStarting dashboard...\n");
+    let describe = |enabled: bool, url: &str| if enabled { url.to_string() } else { "disabled".to_string() };
+    writeln!(out, "\nConfiguration:").unwrap();
+    writeln!(out, "  Prometheus: {}", describe(settings.prometheus.enabled, &settings.prometheus.base_url)).unwrap();
+    writeln!(out, "  Loki: {}", describe(settings.loki.enabled, &settings.loki.base_url)).unwrap();
+    writeln!(out, "\nStarting dashboard...\n").unwrap();
     
-    // Create settings with the provided values
-    let mut settings = Settings::default();
-    settings.prometheus.base_url = prometheus_url;
-    settings.loki.base_url = loki_url;
     settings
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
+    let cli = Cli::parse();
+
+    if cli.bench_render {
+        print!("{}", bench::run_render_bench(100_000, 500, cli.bench_frames)?);
+        return Ok(());
+    }
+
+    match &cli.command {
+        Some(Command::Attach { address }) => return attach(address).await,
+        Some(Command::ExportProfile { path }) => {
+            println!("{}", bundle::export_profile(path)?);
+            return Ok(());
+        }
+        Some(Command::ImportProfile { path, yes }) => {
+            println!("{}", bundle::import_profile(path, *yes)?);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let demo = cli.demo || matches!(cli.command, Some(Command::Demo));
 
-    // Get configuration from user input
-    let settings = get_configuration();
+    // Restore the previous session's UI state, if any. Demo sessions neither
+    // restore nor save it, so the mock's ephemeral URL isn't remembered.
+    let mut saved_state = if demo { PersistedState::default() } else { PersistedState::load() };
 
-    let prometheus_client = PrometheusClient::new(settings.prometheus.base_url.clone());
-    let loki_client = LokiClient::new(settings.loki.base_url.clone());
+    let mut base_settings = Settings::load()?;
+    if let Some(profile) = &cli.profile {
+        base_settings.apply_profile(profile)?;
+        // The profile's endpoints take precedence over the last session's
+        saved_state.forget_endpoints();
+    }
+
+    // In demo mode both APIs are served by an in-process mock
+    let _mock_backend;
+    let settings = if demo {
+        let backend = MockBackend::start().await?;
+        let mut settings = base_settings;
+        settings.prometheus.base_url = backend.base_url.clone();
+        settings.loki.base_url = backend.base_url.clone();
+        settings.prometheus.ssh_host = None;
+        settings.loki.ssh_host = None;
+        if settings.panels.is_empty() {
+            // Show off the presets the mock has data for
+            settings.panels = MockBackend::demo_panels();
+        }
+        if settings.status_pages.is_empty() {
+            // The mock also serves a status page with an incident going on
+            settings.status_pages = vec![StatusPageConfig { name: "Payments".to_string(), url: backend.base_url.clone() }];
+        }
+        if settings.synthetic_checks.is_empty() {
+            settings.synthetic_checks = vec![CheckConfig {
+                name: "Prometheus API".to_string(),
+                url: format!("{}/api/v1/status/buildinfo", backend.base_url),
+                interval_seconds: 10,
+                max_latency_ms: Some(500),
+                body_regex: Some(r#""version""#.to_string()),
+                ..CheckConfig::default()
+            }];
+        }
+        if settings.slos.is_empty() {
+            // The mock's checkout errors come in bursts that set the alerts off
+            settings.slos = vec![SloConfig {
+                name: "Checkout availability".to_string(),
+                objective: 99.9,
+                errors: "checkout_requests_failed_total".to_string(),
+                total: "checkout_requests_total".to_string(),
+            }];
+        }
+        _mock_backend = backend;
+        settings
+    } else {
+        // Get configuration from the config file and user input
+        get_configuration(base_settings, &saved_state)
+    };
 
-    let mut initial_state = AppState::default();
-    initial_state.prometheus_url = settings.prometheus.base_url.clone();
-    initial_state.loki_url = settings.loki.base_url.clone();
+    if !settings.prometheus.enabled && !settings.loki.enabled && settings.log_files.paths.is_empty() {
+        anyhow::bail!("Both Prometheus and Loki are disabled and no [log_files] are set; enable at least one");
+    }
+    settings.ping.validate()?;
+    if settings.log_queries.len() > MAX_LOG_QUERIES {
+        anyhow::bail!(
+            "{} [[log_queries]] are configured; at most {} can be overlaid",
+            settings.log_queries.len(),
+            MAX_LOG_QUERIES
+        );
+    }
+
+    // Endpoints behind an SSH host are queried through a local tunnel; the
+    // configured URLs are still what the UI shows and the session saves
+    let prometheus_tunnel = match &settings.prometheus.ssh_host {
+        Some(host) if settings.prometheus.enabled => {
+            Some(SshTunnel::open(&settings.prometheus.base_url, host, settings.prometheus.remote_port).await?)
+        }
+        _ => None,
+    };
+    let loki_tunnel = match &settings.loki.ssh_host {
+        Some(host) if settings.loki.enabled => {
+            Some(SshTunnel::open(&settings.loki.base_url, host, settings.loki.remote_port).await?)
+        }
+        _ => None,
+    };
+
+    // Both clients record their query timings where the UI can list them,
+    // and share the guard that reduces queries that keep being slow and the
+    // breaker that cools down endpoints that keep failing
+    let query_stats = Arc::new(QueryStats::default());
+    let exchanges = Arc::new(ExchangeLog::default());
+    let query_guard = Arc::new(SlowQueryGuard::new(settings.slow_query_guard.clone()));
+    let circuit_breaker = Arc::new(CircuitBreaker::new(settings.http.breaker.clone()));
+    
+    // A disabled backend gets no client, so nothing is ever fetched from it
+    let prometheus_client = if settings.prometheus.enabled {
+        Some(PrometheusClient::new(
+            prometheus_tunnel
+                .as_ref()
+                .map_or_else(|| settings.prometheus.base_url.clone(), |t| t.local_url.clone()),
+            http::build_client(
+                &settings.http,
+                settings.prometheus.timeout_seconds,
+                http::auth_headers(&settings.prometheus.auth).context("Prometheus auth")?,
+                settings.prometheus.proxy.as_deref(),
+            )?,
+        )
+        .with_limiter(QueryLimiter::new(
+            settings.prometheus.max_concurrent_queries,
+            settings.prometheus.max_queries_per_second,
+        ))
+        .with_uri_templates(UriTemplates::new(&settings.uri_templates)?)
+        .with_bandwidth(settings.bandwidth.clone())
+        .with_query_stats(query_stats.clone())
+        .with_exchange_log(exchanges.clone())
+        .with_guard(query_guard.clone())
+        .with_breaker(circuit_breaker.clone()))
+    } else {
+        None
+    };
+    let loki_client = if settings.loki.enabled {
+        let mut loki_headers = http::loki_headers(settings.loki.tenant_id.as_deref())?;
+        loki_headers.extend(http::auth_headers(&settings.loki.auth).context("Loki auth")?);
+        Some(LokiClient::new(
+            loki_tunnel
+                .as_ref()
+                .map_or_else(|| settings.loki.base_url.clone(), |t| t.local_url.clone()),
+            http::build_client(
+                &settings.http,
+                settings.loki.timeout_seconds,
+                loki_headers,
+                settings.loki.proxy.as_deref(),
+            )?,
+        )
+        .with_limiter(QueryLimiter::new(
+            settings.loki.max_concurrent_queries,
+            settings.loki.max_queries_per_second,
+        ))
+        .with_query_stats(query_stats.clone())
+        .with_exchange_log(exchanges.clone())
+        .with_guard(query_guard.clone())
+        .with_breaker(circuit_breaker.clone()))
+    } else {
+        None
+    };
+
+    let mut log_jump_steps = [0; 2];
+    for (step, value) in log_jump_steps.iter_mut().zip(&settings.ui.log_jump_steps) {
+        *step = rustdash::promql::parse_duration(value)
+            .ok_or_else(|| anyhow::anyhow!("Invalid ui.log_jump_steps duration {:?}; use e.g. 30s or 5m", value))?;
+    }
+    let mut initial_state = AppState {
+        prometheus_url: settings.prometheus.base_url.clone(),
+        loki_url: settings.loki.base_url.clone(),
+        accessible: settings.ui.accessible,
+        refresh_interval_seconds: settings.ui.refresh_interval_seconds,
+        clock_skew_warn_seconds: settings.ui.clock_skew_warn_seconds,
+        show_log_ages: settings.ui.log_ages,
+        show_line_numbers: settings.ui.line_numbers,
+        log_jump_steps,
+        change_markers: settings.ui.change_markers,
+        change_threshold_pct: settings.ui.change_threshold_pct,
+        silence_alarms: settings.silence_alarms.iter().cloned().map(SilenceAlarm::new).collect(),
+        variables: settings.variables.iter().cloned().map(Variable::new).collect(),
+        log_queries: settings.log_queries.clone(),
+        log_files: LogFiles::new(&settings.log_files),
+        external_deps: settings.status_pages.iter().map(DependencyStatus::pending).collect(),
+        ping_targets: settings.ping.hosts.iter().map(|host| PingTarget::new(host)).collect(),
+        synthetic_checks: settings.synthetic_checks.iter().cloned().map(SyntheticCheck::new).collect::<Result<_>>()?,
+        slos: settings.slos.iter().cloned().map(Slo::new).collect::<Result<_>>()?,
+        prometheus_enabled: settings.prometheus.enabled,
+        loki_enabled: settings.loki.enabled,
+        history: if settings.history.persist && !demo {
+            MetricHistory::load(&settings.prometheus.base_url)
+        } else {
+            MetricHistory::new(&settings.prometheus.base_url)
+        }
+        .with_limit(settings.retention.history_limit()),
+        log_retention: settings.retention.log_limit(),
+        ignore: IgnoreList::new(&settings.ignore)?,
+        admin_actions: AdminAction::available(settings.prometheus.enabled && settings.prometheus.admin),
+        loki_admin: settings.loki.enabled && settings.loki.admin,
+        log_actions: settings.log_actions.clone(),
+        uri_truncation: settings.ui.uri_truncation,
+        layout: settings.layout.clone(),
+        uri_error_pattern: settings.uri_errors.enabled.then(|| settings.uri_errors.pattern.clone()),
+        query_stats,
+        exchanges,
+        query_guard,
+        circuit_breaker,
+        // Demo actions go to the mock server, not into the real audit log
+        audit_log: settings.audit.file().filter(|_| !demo).map(AuditLog::open).unwrap_or_default(),
+        ..AppState::default()
+    };
+    saved_state.apply(&mut initial_state);
+    if let Some(address) = &cli.share {
+        initial_state.share = Some(ShareServer::start(address).await?);
+    }
     
     let app_state = Arc::new(Mutex::new(initial_state));
 
-    setup_terminal()?;
+    // The launch backfill window, validated before the terminal is taken over
+    let since = match cli.since.as_ref().or(settings.ui.since.as_ref()) {
+        Some(value) => Some(
+            rustdash::promql::parse_duration(value)
+                .ok_or_else(|| anyhow::anyhow!("Invalid --since duration {:?}; use e.g. 30m, 2h or 1d", value))?,
+        ),
+        None => None,
+    };
 
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    let mut frontend = CrosstermFrontend::new()?;
+    frontend.enter()?;
 
     // Create channel for triggering immediate refresh
-    let (refresh_tx, mut refresh_rx) = mpsc::channel::<()>(10);
+    let (refresh_tx, mut refresh_rx) = mpsc::channel::<RefreshScope>(10);
+    
+    // Notifies the UI loop that the background task changed the state
+    let (changed_tx, changed_rx) = watch::channel(());
+
+    // Confirmed admin actions run off the UI loop, one at a time
+    let (admin_tx, mut admin_rx) = mpsc::channel::<AdminAction>(1);
+    let admin_state = app_state.clone();
+    let admin_prometheus = prometheus_client.clone();
+    let admin_loki = loki_client.clone();
+    let admin_changed = changed_tx.clone();
+    tokio::spawn(async move {
+        while let Some(action) = admin_rx.recv().await {
+            let result = admin::run(&action, admin_prometheus.as_ref(), admin_loki.as_ref()).await;
+            let mut state = admin_state.lock().await;
+            state.status = match &result {
+                Ok(message) => message.clone(),
+                Err(e) => format!("{} failed: {}", action.label(), e),
+            };
+            let entry = action.audit_entry(state.admin_target(&action)).outcome(&result);
+            state.audit(entry);
+            if let Some(menu) = state.admin_menu.as_mut() {
+                menu.running = false;
+                menu.result = Some(result.map_err(|e| e.to_string()));
+            }
+            let _ = admin_changed.send(());
+        }
+    });
+
+    // Queries and completion lookups from the PromQL editor, also off the UI loop
+    let (editor_tx, mut editor_rx) = mpsc::channel::<EditorRequest>(16);
+    if let Some(client) = prometheus_client.clone() {
+        let editor_state = app_state.clone();
+        let editor_changed = changed_tx.clone();
+        let follow_up = editor_tx.clone();
+        tokio::spawn(async move {
+            while let Some(request) = editor_rx.recv().await {
+                if let Some(lookup) = query_editor::serve(request, &client, &editor_state).await {
+                    let _ = follow_up.try_send(EditorRequest::Lookup(lookup));
+                }
+                let _ = editor_changed.send(());
+            }
+        });
+    }
+
+    // External status pages are polled on their own, slower schedule
+    if !settings.status_pages.is_empty() {
+        let client = http::build_client(&settings.http, settings.prometheus.timeout_seconds, Default::default(), None)?;
+        let pages = settings.status_pages.clone();
+        let status_state = app_state.clone();
+        let status_changed = changed_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(status_pages::POLL_SECONDS));
+            loop {
+                interval.tick().await;
+                let statuses = status_pages::check_all(&client, &pages).await;
+                status_state.lock().await.external_deps = statuses;
+                let _ = status_changed.send(());
+            }
+        });
+    }
+
+    // Each synthetic check runs on its own interval, with its own timeout
+    for (idx, config) in settings.synthetic_checks.iter().enumerate() {
+        let client = http::build_client(&settings.http, config.timeout_seconds, Default::default(), None)?;
+        let probe = SyntheticCheck::new(config.clone())?;
+        let check_state = app_state.clone();
+        let check_changed = changed_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(probe.config.interval_seconds.max(1)));
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                let run = synthetic::run(&client, &probe).await;
+                let mut state = check_state.lock().await;
+                let started_failing = state.synthetic_checks[idx].record(run);
+                let check = &state.synthetic_checks[idx];
+                // Unless this check's alerts are ignored for the session
+                if started_failing && state.triage.nags(&Alert::Failing(check.config.name.clone()), check.failing_since.unwrap_or_default()) {
+                    state.bell = true;
+                }
+                drop(state);
+                let _ = check_changed.send(());
+            }
+        });
+    }
+
+    // Every host is pinged at once, each round on the same interval
+    if !settings.ping.hosts.is_empty() {
+        let config = settings.ping.clone();
+        let ping_state = app_state.clone();
+        let ping_changed = changed_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(config.interval_seconds.max(1)));
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                let pings: Vec<_> = config
+                    .hosts
+                    .iter()
+                    .map(|host| {
+                        let (host, timeout) = (host.clone(), config.timeout_seconds);
+                        tokio::spawn(async move { ping::ping(&host, timeout).await })
+                    })
+                    .collect();
+                let mut rtts = Vec::with_capacity(pings.len());
+                for handle in pings {
+                    rtts.push(handle.await.ok().flatten());
+                }
+                let mut state = ping_state.lock().await;
+                for (target, rtt) in state.ping_targets.iter_mut().zip(rtts) {
+                    target.record(rtt);
+                }
+                drop(state);
+                let _ = ping_changed.send(());
+            }
+        });
+    }
+
+    // The host is sampled only while the local system view is open
+    let system_state = app_state.clone();
+    let system_changed = changed_tx.clone();
+    tokio::spawn(async move {
+        let mut sampler = Some(SystemSampler::default());
+        let mut interval = time::interval(Duration::from_secs(system::SAMPLE_SECONDS));
+        interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            if system_state.lock().await.system_view.is_none() {
+                continue;
+            }
+            let Some(mut taken) = sampler.take() else { break };
+            let Ok((returned, snapshot)) = tokio::task::spawn_blocking(move || {
+                let snapshot = taken.sample();
+                (taken, snapshot)
+            })
+            .await
+            else {
+                break;
+            };
+            sampler = Some(returned);
+            if let Some(view) = system_state.lock().await.system_view.as_mut() {
+                view.snapshot = Some(snapshot);
+            }
+            let _ = system_changed.send(());
+        }
+    });
+
+    // The series behind the inspected row are looked up while inspection is on
+    if let Some(client) = prometheus_client.clone() {
+        let inspect_state = app_state.clone();
+        let inspect_changed = changed_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_millis(inspect::POLL_MILLIS));
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                let (uri, time_range) = {
+                    let mut state = inspect_state.lock().await;
+                    let (Some(uri), refreshed) = (state.inspected_uri(), state.metrics_last_success) else { continue };
+                    let time_range = state.metrics_time_range.to_prometheus_range();
+                    let Some(inspection) = state.inspection.as_mut() else { continue };
+                    if !inspection.start(&uri, refreshed, Local::now()) {
+                        continue;
+                    }
+                    (uri, time_range)
+                };
+                let _ = inspect_changed.send(());
+                let result = client.get_uri_series(&uri, &time_range).await.map_err(|e| e.to_string());
+                if let Some(inspection) = inspect_state.lock().await.inspection.as_mut() {
+                    inspection.finish(&uri, result);
+                }
+                let _ = inspect_changed.send(());
+            }
+        });
+    }
 
     let app_state_clone = app_state.clone();
     let settings_clone = settings.clone();
@@ -101,152 +536,73 @@ async fn main() -> Result<()> {
         interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
         
         let mut first_run = true;
+        if let Some(seconds) = since {
+            let _ = changed_tx.send(());
+            fetch::backfill(&app_state_clone, prometheus_client.as_ref(), loki_client.as_ref(), seconds).await;
+        }
         
         loop {
             // Wait for either interval tick or manual refresh signal
+            let mut manual = None;
             if !first_run {
                 tokio::select! {
                     _ = interval.tick() => {}
-                    _ = refresh_rx.recv() => {
-                        // Manual refresh requested, set loading state
-                        let mut state = app_state_clone.lock().await;
-                        state.metrics_loading = true;
-                        drop(state);
-                    }
+                    Some(scope) = refresh_rx.recv() => manual = Some(scope),
                 }
             } else {
                 first_run = false;
             }
             
-            // Get current time range from state
-            let time_range_str = {
-                let state = app_state_clone.lock().await;
-                state.metrics_time_range.to_prometheus_range()
-            };
-            
-            // Fetch metrics with the current time range
-            let metrics = prometheus_client.get_metrics(&time_range_str).await.ok();
-            
-            // Fetch logs directly (get all logs)
-            let all_logs = if let Ok(fetched_logs) = loki_client
-                .get_recent_logs(settings_clone.loki.log_limit)
-                .await
-            {
-                fetched_logs
-            } else {
-                Vec::new()
+            // Ticks skip a backend that is down until its retry is due; asking
+            // for a refresh retries right away
+            let scope = match manual {
+                Some(scope) => scope,
+                None => match app_state_clone.lock().await.due_scope(Local::now()) {
+                    Some(scope) => scope,
+                    None => continue,
+                },
             };
             
-            // Update state while preserving scroll position
-            let mut state = app_state_clone.lock().await;
-            state.metrics = metrics;
-            state.metrics_loading = false; // Clear loading state
-            
-            // Preserve scroll position and selection when updating logs
-            let old_scroll_offset = state.log_scroll_offset;
-            let old_selected_index = state.selected_log_index;
-            let terminal_height = state.last_terminal_height;
-            
-            // Keep track of previous state before processing
-            let old_logs = state.all_logs.clone();
-            let old_fetch_count = state.last_fetch_count;
-            
-            // Process new logs
-            let mut marked_logs = all_logs;
-            let new_count = marked_logs.len();
-            
-            // Initialize all logs as not new
-            for i in 0..new_count {
-                marked_logs[i].is_new = false;
+            fetch::refresh_scope(
+                &app_state_clone,
+                prometheus_client.as_ref(),
+                loki_client.as_ref(),
+                settings_clone.loki.log_limit,
+                &settings_clone.panels,
+                scope,
+            )
+            .await;
+            if manual.is_some() {
+                // Stop the spinner of the refresh that was asked for
+                app_state_clone.lock().await.refreshing = None;
             }
-            
-            // Handle different cases
-            if !state.has_initial_fetch {
-                // First fetch - don't highlight anything
-                state.has_initial_fetch = true;
-                state.last_fetch_count = new_count;
-                state.status = format!("Connected - Initial: {} logs", new_count);
-            } else if new_count > old_fetch_count {
-                // New logs detected! Highlight only the new ones
-                let new_log_count = new_count - old_fetch_count;
-                
-                // Mark only the NEW logs (at the end of the list)
-                for i in old_fetch_count..new_count {
-                    marked_logs[i].is_new = true;
-                }
-                
-                // Clear old highlights when we get truly new logs
-                state.last_fetch_count = new_count;
-                state.status = format!("Connected - {} new logs!", new_log_count);
-            } else {
-                // Same count - preserve existing highlights
-                let mut preserved = 0;
-                
-                // Copy highlight status from old logs if they match
-                for i in 0..new_count.min(old_logs.len()) {
-                    if i < old_logs.len() && old_logs[i].is_new {
-                        // Same position, same message = preserve highlight
-                        if marked_logs[i].message == old_logs[i].message {
-                            marked_logs[i].is_new = true;
-                            preserved += 1;
-                        }
-                    }
-                }
-                
-                state.last_fetch_count = new_count;
-                if preserved > 0 {
-                    state.status = format!("Connected - {} logs highlighted", preserved);
-                } else {
-                    state.status = "Connected".to_string();
-                }
-            }
-            
-            // Check if we had new logs
-            let had_new_logs = new_count > old_fetch_count;
-            
-            state.all_logs = marked_logs;
-            state.last_fetch = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-            
-            // Handle scrolling
-            let is_first_load = old_scroll_offset == 0 && old_selected_index.is_none() && state.logs.is_empty();
-            
-            if is_first_load && !state.all_logs.is_empty() {
-                // First load - scroll to bottom to show latest logs
-                let visible_height = state.get_visible_height(terminal_height);
-                let last_idx = state.all_logs.len().saturating_sub(1);
-                state.log_scroll_offset = last_idx.saturating_sub(visible_height - 1);
-            } else if had_new_logs && old_selected_index.is_none() {
-                // New logs arrived and user isn't selecting - auto-scroll to show them
-                let visible_height = state.get_visible_height(terminal_height);
-                if state.all_logs.len() > visible_height {
-                    state.log_scroll_offset = state.all_logs.len() - visible_height;
-                } else {
-                    state.log_scroll_offset = 0;
-                }
-            } else if old_selected_index.is_some() {
-                // User has selected something, preserve their position
-                state.log_scroll_offset = old_scroll_offset;
-                state.selected_log_index = old_selected_index;
-                
-                // Validate that the selection is still in bounds
-                if let Some(idx) = state.selected_log_index {
-                    if idx >= state.all_logs.len() {
-                        state.selected_log_index = Some(state.all_logs.len().saturating_sub(1));
-                    }
-                }
-            } else {
-                // No new logs, no selection - keep current position
-                state.log_scroll_offset = old_scroll_offset;
-            }
-            
-            // Update visible logs with the last known terminal height
-            state.update_visible_logs_with_height(terminal_height);
+            let _ = changed_tx.send(());
         }
     });
 
-    let res = run_app(&mut terminal, app_state.clone(), settings, refresh_tx).await;
+    let print_summary = cli.summary || settings.ui.summary_on_exit;
+    let persist_history = settings.history.persist;
+    let res = run_app(&mut frontend, app_state.clone(), settings, refresh_tx, admin_tx, editor_tx, changed_rx).await;
 
-    restore_terminal()?;
+    frontend.leave()?;
+
+    let state = app_state.lock().await;
+
+    // Save the working context for the next session
+    if !demo {
+        if let Err(err) = PersistedState::capture(&state).save() {
+            eprintln!("Failed to save state: {err:?}");
+        }
+        if persist_history {
+            if let Err(err) = state.history.save() {
+                eprintln!("Failed to save metric history: {err:?}");
+            }
+        }
+    }
+
+    if print_summary {
+        print!("{}", summary::build_summary(&state));
+    }
 
     if let Err(err) = res {
         eprintln!("Error: {err:?}");
@@ -255,64 +611,1020 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn setup_terminal() -> Result<()> {
-    enable_raw_mode()?;
-    execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)?;
-    Ok(())
-}
-
-fn restore_terminal() -> Result<()> {
-    disable_raw_mode()?;
-    execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+/// Follow a shared session until it ends or `q`, `ESC` or `Ctrl+C` is pressed.
+async fn attach(address: &str) -> Result<()> {
+    let address = share::address(address);
+    let mut stream = tokio::net::TcpStream::connect(&address)
+        .await
+        .with_context(|| format!("Couldn't attach to {}", address))?;
+    // The session's frames are copied to the terminal as they come
+    let mut frontend = CrosstermFrontend::new()?;
+    frontend.enter()?;
+    let detach = tokio::task::spawn_blocking(|| loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    });
+    let mut stdout = tokio::io::stdout();
+    let ended = tokio::select! {
+        _ = tokio::io::copy(&mut stream, &mut stdout) => true,
+        _ = detach => false,
+    };
+    frontend.leave()?;
+    if ended {
+        println!("The shared session at {} ended", address);
+    }
     Ok(())
 }
 
-async fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
+async fn run_app<F: Frontend>(
+    frontend: &mut F,
     app_state: Arc<Mutex<AppState>>,
     settings: Settings,
-    refresh_tx: mpsc::Sender<()>,
+    refresh_tx: mpsc::Sender<RefreshScope>,
+    admin_tx: mpsc::Sender<AdminAction>,
+    editor_tx: mpsc::Sender<EditorRequest>,
+    mut data_changed: watch::Receiver<()>,
 ) -> io::Result<()> {
-    let _prometheus_client = PrometheusClient::new(settings.prometheus.base_url.clone());
-    let _loki_client = LokiClient::new(settings.loki.base_url.clone());
-
+    let mut needs_redraw = true;
+    let mut spinning = false;
+    let mut last_draw = Instant::now();
+    let mut job_signals = job_control_signals()?;
+    let frame_interval = Duration::from_secs(1) / settings.ui.max_fps.max(1);
+    let poll_interval = Duration::from_millis(settings.ui.poll_interval_ms.max(10));
+    // Input is waited for on a blocking thread, one wait at a time, so the
+    // loop can sleep until a key, new data or the next frame, whichever is first
+    let mut input_wait: Option<tokio::task::JoinHandle<io::Result<bool>>> = None;
+    // A key the command palette runs, handled next as if typed
+    let mut replayed: Option<KeyEvent> = None;
+    
     loop {
         // Get current terminal size
-        let terminal_size = terminal.size()?;
+        let terminal_size = frontend.size()?;
+        
+        if data_changed.has_changed().unwrap_or(false) {
+            data_changed.mark_unchanged();
+            needs_redraw = true;
+        }
         
-        // Update terminal height in state for background task
-        {
+        // Only redraw on input or new data, plus once a second so clocks and ages
+        // tick, and every frame while a fetch spinner turns, never faster than max_fps
+        let tick = if spinning { Duration::from_millis(100) } else { Duration::from_secs(1) }.max(frame_interval);
+        let next_frame = if needs_redraw { frame_interval } else { tick };
+        if last_draw.elapsed() >= next_frame {
             let mut state = app_state.lock().await;
+            // Update terminal size in state for background task
             state.last_terminal_height = terminal_size.height;
+            state.last_terminal_width = terminal_size.width;
+            let frame = frontend.draw(&state)?;
+            if let Some(share) = &state.share {
+                share.publish(frame.buffer);
+            }
+            spinning = state.is_fetching();
+            if std::mem::take(&mut state.bell) {
+                frontend.bell()?;
+            }
+            drop(state);
+            
+            needs_redraw = false;
+            last_draw = Instant::now();
         }
-        
-        let state = app_state.lock().await;
-        terminal.draw(|f| draw_ui(f, &state))?;
-        drop(state);
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
+        let next_frame = if needs_redraw { frame_interval } else { tick };
+        let input = input_wait.get_or_insert_with(|| tokio::task::spawn_blocking(move || event::poll(poll_interval)));
+        let wake = if replayed.is_some() {
+            Wake::Frame
+        } else {
+            tokio::select! {
+                ready = input => Wake::Input(ready.map_err(io::Error::other)??),
+                Ok(()) = data_changed.changed() => Wake::Data,
+                Some(signal) = job_signals.recv() => Wake::Signal(signal),
+                _ = time::sleep(next_frame.saturating_sub(last_draw.elapsed())) => Wake::Frame,
+            }
+        };
+        let input_ready = match wake {
+            Wake::Input(ready) => {
+                input_wait = None;
+                ready
+            }
+            Wake::Data => {
+                needs_redraw = true;
+                false
+            }
+            Wake::Signal(signal) => {
+                // Stopped from outside, or continued after a stop we didn't
+                // see coming; the input wait mustn't outlive the terminal
+                if let Some(wait) = input_wait.take() {
+                    let _ = wait.await;
+                }
+                match signal {
+                    JobSignal::Stop => suspend(frontend)?,
+                    JobSignal::Continue => frontend.resume()?,
+                }
+                needs_redraw = true;
+                false
+            }
+            Wake::Frame => false,
+        };
+
+        if input_ready || replayed.is_some() {
+            // Any input (keys, resize) may change what's on screen
+            needs_redraw = true;
+            let event = match replayed.take() {
+                Some(key) => Event::Key(key),
+                None => event::read()?,
+            };
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
+                    // Raw mode delivers Ctrl+Z as a key rather than SIGTSTP
+                    if cfg!(unix) && key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                        suspend(frontend)?;
+                        continue;
+                    }
                     let mut state = app_state.lock().await;
+                    // Keys go to the overlay opened last
+                    let focused = state.focused_modal();
+
+                    // The PromQL editor takes every key, letters included
+                    if let Some(editor) = state.query_editor.as_mut().filter(|_| focused == Some(ModalKind::QueryEditor)) {
+                        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+                        match key.code {
+                            KeyCode::Esc if editor.completing() => editor.dismissed = true,
+                            KeyCode::Esc => {
+                                state.query_editor = None;
+                                state.status = "Closed PromQL editor".to_string();
+                                continue;
+                            }
+                            KeyCode::Enter if !editor.running && !editor.input.text.trim().is_empty() => {
+                                editor.running = true;
+                                editor.dismissed = true;
+                                let _ = editor_tx.try_send(EditorRequest::Run(editor.input.text.clone()));
+                            }
+                            KeyCode::Tab if editor.completing() => {
+                                if let Some(lookup) = editor.accept() {
+                                    let _ = editor_tx.try_send(EditorRequest::Lookup(lookup));
+                                }
+                            }
+                            KeyCode::Up if editor.completing() => editor.select(-1),
+                            KeyCode::Down if editor.completing() => editor.select(1),
+                            KeyCode::Up => editor.scroll(-1),
+                            KeyCode::Down => editor.scroll(1),
+                            KeyCode::PageUp => editor.scroll(-10),
+                            KeyCode::PageDown => editor.scroll(10),
+                            KeyCode::Char(' ') if control => editor.dismissed = false,
+                            KeyCode::Char('u') if control => editor.input.clear_to_start(),
+                            KeyCode::Char(c) if !control => editor.insert(c),
+                            KeyCode::Backspace => editor.backspace(),
+                            KeyCode::Delete => editor.delete(),
+                            KeyCode::Left => editor.input.move_left(),
+                            KeyCode::Right => editor.input.move_right(),
+                            KeyCode::Home => editor.input.home(),
+                            KeyCode::End => editor.input.end(),
+                            _ => {}
+                        }
+                        if let Some(lookup) = editor.update_completions() {
+                            let _ = editor_tx.try_send(EditorRequest::Lookup(lookup));
+                        }
+                        continue;
+                    }
+
+                    // And the notes pad
+                    if let Some(mut pad) = state.notes_pad.take_if(|_| focused == Some(ModalKind::NotesPad)) {
+                        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+                        match key.code {
+                            KeyCode::Esc => {
+                                state.status = "Closed notes".to_string();
+                                continue;
+                            }
+                            KeyCode::Enter if !pad.input.text.trim().is_empty() => {
+                                let text = std::mem::take(&mut pad.input).text.trim().to_string();
+                                let note = Note { at: Local::now(), text, context: notes::context(&state) };
+                                state.notes.push(note);
+                                pad.scroll_offset = 0;
+                                state.status = format!("{} notes", state.notes.len());
+                            }
+                            KeyCode::Char('s') if control => {
+                                state.status = match std::env::current_dir().and_then(|dir| notes::export(&state.notes, &dir)) {
+                                    Ok(path) => format!("Notes exported to {}", path.display()),
+                                    Err(e) => format!("Couldn't export the notes: {}", e),
+                                };
+                            }
+                            KeyCode::Char('u') if control => pad.input.clear_to_start(),
+                            KeyCode::Char(c) if !control => pad.input.insert(c),
+                            KeyCode::Backspace => {
+                                pad.input.backspace();
+                            }
+                            KeyCode::Delete => {
+                                pad.input.delete();
+                            }
+                            KeyCode::Left => pad.input.move_left(),
+                            KeyCode::Right => pad.input.move_right(),
+                            KeyCode::Home => pad.input.home(),
+                            KeyCode::End => pad.input.end(),
+                            KeyCode::Up => pad.scroll_offset = (pad.scroll_offset + 1).min(state.notes.len().saturating_sub(1)),
+                            KeyCode::Down => pad.scroll_offset = pad.scroll_offset.saturating_sub(1),
+                            _ => {}
+                        }
+                        state.notes_pad = Some(pad);
+                        continue;
+                    }
+
+                    // So does the LogQL query bar
+                    if let Some(bar) = state.log_query_bar.as_mut().filter(|_| focused == Some(ModalKind::LogQueryBar)) {
+                        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+                        match key.code {
+                            KeyCode::Esc => {
+                                state.log_query_bar = None;
+                                state.status = "Closed query bar".to_string();
+                            }
+                            KeyCode::Enter if bar.input.text.trim().is_empty() => {
+                                state.log_query_bar = None;
+                                if state.log_filter.query.is_some() {
+                                    state.status = "Query cleared".to_string();
+                                    state.set_log_filter(LogFilter::default());
+                                    let _ = refresh_tx.send(RefreshScope::Logs).await;
+                                }
+                            }
+                            // Tried against Loki by the next logs refresh
+                            KeyCode::Enter if bar.submit().is_some() => {
+                                let _ = refresh_tx.send(RefreshScope::Logs).await;
+                            }
+                            KeyCode::Char('u') if control => {
+                                bar.input.clear_to_start();
+                                bar.edited();
+                            }
+                            KeyCode::Char(c) if !control => {
+                                bar.input.insert(c);
+                                bar.edited();
+                            }
+                            KeyCode::Backspace if bar.input.backspace() => bar.edited(),
+                            KeyCode::Delete if bar.input.delete() => bar.edited(),
+                            KeyCode::Left => bar.input.move_left(),
+                            KeyCode::Right => bar.input.move_right(),
+                            KeyCode::Home => bar.input.home(),
+                            KeyCode::End => bar.input.end(),
+                            _ => {}
+                        }
+                        continue;
+                    }
+
+                    // And the go-to prompt
+                    if let Some(prompt) = state.goto_prompt.as_mut().filter(|_| focused == Some(ModalKind::Goto)) {
+                        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+                        match key.code {
+                            KeyCode::Esc => {
+                                state.goto_prompt = None;
+                                state.status = "Closed go to".to_string();
+                            }
+                            KeyCode::Enter => {
+                                let jump = goto::parse(&prompt.input.text);
+                                match jump.and_then(|target| state.goto_log(target, terminal_size.height)) {
+                                    Ok(status) => {
+                                        state.goto_prompt = None;
+                                        state.status = status;
+                                    }
+                                    Err(e) => {
+                                        if let Some(prompt) = state.goto_prompt.as_mut() {
+                                            prompt.error = Some(e);
+                                        }
+                                    }
+                                }
+                            }
+                            KeyCode::Char('u') if control => prompt.input.clear_to_start(),
+                            KeyCode::Char(c) if !control => {
+                                prompt.input.insert(c);
+                                prompt.error = None;
+                            }
+                            KeyCode::Backspace => {
+                                prompt.input.backspace();
+                            }
+                            KeyCode::Delete => {
+                                prompt.input.delete();
+                            }
+                            KeyCode::Left => prompt.input.move_left(),
+                            KeyCode::Right => prompt.input.move_right(),
+                            KeyCode::Home => prompt.input.home(),
+                            KeyCode::End => prompt.input.end(),
+                            _ => {}
+                        }
+                        continue;
+                    }
+
+                    // The command palette runs its choice from the panels,
+                    // so opening it closes other views
+                    let control = key.modifiers.contains(KeyModifiers::CONTROL);
+                    let admin_busy = state.admin_menu.as_ref().is_some_and(|menu| menu.confirming || menu.running);
+                    if key.code == KeyCode::Char('p') && control && state.palette.is_none() && !admin_busy {
+                        state.close_views();
+                        state.palette = Some(Palette::new(palette::entries(&state)));
+                        state.status = "Command palette".to_string();
+                        continue;
+                    }
+                    if let Some(palette) = state.palette.as_mut().filter(|_| focused == Some(ModalKind::Palette)) {
+                        match key.code {
+                            KeyCode::Esc => {
+                                state.palette = None;
+                                state.status = "Closed command palette".to_string();
+                            }
+                            KeyCode::Enter => {
+                                let target = palette.chosen().map(|entry| entry.target.clone());
+                                state.palette = None;
+                                match target {
+                                    Some(Target::Key(c)) => replayed = Some(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)),
+                                    Some(Target::Panel(idx)) => {
+                                        let mut page = PanelsPage { selected: idx, ..PanelsPage::default() };
+                                        page.scroll_offset = panels_page_title_line(&state, &page, idx).unwrap_or(0);
+                                        state.status = format!("Panel {}", state.panels[idx].title);
+                                        state.panels_page = Some(page);
+                                    }
+                                    Some(Target::LogQuery(idx)) => {
+                                        let query = state.log_queries[idx].clone();
+                                        state.status = format!("Showing {}", query.name);
+                                        state.set_log_filter(LogFilter::query(&query.query));
+                                        let _ = refresh_tx.send(RefreshScope::Logs).await;
+                                    }
+                                    Some(Target::Uri(idx)) => {
+                                        let total = state.metrics.as_ref().map_or(0, |m| m.uri_metrics.len());
+                                        if idx < total {
+                                            state.active_panel = ActivePanel::Metrics;
+                                            select_metric_row(&mut state, idx, total, terminal_size.height);
+                                            replayed = Some(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE));
+                                        }
+                                    }
+                                    None => state.status = "Nothing to run".to_string(),
+                                }
+                            }
+                            KeyCode::Up => palette.select(-1),
+                            KeyCode::Down => palette.select(1),
+                            KeyCode::Char('p') if control => palette.select(-1),
+                            KeyCode::Char('n') if control => palette.select(1),
+                            KeyCode::Char('u') if control => {
+                                palette.input.clear_to_start();
+                                palette.update();
+                            }
+                            KeyCode::Char(c) if !control => {
+                                palette.input.insert(c);
+                                palette.update();
+                            }
+                            KeyCode::Backspace if palette.input.backspace() => palette.update(),
+                            KeyCode::Delete if palette.input.delete() => palette.update(),
+                            KeyCode::Left => palette.input.move_left(),
+                            KeyCode::Right => palette.input.move_right(),
+                            KeyCode::Home => palette.input.home(),
+                            KeyCode::End => palette.input.end(),
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    // The admin menu is modal; a pending confirmation only takes y/n
+                    if let Some(menu) = state.admin_menu.as_mut().filter(|_| focused == Some(ModalKind::AdminMenu)) {
+                        if menu.confirming {
+                            match key.code {
+                                KeyCode::Char('y') => {
+                                    menu.confirming = false;
+                                    if let Some(action) = menu.selected_action().cloned() {
+                                        menu.running = true;
+                                        menu.result = None;
+                                        state.status = format!("Running {}", action.label());
+                                        let _ = admin_tx.send(action).await;
+                                    }
+                                }
+                                KeyCode::Char('n') | KeyCode::Esc => {
+                                    menu.confirming = false;
+                                    state.status = "Cancelled".to_string();
+                                }
+                                _ => {}
+                            }
+                            continue;
+                        }
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => menu.selected = menu.selected.saturating_sub(1),
+                            KeyCode::Down => menu.selected = (menu.selected + 1).min(menu.actions.len().saturating_sub(1)),
+                            KeyCode::Enter if !menu.running => menu.confirming = true,
+                            KeyCode::Char('A') | KeyCode::Esc => {
+                                state.admin_menu = None;
+                                state.status = "Closed admin menu".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    // So is the log line menu; running an action closes it
+                    if let Some(menu) = state.log_action_menu.as_mut().filter(|_| focused == Some(ModalKind::LogActionMenu)) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => menu.selected = menu.selected.saturating_sub(1),
+                            KeyCode::Down => menu.selected = (menu.selected + 1).min(menu.actions.len().saturating_sub(1)),
+                            KeyCode::Enter => {
+                                let (index, action) = (menu.log_index, menu.selected_action().cloned());
+                                state.log_action_menu = None;
+                                if let Some(LogAction::RunCommand { name, command, whole_buffer }) = &action {
+                                    let lines: Vec<&str> = if *whole_buffer {
+                                        state.all_logs.iter().map(|log| log.message.as_str()).collect()
+                                    } else {
+                                        state.all_logs.get(index).map(|log| log.message.as_str()).into_iter().collect()
+                                    };
+                                    let input = lines.join("\n") + "\n";
+                                    // Refreshes carry on in the background meanwhile
+                                    drop(state);
+                                    let result = run_suspended(frontend, command, &input)?;
+                                    app_state.lock().await.status = match result {
+                                        Ok(()) => format!("Back from {}", name),
+                                        Err(e) => format!("{:#}", e),
+                                    };
+                                    continue;
+                                }
+                                if let Some(action) = action {
+                                    if let Some(scope) = run_log_action(&mut state, index, action) {
+                                        let _ = refresh_tx.send(scope).await;
+                                    }
+                                }
+                            }
+                            KeyCode::Char('a') | KeyCode::Esc => {
+                                state.log_action_menu = None;
+                                state.status = "Closed log line menu".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    // The diff popup follows the selection until closed
+                    if state.log_diff && focused == Some(ModalKind::LogDiff) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => {
+                                let selected = state.selected_log_index.unwrap_or(0).saturating_sub(1);
+                                select_log_line(&mut state, selected, terminal_size.height);
+                            }
+                            KeyCode::Down => {
+                                let last = state.all_logs.len().saturating_sub(1);
+                                let selected = (state.selected_log_index.unwrap_or(0) + 1).min(last);
+                                select_log_line(&mut state, selected, terminal_size.height);
+                            }
+                            KeyCode::Char('d') | KeyCode::Esc => {
+                                state.log_diff = false;
+                                state.status = "Closed diff".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    let query_count = state.query_stats.len();
+                    if let Some(popup) = state.query_stats_popup.as_mut().filter(|_| focused == Some(ModalKind::QueryStats)) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => popup.scroll_offset = popup.scroll_offset.saturating_sub(1),
+                            KeyCode::Down => {
+                                popup.scroll_offset = (popup.scroll_offset + 1).min(query_count.saturating_sub(1));
+                            }
+                            KeyCode::Char('S') | KeyCode::Esc => {
+                                state.query_stats_popup = None;
+                                state.status = "Closed query timings".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    if let Some(mut popup) = state.debug_popup.take_if(|_| focused == Some(ModalKind::Debug)) {
+                        let exchange = popup.queries.get(popup.selected).and_then(|(backend, query)| state.exchanges.get(*backend, query));
+                        let body_lines = exchange.as_ref().map_or(0, |exchange| exchange.pretty_body().lines().count());
+                        let page = usize::from(terminal_size.height.saturating_sub(10)).max(1);
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Left if popup.selected > 0 => {
+                                popup.selected -= 1;
+                                popup.scroll_offset = 0;
+                            }
+                            KeyCode::Right if popup.selected + 1 < popup.queries.len() => {
+                                popup.selected += 1;
+                                popup.scroll_offset = 0;
+                            }
+                            KeyCode::Up => popup.scroll_offset = popup.scroll_offset.saturating_sub(1),
+                            KeyCode::Down => popup.scroll_offset = (popup.scroll_offset + 1).min(body_lines.saturating_sub(1)),
+                            KeyCode::PageUp => popup.scroll_offset = popup.scroll_offset.saturating_sub(page),
+                            KeyCode::PageDown => popup.scroll_offset = (popup.scroll_offset + page).min(body_lines.saturating_sub(1)),
+                            KeyCode::Home => popup.scroll_offset = 0,
+                            KeyCode::Char('y') => {
+                                if let Some(exchange) = &exchange {
+                                    state.status = copy_to_clipboard(exchange.to_text());
+                                }
+                            }
+                            KeyCode::Char('D') | KeyCode::Esc => {
+                                state.status = "Closed requests".to_string();
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        state.debug_popup = Some(popup);
+                        continue;
+                    }
+                    
+                    if let Some(mut picker) = state.variable_picker.take_if(|_| focused == Some(ModalKind::Variables)) {
+                        let choices = state.variables.get(picker.variable).map_or(0, |variable| variable.choices().len());
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Left if picker.variable > 0 => picker = VariablePicker::open(&state.variables, picker.variable - 1),
+                            KeyCode::Right if picker.variable + 1 < state.variables.len() => {
+                                picker = VariablePicker::open(&state.variables, picker.variable + 1)
+                            }
+                            KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+                            KeyCode::Down if picker.selected + 1 < choices => picker.selected += 1,
+                            KeyCode::Enter => {
+                                if let Some(variable) = state.variables.get_mut(picker.variable) {
+                                    if let Some(choice) = variable.choices().get(picker.selected) {
+                                        let choice = choice.map(str::to_string);
+                                        variable.selected = choice;
+                                        state.status = format!("${} = {}", variable.config.name, variable.display());
+                                        let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                                    }
+                                }
+                            }
+                            KeyCode::Char('$') | KeyCode::Esc => {
+                                state.status = "Closed variables".to_string();
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        state.variable_picker = Some(picker);
+                        continue;
+                    }
+
+                    if let Some(mut popup) = state.saved_views_popup.take_if(|_| focused == Some(ModalKind::SavedViews)) {
+                        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+                        // Typing a name to save the current view under
+                        if let Some(input) = popup.naming.as_mut() {
+                            match key.code {
+                                KeyCode::Esc => popup.naming = None,
+                                KeyCode::Enter if !input.text.trim().is_empty() => {
+                                    let name = input.text.trim().to_string();
+                                    let view = SavedView::capture(&name, &state);
+                                    popup.selected = saved_views::save(&mut state.saved_views, view);
+                                    popup.naming = None;
+                                    state.status = format!("Saved view {}", name);
+                                }
+                                KeyCode::Char('u') if control => input.clear_to_start(),
+                                KeyCode::Char(c) if !control => input.insert(c),
+                                KeyCode::Backspace => {
+                                    input.backspace();
+                                }
+                                KeyCode::Delete => {
+                                    input.delete();
+                                }
+                                KeyCode::Left => input.move_left(),
+                                KeyCode::Right => input.move_right(),
+                                KeyCode::Home => input.home(),
+                                KeyCode::End => input.end(),
+                                _ => {}
+                            }
+                            state.saved_views_popup = Some(popup);
+                            continue;
+                        }
+                        let count = state.saved_views.len();
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => popup.selected = popup.selected.saturating_sub(1),
+                            KeyCode::Down if popup.selected + 1 < count => popup.selected += 1,
+                            KeyCode::Char('s') => popup.naming = Some(LineInput::default()),
+                            KeyCode::Char('x') | KeyCode::Delete if popup.selected < count => {
+                                let removed = state.saved_views.remove(popup.selected);
+                                popup.selected = popup.selected.min(count.saturating_sub(2));
+                                state.status = format!("Deleted view {}", removed.name);
+                            }
+                            KeyCode::Enter if popup.selected < count => {
+                                let view = state.saved_views[popup.selected].clone();
+                                if let Some(scope) = view.apply(&mut state) {
+                                    let _ = refresh_tx.send(scope).await;
+                                }
+                                state.status = format!("Showing view {}", view.name);
+                                continue;
+                            }
+                            KeyCode::Char('V') | KeyCode::Esc => {
+                                state.status = "Closed saved views".to_string();
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        state.saved_views_popup = Some(popup);
+                        continue;
+                    }
+
+                    let audit_count = state.audit_log.len();
+                    if let Some(popup) = state.audit_popup.as_mut().filter(|_| focused == Some(ModalKind::Audit)) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => popup.scroll_offset = popup.scroll_offset.saturating_sub(1),
+                            KeyCode::Down => {
+                                popup.scroll_offset = (popup.scroll_offset + 1).min(audit_count.saturating_sub(1));
+                            }
+                            KeyCode::Char('U') | KeyCode::Esc => {
+                                state.audit_popup = None;
+                                state.status = "Closed recent actions".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    if state.slo_popup && focused == Some(ModalKind::Slos) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Char('B') | KeyCode::Esc => {
+                                state.slo_popup = false;
+                                state.status = "Closed SLO burn rates".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+
+                    if state.changes_popup && focused == Some(ModalKind::Changes) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Char('K') | KeyCode::Esc => {
+                                state.changes_popup = false;
+                                state.status = "Closed changes".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+
+                    if state.ping_popup && focused == Some(ModalKind::Ping) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Char('N') | KeyCode::Esc => {
+                                state.ping_popup = false;
+                                state.status = "Closed network latency".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    if state.checks_popup && focused == Some(ModalKind::Checks) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Char('C') | KeyCode::Esc => {
+                                state.checks_popup = false;
+                                state.status = "Closed synthetic checks".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    if state.about.is_some() && focused == Some(ModalKind::About) {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Char('r') => {
+                                let _ = refresh_tx.send(RefreshScope::All).await;
+                            }
+                            KeyCode::Char('i') | KeyCode::Esc => {
+                                state.about = None;
+                                state.status = "Closed backend details".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    // The drill-in chart takes all input until it is closed
+                    if let Some(drill_in) = state.drill_in.as_mut() {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End => {
+                                match key.code {
+                                    KeyCode::Left => drill_in.move_cursor(-1),
+                                    KeyCode::Right => drill_in.move_cursor(1),
+                                    KeyCode::Home => drill_in.cursor = Some(0),
+                                    _ => drill_in.cursor = None,
+                                }
+                                // The logs follow the chart's cursor to the same moment
+                                let t = drill_in.time_cursor();
+                                state.set_time_cursor(t, terminal_size.height);
+                            }
+                            KeyCode::Char('r') => {
+                                let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                            }
+                            KeyCode::Char('v') => toggle_inspection(&mut state),
+                            KeyCode::Char('D') => open_debug(&mut state),
+                            KeyCode::Char('X') => export_metrics(&mut state, ExportFormat::Csv),
+                            KeyCode::Char('J') => export_metrics(&mut state, ExportFormat::Json),
+                            KeyCode::Char('g') | KeyCode::Esc => {
+                                state.drill_in = None;
+                                state.status = "Closed history chart".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    if let Some(view) = state.system_view.as_mut() {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => view.scroll_offset = view.scroll_offset.saturating_sub(1),
+                            KeyCode::Down => {
+                                let max_offset = view.snapshot.as_ref().map_or(0, |snapshot| snapshot.processes.len().saturating_sub(1));
+                                view.scroll_offset = (view.scroll_offset + 1).min(max_offset);
+                            }
+                            KeyCode::Char('s') => {
+                                view.sort = view.sort.toggle();
+                                view.scroll_offset = 0;
+                                state.status = format!("Processes by {}", view.sort.as_str());
+                            }
+                            KeyCode::Char('L') | KeyCode::Esc => {
+                                state.system_view = None;
+                                state.status = "Closed local system view".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    if let Some(mut popup) = state.triage_popup.take_if(|_| focused == Some(ModalKind::Triage)) {
+                        let alerts = state.firing_alerts();
+                        let rows = alerts.len() + state.triage.patterns.len();
+                        let triage = match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Char('T') | KeyCode::Esc => {
+                                state.status = "Closed triage".to_string();
+                                continue;
+                            }
+                            KeyCode::Up => {
+                                popup.selected = popup.selected.saturating_sub(1);
+                                None
+                            }
+                            KeyCode::Down => {
+                                popup.selected = (popup.selected + 1).min(rows.saturating_sub(1));
+                                None
+                            }
+                            KeyCode::Char('a') => Some(Some(TriageState::Acknowledged)),
+                            KeyCode::Char('i') => Some(Some(TriageState::Investigating)),
+                            KeyCode::Char('x') => Some(Some(TriageState::Ignored)),
+                            KeyCode::Backspace | KeyCode::Delete => Some(None),
+                            _ => None,
+                        };
+                        if let Some(triage) = triage {
+                            match alerts.get(popup.selected) {
+                                Some((alert, since, _)) => {
+                                    state.triage.set_alert(alert.clone(), *since, triage);
+                                    state.status = format!("{} {}", alert, triage.map_or("untriaged", TriageState::as_str));
+                                }
+                                None => {
+                                    let idx = popup.selected.saturating_sub(alerts.len());
+                                    match (triage, state.triage.patterns.get_mut(idx)) {
+                                        (Some(triage), Some(pattern)) => pattern.state = triage,
+                                        (None, Some(_)) => {
+                                            state.triage.clear_pattern(idx);
+                                            popup.selected = popup.selected.min((rows - 1).saturating_sub(1));
+                                        }
+                                        (_, None) => {}
+                                    }
+                                }
+                            }
+                        }
+                        state.triage_popup = Some(popup);
+                        continue;
+                    }
+                    
+                    if let Some(service_map) = state.service_map.as_mut() {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => service_map.scroll_offset = service_map.scroll_offset.saturating_sub(1),
+                            KeyCode::Down => {
+                                let max_offset = service_map.row_count().saturating_sub(1);
+                                service_map.scroll_offset = (service_map.scroll_offset + 1).min(max_offset);
+                            }
+                            KeyCode::Char('r') => {
+                                let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                            }
+                            KeyCode::Char('m') | KeyCode::Esc => {
+                                state.service_map = None;
+                                state.status = "Closed service map".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    // Moving between panels and switching one's counter view need the rest of the state
+                    if let Some(mut page) = state.panels_page.clone() {
+                        match key.code {
+                            KeyCode::Tab | KeyCode::BackTab if !state.panels.is_empty() => {
+                                // Select the next (or previous) panel and scroll its title to the top
+                                let count = state.panels.len();
+                                page.selected = if key.code == KeyCode::Tab {
+                                    (page.selected + 1) % count
+                                } else {
+                                    (page.selected + count - 1) % count
+                                };
+                                page.scroll_offset = panels_page_title_line(&state, &page, page.selected).unwrap_or(page.scroll_offset);
+                                state.panels_page = Some(page);
+                                continue;
+                            }
+                            KeyCode::Char('c') => {
+                                let selected = page.selected;
+                                let current = state.panels.get(selected).and_then(|panel| {
+                                    panel.counter.map(|view| state.counter_views.get(&selected).copied().unwrap_or(view))
+                                });
+                                match current {
+                                    Some(view) => {
+                                        let next = view.next();
+                                        state.counter_views.insert(selected, next);
+                                        state.status = format!("{}: showing the counter's {}", state.panels[selected].title, next.as_str());
+                                        let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                                    }
+                                    None => state.status = "The selected panel doesn't chart a counter - TAB selects another".to_string(),
+                                }
+                                continue;
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    let panel_lines = state.panels_page.as_ref().map_or(0, |page| panels_page_line_count(&state, page));
+                    if let Some(page) = state.panels_page.as_mut() {
+                        match key.code {
+                            KeyCode::Char('q') => break,
+                            KeyCode::Up => page.scroll_offset = page.scroll_offset.saturating_sub(1),
+                            KeyCode::Down => page.scroll_offset = (page.scroll_offset + 1).min(panel_lines.saturating_sub(1)),
+                            KeyCode::Char('r') => {
+                                let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                            }
+                            KeyCode::Char('s') => page.sort_shift += 1,
+                            KeyCode::Char('d') => page.sort_reversed = !page.sort_reversed,
+                            KeyCode::Char('D') => open_debug(&mut state),
+                            KeyCode::Char('$') => open_variables(&mut state),
+                            KeyCode::Char('p') | KeyCode::Esc => {
+                                state.panels_page = None;
+                                state.status = "Closed panels".to_string();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    
+                    let selected_before = state.selected_log_index;
                     match key.code {
                         KeyCode::Char('q') => break,
-                        KeyCode::Char('r') => {
-                            state.status = "Manual refresh triggered".to_string();
-                            state.last_update = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                        KeyCode::Char('p') | KeyCode::Char('m') if !state.prometheus_enabled => {
+                            state.status = "Prometheus is disabled".to_string();
+                        }
+                        KeyCode::Char('A') if state.admin_actions.is_empty() && !state.loki_admin => {
+                            state.status = "No admin actions: set admin = true under [prometheus] or [loki]".to_string();
+                        }
+                        KeyCode::Char('A') => {
+                            let mut actions = state.admin_actions.clone();
+                            if state.loki_admin {
+                                let selected = state.selected_log_index.filter(|_| state.active_panel == ActivePanel::Logs);
+                                actions.extend(selected.and_then(|idx| AdminAction::delete_stream(&state.all_logs, idx)));
+                            }
+                            state.admin_menu = Some(AdminMenu::new(actions));
+                            state.status = "Admin menu".to_string();
                         }
-                        KeyCode::Tab => {
-                            // Cycle between panels: None -> Logs -> Metrics -> Logs...
-                            state.active_panel = match state.active_panel {
-                                ActivePanel::None => ActivePanel::Logs,
-                                ActivePanel::Logs => ActivePanel::Metrics,
-                                ActivePanel::Metrics => ActivePanel::Logs,
+                        KeyCode::Char('p') => {
+                            state.panels_page = Some(PanelsPage::default());
+                            state.status = format!("{} panel(s)", state.panels.len());
+                        }
+                        KeyCode::Char('S') => {
+                            state.query_stats_popup = Some(QueryStatsPopup::default());
+                            state.status = format!("Timings of {} queries", state.query_stats.len());
+                        }
+                        KeyCode::Char('Q') if !state.prometheus_enabled => {
+                            state.status = "The PromQL editor needs Prometheus".to_string();
+                        }
+                        KeyCode::Char('Q') => {
+                            state.query_editor = Some(QueryEditor::default());
+                            state.status = "PromQL editor".to_string();
+                        }
+                        KeyCode::Char('/') if !state.loki_enabled => {
+                            state.status = "The query bar needs Loki".to_string();
+                        }
+                        KeyCode::Char('/') => {
+                            let query = state.log_filter.logql().or_else(|| state.last_log_query.clone()).unwrap_or_default();
+                            state.log_query_bar = Some(LogQueryBar::new(&query));
+                            state.status = "LogQL query".to_string();
+                        }
+                        KeyCode::Char('X') => export_metrics(&mut state, ExportFormat::Csv),
+                        KeyCode::Char('J') => export_metrics(&mut state, ExportFormat::Json),
+                        KeyCode::Char('W') => {
+                            state.status = match std::env::current_dir().and_then(|dir| report::export(&state, &dir)) {
+                                Ok(path) => format!("Incident report written to {}", path.display()),
+                                Err(e) => format!("Couldn't write the incident report: {}", e),
                             };
+                        }
+                        KeyCode::Char('v') => toggle_inspection(&mut state),
+                        KeyCode::Char('D') => open_debug(&mut state),
+                        KeyCode::Char('V') => {
+                            state.saved_views_popup = Some(SavedViewsPopup::default());
+                            state.status = format!("{} saved views", state.saved_views.len());
+                        }
+                        KeyCode::Char('$') => open_variables(&mut state),
+                        KeyCode::Char('T') => {
+                            state.triage_popup = Some(TriagePopup::default());
+                            state.status = format!("{} alerts firing", state.firing_alerts().len());
+                        }
+                        KeyCode::Char('o') => {
+                            state.notes_pad = Some(NotesPad::default());
+                            state.status = format!("{} notes", state.notes.len());
+                        }
+                        KeyCode::Char('L') => {
+                            state.system_view = Some(SystemView::default());
+                            state.status = "Local system".to_string();
+                        }
+                        KeyCode::Char('K') => {
+                            state.changes_popup = true;
+                            state.status = format!("{} changes since the last refresh", state.metric_changes.changes.len());
+                        }
+                        KeyCode::Char('B') => {
+                            state.slo_popup = true;
+                            let burning = state.slos.iter().filter(|slo| slo.firing().is_some()).count();
+                            state.status = format!("{} SLOs, {} burning", state.slos.len(), burning);
+                        }
+                        KeyCode::Char('N') => {
+                            state.ping_popup = true;
+                            state.status = format!("Pinging {} hosts", state.ping_targets.len());
+                        }
+                        KeyCode::Char('C') => {
+                            state.checks_popup = true;
+                            let failing = state.synthetic_checks.iter().filter(|check| check.is_failing()).count();
+                            state.status = format!("{} synthetic checks, {} failing", state.synthetic_checks.len(), failing);
+                        }
+                        KeyCode::Char('U') => {
+                            state.audit_popup = Some(AuditPopup::default());
+                            state.status = format!("{} recent actions", state.audit_log.len());
+                        }
+                        KeyCode::Char('i') => {
+                            state.about = Some(AboutBackends::default());
+                            state.status = "Backend details".to_string();
+                            let _ = refresh_tx.send(RefreshScope::All).await;
+                        }
+                        KeyCode::Char('m') => {
+                            state.service_map = Some(ServiceMap::default());
+                            state.status = "Service map".to_string();
+                            let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                        }
+                        KeyCode::Char('u') => {
+                            // Back to the filter, time range and ranking before the last change
+                            match state.undo_view() {
+                                Some(scope) => {
+                                    let _ = refresh_tx.send(scope).await;
+                                }
+                                None => state.status = "Nothing to undo".to_string(),
+                            }
+                        }
+                        KeyCode::Char('r') if control => match state.redo_view() {
+                            Some(scope) => {
+                                let _ = refresh_tx.send(scope).await;
+                            }
+                            None => state.status = "Nothing to redo".to_string(),
+                        },
+                        KeyCode::Char('r') | KeyCode::Char('R') => {
+                            // `r` refreshes the focused panel, `R` (or `r` with no focus) everything
+                            let scope = match (key.code, state.active_panel) {
+                                (KeyCode::Char('r'), ActivePanel::Metrics) => RefreshScope::Metrics,
+                                (KeyCode::Char('r'), ActivePanel::Logs) => RefreshScope::Logs,
+                                _ => RefreshScope::All,
+                            };
+                            state.status = match scope {
+                                RefreshScope::All => "Refreshing all sources".to_string(),
+                                RefreshScope::Metrics => "Refreshing metrics".to_string(),
+                                RefreshScope::Logs => "Refreshing logs".to_string(),
+                            };
+                            state.last_update = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                            state.refresh_static = true;
+                            state.refreshing = Some(scope);
+                            let _ = refresh_tx.send(scope).await;
+                        }
+                        KeyCode::Tab | KeyCode::BackTab => {
+                            // Cycle focus between panels; ESC drops it again
+                            state.cycle_focus(key.code == KeyCode::BackTab);
+                            // While zoomed the newly focused panel takes the terminal
+                            state.update_visible_logs_with_height(terminal_size.height);
                             state.status = match state.active_panel {
                                 ActivePanel::None => "No panel active".to_string(),
                                 ActivePanel::Logs => "Logs panel active".to_string(),
                                 ActivePanel::Metrics => "Metrics panel active".to_string(),
                             };
                         }
+                        KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right
+                            if state.active_panel == ActivePanel::None =>
+                        {
+                            // Arrows only act on the focused panel
+                            state.status = "No panel active - press TAB to focus one".to_string();
+                        }
                         KeyCode::Up => {
                             match state.active_panel {
                                 ActivePanel::Logs => {
@@ -346,21 +1658,18 @@ async fn run_app<B: Backend>(
                                     state.update_visible_logs_with_height(terminal_size.height);
                                 }
                                 ActivePanel::Metrics => {
-                                    // Scroll up in metrics
-                                    if state.metrics_scroll_offset > 0 {
-                                        state.metrics_scroll_offset -= 1;
-                                        if let Some(metrics) = &state.metrics {
-                                            state.status = format!("Showing APIs {}-{} of {}", 
-                                                state.metrics_scroll_offset + 1,
-                                                (state.metrics_scroll_offset + 5).min(metrics.uri_metrics.len()),
-                                                metrics.uri_metrics.len()
-                                            );
+                                    // Move the row selection up, scrolling to keep it visible
+                                    if let Some(total) = state.metrics.as_ref().map(|m| m.uri_metrics.len()) {
+                                        if total > 0 {
+                                            let selected = match state.selected_metric_index {
+                                                Some(idx) => idx.saturating_sub(1),
+                                                None => state.metrics_scroll_offset.min(total - 1),
+                                            };
+                                            select_metric_row(&mut state, selected, total, terminal_size.height);
                                         }
                                     }
                                 }
-                                ActivePanel::None => {
-                                    // Do nothing when no panel is active
-                                }
+                                ActivePanel::None => {}
                             }
                         }
                         KeyCode::Down => {
@@ -399,28 +1708,54 @@ async fn run_app<B: Backend>(
                                     state.update_visible_logs_with_height(terminal_size.height);
                                 }
                                 ActivePanel::Metrics => {
-                                    // Scroll down in metrics
-                                    let metrics_info = state.metrics.as_ref().map(|m| (m.uri_metrics.len(), m.uri_metrics.len().saturating_sub(5)));
-                                    if let Some((total, max_offset)) = metrics_info {
-                                        if state.metrics_scroll_offset < max_offset {
-                                            state.metrics_scroll_offset += 1;
-                                            state.status = format!("Showing APIs {}-{} of {}", 
-                                                state.metrics_scroll_offset + 1,
-                                                (state.metrics_scroll_offset + 5).min(total),
-                                                total
-                                            );
+                                    // Move the row selection down, scrolling to keep it visible
+                                    if let Some(total) = state.metrics.as_ref().map(|m| m.uri_metrics.len()) {
+                                        if total > 0 {
+                                            let selected = match state.selected_metric_index {
+                                                Some(idx) => (idx + 1).min(total - 1),
+                                                None => state.metrics_scroll_offset.min(total - 1),
+                                            };
+                                            select_metric_row(&mut state, selected, total, terminal_size.height);
                                         }
                                     }
                                 }
-                                ActivePanel::None => {
-                                    // Do nothing when no panel is active
-                                }
+                                ActivePanel::None => {}
+                            }
+                        }
+                        KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End
+                            if state.active_panel == ActivePanel::Metrics =>
+                        {
+                            // Page through the URI table, or jump to its ends
+                            if let Some(total) = state.metrics.as_ref().map(|m| m.uri_metrics.len()).filter(|&t| t > 0) {
+                                let page = state.metrics_visible_rows(terminal_size.height);
+                                let current = state.selected_metric_index.unwrap_or(state.metrics_scroll_offset);
+                                let selected = match key.code {
+                                    KeyCode::PageUp => current.saturating_sub(page),
+                                    KeyCode::PageDown => current + page,
+                                    KeyCode::Home => 0,
+                                    _ => total - 1,
+                                };
+                                select_metric_row(&mut state, selected.min(total - 1), total, terminal_size.height);
                             }
                         }
-                        KeyCode::Char('[') => {
-                            if state.active_panel == ActivePanel::Logs {
-                                // Move up 5 lines in logs
-                                if let Some(idx) = state.selected_log_index {
+                        KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End
+                            if state.active_panel == ActivePanel::Logs && !state.all_logs.is_empty() =>
+                        {
+                            // Page through the log buffer, or jump to its ends
+                            let total = state.all_logs.len();
+                            let page = state.get_visible_height(terminal_size.height).max(1);
+                            let current = state.selected_log_index.unwrap_or(total - 1);
+                            let selected = match key.code {
+                                KeyCode::PageUp => current.saturating_sub(page),
+                                KeyCode::PageDown => current + page,
+                                KeyCode::Home => 0,
+                                _ => total - 1,
+                            };
+                            select_log_line(&mut state, selected.min(total - 1), terminal_size.height);
+                        }
+                        KeyCode::Char('[') if state.active_panel == ActivePanel::Logs => {
+                            // Move up 5 lines in logs
+                            if let Some(idx) = state.selected_log_index {
                                 let new_idx = idx.saturating_sub(5);
                                 state.selected_log_index = Some(new_idx);
                                 if new_idx < state.log_scroll_offset {
@@ -439,13 +1774,11 @@ async fn run_app<B: Backend>(
                                     state.log_scroll_offset = 0;
                                 }
                                 state.update_visible_logs_with_height(terminal_size.height);
-                                }
                             }
                         }
-                        KeyCode::Char(']') => {
-                            if state.active_panel == ActivePanel::Logs {
-                                // Move down 5 lines in logs
-                                if let Some(idx) = state.selected_log_index {
+                        KeyCode::Char(']') if state.active_panel == ActivePanel::Logs => {
+                            // Move down 5 lines in logs
+                            if let Some(idx) = state.selected_log_index {
                                 let new_idx = (idx + 5).min(state.all_logs.len().saturating_sub(1));
                                 state.selected_log_index = Some(new_idx);
                                 let visible_height = state.get_visible_height(terminal_size.height);
@@ -465,91 +1798,269 @@ async fn run_app<B: Backend>(
                                     state.log_scroll_offset = 0;
                                 }
                                 state.update_visible_logs_with_height(terminal_size.height);
-                                }
                             }
                         }
+                        KeyCode::Char('{') | KeyCode::Char('}') | KeyCode::Char('(') | KeyCode::Char(')') => {
+                            // By time rather than lines: a step covers a few lines in a
+                            // quiet minute and thousands in a burst
+                            let step = match key.code {
+                                KeyCode::Char('{') | KeyCode::Char('}') => state.log_jump_steps[0],
+                                _ => state.log_jump_steps[1],
+                            };
+                            let back = matches!(key.code, KeyCode::Char('{') | KeyCode::Char('('));
+                            state.status = state.jump_logs(if back { -step } else { step }, terminal_size.height);
+                        }
+                        KeyCode::Char('<') | KeyCode::Char('>') if state.active_panel == ActivePanel::Logs => {
+                            // Step the time cursor a minute, selecting the line logged nearest to it
+                            let now = Local::now().timestamp() as f64;
+                            let step = if key.code == KeyCode::Char('<') { -60.0 } else { 60.0 };
+                            let t = Some(state.time_cursor.unwrap_or(now) + step).filter(|&t| t < now);
+                            state.set_time_cursor(t, terminal_size.height);
+                            state.status = match t {
+                                Some(t) => format!("Time cursor at {}", format_time(t, "%H:%M:%S")),
+                                None => "Time cursor follows the latest logs".to_string(),
+                            };
+                        }
                         KeyCode::Esc => {
                             // Deactivate current panel or deselect in logs
                             if state.active_panel == ActivePanel::Logs && state.selected_log_index.is_some() {
                                 // Just deselect the log, keep panel active
                                 state.selected_log_index = None;
                                 state.status = "Log deselected".to_string();
+                            } else if state.active_panel == ActivePanel::Metrics && state.selected_metric_index.is_some() {
+                                state.selected_metric_index = None;
+                                state.status = "URI deselected".to_string();
                             } else {
-                                // Deactivate the panel
+                                // Deactivate the panel, leaving zoom with it
                                 state.active_panel = ActivePanel::None;
+                                state.zoomed = false;
                                 state.selected_log_index = None;
                                 state.status = "No panel active - press TAB to activate a panel".to_string();
                             }
                         }
-                        KeyCode::Char('c') => {
-                            if state.active_panel == ActivePanel::Logs {
-                                // Copy selected log to clipboard
-                                if let Some(idx) = state.selected_log_index {
+                        KeyCode::Char('c') if state.active_panel == ActivePanel::Logs => {
+                            // Copy selected log to clipboard
+                            if let Some(idx) = state.selected_log_index {
                                 if let Some(log) = state.all_logs.get(idx) {
                                     let log_text = format!("[{}] {}", 
                                         log.level, log.message);
-                                    
-                                    // Actually copy to system clipboard
-                                    match ClipboardContext::new() {
-                                        Ok(mut ctx) => {
-                                            match ctx.set_contents(log_text.clone()) {
-                                                Ok(_) => {
-                                                    state.status = format!("Log copied to clipboard ({}...)", 
-                                                        &log_text.chars().take(30).collect::<String>());
-                                                }
-                                                Err(e) => {
-                                                    state.status = format!("Failed to copy: {}", e);
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            state.status = format!("Clipboard unavailable: {}", e);
-                                        }
+                                    state.status = copy_to_clipboard(log_text);
+                                }
+                            }
+                        }
+                        KeyCode::Char('c') | KeyCode::Char('M') if state.active_panel == ActivePanel::Metrics => {
+                            // For Slack or a ticket, without a screenshot of the terminal
+                            let format = if key.code == KeyCode::Char('M') { TableFormat::Markdown } else { TableFormat::Text };
+                            state.status = match table_text::metrics_table(&state, format) {
+                                Some(table) => {
+                                    let rows = state.metrics.as_ref().map_or(0, |metrics| metrics.uri_metrics.len());
+                                    match set_clipboard(table) {
+                                        Ok(()) => format!("Copied the metrics table ({} rows) as {}", rows, format.as_str()),
+                                        Err(e) => e,
                                     }
                                 }
+                                None => "No metrics to copy yet".to_string(),
+                            };
+                        }
+                        KeyCode::Char('a') if state.active_panel == ActivePanel::Logs => {
+                            // Context menu for the selected line
+                            let selected = state.selected_log_index.filter(|&idx| idx < state.all_logs.len());
+                            match selected {
+                                Some(idx) => {
+                                    let log = &state.all_logs[idx];
+                                    let bookmarked = state.bookmarks.contains(&log.nanos);
+                                    let mut actions = LogAction::available(log, &state.log_filter, bookmarked, &state.log_actions);
+                                    actions.extend(LogAction::triage(state.triage.log_state(&log.message)));
+                                    state.log_action_menu = Some(LogActionMenu::new(idx, actions));
                                 }
+                                None => state.status = "Select a log line first".to_string(),
                             }
                         }
-                        KeyCode::Enter => {
-                            if state.active_panel == ActivePanel::Logs {
-                                // Toggle expanded state for selected log
-                                if let Some(selected_idx) = state.selected_log_index {
-                                    if state.expanded_log_index == Some(selected_idx) {
-                                        // Collapse if already expanded
-                                        state.expanded_log_index = None;
-                                        state.status = "Log collapsed".to_string();
-                                    } else {
-                                        // Expand the selected log
-                                        state.expanded_log_index = Some(selected_idx);
-                                        state.status = "Log expanded - press Enter again to collapse".to_string();
-                                    }
-                                    // Update visible logs to reflect the change
-                                    state.update_visible_logs_with_height(terminal_size.height);
+                        KeyCode::Char('d') if state.active_panel == ActivePanel::Logs => {
+                            // Compare the selected line with the one before it
+                            if state.log_diff_pair().is_some() {
+                                state.log_diff = true;
+                            } else {
+                                state.status = "Select a log line with another next to it first".to_string();
+                            }
+                        }
+                        KeyCode::Char('b') if state.active_panel == ActivePanel::Logs => {
+                            let found = state.next_bookmark(terminal_size.height);
+                            if !found {
+                                state.status = "No bookmarked lines (a: bookmark the selected line)".to_string();
+                            }
+                        }
+                        KeyCode::Char('x') if state.active_panel == ActivePanel::Logs => {
+                            // Hide the selected line and others shaped like it
+                            let message = state.selected_log_index.and_then(|idx| state.all_logs.get(idx)).map(|log| log.message.clone());
+                            if let Some(message) = message {
+                                let pattern = state.ignore.ignore_log(&message);
+                                state.expanded_log_index = None;
+                                state.hide_ignored_logs();
+                                state.status = format!("Hiding logs matching {} (H: show hidden)", pattern);
+                                state.audit(AuditEntry::new("ignore_log", "").param("pattern", pattern));
+                            }
+                        }
+                        KeyCode::Char('x') if state.active_panel == ActivePanel::Metrics => {
+                            // Hide the selected URI
+                            let uri = state.selected_metric_index.and_then(|idx| {
+                                state.metrics.as_ref()?.uri_metrics.get(idx).map(|m| m.uri.clone())
+                            });
+                            if let Some(uri) = uri {
+                                state.ignore.ignore_uri(&uri);
+                                state.hide_ignored_metrics();
+                                state.status = format!("Hiding {} (H: show hidden)", uri);
+                                state.audit(AuditEntry::new("ignore_uri", "").param("uri", uri));
+                            }
+                        }
+                        KeyCode::Char('H') => {
+                            // Momentarily show what the ignore lists hide
+                            state.ignore.show_hidden = !state.ignore.show_hidden;
+                            state.status = if state.ignore.show_hidden {
+                                "Showing hidden URIs and logs".to_string()
+                            } else {
+                                "Hiding ignored URIs and logs".to_string()
+                            };
+                            state.hide_ignored_metrics();
+                            state.hide_ignored_logs();
+                            let _ = refresh_tx.send(RefreshScope::All).await;
+                        }
+                        KeyCode::Char('z') if state.active_panel == ActivePanel::None => {
+                            state.status = "Focus a panel with TAB to zoom it".to_string();
+                        }
+                        KeyCode::Char('z') => {
+                            // Give the focused panel the whole terminal, or restore the layout
+                            state.zoomed = !state.zoomed;
+                            state.status = if state.zoomed {
+                                "Panel zoomed - z: restore layout".to_string()
+                            } else {
+                                "Layout restored".to_string()
+                            };
+                            state.update_visible_logs_with_height(terminal_size.height);
+                        }
+                        KeyCode::Char('F') => {
+                            state.flush_logs();
+                            state.status = "Flushed logs - showing lines from now on".to_string();
+                        }
+                        KeyCode::Char('#') => {
+                            state.show_line_numbers = !state.show_line_numbers;
+                            state.status = if state.show_line_numbers {
+                                "Showing line numbers".to_string()
+                            } else {
+                                "Hiding line numbers".to_string()
+                            };
+                        }
+                        KeyCode::Char(':') if state.all_logs.is_empty() => {
+                            state.status = "No log lines to go to".to_string();
+                        }
+                        KeyCode::Char(':') => {
+                            state.goto_prompt = Some(GotoPrompt::default());
+                            state.status = "Go to line or time".to_string();
+                        }
+                        KeyCode::Char('t') => {
+                            // Ages tick with every redraw; no fetch needed
+                            state.show_log_ages = !state.show_log_ages;
+                            state.status = if state.show_log_ages {
+                                "Showing log ages".to_string()
+                            } else {
+                                "Hiding log ages".to_string()
+                            };
+                        }
+                        KeyCode::Enter if state.active_panel == ActivePanel::Logs => {
+                            // Toggle expanded state for selected log
+                            if let Some(selected_idx) = state.selected_log_index {
+                                if state.expanded_log_index == Some(selected_idx) {
+                                    // Collapse if already expanded
+                                    state.expanded_log_index = None;
+                                    state.status = "Log collapsed".to_string();
+                                } else {
+                                    // Expand the selected log
+                                    state.expanded_log_index = Some(selected_idx);
+                                    state.status = "Log expanded - press Enter again to collapse".to_string();
                                 }
+                                // Update visible logs to reflect the change
+                                state.update_visible_logs_with_height(terminal_size.height);
                             }
                         }
-                        KeyCode::Left => {
-                            if state.active_panel == ActivePanel::Metrics {
-                                // Change to previous time range
-                                state.metrics_time_range = state.metrics_time_range.prev();
-                                state.status = format!("Time range: {}", state.metrics_time_range.as_str());
-                                state.metrics_loading = true; // Set loading state
-                                // Trigger immediate refresh
-                                let _ = refresh_tx.send(()).await;
+                        KeyCode::Char('g') if state.active_panel == ActivePanel::Metrics => {
+                            // Open the history chart for the selected URI
+                            let uri = state.selected_metric_index.and_then(|idx| {
+                                state.metrics.as_ref()?.uri_metrics.get(idx).map(|m| m.uri.clone())
+                            });
+                            match uri {
+                                Some(uri) => {
+                                    state.status = format!("History for {}", uri);
+                                    state.drill_in = Some(UriDrillIn::new(uri));
+                                    let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                                }
+                                None => state.status = "Select a URI with ↑/↓ first".to_string(),
                             }
                         }
-                        KeyCode::Right => {
-                            if state.active_panel == ActivePanel::Metrics {
-                                // Change to next time range
-                                state.metrics_time_range = state.metrics_time_range.next();
-                                state.status = format!("Time range: {}", state.metrics_time_range.as_str());
-                                state.metrics_loading = true; // Set loading state
-                                // Trigger immediate refresh
-                                let _ = refresh_tx.send(()).await;
+                        KeyCode::Char('e') if state.active_panel == ActivePanel::Metrics => {
+                            // Narrow the logs to lines about the selected URI
+                            let uri = state.selected_metric_index.and_then(|idx| {
+                                state.metrics.as_ref()?.uri_metrics.get(idx).map(|m| m.uri.clone())
+                            });
+                            match uri {
+                                Some(uri) => {
+                                    let pattern = state.uri_error_pattern.clone().unwrap_or_else(|| uri_errors::DEFAULT_PATTERN.to_string());
+                                    let filter = LogFilter { pattern: Some(uri_errors::uri_pattern(&uri, &pattern)), query: None, ..state.log_filter.clone() };
+                                    state.active_panel = ActivePanel::Logs;
+                                    state.status = format!("Showing logs about {}", uri);
+                                    state.set_log_filter(filter);
+                                    let _ = refresh_tx.send(RefreshScope::Logs).await;
+                                }
+                                None => state.status = "Select a URI with ↑/↓ first".to_string(),
                             }
                         }
+                        KeyCode::Char('s') if state.active_panel == ActivePanel::Metrics => {
+                            // Re-rank the table by the next dimension
+                            let ranking = state.metrics_ranking.next();
+                            state.set_ranking(ranking);
+                            state.selected_metric_index = None;
+                            state.metrics_scroll_offset = 0;
+                            state.status = format!("Ranking by {}", state.metrics_ranking.as_str());
+                            state.metrics_loading = true;
+                            let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                        }
+                        KeyCode::Left | KeyCode::Right
+                            if state.active_panel == ActivePanel::Metrics && key.modifiers.contains(KeyModifiers::SHIFT) =>
+                        {
+                            // Scroll the columns after the frozen URI column
+                            let last = metrics_columns(&state).len() - 1;
+                            state.metrics_column_offset = match key.code {
+                                KeyCode::Left => state.metrics_column_offset.min(last).saturating_sub(1),
+                                _ => (state.metrics_column_offset + 1).min(last),
+                            };
+                            state.status = match state.metrics_column_offset {
+                                0 => "Showing all metrics columns".to_string(),
+                                offset => format!("{} of {} metrics columns scrolled out of view", offset, last + 1),
+                            };
+                        }
+                        KeyCode::Left if state.active_panel == ActivePanel::Metrics => {
+                            // Change to previous time range
+                            let range = state.metrics_time_range.prev();
+                            state.set_time_range(range);
+                            state.status = format!("Time range: {}", state.metrics_time_range.as_str());
+                            state.metrics_loading = true; // Set loading state
+                            // Trigger immediate refresh
+                            let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                        }
+                        KeyCode::Right if state.active_panel == ActivePanel::Metrics => {
+                            // Change to next time range
+                            let range = state.metrics_time_range.next();
+                            state.set_time_range(range);
+                            state.status = format!("Time range: {}", state.metrics_time_range.as_str());
+                            state.metrics_loading = true; // Set loading state
+                            // Trigger immediate refresh
+                            let _ = refresh_tx.send(RefreshScope::Metrics).await;
+                        }
                         _ => {}
                     }
+                    // Moving the log selection moves the time cursor with it
+                    if state.selected_log_index != selected_before && !matches!(key.code, KeyCode::Char('<') | KeyCode::Char('>')) {
+                        state.time_cursor = state.selected_log_index.and_then(|idx| state.all_logs.get(idx)).map(|log| log.nanos as f64 / 1e9);
+                    }
                 }
             }
         }
@@ -557,3 +2068,232 @@ async fn run_app<B: Backend>(
 
     Ok(())
 }
+
+/// Select a metrics row, scrolling so it stays within the visible rows.
+fn select_metric_row(state: &mut AppState, selected: usize, total: usize, terminal_height: u16) {
+    let page = state.metrics_visible_rows(terminal_height);
+    state.selected_metric_index = Some(selected);
+    if selected < state.metrics_scroll_offset {
+        state.metrics_scroll_offset = selected;
+    } else if selected >= state.metrics_scroll_offset + page {
+        state.metrics_scroll_offset = selected + 1 - page;
+    }
+    state.metrics_scroll_offset = state.metrics_scroll_offset.min(total.saturating_sub(page));
+    state.status = format!("Showing APIs {}-{} of {} (selected {})",
+        state.metrics_scroll_offset + 1,
+        (state.metrics_scroll_offset + page).min(total),
+        total,
+        selected + 1
+    );
+}
+
+/// Turn value inspection of the selected metrics row or chart point on or off.
+fn toggle_inspection(state: &mut AppState) {
+    if state.inspection.take().is_some() {
+        state.status = "Inspection off".to_string();
+    } else if !state.prometheus_enabled {
+        state.status = "Inspection needs Prometheus".to_string();
+    } else {
+        state.inspection = Some(Inspection::default());
+        state.status = match state.inspected_uri() {
+            Some(uri) => format!("Inspecting {}", uri),
+            None => "Inspection on - select a URI with ↑/↓".to_string(),
+        };
+    }
+}
+
+/// Open the raw requests and responses behind what has focus.
+fn open_debug(state: &mut AppState) {
+    let popup = DebugPopup::open(state);
+    state.status = format!("{} requests", popup.queries.len());
+    state.debug_popup = Some(popup);
+}
+
+/// Open the variable picker on the first variable.
+fn open_variables(state: &mut AppState) {
+    state.variable_picker = Some(VariablePicker::open(&state.variables, 0));
+    state.status = format!("{} variables", state.variables.len());
+}
+
+/// Select `all_logs[selected]`, scrolling just enough to keep it visible.
+fn select_log_line(state: &mut AppState, selected: usize, terminal_height: u16) {
+    let page = state.get_visible_height(terminal_height).max(1);
+    state.selected_log_index = Some(selected);
+    if selected < state.log_scroll_offset {
+        state.log_scroll_offset = selected;
+    } else if selected >= state.log_scroll_offset + page {
+        state.log_scroll_offset = selected + 1 - page;
+    }
+    state.update_visible_logs_with_height(terminal_height);
+}
+
+/// What ended the key loop's wait.
+enum Wake {
+    Input(bool), // The input wait finished, with an event ready or not
+    Data,        // The background refresh changed the state
+    Signal(JobSignal),
+    Frame,       // Time for the next frame
+}
+
+/// Job control signals, forwarded to the key loop so it can hand the
+/// terminal back before stopping and take it again on resume.
+enum JobSignal {
+    Stop,     // SIGTSTP, e.g. `kill -TSTP` from another shell
+    Continue, // SIGCONT, e.g. after `kill -STOP`
+}
+
+#[cfg(unix)]
+fn job_control_signals() -> io::Result<mpsc::UnboundedReceiver<JobSignal>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    // Handling SIGTSTP replaces the default stop; suspend() stops with SIGSTOP instead
+    let mut stops = signal(SignalKind::from_raw(libc::SIGTSTP))?;
+    let mut continues = signal(SignalKind::from_raw(libc::SIGCONT))?;
+    tokio::spawn(async move {
+        loop {
+            let signal = tokio::select! {
+                Some(()) = stops.recv() => JobSignal::Stop,
+                Some(()) = continues.recv() => JobSignal::Continue,
+                else => break,
+            };
+            if tx.send(signal).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
+
+#[cfg(not(unix))]
+fn job_control_signals() -> io::Result<mpsc::UnboundedReceiver<JobSignal>> {
+    Ok(mpsc::unbounded_channel().1)
+}
+
+/// Restore the terminal and stop like any job sent to the background,
+/// re-entering the dashboard once the shell continues it (`fg`).
+fn suspend<F: Frontend>(frontend: &mut F) -> io::Result<()> {
+    frontend.release()?;
+    #[cfg(unix)]
+    // SAFETY: raise has no preconditions; SIGSTOP returns once continued
+    unsafe {
+        libc::raise(libc::SIGSTOP);
+    }
+    frontend.resume()
+}
+
+/// Hand the terminal over to an external command fed `input`, and take it
+/// back with a full redraw once the command exits. The outer error is the
+/// terminal failing; the inner one the command.
+fn run_suspended<F: Frontend>(frontend: &mut F, command: &str, input: &str) -> io::Result<Result<()>> {
+    frontend.release()?;
+    // With stdout piped for the summary, the command shares the TUI's stderr
+    let stdout = if stdout().is_terminal() { Stdio::inherit() } else { Stdio::from(io::stderr()) };
+    let result = tokio::task::block_in_place(|| log_actions::run_command(command, input, stdout));
+    frontend.resume()?;
+    Ok(result)
+}
+
+/// Write the metrics table, or the history chart's series, to a file in
+/// the current directory.
+fn export_metrics(state: &mut AppState, format: ExportFormat) {
+    let Some(export) = MetricsExport::capture(state) else {
+        state.status = "No metrics to export yet".to_string();
+        return;
+    };
+    let written = std::env::current_dir().and_then(|dir| export.write(format, &dir));
+    state.status = match written {
+        Ok(path) => format!("Exported {} rows to {}", export.rows.len(), path.display()),
+        Err(e) => format!("Couldn't export the metrics: {}", e),
+    };
+}
+
+/// Put `text` on the system clipboard, returning the status line to show.
+fn copy_to_clipboard(text: String) -> String {
+    let preview = text.chars().take(30).collect::<String>();
+    match set_clipboard(text) {
+        Ok(()) => format!("Copied to clipboard ({}...)", preview),
+        Err(e) => e,
+    }
+}
+
+/// Put `text` on the system clipboard, or say why it couldn't be.
+fn set_clipboard(text: String) -> Result<(), String> {
+    let mut ctx = ClipboardContext::new().map_err(|e| format!("Clipboard unavailable: {}", e))?;
+    ctx.set_contents(text).map_err(|e| format!("Failed to copy: {}", e))
+}
+
+/// Run an action from the log line menu on `all_logs[index]`, returning
+/// the refresh it needs, if any.
+fn run_log_action(state: &mut AppState, index: usize, action: LogAction) -> Option<RefreshScope> {
+    let log = state.all_logs.get(index)?.clone();
+    if let Some(text) = action.copy_text(&log) {
+        state.status = copy_to_clipboard(text);
+        return None;
+    }
+    match action {
+        LogAction::FilterStream(stream) => {
+            state.status = format!("Showing only {}", stream);
+            let filter = LogFilter { stream: Some(stream), query: None, ..state.log_filter.clone() };
+            state.set_log_filter(filter);
+            Some(RefreshScope::Logs)
+        }
+        LogAction::ClearStreamFilter => {
+            state.status = "Showing all streams".to_string();
+            let filter = LogFilter { stream: None, ..state.log_filter.clone() };
+            state.set_log_filter(filter);
+            Some(RefreshScope::Logs)
+        }
+        LogAction::Triage(triage) => {
+            let pattern = state.triage.set_log(&log.message, triage);
+            state.status = match triage {
+                Some(triage) => format!("Lines matching {} {}", pattern, triage.as_str()),
+                None => format!("Cleared the triage of lines matching {}", pattern),
+            };
+            None
+        }
+        LogAction::SearchSimilar(pattern) => {
+            state.status = format!("Searching logs for {}", pattern);
+            let filter = LogFilter { pattern: Some(pattern), query: None, ..state.log_filter.clone() };
+            state.set_log_filter(filter);
+            Some(RefreshScope::Logs)
+        }
+        LogAction::ClearSearch => {
+            state.status = "Search cleared".to_string();
+            let filter = LogFilter { pattern: None, ..state.log_filter.clone() };
+            state.set_log_filter(filter);
+            Some(RefreshScope::Logs)
+        }
+        LogAction::HideSimilar => {
+            let pattern = state.ignore.ignore_log(&log.message);
+            state.expanded_log_index = None;
+            state.hide_ignored_logs();
+            state.status = format!("Hiding logs matching {} (H: show hidden)", pattern);
+            state.audit(AuditEntry::new("ignore_log", "").param("pattern", pattern));
+            None
+        }
+        LogAction::Bookmark => {
+            state.bookmarks.insert(log.nanos);
+            state.status = "Bookmarked (b: jump to the next bookmark)".to_string();
+            None
+        }
+        LogAction::RemoveBookmark => {
+            state.bookmarks.remove(&log.nanos);
+            state.status = "Bookmark removed".to_string();
+            None
+        }
+        LogAction::OpenUrl { url, .. } => {
+            state.status = match log_actions::open_url(&url) {
+                Ok(()) => format!("Opened {}", url),
+                Err(e) => format!("{}: {}", e, url),
+            };
+            None
+        }
+        // Copies are handled above, commands by the key loop, which owns the terminal
+        LogAction::CopyLine
+        | LogAction::CopyMessage
+        | LogAction::CopyWithContext
+        | LogAction::CopyStream
+        | LogAction::RunCommand { .. } => None,
+    }
+}
@@ -0,0 +1,173 @@
+//! Metrics written to a file for offline analysis (`X` for CSV, `J` for
+//! JSON): the metrics table, or the series of the history chart while it is
+//! open, with the queries and time range behind them. CSV files carry those
+//! in `#` comment lines ahead of the header, which pandas skips with
+//! `comment="#"`; JSON files in fields next to the rows.
+
+use crate::prometheus;
+use crate::ui::{format_time, AppState};
+use chrono::Local;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// What an export holds, whatever the format.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsExport {
+    pub kind: &'static str, // "table", or "series" for the history chart
+    pub exported_at: String, // RFC 3339
+    pub prometheus: String,
+    pub time_range: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranking: Option<String>, // Table order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>, // The URI charted
+    pub queries: Vec<String>, // Empty when taken from the recorded history ("All")
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<Value>>, // One value per column; null where there is none
+}
+
+fn number(value: f64) -> Value {
+    serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+impl MetricsExport {
+    /// The history chart's series while it shows one, else the table.
+    /// None before there is anything to export.
+    pub fn capture(state: &AppState) -> Option<Self> {
+        let queries = match state.metrics_time_range.as_minutes() {
+            Some(_) => prometheus::uri_metric_queries(&state.metrics_time_range.to_prometheus_range()).to_vec(),
+            None => Vec::new(),
+        };
+        let mut export = Self {
+            kind: "table",
+            exported_at: Local::now().to_rfc3339(),
+            prometheus: state.prometheus_url.clone(),
+            time_range: state.metrics_time_range.as_str().to_string(),
+            ranking: None,
+            uri: None,
+            queries,
+            columns: Vec::new(),
+            rows: Vec::new(),
+        };
+        if let Some(drill_in) = &state.drill_in {
+            let history = drill_in.history.as_ref().filter(|history| !history.latency_ms.is_empty())?;
+            let requests = |t: f64| history.requests_per_min.iter().find(|(at, _)| *at == t).map(|&(_, value)| value);
+            export.kind = "series";
+            export.uri = Some(drill_in.uri.clone());
+            export.queries = history.queries.clone();
+            export.columns = vec!["time", "unix_seconds", "latency_ms", "requests_per_min"];
+            export.rows = history
+                .latency_ms
+                .iter()
+                .map(|&(t, latency)| {
+                    vec![
+                        Value::String(format_time(t, "%Y-%m-%dT%H:%M:%S%:z")),
+                        number(t),
+                        number(latency),
+                        requests(t).map_or(Value::Null, number),
+                    ]
+                })
+                .collect();
+            return Some(export);
+        }
+        let rows = &state.metrics.as_ref().filter(|metrics| !metrics.uri_metrics.is_empty())?.uri_metrics;
+        export.ranking = Some(state.metrics_ranking.as_str().to_string());
+        export.columns = vec!["uri", "avg_duration_ms", "requests_per_min", "errors_per_min", "change_pct", "bytes_per_sec"];
+        export.rows = rows
+            .iter()
+            .map(|metric| {
+                vec![
+                    Value::String(metric.uri.clone()),
+                    number(metric.avg_duration_ms),
+                    number(metric.request_count),
+                    // Only queried while ranking by them
+                    match state.metrics_ranking {
+                        prometheus::Ranking::Errors => number(metric.errors_per_min),
+                        _ => Value::Null,
+                    },
+                    metric.change_pct.map_or(Value::Null, number),
+                    metric.bytes_per_sec.map_or(Value::Null, number),
+                ]
+            })
+            .collect();
+        Some(export)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        let mut comment = |key: &str, value: &str| out.push_str(&format!("# {}: {}\n", key, value));
+        comment("rustdash", self.kind);
+        comment("exported_at", &self.exported_at);
+        comment("prometheus", &self.prometheus);
+        comment("time_range", &self.time_range);
+        if let Some(ranking) = &self.ranking {
+            comment("ranking", ranking);
+        }
+        if let Some(uri) = &self.uri {
+            comment("uri", uri);
+        }
+        for query in &self.queries {
+            comment("query", query);
+        }
+        out.push_str(&self.columns.join(","));
+        out.push('\n');
+        for row in &self.rows {
+            let fields: Vec<String> = row.iter().map(csv_field).collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The export as JSON, its rows as objects keyed by column for
+    /// `pandas.DataFrame(rows)`.
+    pub fn to_json(&self) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        let records: Vec<Value> = self
+            .rows
+            .iter()
+            .map(|row| Value::Object(self.columns.iter().map(|column| column.to_string()).zip(row.iter().cloned()).collect::<Map<_, _>>()))
+            .collect();
+        value["rows"] = Value::Array(records);
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    }
+
+    /// Write to `rustdash-<kind>-<date>-<time>.<ext>` in `dir`.
+    pub fn write(&self, format: ExportFormat, dir: &Path) -> io::Result<PathBuf> {
+        let path = dir.join(format!("rustdash-{}-{}.{}", self.kind, Local::now().format("%Y%m%d-%H%M%S"), format.extension()));
+        let contents = match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Json => self.to_json(),
+        };
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+/// A value as a CSV field, quoted when it holds a comma, quote or newline.
+fn csv_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) if text.contains([',', '"', '\n', '\r']) => format!("\"{}\"", text.replace('"', "\"\"")),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,1028 @@
+//! In-process mock of the Prometheus and Loki HTTP APIs.
+//!
+//! Serves deterministic but time-varying data: per-URI latencies and request
+//! rates that drift over time, and a continuous log stream whose density rises
+//! and falls. Used by `rustdash demo` and as the integration test harness.
+
+use crate::panels::{ContainerSignal, CounterView, IngressFlavor, IngressSignal, PanelConfig, Preset, QueueFlavor, ValueFormat};
+use crate::text::Truncation;
+use anyhow::Result;
+use regex::Regex;
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// (uri, base latency in ms, base requests per second)
+const ENDPOINTS: [(&str, f64, f64); 8] = [
+    ("/api/orders", 45.0, 12.0),
+    ("/api/orders/{id}", 30.0, 25.0),
+    ("/api/payments", 380.0, 3.0),
+    ("/api/users/{id}", 18.0, 40.0),
+    ("/api/search", 220.0, 8.0),
+    ("/api/reports/export", 950.0, 0.4),
+    ("/health", 2.0, 1.0),
+    ("/login", 120.0, 2.5),
+];
+
+/// (instance, share of each endpoint's requests) behind every endpoint
+const INSTANCES: [(&str, f64); 2] = [("10.0.1.12:8080", 0.6), ("10.0.1.13:8080", 0.4)];
+
+/// Unix seconds the mock's counters start from (2024-01-01).
+const COUNTER_EPOCH: f64 = 1_704_067_200.0;
+
+/// (client, server, requests per second, failure ratio, p95 latency in ms)
+const SERVICE_EDGES: [(&str, &str, f64, f64, f64); 6] = [
+    ("gateway", "orders", 40.0, 0.004, 60.0),
+    ("gateway", "users", 55.0, 0.001, 25.0),
+    ("orders", "inventory", 30.0, 0.01, 45.0),
+    ("orders", "payments", 9.0, 0.03, 420.0),
+    ("payments", "bank-gateway", 9.0, 0.06, 380.0),
+    ("users", "postgres", 70.0, 0.0005, 8.0),
+];
+
+/// (consumer group, topic, base lag, lag growth per minute)
+const CONSUMER_GROUPS: [(&str, &str, f64, f64); 4] = [
+    ("orders-processor", "orders", 120.0, 0.0),
+    ("billing", "payments", 900.0, 25.0),
+    ("search-indexer", "catalog", 4_000.0, 120.0),
+    ("audit-log", "events", 15.0, 0.0),
+];
+
+/// (namespace, ingress or upstream cluster, requests per second, 5xx ratio, p95 latency in ms)
+const INGRESSES: [(&str, &str, f64, f64, f64); 4] = [
+    ("shop", "storefront", 180.0, 0.002, 120.0),
+    ("shop", "checkout", 35.0, 0.03, 640.0),
+    ("platform", "grafana", 4.0, 0.0, 90.0),
+    ("platform", "auth", 60.0, 0.08, 1_400.0),
+];
+
+/// (namespace, pod, CPU % of limit, memory % of limit, restarts in the last hour)
+const PODS: [(&str, &str, f64, f64, f64); 4] = [
+    ("shop", "checkout-7d9f8-x2x1q", 45.0, 99.0, 4.0),
+    ("shop", "storefront-5c6b7-lk2p9", 92.0, 55.0, 0.0),
+    ("platform", "auth-6f4d2-qq8rt", 30.0, 83.0, 1.0),
+    ("platform", "grafana-0", 8.0, 40.0, 0.0),
+];
+
+/// (instance, gpu index, utilization %, framebuffer used %, temperature in °C)
+const GPUS: [(&str, &str, f64, f64, f64); 4] = [
+    ("trainer-0:9400", "0", 97.0, 93.0, 84.0),
+    ("trainer-0:9400", "1", 95.0, 88.0, 78.0),
+    ("trainer-1:9400", "0", 12.0, 99.0, 61.0),
+    ("trainer-1:9400", "1", 0.0, 2.0, 35.0),
+];
+
+/// Labels of the mock's log stream.
+const MOCK_STREAM_LABELS: [(&str, &str); 2] = [("service_name", "demo"), ("job", "rustdash-mock")];
+
+/// Milliseconds between log slots; each slot may or may not produce a line.
+const LOG_SLOT_MS: i64 = 700;
+
+/// `job` label of the mock's ingress access log stream.
+const INGRESS_LOG_JOB: &str = "ingress-nginx";
+
+/// Log templates by level: info 0..5, debug 5, warn 6..9, error 9..12.
+const LOG_TEMPLATES: [(&str, &str); 12] = [
+    ("INFO", "GET {uri} 200 {ms}ms user={n}"),
+    ("INFO", "POST {uri} 201 {ms}ms order={n}"),
+    ("INFO", "Cache refresh completed in {ms}ms ({n} keys)"),
+    ("INFO", r#"{"msg":"request completed","uri":"{uri}","status":200,"duration_ms":{ms},"user":{n}}"#),
+    ("INFO", "Published event order.created id={n} trace_id=4bf92f3577b34da6a3ce929d0e0e{n}"),
+    ("DEBUG", "Connection pool stats active={n} idle=4"),
+    ("WARN", "Slow query on orders table took {ms}ms"),
+    ("WARN", "Retrying upstream call to inventory (attempt {n})"),
+    ("WARN", "Rate limit at 80% for client api-key-{n} on {uri}"),
+    ("ERROR", "Payment provider timeout after {ms}ms order={n}"),
+    ("ERROR", "Failed to render report: connection reset by peer"),
+    (
+        "ERROR",
+        "Unhandled exception in {uri}: IllegalStateException: order {n} already settled \
+         at OrderService.settle(OrderService.java:214) at OrderController.pay(OrderController.java:88) \
+         at DispatcherServlet.doDispatch(DispatcherServlet.java:1067)",
+    ),
+];
+
+/// A running mock server. Both APIs are served from `base_url`; the server
+/// stops when this is dropped.
+pub struct MockBackend {
+    pub base_url: String,
+    handle: JoinHandle<()>,
+    controls: Arc<Controls>,
+}
+
+/// Faults a test can inject into a running mock.
+#[derive(Debug)]
+struct Controls {
+    available: AtomicBool,   // Cleared to simulate an outage
+    authorized: AtomicBool,  // Cleared to reject every request's credentials
+    clock_offset: AtomicI64, // Seconds the mock's clock runs ahead, in `time()` and Date headers
+    max_query_length: AtomicI64, // Longest log query range Loki accepts, in seconds; 0 for no limit
+}
+
+impl Drop for MockBackend {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl MockBackend {
+    /// Start the mock on an ephemeral localhost port.
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+
+        let controls = Arc::new(Controls {
+            available: AtomicBool::new(true),
+            authorized: AtomicBool::new(true),
+            clock_offset: AtomicI64::new(0),
+            max_query_length: AtomicI64::new(0),
+        });
+
+        let shared = controls.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let controls = shared.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, &controls).await;
+                });
+            }
+        });
+
+        Ok(Self { base_url, handle, controls })
+    }
+
+    /// Simulate an outage: while unavailable, every request fails with a 503.
+    pub fn set_available(&self, available: bool) {
+        self.controls.available.store(available, Ordering::Relaxed);
+    }
+
+    /// Simulate revoked credentials: while unauthorized, every request fails
+    /// with a 401.
+    pub fn set_authorized(&self, authorized: bool) {
+        self.controls.authorized.store(authorized, Ordering::Relaxed);
+    }
+
+    /// Simulate a server whose clock runs `seconds` ahead (or behind, when
+    /// negative) of the local one.
+    pub fn set_clock_offset(&self, seconds: i64) {
+        self.controls.clock_offset.store(seconds, Ordering::Relaxed);
+    }
+
+    /// Reject log queries spanning more than `seconds` the way Loki's
+    /// `max_query_length` does; 0 lifts the limit.
+    pub fn set_max_query_length(&self, seconds: i64) {
+        self.controls.max_query_length.store(seconds, Ordering::Relaxed);
+    }
+
+    /// Preset panels backed by series the mock serves.
+    pub fn demo_panels() -> Vec<PanelConfig> {
+        vec![
+            PanelConfig {
+                title: Some("Kafka consumer lag".to_string()),
+                warn: None,
+                critical: None,
+                endpoints: Default::default(),
+                aggregate: Default::default(),
+                truncate: Default::default(),
+                repeat: None,
+                forecast: None,
+                preset: Preset::QueueLag { flavor: QueueFlavor::Kafka },
+            },
+            PanelConfig {
+                title: None,
+                warn: None,
+                critical: None,
+                endpoints: Default::default(),
+                aggregate: Default::default(),
+                truncate: Default::default(),
+                repeat: None,
+                forecast: None,
+                preset: Preset::Ingress {
+                    flavor: IngressFlavor::Nginx,
+                    signal: IngressSignal::ErrorRate,
+                },
+            },
+            PanelConfig {
+                title: None,
+                warn: None,
+                critical: None,
+                endpoints: Default::default(),
+                aggregate: Default::default(),
+                truncate: Truncation::Middle,
+                repeat: None,
+                forecast: Some(100.0),
+                preset: Preset::Containers { signal: ContainerSignal::Memory },
+            },
+            PanelConfig {
+                title: Some("Error budget remaining".to_string()),
+                warn: Some(25.0),
+                critical: Some(10.0),
+                endpoints: Default::default(),
+                aggregate: Default::default(),
+                truncate: Default::default(),
+                repeat: None,
+                forecast: None,
+                preset: Preset::Gauge {
+                    query: "slo:error_budget_remaining:percent".to_string(),
+                    unit: "%".to_string(),
+                    min: 0.0,
+                    max: 100.0,
+                },
+            },
+            PanelConfig {
+                title: Some("Pods by memory use".to_string()),
+                warn: Some(80.0),
+                critical: Some(95.0),
+                endpoints: Default::default(),
+                aggregate: Default::default(),
+                truncate: Default::default(),
+                repeat: None,
+                forecast: None,
+                preset: Preset::Table {
+                    query: "100 * sum by (namespace, pod) (container_memory_working_set_bytes) / sum by (namespace, pod) (container_spec_memory_limit_bytes)".to_string(),
+                    columns: vec!["namespace".to_string(), "pod".to_string()],
+                    unit: String::new(),
+                    format: ValueFormat::Percent,
+                    sort: None,
+                    ascending: false,
+                    limit: 10,
+                },
+            },
+            PanelConfig {
+                title: Some("Requests by URI".to_string()),
+                warn: None,
+                critical: None,
+                endpoints: Default::default(),
+                aggregate: Default::default(),
+                truncate: Default::default(),
+                repeat: None,
+                forecast: None,
+                preset: Preset::Counter {
+                    metric: "http_requests_total".to_string(),
+                    by: vec!["uri".to_string()],
+                    labels: Default::default(),
+                    window: "5m".to_string(),
+                    view: CounterView::Rate,
+                },
+            },
+            PanelConfig {
+                title: Some("Request latency".to_string()),
+                warn: None,
+                critical: None,
+                endpoints: Default::default(),
+                aggregate: Default::default(),
+                truncate: Default::default(),
+                repeat: None,
+                forecast: None,
+                preset: Preset::Heatmap {
+                    metric: "http_server_requests_seconds_bucket".to_string(),
+                    labels: Default::default(),
+                },
+            },
+        ]
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, controls: &Controls) -> Result<()> {
+    // Read the request head; GET requests have no body
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") && buffer.len() < 64 * 1024 {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&buffer);
+    let method = head.split_whitespace().next().unwrap_or("GET");
+    let target = head.split_whitespace().nth(1).unwrap_or("/");
+    let clock = now_seconds() + controls.clock_offset.load(Ordering::Relaxed) as f64;
+    let (status, body) = match Url::parse(&format!("http://mock{}", target)) {
+        _ if !controls.available.load(Ordering::Relaxed) => ("503 Service Unavailable", json!({"status": "error", "error": "unavailable"})),
+        _ if !controls.authorized.load(Ordering::Relaxed) => ("401 Unauthorized", json!("no valid credentials")),
+        Ok(url) => route(method, &url, clock, controls),
+        Err(_) => ("400 Bad Request", json!({"status": "error", "error": "bad request"})),
+    };
+
+    // Tag every answer with a content hash and honour conditional requests,
+    // as Prometheus and Loki do behind most caching proxies
+    // A bare string is an error message, which both send as plain text
+    let body = match body {
+        _ if status.starts_with("204") => String::new(),
+        Value::String(message) => message,
+        body => body.to_string(),
+    };
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let etag = format!("\"{:x}\"", hasher.finish());
+    let if_none_match = head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("if-none-match").then(|| value.trim())
+    });
+    let (status, body) = if status.starts_with("200") && if_none_match == Some(etag.as_str()) {
+        ("304 Not Modified", String::new())
+    } else {
+        (status, body)
+    };
+    let date = chrono::DateTime::from_timestamp(clock as i64, 0).unwrap_or_default();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nETag: {}\r\nDate: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        etag,
+        date.format("%a, %d %b %Y %H:%M:%S GMT"),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Answer a request; `clock` is the mock's own idea of the current time.
+fn route(method: &str, url: &Url, clock: f64, controls: &Controls) -> (&'static str, Value) {
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let query = param("query").unwrap_or_default();
+    let now = now_seconds();
+
+    match url.path() {
+        // Admin endpoints only take POST (or PUT), as in Prometheus
+        path if path.starts_with("/api/v1/admin/") && method == "GET" => (
+            "405 Method Not Allowed",
+            json!({"status": "error", "error": "method not allowed"}),
+        ),
+        "/api/v1/admin/tsdb/snapshot" => (
+            "200 OK",
+            json!({"status": "success", "data": {"name": format!("{}-mock", chrono::DateTime::from_timestamp(clock as i64, 0).unwrap_or_default().format("%Y%m%dT%H%M%SZ"))}}),
+        ),
+        "/api/v1/admin/tsdb/clean_tombstones" => ("204 No Content", Value::Null),
+        "/loki/api/v1/delete" if method != "POST" => ("405 Method Not Allowed", json!("method not allowed")),
+        "/loki/api/v1/delete" if query.is_empty() || param("start").is_none() => ("400 Bad Request", json!("query and start are required")),
+        "/loki/api/v1/delete" => ("204 No Content", Value::Null),
+        "/api/v1/query" if query == "time()" => (
+            "200 OK",
+            json!({"status": "success", "data": {"resultType": "scalar", "result": [clock, clock.to_string()]}}),
+        ),
+        "/api/v1/query" => {
+            let response = prometheus_vector(&query, now);
+            ("200 OK", if param("stats").is_some() { with_stats(response, &query, 0.0, false) } else { response })
+        }
+        "/api/v1/query_range" => {
+            let start = param("start").and_then(|v| v.parse().ok()).unwrap_or(now - 3600.0);
+            let end = param("end").and_then(|v| v.parse().ok()).unwrap_or(now);
+            let step = param("step").and_then(|v| parse_step(&v)).unwrap_or(60.0);
+            let response = prometheus_matrix(&query, start, end, step);
+            ("200 OK", if param("stats").is_some() { with_stats(response, &query, end - start, false) } else { response })
+        }
+        "/api/v1/series" => {
+            let matchers: Vec<String> = url.query_pairs().filter(|(key, _)| key == "match[]").map(|(_, value)| value.into_owned()).collect();
+            let series: Vec<Value> = mock_series()
+                .into_iter()
+                .filter(|labels| matchers.iter().any(|matcher| series_matches(labels, matcher)))
+                .map(|labels| json!(labels))
+                .collect();
+            ("200 OK", json!({"status": "success", "data": series}))
+        }
+        "/api/v1/labels" => {
+            let names: BTreeSet<String> = mock_series().into_iter().flat_map(|labels| labels.into_keys()).collect();
+            ("200 OK", json!({"status": "success", "data": names}))
+        }
+        path if path.starts_with("/api/v1/label/") && path.ends_with("/values") => {
+            let name = &path["/api/v1/label/".len()..path.len() - "/values".len()];
+            let values: BTreeSet<String> = mock_series().into_iter().filter_map(|mut labels| labels.remove(name)).collect();
+            ("200 OK", json!({"status": "success", "data": values}))
+        }
+        "/api/v1/status/buildinfo" => (
+            "200 OK",
+            json!({"status": "success", "data": {"version": "2.53.0", "revision": "1f5c37a9d8b0e6c2", "branch": "mock"}}),
+        ),
+        "/api/v1/status/runtimeinfo" => {
+            // Up since the start of the previous day, so the uptime reads in days
+            let start = chrono::DateTime::from_timestamp((clock as i64 / 86400 - 1) * 86400, 0).unwrap_or_default();
+            (
+                "200 OK",
+                json!({"status": "success", "data": {"startTime": start.to_rfc3339(), "storageRetention": "15d", "goroutineCount": 42}}),
+            )
+        }
+        "/api/v1/status/tsdb" => (
+            "200 OK",
+            json!({"status": "success", "data": {"headStats": {"numSeries": 1204 + ENDPOINTS.len() * 40, "numLabelPairs": 380, "chunkCount": 5120}}}),
+        ),
+        "/api/v2/summary.json" => ("200 OK", status_page_summary()),
+        "/loki/api/v1/status/buildinfo" => ("200 OK", json!({"version": "3.1.0", "branch": "mock"})),
+        "/loki/api/v1/labels" => ("200 OK", json!({"status": "success", "data": ["job", "service_name"]})),
+        "/loki/api/v1/query_range" => {
+            let end = param("end").and_then(|v| parse_loki_time(&v)).unwrap_or(now);
+            let since = param("since").and_then(|v| parse_step(&v)).unwrap_or(3600.0);
+            let start = param("start").and_then(|v| parse_loki_time(&v)).unwrap_or(end - since);
+            if let Some(message) = loki_parse_error(&query) {
+                return ("400 Bad Request", json!(message));
+            }
+            let max_length = controls.max_query_length.load(Ordering::Relaxed) as f64;
+            if max_length > 0.0 && end - start > max_length {
+                let message = format!(
+                    "the query time range exceeds the limit (query length: {}s, limit: {}s)",
+                    end - start,
+                    max_length
+                );
+                return ("400 Bad Request", json!(message));
+            }
+            let response = if query.contains("count_over_time") {
+                let step = param("step").and_then(|v| parse_step(&v)).unwrap_or(60.0);
+                loki_volume(start, end, step)
+            } else {
+                let limit = param("limit").and_then(|v| v.parse().ok()).unwrap_or(100);
+                let forward = param("direction").as_deref() == Some("forward");
+                let ingress = query.contains(&format!("job=\"{}\"", INGRESS_LOG_JOB));
+                let limit = if ingress || selects_mock_stream(&query) { limit } else { 0 };
+                loki_streams(start, end, limit, forward, line_filter(&query).as_ref(), ingress)
+            };
+            ("200 OK", with_stats(response, &query, end - start, true))
+        }
+        _ => ("404 Not Found", json!({"status": "error", "error": "not found"})),
+    }
+}
+
+fn now_seconds() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Loki accepts nanosecond or second timestamps.
+fn parse_loki_time(value: &str) -> Option<f64> {
+    let number: f64 = value.parse().ok()?;
+    Some(if number > 1e12 { number / 1e9 } else { number })
+}
+
+/// Steps are given in seconds, optionally with an `s`/`m`/`h`/`d` suffix.
+fn parse_step(value: &str) -> Option<f64> {
+    let (number, unit) = match value.char_indices().last()? {
+        (i, 's') => (&value[..i], 1.0),
+        (i, 'm') => (&value[..i], 60.0),
+        (i, 'h') => (&value[..i], 3600.0),
+        (i, 'd') => (&value[..i], 86400.0),
+        _ => (value, 1.0),
+    };
+    number.parse::<f64>().ok().map(|n| n * unit)
+}
+
+/// SplitMix64, for deterministic noise without a rand dependency.
+fn noise(seed: u64) -> f64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z % 10_000) as f64 / 10_000.0
+}
+
+/// Latency (ms) and request rate (per second) of an endpoint at time `t`.
+fn endpoint_sample(index: usize, t: f64) -> (f64, f64) {
+    let (_, latency, rate) = ENDPOINTS[index];
+    let phase = index as f64 * 1.7;
+    let wave = (t / 90.0 + phase).sin();
+    let jitter = noise((t / 5.0) as u64 * 31 + index as u64);
+    (
+        latency * (1.0 + 0.35 * wave + 0.2 * jitter),
+        rate * (1.0 + 0.25 * (t / 120.0 + phase).cos() + 0.1 * jitter),
+    )
+}
+
+/// Cumulative request count and seconds spent of an endpoint at time `t`,
+/// counted since the mock's epoch. Integrates the waves of `endpoint_sample`
+/// (without the jitter), so the counters only ever grow.
+fn endpoint_totals(index: usize, t: f64) -> (f64, f64) {
+    let (_, latency, rate) = ENDPOINTS[index];
+    let phase = index as f64 * 1.7;
+    let t = t - COUNTER_EPOCH;
+    (
+        rate * (t + 30.0 * (t / 120.0 + phase).sin()),
+        rate * latency / 1000.0 * (t - 31.5 * (t / 90.0 + phase).cos()),
+    )
+}
+
+/// Average response size of an endpoint in bytes.
+fn response_bytes(index: usize) -> f64 {
+    match ENDPOINTS[index].0 {
+        "/api/reports/export" => 2_500_000.0,
+        "/health" => 15.0,
+        _ => 600.0 + 900.0 * index as f64,
+    }
+}
+
+/// Share of an endpoint's requests that fail with a 5xx at time `t`.
+fn error_ratio(index: usize, t: f64) -> f64 {
+    0.005 + 0.08 * noise((t / 60.0) as u64 * 17 + index as u64 * 97)
+}
+
+/// Value of `query` at time `t`, one entry per resulting series.
+fn evaluate(query: &str, t: f64) -> Vec<(Value, f64)> {
+    // `offset 5m` evaluates the whole query that far in the past
+    let t = t - query
+        .split(" offset ")
+        .nth(1)
+        .and_then(|rest| parse_step(rest.split(|c: char| !c.is_ascii_alphanumeric()).next()?))
+        .unwrap_or(0.0);
+    let errors_only = query.contains("status=~\"5..\"");
+    
+    if query.contains("traces_service_graph") {
+        return service_graph(query, t);
+    }
+    if query.contains("kafka_consumergroup_lag") {
+        return consumer_lag(t);
+    }
+    if query.contains("nginx_ingress_controller") || query.contains("envoy_cluster_") {
+        return ingress(query, t);
+    }
+    if query.contains("container_") {
+        return containers(query, t);
+    }
+    if query.contains("DCGM_FI_DEV") {
+        return gpus(query, t);
+    }
+    if query.contains("checkout_requests_failed_total") {
+        return checkout_error_ratio(query, t);
+    }
+    if query.contains("slo:error_budget_remaining:percent") {
+        // A 30-day budget slowly spent, topped up when the window rolls over
+        return vec![(json!({"slo": "checkout-availability"}), 100.0 - (t % (30.0 * 86400.0)) / (30.0 * 86400.0) * 90.0)];
+    }
+    if query.contains("_bucket") {
+        return latency_buckets(t);
+    }
+    if query.contains("prometheus_tsdb_wal_storage_size_bytes") {
+        // Grows through each two-hour head block, then truncates
+        let bytes = 24.0 * 1024.0 * 1024.0 * (1.0 + (t % 7200.0) / 7200.0);
+        return vec![(json!({"job": "prometheus"}), bytes.round())];
+    }
+    let uri_filter = query
+        .split("uri=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next());
+    let matching = || {
+        ENDPOINTS
+            .iter()
+            .enumerate()
+            .filter(move |(_, (uri, _, _))| uri_filter.is_none_or(|filter| filter == *uri))
+    };
+    let is_rate = query.contains("rate(");
+    // `increase(x[5m])` is what the counter grew by over the window
+    let increase_window = query
+        .contains("increase(")
+        .then(|| parse_step(query.split('[').nth(1)?.split(']').next()?))
+        .flatten();
+
+    if query.contains("response_size_bytes_sum") {
+        // Bytes served per second; exports are big, health checks tiny
+        let grouped = query.contains("by(uri)") || query.contains("by (uri)");
+        let values: Vec<_> = matching()
+            .map(|(i, (uri, _, _))| (json!({"uri": uri}), endpoint_sample(i, t).1 * response_bytes(i)))
+            .collect();
+        if grouped {
+            values
+        } else {
+            vec![(json!({}), values.iter().map(|(_, v)| v).sum())]
+        }
+    } else if query.contains("seconds_sum") && query.contains("seconds_count") {
+        // Average latency per URI, in seconds; unaggregated, the second instance is slower
+        if !query.contains("sum(") && !query.contains("sum by") {
+            return matching()
+                .flat_map(|(i, (uri, _, _))| {
+                    INSTANCES.iter().enumerate().map(move |(n, (instance, _))| {
+                        (instance_labels(uri, instance), endpoint_sample(i, t).0 / 1000.0 * (0.9 + 0.25 * n as f64))
+                    })
+                })
+                .collect();
+        }
+        matching()
+            .map(|(i, (uri, _, _))| (json!({"uri": uri}), endpoint_sample(i, t).0 / 1000.0))
+            .collect()
+    } else if query.contains("seconds_sum") {
+        matching()
+            .map(|(i, (uri, _, _))| (json!({"uri": uri}), endpoint_totals(i, t).1))
+            .collect()
+    } else if query.contains("seconds_count") || query.contains("http_requests_total") {
+        let grouped = query.contains("by(uri)") || query.contains("by (uri)");
+        let values: Vec<_> = matching()
+            .map(|(i, (uri, _, _))| {
+                let rate = endpoint_sample(i, t).1 * if errors_only { error_ratio(i, t) } else { 1.0 };
+                // Non-rate queries return an ever-growing counter
+                let counter = |t| endpoint_totals(i, t).0 * if errors_only { 0.02 } else { 1.0 };
+                let value = match increase_window {
+                    Some(window) => counter(t) - counter(t - window),
+                    None if is_rate => rate,
+                    None => counter(t),
+                };
+                (json!({"uri": uri}), value)
+            })
+            .collect();
+        if grouped {
+            values
+        } else if !query.contains("sum(") && !query.contains("count(") {
+            // Unaggregated: each instance's share of the endpoint's series
+            values
+                .iter()
+                .flat_map(|(labels, value)| {
+                    let uri = labels["uri"].as_str().unwrap_or_default();
+                    INSTANCES.iter().map(move |(instance, share)| (instance_labels(uri, instance), value * share))
+                })
+                .collect()
+        } else {
+            vec![(json!({}), values.iter().map(|(_, v)| v).sum())]
+        }
+    } else {
+        Vec::new()
+    }
+}
+
+/// Full label set of one instance's series for `uri`.
+fn instance_labels(uri: &str, instance: &str) -> Value {
+    json!({"uri": uri, "instance": instance, "job": "demo-api", "method": "GET", "outcome": "SUCCESS"})
+}
+
+/// Upper bounds of the mock's request latency histogram, in seconds.
+const LATENCY_BUCKETS: [f64; 12] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, f64::INFINITY];
+
+/// Cumulative rate per latency bucket of a bimodal workload: cache hits
+/// around 20ms and misses around 800ms, whose share swells and fades.
+fn latency_buckets(t: f64) -> Vec<(Value, f64)> {
+    let total = 120.0 * (1.0 + 0.2 * (t / 200.0).sin());
+    let slow_share = 0.25 + 0.2 * (t / 300.0).sin();
+    // Share of a log-normal mode at or under `le`
+    let under = |le: f64, median: f64| {
+        if le.is_infinite() {
+            return 1.0;
+        }
+        let z = (le / median).ln() / 0.5;
+        1.0 / (1.0 + (-1.7 * z).exp())
+    };
+    LATENCY_BUCKETS
+        .iter()
+        .map(|le| {
+            let share = (1.0 - slow_share) * under(*le, 0.02) + slow_share * under(*le, 0.8);
+            let label = if le.is_infinite() { "+Inf".to_string() } else { le.to_string() };
+            (json!({"le": label}), total * share)
+        })
+        .collect()
+}
+
+/// Tempo service graph series: request and failure rates, or p95 latency in seconds.
+fn service_graph(query: &str, t: f64) -> Vec<(Value, f64)> {
+    SERVICE_EDGES
+        .iter()
+        .enumerate()
+        .map(|(i, (client, server, rate, failure, p95))| {
+            let wave = 1.0 + 0.3 * (t / 150.0 + i as f64).sin();
+            let value = if query.contains("histogram_quantile") {
+                p95 * wave / 1000.0
+            } else if query.contains("failed") {
+                rate * failure * wave * (1.0 + noise((t / 30.0) as u64 + i as u64))
+            } else {
+                rate * wave
+            };
+            (json!({"client": client, "server": server}), value)
+        })
+        .collect()
+}
+
+/// Share of failed checkout requests over the query's window: a steady
+/// trickle, and every half hour or so a burst that the short windows catch
+/// in full and the long ones dilute.
+fn checkout_error_ratio(query: &str, t: f64) -> Vec<(Value, f64)> {
+    let window = query.split('[').nth(1).and_then(|rest| parse_step(rest.split(']').next()?)).unwrap_or(300.0);
+    let burst = (t / 900.0).sin().max(0.0).powi(4);
+    vec![(json!({}), 0.0005 + burst * 0.04 * (300.0 / window).powf(0.3))]
+}
+
+/// Lag per consumer group; growing groups fall behind in a sawtooth, as if
+/// they occasionally catch up.
+fn consumer_lag(t: f64) -> Vec<(Value, f64)> {
+    CONSUMER_GROUPS
+        .iter()
+        .enumerate()
+        .map(|(i, (group, topic, base, growth))| {
+            let minutes_into_cycle = (t / 60.0) % 45.0;
+            let jitter = 1.0 + 0.2 * noise((t / 15.0) as u64 * 13 + i as u64);
+            let lag = (base + growth * minutes_into_cycle) * jitter;
+            (json!({"consumergroup": group, "topic": topic}), lag.round())
+        })
+        .collect()
+}
+
+/// Ingress controller or Envoy series, already in the preset's unit: 5xx
+/// percentage, requests per second or p95 latency in ms.
+fn ingress(query: &str, t: f64) -> Vec<(Value, f64)> {
+    INGRESSES
+        .iter()
+        .enumerate()
+        .map(|(i, (namespace, name, rate, errors, p95))| {
+            let wave = 1.0 + 0.2 * (t / 100.0 + i as f64).sin() + 0.1 * noise((t / 15.0) as u64 * 7 + i as u64);
+            let value = if query.contains("histogram_quantile") {
+                p95 * wave
+            } else if query.contains("5..") || query.contains("response_code_class") {
+                errors * wave * 100.0
+            } else {
+                rate * wave
+            };
+            let labels = if query.contains("envoy_cluster_") {
+                json!({"envoy_cluster_name": format!("{}_{}", namespace, name)})
+            } else {
+                json!({"namespace": namespace, "ingress": name})
+            };
+            (labels, value)
+        })
+        .collect()
+}
+
+/// cAdvisor series, already in the preset's unit: % of the limit or restarts.
+fn containers(query: &str, t: f64) -> Vec<(Value, f64)> {
+    PODS.iter()
+        .enumerate()
+        .map(|(i, (namespace, pod, cpu, memory, restarts))| {
+            let wave = 1.0 + 0.03 * (t / 80.0 + i as f64).sin();
+            let value = if query.contains("restarts") {
+                *restarts
+            } else if query.contains("cpu") {
+                cpu * wave
+            } else {
+                memory * wave
+            };
+            (json!({"namespace": namespace, "pod": pod}), value)
+        })
+        .collect()
+}
+
+/// DCGM exporter series, already in the preset's unit.
+fn gpus(query: &str, t: f64) -> Vec<(Value, f64)> {
+    GPUS.iter()
+        .enumerate()
+        .map(|(i, (instance, gpu, utilization, memory, temperature))| {
+            let wave = 1.0 + 0.02 * (t / 60.0 + i as f64).sin();
+            let value = if query.contains("GPU_TEMP") {
+                temperature * wave
+            } else if query.contains("FB_USED") {
+                *memory
+            } else {
+                (utilization * wave).min(100.0)
+            };
+            (json!({"instance": instance, "gpu": gpu}), value)
+        })
+        .collect()
+}
+
+/// Label sets of the series the mock answers queries about, for the
+/// metadata endpoints behind the PromQL editor's completions.
+fn mock_series() -> Vec<BTreeMap<String, String>> {
+    let series = |name: &str, labels: &[(&str, &str)]| {
+        let mut set: BTreeMap<String, String> = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        set.insert("__name__".to_string(), name.to_string());
+        set
+    };
+    let mut all = Vec::new();
+    for (uri, _, _) in ENDPOINTS {
+        for status in ["200", "500"] {
+            let labels = [("job", "shop"), ("instance", "shop:8080"), ("method", "GET"), ("uri", uri), ("status", status)];
+            for name in ["http_requests_total", "http_server_requests_seconds_count", "http_server_requests_seconds_sum"] {
+                all.push(series(name, &labels));
+            }
+        }
+    }
+    for (group, topic, _, _) in CONSUMER_GROUPS {
+        all.push(series("kafka_consumergroup_lag", &[("consumergroup", group), ("topic", topic)]));
+    }
+    for (namespace, pod, _, _, _) in PODS {
+        all.push(series("container_cpu_usage_seconds_total", &[("namespace", namespace), ("pod", pod), ("container", "app")]));
+    }
+    all.push(series("up", &[("job", "shop"), ("instance", "shop:8080")]));
+    all.push(series("up", &[("job", "prometheus"), ("instance", "localhost:9090")]));
+    all
+}
+
+/// Whether `labels` is selected by `matcher`: its metric name and any
+/// `label="value"` equality matchers.
+fn series_matches(labels: &BTreeMap<String, String>, matcher: &str) -> bool {
+    let (name, rest) = matcher.split_once('{').unwrap_or((matcher, ""));
+    let name = name.trim();
+    if !name.is_empty() && labels.get("__name__").map(String::as_str) != Some(name) {
+        return false;
+    }
+    rest.trim_end_matches('}')
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .all(|(label, value)| labels.get(label.trim()).map(String::as_str) == Some(value.trim().trim_matches('"')))
+}
+
+fn prometheus_vector(query: &str, now: f64) -> Value {
+    let result: Vec<Value> = evaluate(query, now)
+        .into_iter()
+        .map(|(metric, value)| json!({"metric": metric, "value": [now, value.to_string()]}))
+        .collect();
+    json!({"status": "success", "data": {"resultType": "vector", "result": result}})
+}
+
+fn prometheus_matrix(query: &str, start: f64, end: f64, step: f64) -> Value {
+    let mut series: Vec<(Value, Vec<Value>)> = Vec::new();
+    let mut t = start;
+    while t <= end && step > 0.0 {
+        for (i, (metric, value)) in evaluate(query, t).into_iter().enumerate() {
+            if series.len() <= i {
+                series.push((metric, Vec::new()));
+            }
+            series[i].1.push(json!([t, value.to_string()]));
+        }
+        t += step;
+    }
+    let result: Vec<Value> = series
+        .into_iter()
+        .map(|(metric, values)| json!({"metric": metric, "values": values}))
+        .collect();
+    json!({"status": "success", "data": {"resultType": "matrix", "result": result}})
+}
+
+/// The log line produced in a slot, if any. Density rises and falls over time.
+fn log_line(slot: i64) -> Option<(&'static str, String)> {
+    let t = (slot * LOG_SLOT_MS) as f64 / 1000.0;
+    let density = 0.55 + 0.4 * (t / 300.0).sin();
+    let roll = noise(slot as u64);
+    if roll > density {
+        return None;
+    }
+
+    // Errors and warnings are rarer than info lines
+    let pick = noise(slot as u64 ^ 0xABCD);
+    let template = match pick {
+        p if p < 0.05 => 9 + (slot % 3) as usize,
+        p if p < 0.15 => 6 + (slot % 3) as usize,
+        p if p < 0.25 => 5,
+        _ => (slot % 5) as usize,
+    };
+    let (level, text) = LOG_TEMPLATES[template];
+    let (uri, _, _) = ENDPOINTS[(slot % ENDPOINTS.len() as i64) as usize];
+    // Logs name the concrete path a templated route was called with
+    let uri = uri.replace("{id}", &(slot % 997).to_string());
+    let message = text
+        .replace("{uri}", &uri)
+        .replace("{ms}", &((noise(slot as u64 * 7) * 900.0) as u64 + 5).to_string())
+        .replace("{n}", &(slot % 10_000).to_string());
+    Some((level, format!("[{}] {}", level, message)))
+}
+
+/// Whether a log query's exact-match labels fit the mock's only stream.
+fn selects_mock_stream(query: &str) -> bool {
+    MOCK_STREAM_LABELS.iter().all(|(label, value)| {
+        match query.split(&format!("{}=\"", label)).nth(1) {
+            Some(rest) => rest.split('"').next() == Some(value),
+            None => true,
+        }
+    })
+}
+
+/// A Statuspage summary.json of a dependency with an incident going on.
+fn status_page_summary() -> Value {
+    json!({
+        "page": {"name": "Payments"},
+        "status": {"indicator": "minor", "description": "Partially Degraded Service"},
+        "components": [
+            {"name": "API", "status": "degraded_performance", "group": false},
+            {"name": "Dashboard", "status": "operational", "group": false},
+            {"name": "Regions", "status": "partial_outage", "group": true},
+        ],
+        "incidents": [{"name": "Elevated card authorization latency", "status": "investigating"}],
+    })
+}
+
+/// Loki's answer to a few queries it can't parse: ones that don't start
+/// with a selector, and `line_format` templates with an unclosed action.
+fn loki_parse_error(query: &str) -> Option<String> {
+    if !query.starts_with('{') && !query.contains('(') {
+        return Some("parse error at line 1, col 1: syntax error: unexpected IDENTIFIER".to_string());
+    }
+    let at = query.find("line_format")?;
+    let template = &query[at..];
+    (template.matches("{{").count() > template.matches("}}").count())
+        .then(|| format!("parse error at line 1, col {}: template: line:1: unclosed action", at + 1))
+}
+
+/// The regex of a `|~ "..."` line filter in a log query, if it has one.
+fn line_filter(query: &str) -> Option<Regex> {
+    let quoted = query.split("|~ \"").nth(1)?;
+    let mut pattern = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next()? {
+                'n' => pattern.push('\n'),
+                escaped => pattern.push(escaped),
+            },
+            _ => pattern.push(c),
+        }
+    }
+    Regex::new(&pattern).ok()
+}
+
+/// Access log line of the ingress in front of the demo service, if one was
+/// logged in `slot`; requests come in bursts independent of the app's lines.
+fn access_log_line(slot: i64) -> Option<(&'static str, String)> {
+    if noise(slot as u64 ^ 0x5EED) > 0.45 {
+        return None;
+    }
+    let (uri, _, _) = ENDPOINTS[(slot as u64 / 3 % ENDPOINTS.len() as u64) as usize];
+    let uri = uri.replace("{id}", &(slot % 997).to_string());
+    let status = if noise(slot as u64 * 13) < 0.04 { 502 } else { 200 };
+    let ms = (noise(slot as u64 * 11) * 400.0) as u64 + 3;
+    let line = format!(
+        "10.0.{}.{} - - \"GET {} HTTP/1.1\" {} {} {}ms",
+        slot % 4,
+        slot % 250 + 2,
+        uri,
+        status,
+        (noise(slot as u64 * 17) * 9000.0) as u64 + 120,
+        ms
+    );
+    Some((if status >= 500 { "ERROR" } else { "INFO" }, line))
+}
+
+fn loki_streams(start: f64, end: f64, limit: usize, forward: bool, filter: Option<&Regex>, ingress: bool) -> Value {
+    // Newest first by default, like direction=backward; oldest first for forward
+    let first_slot = (start * 1000.0) as i64 / LOG_SLOT_MS;
+    let last_slot = (end.min(now_seconds()) * 1000.0) as i64 / LOG_SLOT_MS;
+    let slots: Box<dyn Iterator<Item = i64>> = if forward {
+        Box::new(first_slot..=last_slot)
+    } else {
+        Box::new((first_slot..=last_slot).rev())
+    };
+    let values: Vec<Value> = slots
+        .filter_map(|slot| {
+            let line = if ingress { access_log_line(slot) } else { log_line(slot) };
+            let (_, line) = line.filter(|(_, line)| filter.is_none_or(|regex| regex.is_match(line)))?;
+            // Access log lines land between the app's
+            let nanos = (slot as i128 * LOG_SLOT_MS as i128 + if ingress { LOG_SLOT_MS as i128 / 2 } else { 0 }) * 1_000_000;
+            Some(json!([nanos.to_string(), line]))
+        })
+        .take(limit)
+        .collect();
+    let stream: HashMap<&str, &str> = if ingress {
+        HashMap::from([("job", INGRESS_LOG_JOB)])
+    } else {
+        MOCK_STREAM_LABELS.iter().copied().collect()
+    };
+    json!({
+        "status": "success",
+        "data": {
+            "resultType": "streams",
+            "result": [{"stream": stream, "values": values}],
+        }
+    })
+}
+
+/// Attach the execution stats the servers report: Prometheus' when asked
+/// for with `stats`, Loki's always. Longer ranges, quantiles and line
+/// filters take longer.
+fn with_stats(mut response: Value, query: &str, span: f64, loki: bool) -> Value {
+    let mut seconds = 0.002 + 0.004 * span / 3600.0;
+    if query.contains("histogram_quantile") {
+        seconds += 0.015;
+    }
+    if query.contains("|=") || query.contains("|~") {
+        seconds += 0.03;
+    }
+    response["data"]["stats"] = if loki {
+        json!({"summary": {"execTime": seconds, "totalBytesProcessed": (span * 2048.0) as u64}})
+    } else {
+        json!({"timings": {"evalTotalTime": seconds, "execTotalTime": seconds + 0.0002}, "samples": {"totalQueryableSamples": (span / 15.0) as u64}})
+    };
+    response
+}
+
+fn loki_volume(start: f64, end: f64, step: f64) -> Value {
+    let mut values = Vec::new();
+    let mut t = start;
+    while t <= end && step > 0.0 {
+        let from = ((t - step) * 1000.0) as i64 / LOG_SLOT_MS;
+        let to = (t * 1000.0) as i64 / LOG_SLOT_MS;
+        let count = (from..to).filter(|&slot| log_line(slot).is_some()).count();
+        if count > 0 {
+            values.push(json!([t, count.to_string()]));
+        }
+        t += step;
+    }
+    json!({
+        "status": "success",
+        "data": {"resultType": "matrix", "result": [{"metric": {}, "values": values}]}
+    })
+}
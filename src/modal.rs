@@ -0,0 +1,123 @@
+//! Overlays over the dashboard (menus, pickers, popups, confirmations and
+//! the value inspector) kept as a stack: the one opened last is drawn on top
+//! and gets the keys, and they are placed with [`centered`] rather than each
+//! working out its own rectangle. Each overlay's state stays in its own
+//! `AppState` field; the stack only orders what is open.
+
+use ratatui::layout::Rect;
+
+/// An overlay that can be open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModalKind {
+    Inspection, // Doesn't take keys; drawn under the others
+    AdminMenu,  // Also asks to confirm the chosen action
+    LogActionMenu,
+    LogDiff,
+    About,
+    QueryStats,
+    Audit,
+    Debug,
+    Variables,
+    SavedViews,
+    Checks,
+    Ping,
+    Changes,
+    Slos,
+    QueryEditor,
+    LogQueryBar,
+    Goto,
+    NotesPad,
+    Triage,
+    Palette,
+}
+
+impl ModalKind {
+    /// Every overlay, in the order they stack when opened together.
+    pub const ALL: [ModalKind; 20] = [
+        ModalKind::Inspection,
+        ModalKind::AdminMenu,
+        ModalKind::LogActionMenu,
+        ModalKind::LogDiff,
+        ModalKind::About,
+        ModalKind::QueryStats,
+        ModalKind::Audit,
+        ModalKind::Debug,
+        ModalKind::Variables,
+        ModalKind::SavedViews,
+        ModalKind::Checks,
+        ModalKind::Ping,
+        ModalKind::Changes,
+        ModalKind::Slos,
+        ModalKind::QueryEditor,
+        ModalKind::LogQueryBar,
+        ModalKind::Goto,
+        ModalKind::NotesPad,
+        ModalKind::Triage,
+        ModalKind::Palette,
+    ];
+
+    /// Whether keys go to the overlay while it is on top.
+    pub fn takes_keys(self) -> bool {
+        self != ModalKind::Inspection
+    }
+
+    /// Whether closing the full-area views closes it too; what is being
+    /// typed and the inspector stay.
+    pub fn closes_with_views(self) -> bool {
+        !matches!(
+            self,
+            ModalKind::Inspection
+                | ModalKind::QueryEditor
+                | ModalKind::LogQueryBar
+                | ModalKind::Goto
+                | ModalKind::NotesPad
+                | ModalKind::Palette
+        )
+    }
+}
+
+/// The open overlays, bottom first.
+#[derive(Debug, Clone, Default)]
+pub struct ModalStack {
+    order: Vec<ModalKind>,
+}
+
+impl ModalStack {
+    /// The stack given what is `open` now: overlays closed since drop out,
+    /// newly opened ones go on top.
+    pub fn arrange(&self, open: &[ModalKind]) -> Vec<ModalKind> {
+        let mut order: Vec<ModalKind> = self.order.iter().copied().filter(|kind| open.contains(kind)).collect();
+        order.extend(open.iter().filter(|kind| !self.order.contains(kind)));
+        // The inspector stays under the overlays that take keys
+        order.sort_by_key(|kind| kind.takes_keys());
+        order
+    }
+
+    /// Follow what is open, as [`ModalStack::arrange`] orders it.
+    pub fn sync(&mut self, open: &[ModalKind]) {
+        self.order = self.arrange(open);
+    }
+
+    /// The overlay on top that keys go to.
+    pub fn focused(&self) -> Option<ModalKind> {
+        self.order.iter().rev().copied().find(|kind| kind.takes_keys())
+    }
+
+    /// Open overlays, bottom first.
+    pub fn order(&self) -> &[ModalKind] {
+        &self.order
+    }
+}
+
+/// A `width` x `height` box in the middle of `area`, shrunk to fit.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let (width, height) = (width.min(area.width), height.min(area.height));
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+/// A `width` x `height` box centered across `area`, `top` lines from its top.
+pub fn near_top(area: Rect, width: u16, height: u16, top: u16) -> Rect {
+    let top = top.min(area.height);
+    let (width, height) = (width.min(area.width), height.min(area.height - top));
+    Rect::new(area.x + (area.width - width) / 2, area.y + top, width, height)
+}
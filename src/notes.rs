@@ -0,0 +1,64 @@
+//! Incident notes (`o`): timestamped notes typed during an incident, each
+//! annotated with what the dashboard was showing when it was written (time
+//! range, time cursor, active queries), and exported to Markdown for the
+//! postmortem.
+
+use crate::text::LineInput;
+use crate::ui::{format_time, AppState};
+use chrono::{DateTime, Local};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// One note and what the dashboard showed when it was taken.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub at: DateTime<Local>,
+    pub text: String,
+    pub context: Vec<String>, // e.g. "range 1h", "logs {app=\"api\"} |~ \"timeout\""
+}
+
+/// The notes pad popup while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct NotesPad {
+    pub input: LineInput,
+    pub scroll_offset: usize, // Notes scrolled up from the newest
+}
+
+/// What the dashboard is showing, to annotate a note with.
+pub fn context(state: &AppState) -> Vec<String> {
+    let mut context = vec![format!("range {}", state.metrics_time_range.as_str())];
+    if let Some(t) = state.time_cursor {
+        context.push(format!("cursor {}", format_time(t, "%Y-%m-%d %H:%M:%S")));
+    }
+    if let Some(query) = state.log_filter.logql() {
+        context.push(format!("logs {}", query));
+    }
+    if let Some(drill_in) = &state.drill_in {
+        context.push(format!("uri {}", drill_in.uri));
+    }
+    context
+}
+
+/// The notes as a Markdown timeline, oldest first.
+pub fn to_markdown(notes: &[Note]) -> String {
+    let mut markdown = String::from("## Incident notes\n\n");
+    if notes.is_empty() {
+        markdown.push_str("_No notes taken._\n");
+    }
+    for note in notes {
+        markdown.push_str(&format!("- **{}** {}\n", note.at.format("%Y-%m-%d %H:%M:%S"), note.text));
+        if !note.context.is_empty() {
+            let context: Vec<String> = note.context.iter().map(|item| format!("`{}`", item.replace('`', "'"))).collect();
+            markdown.push_str(&format!("  - {}\n", context.join(", ")));
+        }
+    }
+    markdown
+}
+
+/// Write the notes to `incident-notes-<date>-<time>.md` in `dir`.
+pub fn export(notes: &[Note], dir: &Path) -> io::Result<PathBuf> {
+    let path = dir.join(format!("incident-notes-{}.md", Local::now().format("%Y%m%d-%H%M%S")));
+    fs::write(&path, to_markdown(notes))?;
+    Ok(path)
+}
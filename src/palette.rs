@@ -0,0 +1,167 @@
+//! Command palette (`Ctrl+P`): one fuzzy-searchable list of what the
+//! dashboard can do and show (actions, with the key that also runs them;
+//! the views; the configured panels; the saved `[[log_queries]]`; and the
+//! URIs in the metrics table), run with Enter.
+
+use crate::fuzzy::{self, Match};
+use crate::text::LineInput;
+use crate::ui::AppState;
+
+/// Entries listed at once; the list scrolls past them.
+pub const VISIBLE_ENTRIES: usize = 12;
+
+/// Actions that have a key of their own, run by pressing it.
+pub const ACTIONS: &[(char, &str)] = &[
+    ('R', "Refresh all sources"),
+    ('r', "Refresh the focused panel"),
+    ('u', "Undo the last filter, time range or ranking change"),
+    ('Q', "Open the PromQL editor"),
+    ('/', "Open the LogQL query bar"),
+    ('H', "Show/hide ignored URIs and logs"),
+    ('t', "Show/hide log ages"),
+    ('#', "Show/hide log line numbers"),
+    (':', "Go to a log line or time"),
+    ('F', "Flush the log view"),
+    ('z', "Zoom the focused panel"),
+    ('A', "Open the admin menu"),
+    ('W', "Write an incident report"),
+    ('X', "Export the metrics to CSV"),
+    ('J', "Export the metrics to JSON"),
+    ('v', "Inspect values"),
+    ('q', "Quit"),
+];
+
+/// Full-screen views and popups, also opened by their key.
+pub const VIEWS: &[(char, &str)] = &[
+    ('m', "Service map"),
+    ('p', "Panels page"),
+    ('i', "Backend details"),
+    ('S', "Query timings"),
+    ('U', "Recent actions"),
+    ('C', "Synthetic checks"),
+    ('N', "Network latency"),
+    ('K', "Changes since the last refresh"),
+    ('B', "SLO burn rates"),
+    ('L', "Local system"),
+    ('o', "Incident notes"),
+    ('T', "Triage alerts"),
+    ('D', "Raw requests and responses"),
+    ('$', "Template variables"),
+    ('V', "Saved views"),
+];
+
+/// What choosing an entry does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Key(char),       // Press the key, as if typed
+    Panel(usize),    // Open the panels page at this panel
+    LogQuery(usize), // Show only this saved log query's lines
+    Uri(usize),      // Open the history chart of this metrics table row
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub kind: &'static str, // "action", "view", "panel", "query" or "uri"
+    pub label: String,      // What the search matches
+    pub hint: String,       // Shown dimmed: the key, or the query
+    pub target: Target,
+}
+
+impl Entry {
+    fn new(kind: &'static str, label: &str, hint: String, target: Target) -> Self {
+        Self { kind, label: label.to_string(), hint, target }
+    }
+}
+
+/// Everything on offer in the dashboard's current state. Actions for a
+/// backend that isn't configured, or with nothing to act on, are left out.
+pub fn entries(state: &AppState) -> Vec<Entry> {
+    let available = |key: char| match key {
+        'Q' | 'm' | 'p' => state.prometheus_enabled,
+        'X' | 'J' => state.metrics.as_ref().is_some_and(|metrics| !metrics.uri_metrics.is_empty()),
+        '/' => state.loki_enabled,
+        'A' => !state.admin_actions.is_empty() || state.loki_admin,
+        'C' => !state.synthetic_checks.is_empty(),
+        'N' => !state.ping_targets.is_empty(),
+        'B' => !state.slos.is_empty(),
+        '$' => !state.variables.is_empty(),
+        'u' => state.view_history.can_undo(),
+        ':' => !state.all_logs.is_empty(),
+        _ => true,
+    };
+    let key_entries = |kind, list: &[(char, &str)]| -> Vec<Entry> {
+        list.iter()
+            .filter(|(key, _)| available(*key))
+            .map(|&(key, label)| Entry::new(kind, label, key.to_string(), Target::Key(key)))
+            .collect()
+    };
+    let mut entries = key_entries("action", ACTIONS);
+    entries.extend(key_entries("view", VIEWS));
+    if state.prometheus_enabled {
+        entries.extend(state.panels.iter().enumerate().map(|(idx, panel)| {
+            Entry::new("panel", &panel.title, String::new(), Target::Panel(idx))
+        }));
+    }
+    if state.loki_enabled {
+        entries.extend(state.log_queries.iter().enumerate().map(|(idx, query)| {
+            Entry::new("query", &query.name, query.query.clone(), Target::LogQuery(idx))
+        }));
+    }
+    if let Some(metrics) = &state.metrics {
+        entries.extend(metrics.uri_metrics.iter().enumerate().map(|(idx, metric)| {
+            Entry::new("uri", &metric.uri, String::new(), Target::Uri(idx))
+        }));
+    }
+    entries
+}
+
+/// State of the palette while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct Palette {
+    pub input: LineInput,
+    pub entries: Vec<Entry>,
+    pub matches: Vec<(usize, Match)>, // Indices into entries, best first
+    pub selected: usize,              // Index into matches
+    pub scroll_offset: usize,         // First match shown
+}
+
+impl Palette {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let mut palette = Self { entries, ..Self::default() };
+        palette.update();
+        palette
+    }
+
+    /// Match the entries against the input. Ties keep the entries' order,
+    /// so with nothing typed the list reads actions, views, panels, queries, URIs.
+    pub fn update(&mut self) {
+        let pattern = &self.input.text;
+        self.matches = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| Some((idx, fuzzy::score(pattern, &entry.label)?)))
+            .collect();
+        self.matches.sort_by(|(a, x), (b, y)| y.score.cmp(&x.score).then(a.cmp(b)));
+        self.selected = 0;
+        self.scroll_offset = 0;
+    }
+
+    /// Move the selection, wrapping around, and scroll it into view.
+    pub fn select(&mut self, delta: isize) {
+        let len = self.matches.len() as isize;
+        if len == 0 {
+            return;
+        }
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if self.selected >= self.scroll_offset + VISIBLE_ENTRIES {
+            self.scroll_offset = self.selected + 1 - VISIBLE_ENTRIES;
+        }
+    }
+
+    pub fn chosen(&self) -> Option<&Entry> {
+        self.matches.get(self.selected).map(|(idx, _)| &self.entries[*idx])
+    }
+}
@@ -0,0 +1,897 @@
+//! Configured panels built from presets, shown on the panels page (`p`).
+//!
+//! Each preset expands into a single Prometheus range query whose series
+//! become the panel's rows: latest value, a trend sparkline and a severity
+//! from the panel's thresholds. A panel with `endpoints` runs that query on
+//! each of them through a [`Fanout`].
+//!
+//! The `stat` and `gauge` presets run a query of the user's own that
+//! yields a single value, shown as a big number (and a gauge bar).
+//!
+//! The `table` preset lists the series of the user's own query at the
+//! latest instant, one column per chosen label plus the value.
+//!
+//! Panels charting a counter (the `counter` preset, ingress request rates
+//! and container restarts) can be switched at runtime between its rate,
+//! its increase over the window and the raw counter; see [`CounterView`].
+//!
+//! Row-based panels with a `forecast` value also say when each row's trend
+//! reaches it; see [`forecast`].
+//!
+//! The `heatmap` preset instead turns a histogram's buckets into a
+//! [`Heatmap`]: observations per bucket and time slice, which shows the
+//! shape of a latency distribution (e.g. two modes) that a quantile hides.
+
+use crate::fanout::{Aggregate, Fanout, ENDPOINT_LABEL};
+use crate::forecast;
+use crate::prometheus::{PrometheusClient, PrometheusResponse};
+use crate::promql::{histogram_quantile, increase, rate, Expr, Selector};
+use crate::text::Truncation;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One `[[panels]]` entry from the config file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PanelConfig {
+    pub title: Option<String>,
+    pub warn: Option<f64>,     // Overrides the preset's warning threshold
+    pub critical: Option<f64>, // Overrides the preset's critical threshold
+    #[serde(default)]
+    pub endpoints: BTreeMap<String, String>, // Name -> Prometheus URL; empty queries the main endpoint
+    #[serde(default)]
+    pub aggregate: Aggregate, // How the endpoints' series are combined
+    #[serde(default)]
+    pub truncate: Truncation, // Where row labels too long for the page are cut
+    pub repeat: Option<String>, // Variable whose selected values each get a copy of the panel, side by side
+    pub forecast: Option<f64>, // Value the rows fill up to, e.g. 100 for disk usage in %; rows heading there say when
+    #[serde(flatten)]
+    pub preset: Preset,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "preset", rename_all = "snake_case")]
+pub enum Preset {
+    /// Consumer group lag or queue depth, depending on the exporter.
+    QueueLag {
+        #[serde(default)]
+        flavor: QueueFlavor,
+    },
+    /// Request rate, 5xx share or p95 latency per ingress or upstream cluster.
+    Ingress {
+        #[serde(default)]
+        flavor: IngressFlavor,
+        #[serde(default)]
+        signal: IngressSignal,
+    },
+    /// Container CPU or memory as a share of the limit, or restarts, per pod.
+    Containers {
+        #[serde(default)]
+        signal: ContainerSignal,
+    },
+    /// GPU utilization, memory or temperature per GPU, from the DCGM exporter.
+    Gpu {
+        #[serde(default)]
+        signal: GpuSignal,
+    },
+    /// A single value from the user's own query, shown as a big number.
+    Stat {
+        query: String, // PromQL returning one series, e.g. a recording rule
+        #[serde(default)]
+        unit: String,
+    },
+    /// Like `stat`, with a gauge bar filled between `min` and `max`.
+    Gauge {
+        query: String,
+        #[serde(default)]
+        unit: String,
+        #[serde(default)]
+        min: f64,
+        #[serde(default = "default_gauge_max")]
+        max: f64,
+    },
+    /// The series of the user's own query as a table with a column per label.
+    Table {
+        query: String,
+        columns: Vec<String>, // Labels shown, in order
+        #[serde(default)]
+        unit: String,
+        #[serde(default)]
+        format: ValueFormat,
+        #[serde(default)]
+        sort: Option<String>, // Column sorted by initially, a label or "value" (default)
+        #[serde(default)]
+        ascending: bool,
+        #[serde(default = "default_table_limit")]
+        limit: usize, // Rows shown
+    },
+    /// A counter of the user's choice, summed by some of its labels.
+    Counter {
+        metric: String, // e.g. `http_requests_total`
+        #[serde(default)]
+        by: Vec<String>, // Labels that make the rows; none sums everything
+        #[serde(default)]
+        labels: BTreeMap<String, String>, // Equality matchers narrowing the counter
+        #[serde(default = "default_counter_window")]
+        window: String, // Rate and increase window, e.g. "5m"
+        #[serde(default)]
+        view: CounterView, // How the panel starts out
+    },
+    /// Observations per histogram bucket over time.
+    Heatmap {
+        metric: String, // Bucket series, e.g. `http_server_requests_seconds_bucket`
+        #[serde(default)]
+        labels: BTreeMap<String, String>, // Equality matchers narrowing the histogram, e.g. one uri
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueFlavor {
+    #[default]
+    Kafka,            // danielqsj/kafka_exporter
+    KafkaLagExporter, // seglo/kafka-lag-exporter
+    Rabbitmq,         // RabbitMQ's Prometheus plugin or kbudde/rabbitmq_exporter
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngressFlavor {
+    #[default]
+    Nginx, // kubernetes/ingress-nginx controller metrics
+    Envoy, // Envoy's cluster stats, as exported by Envoy-based gateways and meshes
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngressSignal {
+    #[default]
+    ErrorRate, // Share of 5xx responses, in percent
+    RequestRate,
+    P95Latency,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerSignal {
+    #[default]
+    Memory,   // Working set as % of the memory limit (OOM risk)
+    Cpu,      // Usage as % of the CPU quota (throttling)
+    Restarts, // Restarts over the last hour, from kube-state-metrics
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuSignal {
+    #[default]
+    Utilization, // Busy time in %
+    Memory,      // Framebuffer used, as % of the total
+    Temperature, // Degrees Celsius
+}
+
+fn default_gauge_max() -> f64 {
+    100.0
+}
+
+fn default_table_limit() -> usize {
+    20
+}
+
+fn default_counter_window() -> String {
+    "5m".to_string()
+}
+
+/// How a counter is presented; `c` on the panels page cycles through them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterView {
+    #[default]
+    Rate,     // Per second, averaged over the window
+    Increase, // Added over the window
+    Raw,      // The counter itself, resets and all
+}
+
+impl CounterView {
+    pub fn next(self) -> Self {
+        match self {
+            CounterView::Rate => CounterView::Increase,
+            CounterView::Increase => CounterView::Raw,
+            CounterView::Raw => CounterView::Rate,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CounterView::Rate => "rate",
+            CounterView::Increase => "increase",
+            CounterView::Raw => "raw",
+        }
+    }
+}
+
+/// `selector`'s counter summed by `labels`, as `view` presents it.
+pub fn counter_expr(selector: Selector, window: &str, labels: &[&str], view: CounterView) -> Expr {
+    match view {
+        CounterView::Rate => rate(selector.range(window)).sum_by(labels),
+        CounterView::Increase => increase(selector.range(window)).sum_by(labels),
+        CounterView::Raw => Expr::from(selector).sum_by(labels),
+    }
+}
+
+/// How a table's values are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueFormat {
+    #[default]
+    Number,
+    Bytes,   // 1.5G
+    Percent, // Values already in percent
+    Seconds, // Durations, e.g. 250ms or 1.2s
+}
+
+/// Rate window of the ingress preset's queries.
+const INGRESS_RATE_WINDOW: &str = "5m";
+
+/// Rate window of the heatmap preset's bucket counters.
+const HEATMAP_RATE_WINDOW: &str = "1m";
+
+/// Severity of a row against its panel's thresholds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Ok,
+    Warn,
+    Critical,
+}
+
+#[derive(Debug, Clone)]
+pub struct PanelRow {
+    pub label: String,
+    pub value: f64,
+    pub trend: Vec<f64>, // Oldest first
+    pub severity: Severity,
+    pub full_in: Option<f64>, // Seconds until the trend reaches the panel's `forecast`
+}
+
+/// How a single-value panel is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatStyle {
+    Number,
+    Gauge { min: f64, max: f64 },
+}
+
+impl StatStyle {
+    /// How full the gauge is for `value`, from 0 to 1.
+    pub fn ratio(&self, value: f64) -> f64 {
+        match *self {
+            StatStyle::Gauge { min, max } if max > min => ((value - min) / (max - min)).clamp(0.0, 1.0),
+            StatStyle::Gauge { .. } => 0.0,
+            StatStyle::Number => 0.0,
+        }
+    }
+}
+
+/// A column to sort a table by, and which way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableSort {
+    pub column: usize, // Index into the label columns; their count means the value
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRow {
+    pub labels: Vec<String>, // One per column
+    pub value: f64,
+    pub severity: Severity,
+}
+
+/// Series of a table panel at the latest instant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>, // Label names
+    pub rows: Vec<TableRow>,
+    pub format: ValueFormat,
+    pub sort: TableSort, // As configured
+    pub limit: usize,
+}
+
+impl Table {
+    /// Sort key columns: the labels, then the value.
+    pub fn column_count(&self) -> usize {
+        self.columns.len() + 1
+    }
+
+    /// The configured sort moved `shift` columns to the right, reversed if
+    /// asked; what the panels page's `s` and `d` keys change.
+    pub fn sort_shifted(&self, shift: usize, reversed: bool) -> TableSort {
+        TableSort {
+            column: (self.sort.column + shift) % self.column_count(),
+            descending: self.sort.descending != reversed,
+        }
+    }
+
+    /// The first `limit` rows in `sort` order; labels compare as numbers
+    /// where both are numeric, ties keep the query's order.
+    pub fn sorted_rows(&self, sort: TableSort) -> Vec<&TableRow> {
+        let mut rows: Vec<&TableRow> = self.rows.iter().collect();
+        rows.sort_by(|a, b| {
+            let ordering = match (a.labels.get(sort.column), b.labels.get(sort.column)) {
+                (Some(a), Some(b)) => match (a.parse::<f64>(), b.parse::<f64>()) {
+                    (Ok(a), Ok(b)) => a.total_cmp(&b),
+                    _ => a.cmp(b),
+                },
+                _ => a.value.total_cmp(&b.value),
+            };
+            if sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        rows.truncate(self.limit);
+        rows
+    }
+}
+
+/// Observations per second of a histogram, per bucket and time slice.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Heatmap {
+    pub buckets: Vec<String>, // Upper bound of each bucket, lowest first, e.g. "≤ 250ms"
+    pub times: Vec<f64>,      // Unix seconds of each slice, oldest first
+    pub rates: Vec<Vec<f64>>, // rates[bucket][slice]
+}
+
+impl Heatmap {
+    /// Highest rate of any cell, for scaling the colors.
+    pub fn max_rate(&self) -> f64 {
+        self.rates.iter().flatten().copied().fold(0.0, f64::max)
+    }
+
+    /// Share of the window's observations that fell in each bucket.
+    pub fn shares(&self) -> Vec<f64> {
+        let totals: Vec<f64> = self.rates.iter().map(|row| row.iter().sum()).collect();
+        let sum: f64 = totals.iter().sum();
+        totals.iter().map(|total| if sum > 0.0 { total / sum } else { 0.0 }).collect()
+    }
+}
+
+/// Fetched state of one configured panel.
+#[derive(Debug, Clone)]
+pub struct PanelData {
+    pub title: String,
+    pub unit: String,
+    pub warn: f64,
+    pub critical: f64, // Below `warn` when lower values are worse
+    pub rows: Vec<PanelRow>,
+    pub stat: Option<StatStyle>,  // Drawn as one big value, for the stat and gauge presets
+    pub table: Option<Table>,     // Instead of rows, for the table preset
+    pub heatmap: Option<Heatmap>, // Instead of rows, for the heatmap preset
+    pub error: Option<String>,
+    pub warnings: Vec<String>, // Endpoints that failed while others answered
+    pub truncation: Truncation, // From the panel's `truncate`
+    pub counter: Option<CounterView>, // How a counter panel presents its counter
+    pub query: String,                // The PromQL it ran, to find its request in the debug popup
+    pub repeat: Option<(usize, String)>, // The `[[panels]]` entry and variable value a repeated panel is the copy for
+}
+
+impl PanelData {
+    /// Mark the panel as `[[panels]]` entry `entry`'s copy for `value`,
+    /// naming the value in the title unless it already does.
+    pub fn repeated_for(mut self, entry: usize, value: &str) -> Self {
+        if !self.title.contains(value) {
+            self.title = format!("{} · {}", self.title, value);
+        }
+        self.repeat = Some((entry, value.to_string()));
+        self
+    }
+}
+
+/// Trend window and resolution for panel sparklines.
+const TREND_MINUTES: i64 = 30;
+const TREND_STEP_SECONDS: i64 = 60;
+
+/// What a preset queries and how its rows are labelled and judged.
+struct PresetQuery<'a> {
+    title: &'static str,
+    unit: &'static str,
+    expr: Expr,
+    labels: Vec<&'a str>,
+    warn: f64,
+    critical: f64,
+}
+
+impl PanelConfig {
+    /// The view a panel charting a counter starts in; None for the others.
+    pub fn counter_view(&self) -> Option<CounterView> {
+        match self.preset {
+            Preset::Counter { view, .. } => Some(view),
+            Preset::Ingress { signal: IngressSignal::RequestRate, .. } => Some(CounterView::Rate),
+            Preset::Containers { signal: ContainerSignal::Restarts } => Some(CounterView::Increase),
+            _ => None,
+        }
+    }
+
+    /// The preset's query, a counter presented as `view`.
+    fn query(&self, view: CounterView) -> PresetQuery<'_> {
+        match &self.preset {
+            &Preset::QueueLag { flavor } => {
+                let (metric, labels): (&str, &'static [&'static str]) = match flavor {
+                    QueueFlavor::Kafka => ("kafka_consumergroup_lag", &["consumergroup", "topic"]),
+                    QueueFlavor::KafkaLagExporter => ("kafka_consumergroup_group_lag", &["group", "topic"]),
+                    QueueFlavor::Rabbitmq => ("rabbitmq_queue_messages_ready", &["queue"]),
+                };
+                PresetQuery {
+                    title: match flavor {
+                        QueueFlavor::Rabbitmq => "Queue depth",
+                        _ => "Consumer lag",
+                    },
+                    unit: "msgs",
+                    expr: Expr::from(Selector::new(metric)).sum_by(labels),
+                    labels: labels.to_vec(),
+                    warn: 1_000.0,
+                    critical: 10_000.0,
+                }
+            }
+            &Preset::Ingress { flavor, signal } => ingress_query(flavor, signal, view),
+            &Preset::Containers { signal } => container_query(signal, view),
+            &Preset::Gpu { signal } => gpu_query(signal),
+            Preset::Stat { query, .. } | Preset::Gauge { query, .. } | Preset::Table { query, .. } => PresetQuery {
+                title: "Stat",
+                unit: "",
+                expr: Expr::raw(query),
+                labels: Vec::new(),
+                // Only the panel's own thresholds apply
+                warn: f64::INFINITY,
+                critical: f64::INFINITY,
+            },
+            Preset::Counter { metric, by, labels, window, .. } => {
+                let selector = labels
+                    .iter()
+                    .fold(Selector::new(metric), |selector, (label, value)| selector.eq(label, value));
+                let by: Vec<&str> = by.iter().map(String::as_str).collect();
+                PresetQuery {
+                    title: "Counter",
+                    unit: "",
+                    expr: counter_expr(selector, window, &by, view),
+                    labels: by,
+                    warn: f64::INFINITY,
+                    critical: f64::INFINITY,
+                }
+            }
+            Preset::Heatmap { metric, labels } => heatmap_query(metric, labels),
+        }
+    }
+
+    /// Title and unit of a panel: the configured ones, then the preset's.
+    fn title_and_unit(&self, query: &PresetQuery<'_>, view: CounterView) -> (String, String) {
+        let (title, unit) = match &self.preset {
+            Preset::Stat { query: expr, unit }
+            | Preset::Gauge { query: expr, unit, .. }
+            | Preset::Table { query: expr, unit, .. } => (expr.as_str(), unit.to_string()),
+            Preset::Counter { metric, window, .. } => (
+                metric.as_str(),
+                match view {
+                    CounterView::Rate => "/s".to_string(),
+                    CounterView::Increase => format!("per {}", window),
+                    CounterView::Raw => String::new(),
+                },
+            ),
+            _ => (query.title, query.unit.to_string()),
+        };
+        (self.title.clone().unwrap_or_else(|| title.to_string()), unit)
+    }
+
+    fn stat_style(&self) -> Option<StatStyle> {
+        match self.preset {
+            Preset::Stat { .. } => Some(StatStyle::Number),
+            Preset::Gauge { min, max, .. } => Some(StatStyle::Gauge { min, max }),
+            _ => None,
+        }
+    }
+}
+
+fn heatmap_query(metric: &str, labels: &BTreeMap<String, String>) -> PresetQuery<'static> {
+    let metric = if metric.ends_with("_bucket") { metric.to_string() } else { format!("{}_bucket", metric) };
+    let selector = labels
+        .iter()
+        .fold(Selector::new(&metric), |selector, (label, value)| selector.eq(label, value));
+    PresetQuery {
+        title: "Histogram heatmap",
+        unit: "/s",
+        expr: rate(selector.range(HEATMAP_RATE_WINDOW)).sum_by(&["le"]),
+        labels: vec!["le"],
+        // Cells are judged by color, not thresholds
+        warn: f64::INFINITY,
+        critical: f64::INFINITY,
+    }
+}
+
+/// Upper bound of a bucket for its row label: durations for histograms
+/// observing seconds, plain numbers otherwise.
+fn bucket_label(le: f64, seconds: bool) -> String {
+    // Bounds like 0.025 don't survive scaling exactly; 3 decimals is plenty
+    let trimmed = |value: f64| format!("{:.3}", value).trim_end_matches('0').trim_end_matches('.').to_string();
+    match le {
+        le if le.is_infinite() => "≤ +Inf".to_string(),
+        le if seconds && le < 1.0 => format!("≤ {}ms", trimmed(le * 1000.0)),
+        le if seconds => format!("≤ {}s", trimmed(le)),
+        le => format!("≤ {}", trimmed(le)),
+    }
+}
+
+/// Turn cumulative `le` series (label, points) into per-bucket rates. Bucket
+/// and slice order comes from the data; missing points count as empty, and
+/// empty buckets below the lowest and above the highest observation are
+/// left out.
+pub fn heatmap(series: Vec<(String, Vec<(f64, f64)>)>, seconds: bool) -> Heatmap {
+    let mut buckets: Vec<(f64, BTreeMap<i64, f64>)> = series
+        .into_iter()
+        .filter_map(|(le, points)| {
+            let le = if le == "+Inf" { f64::INFINITY } else { le.parse::<f64>().ok()? };
+            // Keyed by millisecond so slices line up across series
+            let points = points.into_iter().map(|(t, v)| ((t * 1000.0).round() as i64, v)).collect();
+            Some((le, points))
+        })
+        .collect();
+    buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let times: Vec<i64> = buckets
+        .iter()
+        .flat_map(|(_, points)| points.keys().copied())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    // Each bucket counts what its cumulative count adds to the one below
+    let mut rates: Vec<Vec<f64>> = Vec::with_capacity(buckets.len());
+    let mut below = vec![0.0; times.len()];
+    for (_, points) in &buckets {
+        let cumulative: Vec<f64> = times
+            .iter()
+            .zip(&below)
+            .map(|(t, under)| points.get(t).copied().filter(|v| v.is_finite()).unwrap_or(*under))
+            .collect();
+        rates.push(cumulative.iter().zip(&below).map(|(total, under)| (total - under).max(0.0)).collect());
+        below = cumulative.iter().zip(&below).map(|(total, under)| total.max(*under)).collect();
+    }
+
+    let used = |row: &Vec<f64>| row.iter().any(|rate| *rate > 0.0);
+    let first = rates.iter().position(used).unwrap_or(0);
+    let last = rates.iter().rposition(used).map_or(0, |idx| idx + 1);
+    Heatmap {
+        buckets: buckets[first..last.max(first)].iter().map(|(le, _)| bucket_label(*le, seconds)).collect(),
+        times: times.iter().map(|t| *t as f64 / 1000.0).collect(),
+        rates: rates.drain(first..last.max(first)).collect(),
+    }
+}
+
+fn ingress_query(flavor: IngressFlavor, signal: IngressSignal, view: CounterView) -> PresetQuery<'static> {
+    // (requests counter, 5xx selector, latency histogram, row labels)
+    let (requests, errors, buckets, labels): (Selector, Selector, &str, &'static [&'static str]) = match flavor {
+        IngressFlavor::Nginx => (
+            Selector::new("nginx_ingress_controller_requests"),
+            Selector::new("nginx_ingress_controller_requests").re("status", "5.."),
+            "nginx_ingress_controller_request_duration_seconds_bucket",
+            &["namespace", "ingress"],
+        ),
+        IngressFlavor::Envoy => (
+            Selector::new("envoy_cluster_upstream_rq_total"),
+            Selector::new("envoy_cluster_upstream_rq_xx").eq("envoy_response_code_class", "5"),
+            "envoy_cluster_upstream_rq_time_bucket",
+            &["envoy_cluster_name"],
+        ),
+    };
+    match signal {
+        IngressSignal::ErrorRate => PresetQuery {
+            title: match flavor {
+                IngressFlavor::Nginx => "Ingress 5xx rate",
+                IngressFlavor::Envoy => "Envoy upstream 5xx rate",
+            },
+            unit: "%",
+            expr: (rate(errors.range(INGRESS_RATE_WINDOW)).sum_by(labels)
+                / rate(requests.range(INGRESS_RATE_WINDOW)).sum_by(labels))
+            .scale(100.0),
+            labels: labels.to_vec(),
+            warn: 1.0,
+            critical: 5.0,
+        },
+        IngressSignal::RequestRate => PresetQuery {
+            title: match flavor {
+                IngressFlavor::Nginx => "Ingress requests",
+                IngressFlavor::Envoy => "Envoy upstream requests",
+            },
+            unit: match view {
+                CounterView::Rate => "req/s",
+                CounterView::Increase => "req per 5m",
+                CounterView::Raw => "req",
+            },
+            expr: counter_expr(requests, INGRESS_RATE_WINDOW, labels, view),
+            labels: labels.to_vec(),
+            // Traffic has no universal limit; set warn/critical to taste
+            warn: f64::INFINITY,
+            critical: f64::INFINITY,
+        },
+        IngressSignal::P95Latency => {
+            let by_bucket: Vec<&str> = labels.iter().copied().chain(["le"]).collect();
+            let p95 = histogram_quantile(0.95, rate(Selector::new(buckets).range(INGRESS_RATE_WINDOW)).sum_by(&by_bucket));
+            PresetQuery {
+                title: match flavor {
+                    IngressFlavor::Nginx => "Ingress p95 latency",
+                    IngressFlavor::Envoy => "Envoy upstream p95 latency",
+                },
+                unit: "ms",
+                // NGINX observes seconds, Envoy milliseconds
+                expr: match flavor {
+                    IngressFlavor::Nginx => p95.scale(1000.0),
+                    IngressFlavor::Envoy => p95,
+                },
+                labels: labels.to_vec(),
+                warn: 500.0,
+                critical: 1_000.0,
+            }
+        }
+    }
+}
+
+fn container_query(signal: ContainerSignal, view: CounterView) -> PresetQuery<'static> {
+    const LABELS: &[&str] = &["namespace", "pod"];
+    // cAdvisor also reports the pod-level cgroup, with an empty container label
+    let container = |metric: &str| Expr::from(Selector::new(metric).ne("container", ""));
+    match signal {
+        ContainerSignal::Memory => PresetQuery {
+            title: "Container memory (% of limit)",
+            unit: "%",
+            expr: (container("container_memory_working_set_bytes").sum_by(LABELS)
+                / container("container_spec_memory_limit_bytes").sum_by(LABELS))
+            .scale(100.0),
+            labels: LABELS.to_vec(),
+            warn: 80.0,
+            critical: 95.0,
+        },
+        ContainerSignal::Cpu => PresetQuery {
+            title: "Container CPU (% of limit)",
+            unit: "%",
+            expr: (rate(Selector::new("container_cpu_usage_seconds_total").ne("container", "").range("5m")).sum_by(LABELS)
+                / (container("container_spec_cpu_quota") / container("container_spec_cpu_period")).sum_by(LABELS))
+            .scale(100.0),
+            labels: LABELS.to_vec(),
+            warn: 80.0,
+            critical: 95.0,
+        },
+        ContainerSignal::Restarts => PresetQuery {
+            title: "Container restarts",
+            unit: match view {
+                CounterView::Rate => "/s",
+                CounterView::Increase => "per 1h",
+                CounterView::Raw => "",
+            },
+            expr: counter_expr(Selector::new("kube_pod_container_status_restarts_total"), "1h", LABELS, view),
+            labels: LABELS.to_vec(),
+            warn: 1.0,
+            critical: 3.0,
+        },
+    }
+}
+
+fn gpu_query(signal: GpuSignal) -> PresetQuery<'static> {
+    // One row per GPU; `instance` tells the nodes apart
+    const LABELS: &[&str] = &["instance", "gpu"];
+    let gauge = |metric: &str| Expr::from(Selector::new(metric)).max_by(LABELS);
+    match signal {
+        GpuSignal::Utilization => PresetQuery {
+            title: "GPU utilization",
+            unit: "%",
+            expr: gauge("DCGM_FI_DEV_GPU_UTIL"),
+            labels: LABELS.to_vec(),
+            // Busy is the goal on a training cluster; set warn/critical to flag saturation
+            warn: f64::INFINITY,
+            critical: f64::INFINITY,
+        },
+        GpuSignal::Memory => PresetQuery {
+            title: "GPU memory",
+            unit: "%",
+            expr: (gauge("DCGM_FI_DEV_FB_USED") / (gauge("DCGM_FI_DEV_FB_USED") + gauge("DCGM_FI_DEV_FB_FREE"))).scale(100.0),
+            labels: LABELS.to_vec(),
+            warn: 90.0,
+            critical: 98.0,
+        },
+        GpuSignal::Temperature => PresetQuery {
+            title: "GPU temperature",
+            unit: "°C",
+            expr: gauge("DCGM_FI_DEV_GPU_TEMP"),
+            labels: LABELS.to_vec(),
+            warn: 80.0,
+            critical: 90.0,
+        },
+    }
+}
+
+/// Severity of `value`; with `critical` below `warn` (e.g. error budget
+/// remaining) lower values are worse.
+pub fn severity(value: f64, warn: f64, critical: f64) -> Severity {
+    if critical < warn {
+        if value <= critical {
+            Severity::Critical
+        } else if value <= warn {
+            Severity::Warn
+        } else {
+            Severity::Ok
+        }
+    } else if value >= critical {
+        Severity::Critical
+    } else if value >= warn {
+        Severity::Warn
+    } else {
+        Severity::Ok
+    }
+}
+
+/// Run a panel's query over the trend window and build its rows, worst
+/// first. A counter panel is presented as `view` if given, instead of the
+/// configured view.
+pub async fn fetch_panel(client: &PrometheusClient, config: &PanelConfig, view: Option<CounterView>) -> PanelData {
+    let configured = config.counter_view();
+    let counter = view.filter(|_| configured.is_some()).or(configured);
+    let query = config.query(counter.unwrap_or_default());
+    // Thresholds are meant for the configured view; another has none
+    let (warn, critical) = if counter == configured {
+        (config.warn.unwrap_or(query.warn), config.critical.unwrap_or(query.critical))
+    } else {
+        (f64::INFINITY, f64::INFINITY)
+    };
+    let (title, unit) = config.title_and_unit(&query, counter.unwrap_or_default());
+    let mut panel = PanelData {
+        title,
+        unit,
+        warn,
+        critical,
+        rows: Vec::new(),
+        stat: config.stat_style(),
+        table: None,
+        heatmap: None,
+        error: None,
+        warnings: Vec::new(),
+        truncation: config.truncate,
+        counter,
+        query: query.expr.to_string(),
+        repeat: None,
+    };
+
+    if let Preset::Table { columns, format, sort, ascending, limit, .. } = &config.preset {
+        match fetch_table(client, config, &query, columns).await {
+            Ok((rows, warnings)) => {
+                panel.warnings = warnings;
+                let column = sort
+                    .as_ref()
+                    .and_then(|sort| columns.iter().position(|column| column == sort))
+                    .unwrap_or(columns.len());
+                panel.table = Some(Table {
+                    columns: columns.clone(),
+                    rows: rows
+                        .into_iter()
+                        .map(|(labels, value)| TableRow { labels, value, severity: severity(value, warn, critical) })
+                        .collect(),
+                    format: *format,
+                    sort: TableSort { column, descending: !ascending },
+                    limit: *limit,
+                });
+            }
+            Err(e) => panel.error = Some(e.to_string()),
+        }
+        return panel;
+    }
+
+    match fetch_rows(client, config, &query).await {
+        Ok((rows, warnings)) if matches!(config.preset, Preset::Heatmap { .. }) => {
+            panel.warnings = warnings;
+            let seconds = matches!(&config.preset, Preset::Heatmap { metric, .. } if metric.contains("seconds"));
+            panel.heatmap = Some(heatmap(rows, seconds));
+        }
+        Ok((rows, warnings)) => {
+            panel.warnings = warnings;
+            panel.rows = rows
+                .into_iter()
+                .map(|(label, points)| {
+                    let full_in = config.forecast.and_then(|target| forecast::time_to_reach(&points, target));
+                    let trend: Vec<f64> = points.into_iter().map(|(_, v)| v).collect();
+                    let value = trend.last().copied().unwrap_or(0.0);
+                    PanelRow { label, value, trend, severity: severity(value, warn, critical), full_in }
+                })
+                .collect();
+            panel.rows.sort_by(|a, b| b.value.total_cmp(&a.value));
+            if panel.stat.is_some() && panel.rows.len() > 1 {
+                panel.warnings.push(format!(
+                    "Query returned {} series; showing the largest (aggregate it, e.g. with sum())",
+                    panel.rows.len()
+                ));
+            }
+        }
+        Err(e) => panel.error = Some(e.to_string()),
+    }
+    panel
+}
+
+/// Series of a panel's query: row label and (unix seconds, value) points.
+type Rows = Vec<(String, Vec<(f64, f64)>)>;
+
+/// Run a panel's query as a range query, on its endpoints if it has any.
+async fn query_range(client: &PrometheusClient, config: &PanelConfig, expr: &Expr, start: i64, end: i64) -> Result<PrometheusResponse> {
+    let (expr, start, end, step) = (expr.to_string(), start.to_string(), end.to_string(), TREND_STEP_SECONDS.to_string());
+    if config.endpoints.is_empty() {
+        Ok(client.query_range(&expr, &start, &end, &step).await?)
+    } else {
+        let fanout = config
+            .endpoints
+            .iter()
+            .fold(Fanout::new(config.aggregate), |fanout, (name, url)| {
+                fanout.endpoint(name, client.with_base_url(url))
+            });
+        fanout.query_range(&expr, &start, &end, &step).await
+    }
+}
+
+/// Label values of the wanted columns and the value of each series of a
+/// table panel's query. It runs as a range query of a single instant, which
+/// evaluates like an instant query but also works across endpoints.
+async fn fetch_table(
+    client: &PrometheusClient,
+    config: &PanelConfig,
+    query: &PresetQuery<'_>,
+    columns: &[String],
+) -> Result<(Vec<(Vec<String>, f64)>, Vec<String>)> {
+    let now = client.server_now().timestamp();
+    let response = query_range(client, config, &query.expr, now, now).await?;
+    let rows = response
+        .data
+        .result
+        .into_iter()
+        .filter_map(|result| {
+            let value = result
+                .values
+                .unwrap_or_default()
+                .last()
+                .and_then(|(_, v)| v.parse::<f64>().ok())
+                .filter(|v| v.is_finite())?;
+            let mut labels: Vec<String> = columns
+                .iter()
+                .map(|column| result.metric.get(column).cloned().unwrap_or_default())
+                .collect();
+            // Separate series from a fan-out are told apart by their endpoint
+            if let (Some(endpoint), Some(first)) = (result.metric.get(ENDPOINT_LABEL), labels.first_mut()) {
+                *first = format!("[{}] {}", endpoint, first);
+            }
+            Some((labels, value))
+        })
+        .collect();
+    Ok((rows, response.warnings))
+}
+
+async fn fetch_rows(client: &PrometheusClient, config: &PanelConfig, query: &PresetQuery<'_>) -> Result<(Rows, Vec<String>)> {
+    let end = client.server_now().timestamp();
+    let response = query_range(client, config, &query.expr, end - TREND_MINUTES * 60, end).await?;
+
+    let rows = response
+        .data
+        .result
+        .into_iter()
+        .map(|result| {
+            let label = query
+                .labels
+                .iter()
+                .filter_map(|name| result.metric.get(*name).cloned())
+                .collect::<Vec<_>>()
+                .join(" / ");
+            // Separate series from a fan-out are told apart by their endpoint
+            let label = match result.metric.get(ENDPOINT_LABEL) {
+                Some(endpoint) => format!("[{}] {}", endpoint, label),
+                None => label,
+            };
+            let points = result
+                .values
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(t, v)| Some((t, v.parse::<f64>().ok().filter(|v| v.is_finite())?)))
+                .collect();
+            (label, points)
+        })
+        .collect();
+    Ok((rows, response.warnings))
+}
@@ -0,0 +1,129 @@
+use crate::ignore::IgnoreConfig;
+use crate::log_actions::LogFilter;
+use crate::loki::LogEntry;
+use crate::prometheus::Ranking;
+use crate::saved_views::SavedView;
+use crate::ui::{ActivePanel, AppState, TimeRange};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+/// UI state that survives a restart.
+///
+/// Log positions are stored as anchors rather than indices, because the
+/// log buffer after a restart is a fresh fetch and indices no longer line up.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PersistedState {
+    pub prometheus_url: Option<String>,
+    pub loki_url: Option<String>,
+    pub active_panel: Option<ActivePanel>,
+    pub metrics_time_range: Option<TimeRange>,
+    pub metrics_ranking: Option<Ranking>,
+    pub metrics_scroll_offset: usize,
+    pub log_scroll_line: Option<LogAnchor>,   // Topmost visible log
+    pub selected_log_line: Option<LogAnchor>, // Selected log
+    pub log_filter: LogFilter,                // Stream, pattern or LogQL query the logs were narrowed to
+    pub last_log_query: Option<String>,       // Last query accepted from the query bar
+    pub ignored: IgnoreConfig,                // Ignore patterns added with `x`
+    pub saved_views: Vec<SavedView>,          // Views saved with `V`
+}
+
+/// A log line found again in a fresh fetch: its Loki timestamp and stream.
+/// Lines in the same nanosecond are told apart by their stream.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LogAnchor {
+    pub nanos: i64,
+    pub stream: String,
+}
+
+impl LogAnchor {
+    pub fn of(log: &LogEntry) -> Self {
+        Self { nanos: log.nanos, stream: log.stream.clone() }
+    }
+}
+
+/// Location of the state file: `$XDG_STATE_HOME/rustdash/state.json`,
+/// falling back to `~/.local/state/rustdash/state.json`.
+pub fn state_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local").join("state"),
+    };
+    Some(base.join("rustdash").join("state.json"))
+}
+
+impl PersistedState {
+    /// Load the saved state, returning defaults if there is none or it can't be read.
+    pub fn load() -> Self {
+        state_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = state_path().ok_or_else(|| anyhow::anyhow!("Cannot determine state directory"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn capture(state: &AppState) -> Self {
+        Self {
+            prometheus_url: Some(state.prometheus_url.clone()),
+            loki_url: Some(state.loki_url.clone()),
+            active_panel: Some(state.active_panel),
+            metrics_time_range: Some(state.metrics_time_range),
+            metrics_ranking: Some(state.metrics_ranking),
+            metrics_scroll_offset: state.metrics_scroll_offset,
+            log_scroll_line: state.all_logs.get(state.log_scroll_offset).map(LogAnchor::of),
+            selected_log_line: state
+                .selected_log_index
+                .and_then(|idx| state.all_logs.get(idx))
+                .map(LogAnchor::of),
+            log_filter: state.log_filter.clone(),
+            last_log_query: state.last_log_query.clone(),
+            ignored: state.ignore.added().clone(),
+            saved_views: state.saved_views.clone(),
+        }
+    }
+
+    /// Forget what only makes sense against the last session's endpoints: the
+    /// URLs, the log filter and query, and the log positions. Used when a
+    /// profile picks the endpoints.
+    pub fn forget_endpoints(&mut self) {
+        self.prometheus_url = None;
+        self.loki_url = None;
+        self.log_filter = LogFilter::default();
+        self.last_log_query = None;
+        self.log_scroll_line = None;
+        self.selected_log_line = None;
+    }
+
+    /// Apply the restored view settings. Log anchors are kept on the state and
+    /// resolved once the first batch of logs arrives. Call this after the
+    /// backend flags are set, so a panel whose backend is gone isn't restored.
+    pub fn apply(self, state: &mut AppState) {
+        if let Some(panel) = self.active_panel.filter(|panel| state.panel_available(*panel)) {
+            state.active_panel = panel;
+        }
+        if let Some(range) = self.metrics_time_range {
+            state.metrics_time_range = range;
+        }
+        if let Some(ranking) = self.metrics_ranking {
+            state.metrics_ranking = ranking;
+        }
+        state.metrics_scroll_offset = self.metrics_scroll_offset;
+        state.restore_scroll_anchor = self.log_scroll_line;
+        state.restore_selected_anchor = self.selected_log_line;
+        // Before the first fetch, so the logs start out narrowed
+        state.log_pattern = self.log_filter.compile_pattern();
+        state.log_filter = self.log_filter;
+        state.last_log_query = self.last_log_query;
+        state.ignore.extend(self.ignored);
+        state.saved_views = self.saved_views;
+    }
+}
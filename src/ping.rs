@@ -0,0 +1,98 @@
+//! Round-trip latency and loss to the hosts of the `[ping]` config section,
+//! to tell a network problem from an application one before digging into
+//! metrics. Measured with the system `ping`, as sending ICMP directly needs
+//! privileges rustdash shouldn't ask for.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio::process::Command;
+
+/// Samples kept per host, for the chart and the loss.
+pub const HISTORY: usize = 120;
+
+/// The `[ping]` config section.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct PingConfig {
+    pub hosts: Vec<String>,    // Host names or addresses
+    pub interval_seconds: u64, // Between pings of each host
+    pub timeout_seconds: u64,  // A reply later than this counts as lost
+}
+
+impl Default for PingConfig {
+    fn default() -> Self {
+        Self { hosts: Vec::new(), interval_seconds: 5, timeout_seconds: 2 }
+    }
+}
+
+impl PingConfig {
+    /// Hosts are passed to `ping`, so one that looks like an option is refused.
+    pub fn validate(&self) -> Result<()> {
+        match self.hosts.iter().find(|host| host.trim().is_empty() || host.starts_with('-')) {
+            Some(host) => bail!("Invalid [ping] host {:?}", host),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A host and its recent round trips.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingTarget {
+    pub host: String,
+    pub samples: VecDeque<Option<f64>>, // Round trips in ms, oldest first; None for a lost ping
+}
+
+impl PingTarget {
+    pub fn new(host: &str) -> Self {
+        Self { host: host.to_string(), samples: VecDeque::new() }
+    }
+
+    pub fn record(&mut self, rtt_ms: Option<f64>) {
+        self.samples.push_back(rtt_ms);
+        if self.samples.len() > HISTORY {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The latest sample; None before the first ping returns.
+    pub fn last(&self) -> Option<Option<f64>> {
+        self.samples.back().copied()
+    }
+
+    /// Share of the kept pings that got no reply, in percent.
+    pub fn loss_pct(&self) -> Option<f64> {
+        let lost = self.samples.iter().filter(|sample| sample.is_none()).count();
+        (!self.samples.is_empty()).then(|| lost as f64 * 100.0 / self.samples.len() as f64)
+    }
+
+    /// Average round trip of the pings that got a reply.
+    pub fn average_ms(&self) -> Option<f64> {
+        let replies: Vec<f64> = self.samples.iter().flatten().copied().collect();
+        (!replies.is_empty()).then(|| replies.iter().sum::<f64>() / replies.len() as f64)
+    }
+}
+
+/// The round trip `ping` printed: `time=12.3 ms` (Linux, macOS) or
+/// `time<1ms` (Windows, read as 1 ms).
+pub fn parse_rtt(output: &str) -> Option<f64> {
+    let at = output.find("time=").map(|at| at + 5).or_else(|| output.find("time<").map(|at| at + 5))?;
+    let number: String = output[at..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    number.parse().ok()
+}
+
+/// Ping `host` once. None when no reply came back in time, or `ping`
+/// couldn't be run.
+pub async fn ping(host: &str, timeout_seconds: u64) -> Option<f64> {
+    let timeout = timeout_seconds.max(1);
+    let mut command = Command::new("ping");
+    if cfg!(windows) {
+        command.args(["-n", "1", "-w", &(timeout * 1000).to_string()]);
+    } else if cfg!(target_os = "macos") {
+        command.args(["-c", "1", "-t", &timeout.to_string()]);
+    } else {
+        command.args(["-c", "1", "-W", &timeout.to_string()]);
+    }
+    let output = command.arg(host).kill_on_drop(true).output().await.ok()?;
+    output.status.success().then(|| parse_rtt(&String::from_utf8_lossy(&output.stdout))).flatten()
+}
@@ -1,34 +1,163 @@
-use anyhow::Result;
-use reqwest::Client;
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::breaker::{CircuitBreaker, CircuitOpen, SendError};
+use crate::cache::StaticCache;
+use crate::clock::ClockSkew;
+use crate::config::BandwidthConfig;
+use crate::downsample::{auto_step, lttb};
+use crate::error::{self, ErrorClass};
+use crate::exchanges::{Exchange, ExchangeLog};
+use crate::guard::{self, Outcome, SlowQueryGuard};
+use crate::history::UriTotals;
+use crate::limiter::QueryLimiter;
+use crate::promql::{histogram_quantile, rate, Expr, Selector};
+use crate::query_stats::{Backend, QueryStats};
+use crate::templates::UriTemplates;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long build info is reused before it is revalidated.
+const BUILD_INFO_TTL: Duration = Duration::from_secs(600);
+/// How long metric names, label names and values are reused.
+const LABELS_TTL: Duration = Duration::from_secs(300);
+/// How far back the series API looks for a metric's label sets.
+const SERIES_LOOKBACK_SECONDS: i64 = 3600;
+/// How often the server's clock is compared with ours.
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Why a Prometheus request failed.
+#[derive(Debug, thiserror::Error)]
+pub enum PrometheusError {
+    #[error("HTTP {status}: {message}")]
+    Status { status: StatusCode, message: String }, // Error answer without an API error body
+    #[error("HTTP {status}: {message}")]
+    Auth { status: StatusCode, message: String }, // 401/403
+    #[error("{error_type}: {message}")]
+    Api { status: StatusCode, error_type: String, message: String }, // `"status":"error"` body, e.g. bad_data
+    #[error("timed out: {0}")]
+    Timeout(reqwest::Error),
+    #[error(transparent)]
+    Request(reqwest::Error), // No answer: connection refused, DNS, TLS
+    #[error(transparent)]
+    CircuitOpen(CircuitOpen), // Not sent: the endpoint failed too often lately
+    #[error("invalid response: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+impl PrometheusError {
+    /// An error answer with HTTP `status` and body `message`.
+    pub fn status(status: StatusCode, message: &str) -> Self {
+        let message = message.trim().to_string();
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => PrometheusError::Auth { status, message },
+            _ => PrometheusError::Status { status, message },
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            PrometheusError::Status { status, message } => error::classify_status(*status, message),
+            PrometheusError::Auth { .. } => ErrorClass::Auth,
+            PrometheusError::Api { error_type, .. } if error_type == "timeout" => ErrorClass::Timeout,
+            PrometheusError::Api { status, message, .. } => error::classify_status(*status, message),
+            PrometheusError::Timeout(_) => ErrorClass::Timeout,
+            PrometheusError::Request(e) => error::classify_request(e),
+            PrometheusError::CircuitOpen(_) => ErrorClass::Unreachable,
+            PrometheusError::Parse(_) => ErrorClass::Malformed,
+        }
+    }
+}
+
+impl From<reqwest::Error> for PrometheusError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status() {
+            Some(status) => PrometheusError::status(status, &e.to_string()),
+            None if e.is_timeout() => PrometheusError::Timeout(e),
+            None => PrometheusError::Request(e),
+        }
+    }
+}
+
+impl From<SendError> for PrometheusError {
+    fn from(e: SendError) -> Self {
+        match e {
+            SendError::Open(open) => PrometheusError::CircuitOpen(open),
+            SendError::Request(e) => e.into(),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, PrometheusError>;
 
 #[derive(Debug, Clone)]
 pub struct PrometheusClient {
     client: Client,
     base_url: String,
+    limiter: Arc<QueryLimiter>, // Shared by every clone and fan-out endpoint
+    static_cache: Arc<StaticCache>, // Slowly-changing responses, per server
+    templates: Arc<UriTemplates>,   // Raw path -> template rules applied to per-URI results
+    clock_skew: Arc<ClockSkew>,     // Offset of the server's clock, anchors range queries
+    bandwidth: Arc<BandwidthConfig>, // Response size metric, if any
+    query_stats: Arc<QueryStats>,    // Timings of every query sent
+    exchanges: Arc<ExchangeLog>,     // The last request and response of every query
+    guard: Arc<SlowQueryGuard>,      // Narrows or coarsens queries that keep being slow
+    breaker: Arc<CircuitBreaker>,    // Retries and cooldowns per endpoint
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PrometheusResponse {
-    #[allow(dead_code)]
     pub status: String,
-    pub data: PrometheusData,
+    #[serde(default)]
+    pub data: PrometheusData, // Absent when status is "error"
+    #[serde(rename = "errorType")]
+    pub error_type: Option<String>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct PrometheusData {
     #[serde(rename = "resultType")]
     #[allow(dead_code)]
     pub result_type: String,
-    pub result: Vec<PrometheusResult>,
+    #[serde(deserialize_with = "series_or_scalar")]
+    pub result: Vec<PrometheusResult>, // A scalar or string result becomes one series without labels
+    #[serde(default)]
+    pub stats: Option<PrometheusStats>, // Only when asked for with `stats`
+}
+
+/// Series as they are, or a `[time, "value"]` scalar as a single series.
+fn series_or_scalar<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<PrometheusResult>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Answer {
+        Series(Vec<PrometheusResult>),
+        Scalar((f64, String)),
+    }
+
+    Ok(match Answer::deserialize(deserializer)? {
+        Answer::Series(series) => series,
+        Answer::Scalar(value) => vec![PrometheusResult { metric: HashMap::new(), value: Some(value), values: None }],
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrometheusStats {
+    pub timings: PrometheusTimings,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrometheusTimings {
+    pub eval_total_time: f64, // Seconds
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PrometheusResult {
     pub metric: HashMap<String, String>,
     pub value: Option<(f64, String)>,
-    #[allow(dead_code)]
     pub values: Option<Vec<(f64, String)>>,
 }
 
@@ -37,37 +166,551 @@ pub struct UriMetric {
     pub uri: String,
     pub avg_duration_ms: f64,
     pub request_count: f64,
+    pub errors_per_min: f64,       // 5xx responses, only queried when ranking by errors
+    pub change_pct: Option<f64>,   // Latency change vs the previous window, only when ranking by change
+    pub bytes_per_sec: Option<f64>, // Response bandwidth, when a size metric is configured
+}
+
+/// Dimension the metrics table is ranked by. Each one issues its own query set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum Ranking {
+    #[default]
+    Busiest,
+    Slowest,
+    Errors,
+    Change,
+}
+
+impl Ranking {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Ranking::Busiest => "busiest",
+            Ranking::Slowest => "slowest",
+            Ranking::Errors => "most errors",
+            Ranking::Change => "biggest change",
+        }
+    }
+    
+    pub fn next(&self) -> Ranking {
+        match self {
+            Ranking::Busiest => Ranking::Slowest,
+            Ranking::Slowest => Ranking::Errors,
+            Ranking::Errors => Ranking::Change,
+            Ranking::Change => Ranking::Busiest,
+        }
+    }
+
+    /// Order `metrics` by this dimension, highest first.
+    pub fn sort(&self, metrics: &mut [UriMetric]) {
+        match self {
+            Ranking::Busiest => metrics.sort_by(|a, b| b.request_count.total_cmp(&a.request_count)),
+            Ranking::Slowest => metrics.sort_by(|a, b| b.avg_duration_ms.total_cmp(&a.avg_duration_ms)),
+            Ranking::Errors => metrics.sort_by(|a, b| b.errors_per_min.total_cmp(&a.errors_per_min)),
+            Ranking::Change => metrics.sort_by(|a, b| {
+                let magnitude = |m: &UriMetric| m.change_pct.map_or(-1.0, f64::abs);
+                magnitude(b).total_cmp(&magnitude(a))
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct MetricsData {
     pub http_requests_total: f64,
     pub uri_metrics: Vec<UriMetric>,
+    pub warnings: Vec<String>, // Warnings reported by Prometheus (e.g. exceeded maximum resolution)
+    pub errors: Vec<String>,   // Failed queries, shown instead of blank data
+    pub egress_bytes_per_sec: Vec<f64>, // Total response bandwidth per minute over the last 30, oldest first
+}
+
+/// Latency and request rate of one URI over a window, for the drill-in chart.
+#[derive(Debug, Clone, Default)]
+pub struct UriHistory {
+    pub latency_ms: Vec<(f64, f64)>,       // (unix seconds, average latency in ms)
+    pub requests_per_min: Vec<(f64, f64)>, // (unix seconds, requests per minute)
+    pub queries: Vec<String>,              // Latency and request rate queries; empty when charted from the recorded history
+}
+
+/// One series behind an aggregated metrics row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesValue {
+    pub labels: BTreeMap<String, String>, // Full label set, without the metric name
+    pub at: f64,                          // Evaluation time (unix seconds)
+    pub requests_per_min: f64,
+    pub latency_ms: Option<f64>, // None where the series had no requests in the window
+}
+
+/// One caller -> callee edge from Tempo's service graph metrics.
+#[derive(Debug, Clone)]
+pub struct ServiceEdge {
+    pub client: String,
+    pub server: String,
+    pub requests_per_min: f64,
+    pub errors_per_min: f64,
+    pub p95_ms: Option<f64>, // Absent when the latency histogram isn't exported
+}
+
+impl ServiceEdge {
+    pub fn error_ratio(&self) -> f64 {
+        if self.requests_per_min > 0.0 {
+            self.errors_per_min / self.requests_per_min
+        } else {
+            0.0
+        }
+    }
+}
+
+/// What Prometheus reports about itself, for the "about backends" popup.
+/// Each part comes from its own endpoint and is None where that one failed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerInfo {
+    pub version: Option<String>,
+    pub revision: Option<String>,
+    pub storage_retention: Option<String>, // As configured, e.g. "15d" or "15d or 50GiB"
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub head_series: Option<u64>, // Series in the in-memory head block
+    pub wal_bytes: Option<f64>,   // Only known if Prometheus scrapes itself
+}
+
+/// Errors and warnings collected across the queries of one refresh.
+#[derive(Debug, Default)]
+pub struct QueryDiagnostics {
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl QueryDiagnostics {
+    fn push_unique(list: &mut Vec<String>, message: String) {
+        if !list.contains(&message) {
+            list.push(message);
+        }
+    }
+
+    pub fn warning(&mut self, message: String) {
+        Self::push_unique(&mut self.warnings, message);
+    }
+
+    pub fn error(&mut self, message: String) {
+        Self::push_unique(&mut self.errors, message);
+    }
+}
+
+/// The raw counter for "all", otherwise its per-second rate over the range.
+fn windowed(selector: Selector, time_range: &str) -> Expr {
+    if time_range == "all" {
+        Expr::from(selector)
+    } else {
+        rate(selector.range(time_range))
+    }
+}
+
+/// Latency and request rate per URI over the series `series` selects.
+fn uri_queries(series: impl Fn(&str) -> Selector, time_range: &str) -> (Expr, Expr) {
+    let latency = windowed(series("http_server_requests_seconds_sum"), time_range).sum_by(&["uri"])
+        / windowed(series("http_server_requests_seconds_count"), time_range).sum_by(&["uri"]);
+    let count = windowed(series("http_server_requests_seconds_count"), time_range).sum_by(&["uri"]);
+    (latency, count)
+}
+
+/// The queries behind the metrics table's latency and request rate columns.
+pub fn uri_metric_queries(time_range: &str) -> [String; 2] {
+    let (latency, count) = uri_queries(Selector::new, time_range);
+    [latency.to_string(), count.to_string()]
 }
 
 impl PrometheusClient {
-    pub fn new(base_url: String) -> Self {
+    pub fn new(base_url: String, client: Client) -> Self {
         Self {
-            client: Client::new(),
+            client,
             base_url,
+            limiter: Arc::new(QueryLimiter::unlimited()),
+            static_cache: Arc::default(),
+            templates: Arc::default(),
+            clock_skew: Arc::default(),
+            bandwidth: Arc::default(),
+            query_stats: Arc::default(),
+            exchanges: Arc::default(),
+            guard: Arc::default(),
+            breaker: Arc::new(CircuitBreaker::disabled()),
         }
     }
 
+    /// Fold raw paths into templates in per-URI results.
+    pub fn with_uri_templates(mut self, templates: UriTemplates) -> Self {
+        self.templates = Arc::new(templates);
+        self
+    }
+
+    /// Query response sizes for the bandwidth column and egress sparkline.
+    pub fn with_bandwidth(mut self, bandwidth: BandwidthConfig) -> Self {
+        self.bandwidth = Arc::new(bandwidth);
+        self
+    }
+
+    /// Queue queries beyond the limiter's budget.
+    pub fn with_limiter(mut self, limiter: QueryLimiter) -> Self {
+        self.limiter = Arc::new(limiter);
+        self
+    }
+
+    /// Record query timings in `stats`, e.g. one shared with the UI.
+    pub fn with_query_stats(mut self, stats: Arc<QueryStats>) -> Self {
+        self.query_stats = stats;
+        self
+    }
+
+    /// Keep the last request and response of every query in `log`.
+    pub fn with_exchange_log(mut self, log: Arc<ExchangeLog>) -> Self {
+        self.exchanges = log;
+        self
+    }
+
+    /// Reduce queries that keep being slow or hitting limits.
+    pub fn with_guard(mut self, guard: Arc<SlowQueryGuard>) -> Self {
+        self.guard = guard;
+        self
+    }
+
+    /// Retry requests that fail in passing and stop sending to endpoints
+    /// that keep failing.
+    pub fn with_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    /// A client for another server that shares this one's connection settings
+    /// and query budget.
+    pub fn with_base_url(&self, base_url: &str) -> Self {
+        Self {
+            client: self.client.clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            limiter: self.limiter.clone(),
+            static_cache: Arc::default(),
+            templates: self.templates.clone(),
+            clock_skew: self.clock_skew.clone(), // Assumed to run on the same clock
+            bandwidth: self.bandwidth.clone(),
+            query_stats: self.query_stats.clone(),
+            exchanges: self.exchanges.clone(),
+            guard: self.guard.clone(),
+            breaker: self.breaker.clone(),
+        }
+    }
+
+    /// Prometheus version, from `/api/v1/status/buildinfo`. Cached for
+    /// BUILD_INFO_TTL and revalidated with the server's ETag afterwards.
+    pub async fn get_build_info(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct BuildInfo {
+            version: String,
+        }
+        #[derive(Deserialize)]
+        struct BuildInfoResponse {
+            data: BuildInfo,
+        }
+
+        let _permit = self.limiter.acquire().await;
+        let url = format!("{}/api/v1/status/buildinfo", self.base_url);
+        let body = self.static_cache.get(&self.client, &self.breaker, Backend::Prometheus, &url, BUILD_INFO_TTL).await?;
+        Ok(serde_json::from_str::<BuildInfoResponse>(&body)?.data.version)
+    }
+
+    /// Version, retention, uptime and TSDB size from the status endpoints,
+    /// plus the WAL size from Prometheus' own metrics. Fails only if none of
+    /// them answer.
+    pub async fn get_server_info(&self) -> Result<ServerInfo> {
+        #[derive(Deserialize)]
+        struct BuildInfo {
+            version: String,
+            revision: Option<String>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RuntimeInfo {
+            start_time: Option<String>, // RFC 3339
+            storage_retention: Option<String>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct HeadStats {
+            num_series: u64,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct TsdbStatus {
+            head_stats: HeadStats,
+        }
+        #[derive(Deserialize)]
+        struct StatusResponse<T> {
+            data: T,
+        }
+
+        let build_info = async {
+            let _permit = self.limiter.acquire().await;
+            let url = format!("{}/api/v1/status/buildinfo", self.base_url);
+            let body = self.static_cache.get(&self.client, &self.breaker, Backend::Prometheus, &url, BUILD_INFO_TTL).await?;
+            Ok::<_, PrometheusError>(serde_json::from_str::<StatusResponse<BuildInfo>>(&body)?.data)
+        };
+        let (build_info, runtime_info, tsdb, wal) = tokio::join!(
+            build_info,
+            self.get_status::<StatusResponse<RuntimeInfo>>("runtimeinfo"),
+            self.get_status::<StatusResponse<TsdbStatus>>("tsdb"),
+            self.query("prometheus_tsdb_wal_storage_size_bytes"),
+        );
+        let build_info = match (build_info, &runtime_info, &tsdb, &wal) {
+            (Err(e), Err(_), Err(_), Err(_)) => return Err(e),
+            (build_info, ..) => build_info,
+        };
+
+        let (version, revision) = build_info.map_or((None, None), |info| (Some(info.version), info.revision));
+        let (start_time, storage_retention) = runtime_info
+            .map_or((None, None), |info| (info.data.start_time, info.data.storage_retention));
+        Ok(ServerInfo {
+            version,
+            revision: revision.filter(|revision| !revision.is_empty()),
+            storage_retention: storage_retention.filter(|retention| !retention.is_empty()),
+            start_time: start_time
+                .and_then(|time| chrono::DateTime::parse_from_rfc3339(&time).ok())
+                .map(|time| time.with_timezone(&chrono::Utc)),
+            head_series: tsdb.ok().map(|tsdb| tsdb.data.head_stats.num_series),
+            // A series per Prometheus it scrapes; a lone self-scrape is the usual case
+            wal_bytes: wal.ok().and_then(|response| {
+                response.data.result.first()?.value.as_ref()?.1.parse::<f64>().ok()
+            }),
+        })
+    }
+
+    /// GET `/api/v1/status/<name>`, decoded as `T`.
+    async fn get_status<T: serde::de::DeserializeOwned>(&self, name: &str) -> Result<T> {
+        let _permit = self.limiter.acquire().await;
+        let url = format!("{}/api/v1/status/{}", self.base_url, name);
+        let response = self.breaker.send(Backend::Prometheus, self.client.get(&url)).await?.error_for_status()?;
+        Ok(serde_json::from_str(&response.text().await?)?)
+    }
+
+    /// Trigger a TSDB snapshot through the admin API (needs
+    /// `--web.enable-admin-api`), returning the snapshot's directory name.
+    pub async fn create_snapshot(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Snapshot {
+            name: String,
+        }
+        #[derive(Deserialize)]
+        struct SnapshotResponse {
+            data: Snapshot,
+        }
+
+        let body = self.admin_post("snapshot").await?;
+        Ok(serde_json::from_str::<SnapshotResponse>(&body)?.data.name)
+    }
+
+    /// Remove deleted series from disk through the admin API.
+    pub async fn clean_tombstones(&self) -> Result<()> {
+        self.admin_post("clean_tombstones").await.map(|_| ())
+    }
+
+    /// POST to `/api/v1/admin/tsdb/<action>`, returning the body. Errors
+    /// carry Prometheus' message, e.g. "admin APIs disabled".
+    async fn admin_post(&self, action: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct AdminError {
+            error: String,
+        }
+
+        let _permit = self.limiter.acquire().await;
+        let url = format!("{}/api/v1/admin/tsdb/{}", self.base_url, action);
+        let response = self.breaker.send(Backend::Prometheus, self.client.post(&url)).await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<AdminError>(&body).map_or(body, |e| e.error);
+            return Err(PrometheusError::status(status, &message));
+        }
+        Ok(body)
+    }
+
+    /// Every metric name, from `/api/v1/label/__name__/values`.
+    pub async fn get_metric_names(&self) -> Result<Vec<String>> {
+        self.get_label_values("__name__").await
+    }
+
+    /// Every label name, from `/api/v1/labels`.
+    pub async fn get_label_names(&self) -> Result<Vec<String>> {
+        self.get_cached_data(&format!("{}/api/v1/labels", self.base_url)).await
+    }
+
+    /// Every value of `label`, from `/api/v1/label/<label>/values`.
+    pub async fn get_label_values(&self, label: &str) -> Result<Vec<String>> {
+        self.get_cached_data(&format!("{}/api/v1/label/{}/values", self.base_url, label)).await
+    }
+
+    /// Label sets of the series `selector` matched over the last hour, from
+    /// `/api/v1/series`. The window starts on a 5-minute mark, so repeated
+    /// lookups hit the cache.
+    pub async fn get_series(&self, selector: &str) -> Result<Vec<HashMap<String, String>>> {
+        let start = (self.server_now().timestamp() - SERIES_LOOKBACK_SECONDS).div_euclid(300) * 300;
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/api/v1/series", self.base_url),
+            &[("match[]", selector.to_string()), ("start", start.to_string())],
+        )
+        .map_err(|e| PrometheusError::status(StatusCode::BAD_REQUEST, &e.to_string()))?;
+        self.get_cached_data(url.as_str()).await
+    }
+
+    /// The `data` of a metadata endpoint's answer, cached for LABELS_TTL.
+    async fn get_cached_data<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        #[derive(Deserialize)]
+        struct DataResponse<T> {
+            data: T,
+        }
+
+        let _permit = self.limiter.acquire().await;
+        let body = self.static_cache.get(&self.client, &self.breaker, Backend::Prometheus, url, LABELS_TTL).await?;
+        Ok(serde_json::from_str::<DataResponse<T>>(&body)?.data)
+    }
+
+    /// Cache of slowly-changing responses, e.g. to expire it on demand.
+    pub fn static_cache(&self) -> &StaticCache {
+        &self.static_cache
+    }
+
+    /// Seconds Prometheus' clock is ahead of ours (negative when behind),
+    /// from a `time()` query. Re-measured every CLOCK_CHECK_INTERVAL.
+    pub async fn get_clock_skew(&self) -> Result<f64> {
+        #[derive(Deserialize)]
+        struct Scalar {
+            result: (f64, String),
+        }
+        #[derive(Deserialize)]
+        struct ScalarResponse {
+            data: Scalar,
+        }
+
+        if let (Some(seconds), false) = (self.clock_skew.seconds(), self.clock_skew.is_stale(CLOCK_CHECK_INTERVAL)) {
+            return Ok(seconds);
+        }
+        let _permit = self.limiter.acquire().await;
+        let url = format!("{}/api/v1/query", self.base_url);
+        let sent = chrono::Utc::now();
+        let request = self.client.get(&url).query(&[("query", "time()")]);
+        let response = self.breaker.send(Backend::Prometheus, request).await?.error_for_status()?;
+        let body = response.text().await?;
+        self.clock_skew.record(serde_json::from_str::<ScalarResponse>(&body)?.data.result.0, sent);
+        Ok(self.clock_skew.seconds().unwrap_or(0.0))
+    }
+
+    /// The current time on Prometheus' clock, for the ends of range queries.
+    pub fn server_now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.clock_skew.server_now()
+    }
+
+    /// Run an instant query. Once it has been slow for a while, the slow
+    /// query guard narrows its range selectors.
     pub async fn query(&self, query: &str) -> Result<PrometheusResponse> {
+        let _permit = self.limiter.acquire().await;
         let url = format!("{}/api/v1/query", self.base_url);
-        let response = self
-            .client
-            .get(&url)
-            .query(&[("query", query)])
-            .send()
-            .await?
-            .json::<PrometheusResponse>()
-            .await?;
+        let narrowed = guard::narrow_ranges(query, self.guard.level(Backend::Prometheus, query));
+        let request = self.client.get(&url).query(&[("query", narrowed.as_str()), ("stats", "all")]);
+        // Only queries with a window to narrow are worth guarding
+        let guarded = guard::narrow_ranges(query, 1) != query;
+        self.send_timed(query, request, guarded).await
+    }
+
+    /// Send a query request, recording how long it took and, if `guarded`,
+    /// telling the slow query guard how it went.
+    async fn send_timed(&self, query: &str, request: reqwest::RequestBuilder, guarded: bool) -> Result<PrometheusResponse> {
+        let observe = |outcome| {
+            if guarded {
+                self.guard.observe(Backend::Prometheus, query, outcome);
+            }
+        };
+        let started = Instant::now();
+        let exchange = Exchange::new(Backend::Prometheus, query, &request, chrono::Local::now());
+        let elapsed_ms = || started.elapsed().as_secs_f64() * 1000.0;
+        let response = match self.breaker.send(Backend::Prometheus, request).await {
+            Ok(response) => response,
+            Err(e) => {
+                let e = PrometheusError::from(e);
+                if let Some(exchange) = exchange {
+                    self.exchanges.record(exchange.respond(None, &e.to_string(), elapsed_ms()));
+                }
+                if e.class() == ErrorClass::Timeout {
+                    observe(Outcome::TimedOut);
+                }
+                return Err(e);
+            }
+        };
+        let http_status = response.status();
+        let result = match response.text().await {
+            Ok(body) => {
+                if let Some(exchange) = exchange {
+                    self.exchanges.record(exchange.respond(Some(http_status.as_u16()), &body, elapsed_ms()));
+                }
+                Self::parse_response(http_status, &body)
+            }
+            Err(e) => Err(e.into()),
+        };
+        let server_seconds = result
+            .as_ref()
+            .ok()
+            .and_then(|response| response.data.stats.as_ref())
+            .map(|stats| stats.timings.eval_total_time);
+        self.query_stats.record(Backend::Prometheus, query, started.elapsed(), server_seconds);
+        match &result {
+            Ok(_) => observe(Outcome::Finished(started.elapsed())),
+            Err(e) => observe(Outcome::Rejected(e.class(), &e.to_string())),
+        }
+        result
+    }
+
+    /// Decode a Prometheus API response, turning `"status":"error"` bodies
+    /// (sent with 4xx/5xx codes) into errors carrying Prometheus' message.
+    fn parse_response(http_status: StatusCode, body: &str) -> Result<PrometheusResponse> {
+        let parsed = match serde_json::from_str::<PrometheusResponse>(body) {
+            Ok(parsed) => parsed,
+            Err(_) if !http_status.is_success() => return Err(PrometheusError::status(http_status, body)),
+            Err(e) => return Err(e.into()),
+        };
+        
+        if parsed.status == "error" {
+            return Err(PrometheusError::Api {
+                status: http_status,
+                error_type: parsed.error_type.unwrap_or_else(|| "error".to_string()),
+                message: parsed.error.unwrap_or_else(|| "unknown error".to_string()),
+            });
+        }
         
-        Ok(response)
+        Ok(parsed)
     }
 
-    #[allow(dead_code)]
+    /// Run a query, recording its warnings or error in `diagnostics`.
+    async fn query_with_diagnostics(
+        &self,
+        query: &str,
+        diagnostics: &mut QueryDiagnostics,
+    ) -> Option<PrometheusResponse> {
+        match self.query(query).await {
+            Ok(response) => {
+                for warning in &response.warnings {
+                    diagnostics.warning(warning.clone());
+                }
+                if let Some(reduction) = self.guard.reduction(Backend::Prometheus, query) {
+                    diagnostics.warning(format!(
+                        "Slow query: window narrowed {}× ({})",
+                        reduction.factor(),
+                        reduction.reason
+                    ));
+                }
+                Some(response)
+            }
+            Err(e) => {
+                diagnostics.error(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Run a range query. Once it has been slow for a while, the slow query
+    /// guard raises its step.
     pub async fn query_range(
         &self,
         query: &str,
@@ -75,44 +718,43 @@ impl PrometheusClient {
         end: &str,
         step: &str,
     ) -> Result<PrometheusResponse> {
+        let _permit = self.limiter.acquire().await;
         let url = format!("{}/api/v1/query_range", self.base_url);
-        let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("query", query),
-                ("start", start),
-                ("end", end),
-                ("step", step),
-            ])
-            .send()
-            .await?
-            .json::<PrometheusResponse>()
-            .await?;
-        
-        Ok(response)
+        let step = guard::raise_step(step, self.guard.level(Backend::Prometheus, query));
+        let request = self.client.get(&url).query(&[
+            ("query", query),
+            ("start", start),
+            ("end", end),
+            ("step", step.as_str()),
+            ("stats", "all"),
+        ]);
+        self.send_timed(query, request, true).await
     }
 
-    pub async fn get_http_requests_total(&self, time_range: &str) -> Result<f64> {
+    pub async fn get_http_requests_total(
+        &self,
+        time_range: &str,
+        diagnostics: &mut QueryDiagnostics,
+    ) -> Result<f64> {
         // Try common metrics first, then fall back to Prometheus self-monitoring metrics
         let queries = if time_range == "all" {
             // For "all" time, get total counts
             vec![
-                "sum(http_requests_total)".to_string(),
-                "sum(prometheus_http_requests_total)".to_string(),
-                "count(up)".to_string(),
+                Expr::from(Selector::new("http_requests_total")).sum(),
+                Expr::from(Selector::new("prometheus_http_requests_total")).sum(),
+                Expr::from(Selector::new("up")).count(),
             ]
         } else {
             // For specific time ranges, use rate
             vec![
-                format!("sum(rate(http_requests_total[{}]))", time_range),
-                format!("sum(rate(prometheus_http_requests_total[{}]))", time_range),
-                format!("sum(rate(up[{}]))", time_range),
+                windowed(Selector::new("http_requests_total"), time_range).sum(),
+                windowed(Selector::new("prometheus_http_requests_total"), time_range).sum(),
+                windowed(Selector::new("up"), time_range).sum(),
             ]
         };
         
         for query in queries {
-            if let Ok(response) = self.query(&query).await {
+            if let Some(response) = self.query_with_diagnostics(&query.to_string(), diagnostics).await {
                 if let Some(result) = response.data.result.first() {
                     if let Some((_, value)) = &result.value {
                         let val = value.parse::<f64>().unwrap_or(0.0);
@@ -127,140 +769,405 @@ impl PrometheusClient {
         Ok(0.0)
     }
 
-    pub async fn get_uri_metrics(&self, application: Option<&str>, time_range: &str) -> Result<Vec<UriMetric>> {
+    pub async fn get_uri_metrics(
+        &self,
+        application: Option<&str>,
+        time_range: &str,
+        ranking: Ranking,
+        diagnostics: &mut QueryDiagnostics,
+    ) -> Result<Vec<UriMetric>> {
         let mut uri_metrics = Vec::new();
         
-        // Build query based on whether we have an application filter and time range
-        let query = if time_range == "all" {
-            // For "all" time range, use the current values without rate function
-            if let Some(app) = application {
-                format!(
-                    r#"sum by(uri)(http_server_requests_seconds_sum{{application="{}"}}) / sum by(uri)(http_server_requests_seconds_count{{application="{}"}})"#,
-                    app, app
-                )
-            } else {
-                "sum by(uri)(http_server_requests_seconds_sum) / sum by(uri)(http_server_requests_seconds_count)".to_string()
+        // Selectors for the duration histogram, optionally filtered by application
+        let series = |metric: &str| {
+            let selector = Selector::new(metric);
+            match application {
+                Some(app) => selector.eq("application", app),
+                None => selector,
             }
-        } else {
-            // For specific time ranges, use rate function
-            if let Some(app) = application {
-                format!(
-                    r#"sum by(uri)(rate(http_server_requests_seconds_sum{{application="{}"}}[{}])) / sum by(uri)(rate(http_server_requests_seconds_count{{application="{}"}}[{}]))"#,
-                    app, time_range, app, time_range
-                )
-            } else {
-                format!("sum by(uri)(rate(http_server_requests_seconds_sum[{}])) / sum by(uri)(rate(http_server_requests_seconds_count[{}]))", time_range, time_range)
+        };
+        let (latency_query, count_query) = uri_queries(series, time_range);
+        
+        // Each ranking adds the query for its own dimension
+        let extra_query = match ranking {
+            Ranking::Busiest | Ranking::Slowest => None,
+            Ranking::Errors => Some(
+                windowed(series("http_server_requests_seconds_count").re("status", "5.."), time_range)
+                    .sum_by(&["uri"]),
+            ),
+            // "All" has no previous window to compare against
+            Ranking::Change if time_range == "all" => None,
+            Ranking::Change => {
+                let previous = |metric: &str| {
+                    rate(series(metric).range(time_range).offset(time_range)).sum_by(&["uri"])
+                };
+                Some(previous("http_server_requests_seconds_sum") / previous("http_server_requests_seconds_count"))
             }
         };
         
-        match self.query(&query).await {
-            Ok(response) => {
-                for result in response.data.result {
-                    if let Some(uri) = result.metric.get("uri") {
-                        if let Some((_, value)) = &result.value {
-                            if let Ok(duration) = value.parse::<f64>() {
-                                if duration > 0.0 && !duration.is_nan() {
-                                // Get request count for this URI
-                                let count_query = if time_range == "all" {
-                                    // For "all", get the total count
-                                    if let Some(app) = application {
-                                        format!(
-                                            r#"sum(http_server_requests_seconds_count{{application="{}", uri="{}"}})"#,
-                                            app, uri
-                                        )
-                                    } else {
-                                        format!(
-                                            r#"sum(http_server_requests_seconds_count{{uri="{}"}})"#,
-                                            uri
-                                        )
-                                    }
-                                } else {
-                                    // For specific time ranges, use rate
-                                    if let Some(app) = application {
-                                        format!(
-                                            r#"sum(rate(http_server_requests_seconds_count{{application="{}", uri="{}"}}[{}]))"#,
-                                            app, uri, time_range
-                                        )
-                                    } else {
-                                        format!(
-                                            r#"sum(rate(http_server_requests_seconds_count{{uri="{}"}}[{}]))"#,
-                                            uri, time_range
-                                        )
-                                    }
-                                };
-                                
-                                let mut request_count = 0.0;
-                                if let Ok(count_response) = self.query(&count_query).await {
-                                    if let Some(count_result) = count_response.data.result.first() {
-                                        if let Some((_, count_value)) = &count_result.value {
-                                            let count = count_value.parse::<f64>().unwrap_or(0.0);
-                                            // For "all" time, just show total count; for rate, convert to per minute
-                                            request_count = if time_range == "all" {
-                                                count // Total count
-                                            } else {
-                                                count * 60.0 // Convert rate per second to per minute
-                                            };
-                                        }
-                                    }
-                                }
-                                
-                                uri_metrics.push(UriMetric {
-                                    uri: uri.clone(),
-                                    avg_duration_ms: duration * 1000.0, // Convert to milliseconds
-                                    request_count,
-                                });
-                                }
-                            }
-                        }
-                    }
-                }
+        let latencies = self.query_by_uri(&latency_query.to_string(), diagnostics).await;
+        let counts = self.query_by_uri(&count_query.to_string(), diagnostics).await;
+        // Bandwidth is a rate, which a lifetime "all" total doesn't have
+        let bandwidth = match &self.bandwidth.metric {
+            Some(metric) if time_range != "all" => {
+                let label = self.bandwidth.uri_label.as_str();
+                let query = rate(Selector::new(metric).range(time_range)).sum_by(&[label]);
+                Some(self.query_by_label(&query.to_string(), label, diagnostics).await)
             }
-            Err(_e) => {
-                // Query failed, will try fallback
+            _ => None,
+        };
+        let extras = match &extra_query {
+            Some(query) => self.query_by_uri(&query.to_string(), diagnostics).await,
+            None => HashMap::new(),
+        };
+        
+        // For "all" time, counts are totals; for rates, convert per second to per minute
+        let per_minute = if time_range == "all" { 1.0 } else { 60.0 };
+        for (uri, duration) in latencies {
+            if duration > 0.0 && !duration.is_nan() {
+                let extra = extras.get(&uri).copied();
+                uri_metrics.push(UriMetric {
+                    avg_duration_ms: duration * 1000.0, // Convert to milliseconds
+                    request_count: counts.get(&uri).copied().unwrap_or(0.0) * per_minute,
+                    errors_per_min: match ranking {
+                        Ranking::Errors => extra.unwrap_or(0.0) * per_minute,
+                        _ => 0.0,
+                    },
+                    change_pct: match ranking {
+                        Ranking::Change => extra
+                            .filter(|previous| *previous > 0.0)
+                            .map(|previous| (duration - previous) / previous * 100.0),
+                        _ => None,
+                    },
+                    bytes_per_sec: bandwidth.as_ref().map(|bytes| bytes.get(&uri).copied().unwrap_or(0.0)),
+                    uri,
+                });
             }
         }
         
         // If no real data, try alternative queries
         if uri_metrics.is_empty() {
             // Try simpler query without application filter
-            if let Ok(response) = self.query("sum by(uri)(rate(http_requests_total[5m]))").await {
-                for result in response.data.result {
-                    if let Some(uri) = result.metric.get("uri") {
-                        if let Some((_, value)) = &result.value {
-                            if let Ok(count) = value.parse::<f64>() {
-                                if count > 0.0 {
-                                    uri_metrics.push(UriMetric {
-                                        uri: uri.clone(),
-                                        avg_duration_ms: 50.0 + (uri.len() as f64 * 10.0), // Dummy duration based on URI length
-                                        request_count: count * 60.0,
-                                    });
-                                }
-                            }
-                        }
-                    }
+            let fallback = windowed(Selector::new("http_requests_total"), "5m").sum_by(&["uri"]);
+            for (uri, count) in self.query_by_uri(&fallback.to_string(), diagnostics).await {
+                if count > 0.0 {
+                    uri_metrics.push(UriMetric {
+                        avg_duration_ms: 50.0 + (uri.len() as f64 * 10.0), // Dummy duration based on URI length
+                        request_count: count * 60.0,
+                        errors_per_min: 0.0,
+                        change_pct: None,
+                        bytes_per_sec: None,
+                        uri,
+                    });
                 }
             }
         }
         
         // Don't provide test data - just return empty if no real data
         
-        // Sort by request count (highest first)
-        uri_metrics.sort_by(|a, b| b.request_count.partial_cmp(&a.request_count).unwrap());
+        let mut uri_metrics = self.templates.group_metrics(uri_metrics);
+        ranking.sort(&mut uri_metrics);
         
         // Don't truncate - let UI handle pagination
-        // uri_metrics.truncate(5);
         
         Ok(uri_metrics)
     }
 
-    pub async fn get_metrics(&self, time_range: &str) -> Result<MetricsData> {
-        let requests_total = self.get_http_requests_total(time_range).await.unwrap_or(0.0);
+    /// Run an instant query and map each result's `uri` label to its value.
+    async fn query_by_uri(&self, query: &str, diagnostics: &mut QueryDiagnostics) -> HashMap<String, f64> {
+        self.query_by_label(query, "uri", diagnostics).await
+    }
+
+    /// Run an instant query and map each result's `label` to its value.
+    async fn query_by_label(&self, query: &str, label: &str, diagnostics: &mut QueryDiagnostics) -> HashMap<String, f64> {
+        let mut values = HashMap::new();
+        if let Some(response) = self.query_with_diagnostics(query, diagnostics).await {
+            for result in response.data.result {
+                if let (Some(uri), Some((_, value))) = (result.metric.get(label), &result.value) {
+                    if let Ok(value) = value.parse::<f64>() {
+                        values.insert(uri.clone(), value);
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    pub async fn get_metrics(&self, time_range: &str, ranking: Ranking) -> Result<MetricsData> {
+        let mut diagnostics = QueryDiagnostics::default();
+        let requests_total = self
+            .get_http_requests_total(time_range, &mut diagnostics)
+            .await
+            .unwrap_or(0.0);
         // Don't filter by application since it doesn't exist in the metrics
-        let uri_metrics = self.get_uri_metrics(None, time_range).await.unwrap_or_default();
+        let uri_metrics = self
+            .get_uri_metrics(None, time_range, ranking, &mut diagnostics)
+            .await
+            .unwrap_or_default();
+        let egress_bytes_per_sec = match &self.bandwidth.metric {
+            Some(metric) => match self.get_egress_history(metric).await {
+                Ok(egress) => egress,
+                Err(e) => {
+                    diagnostics.warning(format!("Egress history: {}", e));
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
         
         Ok(MetricsData {
             http_requests_total: requests_total,
             uri_metrics,
+            warnings: diagnostics.warnings,
+            errors: diagnostics.errors,
+            egress_bytes_per_sec,
+        })
+    }
+
+    /// Total response bandwidth over the last 30 minutes, one point per
+    /// minute, for the egress sparkline.
+    async fn get_egress_history(&self, metric: &str) -> Result<Vec<f64>> {
+        let end = self.server_now().timestamp() / 60 * 60;
+        let start = end - 30 * 60;
+        let query = rate(Selector::new(metric).range("1m")).sum().to_string();
+        let response = self.query_range(&query, &start.to_string(), &end.to_string(), "60").await?;
+        Ok(response
+            .data
+            .result
+            .into_iter()
+            .next()
+            .and_then(|result| result.values)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, value)| value.parse::<f64>().ok().filter(|v| v.is_finite()).unwrap_or(0.0))
+            .collect())
+    }
+
+    /// Every series behind a metrics row with its full label set, busiest
+    /// first: what `sum by (uri)` adds up into the row.
+    pub async fn get_uri_series(&self, uri: &str, time_range: &str) -> Result<Vec<SeriesValue>> {
+        let series = |metric: &str| match self.templates.members_pattern(uri) {
+            Some(pattern) => Selector::new(metric).re("uri", &pattern),
+            None => Selector::new(metric).eq("uri", uri),
+        };
+        let count = windowed(series("http_server_requests_seconds_count"), time_range);
+        let latency = (windowed(series("http_server_requests_seconds_sum"), time_range) / count.clone()).to_string();
+        let count = count.to_string();
+        let (counts, latencies) = tokio::join!(self.query(&count), self.query(&latency));
+
+        // Division drops the metric name; drop it on both sides to match them up
+        let labels = |metric: HashMap<String, String>| -> BTreeMap<String, String> {
+            metric.into_iter().filter(|(name, _)| name != "__name__").collect()
+        };
+        let latencies: HashMap<BTreeMap<String, String>, f64> = latencies?
+            .data
+            .result
+            .into_iter()
+            .filter_map(|result| {
+                let latency = result.value.as_ref()?.1.parse::<f64>().ok().filter(|v| v.is_finite())?;
+                Some((labels(result.metric), latency * 1000.0))
+            })
+            .collect();
+        let per_minute = if time_range == "all" { 1.0 } else { 60.0 };
+        let mut values: Vec<SeriesValue> = counts?
+            .data
+            .result
+            .into_iter()
+            .filter_map(|result| {
+                let (at, value) = result.value?;
+                let labels = labels(result.metric);
+                Some(SeriesValue {
+                    at,
+                    requests_per_min: value.parse::<f64>().ok()? * per_minute,
+                    latency_ms: latencies.get(&labels).copied(),
+                    labels,
+                })
+            })
+            .collect();
+        values.sort_by(|a, b| b.requests_per_min.total_cmp(&a.requests_per_min));
+        Ok(values)
+    }
+
+    /// Range query for a single URI's latency and request rate over the last
+    /// `window_minutes`, at about one point per chart column.
+    pub async fn get_uri_history(&self, uri: &str, window_minutes: i64, columns: usize) -> Result<UriHistory> {
+        let step = auto_step(window_minutes * 60, columns);
+        // Align to the step so points stay put between refreshes
+        let end = self.server_now().timestamp() / step * step;
+        let start = end - window_minutes * 60;
+        // rate() needs a few scrapes per window to produce a value
+        let rate_window = format!("{}s", (step * 4).max(60));
+        
+        // A templated row covers every raw path its rules match
+        let series = |metric: &str| {
+            let selector = match self.templates.members_pattern(uri) {
+                Some(pattern) => Selector::new(metric).re("uri", &pattern),
+                None => Selector::new(metric).eq("uri", uri),
+            };
+            selector.range(&rate_window)
+        };
+        let latency = (rate(series("http_server_requests_seconds_sum")).sum()
+            / rate(series("http_server_requests_seconds_count")).sum())
+        .to_string();
+        let requests = rate(series("http_server_requests_seconds_count")).sum().to_string();
+        
+        let (start, end, step) = (start.to_string(), end.to_string(), step.to_string());
+        let (latency_response, requests_response) = tokio::join!(
+            self.query_range(&latency, &start, &end, &step),
+            self.query_range(&requests, &start, &end, &step),
+        );
+        
+        let points = |response: PrometheusResponse, scale: f64| -> Vec<(f64, f64)> {
+            response
+                .data
+                .result
+                .into_iter()
+                .next()
+                .and_then(|result| result.values)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(t, v)| v.parse::<f64>().ok().filter(|v| v.is_finite()).map(|v| (t, v * scale)))
+                .collect()
+        };
+        
+        Ok(UriHistory {
+            latency_ms: lttb(&points(latency_response?, 1000.0), columns),
+            requests_per_min: lttb(&points(requests_response?, 60.0), columns),
+            queries: vec![latency, requests],
         })
     }
-}
\ No newline at end of file
+
+    /// Cumulative request counters per URI, sampled into the client-side
+    /// history that backs the "All" range.
+    pub async fn get_uri_totals(&self) -> Result<HashMap<String, UriTotals>> {
+        let count = Expr::from(Selector::new("http_server_requests_seconds_count"))
+            .sum_by(&["uri"])
+            .to_string();
+        let sum = Expr::from(Selector::new("http_server_requests_seconds_sum"))
+            .sum_by(&["uri"])
+            .to_string();
+        let (count, sum) = tokio::join!(self.query(&count), self.query(&sum));
+        
+        let by_uri = |response: PrometheusResponse| -> HashMap<String, f64> {
+            response
+                .data
+                .result
+                .into_iter()
+                .filter_map(|result| {
+                    let uri = result.metric.get("uri")?.clone();
+                    let value = result.value?.1.parse::<f64>().ok().filter(|v| v.is_finite())?;
+                    Some((uri, value))
+                })
+                .collect()
+        };
+        let sum = by_uri(sum?);
+        
+        let totals = by_uri(count?)
+            .into_iter()
+            .map(|(uri, count)| {
+                let sum_seconds = sum.get(&uri).copied().unwrap_or(0.0);
+                (uri, UriTotals { count, sum_seconds })
+            })
+            .collect();
+        Ok(self.templates.group_totals(totals))
+    }
+
+    /// The counters of [`get_uri_totals`](Self::get_uri_totals) as they stood
+    /// every `step` seconds from `start` to `end` (unix seconds), oldest first.
+    /// Fills the history back in after Prometheus was unreachable. Times are
+    /// on our clock, like the history's, and shifted to the server's for the query.
+    pub async fn get_uri_totals_range(&self, start: i64, end: i64, step: i64) -> Result<Vec<(f64, HashMap<String, UriTotals>)>> {
+        let skew = self.clock_skew.seconds().unwrap_or(0.0);
+        let count = Expr::from(Selector::new("http_server_requests_seconds_count"))
+            .sum_by(&["uri"])
+            .to_string();
+        let sum = Expr::from(Selector::new("http_server_requests_seconds_sum"))
+            .sum_by(&["uri"])
+            .to_string();
+        let shift = |t: i64| (t as f64 + skew).round() as i64;
+        let (start, end, step) = (shift(start).to_string(), shift(end).to_string(), step.to_string());
+        let (count, sum) = tokio::join!(
+            self.query_range(&count, &start, &end, &step),
+            self.query_range(&sum, &start, &end, &step),
+        );
+
+        // Keyed by millisecond timestamp so both queries' samples line up
+        let mut samples: BTreeMap<i64, (f64, HashMap<String, UriTotals>)> = BTreeMap::new();
+        for (response, is_count) in [(count?, true), (sum?, false)] {
+            for result in response.data.result {
+                let Some(uri) = result.metric.get("uri") else { continue };
+                for (t, value) in result.values.unwrap_or_default() {
+                    let Some(value) = value.parse::<f64>().ok().filter(|v| v.is_finite()) else { continue };
+                    let (_, totals) = samples.entry((t * 1000.0).round() as i64).or_insert((t, HashMap::new()));
+                    let entry = totals.entry(uri.clone()).or_default();
+                    if is_count {
+                        entry.count = value;
+                    } else {
+                        entry.sum_seconds = value;
+                    }
+                }
+            }
+        }
+        Ok(samples
+            .into_values()
+            .map(|(t, totals)| (t - skew, self.templates.group_totals(totals)))
+            .collect())
+    }
+
+    /// Service-to-service call and error rates from `traces_service_graph_*`
+    /// metrics. Empty when tracing metrics aren't available.
+    pub async fn get_service_graph(&self, time_range: &str) -> Result<Vec<ServiceEdge>> {
+        let edge = ["client", "server"];
+        let requests = windowed(Selector::new("traces_service_graph_request_total"), time_range)
+            .sum_by(&edge)
+            .to_string();
+        let failed = windowed(Selector::new("traces_service_graph_request_failed_total"), time_range)
+            .sum_by(&edge)
+            .to_string();
+        // Quantiles need a rate window; use 5m for "all"
+        let latency_window = if time_range == "all" { "5m" } else { time_range };
+        let p95 = histogram_quantile(
+            0.95,
+            rate(Selector::new("traces_service_graph_request_server_seconds_bucket").range(latency_window))
+                .sum_by(&["le", "client", "server"]),
+        )
+        .to_string();
+        
+        let (requests, failed, p95) = tokio::join!(
+            self.query(&requests),
+            self.query(&failed),
+            self.query(&p95),
+        );
+        
+        let by_edge = |response: Option<PrometheusResponse>| -> HashMap<(String, String), f64> {
+            response
+                .map(|r| r.data.result)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|result| {
+                    let client = result.metric.get("client")?.clone();
+                    let server = result.metric.get("server")?.clone();
+                    let value = result.value?.1.parse::<f64>().ok().filter(|v| v.is_finite())?;
+                    Some(((client, server), value))
+                })
+                .collect()
+        };
+        let failed = by_edge(failed.ok());
+        let p95 = by_edge(p95.ok());
+        
+        let per_minute = if time_range == "all" { 1.0 } else { 60.0 };
+        let mut edges: Vec<ServiceEdge> = by_edge(Some(requests?))
+            .into_iter()
+            .map(|(key, requests)| ServiceEdge {
+                requests_per_min: requests * per_minute,
+                errors_per_min: failed.get(&key).copied().unwrap_or(0.0) * per_minute,
+                p95_ms: p95.get(&key).map(|seconds| seconds * 1000.0),
+                client: key.0,
+                server: key.1,
+            })
+            .collect();
+        edges.sort_by(|a, b| a.client.cmp(&b.client).then_with(|| a.server.cmp(&b.server)));
+        
+        Ok(edges)
+    }
+}
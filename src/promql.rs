@@ -0,0 +1,233 @@
+//! Small typed builder for the PromQL expressions rustdash issues.
+//!
+//! Label values are always escaped, and range functions only accept range
+//! vectors, so the generated queries are well-formed by construction.
+//!
+//! ```
+//! use rustdash::promql::{rate, Selector};
+//!
+//! let query = rate(Selector::new("http_requests_total").eq("job", "api").range("5m"))
+//!     .sum_by(&["uri"]);
+//! assert_eq!(query.to_string(), r#"sum by (uri) (rate(http_requests_total{job="api"}[5m]))"#);
+//! ```
+
+use std::fmt;
+use std::ops::{Add, Div, Mul};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchOp {
+    Eq,
+    NotEq,
+    Regex,
+    NotRegex,
+}
+
+impl MatchOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchOp::Eq => "=",
+            MatchOp::NotEq => "!=",
+            MatchOp::Regex => "=~",
+            MatchOp::NotRegex => "!~",
+        }
+    }
+}
+
+/// Seconds in a duration written the PromQL way: `90s`, `30m`, `2h`, `1d`,
+/// `1w` or combinations such as `1h30m`. None if it isn't one or is zero.
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let mut seconds = 0i64;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let number: i64 = rest[..digits].parse().ok()?;
+        let unit_len = rest[digits..].find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len() - digits);
+        let unit = match &rest[digits..digits + unit_len] {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86_400,
+            "w" => 604_800,
+            _ => return None,
+        };
+        seconds = seconds.checked_add(number.checked_mul(unit)?)?;
+        rest = &rest[digits + unit_len..];
+    }
+    (seconds > 0).then_some(seconds)
+}
+
+/// Quote a label value, escaping backslashes, quotes and newlines.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// An instant vector selector: `metric{label="value", ...}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    metric: String,
+    matchers: Vec<(String, MatchOp, String)>,
+}
+
+impl Selector {
+    pub fn new(metric: &str) -> Self {
+        Self {
+            metric: metric.to_string(),
+            matchers: Vec::new(),
+        }
+    }
+
+    pub fn matching(mut self, label: &str, op: MatchOp, value: &str) -> Self {
+        self.matchers.push((label.to_string(), op, value.to_string()));
+        self
+    }
+
+    pub fn eq(self, label: &str, value: &str) -> Self {
+        self.matching(label, MatchOp::Eq, value)
+    }
+
+    pub fn ne(self, label: &str, value: &str) -> Self {
+        self.matching(label, MatchOp::NotEq, value)
+    }
+
+    pub fn re(self, label: &str, pattern: &str) -> Self {
+        self.matching(label, MatchOp::Regex, pattern)
+    }
+
+    pub fn not_re(self, label: &str, pattern: &str) -> Self {
+        self.matching(label, MatchOp::NotRegex, pattern)
+    }
+
+    /// Turn into a range vector over `duration` (e.g. "5m").
+    pub fn range(self, duration: &str) -> RangeVector {
+        RangeVector(format!("{}[{}]", self, duration))
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.metric)?;
+        if !self.matchers.is_empty() {
+            let matchers: Vec<String> = self
+                .matchers
+                .iter()
+                .map(|(label, op, value)| format!("{}{}{}", label, op.as_str(), quote(value)))
+                .collect();
+            write!(f, "{{{}}}", matchers.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A range vector: `selector[duration]`. Only accepted by range functions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeVector(String);
+
+impl RangeVector {
+    /// Shift the range back in time: `selector[duration] offset by`.
+    pub fn offset(self, by: &str) -> RangeVector {
+        RangeVector(format!("{} offset {}", self.0, by))
+    }
+}
+
+/// A complete PromQL expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(String);
+
+impl From<Selector> for Expr {
+    fn from(selector: Selector) -> Self {
+        Expr(selector.to_string())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub fn rate(range: RangeVector) -> Expr {
+    Expr(format!("rate({})", range.0))
+}
+
+pub fn increase(range: RangeVector) -> Expr {
+    Expr(format!("increase({})", range.0))
+}
+
+pub fn histogram_quantile(quantile: f64, buckets: Expr) -> Expr {
+    Expr(format!("histogram_quantile({}, {})", quantile, buckets.0))
+}
+
+fn by_clause(labels: &[&str]) -> String {
+    format!("by ({})", labels.join(", "))
+}
+
+impl Expr {
+    /// An expression written by the user, e.g. in a panel's config, taken as is.
+    pub fn raw(query: &str) -> Expr {
+        Expr(query.to_string())
+    }
+
+    pub fn sum(self) -> Expr {
+        Expr(format!("sum({})", self.0))
+    }
+
+    pub fn sum_by(self, labels: &[&str]) -> Expr {
+        Expr(format!("sum {} ({})", by_clause(labels), self.0))
+    }
+
+    pub fn avg_by(self, labels: &[&str]) -> Expr {
+        Expr(format!("avg {} ({})", by_clause(labels), self.0))
+    }
+
+    pub fn max_by(self, labels: &[&str]) -> Expr {
+        Expr(format!("max {} ({})", by_clause(labels), self.0))
+    }
+
+    pub fn count(self) -> Expr {
+        Expr(format!("count({})", self.0))
+    }
+
+    pub fn topk(self, k: usize) -> Expr {
+        Expr(format!("topk({}, {})", k, self.0))
+    }
+
+    pub fn scale(self, factor: f64) -> Expr {
+        Expr(format!("({}) * {}", self.0, factor))
+    }
+}
+
+impl Add for Expr {
+    type Output = Expr;
+
+    /// Parenthesized, so a sum can stand on either side of `/`.
+    fn add(self, rhs: Expr) -> Expr {
+        Expr(format!("({} + {})", self.0, rhs.0))
+    }
+}
+
+impl Div for Expr {
+    type Output = Expr;
+
+    fn div(self, rhs: Expr) -> Expr {
+        Expr(format!("{} / {}", self.0, rhs.0))
+    }
+}
+
+impl Mul for Expr {
+    type Output = Expr;
+
+    fn mul(self, rhs: Expr) -> Expr {
+        Expr(format!("({}) * ({})", self.0, rhs.0))
+    }
+}
@@ -0,0 +1,402 @@
+//! Ad-hoc PromQL editor (`Q`): type a query, run it as an instant query and
+//! read the series it returns. While typing, a completion list offers metric
+//! names, label names and label values for the word under the cursor,
+//! fuzzy-matched against what has been typed of it.
+//!
+//! What completions are drawn from is looked up lazily, the first time the
+//! cursor needs it: metric names on the first word, a metric's label sets
+//! (series API) once its selector is opened, and label names or values
+//! across all series for selectors without a metric name.
+
+use crate::fuzzy::{self, Match};
+use crate::prometheus::{PrometheusClient, PrometheusResponse};
+use crate::promql;
+use crate::text::LineInput;
+use crate::ui::AppState;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use tokio::sync::Mutex;
+
+/// Completion entries listed at once.
+pub const MAX_COMPLETIONS: usize = 8;
+
+/// Functions and aggregations, offered along with metric names.
+pub const FUNCTIONS: &[&str] = &[
+    "abs", "absent", "absent_over_time", "avg", "avg_over_time", "bottomk", "ceil", "changes", "clamp", "clamp_max",
+    "clamp_min", "count", "count_over_time", "count_values", "delta", "deriv", "floor", "group", "histogram_quantile",
+    "idelta", "increase", "irate", "label_join", "label_replace", "last_over_time", "max", "max_over_time", "min",
+    "min_over_time", "predict_linear", "quantile", "quantile_over_time", "rate", "resets", "round", "scalar", "sort",
+    "sort_desc", "stddev", "stddev_over_time", "sum", "sum_over_time", "time", "timestamp", "topk", "vector",
+];
+
+/// What the word at the cursor is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Completing {
+    Metric, // A metric or function name
+    // A label inside a selector or a `by (...)` / `without (...)` clause
+    LabelName {
+        metric: Option<String>, // The selector's metric, if it has one
+        in_selector: bool,      // Completing adds `=""`
+    },
+    // Inside a matcher's quotes
+    LabelValue {
+        metric: Option<String>,
+        label: String,
+        regex: bool, // `=~` or `!~`: values are inserted regex-escaped
+    },
+}
+
+/// The word under the cursor and what it completes to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Context {
+    pub completing: Completing,
+    pub start: usize,   // Byte offset where the word starts
+    pub prefix: String, // The word up to the cursor
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == ':'
+}
+
+/// The identifier `text` ends with, after trailing whitespace.
+fn trailing_name(text: &str) -> &str {
+    let text = text.trim_end();
+    let start = text.rfind(|c: char| !is_name_char(c)).map_or(0, |i| i + 1);
+    &text[start..]
+}
+
+/// What completing at byte offset `cursor` of `input` would insert, if anything.
+pub fn context(input: &str, cursor: usize) -> Option<Context> {
+    let before = &input[..cursor];
+    // Open brackets and where they are, and the start of an unclosed string
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut quote: Option<(char, usize)> = None;
+    let mut escaped = false;
+    for (i, c) in before.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some((q, _)) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' | '`' => quote = Some((c, i)),
+                '{' | '(' | '[' => open.push((c, i)),
+                '}' | ')' | ']' => {
+                    open.pop();
+                }
+                _ => {}
+            },
+        }
+    }
+    let selector_metric = |brace: usize| Some(trailing_name(&before[..brace])).filter(|name| !name.is_empty()).map(str::to_string);
+
+    if let Some((quote, at)) = quote {
+        let (bracket, brace) = *open.last()?;
+        if bracket != '{' || quote != '"' {
+            return None;
+        }
+        // `label="`, `label=~"`, ...
+        let matcher = before[..at].trim_end();
+        let regex = matcher.ends_with("=~") || matcher.ends_with("!~");
+        let name_end = matcher.trim_end_matches(['=', '!', '~']);
+        if name_end.len() == matcher.len() {
+            return None;
+        }
+        let label = trailing_name(name_end);
+        if label.is_empty() {
+            return None;
+        }
+        return Some(Context {
+            completing: Completing::LabelValue { metric: selector_metric(brace), label: label.to_string(), regex },
+            start: at + 1,
+            prefix: before[at + 1..].to_string(),
+        });
+    }
+
+    let start = before.rfind(|c: char| !is_name_char(c)).map_or(0, |i| i + 1);
+    let word = &before[start..];
+    let preceding = before[..start].trim_end();
+    let completing = match open.last() {
+        Some(('{', brace)) if preceding.ends_with(['{', ',']) => {
+            Completing::LabelName { metric: selector_metric(*brace), in_selector: true }
+        }
+        Some(('(', paren)) if preceding.ends_with(['(', ',']) && matches!(trailing_name(&before[..*paren]), "by" | "without") => {
+            Completing::LabelName { metric: None, in_selector: false }
+        }
+        Some(('(', _)) | None if !word.is_empty() && !word.starts_with(|c: char| c.is_ascii_digit()) => Completing::Metric,
+        _ => return None,
+    };
+    Some(Context { completing, start, prefix: word.to_string() })
+}
+
+/// A lookup the completions wait for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Lookup {
+    MetricNames,
+    Series(String),      // Label sets of one metric
+    LabelNames,
+    LabelValues(String), // Values of one label across all series
+}
+
+/// Names and values looked up so far.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    pub metrics: Option<Vec<String>>,
+    pub series: HashMap<String, BTreeMap<String, BTreeSet<String>>>, // Label -> values, per metric
+    pub labels: Option<Vec<String>>,
+    pub label_values: HashMap<String, Vec<String>>,
+    pub pending: HashSet<Lookup>, // Sent and not answered yet
+    pub error: Option<String>,    // Why the last lookup failed
+}
+
+/// The answer to a lookup.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Found {
+    Names(Vec<String>),                  // Metric names, label names or label values
+    Series(Vec<HashMap<String, String>>), // Label sets
+}
+
+impl Catalog {
+    /// File the answer to `lookup`. A failed lookup completes to nothing
+    /// rather than being asked again on every key.
+    pub fn store(&mut self, lookup: &Lookup, found: Result<Found, String>) {
+        self.pending.remove(lookup);
+        if found.is_ok() {
+            self.error = None;
+        }
+        let (names, series) = match found {
+            Ok(Found::Names(names)) => (names, Vec::new()),
+            Ok(Found::Series(series)) => (Vec::new(), series),
+            Err(e) => {
+                self.error = Some(e);
+                (Vec::new(), Vec::new())
+            }
+        };
+        match lookup {
+            Lookup::MetricNames => self.metrics = Some(names),
+            Lookup::LabelNames => self.labels = Some(names),
+            Lookup::LabelValues(label) => {
+                self.label_values.insert(label.clone(), names);
+            }
+            Lookup::Series(metric) => self.store_series(metric, series),
+        }
+    }
+
+    /// Index the label sets of `metric`'s series.
+    pub fn store_series(&mut self, metric: &str, series: Vec<HashMap<String, String>>) {
+        let mut labels: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for set in series {
+            for (name, value) in set {
+                if name != "__name__" {
+                    labels.entry(name).or_default().insert(value);
+                }
+            }
+        }
+        self.series.insert(metric.to_string(), labels);
+    }
+
+    /// What `completing` picks from, or the lookup that would tell.
+    fn candidates(&self, completing: &Completing) -> Result<Vec<&str>, Lookup> {
+        match completing {
+            Completing::Metric => {
+                let metrics = self.metrics.as_ref().ok_or(Lookup::MetricNames)?;
+                Ok(metrics.iter().map(String::as_str).chain(FUNCTIONS.iter().copied()).collect())
+            }
+            Completing::LabelName { metric: Some(metric), .. } => {
+                let labels = self.series.get(metric).ok_or_else(|| Lookup::Series(metric.clone()))?;
+                Ok(labels.keys().map(String::as_str).collect())
+            }
+            Completing::LabelName { metric: None, .. } => {
+                let labels = self.labels.as_ref().ok_or(Lookup::LabelNames)?;
+                Ok(labels.iter().map(String::as_str).filter(|name| *name != "__name__").collect())
+            }
+            Completing::LabelValue { metric: Some(metric), label, .. } => {
+                let labels = self.series.get(metric).ok_or_else(|| Lookup::Series(metric.clone()))?;
+                Ok(labels.get(label).into_iter().flatten().map(String::as_str).collect())
+            }
+            Completing::LabelValue { metric: None, label, .. } => {
+                let values = self.label_values.get(label).ok_or_else(|| Lookup::LabelValues(label.clone()))?;
+                Ok(values.iter().map(String::as_str).collect())
+            }
+        }
+    }
+}
+
+/// One series of a query result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultRow {
+    pub series: String, // `name{label="value", ...}`
+    pub value: String,  // The sample, or for a range vector its count and the latest
+}
+
+/// Result rows of an instant query, in the order Prometheus sent them.
+pub fn result_rows(response: &PrometheusResponse) -> Vec<ResultRow> {
+    response
+        .data
+        .result
+        .iter()
+        .map(|result| {
+            let mut labels: Vec<(&String, &String)> = result.metric.iter().filter(|(name, _)| *name != "__name__").collect();
+            labels.sort();
+            let labels: Vec<String> = labels.iter().map(|(name, value)| format!("{}={}", name, promql::quote(value))).collect();
+            let name = result.metric.get("__name__").map_or("", String::as_str);
+            let series = if labels.is_empty() && !name.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}{{{}}}", name, labels.join(", "))
+            };
+            let value = match (&result.value, &result.values) {
+                (Some((_, value)), _) => value.clone(),
+                (None, Some(values)) => {
+                    format!("{} samples, last {}", values.len(), values.last().map_or("-", |(_, value)| value.as_str()))
+                }
+                (None, None) => "-".to_string(),
+            };
+            ResultRow { series, value }
+        })
+        .collect()
+}
+
+/// Work the editor hands to the background task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditorRequest {
+    Lookup(Lookup),
+    Run(String),
+}
+
+/// State of the editor popup while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct QueryEditor {
+    pub input: LineInput,
+    pub context: Option<Context>,           // What the word at the cursor completes to
+    pub completions: Vec<(String, Match)>,  // Best matches first
+    pub selected: usize,                    // Completion Tab inserts
+    pub dismissed: bool,                    // Completions hidden with ESC until the next edit
+    pub catalog: Catalog,
+    pub running: bool,                      // The query was sent and not answered yet
+    pub result: Option<Result<Vec<ResultRow>, String>>,
+    pub scroll_offset: usize,               // First result row shown
+}
+
+impl QueryEditor {
+    /// Whether the completion list is showing.
+    pub fn completing(&self) -> bool {
+        !self.dismissed && !self.completions.is_empty()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.input.insert(c);
+        self.dismissed = false;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.input.backspace() {
+            self.dismissed = false;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.input.delete() {
+            self.dismissed = false;
+        }
+    }
+
+    /// Scroll the result rows by `delta`.
+    pub fn scroll(&mut self, delta: isize) {
+        let rows = match &self.result {
+            Some(Ok(rows)) => rows.len(),
+            _ => 0,
+        };
+        self.scroll_offset = self.scroll_offset.saturating_add_signed(delta).min(rows.saturating_sub(1));
+    }
+
+    /// Move through the completion list, wrapping around.
+    pub fn select(&mut self, delta: isize) {
+        let len = self.completions.len() as isize;
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// Recompute the completions for the cursor's position. Returns the
+    /// lookup they wait for, if it hasn't been asked for yet.
+    pub fn update_completions(&mut self) -> Option<Lookup> {
+        let context = context(&self.input.text, self.input.cursor);
+        if context != self.context {
+            self.selected = 0;
+        }
+        self.context = context;
+        self.completions.clear();
+        let context = self.context.as_ref()?;
+        match self.catalog.candidates(&context.completing) {
+            Ok(candidates) => {
+                self.completions = fuzzy::rank(&context.prefix, candidates, MAX_COMPLETIONS)
+                    .into_iter()
+                    .filter(|(candidate, _)| *candidate != context.prefix)
+                    .map(|(candidate, matched)| (candidate.to_string(), matched))
+                    .collect();
+                self.selected = self.selected.min(self.completions.len().saturating_sub(1));
+                None
+            }
+            Err(lookup) => self.catalog.pending.insert(lookup.clone()).then_some(lookup),
+        }
+    }
+
+    /// Replace the word at the cursor with the selected completion. Label
+    /// values are quoted and escaped, and a label picked in a selector gets
+    /// `=""` with the cursor between the quotes.
+    pub fn accept(&mut self) -> Option<Lookup> {
+        let (context, (choice, _)) = (self.context.clone()?, self.completions.get(self.selected)?.clone());
+        let (text, cursor_back) = match &context.completing {
+            Completing::Metric => (choice, 0),
+            // Unless the matcher's operator is already there
+            Completing::LabelName { in_selector: true, .. } if !self.input.text[self.input.cursor..].starts_with(['=', '!']) => {
+                (format!("{}=\"\"", choice), 1)
+            }
+            Completing::LabelName { .. } => (choice, 0),
+            Completing::LabelValue { regex, .. } => {
+                let value = if *regex { regex::escape(&choice) } else { choice };
+                let quoted = promql::quote(&value);
+                let escaped = &quoted[1..quoted.len() - 1];
+                if self.input.text[self.input.cursor..].starts_with('"') {
+                    // Step over the closing quote that is already there
+                    self.input.text.remove(self.input.cursor);
+                }
+                (format!("{}\"", escaped), 0)
+            }
+        };
+        self.input.text.replace_range(context.start..self.input.cursor, &text);
+        self.input.cursor = context.start + text.len() - cursor_back;
+        self.update_completions()
+    }
+}
+
+/// Carry out `request` for the open editor and store the answer in it.
+/// Returns a lookup the refreshed completions now wait for, if any.
+pub async fn serve(request: EditorRequest, client: &PrometheusClient, state: &Mutex<AppState>) -> Option<Lookup> {
+    match request {
+        EditorRequest::Run(query) => {
+            let result = client.query(&query).await;
+            let mut state = state.lock().await;
+            let editor = state.query_editor.as_mut()?;
+            editor.running = false;
+            editor.scroll_offset = 0;
+            editor.result = Some(match result {
+                Ok(response) => Ok(result_rows(&response)),
+                Err(e) => Err(format!("Prometheus {}: {}", e.class().as_str(), e)),
+            });
+            None
+        }
+        EditorRequest::Lookup(lookup) => {
+            let found = match &lookup {
+                Lookup::MetricNames => client.get_metric_names().await.map(Found::Names),
+                Lookup::LabelNames => client.get_label_names().await.map(Found::Names),
+                Lookup::LabelValues(label) => client.get_label_values(label).await.map(Found::Names),
+                Lookup::Series(metric) => client.get_series(metric).await.map(Found::Series),
+            };
+            let mut state = state.lock().await;
+            let editor = state.query_editor.as_mut()?;
+            editor.catalog.store(&lookup, found.map_err(|e| e.to_string()));
+            editor.update_completions()
+        }
+    }
+}
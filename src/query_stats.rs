@@ -0,0 +1,104 @@
+//! How long each PromQL and LogQL query takes, to find the dashboard
+//! queries that are expensive for the backends.
+//!
+//! End-to-end time runs from sending the request to having the decoded
+//! response, not counting time spent queued by the limiter. Server time is
+//! what the backend reports itself: Prometheus' `evalTotalTime` (asked for
+//! with `stats=all`) and Loki's `execTime`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Which backend ran a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    Prometheus,
+    Loki,
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Backend::Prometheus => "PromQL",
+            Backend::Loki => "LogQL",
+        }
+    }
+}
+
+/// Accumulated timings of one query text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryTiming {
+    pub backend: Backend,
+    pub query: String,
+    pub runs: u64,
+    pub last_ms: f64,                // End-to-end time of the latest run
+    pub max_ms: f64,                 // Slowest run end to end
+    pub total_ms: f64,               // End-to-end time of all runs
+    pub last_server_ms: Option<f64>, // None if the backend didn't report it
+    pub total_server_ms: f64,        // Server time of the runs that reported it
+}
+
+impl QueryTiming {
+    /// What the query has cost the backend so far: its server time where
+    /// reported, otherwise its end-to-end time.
+    pub fn cost_ms(&self) -> f64 {
+        if self.total_server_ms > 0.0 {
+            self.total_server_ms
+        } else {
+            self.total_ms
+        }
+    }
+
+    pub fn average_ms(&self) -> f64 {
+        self.total_ms / self.runs.max(1) as f64
+    }
+}
+
+/// Timings per query, shared by the clients that record them and the UI.
+#[derive(Debug, Default)]
+pub struct QueryStats {
+    timings: Mutex<HashMap<(Backend, String), QueryTiming>>,
+}
+
+impl QueryStats {
+    /// Record a completed run of `query`.
+    pub fn record(&self, backend: Backend, query: &str, elapsed: Duration, server_seconds: Option<f64>) {
+        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+        let server_ms = server_seconds.filter(|seconds| seconds.is_finite()).map(|seconds| seconds * 1000.0);
+        let mut timings = self.timings.lock().unwrap();
+        let timing = timings
+            .entry((backend, query.to_string()))
+            .or_insert_with(|| QueryTiming {
+                backend,
+                query: query.to_string(),
+                runs: 0,
+                last_ms: 0.0,
+                max_ms: 0.0,
+                total_ms: 0.0,
+                last_server_ms: None,
+                total_server_ms: 0.0,
+            });
+        timing.runs += 1;
+        timing.last_ms = elapsed_ms;
+        timing.max_ms = timing.max_ms.max(elapsed_ms);
+        timing.total_ms += elapsed_ms;
+        timing.last_server_ms = server_ms;
+        timing.total_server_ms += server_ms.unwrap_or(0.0);
+    }
+
+    /// Every query seen, most costly first.
+    pub fn by_cost(&self) -> Vec<QueryTiming> {
+        let mut timings: Vec<QueryTiming> = self.timings.lock().unwrap().values().cloned().collect();
+        timings.sort_by(|a, b| b.cost_ms().total_cmp(&a.cost_ms()).then_with(|| a.query.cmp(&b.query)));
+        timings
+    }
+
+    pub fn len(&self) -> usize {
+        self.timings.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,147 @@
+//! Markdown incident report (`W`): what the session saw, written to a file
+//! as a starting point for the postmortem. Covers the alerts timeline, the
+//! top error patterns, the worst endpoints then and now, the bookmarked log
+//! lines with the lines around them, and the incident notes.
+
+use crate::loki::LogEntry;
+use crate::notes;
+use crate::summary::top_error_messages;
+use crate::ui::{format_time, AppState};
+use chrono::{DateTime, Local};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Endpoints compared in the report.
+const WORST_ENDPOINTS: usize = 5;
+/// Lines shown before and after each bookmarked line.
+const EXCERPT_CONTEXT: usize = 2;
+
+/// When each alert fired or cleared, oldest first: synthetic checks failing
+/// and recovering, streams going silent, and external incidents.
+pub fn alert_timeline(state: &AppState) -> Vec<(DateTime<Local>, String)> {
+    let mut timeline = Vec::new();
+    for check in &state.synthetic_checks {
+        let mut failing = false;
+        for run in &check.runs {
+            if !run.passed() && !failing {
+                timeline.push((run.at, format!("Check {} failing: {}", check.config.name, run.failures.join(", "))));
+            } else if run.passed() && failing {
+                timeline.push((run.at, format!("Check {} recovered", check.config.name)));
+            }
+            failing = !run.passed();
+        }
+    }
+    for alarm in &state.silence_alarms {
+        if let Some(since) = alarm.silent_since {
+            timeline.push((since, format!("{} went silent ({} min without logs)", alarm.rule.label(), alarm.rule.minutes)));
+        }
+    }
+    for dependency in state.external_deps.iter().filter(|dependency| !dependency.incidents.is_empty()) {
+        if let Some(checked) = dependency.checked {
+            timeline.push((checked, format!("{} reports {}: {}", dependency.name, dependency.indicator.as_str(), dependency.incidents.join("; "))));
+        }
+    }
+    timeline.sort_by_key(|(at, _)| *at);
+    timeline
+}
+
+/// Latency (ms) and requests per minute of `uri` over the first half of the
+/// recorded history, to compare the current numbers with.
+fn before(state: &AppState, uri: &str) -> Option<(f64, f64)> {
+    let history = state.history.uri_history(uri);
+    let half = |points: &[(f64, f64)]| {
+        let first = &points[..points.len().div_ceil(2)];
+        (!first.is_empty()).then(|| first.iter().map(|(_, value)| value).sum::<f64>() / first.len() as f64)
+    };
+    Some((half(&history.latency_ms)?, half(&history.requests_per_min)?))
+}
+
+fn log_line(log: &LogEntry) -> String {
+    format!("{} {:<5} {} {}", log.timestamp, log.level, log.stream, log.message)
+}
+
+/// The report for the session so far.
+pub fn build(state: &AppState) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Incident report - {}\n", Local::now().format("%Y-%m-%d %H:%M"));
+    let _ = writeln!(out, "- **Prometheus:** {}", state.prometheus_url);
+    let _ = writeln!(out, "- **Loki:** {}", state.loki_url);
+    let _ = writeln!(out, "- **Time range:** {}", state.metrics_time_range.as_str());
+    if let Some(since) = state.history.since() {
+        let _ = writeln!(out, "- **Metric history since:** {}", format_time(since, "%Y-%m-%d %H:%M:%S"));
+    }
+
+    let _ = writeln!(out, "\n## Alerts\n");
+    let timeline = alert_timeline(state);
+    if timeline.is_empty() {
+        let _ = writeln!(out, "_No alerts fired._");
+    }
+    for (at, event) in timeline {
+        let _ = writeln!(out, "- **{}** {}", at.format("%Y-%m-%d %H:%M:%S"), event);
+    }
+
+    let _ = writeln!(out, "\n## Top error patterns\n");
+    let errors = top_error_messages(state);
+    if errors.is_empty() {
+        let _ = writeln!(out, "_No errors in the loaded logs._");
+    }
+    for (count, message) in errors {
+        let _ = writeln!(out, "- {}x\n  ```text\n  {}\n  ```", count, message);
+    }
+
+    let _ = writeln!(out, "\n## Worst endpoints\n");
+    match &state.metrics {
+        Some(metrics) if !metrics.uri_metrics.is_empty() => {
+            let mut worst: Vec<_> = metrics.uri_metrics.iter().collect();
+            worst.sort_by(|a, b| b.avg_duration_ms.total_cmp(&a.avg_duration_ms));
+            let _ = writeln!(out, "| URI | Latency before | Latency now | Change | Req/min before | Req/min now |");
+            let _ = writeln!(out, "|---|---:|---:|---:|---:|---:|");
+            for metric in worst.into_iter().take(WORST_ENDPOINTS) {
+                let (latency, rate, change) = match before(state, &metric.uri) {
+                    Some((latency, rate)) => {
+                        let change = if latency > 0.0 { format!("{:+.0}%", (metric.avg_duration_ms / latency - 1.0) * 100.0) } else { "-".to_string() };
+                        (format!("{:.1} ms", latency), format!("{:.0}", rate), change)
+                    }
+                    None => ("-".to_string(), "-".to_string(), "-".to_string()),
+                };
+                let _ = writeln!(
+                    out,
+                    "| `{}` | {} | {:.1} ms | {} | {} | {:.0} |",
+                    metric.uri, latency, metric.avg_duration_ms, change, rate, metric.request_count
+                );
+            }
+            let _ = writeln!(out, "\n_Before: the first half of the metric history; now: the last refresh._");
+        }
+        _ => {
+            let _ = writeln!(out, "_No metrics._");
+        }
+    }
+
+    let _ = writeln!(out, "\n## Bookmarked log lines\n");
+    let bookmarked: Vec<usize> = state.all_logs.iter().enumerate().filter(|(_, log)| state.bookmarks.contains(&log.nanos)).map(|(idx, _)| idx).collect();
+    if bookmarked.is_empty() {
+        let _ = writeln!(out, "_No bookmarks._");
+    }
+    for idx in bookmarked {
+        let log = &state.all_logs[idx];
+        let _ = writeln!(out, "- {} `{}`\n  ```text", log.timestamp, log.stream);
+        let lines = idx.saturating_sub(EXCERPT_CONTEXT)..(idx + EXCERPT_CONTEXT + 1).min(state.all_logs.len());
+        for (line, excerpt) in state.all_logs[lines.clone()].iter().enumerate() {
+            let marker = if lines.start + line == idx { ">" } else { " " };
+            let _ = writeln!(out, "  {} {}", marker, log_line(excerpt));
+        }
+        let _ = writeln!(out, "  ```");
+    }
+
+    let _ = write!(out, "\n{}", notes::to_markdown(&state.notes));
+    out
+}
+
+/// Write the report to `incident-report-<date>-<time>.md` in `dir`.
+pub fn export(state: &AppState, dir: &Path) -> io::Result<PathBuf> {
+    let path = dir.join(format!("incident-report-{}.md", Local::now().format("%Y%m%d-%H%M%S")));
+    fs::write(&path, build(state))?;
+    Ok(path)
+}
@@ -0,0 +1,94 @@
+//! Bounds on what a long-running session keeps in memory: the log buffer
+//! and the client-side metric history, each capped by entry count and
+//! optionally by approximate size. The oldest data goes first.
+//!
+//! Sizes are estimates of the heap held by the entries (struct sizes plus
+//! string and map capacities), not allocator-exact RSS.
+
+use crate::loki::LogEntry;
+use serde::{Deserialize, Serialize};
+use std::mem::size_of;
+
+/// Log lines kept across refreshes by default.
+pub const DEFAULT_LOG_LINES: usize = 5000;
+
+/// History snapshots kept by default before the older half is thinned out.
+pub const DEFAULT_HISTORY_SNAPSHOTS: usize = 2000;
+
+/// The `[retention]` config section.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub log_lines: usize,               // Lines kept in the log buffer
+    pub log_memory_mb: Option<f64>,     // Also drop the oldest lines beyond this size
+    pub history_snapshots: usize,       // Snapshots kept for the "All" range
+    pub history_memory_mb: Option<f64>, // Also thin the history beyond this size
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            log_lines: DEFAULT_LOG_LINES,
+            log_memory_mb: None,
+            history_snapshots: DEFAULT_HISTORY_SNAPSHOTS,
+            history_memory_mb: None,
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn log_limit(&self) -> Limit {
+        Limit::new(self.log_lines, self.log_memory_mb)
+    }
+
+    pub fn history_limit(&self) -> Limit {
+        Limit::new(self.history_snapshots, self.history_memory_mb)
+    }
+}
+
+/// How much of one kind of data to keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    pub entries: usize,
+    pub bytes: Option<usize>,
+}
+
+impl Limit {
+    fn new(entries: usize, memory_mb: Option<f64>) -> Self {
+        Self {
+            entries: entries.max(1),
+            bytes: memory_mb.map(|mb| (mb.max(0.0) * 1024.0 * 1024.0) as usize),
+        }
+    }
+
+    /// Whether `entries` entries taking `bytes` are over the limit.
+    pub fn exceeded(&self, entries: usize, bytes: usize) -> bool {
+        entries > self.entries || self.bytes.is_some_and(|max| bytes > max)
+    }
+}
+
+/// Approximate heap held by one buffered log line.
+pub fn log_bytes(log: &LogEntry) -> usize {
+    size_of::<LogEntry>() + log.timestamp.capacity() + log.message.capacity() + log.level.capacity() + log.stream.capacity()
+}
+
+/// Approximate heap held by a log buffer.
+pub fn logs_bytes(logs: &[LogEntry]) -> usize {
+    logs.iter().map(log_bytes).sum()
+}
+
+/// How many of the oldest `logs` (oldest first) to drop to get within
+/// `limit`. The newest line is always kept.
+pub fn logs_over_limit(logs: &[LogEntry], limit: Limit) -> usize {
+    let mut bytes = 0;
+    let kept = logs
+        .iter()
+        .rev()
+        .enumerate()
+        .take_while(|(kept, log)| {
+            bytes += log_bytes(log);
+            *kept == 0 || !limit.exceeded(kept + 1, bytes)
+        })
+        .count();
+    logs.len() - kept
+}
@@ -0,0 +1,118 @@
+//! Saved views (`V`): the whole view (log filter or query, time range,
+//! ranking, focused panel, selected URI and log line, variable selections)
+//! kept under a name and brought back later. They are written to the state
+//! file, so they outlive the session.
+
+use crate::fetch::RefreshScope;
+use crate::log_actions::LogFilter;
+use crate::persist::LogAnchor;
+use crate::prometheus::Ranking;
+use crate::text::LineInput;
+use crate::ui::{ActivePanel, AppState, TimeRange};
+use crate::undo::ViewState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A named snapshot of the view.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SavedView {
+    pub name: String,
+    pub active_panel: ActivePanel,
+    pub time_range: TimeRange,
+    pub ranking: Ranking,
+    #[serde(default)]
+    pub log_filter: LogFilter,
+    #[serde(default)]
+    pub selected_uri: Option<String>,
+    #[serde(default)]
+    pub selected_line: Option<LogAnchor>, // Selected log line, as the state file keeps it
+    #[serde(default)]
+    pub variables: BTreeMap<String, Option<String>>, // Selection per variable name; None is All
+}
+
+impl SavedView {
+    /// What `state` shows now, as `name`.
+    pub fn capture(name: &str, state: &AppState) -> Self {
+        let selected_uri = state
+            .selected_metric_index
+            .and_then(|idx| state.metrics.as_ref()?.uri_metrics.get(idx))
+            .map(|metric| metric.uri.clone());
+        Self {
+            name: name.to_string(),
+            active_panel: state.active_panel,
+            time_range: state.metrics_time_range,
+            ranking: state.metrics_ranking,
+            log_filter: state.log_filter.clone(),
+            selected_uri,
+            selected_line: state.selected_log_index.and_then(|idx| state.all_logs.get(idx)).map(LogAnchor::of),
+            variables: state.variables.iter().map(|variable| (variable.config.name.clone(), variable.selected.clone())).collect(),
+        }
+    }
+
+    /// Show this view in `state` as one undoable change. The selected URI
+    /// and log line are picked once they show. Returns the refresh needed,
+    /// None when the view already showed this.
+    pub fn apply(&self, state: &mut AppState) -> Option<RefreshScope> {
+        let view = ViewState { log_filter: self.log_filter.clone(), time_range: self.time_range, ranking: self.ranking };
+        let scope = state.show_view(view);
+        let logs = scope.is_some_and(RefreshScope::includes_logs);
+        let mut metrics = scope.is_some_and(RefreshScope::includes_metrics);
+        for variable in &mut state.variables {
+            if let Some(selected) = self.variables.get(&variable.config.name).filter(|selected| **selected != variable.selected) {
+                variable.selected = selected.clone();
+                metrics = true; // The panels run with the selections
+            }
+        }
+        if state.panel_available(self.active_panel) {
+            state.active_panel = self.active_panel;
+        }
+        state.restore_selected_uri = self.selected_uri.clone();
+        state.resolve_selected_uri();
+        state.restore_selected_anchor = self.selected_line.clone();
+        // With the same filter the buffered lines stay, so the line can be found now
+        if !logs && !state.all_logs.is_empty() {
+            state.resolve_restore_anchors();
+        }
+        match (metrics, logs) {
+            (true, true) => Some(RefreshScope::All),
+            (true, false) => Some(RefreshScope::Metrics),
+            (false, true) => Some(RefreshScope::Logs),
+            (false, false) => None,
+        }
+    }
+
+    /// What the view shows, for the list.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.time_range.as_str().to_string(), format!("by {}", self.ranking.as_str())];
+        if let Some(query) = self.log_filter.logql().or_else(|| self.log_filter.pattern.clone()) {
+            parts.push(query);
+        }
+        if let Some(uri) = &self.selected_uri {
+            parts.push(uri.clone());
+        }
+        parts.extend(self.variables.iter().map(|(name, selected)| format!("{}={}", name, selected.as_deref().unwrap_or("All"))));
+        parts.join(" · ")
+    }
+}
+
+/// Add `view` to `views`, replacing a view of the same name. Returns where
+/// it went.
+pub fn save(views: &mut Vec<SavedView>, view: SavedView) -> usize {
+    match views.iter().position(|saved| saved.name == view.name) {
+        Some(idx) => {
+            views[idx] = view;
+            idx
+        }
+        None => {
+            views.push(view);
+            views.len() - 1
+        }
+    }
+}
+
+/// The saved views popup while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct SavedViewsPopup {
+    pub selected: usize,
+    pub naming: Option<LineInput>, // Name being typed to save the current view under
+}
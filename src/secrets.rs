@@ -0,0 +1,115 @@
+//! Credentials referenced from the config instead of written into it: a
+//! secret names where its value lives (an environment variable, a `KEY=value`
+//! file, a command such as `pass show ...`, or the OS keyring) and is read
+//! once at startup.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Keyring service the `keyring` source looks entries up under.
+pub const KEYRING_SERVICE: &str = "rustdash";
+
+/// Where a secret's value comes from; exactly one source must be set, e.g.
+/// `{ env = "LOKI_TOKEN" }` or `{ command = "pass show monitoring/loki" }`.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Secret {
+    pub env: Option<String>,      // Environment variable holding the value
+    pub env_file: Option<String>, // File of KEY=value lines to read `key` from
+    pub key: Option<String>,
+    pub command: Option<String>,  // Shell command printing the value on its first line
+    pub keyring: Option<String>,  // Account under the "rustdash" service in the OS keyring
+}
+
+impl Secret {
+    /// Read the value from the configured source.
+    pub fn resolve(&self) -> Result<String> {
+        let sources = [self.env.is_some(), self.env_file.is_some(), self.command.is_some(), self.keyring.is_some()];
+        match sources.iter().filter(|set| **set).count() {
+            0 => anyhow::bail!("Secret has no source; set one of env, env_file, command or keyring"),
+            1 => {}
+            _ => anyhow::bail!("Secret sets more than one of env, env_file, command and keyring"),
+        }
+        let value = if let Some(name) = &self.env {
+            std::env::var(name).with_context(|| format!("Environment variable {} is not set", name))?
+        } else if let Some(path) = &self.env_file {
+            let key = self.key.as_deref().context("env_file needs the key to read")?;
+            read_env_file(path, key)?
+        } else if let Some(command) = &self.command {
+            first_line(run(Command::new("sh").arg("-c").arg(command), command)?)
+        } else {
+            let account = self.keyring.as_deref().unwrap_or_default();
+            first_line(run(&mut keyring_lookup(account)?, "keyring lookup")?)
+        };
+        if value.is_empty() {
+            anyhow::bail!("Secret resolved to an empty value");
+        }
+        Ok(value)
+    }
+}
+
+/// The value of `key` in a file of `KEY=value` lines. Blank lines, `#`
+/// comments and an `export ` prefix are skipped, and quotes around the
+/// value removed, so shell env files work as they are.
+pub fn read_env_file(path: &str, key: &str) -> Result<String> {
+    let path = expand_home(path);
+    let contents = std::fs::read_to_string(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("export ").unwrap_or(line).split_once('='))
+        .find(|(name, _)| name.trim() == key)
+        .map(|(_, value)| unquote(value.trim()).to_string())
+        .with_context(|| format!("No {} in {}", key, path.display()))
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
+/// `~/...` relative to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Standard output of `command`, which must exit successfully. `what` names
+/// it in errors; the output itself never appears in them.
+fn run(command: &mut Command, what: &str) -> Result<String> {
+    let output = command.output().with_context(|| format!("Cannot run {}", what))?;
+    if !output.status.success() {
+        anyhow::bail!("{} exited with {}", what, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `pass` and friends print the secret on the first line, metadata after it.
+fn first_line(output: String) -> String {
+    output.lines().next().unwrap_or_default().trim_end().to_string()
+}
+
+/// The platform's keyring CLI asked for `account`: `security` on macOS,
+/// libsecret's `secret-tool` elsewhere.
+fn keyring_lookup(account: &str) -> Result<Command> {
+    let mut command;
+    if cfg!(target_os = "macos") {
+        command = Command::new("security");
+        command.args(["find-generic-password", "-s", KEYRING_SERVICE, "-a", account, "-w"]);
+    } else if cfg!(unix) {
+        command = Command::new("secret-tool");
+        command.args(["lookup", "service", KEYRING_SERVICE, "account", account]);
+    } else {
+        anyhow::bail!("No keyring support on this platform; use a command source instead");
+    }
+    Ok(command)
+}
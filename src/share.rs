@@ -0,0 +1,104 @@
+//! Read-only session sharing: with `--share ADDR` every rendered frame is
+//! served over TCP, so a colleague can follow an investigation live with
+//! `rustdash attach ADDR` (plain `nc` works too). Nothing viewers send is
+//! read. A bare port binds to localhost; to share across machines, attach
+//! through an SSH tunnel (`ssh -L 7000:localhost:7000 host`).
+
+use anyhow::{Context, Result};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::buffer::Buffer;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+/// `7000` is short for `127.0.0.1:7000`.
+pub fn address(address: &str) -> String {
+    if address.parse::<u16>().is_ok() {
+        format!("127.0.0.1:{}", address)
+    } else {
+        address.to_string()
+    }
+}
+
+/// A frame as terminal output that redraws the whole screen, so a viewer
+/// joining late sees it all. Wrapped in a synchronized update so redraws
+/// don't flicker where the terminal supports it.
+pub fn encode(buffer: &Buffer) -> Vec<u8> {
+    let mut output = b"\x1b[?2026h\x1b[?25l\x1b[0m\x1b[2J".to_vec();
+    // Against a blank buffer the diff is every non-blank cell, wide characters handled
+    let _ = CrosstermBackend::new(&mut output).draw(Buffer::empty(buffer.area).diff(buffer).into_iter());
+    output.extend_from_slice(b"\x1b[0m\x1b[?2026l");
+    output
+}
+
+/// Pause after a failed accept, e.g. while out of file descriptors, so the
+/// loop doesn't spin before retrying.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Serves the latest frame to every attached viewer.
+#[derive(Debug)]
+pub struct ShareServer {
+    pub address: SocketAddr,
+    frames: watch::Sender<Arc<Vec<u8>>>,
+    viewers: Arc<AtomicUsize>,
+    accept_error: Arc<Mutex<Option<String>>>, // Why the last accept failed; cleared by the next viewer
+}
+
+impl ShareServer {
+    pub async fn start(address: &str) -> Result<Self> {
+        let listener = TcpListener::bind(self::address(address))
+            .await
+            .with_context(|| format!("Couldn't share the session on {}", address))?;
+        let address = listener.local_addr()?;
+        let (frames, _) = watch::channel(Arc::new(Vec::new()));
+        let viewers = Arc::new(AtomicUsize::new(0));
+        let accept_error = Arc::new(Mutex::new(None));
+        let (accept_frames, accept_viewers, failed) = (frames.clone(), viewers.clone(), accept_error.clone());
+        tokio::spawn(async move {
+            loop {
+                let mut stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    // Usually passing; keep sharing once it does
+                    Err(e) => {
+                        *failed.lock().unwrap() = Some(e.to_string());
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    }
+                };
+                *failed.lock().unwrap() = None;
+                let mut frames = accept_frames.subscribe();
+                let viewers = accept_viewers.clone();
+                viewers.fetch_add(1, Ordering::Relaxed);
+                tokio::spawn(async move {
+                    // A slow viewer skips to the latest frame rather than queueing them
+                    loop {
+                        let frame = frames.borrow_and_update().clone();
+                        if stream.write_all(&frame).await.is_err() || frames.changed().await.is_err() {
+                            break;
+                        }
+                    }
+                    viewers.fetch_sub(1, Ordering::Relaxed);
+                });
+            }
+        });
+        Ok(Self { address, frames, viewers, accept_error })
+    }
+
+    /// Send a rendered frame to the viewers, and keep it for the next one to attach.
+    pub fn publish(&self, buffer: &Buffer) {
+        self.frames.send_replace(Arc::new(encode(buffer)));
+    }
+
+    pub fn viewers(&self) -> usize {
+        self.viewers.load(Ordering::Relaxed)
+    }
+
+    /// Why new viewers can't attach right now, if they can't.
+    pub fn accept_error(&self) -> Option<String> {
+        self.accept_error.lock().unwrap().clone()
+    }
+}
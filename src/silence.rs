@@ -0,0 +1,57 @@
+//! Dead man's switch for log streams. A chatty service that stops logging has
+//! often fallen over before anything gets to log an error, so configured
+//! streams raise a banner (and ring the bell) after a stretch of silence.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// One `[[silence_alarms]]` rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SilenceRule {
+    pub name: Option<String>, // Shown in the banner instead of the selector
+    pub selector: String,     // LogQL stream selector, e.g. `{service_name="checkout"}`
+    pub minutes: u64,         // Alarm once the stream has been silent this long
+}
+
+impl SilenceRule {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.selector)
+    }
+}
+
+/// A rule and what its last check found.
+#[derive(Debug, Clone)]
+pub struct SilenceAlarm {
+    pub rule: SilenceRule,
+    pub silent_since: Option<DateTime<Local>>, // When the alarm went off; None while lines arrive
+    pub error: Option<String>,                 // Last check failed; the previous verdict stands
+}
+
+impl SilenceAlarm {
+    pub fn new(rule: SilenceRule) -> Self {
+        Self {
+            rule,
+            silent_since: None,
+            error: None,
+        }
+    }
+
+    pub fn is_firing(&self) -> bool {
+        self.silent_since.is_some()
+    }
+
+    /// Record a check at `now` that found lines in the window (or not).
+    /// Returns true when this check set the alarm off.
+    pub fn update(&mut self, has_logs: bool, now: DateTime<Local>) -> bool {
+        self.error = None;
+        if has_logs {
+            self.silent_since = None;
+            false
+        } else if self.silent_since.is_none() {
+            self.silent_since = Some(now);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Multi-window, multi-burn-rate alerting for the SLOs in `[[slos]]`, as
+//! described in Google's SRE workbook: each SLO's error ratio is measured
+//! over 5m, 30m, 1h, 2h, 6h, 1d and 3d and divided by its error budget, and
+//! an alert fires when both windows of a pair burn faster than the pair's
+//! threshold.
+//! The long window keeps a short blip from alerting, the short one lets the
+//! alert clear soon after the burning stops. `B` lists the SLOs with their
+//! burn rates, and firing ones join the alert banner.
+
+use crate::panels::Severity;
+use crate::prometheus::{PrometheusClient, PrometheusError};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// One `[[slos]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct SloConfig {
+    pub name: String,
+    pub objective: f64, // Share of good events promised, in percent, e.g. 99.9
+    pub errors: String, // Counter of bad events, e.g. `http_requests_total{status=~"5.."}`
+    pub total: String,  // Counter of all events, e.g. `http_requests_total`
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            objective: 99.9,
+            errors: String::new(),
+            total: String::new(),
+        }
+    }
+}
+
+impl SloConfig {
+    /// The share of events allowed to be bad, from 0 to 1.
+    pub fn error_budget(&self) -> f64 {
+        1.0 - self.objective / 100.0
+    }
+
+    /// PromQL for the share of bad events over `window`.
+    pub fn error_ratio_query(&self, window: &str) -> String {
+        format!("sum(rate({}[{}])) / sum(rate({}[{}]))", self.errors, window, self.total, window)
+    }
+}
+
+/// The windows burn rates are measured over, shortest first.
+pub const WINDOWS: [&str; 7] = ["5m", "30m", "1h", "2h", "6h", "1d", "3d"];
+
+/// A pair of windows that alerts when both burn faster than `threshold`.
+#[derive(Debug, PartialEq)]
+pub struct BurnAlert {
+    pub long: &'static str,
+    pub short: &'static str,
+    pub threshold: f64, // Times the rate that would spend the budget exactly over the SLO period
+    pub severity: Severity,
+    pub action: &'static str, // What it calls for: "page" or "ticket"
+}
+
+/// The workbook's alerts for a 30-day SLO period, most severe first.
+pub const BURN_ALERTS: [BurnAlert; 4] = [
+    // 2% of the budget gone within an hour
+    BurnAlert { long: "1h", short: "5m", threshold: 14.4, severity: Severity::Critical, action: "page" },
+    // 5% of the budget gone within six hours
+    BurnAlert { long: "6h", short: "30m", threshold: 6.0, severity: Severity::Critical, action: "page" },
+    // 10% of the budget gone within a day
+    BurnAlert { long: "1d", short: "2h", threshold: 3.0, severity: Severity::Warn, action: "ticket" },
+    // 10% of the budget gone within three days
+    BurnAlert { long: "3d", short: "6h", threshold: 1.0, severity: Severity::Warn, action: "ticket" },
+];
+
+/// Burn rate per window of [`WINDOWS`]; None where there were no events.
+pub type BurnRates = [Option<f64>; 7];
+
+/// An SLO and its latest burn rates.
+#[derive(Debug, Clone)]
+pub struct Slo {
+    pub config: SloConfig,
+    pub burn_rates: BurnRates,
+    pub burning_since: Option<DateTime<Local>>, // When its alert fired; None while within budget
+    pub error: Option<String>,                  // Last evaluation failed; the previous rates stand
+}
+
+impl Slo {
+    pub fn new(config: SloConfig) -> Result<Self> {
+        if !(config.objective > 0.0 && config.objective < 100.0) {
+            bail!("SLO '{}' needs an objective between 0 and 100 (percent), not {}", config.name, config.objective);
+        }
+        if config.errors.is_empty() || config.total.is_empty() {
+            bail!("SLO '{}' needs both an `errors` and a `total` counter", config.name);
+        }
+        Ok(Self { config, burn_rates: [None; 7], burning_since: None, error: None })
+    }
+
+    pub fn burn_rate(&self, window: &str) -> Option<f64> {
+        let idx = WINDOWS.iter().position(|w| *w == window)?;
+        self.burn_rates[idx]
+    }
+
+    /// The most severe alert whose windows both burn faster than its threshold.
+    pub fn firing(&self) -> Option<&'static BurnAlert> {
+        BURN_ALERTS.iter().find(|alert| {
+            let over = |window| self.burn_rate(window).is_some_and(|rate| rate > alert.threshold);
+            over(alert.long) && over(alert.short)
+        })
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.firing().map_or(Severity::Ok, |alert| alert.severity)
+    }
+
+    /// "burning 19.1× over 1h, 40.2× over 5m (page)" while an alert fires.
+    pub fn describe_firing(&self) -> Option<String> {
+        let alert = self.firing()?;
+        let rate = |window| self.burn_rate(window).unwrap_or_default();
+        Some(format!(
+            "burning {:.1}× over {}, {:.1}× over {} ({})",
+            rate(alert.long),
+            alert.long,
+            rate(alert.short),
+            alert.short,
+            alert.action
+        ))
+    }
+
+    /// Record an evaluation at `now`. Returns true when it set an alert off
+    /// or raised it from ticket to page.
+    pub fn update(&mut self, burn_rates: BurnRates, now: DateTime<Local>) -> bool {
+        let pages = |alert: &BurnAlert| alert.action == "page";
+        let before = self.firing();
+        self.burn_rates = burn_rates;
+        self.error = None;
+        let after = self.firing();
+        match after {
+            None => self.burning_since = None,
+            Some(_) if self.burning_since.is_none() => self.burning_since = Some(now),
+            Some(_) => {}
+        }
+        after.is_some_and(|after| before.is_none_or(|before| pages(after) && !pages(before)))
+    }
+}
+
+/// The SLO's burn rate over each of [`WINDOWS`].
+pub async fn get_burn_rates(client: &PrometheusClient, config: &SloConfig) -> Result<BurnRates, PrometheusError> {
+    let queries = WINDOWS.map(|window| config.error_ratio_query(window));
+    let responses = tokio::join!(
+        client.query(&queries[0]),
+        client.query(&queries[1]),
+        client.query(&queries[2]),
+        client.query(&queries[3]),
+        client.query(&queries[4]),
+        client.query(&queries[5]),
+        client.query(&queries[6]),
+    );
+    let mut rates = [None; 7];
+    let responses = [responses.0, responses.1, responses.2, responses.3, responses.4, responses.5, responses.6];
+    for (rate, response) in rates.iter_mut().zip(responses) {
+        // No events in the window make 0/0, which has no rate
+        *rate = response?
+            .data
+            .result
+            .first()
+            .and_then(|result| result.value.as_ref()?.1.parse::<f64>().ok())
+            .filter(|ratio| ratio.is_finite())
+            .map(|ratio| ratio / config.error_budget());
+    }
+    Ok(rates)
+}
@@ -0,0 +1,158 @@
+//! External dependency status: the public Atlassian Statuspage pages of
+//! `[[status_pages]]` (AWS, Stripe, GitHub, ...) are polled and summarized in
+//! the endpoints bar, because a latency spike is sometimes their outage.
+
+use chrono::{DateTime, Local};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// Seconds between polls; public pages change slowly and rate-limit.
+pub const POLL_SECONDS: u64 = 60;
+
+/// One `[[status_pages]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct StatusPageConfig {
+    pub name: String, // Shown in the endpoints bar
+    pub url: String,  // Page URL, e.g. https://www.githubstatus.com, or its summary.json
+}
+
+impl StatusPageConfig {
+    /// The page's Statuspage v2 summary endpoint.
+    pub fn summary_url(&self) -> String {
+        if self.url.ends_with(".json") {
+            self.url.clone()
+        } else {
+            format!("{}/api/v2/summary.json", self.url.trim_end_matches('/'))
+        }
+    }
+}
+
+/// Overall state of a page, from its `status.indicator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indicator {
+    Operational,
+    Minor,
+    Major,
+    Critical,
+    Maintenance,
+    Unknown, // Not checked yet, or the page couldn't be read
+}
+
+impl Indicator {
+    fn parse(indicator: &str) -> Self {
+        match indicator {
+            "none" => Self::Operational,
+            "minor" => Self::Minor,
+            "major" => Self::Major,
+            "critical" => Self::Critical,
+            "maintenance" => Self::Maintenance,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Operational => "ok",
+            Self::Minor => "minor",
+            Self::Major => "major",
+            Self::Critical => "critical",
+            Self::Maintenance => "maintenance",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Latest known status of one dependency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyStatus {
+    pub name: String,
+    pub indicator: Indicator,
+    pub description: String,      // The page's own summary, or why it couldn't be read
+    pub incidents: Vec<String>,   // Unresolved incidents
+    pub degraded: Vec<String>,    // Components that aren't operational
+    pub checked: Option<DateTime<Local>>,
+}
+
+impl DependencyStatus {
+    /// Not checked yet.
+    pub fn pending(page: &StatusPageConfig) -> Self {
+        Self {
+            name: page.name.clone(),
+            indicator: Indicator::Unknown,
+            description: "Not checked yet".to_string(),
+            incidents: Vec::new(),
+            degraded: Vec::new(),
+            checked: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    status: Status,
+    #[serde(default)]
+    incidents: Vec<Incident>,
+    #[serde(default)]
+    components: Vec<Component>,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    indicator: String,
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct Incident {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Component {
+    name: String,
+    status: String,
+    #[serde(default)]
+    group: bool, // Groups repeat their components' status
+}
+
+/// Read a summary.json body.
+pub fn parse_summary(name: &str, body: &str) -> Result<DependencyStatus, serde_json::Error> {
+    let summary: Summary = serde_json::from_str(body)?;
+    Ok(DependencyStatus {
+        name: name.to_string(),
+        indicator: Indicator::parse(&summary.status.indicator),
+        description: summary.status.description,
+        incidents: summary.incidents.into_iter().map(|incident| incident.name).collect(),
+        degraded: summary
+            .components
+            .into_iter()
+            .filter(|component| !component.group && component.status != "operational")
+            .map(|component| format!("{} ({})", component.name, component.status.replace('_', " ")))
+            .collect(),
+        checked: Some(Local::now()),
+    })
+}
+
+/// Poll one page; a page that can't be read is reported as unknown.
+pub async fn check(client: &Client, page: &StatusPageConfig) -> DependencyStatus {
+    let body = match client.get(page.summary_url()).send().await {
+        Ok(response) if response.status().is_success() => response.text().await.map_err(|e| e.to_string()),
+        Ok(response) => Err(format!("HTTP {}", response.status())),
+        Err(e) => Err(e.to_string()),
+    };
+    body.and_then(|body| parse_summary(&page.name, &body).map_err(|e| format!("unexpected response: {}", e)))
+        .unwrap_or_else(|error| DependencyStatus {
+            description: format!("Couldn't read the status page: {}", error),
+            checked: Some(Local::now()),
+            ..DependencyStatus::pending(page)
+        })
+}
+
+/// Poll every page, in the configured order.
+pub async fn check_all(client: &Client, pages: &[StatusPageConfig]) -> Vec<DependencyStatus> {
+    let mut statuses = Vec::with_capacity(pages.len());
+    for page in pages {
+        statuses.push(check(client, page).await);
+    }
+    statuses
+}
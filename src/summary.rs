@@ -0,0 +1,88 @@
+use crate::ui::AppState;
+use chrono::Local;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const TOP_N: usize = 5;
+
+/// Build the plain-text summary printed on exit.
+pub fn build_summary(state: &AppState) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "RustDash summary - {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    let _ = writeln!(out, "Time range: {}, last fetch: {}", state.metrics_time_range.as_str(), state.last_fetch);
+
+    let _ = writeln!(out, "\nEndpoint health");
+    let prometheus_health = match &state.metrics {
+        _ if !state.prometheus_enabled => "DISABLED".to_string(),
+        Some(metrics) if !metrics.errors.is_empty() => format!("ERROR ({})", metrics.errors[0]),
+        Some(_) => "OK".to_string(),
+        None => "NO DATA".to_string(),
+    };
+    let loki_health = match state.all_logs.is_empty() {
+        _ if !state.loki_enabled => "DISABLED",
+        true => "NO DATA",
+        false => "OK",
+    };
+    let _ = writeln!(out, "  Prometheus {}: {}", state.prometheus_url, prometheus_health);
+    let _ = writeln!(out, "  Loki {}: {}", state.loki_url, loki_health);
+
+    // The banner's alerts: silent log streams, failing checks, burning SLOs
+    let _ = writeln!(out, "\nFiring alerts");
+    let alerts = state.firing_alerts();
+    if alerts.is_empty() {
+        let _ = writeln!(out, "  (none)");
+    }
+    for (alert, since, reason) in alerts {
+        let _ = writeln!(out, "  {}  since {}  {}", alert, since.format("%H:%M:%S"), reason);
+    }
+
+    let _ = writeln!(out, "\nWorst latencies");
+    match &state.metrics {
+        Some(metrics) if !metrics.uri_metrics.is_empty() => {
+            let mut worst: Vec<_> = metrics.uri_metrics.iter().collect();
+            worst.sort_by(|a, b| b.avg_duration_ms.total_cmp(&a.avg_duration_ms));
+            for uri_metric in worst.iter().take(TOP_N) {
+                let _ = writeln!(
+                    out,
+                    "  {:>8.1}ms  {:>8.0} req/min  {}",
+                    uri_metric.avg_duration_ms, uri_metric.request_count, uri_metric.uri
+                );
+            }
+        }
+        _ => {
+            let _ = writeln!(out, "  (no metrics)");
+        }
+    }
+
+    let _ = writeln!(out, "\nTop error messages");
+    let errors = top_error_messages(state);
+    if errors.is_empty() {
+        let _ = writeln!(out, "  (no errors in loaded logs)");
+    }
+    for (count, message) in errors {
+        let _ = writeln!(out, "  {:>5}x  {}", count, message);
+    }
+
+    out
+}
+
+/// Group ERROR logs by message with digits masked, so messages that differ
+/// only in ids or timestamps count together. Most frequent first.
+pub fn top_error_messages(state: &AppState) -> Vec<(usize, String)> {
+    let mut groups: HashMap<String, (usize, String)> = HashMap::new();
+    for log in state.all_logs.iter().filter(|log| log.level == "ERROR") {
+        let key: String = log
+            .message
+            .chars()
+            .map(|c| if c.is_ascii_digit() { '#' } else { c })
+            .collect();
+        let entry = groups.entry(key).or_insert_with(|| (0, log.message.clone()));
+        entry.0 += 1;
+    }
+
+    let mut top: Vec<_> = groups.into_values().collect();
+    top.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    top.truncate(TOP_N);
+    top
+}
@@ -0,0 +1,149 @@
+//! Synthetic HTTP checks: requests from `[[synthetic_checks]]` run on an
+//! interval and are asserted on (status, latency budget, body regex), for
+//! lightweight uptime checks without deploying blackbox_exporter. A failing
+//! check joins the alert banner; `C` charts each check's recent runs.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Runs kept per check, for the chart and the uptime.
+pub const HISTORY: usize = 120;
+
+/// One `[[synthetic_checks]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct CheckConfig {
+    pub name: String,
+    pub url: String,
+    pub method: String,              // GET, HEAD, POST, ...
+    pub body: Option<String>,        // Request body, e.g. for a POST
+    pub interval_seconds: u64,
+    pub timeout_seconds: u64,
+    pub expect_status: Option<u16>,  // Any 2xx when unset
+    pub max_latency_ms: Option<u64>, // Latency budget
+    pub body_regex: Option<String>,  // Searched for in the response body
+}
+
+impl Default for CheckConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: String::new(),
+            method: "GET".to_string(),
+            body: None,
+            interval_seconds: 30,
+            timeout_seconds: 10,
+            expect_status: None,
+            max_latency_ms: None,
+            body_regex: None,
+        }
+    }
+}
+
+/// The outcome of one run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckRun {
+    pub at: DateTime<Local>,
+    pub latency_ms: f64,
+    pub status: Option<u16>,    // None when no response came back
+    pub failures: Vec<String>,  // Assertions that didn't hold; empty when the run passed
+}
+
+impl CheckRun {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A check and its recent runs.
+#[derive(Debug, Clone)]
+pub struct SyntheticCheck {
+    pub config: CheckConfig,
+    method: Method,
+    body_regex: Option<Regex>,
+    pub runs: VecDeque<CheckRun>,                 // Oldest first, at most HISTORY
+    pub failing_since: Option<DateTime<Local>>,   // First failed run of the current failure streak
+}
+
+impl SyntheticCheck {
+    pub fn new(config: CheckConfig) -> Result<Self> {
+        let method = Method::from_bytes(config.method.to_uppercase().as_bytes())
+            .with_context(|| format!("Invalid method '{}' in synthetic check '{}'", config.method, config.name))?;
+        let body_regex = match &config.body_regex {
+            Some(pattern) => Some(
+                Regex::new(pattern)
+                    .with_context(|| format!("Invalid body_regex '{}' in synthetic check '{}'", pattern, config.name))?,
+            ),
+            None => None,
+        };
+        Ok(Self { config, method, body_regex, runs: VecDeque::new(), failing_since: None })
+    }
+
+    /// What a response fails of the check's assertions.
+    pub fn assert(&self, status: u16, latency_ms: f64, body: &str) -> Vec<String> {
+        let mut failures = Vec::new();
+        match self.config.expect_status {
+            Some(expected) if status != expected => failures.push(format!("status {} (expected {})", status, expected)),
+            None if !(200..300).contains(&status) => failures.push(format!("status {} (expected 2xx)", status)),
+            _ => {}
+        }
+        if let Some(budget) = self.config.max_latency_ms.filter(|&budget| latency_ms > budget as f64) {
+            failures.push(format!("took {:.0} ms (budget {} ms)", latency_ms, budget));
+        }
+        if let Some(regex) = self.body_regex.as_ref().filter(|regex| !regex.is_match(body)) {
+            failures.push(format!("body doesn't match `{}`", regex.as_str()));
+        }
+        failures
+    }
+
+    /// Keep a run. Returns true when it starts a failure streak.
+    pub fn record(&mut self, run: CheckRun) -> bool {
+        let started_failing = !run.passed() && self.failing_since.is_none();
+        self.failing_since = if run.passed() { None } else { self.failing_since.or(Some(run.at)) };
+        self.runs.push_back(run);
+        if self.runs.len() > HISTORY {
+            self.runs.pop_front();
+        }
+        started_failing
+    }
+
+    pub fn is_failing(&self) -> bool {
+        self.failing_since.is_some()
+    }
+
+    pub fn last(&self) -> Option<&CheckRun> {
+        self.runs.back()
+    }
+
+    /// Share of the kept runs that passed, in percent.
+    pub fn uptime_pct(&self) -> Option<f64> {
+        let passed = self.runs.iter().filter(|run| run.passed()).count();
+        (!self.runs.is_empty()).then(|| passed as f64 * 100.0 / self.runs.len() as f64)
+    }
+}
+
+/// Send the check's request once and assert on the response.
+pub async fn run(client: &Client, check: &SyntheticCheck) -> CheckRun {
+    let at = Local::now();
+    let started = Instant::now();
+    let mut request = client.request(check.method.clone(), &check.config.url);
+    if let Some(body) = &check.config.body {
+        request = request.body(body.clone());
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            let failure = if e.is_timeout() { "timed out".to_string() } else { format!("request failed: {}", e) };
+            return CheckRun { at, latency_ms: started.elapsed().as_secs_f64() * 1000.0, status: None, failures: vec![failure] };
+        }
+    };
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    CheckRun { at, latency_ms, status: Some(status), failures: check.assert(status, latency_ms, &body) }
+}
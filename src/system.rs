@@ -0,0 +1,122 @@
+//! Local system view (`L`): the host's load, CPU and memory, and its top
+//! processes by CPU or memory, for when rustdash runs on the box being
+//! debugged. The host is only sampled while the view is open.
+
+use crate::tcp::{self, PortConnections};
+use sysinfo::{ProcessesToUpdate, System};
+
+/// Seconds between samples while the view is open.
+pub const SAMPLE_SECONDS: u64 = 2;
+
+/// What the process list is ordered by, toggled with `s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+}
+
+impl ProcessSort {
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Cpu => Self::Memory,
+            Self::Memory => Self::Cpu,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cpu => "CPU",
+            Self::Memory => "memory",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_pct: f32,      // Of one core, so up to 100 × cores
+    pub memory_bytes: u64, // Resident
+}
+
+/// One sample of the host.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SystemSnapshot {
+    pub host: Option<String>,
+    pub load: [f64; 3], // 1, 5 and 15 minute load averages; zero where not reported
+    pub cpu_count: usize,
+    pub cpu_pct: f32, // Of all cores
+    pub memory_used: u64,
+    pub memory_total: u64,
+    pub swap_used: u64,
+    pub swap_total: u64,
+    pub uptime_seconds: u64,
+    pub processes: Vec<ProcessInfo>,
+    pub tcp: Option<Vec<PortConnections>>, // None where /proc/net isn't available
+}
+
+impl SystemSnapshot {
+    /// The processes ordered by `sort`, heaviest first.
+    pub fn sorted(&self, sort: ProcessSort) -> Vec<&ProcessInfo> {
+        let mut processes: Vec<&ProcessInfo> = self.processes.iter().collect();
+        match sort {
+            ProcessSort::Cpu => processes.sort_by(|a, b| b.cpu_pct.total_cmp(&a.cpu_pct).then(b.memory_bytes.cmp(&a.memory_bytes))),
+            ProcessSort::Memory => processes.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes).then(b.cpu_pct.total_cmp(&a.cpu_pct))),
+        }
+        processes
+    }
+}
+
+/// Samples the host. CPU usage is measured between two refreshes, so the
+/// first sample reports none.
+pub struct SystemSampler {
+    system: System,
+}
+
+impl Default for SystemSampler {
+    fn default() -> Self {
+        Self { system: System::new() }
+    }
+}
+
+impl SystemSampler {
+    pub fn sample(&mut self) -> SystemSnapshot {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        self.system.refresh_processes(ProcessesToUpdate::All, true);
+        let load = System::load_average();
+        SystemSnapshot {
+            host: System::host_name(),
+            load: [load.one, load.five, load.fifteen],
+            cpu_count: self.system.cpus().len(),
+            cpu_pct: self.system.global_cpu_usage(),
+            memory_used: self.system.used_memory(),
+            memory_total: self.system.total_memory(),
+            swap_used: self.system.used_swap(),
+            swap_total: self.system.total_swap(),
+            uptime_seconds: System::uptime(),
+            processes: self
+                .system
+                .processes()
+                .values()
+                .filter(|process| process.thread_kind().is_none()) // Linux lists threads too
+                .map(|process| ProcessInfo {
+                    pid: process.pid().as_u32(),
+                    name: process.name().to_string_lossy().into_owned(),
+                    cpu_pct: process.cpu_usage(),
+                    memory_bytes: process.memory(),
+                })
+                .collect(),
+            tcp: tcp::listening_ports(),
+        }
+    }
+}
+
+/// State of the local system view while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct SystemView {
+    pub snapshot: Option<SystemSnapshot>, // None until the first sample
+    pub sort: ProcessSort,
+    pub scroll_offset: usize,
+}
@@ -0,0 +1,104 @@
+//! The metrics table as text for the clipboard (`c` and `M` on the metrics
+//! panel): aligned columns for a chat code block, or a Markdown table for a
+//! ticket. It has the columns the table shows, in its ranking order, under
+//! a line saying what was measured and when.
+
+use crate::prometheus::{Ranking, UriMetric};
+use crate::text;
+use crate::ui::{humanize_bytes, AppState};
+use chrono::Local;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableFormat {
+    Text,     // Columns padded with spaces
+    Markdown, // A pipe table, numbers right-aligned
+}
+
+impl TableFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TableFormat::Text => "text",
+            TableFormat::Markdown => "Markdown",
+        }
+    }
+}
+
+struct Column {
+    title: &'static str,
+    numeric: bool,
+    value: fn(&UriMetric) -> String,
+}
+
+/// Columns of the table as drawn: the ranking's own figure only while
+/// ranking by it, bandwidth only when a size metric answered.
+fn columns(ranking: Ranking, rows: &[UriMetric]) -> Vec<Column> {
+    let mut columns = vec![
+        Column { title: "URI", numeric: false, value: |m| m.uri.clone() },
+        Column { title: "Avg (ms)", numeric: true, value: |m| format!("{:.1}", m.avg_duration_ms) },
+        Column { title: "Req/min", numeric: true, value: |m| format!("{:.0}", m.request_count) },
+    ];
+    match ranking {
+        Ranking::Errors => {
+            columns.push(Column { title: "Err/min", numeric: true, value: |m| format!("{:.1}", m.errors_per_min) })
+        }
+        Ranking::Change => columns.push(Column {
+            title: "Δ prev",
+            numeric: true,
+            value: |m| m.change_pct.map_or("-".to_string(), |pct| format!("{:+.0}%", pct)),
+        }),
+        Ranking::Busiest | Ranking::Slowest => {}
+    }
+    if rows.iter().any(|m| m.bytes_per_sec.is_some()) {
+        columns.push(Column {
+            title: "BW",
+            numeric: true,
+            value: |m| m.bytes_per_sec.map_or("-".to_string(), |bytes| format!("{}/s", humanize_bytes(bytes))),
+        });
+    }
+    columns
+}
+
+/// The metrics table in `format`, or None before any metrics arrived.
+pub fn metrics_table(state: &AppState, format: TableFormat) -> Option<String> {
+    let rows = &state.metrics.as_ref().filter(|metrics| !metrics.uri_metrics.is_empty())?.uri_metrics;
+    let columns = columns(state.metrics_ranking, rows);
+    let cells: Vec<Vec<String>> = rows.iter().map(|row| columns.iter().map(|column| (column.value)(row)).collect()).collect();
+    let heading = format!(
+        "API response times, {} average by {}, {}",
+        state.metrics_time_range.as_str(),
+        state.metrics_ranking.as_str(),
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+    let mut out = vec![heading, String::new()];
+    match format {
+        TableFormat::Text => {
+            let widths: Vec<usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(idx, column)| cells.iter().map(|row| text::width(&row[idx])).fold(text::width(column.title), usize::max))
+                .collect();
+            let line = |values: Vec<&str>| {
+                let padded: Vec<String> = values
+                    .iter()
+                    .zip(&columns)
+                    .zip(&widths)
+                    .map(|((value, column), &width)| {
+                        let pad = " ".repeat(width - text::width(value));
+                        if column.numeric { format!("{}{}", pad, value) } else { format!("{}{}", value, pad) }
+                    })
+                    .collect();
+                padded.join("  ").trim_end().to_string()
+            };
+            out.push(line(columns.iter().map(|column| column.title).collect()));
+            out.push(widths.iter().map(|&width| "-".repeat(width)).collect::<Vec<_>>().join("  "));
+            out.extend(cells.iter().map(|row| line(row.iter().map(String::as_str).collect())));
+        }
+        TableFormat::Markdown => {
+            let line = |values: Vec<String>| format!("| {} |", values.join(" | "));
+            out.push(line(columns.iter().map(|column| column.title.to_string()).collect()));
+            out.push(line(columns.iter().map(|column| if column.numeric { "---:" } else { "---" }.to_string()).collect()));
+            out.extend(cells.iter().map(|row| line(row.iter().map(|cell| cell.replace('|', "\\|")).collect())));
+        }
+    }
+    Some(out.join("\n") + "\n")
+}
@@ -0,0 +1,94 @@
+//! TCP connection states per listening port, read from `/proc/net/tcp` and
+//! `/proc/net/tcp6` (no eBPF or conntrack needed). A full accept queue or
+//! piling CLOSE_WAITs often explains a slow service sooner than its metrics.
+//! Linux only; shown in the local system view.
+
+use std::collections::BTreeMap;
+
+/// Socket states as numbered in `/proc/net/tcp`.
+const ESTABLISHED: u8 = 0x01;
+const SYN_RECV: u8 = 0x03;
+const TIME_WAIT: u8 = 0x06;
+const CLOSE_WAIT: u8 = 0x08;
+const LISTEN: u8 = 0x0A;
+
+/// One row of `/proc/net/tcp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Socket {
+    pub local_port: u16,
+    pub state: u8,
+    pub tx_queue: u32, // For a listener, its accept backlog
+    pub rx_queue: u32, // For a listener, connections waiting to be accepted
+}
+
+/// Connections to one listening port, by state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortConnections {
+    pub port: u16,
+    pub established: usize,
+    pub syn_recv: usize,
+    pub time_wait: usize,
+    pub close_wait: usize,
+    pub other: usize,      // Closing: FIN_WAIT, LAST_ACK, ...
+    pub accept_queue: u32, // Waiting for the application to accept them
+    pub backlog: u32,
+}
+
+impl PortConnections {
+    /// Every connection slot in the accept queue is taken; new ones are dropped.
+    pub fn queue_full(&self) -> bool {
+        self.backlog > 0 && self.accept_queue >= self.backlog
+    }
+}
+
+/// The sockets of a `/proc/net/tcp` or `tcp6` table; unreadable rows are skipped.
+pub fn parse(table: &str) -> Vec<Socket> {
+    table
+        .lines()
+        .skip(1) // Column names
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1); // `sl`
+            let local = fields.next()?;
+            let _remote = fields.next()?;
+            let state = u8::from_str_radix(fields.next()?, 16).ok()?;
+            let (tx_queue, rx_queue) = fields.next()?.split_once(':')?;
+            Some(Socket {
+                local_port: u16::from_str_radix(local.rsplit_once(':')?.1, 16).ok()?,
+                state,
+                tx_queue: u32::from_str_radix(tx_queue, 16).ok()?,
+                rx_queue: u32::from_str_radix(rx_queue, 16).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Connections by state for each listening port, lowest port first. A port
+/// listened on over both IPv4 and IPv6 is counted once.
+pub fn summarize(sockets: &[Socket]) -> Vec<PortConnections> {
+    let mut ports: BTreeMap<u16, PortConnections> = BTreeMap::new();
+    for listener in sockets.iter().filter(|socket| socket.state == LISTEN) {
+        let port = ports.entry(listener.local_port).or_insert_with(|| PortConnections { port: listener.local_port, ..Default::default() });
+        port.accept_queue += listener.rx_queue;
+        port.backlog = port.backlog.max(listener.tx_queue);
+    }
+    for socket in sockets.iter().filter(|socket| socket.state != LISTEN) {
+        let Some(port) = ports.get_mut(&socket.local_port) else { continue };
+        match socket.state {
+            ESTABLISHED => port.established += 1,
+            SYN_RECV => port.syn_recv += 1,
+            TIME_WAIT => port.time_wait += 1,
+            CLOSE_WAIT => port.close_wait += 1,
+            _ => port.other += 1,
+        }
+    }
+    ports.into_values().collect()
+}
+
+/// This host's listening ports; None where `/proc/net/tcp` can't be read.
+pub fn listening_ports() -> Option<Vec<PortConnections>> {
+    let mut sockets = parse(&std::fs::read_to_string("/proc/net/tcp").ok()?);
+    if let Ok(table) = std::fs::read_to_string("/proc/net/tcp6") {
+        sockets.extend(parse(&table));
+    }
+    Some(summarize(&sockets))
+}
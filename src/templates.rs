@@ -0,0 +1,114 @@
+//! Rules that fold raw request paths into templates (`/api/users/1234` →
+//! `/api/users/{id}`) for services that don't label templated paths, so the
+//! metrics table lists logical endpoints instead of one row per URL.
+
+use crate::history::UriTotals;
+use crate::prometheus::UriMetric;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One `[[uri_templates]]` rule. The pattern must match the whole path;
+/// the template may refer to capture groups as `$1` or `$name`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UriTemplate {
+    pub pattern: String,
+    pub template: String,
+}
+
+/// Compiled rules, tried in order; the first match wins.
+#[derive(Debug, Clone, Default)]
+pub struct UriTemplates {
+    rules: Vec<(Regex, UriTemplate)>,
+}
+
+impl UriTemplates {
+    pub fn new(rules: &[UriTemplate]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let regex = Regex::new(&format!("^(?:{})$", rule.pattern))
+                    .with_context(|| format!("Invalid uri_templates pattern '{}'", rule.pattern))?;
+                Ok((regex, rule.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The template `uri` belongs to, or `uri` itself when no rule matches.
+    pub fn apply(&self, uri: &str) -> String {
+        self.rules
+            .iter()
+            .find(|(regex, _)| regex.is_match(uri))
+            .map(|(regex, rule)| regex.replace(uri, rule.template.as_str()).into_owned())
+            .unwrap_or_else(|| uri.to_string())
+    }
+
+    /// Prometheus regex for the raw paths behind `template`, for queries about
+    /// a single table row. None when no rule has `template` as its literal
+    /// template, e.g. a path no rule matched.
+    pub fn members_pattern(&self, template: &str) -> Option<String> {
+        let patterns: Vec<&str> = self
+            .rules
+            .iter()
+            .filter(|(_, rule)| rule.template == template)
+            .map(|(_, rule)| rule.pattern.as_str())
+            .collect();
+        (!patterns.is_empty()).then(|| patterns.join("|"))
+    }
+
+    /// Merge rows that share a template. Request, error and byte rates add up;
+    /// latencies and latency changes are averaged weighted by request rate.
+    pub fn group_metrics(&self, metrics: Vec<UriMetric>) -> Vec<UriMetric> {
+        if self.is_empty() {
+            return metrics;
+        }
+        let mut groups: Vec<UriMetric> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for metric in metrics {
+            let uri = self.apply(&metric.uri);
+            match index.get(&uri) {
+                Some(&idx) => {
+                    let group = &mut groups[idx];
+                    let (a, b) = (group.request_count, metric.request_count);
+                    let mean = |x: f64, y: f64| if a + b > 0.0 { (x * a + y * b) / (a + b) } else { (x + y) / 2.0 };
+                    group.avg_duration_ms = mean(group.avg_duration_ms, metric.avg_duration_ms);
+                    group.change_pct = match (group.change_pct, metric.change_pct) {
+                        (Some(x), Some(y)) => Some(mean(x, y)),
+                        (x, y) => x.or(y),
+                    };
+                    group.request_count += metric.request_count;
+                    group.errors_per_min += metric.errors_per_min;
+                    group.bytes_per_sec = match (group.bytes_per_sec, metric.bytes_per_sec) {
+                        (Some(x), Some(y)) => Some(x + y),
+                        (x, y) => x.or(y),
+                    };
+                }
+                None => {
+                    index.insert(uri.clone(), groups.len());
+                    groups.push(UriMetric { uri, ..metric });
+                }
+            }
+        }
+        groups
+    }
+
+    /// Sum cumulative counters per template.
+    pub fn group_totals(&self, totals: HashMap<String, UriTotals>) -> HashMap<String, UriTotals> {
+        if self.is_empty() {
+            return totals;
+        }
+        let mut grouped: HashMap<String, UriTotals> = HashMap::new();
+        for (uri, totals) in totals {
+            let entry = grouped.entry(self.apply(&uri)).or_default();
+            entry.count += totals.count;
+            entry.sum_seconds += totals.sum_seconds;
+        }
+        grouped
+    }
+}
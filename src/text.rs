@@ -0,0 +1,155 @@
+//! Fitting text into table columns by display width rather than bytes, so
+//! localized routes and emoji neither split a character nor misalign the
+//! columns that follow.
+
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Where text too wide for its column is cut.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Truncation {
+    #[default]
+    End,    // `/api/orders/{id}/det…`
+    Middle, // `/api/…/details`, keeping the most specific part of a path
+}
+
+/// Columns `text` takes up on screen.
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// Cut `text` to at most `max_width` columns, marking the cut with `…`.
+pub fn truncate(text: &str, max_width: usize, truncation: Truncation) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let room = max_width.saturating_sub(1); // Leave a column for the ellipsis
+    if room == 0 {
+        return "…".repeat(max_width);
+    }
+    match truncation {
+        Truncation::End => format!("{}…", take_width(text, room)),
+        Truncation::Middle => truncate_segments(text, max_width).unwrap_or_else(|| {
+            let tail = take_width_end(text, room / 2);
+            format!("{}…{}", take_width(text, room - tail.width()), tail)
+        }),
+    }
+}
+
+/// `text` padded with spaces to `column_width` columns, cut first if wider.
+pub fn fit(text: &str, column_width: usize, truncation: Truncation) -> String {
+    let text = truncate(text, column_width, truncation);
+    let padding = column_width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// Middle truncation on whole path segments: as many leading segments and
+/// then trailing ones as fit around `/…/`. None if not even the first and
+/// last segment fit.
+fn truncate_segments(path: &str, max_width: usize) -> Option<String> {
+    let segments: Vec<&str> = path.split('/').collect();
+    let join = |head: usize, tail: usize| {
+        format!("{}/…/{}", segments[..head].join("/"), segments[segments.len() - tail..].join("/"))
+    };
+    // A leading slash makes the first segment empty; keep the one after it too
+    let (mut head, mut tail) = (if path.starts_with('/') { 2 } else { 1 }, 1);
+    if head + tail >= segments.len() || join(head, tail).width() > max_width {
+        return None;
+    }
+    while head + tail + 1 < segments.len() && join(head + 1, tail).width() <= max_width {
+        head += 1;
+    }
+    while head + tail + 1 < segments.len() && join(head, tail + 1).width() <= max_width {
+        tail += 1;
+    }
+    Some(join(head, tail))
+}
+
+/// Longest prefix of `text` at most `max_width` columns wide.
+pub fn take_width(text: &str, max_width: usize) -> &str {
+    let mut used = 0;
+    for (idx, c) in text.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > max_width {
+            return &text[..idx];
+        }
+    }
+    text
+}
+
+/// Longest suffix of `text` at most `max_width` columns wide.
+fn take_width_end(text: &str, max_width: usize) -> &str {
+    let mut used = 0;
+    for (idx, c) in text.char_indices().rev() {
+        used += c.width().unwrap_or(0);
+        if used > max_width {
+            return &text[idx + c.len_utf8()..];
+        }
+    }
+    text
+}
+
+/// A single line of text being typed, with a cursor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineInput {
+    pub text: String,
+    pub cursor: usize, // Byte offset into text, on a char boundary
+}
+
+impl LineInput {
+    /// Start editing `text` with the cursor at its end.
+    pub fn new(text: &str) -> Self {
+        Self { text: text.to_string(), cursor: text.len() }
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Remove the char before the cursor; false if there was none.
+    pub fn backspace(&mut self) -> bool {
+        let Some(c) = self.text[..self.cursor].chars().next_back() else {
+            return false;
+        };
+        self.cursor -= c.len_utf8();
+        self.text.remove(self.cursor);
+        true
+    }
+
+    /// Remove the char at the cursor; false if there was none.
+    pub fn delete(&mut self) -> bool {
+        if self.cursor == self.text.len() {
+            return false;
+        }
+        self.text.remove(self.cursor);
+        true
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    /// Remove everything before the cursor (Ctrl+U).
+    pub fn clear_to_start(&mut self) {
+        self.text.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+}
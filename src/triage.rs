@@ -0,0 +1,119 @@
+//! Triage states local to the session (`T`): alerts in the banner and log
+//! line patterns can be acknowledged, marked as under investigation or
+//! ignored, so the banner and the log highlights stop nagging about what has
+//! been seen while new alerts and patterns still surface. Nothing is sent
+//! to the backends.
+
+use crate::ignore;
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriageState {
+    Acknowledged,  // Seen: quiet until it clears and fires again
+    Investigating, // Still shown, marked as being looked into
+    Ignored,       // Quiet for the rest of the session
+}
+
+impl TriageState {
+    pub const ALL: [TriageState; 3] = [TriageState::Acknowledged, TriageState::Investigating, TriageState::Ignored];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Acknowledged => "acknowledged",
+            Self::Investigating => "investigating",
+            Self::Ignored => "ignored",
+        }
+    }
+}
+
+/// An alert of the banner.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Alert {
+    Silent(String),  // A silence alarm, by its label
+    Failing(String), // A synthetic check, by its name
+    Burning(String), // An SLO spending its error budget too fast, by its name
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Silent(label) => write!(f, "SILENT {}", label),
+            Self::Failing(name) => write!(f, "FAILING {}", name),
+            Self::Burning(name) => write!(f, "BURNING {}", name),
+        }
+    }
+}
+
+/// Log lines shaped like one the pattern was taken from.
+#[derive(Debug, Clone)]
+pub struct LogPattern {
+    pub pattern: String, // As for hiding similar lines: the line with its numbers generalized
+    regex: Regex,
+    pub state: TriageState,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Triage {
+    alerts: BTreeMap<Alert, (TriageState, DateTime<Local>)>, // With when the triaged firing started
+    pub patterns: Vec<LogPattern>,
+}
+
+impl Triage {
+    /// Triage the firing of `alert` that started at `since`; None clears it.
+    pub fn set_alert(&mut self, alert: Alert, since: DateTime<Local>, state: Option<TriageState>) {
+        match state {
+            Some(state) => {
+                self.alerts.insert(alert, (state, since));
+            }
+            None => {
+                self.alerts.remove(&alert);
+            }
+        }
+    }
+
+    /// How the firing of `alert` that started at `since` was triaged. An
+    /// acknowledgement only covers the firing it was given for.
+    pub fn alert_state(&self, alert: &Alert, since: DateTime<Local>) -> Option<TriageState> {
+        match self.alerts.get(alert) {
+            Some((TriageState::Acknowledged, acknowledged)) if *acknowledged != since => None,
+            Some((state, _)) => Some(*state),
+            None => None,
+        }
+    }
+
+    /// Whether the alert still belongs in the banner and may ring the bell.
+    pub fn nags(&self, alert: &Alert, since: DateTime<Local>) -> bool {
+        matches!(self.alert_state(alert, since), None | Some(TriageState::Investigating))
+    }
+
+    /// Triage lines shaped like `message`; None clears it. Returns the pattern.
+    pub fn set_log(&mut self, message: &str, state: Option<TriageState>) -> String {
+        let pattern = ignore::similar_pattern(message);
+        self.patterns.retain(|triaged| triaged.pattern != pattern);
+        if let Some(state) = state {
+            let regex = Regex::new(&pattern).expect("similar_pattern escapes the line");
+            self.patterns.push(LogPattern { pattern: pattern.clone(), regex, state });
+        }
+        pattern
+    }
+
+    /// How a log line's pattern was triaged.
+    pub fn log_state(&self, message: &str) -> Option<TriageState> {
+        self.patterns.iter().find(|triaged| triaged.regex.is_match(message)).map(|triaged| triaged.state)
+    }
+
+    pub fn clear_pattern(&mut self, idx: usize) {
+        if idx < self.patterns.len() {
+            self.patterns.remove(idx);
+        }
+    }
+}
+
+/// The triage popup while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct TriagePopup {
+    pub selected: usize, // Firing alerts first, then the triaged log patterns
+}
@@ -0,0 +1,115 @@
+//! Supervised `ssh -L` tunnels for endpoints only reachable from a remote host.
+
+use anyhow::{bail, Result};
+use reqwest::Url;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+/// How long to wait for a new tunnel to accept connections.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+/// Upper bound for the reconnect backoff.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A local port forwarded to an endpoint through an SSH host. The ssh process
+/// is restarted whenever it exits and killed when this is dropped.
+pub struct SshTunnel {
+    pub local_url: String, // base_url rewritten to the local end of the tunnel
+    handle: JoinHandle<()>,
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// The host is passed to `ssh`, so one that looks like an option is refused.
+pub fn validate_ssh_host(section: &str, host: &str) -> Result<()> {
+    if host.trim().is_empty() || host.starts_with('-') {
+        bail!("Invalid {}.ssh_host {:?}", section, host);
+    }
+    Ok(())
+}
+
+impl SshTunnel {
+    /// Forward a free local port to `base_url`'s host (as seen from `ssh_host`)
+    /// on `remote_port`, defaulting to the URL's port, and wait until it is up.
+    pub async fn open(base_url: &str, ssh_host: &str, remote_port: Option<u16>) -> Result<Self> {
+        let mut url = Url::parse(base_url)?;
+        let remote_host = url
+            .host_str()
+            .ok_or_else(|| anyhow::anyhow!("No host in {}", base_url))?
+            .to_string();
+        let remote_port = remote_port
+            .or_else(|| url.port_or_known_default())
+            .ok_or_else(|| anyhow::anyhow!("No port for {}", base_url))?;
+
+        let local_port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let forward = format!("127.0.0.1:{}:{}:{}", local_port, remote_host, remote_port);
+        let handle = tokio::spawn(supervise(ssh_host.to_string(), forward));
+
+        url.set_host(Some("127.0.0.1"))?;
+        url.set_port(Some(local_port))
+            .map_err(|_| anyhow::anyhow!("Cannot set port on {}", base_url))?;
+        let tunnel = Self {
+            local_url: url.as_str().trim_end_matches('/').to_string(),
+            handle,
+        };
+
+        let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+        while TcpStream::connect(("127.0.0.1", local_port)).await.is_err() {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "SSH tunnel via {} to {}:{} did not come up",
+                    ssh_host,
+                    remote_host,
+                    remote_port
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+
+        Ok(tunnel)
+    }
+}
+
+/// Keep an `ssh -N -L <forward> <host>` process running, restarting it with
+/// exponential backoff whenever it exits.
+async fn supervise(ssh_host: String, forward: String) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let started = tokio::time::Instant::now();
+        let child = Command::new("ssh")
+            .args(["-N", "-o", "BatchMode=yes", "-o", "ExitOnForwardFailure=yes"])
+            .args(["-o", "ServerAliveInterval=15", "-o", "ServerAliveCountMax=3"])
+            .args(["-L", &forward, "--", &ssh_host])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped()) // Keep ssh's messages off the TUI
+            .kill_on_drop(true)
+            .spawn();
+
+        match child {
+            Ok(child) => match child.wait_with_output().await {
+                Ok(output) => log::warn!(
+                    "SSH tunnel to {} exited ({}): {}",
+                    ssh_host,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(e) => log::warn!("SSH tunnel to {} failed: {}", ssh_host, e),
+            },
+            Err(e) => log::warn!("Failed to start ssh for {}: {}", ssh_host, e),
+        }
+
+        // A tunnel that stayed up for a while starts over with a short delay
+        if started.elapsed() > MAX_BACKOFF {
+            backoff = Duration::from_secs(1);
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
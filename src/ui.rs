@@ -1,11 +1,57 @@
-use crate::loki::LogEntry;
-use crate::prometheus::MetricsData;
-use chrono::Local;
+use crate::admin::{AdminAction, AdminMenu};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::backoff::Backoff;
+use crate::breaker::CircuitBreaker;
+use crate::changes::{ChangeKind, MetricChange, MetricChanges};
+use crate::diff;
+use crate::error::ErrorClass;
+use crate::exchanges::{DebugPopup, ExchangeLog};
+use crate::fetch::RefreshScope;
+use crate::forecast;
+use crate::goto::{self, GotoPrompt, GotoTarget};
+use crate::guard::SlowQueryGuard;
+use crate::history::MetricHistory;
+use crate::ignore::IgnoreList;
+use crate::inspect::{self, Inspection};
+use crate::layout::{CellContent, LayoutConfig};
+use crate::log_actions::{LogActionMenu, LogActionsConfig, LogFilter};
+use crate::log_files::LogFiles;
+use crate::log_overlay::{LogQuery, MAX_LOG_QUERIES};
+use crate::log_query::{self, LogQueryBar, TokenKind};
+use crate::loki::{LevelCounts, LogEntry};
+use crate::modal::{self, ModalKind, ModalStack};
+use crate::palette::{Palette, VISIBLE_ENTRIES};
+use crate::ping::PingTarget;
+use crate::panels::{CounterView, Heatmap, PanelData, PanelRow, Severity, StatStyle, Table, TableSort, ValueFormat};
+use crate::query_editor::{Completing, QueryEditor, FUNCTIONS};
+use crate::prometheus::{self, MetricsData, Ranking, ServerInfo, ServiceEdge, UriHistory, UriMetric};
+use crate::query_stats::{Backend, QueryStats};
+use crate::retention::{self, Limit, RetentionConfig};
+use crate::silence::SilenceAlarm;
+use crate::slo::{Slo, BURN_ALERTS, WINDOWS};
+use crate::status_pages::{DependencyStatus, Indicator};
+use crate::synthetic::SyntheticCheck;
+use crate::notes::{Note, NotesPad};
+use crate::persist::LogAnchor;
+use crate::saved_views::{SavedView, SavedViewsPopup};
+use crate::share::ShareServer;
+use crate::system::{ProcessSort, SystemView};
+use crate::tcp::PortConnections;
+use crate::triage::{Alert, Triage, TriagePopup, TriageState};
+use crate::text::{self, Truncation};
+use crate::undo::{ViewHistory, ViewState};
+use crate::variables::{self, Variable, VariablePicker};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    symbols::Marker,
+    widgets::{Axis, Block, BorderType, Borders, Chart, Clear, Dataset, Gauge, GraphType, List, ListItem, Paragraph},
     Frame,
 };
 
@@ -21,23 +67,224 @@ pub struct AppState {
     pub log_scroll_offset: usize,
     pub all_logs: Vec<LogEntry>,  // Store all logs for scrolling
     pub last_terminal_height: u16, // Track terminal height for background updates
+    pub last_terminal_width: u16,  // Sizes range queries to the chart width
     pub last_fetch_count: usize,   // Track how many logs we had in the last fetch
     pub has_initial_fetch: bool,   // Track if we've done the initial fetch
     pub active_panel: ActivePanel,  // Which panel is currently active
     pub metrics_scroll_offset: usize, // Scroll offset for metrics
+    pub metrics_column_offset: usize, // Metrics table columns scrolled past the frozen URI column
     pub metrics_time_range: TimeRange, // Current time range for metrics
+    pub metrics_ranking: Ranking, // Dimension the metrics table is ranked by
     pub metrics_loading: bool, // Whether metrics are currently loading
     pub expanded_log_index: Option<usize>, // Index of the log that is expanded to show full content
+    pub restore_scroll_anchor: Option<LogAnchor>,   // Persisted line to scroll to on first fetch
+    pub restore_selected_anchor: Option<LogAnchor>, // Persisted line to select on first fetch
+    pub restore_selected_uri: Option<String>,    // Saved view's URI to select once the metrics table shows it
+    pub accessible: bool, // Screen-reader friendly mode: text indicators, no bar charts
+    pub log_volume: Vec<u64>, // Log counts per minute over the last 30 minutes, oldest first
+    pub metrics_last_success: Option<DateTime<Local>>, // Last successful Prometheus fetch
+    pub logs_last_success: Option<DateTime<Local>>,    // Last successful Loki fetch
+    pub refresh_interval_seconds: u64, // Data older than twice this is flagged as stale
+    pub selected_metric_index: Option<usize>, // Selected URI row in the metrics panel
+    pub drill_in: Option<UriDrillIn>, // Full-width history chart for one URI
+    pub service_map: Option<ServiceMap>, // Service dependency overview, toggled with `m`
+    pub panels: Vec<PanelData>, // Configured [[panels]], refreshed with the metrics
+    pub panels_page: Option<PanelsPage>, // Panels page, toggled with `p`
+    pub counter_views: HashMap<usize, CounterView>, // Counter view picked per panel index with `c` on the panels page
+    pub history: MetricHistory, // Per-URI counters sampled each refresh, charted for "All"
+    pub prometheus_enabled: bool, // false when running without Prometheus
+    pub loki_enabled: bool,       // false when running without Loki
+    pub prometheus_version: Option<String>, // From build info, cached by the client
+    pub loki_version: Option<String>,
+    pub refresh_static: bool, // Revalidate cached build info and labels on the next fetch
+    pub ignore: IgnoreList,      // URIs and log lines kept out of the tables
+    pub hidden_uri_count: usize, // Rows the ignore list removed from the last metrics fetch
+    pub hidden_log_count: usize, // Lines the ignore list removed from the last log fetch
+    pub log_level_counts: LevelCounts, // Lines per level in `all_logs`, counted when it changes
+    pub refreshing: Option<RefreshScope>, // Manual refresh requested, shown as a spinner until it's done
+    pub metrics_fetch_started: Option<DateTime<Local>>, // Prometheus fetch in flight since
+    pub logs_fetch_started: Option<DateTime<Local>>,    // Loki fetch in flight since
+    pub metrics_backoff: Backoff, // Retry schedule while Prometheus is unreachable
+    pub logs_backoff: Backoff,    // Retry schedule while Loki is unreachable
+    pub prometheus_clock_skew: Option<f64>, // Seconds Prometheus' clock is ahead of ours
+    pub loki_clock_skew: Option<f64>,       // Seconds Loki's clock is ahead of ours
+    pub clock_skew_warn_seconds: u64,       // Skew beyond this is flagged in the endpoints bar
+    pub show_log_ages: bool, // Prefix log lines with their age ("12s", "3m"), toggled with `t`
+    pub show_line_numbers: bool, // Number the buffered log lines in a gutter, toggled with `#`
+    pub log_jump_steps: [i64; 2], // Seconds `{`/`}` and `(`/`)` move the log selection
+    pub silence_alarms: Vec<SilenceAlarm>, // Configured streams and whether they've gone quiet
+    pub log_queries: Vec<LogQuery>,        // Overlaid in the logs panel; a line's `source` indexes them
+    pub log_files: Option<LogFiles>,       // Local files followed into the logs panel
+    pub external_deps: Vec<DependencyStatus>, // Polled `[[status_pages]]`, in config order
+    pub synthetic_checks: Vec<SyntheticCheck>, // `[[synthetic_checks]]` and their recent runs
+    pub checks_popup: bool,                // Synthetic checks chart, opened with `C`
+    pub ping_targets: Vec<PingTarget>,     // `[ping]` hosts and their recent round trips
+    pub ping_popup: bool,                  // Network latency chart, opened with `N`
+    pub changes_popup: bool,               // Metrics changes since the last refresh, opened with `K`
+    pub slos: Vec<Slo>,                    // `[[slos]]` and their latest burn rates
+    pub slo_popup: bool,                   // SLO burn rates, opened with `B`
+    pub metric_changes: MetricChanges,     // Metrics rows that changed between the last two refreshes
+    pub change_markers: bool,              // Mark the changed rows in the metrics table
+    pub change_threshold_pct: f64,         // Latency change, in percent, that marks a row
+    pub system_view: Option<SystemView>,   // Local processes, load and memory, opened with `L`
+    pub share: Option<ShareServer>,        // Frames served to `rustdash attach` viewers, with --share
+    pub notes: Vec<Note>,                  // Incident notes taken this session, oldest first
+    pub notes_pad: Option<NotesPad>,       // Notes pad popup, opened with `o`
+    pub triage: Triage,                    // Alerts and log patterns acknowledged, investigated or ignored
+    pub triage_popup: Option<TriagePopup>, // Triage popup, opened with `T`
+    pub inspection: Option<Inspection>,    // Exact values and series of the selected row or point, toggled with `v`
+    pub bell: bool, // Ring the terminal bell on the next frame
+    pub admin_actions: Vec<AdminAction>, // Offered in the admin menu; empty unless a backend has `admin = true`
+    pub loki_admin: bool, // Offer a delete request for the selected log line's stream
+    pub admin_menu: Option<AdminMenu>,   // Admin menu popup, opened with `A`
+    pub logs_flushed_before: Option<i64>, // Lines up to this (unix nanos) were flushed with `F` and stay out
+    pub log_actions: LogActionsConfig,    // Trace and link templates offered in the log line menu
+    pub log_action_menu: Option<LogActionMenu>, // Context menu for the selected log line, opened with `a`
+    pub log_filter: LogFilter,            // Stream and line filter the logs panel is narrowed to
+    pub log_pattern: Option<Regex>,       // The filter's line pattern compiled once, for local file lines
+    pub bookmarks: BTreeSet<i64>,         // Bookmarked log lines (unix nanos), `b` jumps between them
+    pub zoomed: bool,                     // The focused panel fills the terminal (`z`)
+    pub log_diff: bool,                   // Diff popup of the selected line against the one before it (`d`)
+    pub log_retention: Limit,             // How many (and how large) buffered log lines are kept
+    pub uri_truncation: Truncation,       // Where long URIs are cut in the metrics table
+    pub layout: LayoutConfig,             // `[layout]` grid of the main area
+    pub uri_error_pattern: Option<String>, // Rule matching error lines to URIs; None hides the "Last error" column
+    pub error_logs: Vec<LogEntry>,        // Recent ERROR lines, oldest first
+    pub uri_errors: HashMap<String, LogEntry>, // Newest of error_logs per URI row
+    pub about: Option<AboutBackends>,     // Server details popup, opened with `i`
+    pub query_stats: Arc<QueryStats>,     // Timings recorded by the clients
+    pub query_stats_popup: Option<QueryStatsPopup>, // Query timings popup, opened with `S`
+    pub exchanges: Arc<ExchangeLog>,     // Last request and response of each query, recorded by the clients
+    pub debug_popup: Option<DebugPopup>, // Raw requests and responses of the focused panel, opened with `D`
+    pub variables: Vec<Variable>,        // `[[variables]]` and their selections, substituted into the panels
+    pub variable_picker: Option<VariablePicker>, // Variable picker popup, opened with `$`
+    pub query_guard: Arc<SlowQueryGuard>, // Queries narrowed or coarsened for being slow
+    pub circuit_breaker: Arc<CircuitBreaker>, // Endpoints cooling down after failing repeatedly
+    pub time_cursor: Option<f64>,         // Instant under investigation (unix seconds); None follows the latest
+    pub audit_log: AuditLog,              // Changes made through the dashboard
+    pub audit_popup: Option<AuditPopup>,  // Recent actions popup, opened with `U`
+    pub query_editor: Option<QueryEditor>, // Ad-hoc PromQL editor, opened with `Q`
+    pub log_query_bar: Option<LogQueryBar>, // LogQL query bar, opened with `/`
+    pub last_log_query: Option<String>,     // Last query Loki accepted from the bar; `/` starts from it once the filter is cleared
+    pub palette: Option<Palette>,           // Command palette, opened with Ctrl+P
+    pub modals: ModalStack,                 // Stacking order of the open overlays; keys go to the top one
+    pub view_history: ViewHistory,          // Filter, time range and ranking changes to undo (`u`) and redo (Ctrl+R)
+    pub saved_views: Vec<SavedView>,        // Named snapshots of the view, kept in the state file
+    pub saved_views_popup: Option<SavedViewsPopup>, // Saved views popup, opened with `V`
+    pub goto_prompt: Option<GotoPrompt>,    // Go-to-line/time prompt over the logs, opened with `:`
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Default)]
+pub struct PanelsPage {
+    pub scroll_offset: usize,
+    pub selected: usize,       // Panel that `c` switches the counter view of, moved with TAB
+    pub sort_shift: usize,     // Table panels sort this many columns right of their configured one
+    pub sort_reversed: bool,   // Table panels sort opposite their configured direction
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QueryStatsPopup {
+    pub scroll_offset: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditPopup {
+    pub scroll_offset: usize,
+}
+
+/// Service-to-service edges from tracing metrics, opened with `m`.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceMap {
+    pub edges: Option<Vec<ServiceEdge>>, // None until the first fetch returns
+    pub error: Option<String>,
+    pub scroll_offset: usize,
+}
+
+impl ServiceMap {
+    /// Rows in the adjacency list: one per caller plus one per edge.
+    pub fn row_count(&self) -> usize {
+        let edges = self.edges.as_deref().unwrap_or_default();
+        let callers = edges.windows(2).filter(|pair| pair[0].client != pair[1].client).count();
+        edges.len() + callers + usize::from(!edges.is_empty())
+    }
+}
+
+/// Version, retention and storage details of the backends, opened with `i`.
+#[derive(Debug, Clone, Default)]
+pub struct AboutBackends {
+    pub prometheus: Option<ServerInfo>, // None until the first fetch returns
+    pub error: Option<String>,
+}
+
+/// History chart for a single URI, opened with `g` on a metrics row.
+#[derive(Debug, Clone)]
+pub struct UriDrillIn {
+    pub uri: String,
+    pub history: Option<UriHistory>, // None until the first range query returns
+    pub error: Option<String>,
+    pub cursor: Option<usize>, // Sample index under the cursor; None follows the latest
+}
+
+impl UriDrillIn {
+    pub fn new(uri: String) -> Self {
+        Self {
+            uri,
+            history: None,
+            error: None,
+            cursor: None,
+        }
+    }
+
+    fn sample_count(&self) -> usize {
+        self.history.as_ref().map_or(0, |h| h.latency_ms.len())
+    }
+
+    pub fn cursor_index(&self) -> Option<usize> {
+        let count = self.sample_count();
+        if count == 0 {
+            return None;
+        }
+        Some(self.cursor.unwrap_or(count - 1).min(count - 1))
+    }
+
+    pub fn move_cursor(&mut self, delta: isize) {
+        let count = self.sample_count();
+        if let Some(idx) = self.cursor_index() {
+            let next = (idx as isize + delta).clamp(0, count as isize - 1) as usize;
+            // Stepping onto the newest sample resumes following it
+            self.cursor = if next == count - 1 { None } else { Some(next) };
+        }
+    }
+
+    /// The time cursor this chart's cursor puts the other panels on: the
+    /// sample's time, or None while it follows the latest.
+    pub fn time_cursor(&self) -> Option<f64> {
+        self.cursor?;
+        let history = self.history.as_ref()?;
+        self.cursor_index().and_then(|idx| history.latency_ms.get(idx)).map(|(t, _)| *t)
+    }
+
+    /// Put the cursor on the sample nearest `t`, or follow the latest.
+    pub fn follow_time_cursor(&mut self, t: Option<f64>) {
+        let samples = self.history.as_ref().map_or(&[][..], |h| h.latency_ms.as_slice());
+        self.cursor = t.and_then(|t| {
+            samples
+                .iter()
+                .enumerate()
+                .min_by(|(_, (a, _)), (_, (b, _))| (a - t).abs().total_cmp(&(b - t).abs()))
+                .map(|(idx, _)| idx)
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum ActivePanel {
     None,    // No panel is active
     Logs,
     Metrics,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum TimeRange {
     OneMin,
     FiveMin,   // Default
@@ -70,7 +317,7 @@ impl TimeRange {
         }
     }
     
-    pub fn to_prometheus_range(&self) -> String {
+    pub fn to_prometheus_range(self) -> String {
         match self {
             TimeRange::OneMin => "1m".to_string(),
             TimeRange::FiveMin => "5m".to_string(),
@@ -118,18 +365,604 @@ impl Default for AppState {
             log_scroll_offset: 0,
             all_logs: Vec::new(),
             last_terminal_height: 50,
+            last_terminal_width: 120,
             last_fetch_count: 0,
             has_initial_fetch: false,
             active_panel: ActivePanel::None,  // Start with no panel active
             metrics_scroll_offset: 0,
+            metrics_column_offset: 0,
             metrics_time_range: TimeRange::FiveMin,  // Default to 5 minutes
+            metrics_ranking: Ranking::Busiest,
             metrics_loading: false,
             expanded_log_index: None,
+            restore_scroll_anchor: None,
+            restore_selected_anchor: None,
+            restore_selected_uri: None,
+            accessible: false,
+            log_volume: Vec::new(),
+            metrics_last_success: None,
+            logs_last_success: None,
+            refresh_interval_seconds: 5,
+            selected_metric_index: None,
+            drill_in: None,
+            service_map: None,
+            panels: Vec::new(),
+            panels_page: None,
+            counter_views: HashMap::new(),
+            history: MetricHistory::default(),
+            prometheus_enabled: true,
+            loki_enabled: true,
+            prometheus_version: None,
+            loki_version: None,
+            refresh_static: false,
+            ignore: IgnoreList::default(),
+            hidden_uri_count: 0,
+            hidden_log_count: 0,
+            log_level_counts: LevelCounts::default(),
+            refreshing: None,
+            metrics_fetch_started: None,
+            logs_fetch_started: None,
+            metrics_backoff: Backoff::default(),
+            logs_backoff: Backoff::default(),
+            prometheus_clock_skew: None,
+            loki_clock_skew: None,
+            clock_skew_warn_seconds: 10,
+            show_log_ages: false,
+            show_line_numbers: false,
+            log_jump_steps: [30, 300],
+            silence_alarms: Vec::new(),
+            log_queries: Vec::new(),
+            log_files: None,
+            external_deps: Vec::new(),
+            synthetic_checks: Vec::new(),
+            checks_popup: false,
+            ping_targets: Vec::new(),
+            ping_popup: false,
+            changes_popup: false,
+            slos: Vec::new(),
+            slo_popup: false,
+            metric_changes: MetricChanges::default(),
+            change_markers: true,
+            change_threshold_pct: 50.0,
+            system_view: None,
+            share: None,
+            notes: Vec::new(),
+            notes_pad: None,
+            triage: Triage::default(),
+            triage_popup: None,
+            inspection: None,
+            bell: false,
+            admin_actions: Vec::new(),
+            loki_admin: false,
+            admin_menu: None,
+            logs_flushed_before: None,
+            log_actions: LogActionsConfig::default(),
+            log_action_menu: None,
+            log_filter: LogFilter::default(),
+            log_pattern: None,
+            bookmarks: BTreeSet::new(),
+            zoomed: false,
+            log_diff: false,
+            log_retention: RetentionConfig::default().log_limit(),
+            uri_truncation: Truncation::End,
+            layout: LayoutConfig::default(),
+            uri_error_pattern: None,
+            error_logs: Vec::new(),
+            uri_errors: HashMap::new(),
+            about: None,
+            query_stats: Arc::default(),
+            query_stats_popup: None,
+            exchanges: Arc::default(),
+            debug_popup: None,
+            variables: Vec::new(),
+            variable_picker: None,
+            query_guard: Arc::default(),
+            circuit_breaker: Arc::default(),
+            time_cursor: None,
+            audit_log: AuditLog::default(),
+            audit_popup: None,
+            query_editor: None,
+            log_query_bar: None,
+            last_log_query: None,
+            palette: None,
+            modals: ModalStack::default(),
+            view_history: ViewHistory::default(),
+            saved_views: Vec::new(),
+            saved_views_popup: None,
+            goto_prompt: None,
         }
     }
 }
 
 impl AppState {
+    /// Whether the logs panel has a source: Loki, or followed local files.
+    pub fn logs_enabled(&self) -> bool {
+        self.loki_enabled || self.log_files.is_some()
+    }
+
+    /// Whether `panel` has a backend behind it.
+    pub fn panel_available(&self, panel: ActivePanel) -> bool {
+        match panel {
+            ActivePanel::None => true,
+            ActivePanel::Logs => self.logs_enabled(),
+            ActivePanel::Metrics => self.prometheus_enabled,
+        }
+    }
+
+    /// Indices into `all_logs` of the older and newer line the diff popup
+    /// compares: the selected line and the one before it, or after it for
+    /// the oldest line.
+    pub fn log_diff_pair(&self) -> Option<(usize, usize)> {
+        let selected = self.selected_log_index?;
+        if self.all_logs.len() < 2 || selected >= self.all_logs.len() {
+            return None;
+        }
+        Some(if selected == 0 { (0, 1) } else { (selected - 1, selected) })
+    }
+
+    /// The panel filling the terminal: the focused one while zoom is on.
+    pub fn zoomed_panel(&self) -> Option<ActivePanel> {
+        (self.zoomed && self.active_panel != ActivePanel::None).then_some(self.active_panel)
+    }
+
+    /// Move keyboard focus to the next panel (Tab) or the previous one
+    /// (Shift+Tab), skipping panels without a backend. From no focus,
+    /// Tab starts at the logs and Shift+Tab at the metrics.
+    pub fn cycle_focus(&mut self, backwards: bool) {
+        let order = if backwards {
+            [ActivePanel::Metrics, ActivePanel::Logs]
+        } else {
+            [ActivePanel::Logs, ActivePanel::Metrics]
+        };
+        let start = order.iter().position(|&panel| panel == self.active_panel).map_or(0, |idx| idx + 1);
+        if let Some(&next) = (start..start + order.len()).map(|idx| &order[idx % order.len()]).find(|&&panel| self.panel_available(panel)) {
+            self.active_panel = next;
+        }
+    }
+
+    /// Whether a fetch or a requested refresh is in flight, i.e. a spinner shows.
+    pub fn is_fetching(&self) -> bool {
+        self.refreshing.is_some() || self.metrics_fetch_started.is_some() || self.logs_fetch_started.is_some()
+    }
+
+    /// Sources a scheduled refresh at `now` should fetch, leaving out backends
+    /// that are waiting out a retry delay. None when neither is due.
+    pub fn due_scope(&self, now: DateTime<Local>) -> Option<RefreshScope> {
+        match (self.metrics_backoff.ready(now), self.logs_backoff.ready(now)) {
+            (true, true) => Some(RefreshScope::All),
+            (true, false) => Some(RefreshScope::Metrics),
+            (false, true) => Some(RefreshScope::Logs),
+            (false, false) => None,
+        }
+    }
+
+    /// Drop ignored rows from the metrics table, keeping the selection in range.
+    pub fn hide_ignored_metrics(&mut self) {
+        let Some(metrics) = self.metrics.as_mut() else { return };
+        let before = metrics.uri_metrics.len();
+        metrics.uri_metrics.retain(|metric| !self.ignore.hides_uri(&metric.uri));
+        let remaining = metrics.uri_metrics.len();
+        self.hidden_uri_count += before - remaining;
+        self.selected_metric_index = match self.selected_metric_index {
+            Some(_) if remaining == 0 => None,
+            selected => selected.map(|idx| idx.min(remaining - 1)),
+        };
+    }
+
+    /// Drop ignored lines from the loaded logs, keeping the selection in range.
+    pub fn hide_ignored_logs(&mut self) {
+        let before = self.all_logs.len();
+        let ignore = &self.ignore;
+        self.all_logs.retain(|log| !ignore.hides_log(&log.message));
+        let removed = before - self.all_logs.len();
+        if removed == 0 {
+            return;
+        }
+        self.hidden_log_count += removed;
+        self.log_level_counts = LevelCounts::of(&self.all_logs);
+        self.last_fetch_count = self.last_fetch_count.saturating_sub(removed);
+        self.selected_log_index = match self.selected_log_index {
+            Some(_) if self.all_logs.is_empty() => None,
+            selected => selected.map(|idx| idx.min(self.all_logs.len() - 1)),
+        };
+        self.update_visible_logs_with_height(self.last_terminal_height);
+    }
+
+    /// Empty the log buffer and start over from the next line Loki delivers:
+    /// lines already logged stay out of later fetches, and everything after
+    /// the flush counts as new.
+    pub fn flush_logs(&mut self) {
+        let newest = self.all_logs.last().map(|log| log.nanos);
+        self.logs_flushed_before = newest.max(self.logs_flushed_before);
+        self.all_logs.clear();
+        self.logs.clear();
+        self.selected_log_index = None;
+        self.expanded_log_index = None;
+        self.log_scroll_offset = 0;
+        self.last_fetch_count = 0;
+        self.hidden_log_count = 0;
+        self.log_level_counts = LevelCounts::default();
+        // Skip the quiet first-fetch baseline, so the lines that follow are highlighted
+        self.has_initial_fetch = true;
+    }
+
+    /// Append `entry` to the audit log; failing to write it replaces the status.
+    pub fn audit(&mut self, entry: AuditEntry) {
+        if let Err(e) = self.audit_log.record(entry) {
+            self.status = format!("Audit log not written: {:#}", e);
+        }
+    }
+
+    /// Server an admin action goes to.
+    pub fn admin_target(&self, action: &AdminAction) -> &str {
+        match action {
+            AdminAction::PrometheusSnapshot | AdminAction::PrometheusCleanTombstones => &self.prometheus_url,
+            AdminAction::LokiDelete { .. } => &self.loki_url,
+        }
+    }
+
+    /// Close the popups and full-area views, back to the panels.
+    pub fn close_views(&mut self) {
+        self.drill_in = None;
+        self.service_map = None;
+        self.panels_page = None;
+        self.system_view = None;
+        for kind in ModalKind::ALL.into_iter().filter(|kind| kind.closes_with_views()) {
+            self.dismiss(kind);
+        }
+    }
+
+    /// Overlays open now, in [`ModalKind::ALL`] order.
+    pub fn open_modals(&self) -> Vec<ModalKind> {
+        ModalKind::ALL.into_iter().filter(|&kind| self.is_open(kind)).collect()
+    }
+
+    /// Whether the overlay `kind` is open and has something to show.
+    pub fn is_open(&self, kind: ModalKind) -> bool {
+        match kind {
+            ModalKind::Inspection => self
+                .inspection
+                .as_ref()
+                .is_some_and(|inspection| self.inspected_uri().is_some_and(|uri| uri == inspection.uri)),
+            ModalKind::AdminMenu => self.admin_menu.is_some(),
+            ModalKind::LogActionMenu => self.log_action_menu.is_some(),
+            ModalKind::LogDiff => self.log_diff,
+            ModalKind::About => self.about.is_some(),
+            ModalKind::QueryStats => self.query_stats_popup.is_some(),
+            ModalKind::Audit => self.audit_popup.is_some(),
+            ModalKind::Debug => self.debug_popup.is_some(),
+            ModalKind::Variables => self.variable_picker.is_some(),
+            ModalKind::SavedViews => self.saved_views_popup.is_some(),
+            ModalKind::Checks => self.checks_popup,
+            ModalKind::Ping => self.ping_popup,
+            ModalKind::Changes => self.changes_popup,
+            ModalKind::Slos => self.slo_popup,
+            ModalKind::QueryEditor => self.query_editor.is_some(),
+            ModalKind::LogQueryBar => self.log_query_bar.is_some(),
+            ModalKind::Goto => self.goto_prompt.is_some(),
+            ModalKind::NotesPad => self.notes_pad.is_some(),
+            ModalKind::Triage => self.triage_popup.is_some(),
+            ModalKind::Palette => self.palette.is_some(),
+        }
+    }
+
+    /// Close the overlay `kind`.
+    pub fn dismiss(&mut self, kind: ModalKind) {
+        match kind {
+            ModalKind::Inspection => self.inspection = None,
+            ModalKind::AdminMenu => self.admin_menu = None,
+            ModalKind::LogActionMenu => self.log_action_menu = None,
+            ModalKind::LogDiff => self.log_diff = false,
+            ModalKind::About => self.about = None,
+            ModalKind::QueryStats => self.query_stats_popup = None,
+            ModalKind::Audit => self.audit_popup = None,
+            ModalKind::Debug => self.debug_popup = None,
+            ModalKind::Variables => self.variable_picker = None,
+            ModalKind::SavedViews => self.saved_views_popup = None,
+            ModalKind::Checks => self.checks_popup = false,
+            ModalKind::Ping => self.ping_popup = false,
+            ModalKind::Changes => self.changes_popup = false,
+            ModalKind::Slos => self.slo_popup = false,
+            ModalKind::QueryEditor => self.query_editor = None,
+            ModalKind::LogQueryBar => self.log_query_bar = None,
+            ModalKind::Goto => self.goto_prompt = None,
+            ModalKind::NotesPad => self.notes_pad = None,
+            ModalKind::Triage => self.triage_popup = None,
+            ModalKind::Palette => self.palette = None,
+        }
+    }
+
+    /// Bring the overlay stack up to date with what is open and return the
+    /// overlay keys go to.
+    pub fn focused_modal(&mut self) -> Option<ModalKind> {
+        let open = self.open_modals();
+        self.modals.sync(&open);
+        self.modals.focused()
+    }
+
+    /// Alerts firing now, with when they started and what they say.
+    pub fn firing_alerts(&self) -> Vec<(Alert, DateTime<Local>, String)> {
+        let silent = self.silence_alarms.iter().filter_map(|alarm| {
+            let since = alarm.silent_since?;
+            Some((Alert::Silent(alarm.rule.label().to_string()), since, format!("no logs for {}m+", alarm.rule.minutes)))
+        });
+        let failing = self.synthetic_checks.iter().filter_map(|check| {
+            let since = check.failing_since?;
+            let reason = check.last().map(|run| run.failures.join(", ")).unwrap_or_default();
+            Some((Alert::Failing(check.config.name.clone()), since, reason))
+        });
+        let burning = self.slos.iter().filter_map(|slo| {
+            Some((Alert::Burning(slo.config.name.clone()), slo.burning_since?, slo.describe_firing()?))
+        });
+        silent.chain(failing).chain(burning).collect()
+    }
+
+    /// The URI whose values are inspected: the drill-in chart's, or the
+    /// selected metrics row's while the metrics panel shows.
+    pub fn inspected_uri(&self) -> Option<String> {
+        if let Some(drill_in) = &self.drill_in {
+            return Some(drill_in.uri.clone());
+        }
+        if self.active_panel != ActivePanel::Metrics || self.service_map.is_some() || self.panels_page.is_some() || self.system_view.is_some() {
+            return None;
+        }
+        let idx = self.selected_metric_index?;
+        self.metrics.as_ref()?.uri_metrics.get(idx).map(|metric| metric.uri.clone())
+    }
+
+    /// Firing alerts that haven't been acknowledged or ignored.
+    pub fn nagging_alerts(&self) -> Vec<(Alert, DateTime<Local>, String)> {
+        self.firing_alerts().into_iter().filter(|(alert, since, _)| self.triage.nags(alert, *since)).collect()
+    }
+
+    /// Narrow the logs panel to `filter` (or widen it again). The buffer
+    /// starts over, since its lines came from the previous query. The change
+    /// can be undone.
+    pub fn set_log_filter(&mut self, filter: LogFilter) {
+        if filter != self.log_filter {
+            self.view_history.record(self.view());
+        }
+        self.apply_log_filter(filter);
+    }
+
+    /// Set the filter without recording the change.
+    fn apply_log_filter(&mut self, filter: LogFilter) {
+        let mut entry = AuditEntry::new("log_filter", &self.loki_url);
+        if let Some(stream) = &filter.stream {
+            entry = entry.param("stream", stream);
+        }
+        if let Some(pattern) = &filter.pattern {
+            entry = entry.param("pattern", pattern);
+        }
+        if let Some(query) = &filter.query {
+            entry = entry.param("query", query);
+        }
+        self.audit(entry);
+        self.log_pattern = filter.compile_pattern();
+        self.log_filter = filter;
+        self.all_logs.clear();
+        self.logs.clear();
+        self.selected_log_index = None;
+        self.expanded_log_index = None;
+        self.log_scroll_offset = 0;
+        self.last_fetch_count = 0;
+        self.hidden_log_count = 0;
+        self.log_level_counts = LevelCounts::default();
+        self.has_initial_fetch = false;
+    }
+
+    /// Show the metrics over `range`, undoably.
+    pub fn set_time_range(&mut self, range: TimeRange) {
+        if range != self.metrics_time_range {
+            self.view_history.record(self.view());
+            self.metrics_time_range = range;
+        }
+    }
+
+    /// Rank the metrics table by `ranking`, undoably.
+    pub fn set_ranking(&mut self, ranking: Ranking) {
+        if ranking != self.metrics_ranking {
+            self.view_history.record(self.view());
+            self.metrics_ranking = ranking;
+        }
+    }
+
+    /// What the view shows, as far as undo goes.
+    pub fn view(&self) -> ViewState {
+        ViewState { log_filter: self.log_filter.clone(), time_range: self.metrics_time_range, ranking: self.metrics_ranking }
+    }
+
+    /// Show `view` as one undoable change. Returns the refresh it needs,
+    /// None when it is what shows already.
+    pub fn show_view(&mut self, view: ViewState) -> Option<RefreshScope> {
+        if view == self.view() {
+            return None;
+        }
+        self.view_history.record(self.view());
+        Some(self.restore_view(view))
+    }
+
+    /// Go back to the view before the last change. Returns the refresh the
+    /// restored view needs, or None with nothing to undo.
+    pub fn undo_view(&mut self) -> Option<RefreshScope> {
+        let previous = self.view_history.undo(self.view())?;
+        self.status = format!("Undid: {}", self.view().describe_change(&previous));
+        Some(self.restore_view(previous))
+    }
+
+    /// Redo the change the last undo took back.
+    pub fn redo_view(&mut self) -> Option<RefreshScope> {
+        let next = self.view_history.redo(self.view())?;
+        self.status = format!("Redid: {}", self.view().describe_change(&next));
+        Some(self.restore_view(next))
+    }
+
+    fn restore_view(&mut self, view: ViewState) -> RefreshScope {
+        let logs = view.log_filter != self.log_filter;
+        let metrics = view.time_range != self.metrics_time_range || view.ranking != self.metrics_ranking;
+        if logs {
+            self.apply_log_filter(view.log_filter);
+        }
+        if metrics {
+            self.metrics_time_range = view.time_range;
+            if view.ranking != self.metrics_ranking {
+                self.metrics_ranking = view.ranking;
+                self.selected_metric_index = None;
+                self.metrics_scroll_offset = 0;
+            }
+            self.metrics_loading = true;
+        }
+        match (metrics, logs) {
+            (true, false) => RefreshScope::Metrics,
+            (false, true) => RefreshScope::Logs,
+            _ => RefreshScope::All,
+        }
+    }
+
+    /// Select the next bookmarked line after the selection, wrapping around,
+    /// and scroll it into view. Returns false if no buffered line is bookmarked.
+    pub fn next_bookmark(&mut self, terminal_height: u16) -> bool {
+        let start = self.selected_log_index.map_or(0, |idx| idx + 1);
+        let count = self.all_logs.len();
+        let Some(idx) = (0..count)
+            .map(|offset| (start + offset) % count)
+            .find(|&idx| self.bookmarks.contains(&self.all_logs[idx].nanos))
+        else {
+            return false;
+        };
+        self.selected_log_index = Some(idx);
+        let visible_height = self.get_visible_height(terminal_height);
+        if idx < self.log_scroll_offset || idx >= self.log_scroll_offset + visible_height {
+            self.log_scroll_offset = idx.saturating_sub(visible_height / 2);
+        }
+        self.update_visible_logs_with_height(terminal_height);
+        true
+    }
+
+    /// Put the time cursor on `t` (unix seconds), or let it follow the latest
+    /// data again. The log line nearest that moment is selected and centered,
+    /// and an open drill-in chart moves its cursor to the nearest sample.
+    pub fn set_time_cursor(&mut self, t: Option<f64>, terminal_height: u16) {
+        self.time_cursor = t;
+        if let Some(drill_in) = self.drill_in.as_mut() {
+            drill_in.follow_time_cursor(t);
+        }
+        match t.and_then(|t| nearest_log(&self.all_logs, t)) {
+            Some(idx) => {
+                self.selected_log_index = Some(idx);
+                self.log_scroll_offset = idx.saturating_sub(self.get_visible_height(terminal_height) / 2);
+            }
+            None if t.is_none() => {
+                // Back to the newest lines
+                self.selected_log_index = None;
+                self.log_scroll_offset = self.all_logs.len();
+            }
+            None => {}
+        }
+        self.update_visible_logs_with_height(terminal_height);
+    }
+
+    /// Select the log line `target` points at, centered, with the time
+    /// cursor on it. Returns the status to show, or why there's no such line.
+    pub fn goto_log(&mut self, target: GotoTarget, terminal_height: u16) -> Result<String, String> {
+        let count = self.all_logs.len();
+        let from = self.selected_log_index.and_then(|idx| self.all_logs.get(idx)).or(self.all_logs.last());
+        let Some(from) = from else {
+            return Err("No log lines to go to".to_string());
+        };
+        let t = match target {
+            GotoTarget::Line(line) if line > count => return Err(format!("There are only {} lines", count)),
+            GotoTarget::Line(line) => self.all_logs[line - 1].nanos as f64 / 1e9,
+            GotoTarget::Time(time) => goto::time_on_day_of(time, from).ok_or_else(|| format!("{} doesn't exist that day", time))?,
+        };
+        self.active_panel = ActivePanel::Logs;
+        self.set_time_cursor(Some(t), terminal_height);
+        if let GotoTarget::Line(line) = target {
+            // Lines logged in the same instant: the numbered one, not the first
+            self.selected_log_index = Some(line - 1);
+            self.log_scroll_offset = (line - 1).saturating_sub(self.get_visible_height(terminal_height) / 2);
+            self.update_visible_logs_with_height(terminal_height);
+        }
+        let line = self.selected_log_index.map_or(0, |idx| idx + 1);
+        Ok(match target {
+            GotoTarget::Line(_) => format!("Line {} of {}", line, count),
+            GotoTarget::Time(time) => format!("Line {} of {}, nearest {}", line, count, time),
+        })
+    }
+
+    /// Move the log selection `seconds` from the selected line (the newest
+    /// without one): back to the last line logged by then, or on to the
+    /// first logged after, however many lines that skips.
+    pub fn jump_logs(&mut self, seconds: i64, terminal_height: u16) -> String {
+        let Some(last) = self.all_logs.len().checked_sub(1) else {
+            return "No log lines".to_string();
+        };
+        let from = self.selected_log_index.unwrap_or(last).min(last);
+        let target = self.all_logs[from].nanos.saturating_add(seconds.saturating_mul(1_000_000_000));
+        let to = if seconds < 0 {
+            self.all_logs.partition_point(|log| log.nanos <= target).saturating_sub(1)
+        } else {
+            self.all_logs.partition_point(|log| log.nanos < target).min(last)
+        };
+        if to == from && self.selected_log_index.is_some() {
+            return if seconds < 0 { "No earlier log lines" } else { "No later log lines" }.to_string();
+        }
+        self.active_panel = ActivePanel::Logs;
+        self.selected_log_index = Some(to);
+        self.log_scroll_offset = to.saturating_sub(self.get_visible_height(terminal_height) / 2);
+        self.update_visible_logs_with_height(terminal_height);
+        let t = self.all_logs[to].nanos as f64 / 1e9;
+        self.time_cursor = Some(t);
+        if let Some(drill_in) = self.drill_in.as_mut() {
+            drill_in.follow_time_cursor(Some(t));
+        }
+        format!(
+            "Log line {} at {}, {} lines {}",
+            to + 1,
+            format_time(t, "%H:%M:%S"),
+            to.abs_diff(from),
+            if seconds < 0 { "back" } else { "on" },
+        )
+    }
+
+    /// Select the saved view's URI once the metrics table shows it.
+    pub fn resolve_selected_uri(&mut self) {
+        let Some(uri) = &self.restore_selected_uri else { return };
+        let idx = self.metrics.as_ref().and_then(|metrics| metrics.uri_metrics.iter().position(|metric| metric.uri == *uri));
+        if idx.is_some() {
+            self.selected_metric_index = idx;
+            self.restore_selected_uri = None;
+        }
+    }
+
+    /// Position the log view on the anchors restored from the state file.
+    /// Returns false if there was nothing to restore.
+    pub fn resolve_restore_anchors(&mut self) -> bool {
+        let scroll_anchor = self.restore_scroll_anchor.take();
+        let selected_anchor = self.restore_selected_anchor.take();
+        if scroll_anchor.is_none() && selected_anchor.is_none() {
+            return false;
+        }
+
+        // The line itself if it is still there, else the first one after it
+        let nearest = |anchor: &LogAnchor| {
+            self.all_logs
+                .iter()
+                .position(|log| log.nanos == anchor.nanos && log.stream == anchor.stream)
+                .or_else(|| self.all_logs.iter().position(|log| log.nanos >= anchor.nanos))
+                .unwrap_or(self.all_logs.len().saturating_sub(1))
+        };
+
+        if let Some(anchor) = scroll_anchor {
+            self.log_scroll_offset = nearest(&anchor);
+        }
+        if let Some(anchor) = selected_anchor {
+            let idx = nearest(&anchor);
+            self.selected_log_index = Some(idx);
+            self.log_scroll_offset = self.log_scroll_offset.min(idx);
+        }
+        true
+    }
     
     pub fn update_visible_logs_with_height(&mut self, terminal_height: u16) {
         // More accurate calculation based on actual layout
@@ -141,17 +974,7 @@ impl AppState {
         // - Margins: 2 lines (top and bottom)
         // - Borders and padding: ~3 lines
         
-        let metrics_height = if terminal_height < 30 {
-            6
-        } else if terminal_height > 50 {
-            12
-        } else {
-            10
-        };
-        
-        let fixed_lines = 3 + 3 + metrics_height + 3 + 2 + 3; // Total fixed UI
-        let visible_height = terminal_height.saturating_sub(fixed_lines) as usize;
-        let visible_height = visible_height.max(5); // At least 5 lines
+        let visible_height = self.get_visible_height(terminal_height).max(5); // At least 5 lines
         
         // Ensure scroll offset doesn't go beyond valid range
         if !self.all_logs.is_empty() {
@@ -166,17 +989,79 @@ impl AppState {
     }
     
     pub fn get_visible_height(&self, terminal_height: u16) -> usize {
-        let metrics_height = if terminal_height < 30 {
-            6
-        } else if terminal_height > 50 {
-            12
-        } else {
-            10
-        };
+        if self.zoomed_panel() == Some(ActivePanel::Logs) {
+            // The panel is the whole terminal: just its borders and header
+            return terminal_height.saturating_sub(3) as usize;
+        }
+        if let Some(cell) = self.grid_cell(CellContent::Logs, terminal_height) {
+            // Borders and the column header
+            return cell.height.saturating_sub(3) as usize;
+        }
+        let metrics_height = metrics_area_height(terminal_height);
         
         let fixed_lines = 3 + 3 + metrics_height + 3 + 2 + 3;
         terminal_height.saturating_sub(fixed_lines) as usize
     }
+
+    /// Where `content` goes in the `[layout]` grid at `terminal_height`,
+    /// following draw_ui; None without a grid or a cell for it.
+    fn grid_cell(&self, content: CellContent, terminal_height: u16) -> Option<Rect> {
+        if self.zoomed_panel().is_some() || !self.layout.is_grid() {
+            return None;
+        }
+        // Inside the margin, below the header and endpoints, above the footer
+        let area = Rect::new(1, 7, self.last_terminal_width.saturating_sub(2), terminal_height.saturating_sub(2 + 3 + 3 + 3));
+        self.layout.area_of(content, area)
+    }
+
+    /// URI rows the metrics table shows at `terminal_height`, following the
+    /// layout of draw_ui and draw_metrics_compact.
+    pub fn metrics_visible_rows(&self, terminal_height: u16) -> usize {
+        let area_height = if self.zoomed_panel() == Some(ActivePanel::Metrics) {
+            terminal_height
+        } else if let Some(cell) = self.grid_cell(CellContent::Metrics, terminal_height) {
+            // The cell sets the size rather than sharing with the logs
+            return usize::from(cell.height).saturating_sub(2 + 3).max(1);
+        } else if self.logs_enabled() {
+            metrics_area_height(terminal_height)
+        } else {
+            // Margin, header, endpoints and footer; the logs area is ours
+            terminal_height.saturating_sub(2 + 3 + 3 + 3)
+        };
+        // Borders, column header, blank line and totals
+        let rows = usize::from(area_height).saturating_sub(2 + 3);
+        metrics_row_cap(self).min(rows).max(1)
+    }
+}
+
+/// Index of the line in `logs` (oldest first) closest to `t` (unix seconds).
+pub fn nearest_log(logs: &[LogEntry], t: f64) -> Option<usize> {
+    let nanos = (t * 1e9) as i64;
+    let after = logs.partition_point(|log| log.nanos < nanos);
+    [after.checked_sub(1), (after < logs.len()).then_some(after)]
+        .into_iter()
+        .flatten()
+        .min_by_key(|&idx| (logs[idx].nanos - nanos).abs())
+}
+
+/// Smallest drill-in area that also fits the strip of logs at the cursor.
+const CURSOR_LOGS_MIN_AREA: u16 = 24;
+
+/// Height of the metrics panel when it shares the screen with the logs.
+fn metrics_area_height(terminal_height: u16) -> u16 {
+    if terminal_height < 30 {
+        6  // Smaller metrics area for small terminals
+    } else if terminal_height > 50 {
+        12 // Larger metrics area for big terminals
+    } else {
+        10 // Default
+    }
+}
+
+/// Most URI rows shown at once; capped while the logs panel shares the
+/// screen, unless a `[layout]` grid sizes the metrics cell.
+fn metrics_row_cap(state: &AppState) -> usize {
+    if state.logs_enabled() && state.zoomed_panel().is_none() && !state.layout.is_grid() { 10 } else { usize::MAX }
 }
 
 pub fn draw_ui(frame: &mut Frame, state: &AppState) {
@@ -188,14 +1073,20 @@ pub fn draw_ui(frame: &mut Frame, state: &AppState) {
         return;
     }
     
+    // A zoomed panel takes the whole terminal unless a full-area view is open
+    let full_area_view = state.drill_in.is_some() || state.service_map.is_some() || state.panels_page.is_some();
+    if let Some(panel) = state.zoomed_panel().filter(|_| !full_area_view) {
+        if panel == ActivePanel::Metrics {
+            draw_metrics_compact(frame, size, state, size);
+        } else {
+            draw_logs_wide(frame, size, state, size);
+        }
+        draw_popups(frame, size, state);
+        return;
+    }
+    
     // Adjust layout based on terminal size
-    let metrics_height = if size.height < 30 {
-        6  // Smaller metrics area for small terminals
-    } else if size.height > 50 {
-        12 // Larger metrics area for big terminals
-    } else {
-        10 // Default
-    };
+    let metrics_height = metrics_area_height(size.height);
     
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -210,549 +1101,3196 @@ pub fn draw_ui(frame: &mut Frame, state: &AppState) {
         .split(frame.area());
 
     draw_header(frame, chunks[0], state);
-    draw_endpoints(frame, chunks[1], state);
-    draw_metrics_compact(frame, chunks[2], state, size);
-    draw_logs_wide(frame, chunks[3], state, size);
+    let alerts = state.nagging_alerts();
+    if !alerts.is_empty() {
+        // Alerts outrank the endpoint details until the stream speaks again
+        // or the check passes, or they are acknowledged
+        draw_alert_banner(frame, chunks[1], state, &alerts);
+    } else {
+        draw_endpoints(frame, chunks[1], state);
+    }
+    if let Some(drill_in) = &state.drill_in {
+        // The drill-in chart takes over the metrics and logs area, keeping a
+        // strip of the logs around the time cursor when there is room
+        let area = chunks[2].union(chunks[3]);
+        if state.logs_enabled() && area.height >= CURSOR_LOGS_MIN_AREA {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(16),   // History chart
+                    Constraint::Length(8), // Logs around the cursor
+                ])
+                .split(area);
+            draw_drill_in(frame, rows[0], state, drill_in);
+            draw_cursor_logs(frame, rows[1], state);
+        } else {
+            draw_drill_in(frame, area, state, drill_in);
+        }
+    } else if let Some(service_map) = &state.service_map {
+        draw_service_map(frame, chunks[2].union(chunks[3]), state, service_map);
+    } else if let Some(page) = &state.panels_page {
+        draw_panels_page(frame, chunks[2].union(chunks[3]), state, page);
+    } else if let Some(view) = &state.system_view {
+        draw_system(frame, chunks[2].union(chunks[3]), view);
+    } else if state.layout.is_grid() {
+        draw_grid(frame, chunks[2].union(chunks[3]), state, size);
+    } else if !state.logs_enabled() {
+        // Without a backend the panel is dropped and the other takes its space
+        draw_metrics_compact(frame, chunks[2].union(chunks[3]), state, size);
+    } else if !state.prometheus_enabled {
+        draw_logs_wide(frame, chunks[2].union(chunks[3]), state, size);
+    } else {
+        draw_metrics_compact(frame, chunks[2], state, size);
+        draw_logs_wide(frame, chunks[3], state, size);
+    }
     draw_footer(frame, chunks[4], state);
+    draw_popups(frame, size, state);
 }
 
-fn draw_size_warning(frame: &mut Frame, size: Rect) {
-    let warning = Paragraph::new(vec![
-        Line::from(""),
-        Line::from(Span::styled(
-            "Terminal Too Small",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-        )),
-        Line::from(""),
-        Line::from(format!("Current: {}x{}", size.width, size.height)),
-        Line::from("Required: 80x24 minimum"),
-        Line::from(""),
-        Line::from("Please resize your terminal window"),
-    ])
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Red))
-            .title(" Warning "),
-    )
-    .alignment(Alignment::Center);
-    
-    frame.render_widget(warning, size);
+/// The main area laid out as the `[layout]` grid. Cells of a backend that
+/// isn't configured stay empty.
+fn draw_grid(frame: &mut Frame, area: Rect, state: &AppState, size: Rect) {
+    for (content, rect) in state.layout.areas(area) {
+        if rect.width < 3 || rect.height < 3 {
+            continue;
+        }
+        match content {
+            CellContent::Metrics if state.prometheus_enabled => draw_metrics_compact(frame, rect, state, size),
+            CellContent::Logs if state.logs_enabled() => draw_logs_wide(frame, rect, state, size),
+            CellContent::Panel(idx) => draw_panel_cell(frame, rect, state, idx),
+            CellContent::Metrics | CellContent::Logs => {}
+        }
+    }
 }
 
-fn draw_header(frame: &mut Frame, area: Rect, state: &AppState) {
-    let header = Paragraph::new(vec![Line::from(vec![
-        Span::raw(" "),
-        Span::styled(
-            "RustDash",
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::raw(" | "),
-        Span::styled(
-            format!("Fetch: {}", state.last_fetch),
-            Style::default().fg(Color::Yellow),
-        ),
-        Span::raw(" | "),
-        Span::styled(
-            format!("Update: {}", state.last_update),
-            Style::default().fg(Color::Gray),
-        ),
-    ])])
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_type(ratatui::widgets::BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Cyan)),
-    )
-    .alignment(Alignment::Center);
+/// One panel of the panels page in a grid cell, titled like it.
+fn draw_panel_cell(frame: &mut Frame, area: Rect, state: &AppState, idx: usize) {
+    let block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::DarkGray));
+    let inner = block.inner(area);
+    let Some(panel) = state.panels.get(idx) else {
+        frame.render_widget(block.title(format!(" Panel {} ", idx)), area);
+        let text = if state.metrics_last_success.is_some() { "No such panel" } else { "Loading…" };
+        frame.render_widget(Paragraph::new(text).style(Style::default().fg(Color::Gray)), inner);
+        return;
+    };
+    frame.render_widget(block.title(format!(" {} ", panel.title)), area);
+    // Nothing selected; the block's title stands in for the panel's own
+    let page = PanelsPage { selected: usize::MAX, ..PanelsPage::default() };
+    let (lines, gauges) = panel_lines(state, &page, idx, usize::from(inner.width));
+    frame.render_widget(Paragraph::new(lines.into_iter().skip(1).collect::<Vec<_>>()), inner);
+    for GaugeBar { line, width, ratio, color, label, .. } in gauges {
+        let Some(y) = line.checked_sub(1).map(|row| inner.y + row as u16).filter(|y| *y < inner.bottom()) else {
+            continue;
+        };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(color).bg(Color::DarkGray))
+            .use_unicode(true)
+            .ratio(ratio)
+            .label(label);
+        frame.render_widget(gauge, Rect::new(inner.x + 2, y, width as u16, 1));
+    }
+}
 
-    frame.render_widget(header, area);
+/// Overlays drawn over whatever layout is showing, the one opened last on top.
+fn draw_popups(frame: &mut Frame, size: Rect, state: &AppState) {
+    for kind in state.modals.arrange(&state.open_modals()) {
+        match kind {
+            ModalKind::Inspection => {
+                if let Some(inspection) = &state.inspection {
+                    draw_inspection(frame, size, state, inspection);
+                }
+            }
+            ModalKind::AdminMenu => {
+                if let Some(menu) = &state.admin_menu {
+                    draw_admin_menu(frame, size, state, menu);
+                }
+            }
+            ModalKind::LogActionMenu => {
+                if let Some(menu) = &state.log_action_menu {
+                    draw_log_action_menu(frame, size, state, menu);
+                }
+            }
+            ModalKind::LogDiff => {
+                if let Some(pair) = state.log_diff_pair() {
+                    draw_log_diff(frame, size, state, pair);
+                }
+            }
+            ModalKind::About => {
+                if let Some(about) = &state.about {
+                    draw_about(frame, size, state, about);
+                }
+            }
+            ModalKind::QueryStats => {
+                if let Some(popup) = &state.query_stats_popup {
+                    draw_query_stats(frame, size, state, popup);
+                }
+            }
+            ModalKind::Audit => {
+                if let Some(popup) = &state.audit_popup {
+                    draw_audit(frame, size, state, popup);
+                }
+            }
+            ModalKind::Debug => {
+                if let Some(popup) = &state.debug_popup {
+                    draw_debug(frame, size, state, popup);
+                }
+            }
+            ModalKind::Variables => {
+                if let Some(picker) = &state.variable_picker {
+                    draw_variables(frame, size, state, picker);
+                }
+            }
+            ModalKind::SavedViews => {
+                if let Some(popup) = &state.saved_views_popup {
+                    draw_saved_views(frame, size, state, popup);
+                }
+            }
+            ModalKind::Checks => draw_checks(frame, size, state),
+            ModalKind::Ping => draw_ping(frame, size, state),
+            ModalKind::Changes => draw_changes(frame, size, state),
+            ModalKind::Slos => draw_slos(frame, size, state),
+            ModalKind::QueryEditor => {
+                if let Some(editor) = &state.query_editor {
+                    draw_query_editor(frame, size, editor);
+                }
+            }
+            ModalKind::LogQueryBar => {
+                if let Some(bar) = &state.log_query_bar {
+                    draw_log_query_bar(frame, size, bar);
+                }
+            }
+            ModalKind::Goto => {
+                if let Some(prompt) = &state.goto_prompt {
+                    draw_goto_prompt(frame, size, state, prompt);
+                }
+            }
+            ModalKind::NotesPad => {
+                if let Some(pad) = &state.notes_pad {
+                    draw_notes_pad(frame, size, state, pad);
+                }
+            }
+            ModalKind::Triage => {
+                if let Some(popup) = &state.triage_popup {
+                    draw_triage(frame, size, state, popup);
+                }
+            }
+            ModalKind::Palette => {
+                if let Some(palette) = &state.palette {
+                    draw_palette(frame, size, palette);
+                }
+            }
+        }
+    }
 }
 
-fn draw_endpoints(frame: &mut Frame, area: Rect, state: &AppState) {
-    let endpoints = Paragraph::new(vec![Line::from(vec![
-        Span::styled("Prometheus: ", Style::default().fg(Color::Yellow)),
-        Span::raw(&state.prometheus_url),
-        Span::raw(" | "),
-        Span::styled("Loki: ", Style::default().fg(Color::Magenta)),
-        Span::raw(&state.loki_url),
-    ])])
-    .block(
+/// The command palette near the top: the search input and the best matches.
+fn draw_palette(frame: &mut Frame, size: Rect, palette: &Palette) {
+    let width = size.width.saturating_sub(10).min(90);
+    let height = (VISIBLE_ENTRIES as u16 + 3).min(size.height.saturating_sub(4));
+    let area = modal::near_top(size, width, height, 2);
+    let gray = Style::default().fg(Color::Gray);
+
+    let (before, after) = palette.input.text.split_at(palette.input.cursor);
+    let mut under_cursor = after.chars();
+    let cursor_cell = under_cursor.next().map_or(" ".to_string(), String::from);
+    let mut lines = vec![Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::raw(before.to_string()),
+        Span::styled(cursor_cell, Style::default().add_modifier(Modifier::REVERSED)),
+        Span::raw(under_cursor.as_str().to_string()),
+    ])];
+
+    let inner_width = usize::from(width.saturating_sub(2));
+    for (row, (idx, matched)) in palette.matches.iter().enumerate().skip(palette.scroll_offset).take(VISIBLE_ENTRIES) {
+        let entry = &palette.entries[*idx];
+        let base = if row == palette.selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+        let hint = format!(" {} {:>6}", entry.hint, entry.kind);
+        let label_width = inner_width.saturating_sub(text::width(&hint) + 2).max(10);
+        let label = text::truncate(&entry.label, label_width, Truncation::End);
+        let mut spans = vec![Span::styled("  ", base)];
+        for (i, c) in label.chars().enumerate() {
+            let style = if matched.positions.contains(&i) { base.fg(Color::Yellow).add_modifier(Modifier::BOLD) } else { base };
+            spans.push(Span::styled(c.to_string(), style));
+        }
+        let padding = label_width.saturating_sub(text::width(&label));
+        let hint = text::truncate(&hint, inner_width.saturating_sub(label_width + 2), Truncation::Middle);
+        spans.push(Span::styled(format!("{}{}", " ".repeat(padding), hint), base.fg(Color::Gray)));
+        lines.push(Line::from(spans));
+    }
+    if palette.matches.is_empty() {
+        lines.push(Line::from(Span::styled("  Nothing matches", gray)));
+    }
+
+    let title = format!(" Commands ({}/{}) ", palette.matches.len(), palette.entries.len());
+    let popup = Paragraph::new(lines).block(
         Block::default()
+            .title(title)
+            .title_bottom(Line::from(Span::styled(" Enter: run  ↑/↓: select  ESC: close ", gray)))
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Gray)),
-    )
-    .alignment(Alignment::Center);
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
 
-    frame.render_widget(endpoints, area);
+/// Firing alerts and triaged log patterns with their triage state.
+fn draw_triage(frame: &mut Frame, size: Rect, state: &AppState, popup: &TriagePopup) {
+    let alerts = state.firing_alerts();
+    let width = size.width.saturating_sub(10).min(110);
+    let height = ((alerts.len() + state.triage.patterns.len()) as u16 + 6).max(8).min(size.height.saturating_sub(4));
+    let area = modal::centered(size, width, height);
+    let gray = Style::default().fg(Color::Gray);
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let row = |idx: usize, label: String, triage: Option<TriageState>| {
+        let base = if idx == popup.selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+        let (marker, color) = match triage {
+            None => ("new", Color::Red),
+            Some(TriageState::Acknowledged) => ("acknowledged", Color::Green),
+            Some(TriageState::Investigating) => ("investigating", Color::Yellow),
+            Some(TriageState::Ignored) => ("ignored", Color::DarkGray),
+        };
+        Line::from(vec![Span::styled(format!("  {:<14}", marker), base.fg(color)), Span::styled(label, base)])
+    };
+
+    let mut lines = vec![Line::styled("Alerts", heading)];
+    if alerts.is_empty() {
+        lines.push(Line::styled("  None firing", gray));
+    }
+    for (idx, (alert, since, reason)) in alerts.iter().enumerate() {
+        let label = format!("{}: {} (since {})", alert, reason, since.format("%H:%M:%S"));
+        lines.push(row(idx, label, state.triage.alert_state(alert, *since)));
+    }
+    lines.push(Line::styled("Log patterns", heading));
+    if state.triage.patterns.is_empty() {
+        lines.push(Line::styled("  None triaged (a on a log line)", gray));
+    }
+    for (idx, pattern) in state.triage.patterns.iter().enumerate() {
+        lines.push(row(alerts.len() + idx, pattern.pattern.clone(), Some(pattern.state)));
+    }
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Triage ")
+            .title_bottom(Line::styled(" a: acknowledge  i: investigating  x: ignore  Backspace: clear  T/ESC: close ", gray))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
 }
 
-fn draw_metrics_compact(frame: &mut Frame, area: Rect, state: &AppState, _terminal_size: Rect) {
-    // Calculate time range based on current setting
-    let now = chrono::Local::now();
-    let time_range_display = if let Some(minutes) = state.metrics_time_range.as_minutes() {
-        let from_time = (now - chrono::TimeDelta::try_minutes(minutes).unwrap()).format("%H:%M:%S");
-        let to_time = now.format("%H:%M:%S");
-        format!("[{}] ({} → {})", state.metrics_time_range.as_str(), from_time, to_time)
-    } else {
-        format!("[{}] (All time)", state.metrics_time_range.as_str())
+/// The template variables with their selections, and the values of the one
+/// being picked.
+fn draw_variables(frame: &mut Frame, size: Rect, state: &AppState, picker: &VariablePicker) {
+    let width = size.width.saturating_sub(10).min(80);
+    let height = size.height.saturating_sub(4).min(20);
+    let area = modal::centered(size, width, height);
+    let gray = Style::default().fg(Color::Gray);
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+    let mut lines = Vec::new();
+    if state.variables.is_empty() {
+        lines.push(Line::styled("No variables configured", gray));
+        lines.push(Line::styled("Add [[variables]] entries to config.toml, e.g. name = \"namespace\", label = \"namespace\"", gray));
+    }
+    for (idx, variable) in state.variables.iter().enumerate() {
+        let style = if idx == picker.variable { heading } else { Style::default() };
+        let marker = if idx == picker.variable { "▶ " } else { "  " };
+        lines.push(Line::styled(format!("{}${} = {}", marker, variable.config.name, variable.display()), style));
+    }
+    if let Some(variable) = state.variables.get(picker.variable) {
+        lines.push(Line::from(""));
+        // Upstream selections filled in, as the lookup ran
+        let upstream = &state.variables[..picker.variable];
+        let source = variable.config.metric.as_deref().map_or(String::new(), |metric| format!(" on {}", variables::substitute(metric, upstream)));
+        lines.push(Line::styled(format!("Values of {}{}", variable.config.label, source), heading));
+        if let Some(error) = &variable.error {
+            lines.push(Line::styled(format!("  {}", error), Style::default().fg(Color::Red)));
+        }
+        let choices = variable.choices();
+        if choices.is_empty() && variable.error.is_none() {
+            lines.push(Line::styled("  None found yet", gray));
+        }
+        // Keep the cursor in view below the variables
+        let room = usize::from(height.saturating_sub(2)).saturating_sub(lines.len()).max(1);
+        let start = picker.selected.saturating_sub(room - 1);
+        for (idx, choice) in choices.iter().enumerate().skip(start).take(room) {
+            let style = if idx == picker.selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+            let current = if *choice == variable.selected.as_deref() { "● " } else { "  " };
+            lines.push(Line::styled(format!("  {}{}", current, choice.unwrap_or("All")), style));
+        }
+    }
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Variables ")
+            .title_bottom(Line::styled(" ←/→: variable  ↑/↓: value  Enter: select  $/ESC: close ", gray))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Saved views, each with what it shows, and the name being typed to save
+/// the current view under.
+fn draw_saved_views(frame: &mut Frame, size: Rect, state: &AppState, popup: &SavedViewsPopup) {
+    let width = size.width.saturating_sub(10).min(100);
+    let height = size.height.saturating_sub(4).min(20);
+    let area = modal::centered(size, width, height);
+    let gray = Style::default().fg(Color::Gray);
+    let name_width = state.saved_views.iter().map(|view| text::width(&view.name)).max().unwrap_or(0).min(24);
+
+    let mut lines = Vec::new();
+    if state.saved_views.is_empty() {
+        lines.push(Line::styled("No saved views: press s to save what shows now", gray));
+    }
+    // Keep the selection in view, leaving a line for the name input
+    let room = usize::from(height.saturating_sub(3)).max(1);
+    let start = popup.selected.saturating_sub(room - 1);
+    for (idx, view) in state.saved_views.iter().enumerate().skip(start).take(room) {
+        let style = if idx == popup.selected && popup.naming.is_none() { Style::default().bg(Color::DarkGray) } else { Style::default() };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {} ", text::fit(&view.name, name_width, Truncation::End)), style.add_modifier(Modifier::BOLD)),
+            Span::styled(format!(" {}", view.summary()), style.fg(Color::Gray)),
+        ]));
+    }
+    let hint = match &popup.naming {
+        Some(input) => {
+            let (before, after) = input.text.split_at(input.cursor);
+            let mut under_cursor = after.chars();
+            let cursor_cell = under_cursor.next().map_or(" ".to_string(), String::from);
+            lines.push(Line::from(vec![
+                Span::styled("Save as: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(before.to_string()),
+                Span::styled(cursor_cell, Style::default().add_modifier(Modifier::REVERSED)),
+                Span::raw(under_cursor.as_str().to_string()),
+            ]));
+            " Enter: save  ESC: cancel "
+        }
+        None => " Enter: show  s: save current  x: delete  ↑/↓: select  V/ESC: close ",
     };
-    
-    let (border_color, base_title, help_text) = match state.active_panel {
-        ActivePanel::Metrics => (
-            Color::Cyan,
-            "API Response Times",
-            " [↑/↓: scroll, ←/→: time range, ESC: exit] "
-        ),
-        ActivePanel::None => (
-            Color::Gray,
-            "API Response Times",
-            " [TAB to activate] "
-        ),
-        _ => (
-            Color::Yellow,
-            "API Response Times",
-            " [TAB to switch here] "
-        ),
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" Saved views ({}) ", state.saved_views.len()))
+            .title_bottom(Line::styled(hint, gray))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The exact values of the drill-in chart's cursor point or the selected
+/// metrics row, along the bottom, with their queries and the series behind them.
+fn draw_inspection(frame: &mut Frame, size: Rect, state: &AppState, inspection: &Inspection) {
+    let gray = Style::default().fg(Color::Gray);
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let recorded = || vec![Line::styled("From the metric history recorded by rustdash", gray)];
+    let queries = |queries: &[String]| queries.iter().map(|query| Line::styled(format!("query: {}", query), gray)).collect::<Vec<_>>();
+
+    let point = state.drill_in.as_ref().and_then(|drill_in| {
+        let history = drill_in.history.as_ref()?;
+        let &(t, latency) = history.latency_ms.get(drill_in.cursor_index()?)?;
+        let requests = history.requests_per_min.iter().find(|(rt, _)| *rt == t).map(|(_, v)| *v);
+        Some((t, latency, requests, &history.queries))
+    });
+    let mut lines = match point {
+        Some((t, latency, requests, history_queries)) => {
+            let requests = requests.map_or("-".to_string(), |v| v.to_string());
+            let mut lines = vec![Line::styled(
+                format!("{} at {}  latency {} ms  {} req/min", inspection.uri, format_time(t, "%Y-%m-%d %H:%M:%S"), latency, requests),
+                heading,
+            )];
+            lines.extend(if history_queries.is_empty() { recorded() } else { queries(history_queries) });
+            lines
+        }
+        None => {
+            let metric = state.metrics.as_ref().and_then(|metrics| metrics.uri_metrics.iter().find(|metric| metric.uri == inspection.uri));
+            let Some(metric) = metric else { return };
+            let at = state.metrics_last_success.map_or("-".to_string(), |at| at.format("%Y-%m-%d %H:%M:%S").to_string());
+            let mut lines = vec![Line::styled(
+                format!("{} at {}  latency {} ms  {} req/min", metric.uri, at, metric.avg_duration_ms, metric.request_count),
+                heading,
+            )];
+            lines.extend(match state.metrics_time_range.as_minutes() {
+                Some(_) => queries(&prometheus::uri_metric_queries(&state.metrics_time_range.to_prometheus_range())),
+                None => recorded(),
+            });
+            lines
+        }
     };
-    
-    let title = format!(" {} {} {} ", base_title, time_range_display, help_text);
-    
-    let metrics_block = Block::default()
-        .title(title)
+
+    match (&inspection.series, &inspection.error) {
+        (_, Some(error)) => lines.push(Line::styled(format!("Series lookup failed: {}", error), Style::default().fg(Color::Red))),
+        (None, None) => lines.push(Line::styled("Looking up the series behind it...", Style::default().fg(Color::Yellow))),
+        (Some(series), None) => {
+            let at = series.first().map_or(String::new(), |first| format!(" at {}", format_time(first.at, "%H:%M:%S")));
+            lines.push(Line::styled(format!("{} series{}:", series.len(), at), heading));
+            for value in series {
+                let latency = value.latency_ms.map_or("-".to_string(), |latency| format!("{:.3} ms", latency));
+                lines.push(Line::from(vec![
+                    Span::raw(format!("  {:>10.3} req/min  {:>12}  ", value.requests_per_min, latency)),
+                    Span::styled(inspect::format_labels(&value.labels), gray),
+                ]));
+            }
+        }
+    }
+
+    let width = size.width.saturating_sub(4);
+    let height = (lines.len() as u16 + 2).min(size.height / 2);
+    lines.truncate(usize::from(height.saturating_sub(2)));
+    let area = Rect::new(size.x + 2, size.bottom().saturating_sub(height + 1), width, height);
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Inspect ")
+            .title_bottom(Line::styled(" v: close ", gray))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The notes taken so far, newest at the bottom, above the input for the next one.
+fn draw_notes_pad(frame: &mut Frame, size: Rect, state: &AppState, pad: &NotesPad) {
+    let width = size.width.saturating_sub(10).min(110);
+    let height = size.height.saturating_sub(6).min(24);
+    let area = modal::centered(size, width, height);
+    let gray = Style::default().fg(Color::Gray);
+
+    let mut lines = Vec::new();
+    if state.notes.is_empty() {
+        lines.push(Line::styled("No notes yet: type one and press Enter", gray));
+    }
+    for note in &state.notes[..state.notes.len().saturating_sub(pad.scroll_offset)] {
+        lines.push(Line::from(vec![
+            Span::styled(note.at.format("%H:%M:%S ").to_string(), Style::default().fg(Color::Cyan)),
+            Span::raw(note.text.clone()),
+        ]));
+        if !note.context.is_empty() {
+            lines.push(Line::styled(format!("         {}", note.context.join(" · ")), Style::default().fg(Color::DarkGray)));
+        }
+    }
+    // Keep the newest shown notes in view above the input
+    let room = usize::from(height.saturating_sub(4));
+    let lines: Vec<Line> = lines.split_off(lines.len().saturating_sub(room));
+
+    let (before, after) = pad.input.text.split_at(pad.input.cursor);
+    let mut under_cursor = after.chars();
+    let cursor_cell = under_cursor.next().map_or(" ".to_string(), String::from);
+    let input = Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::raw(before.to_string()),
+        Span::styled(cursor_cell, Style::default().add_modifier(Modifier::REVERSED)),
+        Span::raw(under_cursor.as_str().to_string()),
+    ]);
+
+    let block = Block::default()
+        .title(format!(" Incident notes ({}) ", state.notes.len()))
+        .title_bottom(Line::styled(" Enter: add  Ctrl+S: export Markdown  ↑/↓: scroll  ESC: close ", gray))
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(border_color));
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+    frame.render_widget(Paragraph::new(lines), rows[0]);
+    frame.render_widget(Paragraph::new(input), rows[1]);
+}
 
-    // Draw the block first
-    frame.render_widget(metrics_block, area);
-    
-    // Create inner area for content
-    let inner = ratatui::layout::Rect {
-        x: area.x + 1,
-        y: area.y + 1,
-        width: area.width.saturating_sub(2),
-        height: area.height.saturating_sub(2),
+/// The LogQL query bar along the bottom, over the footer, with the error
+/// of the last submit under the input.
+fn draw_log_query_bar(frame: &mut Frame, size: Rect, bar: &LogQueryBar) {
+    let area = Rect::new(size.x + 1, size.bottom().saturating_sub(5), size.width.saturating_sub(2), 4);
+    let input = &bar.input;
+    let error_at = bar.error.as_ref().and_then(|e| e.at.clone());
+
+    // Color each char by its token, then mark the error and the cursor
+    let tokens = log_query::tokenize(&input.text);
+    let mut spans = vec![Span::styled("/ ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))];
+    for (i, c) in input.text.char_indices() {
+        let kind = tokens.iter().find(|token| (token.start..token.end).contains(&i)).map(|token| token.kind);
+        let mut style = match kind {
+            Some(TokenKind::Name) => Style::default().fg(Color::Cyan),
+            Some(TokenKind::Keyword) => Style::default().fg(Color::Magenta),
+            Some(TokenKind::Operator) => Style::default().fg(Color::Yellow),
+            Some(TokenKind::String) => Style::default().fg(Color::Green),
+            Some(TokenKind::Number) => Style::default().fg(Color::Blue),
+            Some(TokenKind::Unclosed | TokenKind::Invalid) => Style::default().fg(Color::Red),
+            Some(TokenKind::Punct) | None => Style::default(),
+        };
+        if error_at.as_ref().is_some_and(|at| at.contains(&i)) {
+            style = style.fg(Color::Red).add_modifier(Modifier::UNDERLINED);
+        }
+        if i == input.cursor {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        spans.push(Span::styled(c.to_string(), style));
+    }
+    if input.cursor == input.text.len() {
+        // Past the end, or where an error at the end of the query points
+        let style = if error_at.is_some_and(|at| at.start == input.text.len()) { Style::default().bg(Color::Red) } else { Style::default() };
+        spans.push(Span::styled(" ", style.add_modifier(Modifier::REVERSED)));
+    }
+
+    let status = match (&bar.error, &bar.submitted) {
+        (Some(e), _) => Span::styled(format!("✗ {}", e), Style::default().fg(Color::Red)),
+        (None, Some(_)) => Span::styled("Checking with Loki...", Style::default().fg(Color::Yellow)),
+        (None, None) => Span::styled("Enter: apply (empty clears the query)  ESC: close", Style::default().fg(Color::Gray)),
     };
-    
-    if state.metrics_loading {
-        // Show loading indicator
-        let loading_text = vec![
-            Line::from(""),
-            Line::from(vec![
-                Span::styled(
-                    "⏳ Loading metrics...",
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
-                ),
-            ]),
-            Line::from(""),
-            Line::from(vec![
+    let popup = Paragraph::new(vec![Line::from(spans), Line::from(status)]).block(
+        Block::default()
+            .title(" LogQL ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The go-to prompt along the bottom, with why the last jump failed.
+fn draw_goto_prompt(frame: &mut Frame, size: Rect, state: &AppState, prompt: &GotoPrompt) {
+    let area = Rect::new(size.x + 1, size.bottom().saturating_sub(5), size.width.saturating_sub(2), 4);
+    let (before, after) = prompt.input.text.split_at(prompt.input.cursor);
+    let mut under_cursor = after.chars();
+    let cursor_cell = under_cursor.next().map_or(" ".to_string(), String::from);
+    let input = Line::from(vec![
+        Span::styled(": ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::raw(before.to_string()),
+        Span::styled(cursor_cell, Style::default().add_modifier(Modifier::REVERSED)),
+        Span::raw(under_cursor.as_str().to_string()),
+    ]);
+    let status = match &prompt.error {
+        Some(e) => Span::styled(format!("✗ {}", e), Style::default().fg(Color::Red)),
+        None => Span::styled(
+            format!("Line 1-{} or HH:MM[:SS]  Enter: go  ESC: close", state.all_logs.len()),
+            Style::default().fg(Color::Gray),
+        ),
+    };
+    let popup = Paragraph::new(vec![input, Line::from(status)]).block(
+        Block::default()
+            .title(" Go to line or time ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup with the PromQL input, its completions and the last result.
+fn draw_query_editor(frame: &mut Frame, size: Rect, editor: &QueryEditor) {
+    let width = size.width.saturating_sub(6);
+    let height = size.height.saturating_sub(4);
+    let area = modal::centered(size, width, height);
+    let gray = Style::default().fg(Color::Gray);
+
+    // The cursor is the reversed cell after the text before it
+    let (before, after) = editor.input.text.split_at(editor.input.cursor);
+    let mut under_cursor = after.chars();
+    let cursor_cell = under_cursor.next().map_or(" ".to_string(), String::from);
+    let mut lines = vec![Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::raw(before.to_string()),
+        Span::styled(cursor_cell, Style::default().add_modifier(Modifier::REVERSED)),
+        Span::raw(under_cursor.as_str().to_string()),
+    ])];
+
+    if let (false, Some(context)) = (editor.dismissed, &editor.context) {
+        let kind = |candidate: &str| match &context.completing {
+            Completing::Metric if FUNCTIONS.contains(&candidate) => "function",
+            Completing::Metric => "metric",
+            Completing::LabelName { .. } => "label",
+            Completing::LabelValue { .. } => "value",
+        };
+        for (idx, (candidate, matched)) in editor.completions.iter().enumerate() {
+            let base = if idx == editor.selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+            let mut spans = vec![Span::styled("  ", base)];
+            for (i, c) in candidate.chars().enumerate() {
+                let style = if matched.positions.contains(&i) {
+                    base.fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    base
+                };
+                spans.push(Span::styled(c.to_string(), style));
+            }
+            spans.push(Span::styled(format!("  {}", kind(candidate)), base.fg(Color::Gray)));
+            lines.push(Line::from(spans));
+        }
+        if editor.completions.is_empty() && !editor.catalog.pending.is_empty() {
+            lines.push(Line::from(Span::styled("  Looking up completions...", gray)));
+        }
+    }
+    if let Some(error) = &editor.catalog.error {
+        lines.push(Line::from(Span::styled(format!("  Completions unavailable: {}", error), Style::default().fg(Color::Red))));
+    }
+    lines.push(Line::from(""));
+
+    let body_rows = usize::from(height.saturating_sub(2)).saturating_sub(lines.len() + 1);
+    match &editor.result {
+        _ if editor.running => lines.push(Line::from(Span::styled("Running...", Style::default().fg(Color::Yellow)))),
+        Some(Ok(rows)) if rows.is_empty() => lines.push(Line::from(Span::styled("No series", gray))),
+        Some(Ok(rows)) => {
+            let value_width = rows.iter().map(|row| text::width(&row.value)).max().unwrap_or(0).min(24);
+            let series_width = usize::from(width.saturating_sub(4)).saturating_sub(value_width + 2);
+            let start = editor.scroll_offset.min(rows.len().saturating_sub(1));
+            for row in rows.iter().skip(start).take(body_rows) {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:>width$}  ", text::truncate(&row.value, value_width, Truncation::End), width = value_width), Style::default().fg(Color::Green)),
+                    Span::raw(text::truncate(&row.series, series_width, Truncation::Middle)),
+                ]));
+            }
+        }
+        Some(Err(error)) => lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red)))),
+        None => lines.push(Line::from(Span::styled("Enter runs the query at the current time", gray))),
+    }
+    lines.push(Line::from(Span::styled(
+        "Enter: run  Tab: complete  ↑/↓: pick completion / scroll  Ctrl+Space: suggest  ESC: close",
+        gray,
+    )));
+
+    let title = match &editor.result {
+        Some(Ok(rows)) => format!(" PromQL ({} series) ", rows.len()),
+        _ => " PromQL ".to_string(),
+    };
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup listing the latest audited actions, newest first.
+fn draw_audit(frame: &mut Frame, size: Rect, state: &AppState, popup: &AuditPopup) {
+    let width = size.width.saturating_sub(6);
+    let height = size.height.saturating_sub(4);
+    let area = modal::centered(size, width, height);
+
+    let details_width = usize::from(width.saturating_sub(2)).saturating_sub(61).max(10);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{:<21}{:<12}{:<28}{}", "Time", "User", "Action", "Details"),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    ))];
+    if state.audit_log.is_empty() {
+        lines.push(Line::from(Span::styled("No actions recorded yet", Style::default().fg(Color::Gray))));
+    }
+    let body_rows = usize::from(height.saturating_sub(4));
+    let start = popup.scroll_offset.min(state.audit_log.len().saturating_sub(1));
+    for entry in state.audit_log.recent().skip(start).take(body_rows) {
+        let time = DateTime::parse_from_rfc3339(&entry.time)
+            .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|_| entry.time.clone());
+        let mut details: Vec<String> = entry.params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        if !entry.target.is_empty() {
+            details.push(format!("on {}", entry.target));
+        }
+        match (entry.ok, entry.result.is_empty()) {
+            (true, true) => {}
+            (true, false) => details.push(format!("- {}", entry.result)),
+            (false, _) => details.push(format!("- FAILED: {}", entry.result)),
+        }
+        let style = if entry.ok { Style::default() } else { Style::default().fg(Color::Red) };
+        lines.push(Line::styled(format!(
+            "{:<21}{:<12}{:<28}{}",
+            time,
+            text::truncate(&entry.user, 11, Truncation::End),
+            text::truncate(&entry.action, 27, Truncation::End),
+            text::truncate(&details.join(" "), details_width, Truncation::End),
+        ), style));
+    }
+    lines.push(Line::from(Span::styled(
+        match state.audit_log.path() {
+            Some(path) => format!("Logged to {}  ↑/↓: scroll  U/ESC: close", path.display()),
+            None => "Not written to a file  ↑/↓: scroll  U/ESC: close".to_string(),
+        },
+        Style::default().fg(Color::Gray),
+    )));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" Recent actions ({}) ", state.audit_log.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup listing every query sent with its timings, most costly first.
+fn draw_query_stats(frame: &mut Frame, size: Rect, state: &AppState, popup: &QueryStatsPopup) {
+    let width = size.width.saturating_sub(6);
+    let height = size.height.saturating_sub(4);
+    let area = modal::centered(size, width, height);
+    let timings = state.query_stats.by_cost();
+
+    let query_width = usize::from(width.saturating_sub(2)).saturating_sub(59).max(10);
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:<7}{:>6}{:>9}{:>9}{:>9}{:>9}{:>8}  {}",
+            "Kind", "Runs", "Cost", "Server", "Last", "Avg", "Max", "Query"
+        ),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    ))];
+    if timings.is_empty() {
+        lines.push(Line::from(Span::styled("No queries sent yet", Style::default().fg(Color::Gray))));
+    }
+    let body_rows = usize::from(height.saturating_sub(4));
+    let start = popup.scroll_offset.min(timings.len().saturating_sub(1));
+    for timing in timings.iter().skip(start).take(body_rows) {
+        // Queries are one line here; newlines in multi-line PromQL become spaces
+        let mut query = timing.query.split_whitespace().collect::<Vec<_>>().join(" ");
+        let reduction = state.query_guard.reduction(timing.backend, &timing.query);
+        if let Some(reduction) = &reduction {
+            query = format!("[reduced {}×: {}] {}", reduction.factor(), reduction.reason, query);
+        }
+        let style = if reduction.is_some() { Style::default().fg(Color::Yellow) } else { Style::default() };
+        lines.push(Line::styled(format!(
+            "{:<7}{:>6}{:>9}{:>9}{:>9}{:>9}{:>8}  {}",
+            timing.backend.as_str(),
+            timing.runs,
+            format_ms(timing.cost_ms()),
+            timing.last_server_ms.map_or("-".to_string(), format_ms),
+            format_ms(timing.last_ms),
+            format_ms(timing.average_ms()),
+            format_ms(timing.max_ms),
+            text::truncate(&query, query_width, Truncation::End),
+        ), style));
+    }
+    lines.push(Line::from(Span::styled(
+        "Cost: total server time (end to end where not reported)  ↑/↓: scroll  S/ESC: close",
+        Style::default().fg(Color::Gray),
+    )));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" Query timings ({} queries) ", timings.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The selected exchange of the focused panel: its request URL and decoded
+/// parameters, then the status and the pretty-printed response, scrolled.
+fn draw_debug(frame: &mut Frame, size: Rect, state: &AppState, popup: &DebugPopup) {
+    let width = size.width.saturating_sub(6);
+    let height = size.height.saturating_sub(4);
+    let area = modal::centered(size, width, height);
+    let gray = Style::default().fg(Color::Gray);
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+    let exchange = popup.queries.get(popup.selected).and_then(|(backend, query)| state.exchanges.get(*backend, query));
+    let mut lines = Vec::new();
+    match &exchange {
+        None => lines.push(Line::styled("No requests sent for this panel yet", gray)),
+        Some(exchange) => {
+            lines.push(Line::styled(format!("GET {}", exchange.endpoint()), heading));
+            for (name, value) in exchange.params() {
+                lines.push(Line::from(vec![Span::styled(format!("  {} = ", name), gray), Span::raw(value)]));
+            }
+            let (status, color) = match exchange.status {
+                Some(status) if (200..300).contains(&status) => (format!("HTTP {}", status), Color::Green),
+                Some(status) => (format!("HTTP {}", status), Color::Red),
+                None => ("No response".to_string(), Color::Red),
+            };
+            let truncated = if exchange.truncated { "  (truncated)" } else { "" };
+            lines.push(Line::from(vec![
+                Span::styled(status, Style::default().fg(color).add_modifier(Modifier::BOLD)),
                 Span::styled(
-                    "Please wait while fetching data from Prometheus",
-                    Style::default().fg(Color::Gray),
+                    format!("  {}  at {}  {}{}", format_ms(exchange.elapsed_ms), exchange.at.format("%H:%M:%S"), humanize_bytes(exchange.body.len() as f64), truncated),
+                    gray,
                 ),
-            ]),
-        ];
-        
-        let loading_widget = Paragraph::new(loading_text)
-            .alignment(Alignment::Center);
-            
-        frame.render_widget(loading_widget, inner);
-    } else if let Some(metrics) = &state.metrics {
-        
-        // Calculate dynamic column widths based on terminal width
-        let available_width = inner.width as usize;
-        
-        // Find the longest URI to determine minimum needed width
-        let max_uri_len = metrics.uri_metrics.iter()
-            .map(|m| m.uri.len())
-            .max()
-            .unwrap_or(20)
-            .min(available_width / 3); // Cap at 1/3 of terminal width
-        
-        // Fixed edge columns
-        let uri_column_width = max_uri_len + 2; // URI on left edge with small padding
-        let req_width = 8; // Reqs/min on right edge (shortened)
-        let ms_width = 7;  // ms value (shortened)
-        let spacing = 2;   // Small spacing between bar and numbers
-        
-        // Calculate middle space for bar chart
-        let middle_space = available_width.saturating_sub(uri_column_width + ms_width + req_width + spacing);
-        let bar_width = middle_space.max(20); // Bar chart takes all middle space, minimum 20 chars
-        
-        // Build lines for each URI metric
-        let mut lines = Vec::new();
-        
-        // Add header with proper alignment matching data lines
-        let uri_header = format!("{:<width$}", "URI", width = uri_column_width);
-        let response_header = format!("{:^width$}", "Response Time (ms)", width = bar_width + ms_width + 1);
-        let req_header = format!("{:>width$}", "Req/min", width = req_width);
-        
-        // Create header line with separate spans to match data line structure
+            ]));
+            let body = exchange.pretty_body();
+            let body_rows = usize::from(height.saturating_sub(2)).saturating_sub(lines.len() + 1);
+            let total = body.lines().count();
+            let start = popup.scroll_offset.min(total.saturating_sub(body_rows));
+            lines.extend(body.lines().skip(start).take(body_rows).map(|line| Line::raw(line.to_string())));
+        }
+    }
+
+    let title = match popup.queries.get(popup.selected) {
+        Some((backend, query)) => format!(
+            " Request {}/{} {}: {} ",
+            popup.selected + 1,
+            popup.queries.len(),
+            backend.as_str(),
+            text::truncate(&query.split_whitespace().collect::<Vec<_>>().join(" "), usize::from(width).saturating_sub(30), Truncation::End)
+        ),
+        None => " Requests ".to_string(),
+    };
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .title_bottom(Line::styled(" ←/→: request  ↑/↓ PgUp/PgDn: scroll  y: copy as curl + response  D/ESC: close ", gray))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup with each synthetic check's last result, uptime and a strip of its
+/// recent runs: bar height is latency, red runs failed.
+fn draw_checks(frame: &mut Frame, size: Rect, state: &AppState) {
+    let width = size.width.saturating_sub(6);
+    let height = (state.synthetic_checks.len() as u16 * 4 + 3).max(6).min(size.height.saturating_sub(4));
+    let area = modal::centered(size, width, height);
+    let strip_width = usize::from(width.saturating_sub(4));
+
+    let mut lines = Vec::new();
+    if state.synthetic_checks.is_empty() {
+        lines.push(Line::from(Span::styled("No [[synthetic_checks]] configured", Style::default().fg(Color::Gray))));
+    }
+    for check in &state.synthetic_checks {
         lines.push(Line::from(vec![
+            Span::styled(check.config.name.clone(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {} {}", check.config.method.to_uppercase(), check.config.url), Style::default().fg(Color::Gray)),
+        ]));
+        let mut result = match check.last() {
+            None => vec![Span::styled("  Not run yet", Style::default().fg(Color::DarkGray))],
+            Some(run) if run.passed() => vec![Span::styled(
+                format!("  ✓ {} in {:.0} ms", run.status.unwrap_or_default(), run.latency_ms),
+                Style::default().fg(Color::Green),
+            )],
+            Some(run) => vec![Span::styled(format!("  ✗ {}", run.failures.join(", ")), Style::default().fg(Color::Red))],
+        };
+        if let Some(uptime) = check.uptime_pct() {
+            result.push(Span::styled(format!("  uptime {:.1}% of {} runs", uptime, check.runs.len()), Style::default().fg(Color::Gray)));
+        }
+        lines.push(Line::from(result));
+        let shown: Vec<_> = check.runs.iter().skip(check.runs.len().saturating_sub(strip_width)).collect();
+        if state.accessible {
+            let failed = shown.iter().filter(|run| !run.passed()).count();
+            let max = shown.iter().map(|run| run.latency_ms).fold(0.0, f64::max);
+            lines.push(Line::from(format!("  {} failed, slowest {:.0} ms", failed, max)));
+        } else {
+            lines.push(latency_strip(shown.iter().map(|run| (Some(run.latency_ms), run.passed()))));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled("C/ESC: close", Style::default().fg(Color::Gray))));
+
+    let failing = state.synthetic_checks.iter().filter(|check| check.is_failing()).count();
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" Synthetic checks ({}, {} failing) ", state.synthetic_checks.len(), failing))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(if failing > 0 { Color::Red } else { Color::Cyan })),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// One bar per sample, its height the latency relative to the slowest:
+/// green when the sample is good, red when not, `×` when there was none.
+fn latency_strip(samples: impl Iterator<Item = (Option<f64>, bool)> + Clone) -> Line<'static> {
+    const BARS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    let max = samples.clone().filter_map(|(latency, _)| latency).fold(1.0, f64::max);
+    let mut strip = vec![Span::raw("  ")];
+    strip.extend(samples.map(|(latency, good)| {
+        let bar = latency.map_or("×", |latency| BARS[((latency / max * 7.0).round() as usize).min(7)]);
+        Span::styled(bar, Style::default().fg(if good { Color::Green } else { Color::Red }))
+    }));
+    Line::from(strip)
+}
+
+/// Popup with each SLO's burn rate per window and the alert it sets off:
+/// a window is colored by the most severe alert it belongs to once it burns
+/// faster than that alert's threshold.
+fn draw_slos(frame: &mut Frame, size: Rect, state: &AppState) {
+    let width = size.width.saturating_sub(6).min(90);
+    let height = (state.slos.len() as u16 * 4 + 6).max(8).min(size.height.saturating_sub(4));
+    let area = modal::centered(size, width, height);
+
+    let mut lines = Vec::new();
+    if state.slos.is_empty() {
+        lines.push(Line::from(Span::styled("No [[slos]] configured", Style::default().fg(Color::Gray))));
+    }
+    for slo in &state.slos {
+        lines.push(Line::from(vec![
+            Span::styled(slo.config.name.clone(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
             Span::styled(
-                uri_header,
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-            ),
-            Span::styled(
-                response_header,
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-            ),
-            Span::styled(
-                req_header,
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                format!("  objective {}%, error budget {:.3}%", slo.config.objective, slo.config.error_budget() * 100.0),
+                Style::default().fg(Color::Gray),
             ),
         ]));
-        
-        // Calculate visible metrics based on area height
-        let visible_count = (inner.height as usize).saturating_sub(3).min(10); // Header + footer space
-        let start_idx = state.metrics_scroll_offset;
-        let end_idx = (start_idx + visible_count).min(metrics.uri_metrics.len());
-        
-        // Find the max duration for scaling the bars
-        let max_duration = metrics.uri_metrics.iter()
-            .map(|m| m.avg_duration_ms)
-            .fold(0.0_f64, f64::max)
+        let mut rates = vec![Span::raw(" ")];
+        for window in WINDOWS {
+            let alert = BURN_ALERTS.iter().find(|alert| alert.long == window || alert.short == window);
+            let (text, style) = match slo.burn_rate(window) {
+                None => ("-".to_string(), Style::default().fg(Color::DarkGray)),
+                Some(rate) => {
+                    let color = match alert {
+                        Some(alert) if rate > alert.threshold => severity_style(alert.severity).0,
+                        _ => Color::Green,
+                    };
+                    (format!("{:.1}×", rate), Style::default().fg(color))
+                }
+            };
+            rates.push(Span::styled(format!(" {} ", window), Style::default().fg(Color::Gray)));
+            rates.push(Span::styled(format!("{:<8}", text), style));
+        }
+        lines.push(Line::from(rates));
+        let (color, label) = severity_style(slo.severity());
+        let verdict = match slo.describe_firing() {
+            Some(firing) => format!("  {}: {}", label, firing),
+            None => format!("  {}: within budget", label),
+        };
+        lines.push(Line::from(Span::styled(verdict, Style::default().fg(color).add_modifier(Modifier::BOLD))));
+        if let Some(error) = &slo.error {
+            lines.push(Line::from(Span::styled(format!("  Query failed: {}", error), Style::default().fg(Color::Red))));
+        }
+        lines.push(Line::from(""));
+    }
+    for action in ["page", "ticket"] {
+        let rules: Vec<String> = BURN_ALERTS
+            .iter()
+            .filter(|alert| alert.action == action)
+            .map(|alert| format!("{} and {} > {}×", alert.long, alert.short, alert.threshold))
+            .collect();
+        lines.push(Line::from(Span::styled(format!("{}: {}", action, rules.join(", ")), Style::default().fg(Color::Gray))));
+    }
+    lines.push(Line::from(Span::styled("B/ESC: close", Style::default().fg(Color::Gray))));
+
+    let burning = state.slos.iter().filter(|slo| slo.firing().is_some()).count();
+    let border = if state.slos.iter().any(|slo| slo.severity() == Severity::Critical) {
+        Color::Red
+    } else if burning > 0 {
+        Color::Yellow
+    } else {
+        Color::Cyan
+    };
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" SLO burn rates ({}, {} burning) ", state.slos.len(), burning))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup listing the metrics rows that changed since the refresh before.
+fn draw_changes(frame: &mut Frame, size: Rect, state: &AppState) {
+    let changes = &state.metric_changes.changes;
+    let width = size.width.saturating_sub(6).min(100);
+    let height = (changes.len() as u16 + 4).max(5).min(size.height.saturating_sub(4));
+    let area = modal::centered(size, width, height);
+    let uri_width = usize::from(width.saturating_sub(4)).saturating_sub(36).max(10);
+
+    let mut lines = Vec::new();
+    if state.metric_changes.since.is_none() {
+        lines.push(Line::styled("Waiting for a second refresh to compare", Style::default().fg(Color::Gray)));
+    } else if changes.is_empty() {
+        lines.push(Line::styled(
+            format!("Nothing moved by {:.0}% or more", state.change_threshold_pct),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    let rows = usize::from(height.saturating_sub(3));
+    for change in changes.iter().take(rows) {
+        let (marker, _) = change.marker();
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} ", marker), change_style(change)),
+            Span::raw(format!("{}  ", text::fit(&change.uri, uri_width, state.uri_truncation))),
+            Span::styled(change.describe(), change_style(change)),
+        ]));
+    }
+    lines.push(Line::styled("K/ESC: close", Style::default().fg(Color::Gray)));
+
+    let title = match state.metric_changes.since {
+        Some(since) => format!(" Changes since {} ({}) ", since.format("%H:%M:%S"), changes.len()),
+        None => " Changes ".to_string(),
+    };
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Color of a change: red for slower, green for faster, cyan for new rows.
+fn change_style(change: &MetricChange) -> Style {
+    let color = match change.kind {
+        ChangeKind::New => Color::Cyan,
+        ChangeKind::Vanished => Color::DarkGray,
+        ChangeKind::Latency { before_ms, after_ms } if after_ms > before_ms => Color::Red,
+        ChangeKind::Latency { .. } => Color::Green,
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+/// Popup with each `[ping]` host's round trips and loss.
+fn draw_ping(frame: &mut Frame, size: Rect, state: &AppState) {
+    let width = size.width.saturating_sub(6);
+    let height = (state.ping_targets.len() as u16 * 3 + 3).max(6).min(size.height.saturating_sub(4));
+    let area = modal::centered(size, width, height);
+    let strip_width = usize::from(width.saturating_sub(4));
+
+    let mut lines = Vec::new();
+    if state.ping_targets.is_empty() {
+        lines.push(Line::from(Span::styled("No [ping] hosts configured", Style::default().fg(Color::Gray))));
+    }
+    for target in &state.ping_targets {
+        let (last, style) = match target.last() {
+            None => ("waiting for a reply".to_string(), Style::default().fg(Color::DarkGray)),
+            Some(None) => ("lost".to_string(), Style::default().fg(Color::Red)),
+            Some(Some(rtt)) => (format!("{:.1} ms", rtt), Style::default().fg(Color::Green)),
+        };
+        let mut header = vec![
+            Span::styled(format!("{:<24}", target.host), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::styled(last, style),
+        ];
+        if let Some(loss) = target.loss_pct() {
+            let loss_style = if loss > 0.0 { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::Gray) };
+            let average = target.average_ms().map_or("-".to_string(), |average| format!("{:.1} ms", average));
+            header.push(Span::styled(
+                format!("  avg {}  loss {:.0}% of {}", average, loss, target.samples.len()),
+                loss_style,
+            ));
+        }
+        lines.push(Line::from(header));
+        let shown = target.samples.iter().skip(target.samples.len().saturating_sub(strip_width));
+        if state.accessible {
+            let max = shown.flatten().fold(0.0, |max: f64, rtt| max.max(*rtt));
+            lines.push(Line::from(format!("  slowest {:.1} ms", max)));
+        } else {
+            lines.push(latency_strip(shown.map(|rtt| (*rtt, rtt.is_some()))));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled("N/ESC: close", Style::default().fg(Color::Gray))));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" Network latency ({} hosts) ", state.ping_targets.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup with what each backend reports about itself.
+fn draw_about(frame: &mut Frame, size: Rect, state: &AppState, about: &AboutBackends) {
+    let heading = |name: &str, url: &str| {
+        Line::from(vec![
+            Span::styled(name.to_string(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {}", url), Style::default().fg(Color::Gray)),
+        ])
+    };
+    let field = |label: &str, value: Option<String>| {
+        Line::from(vec![
+            Span::raw(format!("  {:<16}", label)),
+            match value {
+                Some(value) => Span::raw(value),
+                None => Span::styled("unknown", Style::default().fg(Color::DarkGray)),
+            },
+        ])
+    };
+
+    let mut lines = vec![heading("Prometheus", &state.prometheus_url)];
+    match (&about.prometheus, &about.error) {
+        _ if !state.prometheus_enabled => lines.push(field("Status", Some("disabled".to_string()))),
+        (_, Some(error)) => lines.push(Line::from(Span::styled(
+            format!("  Status queries failed: {}", error),
+            Style::default().fg(Color::Red),
+        ))),
+        (None, None) => lines.push(Line::from(Span::styled("  Loading...", Style::default().fg(Color::Yellow)))),
+        (Some(info), None) => {
+            let version = info.version.as_ref().map(|version| match &info.revision {
+                Some(revision) => format!("{} ({})", version, revision.chars().take(12).collect::<String>()),
+                None => version.clone(),
+            });
+            let uptime = info.start_time.map(|start| {
+                let seconds = (Local::now().with_timezone(&chrono::Utc) - start).num_seconds();
+                format!("{} (since {})", humanize_age(seconds), start.with_timezone(&Local).format("%Y-%m-%d %H:%M"))
+            });
+            lines.push(field("Version", version));
+            lines.push(field("Uptime", uptime));
+            lines.push(field("Retention", info.storage_retention.clone()));
+            lines.push(field("Head series", info.head_series.map(|series| series.to_string())));
+            lines.push(field("WAL size", info.wal_bytes.map(humanize_bytes)));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(heading("Loki", &state.loki_url));
+    if state.loki_enabled {
+        lines.push(field("Version", state.loki_version.clone()));
+    } else {
+        lines.push(field("Status", Some("disabled".to_string())));
+    }
+    if !state.external_deps.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(heading("External dependencies", "status pages"));
+        for dep in &state.external_deps {
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {:<16}", dep.name)),
+                Span::styled(dep.description.clone(), indicator_style(dep.indicator)),
+            ]));
+            for incident in &dep.incidents {
+                lines.push(Line::from(Span::styled(format!("    Incident: {}", incident), Style::default().fg(Color::Yellow))));
+            }
+            for component in &dep.degraded {
+                lines.push(Line::from(Span::styled(format!("    {}", component), Style::default().fg(Color::Gray))));
+            }
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("r: refresh  i/ESC: close", Style::default().fg(Color::Gray))));
+
+    let width = size.width.saturating_sub(10).min(72);
+    let height = (lines.len() as u16 + 2).max(16).min(size.height);
+    let area = modal::centered(size, width, height);
+
+    let popup = Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: false }).block(
+        Block::default()
+            .title(" About backends ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup comparing two log lines character by character: removed text
+/// marked in the older line, added text in the newer one.
+fn draw_log_diff(frame: &mut Frame, size: Rect, state: &AppState, (older, newer): (usize, usize)) {
+    let width = size.width.saturating_sub(10);
+    let height = size.height.saturating_sub(6);
+    let area = modal::centered(size, width, height);
+    let (old_log, new_log) = (&state.all_logs[older], &state.all_logs[newer]);
+    let segments = diff::diff(&old_log.message, &new_log.message);
+
+    let heading = |label: &str, log: &LogEntry| {
+        Line::from(Span::styled(
+            format!("{} {} [{}] {}", label, log.timestamp, log.level, log.stream),
+            Style::default().fg(Color::Gray),
+        ))
+    };
+    let side = |segments: Vec<&diff::Segment>| {
+        Line::from(
+            segments
+                .into_iter()
+                .map(|segment| match (segment.change, state.accessible) {
+                    (diff::Change::Same, _) => Span::raw(segment.text.clone()),
+                    // Brackets instead of color, as in word-diff output
+                    (diff::Change::Removed, true) => Span::raw(format!("[-{}-]", segment.text)),
+                    (diff::Change::Added, true) => Span::raw(format!("{{+{}+}}", segment.text)),
+                    (diff::Change::Removed, false) => {
+                        Span::styled(segment.text.clone(), Style::default().fg(Color::Black).bg(Color::Red))
+                    }
+                    (diff::Change::Added, false) => {
+                        Span::styled(segment.text.clone(), Style::default().fg(Color::Black).bg(Color::Green))
+                    }
+                })
+                .collect::<Vec<_>>(),
+        )
+    };
+    let summary = match diff::change_count(&segments) {
+        0 => "Identical".to_string(),
+        1 => "1 difference".to_string(),
+        count => format!("{} differences", count),
+    };
+    let lines = vec![
+        heading("Older", old_log),
+        side(diff::old_side(&segments).collect()),
+        Line::from(""),
+        heading("Newer", new_log),
+        side(diff::new_side(&segments).collect()),
+        Line::from(""),
+        Line::from(Span::styled(summary, Style::default().fg(Color::Cyan))),
+        Line::from(Span::styled(
+            "↑/↓: compare other lines  d/ESC: close",
+            Style::default().fg(Color::Gray),
+        )),
+    ];
+
+    let popup = Paragraph::new(lines)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(format!(" Diff of lines {} and {} ", older + 1, newer + 1))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup listing the actions on the selected log line.
+fn draw_log_action_menu(frame: &mut Frame, size: Rect, state: &AppState, menu: &LogActionMenu) {
+    let width = size.width.saturating_sub(10).min(72);
+    let height = (menu.actions.len() as u16 + 5).min(size.height);
+    let area = modal::centered(size, width, height);
+
+    let mut lines = Vec::new();
+    if let Some(log) = state.all_logs.get(menu.log_index) {
+        let (line, _) = truncate_message(&format!("[{}] {}", log.level, log.message), usize::from(width.saturating_sub(4)));
+        lines.push(Line::from(Span::styled(line, Style::default().fg(Color::Gray))));
+        lines.push(Line::from(""));
+    }
+    for (idx, action) in menu.actions.iter().enumerate() {
+        let selected = idx == menu.selected;
+        let marker = if selected { "> " } else { "  " };
+        let style = if selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+        let (label, _) = truncate_message(&action.label(), usize::from(width.saturating_sub(6)));
+        lines.push(Line::from(format!("{}{}", marker, label)).style(style));
+    }
+    lines.push(Line::from(Span::styled(
+        "↑/↓: select  Enter: run  a/ESC: close",
+        Style::default().fg(Color::Gray),
+    )));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Log line ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Popup listing the admin actions, or asking to confirm the selected one.
+fn draw_admin_menu(frame: &mut Frame, size: Rect, state: &AppState, menu: &AdminMenu) {
+    let width = size.width.saturating_sub(10).min(72);
+    let height = if menu.confirming { 16 } else { menu.actions.len() as u16 + 9 }.min(size.height);
+    let area = modal::centered(size, width, height);
+
+    let mut lines = Vec::new();
+    if let (true, Some(action)) = (menu.confirming, menu.selected_action()) {
+        let target = state.admin_target(action);
+        if action.is_destructive() {
+            lines.push(Line::from(Span::styled(
+                " PERMANENT DELETION ",
+                Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+        }
+        lines.push(Line::from(Span::styled(
+            format!("{}?", action.label()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(format!("Server: {}", target)));
+        lines.push(Line::from(""));
+        lines.push(Line::from(action.warning()));
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("y", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+            Span::raw(": run it   "),
+            Span::styled("n", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::raw("/ESC: cancel"),
+        ]));
+    } else {
+        if menu.actions.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "Select a log line to delete its stream",
+                Style::default().fg(Color::Gray),
+            )));
+        }
+        for (idx, action) in menu.actions.iter().enumerate() {
+            let selected = idx == menu.selected;
+            let marker = if selected { "> " } else { "  " };
+            let style = if selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+            lines.push(Line::from(format!("{}{}", marker, action.label())).style(style));
+        }
+        lines.push(Line::from(""));
+        match &menu.result {
+            _ if menu.running => lines.push(Line::from(Span::styled("Running...", Style::default().fg(Color::Yellow)))),
+            Some(Ok(message)) => lines.push(Line::from(Span::styled(message.clone(), Style::default().fg(Color::Green)))),
+            Some(Err(error)) => lines.push(Line::from(Span::styled(format!("Failed: {}", error), Style::default().fg(Color::Red)))),
+            None => {}
+        }
+        lines.push(Line::from(Span::styled(
+            "↑/↓: select  Enter: run (asks first)  A/ESC: close",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+
+    let popup = Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: true }).block(
+        Block::default()
+            .title(" Admin ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+fn draw_size_warning(frame: &mut Frame, size: Rect) {
+    let warning = Paragraph::new(vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "Terminal Too Small",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(format!("Current: {}x{}", size.width, size.height)),
+        Line::from("Required: 80x24 minimum"),
+        Line::from(""),
+        Line::from("Please resize your terminal window"),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .title(" Warning "),
+    )
+    .alignment(Alignment::Center);
+    
+    frame.render_widget(warning, size);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, state: &AppState) {
+    let header = Paragraph::new(vec![Line::from(vec![
+        Span::raw(" "),
+        Span::styled(
+            "RustDash",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" | "),
+        Span::styled(
+            format!("Fetch: {}", state.last_fetch),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::raw(" | "),
+        Span::styled(
+            format!("Update: {}", state.last_update),
+            Style::default().fg(Color::Gray),
+        ),
+    ])])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Cyan)),
+    )
+    .alignment(Alignment::Center);
+
+    frame.render_widget(header, area);
+}
+
+fn draw_endpoints(frame: &mut Frame, area: Rect, state: &AppState) {
+    let endpoint = |enabled: bool, url: &str, version: &Option<String>| {
+        if enabled {
+            match version {
+                Some(version) => Span::raw(format!("{} (v{})", url, version.trim_start_matches('v'))),
+                None => Span::raw(url.to_string()),
+            }
+        } else {
+            Span::styled("disabled", Style::default().fg(Color::DarkGray))
+        }
+    };
+    // Range queries and log ages follow the server's clock, but a large skew
+    // still throws off the local times shown in the header
+    let skew = |skew: Option<f64>| match skew {
+        Some(seconds) if seconds.abs() > state.clock_skew_warn_seconds as f64 => Span::styled(
+            format!(" clock {:+}s", seconds.round() as i64),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ),
+        _ => Span::raw(""),
+    };
+    let mut spans = vec![
+        Span::styled("Prometheus: ", Style::default().fg(Color::Yellow)),
+        endpoint(state.prometheus_enabled, &state.prometheus_url, &state.prometheus_version),
+        skew(state.prometheus_clock_skew),
+        Span::raw(" | "),
+        Span::styled("Loki: ", Style::default().fg(Color::Magenta)),
+        endpoint(state.loki_enabled, &state.loki_url, &state.loki_version),
+        skew(state.loki_clock_skew),
+    ];
+    if !state.external_deps.is_empty() {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled("External:", Style::default().fg(Color::Blue)));
+        for dep in &state.external_deps {
+            spans.push(Span::styled(format!(" {}", dependency_label(dep, state.accessible)), indicator_style(dep.indicator)));
+        }
+    }
+    let endpoints = Paragraph::new(vec![Line::from(spans)])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray)),
+    )
+    .alignment(Alignment::Center);
+
+    frame.render_widget(endpoints, area);
+}
+
+/// A dependency in the endpoints bar: its name and a mark, spelled out
+/// unless all is well (in accessible mode, always).
+fn dependency_label(dep: &DependencyStatus, accessible: bool) -> String {
+    match (dep.indicator, accessible) {
+        (indicator, true) => format!("{} {}", dep.name, indicator.as_str()),
+        (Indicator::Operational, false) => format!("{} ✓", dep.name),
+        (Indicator::Unknown, false) => format!("{} ?", dep.name),
+        (Indicator::Maintenance, false) => format!("{} ⚙ maintenance", dep.name),
+        (indicator, false) => format!("{} ▲ {}", dep.name, indicator.as_str()),
+    }
+}
+
+fn indicator_style(indicator: Indicator) -> Style {
+    match indicator {
+        Indicator::Operational => Style::default().fg(Color::Green),
+        Indicator::Minor => Style::default().fg(Color::Yellow),
+        Indicator::Major | Indicator::Critical => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        Indicator::Maintenance => Style::default().fg(Color::Blue),
+        Indicator::Unknown => Style::default().fg(Color::DarkGray),
+    }
+}
+
+fn draw_alert_banner(frame: &mut Frame, area: Rect, state: &AppState, alerts: &[(Alert, DateTime<Local>, String)]) {
+    let mut spans = Vec::new();
+    for (alert, since, reason) in alerts {
+        if !spans.is_empty() {
+            spans.push(Span::styled(" | ", Style::default().fg(Color::Red)));
+        }
+        let text = format!("{}: {} (since {})", alert, reason, since.format("%H:%M:%S"));
+        // Alerts being looked into stay, toned down
+        if state.triage.alert_state(alert, *since) == Some(TriageState::Investigating) {
+            spans.push(Span::styled(format!("{} [investigating]", text), Style::default().fg(Color::Yellow)));
+        } else {
+            spans.push(Span::styled(text, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
+        }
+    }
+    let banner = Paragraph::new(Line::from(spans))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red)),
+    )
+    .alignment(Alignment::Center);
+
+    frame.render_widget(banner, area);
+}
+
+fn draw_metrics_compact(frame: &mut Frame, area: Rect, state: &AppState, _terminal_size: Rect) {
+    // Calculate time range based on current setting
+    let now = chrono::Local::now();
+    let time_range_display = if let Some(minutes) = state.metrics_time_range.as_minutes() {
+        let from_time = (now - chrono::TimeDelta::try_minutes(minutes).unwrap()).format("%H:%M:%S");
+        let to_time = now.format("%H:%M:%S");
+        format!("[{}] ({} → {})", state.metrics_time_range.as_str(), from_time, to_time)
+    } else {
+        format!("[{}] (All time)", state.metrics_time_range.as_str())
+    };
+    
+    let base_title = "API Response Times";
+    let help_text = match state.active_panel {
+        ActivePanel::Metrics if state.zoomed => " [↑/↓/PgUp/PgDn: select, g: history, e: logs, s: rank, c/M: copy, ←/→: time range, ⇧←/→: columns, z: restore layout] ",
+        ActivePanel::Metrics => " [↑/↓/PgUp/PgDn: select, g: history, e: logs, s: rank, x: hide, c/M: copy, ←/→: time range, ⇧←/→: columns, z: zoom, ESC: unfocus] ",
+        ActivePanel::None => " [TAB to focus] ",
+        ActivePanel::Logs => " [TAB to switch here] ",
+    };
+    
+    // Border color alone signals focus, so spell it out in accessible mode
+    let focus_marker = if state.accessible && state.active_panel == ActivePanel::Metrics { "[ACTIVE] " } else { "" };
+    let title = format!(
+        " {}{}{} {} by {} {} ",
+        focus_marker,
+        refresh_spinner(state, RefreshScope::Metrics),
+        base_title,
+        time_range_display,
+        state.metrics_ranking.as_str(),
+        help_text
+    );
+    
+    let metrics_block = focus_block(state, ActivePanel::Metrics)
+        .title(title)
+        .title_bottom(staleness_line("metrics", state.metrics_last_success, &state.metrics_backoff, state));
+
+    // Draw the block first
+    frame.render_widget(metrics_block, area);
+    
+    // Create inner area for content
+    let inner = ratatui::layout::Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+    
+    if state.metrics_loading {
+        // Show loading indicator
+        let loading_text = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(
+                    if state.accessible { "Loading metrics..." } else { "⏳ Loading metrics..." },
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
+            ]),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(
+                    "Please wait while fetching data from Prometheus",
+                    Style::default().fg(Color::Gray),
+                ),
+            ]),
+        ];
+        
+        let loading_widget = Paragraph::new(loading_text)
+            .alignment(Alignment::Center);
+            
+        frame.render_widget(loading_widget, inner);
+    } else if let Some(metrics) = state.metrics.as_ref().filter(|m| m.uri_metrics.is_empty() && !m.errors.is_empty()) {
+        // Every query failed - show why instead of a blank table
+        let mut lines = vec![Line::from(Span::styled(
+            "Prometheus query failed:",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ))];
+        for error in &metrics.errors {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", error),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        for warning in &metrics.warnings {
+            lines.push(Line::from(Span::styled(
+                format!("Warning: {}", warning),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        
+        let error_widget = Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: true });
+        
+        frame.render_widget(error_widget, inner);
+    } else if let Some(metrics) = &state.metrics {
+        
+        // Calculate dynamic column widths based on terminal width
+        let available_width = inner.width as usize;
+        
+        // Find the longest URI to determine minimum needed width
+        let max_uri_len = metrics.uri_metrics.iter()
+            .map(|m| text::width(&m.uri))
+            .max()
+            .unwrap_or(20)
+            .min(available_width / 3); // Cap at 1/3 of terminal width
+        
+        // The URI column stays put; the columns after it scroll sideways
+        // (Shift+←/→), the leftmost ones leaving first
+        let scrolled = scrolled_metrics_columns(state);
+        let shows = |column: MetricsColumn| !scrolled.contains(&column);
+        
+        // Fixed edge columns
+        let uri_column_width = max_uri_len + 2; // URI on left edge with small padding
+        let req_width = if shows(MetricsColumn::Rank) { 8 } else { 0 }; // Reqs/min on right edge (shortened)
+        let ms_width = if shows(MetricsColumn::Response) { 7 } else { 0 }; // ms value (shortened)
+        let spacing = 2;   // Small spacing between bar and numbers
+        // Bandwidth column only when a response size metric answered
+        let show_bandwidth = metrics_columns(state).contains(&MetricsColumn::Bandwidth) && shows(MetricsColumn::Bandwidth);
+        let bw_width = if show_bandwidth { 9 } else { 0 };
+        
+        // Calculate middle space for bar chart
+        let middle_space = available_width.saturating_sub(uri_column_width + ms_width + req_width + bw_width + spacing);
+        // Last error column takes from the middle while the bar keeps 20 chars; dropped when too narrow to read
+        let bar_room = if shows(MetricsColumn::Response) { 20 } else { 0 };
+        let error_width = if state.uri_error_pattern.is_some() { middle_space.saturating_sub(bar_room).min(48) } else { 0 };
+        let error_width = if error_width < 12 { 0 } else { error_width };
+        let middle_space = middle_space - error_width;
+        let bar_width = if shows(MetricsColumn::Response) { middle_space.max(20) } else { 0 }; // Bar chart takes all middle space, minimum 20 chars
+        
+        // Build lines for each URI metric
+        let mut lines = Vec::new();
+        
+        // Add header with proper alignment matching data lines
+        let bar_width = if state.accessible { 0 } else { bar_width };
+        let response_title = if state.accessible { "Avg response" } else { "Response Time (ms)" };
+        let uri_header = format!("{:<width$}", "URI", width = uri_column_width);
+        let response_header = match shows(MetricsColumn::Response) {
+            true => format!("{:^width$}", response_title, width = bar_width + ms_width + 1),
+            false => String::new(),
+        };
+        let req_header = format!("{:>width$}", rank_column_title(state.metrics_ranking), width = req_width);
+        let bw_header = format!("{:>width$}", if show_bandwidth { "BW" } else { "" }, width = bw_width);
+        let error_header = if error_width > 0 { format!("  {:<width$}", "Last error", width = error_width - 2) } else { String::new() };
+        
+        // Create header line with separate spans to match data line structure
+        lines.push(Line::from(vec![
+            Span::styled(
+                uri_header,
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ),
+            Span::styled(
+                response_header,
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ),
+            Span::styled(
+                req_header,
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ),
+            Span::styled(
+                bw_header,
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ),
+            Span::styled(
+                error_header,
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ),
+        ]));
+        
+        // Calculate visible metrics based on area height
+        // Header + footer space, plus the egress line when there is one
+        let footer_lines = if metrics.egress_bytes_per_sec.is_empty() { 3 } else { 4 };
+        let visible_count = (inner.height as usize).saturating_sub(footer_lines).min(metrics_row_cap(state));
+        // Fewer rows after a refresh can leave the offset past the end
+        let start_idx = state.metrics_scroll_offset.min(metrics.uri_metrics.len().saturating_sub(visible_count));
+        let end_idx = (start_idx + visible_count).min(metrics.uri_metrics.len());
+        
+        // Find the max duration for scaling the bars
+        let max_duration = metrics.uri_metrics.iter()
+            .map(|m| m.avg_duration_ms)
+            .fold(0.0_f64, f64::max)
             .max(1.0); // Avoid division by zero
         
-        // Add each visible URI metric
-        for uri_metric in &metrics.uri_metrics[start_idx..end_idx] {
-            // Truncate URI if too long for the calculated width
-            let display_uri = if uri_metric.uri.len() > uri_column_width - 2 {
-                format!("{}...", &uri_metric.uri[..uri_column_width.saturating_sub(5)])
-            } else {
-                uri_metric.uri.clone()
-            };
-            
-            // Color code based on response time
-            let duration_color = if uri_metric.avg_duration_ms < 100.0 {
-                Color::Green
-            } else if uri_metric.avg_duration_ms < 500.0 {
-                Color::Yellow
+        // Add each visible URI metric
+        for (idx, uri_metric) in metrics.uri_metrics.iter().enumerate().take(end_idx).skip(start_idx) {
+            let selected = state.active_panel == ActivePanel::Metrics && state.selected_metric_index == Some(idx);
+            let row_style = if selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+            // Cut and pad the URI by display width, so wide characters keep the columns aligned
+            let fitted_uri = text::fit(&uri_metric.uri, max_uri_len, state.uri_truncation);
+            let display_uri = format!("{}  ", fitted_uri);
+            // Changed since the last refresh: a blinking marker in the gap after the URI
+            let change = state.metric_changes.get(&uri_metric.uri).filter(|_| state.change_markers);
+            // "2m timeout calling inventory" for the newest error line about this URI
+            let last_error = state
+                .uri_errors
+                .get(&uri_metric.uri)
+                .map(|log| format!("{} {}", humanize_age(log_age_seconds(log, state)), log.message.trim()));
+            
+            if state.accessible {
+                // Textual severity instead of color and bar length
+                lines.push(Line::from(format!(
+                    "{} avg={:.1}ms {:<4} req/min={:.0}{}{}{}{}{}",
+                    display_uri,
+                    uri_metric.avg_duration_ms,
+                    latency_label(uri_metric.avg_duration_ms),
+                    uri_metric.request_count,
+                    match state.metrics_ranking {
+                        Ranking::Errors | Ranking::Change => format!(
+                            " {}={}",
+                            rank_column_title(state.metrics_ranking).to_lowercase(),
+                            rank_column_value(state.metrics_ranking, uri_metric)
+                        ),
+                        _ => String::new(),
+                    },
+                    uri_metric
+                        .bytes_per_sec
+                        .map(|bytes| format!(" bw={}/s", humanize_bytes(bytes)))
+                        .unwrap_or_default(),
+                    if selected { " [selected]" } else { "" },
+                    change.map(|change| format!(" change: {}", change.describe())).unwrap_or_default(),
+                    last_error.as_ref().map(|error| format!(" error: {}", error)).unwrap_or_default(),
+                )).style(row_style));
+                continue;
+            }
+            
+            // Color code based on response time
+            let duration_color = if uri_metric.avg_duration_ms < 100.0 {
+                Color::Green
+            } else if uri_metric.avg_duration_ms < 500.0 {
+                Color::Yellow
+            } else {
+                Color::Red
+            };
+            
+            // Create the bar visualization
+            let bar_filled = ((uri_metric.avg_duration_ms / max_duration) * bar_width as f64) as usize;
+            let bar_filled = bar_filled.min(bar_width);
+            
+            let bar_char = match duration_color {
+                Color::Green => "█",
+                Color::Yellow => "█",
+                Color::Red => "█",
+                _ => "█",
+            };
+            
+            let bar_string = format!("{:█<width$}", "", width = bar_filled)
+                .replace(' ', bar_char);
+            let bar_empty = " ".repeat(bar_width.saturating_sub(bar_filled));
+            
+            // Build the line with proper spacing
+            let ms_part = match shows(MetricsColumn::Response) {
+                true => format!(" {:>width$.1}", uri_metric.avg_duration_ms, width = ms_width),
+                false => String::new(),
+            };
+            let req_part = match shows(MetricsColumn::Rank) {
+                true => format!("{:>width$}", rank_column_value(state.metrics_ranking, uri_metric), width = req_width),
+                false => String::new(),
+            };
+            let bw_part = match uri_metric.bytes_per_sec {
+                _ if !show_bandwidth => String::new(),
+                Some(bytes) => format!("{:>width$}", format!("{}/s", humanize_bytes(bytes)), width = bw_width),
+                None => " ".repeat(bw_width),
+            };
+            
+            let line_spans = vec![
+                Span::styled(
+                    format!("{} ", fitted_uri),
+                    Style::default().fg(Color::White),
+                ),
+                match change {
+                    Some(change) => Span::styled(change.marker().0, change_style(change).add_modifier(Modifier::SLOW_BLINK)),
+                    None => Span::raw(" "),
+                },
+                Span::styled(
+                    bar_string,
+                    Style::default().fg(duration_color),
+                ),
+                Span::styled(
+                    bar_empty,
+                    Style::default(),
+                ),
+                Span::styled(
+                    ms_part,
+                    Style::default().fg(duration_color).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    req_part,
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    bw_part,
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::styled(
+                    match &last_error {
+                        Some(error) if error_width > 0 => format!("  {}", text::truncate(error, error_width - 2, Truncation::End)),
+                        _ => String::new(),
+                    },
+                    Style::default().fg(Color::Red),
+                ),
+            ];
+            
+            lines.push(Line::from(line_spans).style(row_style));
+        }
+        
+        // Add total requests/sec and scale info at the bottom
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+            
+            // Add scale and period info
+            let scale_text = if state.accessible {
+                "OK < 100ms, SLOW < 500ms, HIGH >= 500ms".to_string()
+            } else {
+                format!("Scale: █ = {:.0}ms", max_duration)
+            };
+            let period_text = match state.metrics_time_range {
+                TimeRange::OneMin => "1-minute average",
+                TimeRange::FiveMin => "5-minute average",
+                TimeRange::ThirtyMin => "30-minute average",
+                TimeRange::OneHour => "1-hour average",
+                TimeRange::OneDay => "24-hour average",
+                TimeRange::All => match state.history.since() {
+                    Some(since) => &format!("Average observed since {}", format_time(since, "%m-%d %H:%M")),
+                    None => "All-time average",
+                },
+            };
+            // Page indicator once the table doesn't fit
+            let total = metrics.uri_metrics.len();
+            let rows_text = if total > visible_count {
+                format!("  |  rows {}–{} of {}", start_idx + 1, end_idx, total)
+            } else {
+                String::new()
+            };
+            let columns_text = match scrolled.len() {
+                0 => String::new(),
+                1 => "  |  ◀ 1 column scrolled (Shift+←)".to_string(),
+                count => format!("  |  ◀ {} columns scrolled (Shift+←)", count),
+            };
+            let hidden_text = if state.hidden_uri_count > 0 {
+                format!("  |  {} hidden (H: show)", state.hidden_uri_count)
+            } else if state.ignore.show_hidden {
+                "  |  showing hidden".to_string()
+            } else {
+                String::new()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("Total Req/s: {:.2}  |  {}  |  {}{}{}{}", 
+                        metrics.http_requests_total, scale_text, period_text, rows_text, columns_text, hidden_text),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]));
+            
+            // Total egress over the last half hour
+            if let Some(&current) = metrics.egress_bytes_per_sec.last() {
+                let peak = metrics.egress_bytes_per_sec.iter().copied().fold(0.0_f64, f64::max);
+                let text = if state.accessible {
+                    format!("Egress: {}/s now, peak {}/s over 30m", humanize_bytes(current), humanize_bytes(peak))
+                } else {
+                    format!("Egress {} {}/s (30m)", sparkline(&metrics.egress_bytes_per_sec), humanize_bytes(current))
+                };
+                lines.push(Line::from(Span::styled(text, Style::default().fg(Color::Magenta))));
+            }
+            
+            // Surface Prometheus warnings and partial failures
+            if !metrics.warnings.is_empty() || !metrics.errors.is_empty() {
+                let mut issues = Vec::new();
+                if !metrics.errors.is_empty() {
+                    issues.push(format!("{} query error(s): {}", metrics.errors.len(), metrics.errors[0]));
+                }
+                issues.extend(metrics.warnings.iter().map(|w| format!("Warning: {}", w)));
+                lines.push(Line::from(Span::styled(
+                    issues.join("  |  "),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+        }
+        
+        // If no URI metrics but we have total
+        if metrics.uri_metrics.is_empty() && metrics.http_requests_total > 0.0 {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    "No per-URI metrics available",
+                    Style::default().fg(Color::Gray),
+                ),
+            ]));
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("Total Requests/sec: {:.2}", metrics.http_requests_total),
+                    Style::default().fg(Color::Green),
+                ),
+            ]));
+        }
+        
+        let metrics_content = Paragraph::new(lines)
+            .alignment(Alignment::Left);
+        
+        frame.render_widget(metrics_content, inner);
+    } else {
+        let no_data = Paragraph::new("No metrics data available")
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center);
+        
+        frame.render_widget(no_data, inner);
+    }
+}
+
+/// Spinner (with a trailing space) while `panel`'s source is being fetched or
+/// a manual refresh of it is pending, or an empty string. Fetches running for
+/// a while show their age, so a slow backend stands out from a dead one.
+fn refresh_spinner(state: &AppState, panel: RefreshScope) -> String {
+    const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    let started = match panel {
+        RefreshScope::Logs => state.logs_fetch_started,
+        _ => state.metrics_fetch_started,
+    };
+    let requested = matches!(state.refreshing, Some(scope) if scope == panel || scope == RefreshScope::All);
+    if started.is_none() && !requested {
+        return String::new();
+    }
+
+    let now = Local::now();
+    let elapsed = started.map_or(0, |started| (now - started).num_seconds());
+    let indicator = if state.accessible {
+        "loading".to_string()
+    } else {
+        FRAMES[now.timestamp_subsec_millis() as usize / 100 % FRAMES.len()].to_string()
+    };
+    let label = if elapsed >= 2 { format!("{} {}s", indicator, elapsed) } else { indicator };
+    if state.accessible { format!("({}) ", label) } else { format!("{} ", label) }
+}
+
+/// Bordered block for a panel that can take keyboard focus: a thick cyan
+/// border and bold title while focused, dimmed while another panel is.
+fn focus_block(state: &AppState, panel: ActivePanel) -> Block<'static> {
+    let (color, border_type, title_style) = if state.active_panel == panel {
+        (Color::Cyan, BorderType::Thick, Style::default().add_modifier(Modifier::BOLD))
+    } else if state.active_panel == ActivePanel::None {
+        (Color::Gray, BorderType::Plain, Style::default())
+    } else {
+        (Color::DarkGray, BorderType::Plain, Style::default().fg(Color::DarkGray))
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .border_style(Style::default().fg(color))
+        .title_style(title_style)
+}
+
+/// "E:12 W:40 I:310" in the levels' colors, debug only when there is some.
+fn level_count_spans(counts: LevelCounts) -> Vec<Span<'static>> {
+    let levels = [
+        ("E", counts.error, Color::Red),
+        ("W", counts.warn, Color::Yellow),
+        ("I", counts.info, Color::Green),
+        ("D", counts.debug, Color::Gray),
+    ];
+    levels
+        .into_iter()
+        .filter(|&(label, count, _)| label != "D" || count > 0)
+        .map(|(label, count, color)| {
+            let style = if count > 0 { Style::default().fg(color) } else { Style::default().fg(Color::DarkGray) };
+            Span::styled(format!("{}:{} ", label, count), style)
+        })
+        .collect()
+}
+
+fn draw_logs_wide(frame: &mut Frame, area: Rect, state: &AppState, _terminal_size: Rect) {
+    let help_text = match state.active_panel {
+        ActivePanel::Logs if state.selected_log_index.is_some() => {
+            " ↑/↓: navigate | Enter: expand/collapse | [/]: 5 lines | a: actions | d: diff | c: copy | x: hide similar | ESC: deselect "
+        }
+        ActivePanel::Logs if state.zoomed => " ↑/↓: select & navigate | [/]: jump 5 lines | z: restore layout | ESC: unfocus panel ",
+        ActivePanel::Logs => " ↑/↓: select & navigate | [/]: jump 5 lines | </>: time cursor | {/} (/): by time | t: ages | :: go to | z: zoom | ESC: unfocus panel ",
+        ActivePanel::None => " TAB: focus this panel ",
+        ActivePanel::Metrics => " TAB: switch to this panel ",
+    };
+    
+    // Count how many logs are marked as new
+    let new_count = state.logs.iter().filter(|log| log.is_new).count();
+    let focus_marker = if state.accessible && state.active_panel == ActivePanel::Logs { "[ACTIVE] " } else { "" };
+    let mut counts = format!("{} entries", state.all_logs.len());
+    if new_count > 0 {
+        counts.push_str(&format!(", {} new", new_count));
+    }
+    if state.hidden_log_count > 0 {
+        counts.push_str(&format!(", {} hidden", state.hidden_log_count));
+    } else if state.ignore.show_hidden {
+        counts.push_str(", showing hidden");
+    }
+    if !state.bookmarks.is_empty() {
+        counts.push_str(&format!(", {} bookmarked", state.bookmarks.len()));
+    }
+    if state.log_filter.is_active() {
+        counts.push_str(&format!(", only {}", state.log_filter.describe()));
+    }
+    let mut title = vec![Span::raw(format!(
+        " {}{}Loki Logs [{}] ",
+        focus_marker,
+        refresh_spinner(state, RefreshScope::Logs),
+        counts
+    ))];
+    if !state.all_logs.is_empty() {
+        title.extend(level_count_spans(state.log_level_counts));
+    }
+    title.push(Span::raw(format!("{} ", help_text)));
+    let mut logs_block = focus_block(state, ActivePanel::Logs)
+        .title(Line::from(title))
+        .title_bottom(staleness_line("logs", state.logs_last_success, &state.logs_backoff, state));
+    
+    // Log volume histogram along the bottom border
+    if !state.log_volume.is_empty() {
+        let total: u64 = state.log_volume.iter().sum();
+        // The minute under the time cursor, counted back from the newest bucket
+        let cursor_bucket = state.time_cursor.and_then(|t| {
+            let minutes_ago = ((Local::now().timestamp() as f64 - t) / 60.0).floor().max(0.0) as usize;
+            state.log_volume.len().checked_sub(minutes_ago + 1)
+        });
+        let volume = if state.accessible {
+            let peak = state.log_volume.iter().max().copied().unwrap_or(0);
+            let cursor = match (state.time_cursor, cursor_bucket) {
+                (Some(t), Some(bucket)) => format!(", {} at {}", state.log_volume[bucket], format_time(t, "%H:%M")),
+                _ => String::new(),
+            };
+            Line::from(format!(" Volume 30m: {} logs, peak {}/min{} ", total, peak, cursor))
+        } else {
+            let bars = sparkline(&state.log_volume.iter().map(|&v| v as f64).collect::<Vec<_>>());
+            let mut spans = vec![Span::raw(" Volume 30m ")];
+            spans.extend(bars.chars().enumerate().map(|(idx, bar)| match cursor_bucket {
+                // An empty minute still needs something to highlight
+                Some(bucket) if bucket == idx => Span::styled(
+                    if bar == ' ' { "▁".to_string() } else { bar.to_string() },
+                    Style::default().fg(Color::Black).bg(Color::Cyan),
+                ),
+                _ => Span::raw(bar.to_string()),
+            }));
+            spans.push(Span::raw(format!(" ({} logs) ", total)));
+            Line::from(spans)
+        };
+        logs_block = logs_block.title_bottom(volume.right_aligned());
+    }
+
+    if !state.logs.is_empty() {
+        // Get the available width for log messages
+        let available_width = area.width.saturating_sub(4) as usize; // Account for borders
+        
+        // Overlaid queries get a chip with their name, as wide as the longest
+        let chip_width = state.log_queries.iter().map(|query| text::width(&query.name)).max().map(|width| width.min(12));
+        // Numbers as wide as the last line's, counted over the whole buffer
+        let gutter_width = state.show_line_numbers.then(|| state.all_logs.len().max(1).to_string().len());
+        
+        let log_items: Vec<ListItem> = state
+            .logs
+            .iter()
+            .enumerate()
+            .map(|(index, log)| {
+                let chip = chip_width.map(|width| source_chip(log.source, &state.log_queries, width, state.accessible));
+                let chip_len = chip.as_ref().map_or(0, |chip| text::width(&chip.content));
+                // Acknowledged and ignored patterns aren't highlighted as new; ignored ones are dimmed
+                let triaged = state.triage.log_state(&log.message);
+                let is_new = log.is_new && matches!(triaged, None | Some(TriageState::Investigating));
+                let ignored = triaged == Some(TriageState::Ignored);
+                // Determine color based on whether log is new or not
+                let level_color = match log.level.as_str() {
+                    _ if ignored => Color::DarkGray,
+                    "ERROR" => Color::Red,
+                    "WARN" => Color::Yellow,
+                    "INFO" => Color::Green,
+                    "DEBUG" => Color::Gray,
+                    _ => Color::White,
+                };
+
+                // Calculate the width needed for the level and, if shown, the age
+                let level_str = format!("[{:5}]", log.level); // Fixed width for alignment
+                let age = if state.show_log_ages {
+                    format!("{:>4} ", humanize_age(log_age_seconds(log, state)))
+                } else {
+                    String::new()
+                };
+                let gutter = gutter_width
+                    .map(|width| format!("{:>width$} ", state.log_scroll_offset + index + 1))
+                    .unwrap_or_default();
+                let prefix_len = gutter.len() + chip_len + age.len() + level_str.len() + 1; // +1 for space
+                
+                // Markers: arrows and triangles normally, words in accessible mode
+                let (new_marker, plain_marker, expanded_marker, truncated_marker) = if state.accessible {
+                    ("NEW ", "    ", " [expanded]", " [more]")
+                } else {
+                    ("→ ", "  ", " ▼", " ▶")
+                };
+                let bookmark_marker = if state.accessible { "MARK" } else { "★ " };
+                let is_bookmarked = state.bookmarks.contains(&log.nanos);
+                
+                // Calculate available width for message
+                let marker_width = new_marker.chars().count();
+                let message_width = available_width.saturating_sub(prefix_len + marker_width - 2);
+                
+                // Check if this log is selected
+                // Like the metrics table, the selection only shows while the arrows move it
+                // The line at the time cursor stays highlighted too
+                let is_selected = (state.active_panel == ActivePanel::Logs || state.time_cursor.is_some()) && state.selected_log_index
+                    .map(|selected| selected == state.log_scroll_offset + index)
+                    .unwrap_or(false);
+                let age_style = if is_selected {
+                    Style::default().bg(Color::DarkGray).fg(Color::Gray)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                
+                // Check if this log is expanded
+                let is_expanded = state.expanded_log_index
+                    .map(|expanded| expanded == state.log_scroll_offset + index)
+                    .unwrap_or(false);
+                
+                // Handle message display based on expanded state
+                let (message_lines, is_truncated) = if is_expanded {
+                    // Show full message, wrapped across multiple lines
+                    (wrap_message(&log.message, message_width), false)
+                } else {
+                    // Normal display - truncate if needed
+                    let (line, truncated) = truncate_message(&log.message, message_width);
+                    (vec![line], truncated)
+                };
+                
+                let style = if is_selected {
+                    Style::default().bg(Color::DarkGray).fg(Color::White)
+                } else if is_new {
+                    // Make the entire new log line stand out with brighter text
+                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
+                } else if ignored {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+
+                let mut content = Vec::new();
+                
+                // Build the first line with level and first message line
+                if let Some(first_message) = message_lines.first() {
+                    if is_new {
+                        // New logs: Add a special marker and highlight
+                        let mut line_spans = vec![
+                            Span::styled(
+                                new_marker,  // Arrow indicator for new logs
+                                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                            ),
+                            Span::styled(gutter.clone(), age_style),
+                            chip.clone().unwrap_or_default(),
+                            Span::styled(age.clone(), age_style),
+                            Span::styled(
+                                level_str.clone(),
+                                if is_selected {
+                                    Style::default().bg(Color::DarkGray).fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                                } else {
+                                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                                },
+                            ),
+                            Span::raw(" "),
+                            Span::styled(
+                                first_message.clone(),
+                                if is_selected {
+                                    Style::default().bg(Color::DarkGray).fg(Color::White)
+                                } else {
+                                    Style::default().fg(Color::Yellow)
+                                },
+                            ),
+                        ];
+                        
+                        // Add expand/collapse indicator if truncated or expanded
+                        if is_expanded {
+                            line_spans.push(Span::styled(expanded_marker, Style::default().fg(Color::Cyan)));
+                        } else if is_truncated {
+                            line_spans.push(Span::styled(truncated_marker, Style::default().fg(Color::Cyan)));
+                        }
+                        
+                        if triaged == Some(TriageState::Investigating) {
+                            line_spans.push(Span::styled(" [investigating]", Style::default().fg(Color::Cyan)));
+                        }
+                        content.push(Line::from(line_spans));
+                    } else {
+                        // Normal logs
+                        let mut line_spans = vec![
+                            if is_selected && state.accessible {
+                                Span::raw(">   ")
+                            } else if is_bookmarked {
+                                Span::styled(bookmark_marker, Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))
+                            } else {
+                                Span::raw(plain_marker) // Spacing to align with new logs
+                            },
+                            Span::styled(gutter.clone(), age_style),
+                            chip.clone().unwrap_or_default(),
+                            Span::styled(age.clone(), age_style),
+                            Span::styled(
+                                level_str.clone(),
+                                if is_selected {
+                                    Style::default().bg(Color::DarkGray).fg(level_color).add_modifier(Modifier::BOLD)
+                                } else {
+                                    Style::default().fg(level_color).add_modifier(Modifier::BOLD)
+                                },
+                            ),
+                            Span::raw(" "),
+                            Span::styled(
+                                first_message.clone(),
+                                style,
+                            ),
+                        ];
+                        
+                        // Add expand/collapse indicator if truncated or expanded
+                        if is_expanded {
+                            line_spans.push(Span::styled(expanded_marker, Style::default().fg(Color::Cyan)));
+                        } else if is_truncated {
+                            line_spans.push(Span::styled(truncated_marker, Style::default().fg(Color::Cyan)));
+                        }
+                        
+                        if triaged == Some(TriageState::Investigating) {
+                            line_spans.push(Span::styled(" [investigating]", Style::default().fg(Color::Cyan)));
+                        }
+                        content.push(Line::from(line_spans));
+                    }
+                    
+                    // Add continuation lines if expanded
+                    if is_expanded && message_lines.len() > 1 {
+                        for continuation_line in &message_lines[1..] {
+                            // Add indentation to align with the message part
+                            let indent = " ".repeat(prefix_len + marker_width); // Align past the arrow/spacing
+                            content.push(Line::from(vec![
+                                Span::raw(indent),
+                                Span::styled(
+                                    continuation_line.clone(),
+                                    if is_selected {
+                                        Style::default().bg(Color::DarkGray).fg(Color::White)
+                                    } else if is_new {
+                                        Style::default().fg(Color::Yellow)
+                                    } else {
+                                        style
+                                    },
+                                ),
+                            ]));
+                        }
+                    }
+                };
+
+                ListItem::new(content)
+            })
+            .collect();
+
+        let logs_list = List::new(log_items)
+            .block(logs_block)
+            .style(Style::default().fg(Color::White));
+        
+        frame.render_widget(logs_list, area);
+    } else {
+        let message = if !state.has_initial_fetch {
+            vec![Line::from("Waiting for the first fetch from Loki...")]
+        } else if state.logs_last_success.is_none() {
+            vec![Line::from(format!("Could not reach Loki at {}", state.loki_url))]
+        } else {
+            vec![
+                Line::from("No logs found in Loki"),
+                Line::from("rustdash looks for streams labelled service_name, app, host or level;"),
+                Line::from("check the labels your log shipper sets, or try `rustdash --demo`"),
+            ]
+        };
+        let no_logs = Paragraph::new(message)
+            .style(Style::default().fg(Color::Gray))
+            .block(logs_block)
+            .alignment(Alignment::Center);
+        
+        frame.render_widget(no_logs, area);
+    }
+}
+
+/// Seconds since `log` was written, by Loki's clock so that skew between the
+/// machines doesn't show as lines from the future or the past.
+pub fn log_age_seconds(log: &LogEntry, state: &AppState) -> i64 {
+    let skew_ms = (state.loki_clock_skew.unwrap_or(0.0) * 1000.0) as i64;
+    (chrono::Utc::now().timestamp_millis() + skew_ms - log.nanos / 1_000_000) / 1000
+}
+
+/// Wrap a log message into lines of at most `width` columns, preferring to
+/// break at a space in the second half of the line.
+pub fn wrap_message(message: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut remaining = message;
+    
+    while !remaining.is_empty() {
+        if text::width(remaining) <= width {
+            lines.push(remaining.to_string());
+            break;
+        } else {
+            // A character wider than the line still takes one
+            let mut line = text::take_width(remaining, width);
+            if line.is_empty() {
+                line = &remaining[..remaining.chars().next().map_or(0, char::len_utf8)];
+            }
+            // Find a good break point (space, if possible)
+            let mut break_point = line.len();
+            if let Some(space_pos) = line.rfind(' ') {
+                if space_pos > line.len() / 2 {
+                    break_point = space_pos;
+                }
+            }
+            lines.push(remaining[..break_point].to_string());
+            remaining = remaining[break_point..].trim_start();
+        }
+    }
+    lines
+}
+
+/// Truncate a log message to `width` columns with an ellipsis. Returns the
+/// line and whether anything was cut off.
+pub fn truncate_message(message: &str, width: usize) -> (String, bool) {
+    if text::width(message) > width && width > 20 {
+        // Add ellipsis if message is too long
+        (format!("{}...", text::take_width(message, width.saturating_sub(3))), true)
+    } else {
+        (message.to_string(), false)
+    }
+}
+
+fn draw_drill_in(frame: &mut Frame, area: Rect, state: &AppState, drill_in: &UriDrillIn) {
+    let window = match (state.metrics_time_range.as_minutes(), state.history.since()) {
+        (Some(_), _) => format!("last {}", state.metrics_time_range.as_str()),
+        (None, Some(since)) => format!("observed since {}", format_time(since, "%m-%d %H:%M")),
+        (None, None) => "observed".to_string(),
+    };
+    let focus_marker = if state.accessible { "[ACTIVE] " } else { "" };
+    let block = Block::default()
+        .title(format!(
+            " {}{} - {} [←/→: cursor, v: inspect, X/J: export, g/ESC: close] ",
+            focus_marker, drill_in.uri, window
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let history = match (&drill_in.history, &drill_in.error) {
+        (Some(history), _) if !history.latency_ms.is_empty() => history,
+        (_, Some(error)) => {
+            let error_widget = Paragraph::new(format!("Range query failed: {}", error))
+                .style(Style::default().fg(Color::Red))
+                .wrap(ratatui::widgets::Wrap { trim: true });
+            frame.render_widget(error_widget, inner);
+            return;
+        }
+        (Some(_), None) => {
+            let message = if state.metrics_time_range.as_minutes().is_some() {
+                "No samples for this URI in the selected window"
             } else {
-                Color::Red
-            };
-            
-            // Create the bar visualization
-            let bar_filled = ((uri_metric.avg_duration_ms / max_duration) * bar_width as f64) as usize;
-            let bar_filled = bar_filled.min(bar_width);
-            
-            let bar_char = match duration_color {
-                Color::Green => "█",
-                Color::Yellow => "█",
-                Color::Red => "█",
-                _ => "█",
+                "Not enough samples recorded for this URI yet"
             };
-            
-            let bar_string = format!("{:█<width$}", "", width = bar_filled)
-                .replace(' ', bar_char);
-            let bar_empty = " ".repeat(bar_width.saturating_sub(bar_filled));
-            
-            // Build the line with proper spacing
-            let uri_part = format!("{:<width$}", display_uri, width = uri_column_width);
-            let ms_part = format!("{:>width$.1}", uri_metric.avg_duration_ms, width = ms_width);
-            let req_part = format!("{:>width$.0}", uri_metric.request_count, width = req_width);
-            
-            let line_spans = vec![
-                Span::styled(
-                    uri_part,
-                    Style::default().fg(Color::White),
-                ),
-                Span::styled(
-                    bar_string,
-                    Style::default().fg(duration_color),
-                ),
-                Span::styled(
-                    bar_empty,
-                    Style::default(),
-                ),
-                Span::styled(
-                    format!(" {}", ms_part),
-                    Style::default().fg(duration_color).add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(
-                    req_part,
-                    Style::default().fg(Color::Cyan),
-                ),
-            ];
-            
-            lines.push(Line::from(line_spans));
+            let empty = Paragraph::new(message)
+                .style(Style::default().fg(Color::Gray))
+                .alignment(Alignment::Center);
+            frame.render_widget(empty, inner);
+            return;
         }
-        
-        // Add total requests/sec and scale info at the bottom
-        if !lines.is_empty() {
-            lines.push(Line::from(""));
-            
-            // Add scale and period info
-            let scale_text = format!("Scale: █ = {:.0}ms", max_duration);
-            let period_text = match state.metrics_time_range {
-                TimeRange::OneMin => "1-minute average",
-                TimeRange::FiveMin => "5-minute average",
-                TimeRange::ThirtyMin => "30-minute average",
-                TimeRange::OneHour => "1-hour average",
-                TimeRange::OneDay => "24-hour average",
-                TimeRange::All => "All-time average",
-            };
-            lines.push(Line::from(vec![
-                Span::styled(
-                    format!("Total Req/s: {:.2}  |  {}  |  {}", 
-                        metrics.http_requests_total, scale_text, period_text),
-                    Style::default().fg(Color::Gray),
-                ),
-            ]));
+        (None, None) => {
+            let loading = Paragraph::new("Loading history...")
+                .style(Style::default().fg(Color::Yellow))
+                .alignment(Alignment::Center);
+            frame.render_widget(loading, inner);
+            return;
         }
-        
-        // If no URI metrics but we have total
-        if metrics.uri_metrics.is_empty() && metrics.http_requests_total > 0.0 {
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![
-                Span::styled(
-                    "No per-URI metrics available",
-                    Style::default().fg(Color::Gray),
-                ),
-            ]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![
-                Span::styled(
-                    format!("Total Requests/sec: {:.2}", metrics.http_requests_total),
-                    Style::default().fg(Color::Green),
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Summary and cursor readout
+            Constraint::Min(5),    // Latency chart
+            Constraint::Min(5),    // Request rate chart
+        ])
+        .split(inner);
+
+    let stats_line = |label: &str, unit: &str, points: &[(f64, f64)]| match series_stats(points) {
+        Some((min, max, avg, p95)) => Line::from(format!(
+            "{:<12} min {:.1}{unit}  max {:.1}{unit}  avg {:.1}{unit}  p95 {:.1}{unit}",
+            label, min, max, avg, p95
+        )),
+        None => Line::from(format!("{:<12} no data", label)),
+    };
+    let cursor = drill_in.cursor_index();
+    let cursor_line = match cursor.and_then(|idx| history.latency_ms.get(idx)) {
+        Some(&(t, latency)) => {
+            // Request samples can be missing where latency isn't, so match by time
+            let requests = history
+                .requests_per_min
+                .iter()
+                .find(|(rt, _)| *rt == t)
+                .map_or("-".to_string(), |(_, v)| format!("{:.0}", v));
+            Line::from(Span::styled(
+                format!(
+                    "Cursor {}{}  latency {:.1}ms {}  req/min {}",
+                    format_time(t, "%H:%M:%S"),
+                    if drill_in.cursor.is_none() { " (latest)" } else { "" },
+                    latency,
+                    latency_label(latency),
+                    requests
                 ),
-            ]));
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ))
         }
-        
-        let metrics_content = Paragraph::new(lines)
-            .alignment(Alignment::Left);
-        
-        frame.render_widget(metrics_content, inner);
-    } else {
-        let no_data = Paragraph::new("No metrics data available")
+        None => Line::from(""),
+    };
+    let summary = Paragraph::new(vec![
+        stats_line("Latency", "ms", &history.latency_ms),
+        stats_line("Req/min", "", &history.requests_per_min),
+        cursor_line,
+    ]);
+    frame.render_widget(summary, rows[0]);
+
+    let cursor_time = cursor.and_then(|idx| history.latency_ms.get(idx)).map(|(t, _)| *t);
+    draw_history_chart(frame, rows[1], "Latency (ms)", &history.latency_ms, Color::Yellow, cursor_time);
+    draw_history_chart(frame, rows[2], "Requests/min", &history.requests_per_min, Color::Green, cursor_time);
+}
+
+/// The buffered log lines around the time cursor, under the drill-in chart,
+/// so moving its cursor shows what was logged at that moment.
+fn draw_cursor_logs(frame: &mut Frame, area: Rect, state: &AppState) {
+    let title = match state.time_cursor {
+        Some(t) => format!(" Logs at {} ", format_time(t, "%H:%M:%S")),
+        None => " Latest logs ".to_string(),
+    };
+    let block = Block::default().title(title).borders(Borders::ALL).border_style(Style::default().fg(Color::DarkGray));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = inner.height as usize;
+    let total = state.all_logs.len();
+    let start = match state.selected_log_index.filter(|_| state.time_cursor.is_some()) {
+        Some(idx) => idx.saturating_sub(rows / 2).min(total.saturating_sub(rows)),
+        None => total.saturating_sub(rows),
+    };
+    let lines: Vec<Line> = state
+        .all_logs
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(rows)
+        .map(|(idx, log)| {
+            let at_cursor = state.time_cursor.is_some() && state.selected_log_index == Some(idx);
+            let prefix = format!(
+                "{}{} [{:5}] ",
+                if at_cursor && state.accessible { "> " } else { "  " },
+                format_time(log.nanos as f64 / 1e9, "%H:%M:%S"),
+                log.level
+            );
+            let message_width = (inner.width as usize).saturating_sub(text::width(&prefix));
+            let line = format!("{}{}", prefix, text::truncate(&log.message, message_width, Truncation::End));
+            if at_cursor {
+                Line::from(Span::styled(line, Style::default().bg(Color::DarkGray).fg(Color::White)))
+            } else {
+                Line::from(line)
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_system(frame: &mut Frame, area: Rect, view: &SystemView) {
+    let block = Block::default()
+        .title(format!(" Local system [by {}] [s: sort, ↑/↓: scroll, L/ESC: close] ", view.sort.as_str()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let Some(snapshot) = &view.snapshot else {
+        let loading = Paragraph::new("Sampling the host...").style(Style::default().fg(Color::Yellow)).alignment(Alignment::Center);
+        frame.render_widget(loading, inner);
+        return;
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(3), Constraint::Min(1)])
+        .split(inner);
+    let host = snapshot.host.as_deref().unwrap_or("this host");
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(host.to_string(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::raw(format!(
+                "  load {:.2} {:.2} {:.2} ({} CPUs)  up {}  {} processes",
+                snapshot.load[0],
+                snapshot.load[1],
+                snapshot.load[2],
+                snapshot.cpu_count,
+                humanize_age(snapshot.uptime_seconds as i64),
+                snapshot.processes.len()
+            )),
+        ])),
+        rows[0],
+    );
+
+    let ratio = |used: u64, total: u64| if total == 0 { 0.0 } else { (used as f64 / total as f64).clamp(0.0, 1.0) };
+    let gauge_color = |ratio: f64| if ratio >= 0.9 { Color::Red } else if ratio >= 0.7 { Color::Yellow } else { Color::Green };
+    let gauges = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3); 3])
+        .split(rows[1]);
+    let cpu = f64::from(snapshot.cpu_pct / 100.0).clamp(0.0, 1.0);
+    let memory = ratio(snapshot.memory_used, snapshot.memory_total);
+    let swap = ratio(snapshot.swap_used, snapshot.swap_total);
+    for (area, title, value, label) in [
+        (gauges[0], "CPU", cpu, format!("{:.0}%", snapshot.cpu_pct)),
+        (
+            gauges[1],
+            "Memory",
+            memory,
+            format!("{} / {}", humanize_bytes(snapshot.memory_used as f64), humanize_bytes(snapshot.memory_total as f64)),
+        ),
+        (
+            gauges[2],
+            "Swap",
+            swap,
+            format!("{} / {}", humanize_bytes(snapshot.swap_used as f64), humanize_bytes(snapshot.swap_total as f64)),
+        ),
+    ] {
+        let gauge = Gauge::default()
+            .block(Block::default().title(format!(" {} ", title)).borders(Borders::ALL))
+            .gauge_style(Style::default().fg(gauge_color(value)))
+            .ratio(value)
+            .label(label);
+        frame.render_widget(gauge, area);
+    }
+
+    // Listening ports sit beside the processes, where /proc/net can be read
+    let (process_area, tcp_area) = match &snapshot.tcp {
+        Some(_) => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(40), Constraint::Length(54)])
+                .split(rows[2]);
+            (columns[0], Some(columns[1]))
+        }
+        None => (rows[2], None),
+    };
+    if let (Some(ports), Some(area)) = (&snapshot.tcp, tcp_area) {
+        draw_tcp_ports(frame, area, ports);
+    }
+
+    let name_width = usize::from(process_area.width).saturating_sub(8 + 9 + 11 + 8).max(8);
+    let marker = |sort: ProcessSort| if view.sort == sort { "▼" } else { " " };
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:>7} {:<name_width$}{:>8}{} {:>9}{} {:>6}",
+            "PID",
+            "NAME",
+            "CPU%",
+            marker(ProcessSort::Cpu),
+            "MEM",
+            marker(ProcessSort::Memory),
+            "MEM%",
+        ),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    ))];
+    let processes = snapshot.sorted(view.sort);
+    let visible = usize::from(process_area.height.saturating_sub(1));
+    for process in processes.iter().skip(view.scroll_offset).take(visible) {
+        let memory_pct = ratio(process.memory_bytes, snapshot.memory_total) * 100.0;
+        let style = if process.cpu_pct >= 80.0 || memory_pct >= 20.0 { Style::default().fg(Color::Yellow) } else { Style::default() };
+        lines.push(Line::styled(
+            format!(
+                "{:>7} {:<name_width$}{:>8.1}  {:>9}  {:>6.1}",
+                process.pid,
+                text::truncate(&process.name, name_width, Truncation::End),
+                process.cpu_pct,
+                humanize_bytes(process.memory_bytes as f64),
+                memory_pct,
+            ),
+            style,
+        ));
+    }
+    frame.render_widget(Paragraph::new(lines), process_area);
+}
+
+fn draw_tcp_ports(frame: &mut Frame, area: Rect, ports: &[PortConnections]) {
+    let block = Block::default().title(format!(" TCP by listening port ({}) ", ports.len())).borders(Borders::LEFT);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{:>6} {:>6} {:>7} {:>7} {:>7} {:>6} {:>7}", "PORT", "ESTAB", "SYN_RCV", "T_WAIT", "C_WAIT", "OTHER", "QUEUE"),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    ))];
+    lines.extend(ports.iter().map(|port| {
+        // A full accept queue drops connections; CLOSE_WAITs pile up when the application doesn't close them
+        let style = if port.queue_full() {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else if port.close_wait > 0 || port.syn_recv > 0 {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        Line::styled(
+            format!(
+                "{:>6} {:>6} {:>7} {:>7} {:>7} {:>6} {:>7}",
+                port.port,
+                port.established,
+                port.syn_recv,
+                port.time_wait,
+                port.close_wait,
+                port.other,
+                format!("{}/{}", port.accept_queue, port.backlog),
+            ),
+            style,
+        )
+    }));
+    if ports.is_empty() {
+        lines.push(Line::styled("No listening ports", Style::default().fg(Color::DarkGray)));
+    }
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_service_map(frame: &mut Frame, area: Rect, state: &AppState, service_map: &ServiceMap) {
+    let focus_marker = if state.accessible { "[ACTIVE] " } else { "" };
+    let block = Block::default()
+        .title(format!(
+            " {}Service Map [{}] [↑/↓: scroll, m/ESC: close] ",
+            focus_marker,
+            state.metrics_time_range.as_str()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let edges = match (&service_map.edges, &service_map.error) {
+        (_, Some(error)) => {
+            let error_widget = Paragraph::new(format!("Service graph query failed: {}", error))
+                .style(Style::default().fg(Color::Red))
+                .wrap(ratatui::widgets::Wrap { trim: true });
+            frame.render_widget(error_widget, inner);
+            return;
+        }
+        (None, None) => {
+            let loading = Paragraph::new("Loading service graph...")
+                .style(Style::default().fg(Color::Yellow))
+                .alignment(Alignment::Center);
+            frame.render_widget(loading, inner);
+            return;
+        }
+        (Some(edges), None) if edges.is_empty() => {
+            let empty = Paragraph::new(vec![
+                Line::from("No service graph metrics found"),
+                Line::from("Enable Tempo's service graph processor to export traces_service_graph_request_total"),
+            ])
             .style(Style::default().fg(Color::Gray))
             .alignment(Alignment::Center);
-        
-        frame.render_widget(no_data, inner);
+            frame.render_widget(empty, inner);
+            return;
+        }
+        (Some(edges), None) => edges,
+    };
+
+    // Adjacency list: each caller followed by the services it calls
+    let name_width = edges.iter().map(|e| e.server.chars().count()).max().unwrap_or(10).clamp(13, 40);
+    let header = Line::from(Span::styled(
+        format!(
+            "  {:<header_width$}{:>10}  {:>10}  {:>7}  {:>9}",
+            "Caller → callee", "Req/min", "Err/min", "Err %", "p95",
+            header_width = name_width + 2 // Rows indent callees behind an arrow
+        ),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    ));
+    let mut lines = Vec::new();
+    let mut current_client: Option<&str> = None;
+    for edge in edges {
+        if current_client != Some(edge.client.as_str()) {
+            current_client = Some(edge.client.as_str());
+            lines.push(Line::from(Span::styled(
+                edge.client.clone(),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )));
+        }
+        let ratio = edge.error_ratio();
+        let (color, label) = if ratio >= 0.05 {
+            (Color::Red, "HIGH")
+        } else if ratio >= 0.01 {
+            (Color::Yellow, "WARN")
+        } else {
+            (Color::Green, "OK")
+        };
+        let arrow = if state.accessible { "->" } else { "→ " };
+        let severity = if state.accessible { format!(" {}", label) } else { String::new() };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "  {}{:<name_width$}{:>10.1}  {:>10.2}  {:>6.1}%  {:>9}{}",
+                arrow,
+                edge.server,
+                edge.requests_per_min,
+                edge.errors_per_min,
+                ratio * 100.0,
+                edge.p95_ms.map_or("-".to_string(), |ms| format!("{:.0}ms", ms)),
+                severity,
+            ),
+            Style::default().fg(color),
+        )));
+    }
+
+    // The header stays put while the list scrolls
+    let start = service_map.scroll_offset.min(lines.len().saturating_sub(1));
+    let visible: Vec<Line> = std::iter::once(header).chain(lines.into_iter().skip(start)).collect();
+    frame.render_widget(Paragraph::new(visible), inner);
+}
+
+fn draw_panels_page(frame: &mut Frame, area: Rect, state: &AppState, page: &PanelsPage) {
+    let focus_marker = if state.accessible { "[ACTIVE] " } else { "" };
+    let block = Block::default()
+        .title(format!(
+            " {}Panels{} [↑/↓: scroll, TAB: next panel, c: counter rate/increase/raw, s/d: table sort column/direction, $: variables, p/ESC: close] ",
+            focus_marker,
+            variable_selections(state)
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if state.panels.is_empty() {
+        let empty = Paragraph::new(vec![
+            Line::from("No panels configured"),
+            Line::from("Add [[panels]] entries to config.toml, e.g. preset = \"queue_lag\""),
+        ])
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+        frame.render_widget(empty, inner);
+        return;
+    }
+
+    let PageLines { lines, gauges, .. } = panels_page_lines(state, page, inner.width as usize);
+    let start = page.scroll_offset.min(lines.len().saturating_sub(1));
+    frame.render_widget(Paragraph::new(lines.into_iter().skip(start).collect::<Vec<_>>()), inner);
+
+    // Gauge bars go over the blank lines left for them, where scrolled into view
+    for GaugeBar { line, x, width, ratio, color, label } in gauges {
+        let Some(y) = line.checked_sub(start).map(|row| inner.y + row as u16).filter(|y| *y < inner.bottom()) else {
+            continue;
+        };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(color).bg(Color::DarkGray))
+            .use_unicode(true)
+            .ratio(ratio)
+            .label(label);
+        frame.render_widget(gauge, Rect::new(inner.x + x as u16 + 2, y, width as u16, 1));
+    }
+}
+
+/// The variables' selections for the panels page title, e.g. ` namespace=shop`.
+fn variable_selections(state: &AppState) -> String {
+    state.variables.iter().map(|variable| format!(" {}={}", variable.config.name, variable.display())).collect()
+}
+
+/// Width of the panels page inside the screen margin and its borders.
+fn panels_page_width(state: &AppState) -> usize {
+    usize::from(state.last_terminal_width.saturating_sub(4))
+}
+
+/// Lines of the panels page, for scrolling it.
+pub fn panels_page_line_count(state: &AppState, page: &PanelsPage) -> usize {
+    panels_page_lines(state, page, panels_page_width(state)).lines.len()
+}
+
+/// Line of the panels page where panel `idx` starts, to scroll it into view.
+pub fn panels_page_title_line(state: &AppState, page: &PanelsPage, idx: usize) -> Option<usize> {
+    panels_page_lines(state, page, panels_page_width(state)).titles.get(idx).copied()
+}
+
+/// Narrowest cell of a grid of repeated panels.
+const MIN_GRID_CELL_WIDTH: usize = 40;
+
+/// A gauge bar to draw over a blank line: where, how full, its color and label.
+struct GaugeBar {
+    line: usize,
+    x: usize, // Column of the grid cell it belongs to
+    width: usize,
+    ratio: f64,
+    color: Color,
+    label: String,
+}
+
+/// The panels page's lines, the gauge bars to draw over blank ones and
+/// where each panel's title is.
+struct PageLines {
+    lines: Vec<Line<'static>>,
+    gauges: Vec<GaugeBar>,
+    titles: Vec<usize>,
+}
+
+/// The panels one after another, except the copies of a repeated panel,
+/// which go side by side in a grid of as many columns as fit.
+fn panels_page_lines(state: &AppState, page: &PanelsPage, width: usize) -> PageLines {
+    let mut out = PageLines { lines: Vec::new(), gauges: Vec::new(), titles: Vec::new() };
+    let mut idx = 0;
+    while idx < state.panels.len() {
+        let entry = state.panels[idx].repeat.as_ref().map(|(entry, _)| *entry);
+        let copies = match entry {
+            Some(entry) => state.panels[idx..].iter().take_while(|panel| panel.repeat.as_ref().is_some_and(|(from, _)| *from == entry)).count(),
+            None => 1,
+        };
+        let columns = if entry.is_some() { (width / MIN_GRID_CELL_WIDTH).clamp(1, copies) } else { 1 };
+        let cell_width = width / columns;
+        for row in (idx..idx + copies).collect::<Vec<_>>().chunks(columns) {
+            let top = out.lines.len();
+            let mut cells = Vec::new();
+            for (column, &panel) in row.iter().enumerate() {
+                let (lines, gauges) = panel_lines(state, page, panel, cell_width);
+                out.titles.push(top);
+                out.gauges.extend(gauges.into_iter().map(|gauge| GaugeBar { line: top + gauge.line, x: column * cell_width, ..gauge }));
+                cells.push(lines);
+            }
+            if let [lines] = cells.as_slice() {
+                out.lines.extend(lines.iter().cloned());
+                continue;
+            }
+            let height = cells.iter().map(Vec::len).max().unwrap_or(0);
+            for line in 0..height {
+                // A column between cells
+                let spans = cells.iter().flat_map(|lines| fit_line(lines.get(line), cell_width - 1).into_iter().chain([Span::raw(" ")]));
+                out.lines.push(Line::from(spans.collect::<Vec<_>>()));
+            }
+        }
+        idx += copies;
+    }
+    out
+}
+
+/// `line` cut or padded to `width` columns, keeping its styles.
+fn fit_line(line: Option<&Line<'static>>, width: usize) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut used = 0;
+    for span in line.map_or(&[][..], |line| line.spans.as_slice()) {
+        let content = text::take_width(&span.content, width - used).to_string();
+        used += text::width(&content);
+        spans.push(Span::styled(content, span.style));
+    }
+    spans.push(Span::raw(" ".repeat(width - used)));
+    spans
+}
+
+/// One panel's lines at `width` columns, with its gauge bar (on a line of its own).
+fn panel_lines(state: &AppState, page: &PanelsPage, idx: usize, width: usize) -> (Vec<Line<'static>>, Vec<GaugeBar>) {
+    let panel = &state.panels[idx];
+    let mut lines = Vec::new();
+    let mut gauges = Vec::new();
+    let thresholds = if panel.warn.is_finite() || panel.critical.is_finite() {
+        let bound = if panel.critical < panel.warn { "≤" } else { "≥" };
+        format!("  (warn {} {}, critical {} {})", bound, panel.warn, bound, panel.critical)
+    } else {
+        String::new()
+    };
+    let view = panel.counter.map_or(String::new(), |view| format!(" [{}]", view.as_str()));
+    let (marker, title_style) = match (idx == page.selected, state.accessible) {
+        (true, true) => ("> ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED)),
+        (true, false) => ("▶ ", Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        (false, _) => ("", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED)),
+    };
+    lines.push(Line::from(Span::styled(format!("{}{}{}{}", marker, panel.title, view, thresholds), title_style)));
+    if let Some(error) = &panel.error {
+        lines.push(Line::from(Span::styled(format!("  Query failed: {}", error), Style::default().fg(Color::Red))));
+    } else if panel.rows.is_empty()
+        && panel.heatmap.as_ref().is_none_or(|heatmap| heatmap.buckets.is_empty())
+        && panel.table.as_ref().is_none_or(|table| table.rows.is_empty())
+    {
+        lines.push(Line::from(Span::styled("  No series", Style::default().fg(Color::Gray))));
+    }
+    for warning in &panel.warnings {
+        lines.push(Line::from(Span::styled(format!("  Warning: {}", warning), Style::default().fg(Color::Yellow))));
+    }
+    if let Some(heatmap) = panel.heatmap.as_ref().filter(|heatmap| !heatmap.buckets.is_empty()) {
+        lines.extend(heatmap_lines(heatmap, width, state.accessible));
+    }
+    if let Some(table) = panel.table.as_ref().filter(|table| !table.rows.is_empty()) {
+        let sort = table.sort_shifted(page.sort_shift, page.sort_reversed);
+        lines.extend(table_lines(table, sort, &panel.unit, panel.truncation, state.accessible));
+    }
+    if let (Some(style), Some(row)) = (panel.stat, panel.rows.first()) {
+        lines.extend(stat_lines(row, &panel.unit, state.accessible));
+        if let Some(hint) = forecast_span(row) {
+            lines.push(Line::from(vec![Span::raw("  "), hint]));
+        }
+        if let StatStyle::Gauge { min, max } = style {
+            let ratio = style.ratio(row.value);
+            let label = format!("{:.0}% of {}–{}", ratio * 100.0, format_panel_value(min), format_panel_value(max));
+            let (color, _) = severity_style(row.severity);
+            gauges.push(GaugeBar { line: lines.len(), x: 0, width: width.saturating_sub(4).min(60), ratio, color, label });
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(""));
+        return (lines, gauges);
+    }
+    let label_width = panel.rows.iter().map(|r| text::width(&r.label)).max().unwrap_or(0).min(50);
+    for row in &panel.rows {
+        let (color, label) = severity_style(row.severity);
+        let trend = if state.accessible {
+            // Describe the direction instead of drawing it
+            match (row.trend.first(), row.trend.last()) {
+                (Some(first), Some(last)) if last > first => format!("rising from {:.0}", first),
+                (Some(first), Some(last)) if last < first => format!("falling from {:.0}", first),
+                _ => "flat".to_string(),
+            }
+        } else {
+            trend_sparkline(&row.trend)
+        };
+        let mut spans = vec![
+            Span::raw(format!("  {}  ", text::fit(&row.label, label_width, panel.truncation))),
+            Span::styled(
+                format!("{:>12} {:<5}", format_panel_value(row.value), &panel.unit),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" {} ", trend), Style::default().fg(color)),
+            Span::styled(label, Style::default().fg(color)),
+        ];
+        if let Some(hint) = forecast_span(row) {
+            spans.extend([Span::raw("  "), hint]);
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    (lines, gauges)
+}
+
+/// A row's "full in ~3d" hint, redder the sooner it fills up.
+fn forecast_span(row: &PanelRow) -> Option<Span<'static>> {
+    let seconds = row.full_in?;
+    let color = match seconds {
+        s if s < 86400.0 => Color::Red,
+        s if s < 7.0 * 86400.0 => Color::Yellow,
+        _ => Color::Gray,
+    };
+    Some(Span::styled(forecast::describe(seconds), Style::default().fg(color)))
+}
+
+/// A table panel: a header naming the columns, with the sorted one marked,
+/// then its rows with the value colored by severity.
+fn table_lines(table: &Table, sort: TableSort, unit: &str, truncation: Truncation, accessible: bool) -> Vec<Line<'static>> {
+    let rows = table.sorted_rows(sort);
+    let value_header = if unit.is_empty() { "value".to_string() } else { format!("value ({})", unit) };
+    let headers: Vec<&str> = table.columns.iter().map(String::as_str).chain([value_header.as_str()]).collect();
+    let marker = match (sort.descending, accessible) {
+        (true, false) => " ▼",
+        (false, false) => " ▲",
+        (true, true) => " (sorted, descending)",
+        (false, true) => " (sorted, ascending)",
+    };
+    let headers: Vec<String> = headers
+        .iter()
+        .enumerate()
+        .map(|(idx, header)| if idx == sort.column { format!("{}{}", header, marker) } else { header.to_string() })
+        .collect();
+    let values: Vec<String> = rows.iter().map(|row| format_table_value(table.format, row.value)).collect();
+    // Label columns as wide as their widest cell, up to 40
+    let widths: Vec<usize> = (0..table.columns.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| text::width(&row.labels[column]))
+                .chain([text::width(&headers[column])])
+                .max()
+                .unwrap_or(0)
+                .min(40)
+        })
+        .collect();
+    let value_width = values.iter().map(|value| text::width(value)).chain([text::width(&headers[table.columns.len()])]).max().unwrap_or(0);
+
+    let mut header = String::from(" ");
+    for (column, width) in widths.iter().enumerate() {
+        header.push_str(&format!(" {} ", text::fit(&headers[column], *width, truncation)));
+    }
+    header.push_str(&format!(" {:>width$}", headers[table.columns.len()], width = value_width));
+    let mut lines = vec![Line::from(Span::styled(header, Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD)))];
+    for (row, value) in rows.iter().zip(values) {
+        let mut cells = String::from(" ");
+        for (column, width) in widths.iter().enumerate() {
+            cells.push_str(&format!(" {} ", text::fit(&row.labels[column], *width, truncation)));
+        }
+        let (color, label) = severity_style(row.severity);
+        let mut spans = vec![
+            Span::raw(cells),
+            Span::styled(format!(" {:>width$}", value, width = value_width), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        ];
+        if accessible || row.severity != Severity::Ok {
+            spans.push(Span::styled(format!("  {}", label), Style::default().fg(color)));
+        }
+        lines.push(Line::from(spans));
     }
+    if table.rows.len() > rows.len() {
+        lines.push(Line::from(Span::styled(
+            format!("  … {} more", table.rows.len() - rows.len()),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    lines
 }
 
-fn draw_logs_wide(frame: &mut Frame, area: Rect, state: &AppState, _terminal_size: Rect) {
-    let (border_color, help_text) = match state.active_panel {
-        ActivePanel::Logs => {
-            let text = if state.selected_log_index.is_some() {
-                " ↑/↓: navigate | Enter: expand/collapse | [/]: 5 lines | c: copy | ESC: exit "
-            } else {
-                " ↑/↓: select & navigate | [/]: jump 5 lines | ESC: deactivate panel "
-            };
-            (Color::Cyan, text)
-        },
-        ActivePanel::None => (
-            Color::Gray,
-            " TAB: activate this panel "
-        ),
-        _ => (
-            Color::Magenta,
-            " TAB: switch to this panel "
+/// A table value in its panel's format.
+fn format_table_value(format: ValueFormat, value: f64) -> String {
+    match format {
+        ValueFormat::Number => format_panel_value(value),
+        ValueFormat::Bytes => humanize_bytes(value),
+        ValueFormat::Percent => format!("{:.1}%", value),
+        ValueFormat::Seconds => format_ms(value * 1000.0),
+    }
+}
+
+/// Background colors of the overlaid log queries' chips, by query index.
+const SOURCE_COLORS: [Color; MAX_LOG_QUERIES] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Blue,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightRed,
+];
+
+/// Chip naming the overlaid query a log line came from, padded to `width`;
+/// in accessible mode the name in brackets.
+fn source_chip(source: Option<usize>, queries: &[LogQuery], width: usize, accessible: bool) -> Span<'static> {
+    let name = source.and_then(|idx| queries.get(idx)).map_or("", |query| query.name.as_str());
+    let name = text::fit(name, width, Truncation::End);
+    match source {
+        _ if accessible => Span::raw(format!("[{}] ", name)),
+        Some(idx) => Span::styled(
+            format!(" {} ", name),
+            Style::default().bg(SOURCE_COLORS[idx % SOURCE_COLORS.len()]).fg(Color::Black),
         ),
-    };
-    
-    // Count how many logs are marked as new
-    let new_count = state.logs.iter().filter(|log| log.is_new).count();
-    let title = if new_count > 0 {
-        format!(" Loki Logs [{} entries, {} new] {} ", state.all_logs.len(), new_count, help_text)
-    } else {
-        format!(" Loki Logs [{} entries] {} ", state.all_logs.len(), help_text)
-    };
-    let logs_block = Block::default()
-        .title(title)
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(border_color));
+        None => Span::raw(format!(" {} ", name)),
+    }
+}
 
-    if !state.logs.is_empty() {
-        // Get the available width for log messages
-        let available_width = area.width.saturating_sub(4) as usize; // Account for borders
-        
-        let log_items: Vec<ListItem> = state
-            .logs
-            .iter()
+fn severity_style(severity: Severity) -> (Color, &'static str) {
+    match severity {
+        Severity::Ok => (Color::Green, "OK"),
+        Severity::Warn => (Color::Yellow, "WARN"),
+        Severity::Critical => (Color::Red, "CRIT"),
+    }
+}
+
+/// A single-value panel's value in large digits, colored by severity, with
+/// its unit and severity beside it; plain text in accessible mode.
+fn stat_lines(row: &PanelRow, unit: &str, accessible: bool) -> Vec<Line<'static>> {
+    let (color, label) = severity_style(row.severity);
+    let style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+    let value = format_panel_value(row.value);
+    match big_digits(&value).filter(|_| !accessible) {
+        Some(rows) => rows
+            .into_iter()
             .enumerate()
-            .map(|(index, log)| {
-                // Determine color based on whether log is new or not
-                let level_color = match log.level.as_str() {
-                    "ERROR" => Color::Red,
-                    "WARN" => Color::Yellow,
-                    "INFO" => Color::Green,
-                    "DEBUG" => Color::Gray,
-                    _ => Color::White,
-                };
+            .map(|(idx, digits)| {
+                let mut spans = vec![Span::raw("  "), Span::styled(digits, style)];
+                if idx == 1 {
+                    spans.push(Span::styled(format!(" {}  {}", unit, label), Style::default().fg(color)));
+                }
+                Line::from(spans)
+            })
+            .collect(),
+        None => vec![Line::from(vec![
+            Span::styled(format!("  {} {}", value, unit), style),
+            Span::styled(format!("  {}", label), Style::default().fg(color)),
+        ])],
+    }
+}
 
-                // Calculate the width needed for level only (no timestamp)
-                let level_str = format!("[{:5}]", log.level); // Fixed width for alignment
-                let prefix_len = level_str.len() + 1; // +1 for space
-                
-                // Calculate available width for message
-                let message_width = available_width.saturating_sub(prefix_len);
-                
-                // Check if this log is selected
-                let is_selected = state.selected_log_index
-                    .map(|selected| selected == state.log_scroll_offset + index)
-                    .unwrap_or(false);
-                
-                // Check if this log is expanded
-                let is_expanded = state.expanded_log_index
-                    .map(|expanded| expanded == state.log_scroll_offset + index)
-                    .unwrap_or(false);
-                
-                // Handle message display based on expanded state
-                let (message_lines, is_truncated) = if is_expanded {
-                    // Show full message, wrapped across multiple lines
-                    let mut lines = Vec::new();
-                    let mut remaining = log.message.as_str();
-                    
-                    while !remaining.is_empty() {
-                        if remaining.len() <= message_width {
-                            lines.push(remaining.to_string());
-                            break;
-                        } else {
-                            // Find a good break point (space, if possible)
-                            let mut break_point = message_width;
-                            if let Some(space_pos) = remaining[..message_width].rfind(' ') {
-                                if space_pos > message_width / 2 {
-                                    break_point = space_pos;
-                                }
-                            }
-                            lines.push(remaining[..break_point].to_string());
-                            remaining = remaining[break_point..].trim_start();
-                        }
-                    }
-                    (lines, false)
-                } else {
-                    // Normal display - truncate if needed
-                    if log.message.len() > message_width && message_width > 20 {
-                        // Add ellipsis if message is too long
-                        (vec![format!("{}...", &log.message[..message_width.saturating_sub(3)])], true)
-                    } else {
-                        (vec![log.message.clone()], false)
-                    }
-                };
-                
-                let style = if is_selected {
-                    Style::default().bg(Color::DarkGray).fg(Color::White)
-                } else if log.is_new {
-                    // Make the entire new log line stand out with brighter text
-                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default()
-                };
+/// `text` as three rows of box-drawing digits; None if it has characters
+/// the font lacks.
+fn big_digits(text: &str) -> Option<[String; 3]> {
+    let mut rows: [String; 3] = Default::default();
+    for c in text.chars() {
+        let glyph: [&str; 3] = match c {
+            '0' => ["┏━┓", "┃ ┃", "┗━┛"],
+            '1' => [" ┓ ", " ┃ ", " ┻ "],
+            '2' => ["┏━┓", "┏━┛", "┗━━"],
+            '3' => ["┏━┓", " ━┫", "┗━┛"],
+            '4' => ["╻ ╻", "┗━┫", "  ╹"],
+            '5' => ["┏━━", "┗━┓", "┗━┛"],
+            '6' => ["┏━━", "┣━┓", "┗━┛"],
+            '7' => ["━━┓", "  ┃", "  ╹"],
+            '8' => ["┏━┓", "┣━┫", "┗━┛"],
+            '9' => ["┏━┓", "┗━┫", "┗━┛"],
+            '.' => [" ", " ", "▪"],
+            '-' => ["   ", "━━━", "   "],
+            _ => return None,
+        };
+        for (row, part) in rows.iter_mut().zip(glyph) {
+            row.push_str(part);
+            row.push(' ');
+        }
+    }
+    Some(rows)
+}
 
-                let mut content = Vec::new();
-                
-                // Build the first line with level and first message line
-                if let Some(first_message) = message_lines.first() {
-                    if log.is_new {
-                        // New logs: Add a special marker and highlight
-                        let mut line_spans = vec![
-                            Span::styled(
-                                "→ ",  // Arrow indicator for new logs
-                                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
-                            ),
-                            Span::styled(
-                                level_str.clone(),
-                                if is_selected {
-                                    Style::default().bg(Color::DarkGray).fg(Color::Yellow).add_modifier(Modifier::BOLD)
-                                } else {
-                                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-                                },
-                            ),
-                            Span::raw(" "),
-                            Span::styled(
-                                first_message.clone(),
-                                if is_selected {
-                                    Style::default().bg(Color::DarkGray).fg(Color::White)
-                                } else {
-                                    Style::default().fg(Color::Yellow)
-                                },
-                            ),
-                        ];
-                        
-                        // Add expand/collapse indicator if truncated or expanded
-                        if is_expanded {
-                            line_spans.push(Span::styled(" ▼", Style::default().fg(Color::Cyan)));
-                        } else if is_truncated {
-                            line_spans.push(Span::styled(" ▶", Style::default().fg(Color::Cyan)));
-                        }
-                        
-                        content.push(Line::from(line_spans));
-                    } else {
-                        // Normal logs
-                        let mut line_spans = vec![
-                            Span::raw("  "),  // Spacing to align with new logs
-                            Span::styled(
-                                level_str.clone(),
-                                if is_selected {
-                                    Style::default().bg(Color::DarkGray).fg(level_color).add_modifier(Modifier::BOLD)
-                                } else {
-                                    Style::default().fg(level_color).add_modifier(Modifier::BOLD)
-                                },
-                            ),
-                            Span::raw(" "),
-                            Span::styled(
-                                first_message.clone(),
-                                style,
-                            ),
-                        ];
-                        
-                        // Add expand/collapse indicator if truncated or expanded
-                        if is_expanded {
-                            line_spans.push(Span::styled(" ▼", Style::default().fg(Color::Cyan)));
-                        } else if is_truncated {
-                            line_spans.push(Span::styled(" ▶", Style::default().fg(Color::Cyan)));
-                        }
-                        
-                        content.push(Line::from(line_spans));
-                    }
-                    
-                    // Add continuation lines if expanded
-                    if is_expanded && message_lines.len() > 1 {
-                        for continuation_line in &message_lines[1..] {
-                            // Add indentation to align with the message part
-                            let indent = " ".repeat(prefix_len + 2); // +2 for the arrow/spacing
-                            content.push(Line::from(vec![
-                                Span::raw(indent),
-                                Span::styled(
-                                    continuation_line.clone(),
-                                    if is_selected {
-                                        Style::default().bg(Color::DarkGray).fg(Color::White)
-                                    } else if log.is_new {
-                                        Style::default().fg(Color::Yellow)
-                                    } else {
-                                        style
-                                    },
-                                ),
-                            ]));
-                        }
-                    }
-                };
+/// Rows of a histogram heatmap, highest bucket first: one cell per time
+/// slice (the latest that fit), shaded and colored by its rate relative to
+/// the busiest cell, then the bucket's share of the window.
+fn heatmap_lines(heatmap: &Heatmap, width: usize, accessible: bool) -> Vec<Line<'static>> {
+    const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+    const COLORS: [Color; 5] = [Color::DarkGray, Color::Blue, Color::Cyan, Color::Yellow, Color::Red];
+    let label_width = heatmap.buckets.iter().map(|label| text::width(label)).max().unwrap_or(0);
+    // Indent, label, gap, cells, share column
+    let columns = width.saturating_sub(2 + label_width + 1 + 7).max(1);
+    let skip = heatmap.times.len().saturating_sub(columns);
+    let max = heatmap.max_rate();
+    // Square root keeps a quieter second mode visible next to a busy one
+    let level = |rate: f64| {
+        if rate <= 0.0 || max <= 0.0 {
+            0
+        } else {
+            1 + ((rate / max).sqrt() * 3.0).round() as usize
+        }
+    };
 
-                ListItem::new(content)
-            })
-            .collect();
+    let mut lines = Vec::new();
+    for ((label, rates), share) in heatmap.buckets.iter().zip(&heatmap.rates).zip(heatmap.shares()).rev() {
+        let mut spans = vec![Span::raw(format!("  {:>width$} ", label, width = label_width))];
+        spans.extend(rates.iter().skip(skip).map(|rate| {
+            let level = level(*rate).min(4);
+            Span::styled(SHADES[level].to_string(), Style::default().fg(COLORS[level]))
+        }));
+        spans.push(Span::styled(format!(" {:>5.1}%", share * 100.0), Style::default().fg(Color::Gray)));
+        lines.push(Line::from(spans));
+    }
 
-        let logs_list = List::new(log_items)
-            .block(logs_block)
-            .style(Style::default().fg(Color::White));
-        
-        frame.render_widget(logs_list, area);
+    let shown = &heatmap.times[skip..];
+    if let (Some(first), Some(last)) = (shown.first(), shown.last()) {
+        let start = format!("-{}", humanize_age((last - first) as i64));
+        let gap = shown.len().saturating_sub(start.len() + 3);
+        lines.push(Line::from(Span::styled(
+            format!("  {:width$} {}{:gap$}now", "", start, "", width = label_width, gap = gap),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    let legend = if accessible {
+        format!("  Shading: blank = none, ░ ▒ ▓ █ = up to {}/s per cell", format_panel_value(max))
     } else {
-        let no_logs = Paragraph::new("No logs available")
-            .style(Style::default().fg(Color::Gray))
-            .block(logs_block)
-            .alignment(Alignment::Center);
-        
-        frame.render_widget(no_logs, area);
+        format!("  ░ few  ▒  ▓  █ {}/s", format_panel_value(max))
+    };
+    lines.push(Line::from(Span::styled(legend, Style::default().fg(Color::Gray))));
+    lines
+}
+
+fn draw_history_chart(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    points: &[(f64, f64)],
+    color: Color,
+    cursor_time: Option<f64>,
+) {
+    let (x_min, x_max) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.0, last.0.max(first.0 + 1.0)),
+        _ => return,
+    };
+    let y_max = points.iter().map(|(_, v)| *v).fold(0.0_f64, f64::max).max(1.0) * 1.1;
+    let cursor_points = cursor_time.map(|t| [(t, 0.0), (t, y_max)]);
+
+    let mut datasets = vec![Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(points)];
+    if let Some(cursor_points) = &cursor_points {
+        datasets.push(
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::White))
+                .data(cursor_points),
+        );
+    }
+
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(format!(" {} ", title)).borders(Borders::TOP))
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([x_min, x_max])
+                .labels(vec![
+                    Span::raw(format_time(x_min, "%H:%M")),
+                    Span::raw(format_time((x_min + x_max) / 2.0, "%H:%M")),
+                    Span::raw(format_time(x_max, "%H:%M")),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, y_max])
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.0}", y_max / 2.0)),
+                    Span::raw(format!("{:.0}", y_max)),
+                ]),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Min, max, average and 95th percentile of a series' values.
+fn series_stats(points: &[(f64, f64)]) -> Option<(f64, f64, f64, f64)> {
+    if points.is_empty() {
+        return None;
     }
+    let mut values: Vec<f64> = points.iter().map(|(_, v)| *v).collect();
+    values.sort_by(f64::total_cmp);
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    let p95_idx = ((values.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+    Some((values[0], values[values.len() - 1], avg, values[p95_idx]))
+}
+
+/// Local wall-clock time of a unix timestamp.
+pub fn format_time(unix_seconds: f64, format: &str) -> String {
+    DateTime::from_timestamp(unix_seconds as i64, 0)
+        .map(|t| t.with_timezone(&Local).format(format).to_string())
+        .unwrap_or_default()
 }
 
 fn draw_footer(frame: &mut Frame, area: Rect, state: &AppState) {
-    let footer_text = vec![Line::from(vec![
+    let mut spans = vec![
         Span::styled("Status: ", Style::default().fg(Color::Gray)),
         Span::styled(
             &state.status,
@@ -764,7 +4302,47 @@ fn draw_footer(frame: &mut Frame, area: Rect, state: &AppState) {
         Span::styled(" to quit | ", Style::default().fg(Color::Gray)),
         Span::styled("'r'", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
         Span::styled(" to refresh", Style::default().fg(Color::Gray)),
-    ])];
+        Span::raw(" | "),
+        Span::styled(memory_usage(state), Style::default().fg(Color::Gray)),
+    ];
+    // Queries the slow query guard is running reduced
+    let reduced = state.query_guard.reductions().len();
+    if reduced > 0 {
+        let marker = if state.accessible { "WARNING: " } else { "⚠ " };
+        let noun = if reduced == 1 { "query" } else { "queries" };
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            format!("{}{} slow {} reduced (S)", marker, reduced, noun),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ));
+    }
+    // Endpoints turning requests away until their cooldown ends
+    let open = state.circuit_breaker.open_circuits();
+    if let Some(first) = open.first() {
+        let marker = if state.accessible { "CIRCUIT OPEN: " } else { "⛔ " };
+        let backend = match first.backend {
+            Backend::Prometheus => "Prometheus",
+            Backend::Loki => "Loki",
+        };
+        let more = if open.len() > 1 { format!(" (+{})", open.len() - 1) } else { String::new() };
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            format!("{}{} {} cooling down {}s{}", marker, backend, first.path(), first.retry_in.as_secs().max(1), more),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(share) = &state.share {
+        let viewers = share.viewers();
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            format!("Shared on {} ({} {})", share.address, viewers, if viewers == 1 { "viewer" } else { "viewers" }),
+            Style::default().fg(Color::Magenta),
+        ));
+        if let Some(error) = share.accept_error() {
+            spans.push(Span::styled(format!(" can't accept viewers: {}", error), Style::default().fg(Color::Red)));
+        }
+    }
+    let footer_text = vec![Line::from(spans)];
 
     let footer = Paragraph::new(footer_text)
         .block(
@@ -775,4 +4353,185 @@ fn draw_footer(frame: &mut Frame, area: Rect, state: &AppState) {
         .alignment(Alignment::Center);
 
     frame.render_widget(footer, area);
-}
\ No newline at end of file
+}
+
+/// Footer note on the memory held by buffered data, e.g.
+/// "Mem: logs 2.1M (5000/5000) history 310.4K".
+fn memory_usage(state: &AppState) -> String {
+    let mut usage = format!(
+        "Mem: logs {} ({}/{})",
+        humanize_bytes(retention::logs_bytes(&state.all_logs) as f64),
+        state.all_logs.len(),
+        state.log_retention.entries
+    );
+    if state.prometheus_enabled {
+        usage.push_str(&format!(" history {}", humanize_bytes(state.history.bytes() as f64)));
+    }
+    usage
+}
+
+/// Columns of the metrics table after the URI, left to right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricsColumn {
+    Response, // Latency bar and milliseconds
+    Rank,     // The ranking's figure, e.g. Req/min
+    Bandwidth,
+    LastError,
+}
+
+/// The columns after the URI the table has now: bandwidth only when a
+/// response size metric answered, the last error only with a pattern set.
+pub fn metrics_columns(state: &AppState) -> Vec<MetricsColumn> {
+    let mut columns = vec![MetricsColumn::Response, MetricsColumn::Rank];
+    let rows = state.metrics.as_ref().map_or(&[][..], |metrics| metrics.uri_metrics.as_slice());
+    if rows.iter().any(|m| m.bytes_per_sec.is_some()) {
+        columns.push(MetricsColumn::Bandwidth);
+    }
+    if state.uri_error_pattern.is_some() {
+        columns.push(MetricsColumn::LastError);
+    }
+    columns
+}
+
+/// The columns scrolled out of view to the left; the last one always shows.
+pub fn scrolled_metrics_columns(state: &AppState) -> Vec<MetricsColumn> {
+    let columns = metrics_columns(state);
+    let offset = state.metrics_column_offset.min(columns.len() - 1);
+    columns[..offset].to_vec()
+}
+
+/// Header of the right-hand metrics column, which shows the ranking's dimension.
+fn rank_column_title(ranking: Ranking) -> &'static str {
+    match ranking {
+        Ranking::Busiest | Ranking::Slowest => "Req/min",
+        Ranking::Errors => "Err/min",
+        Ranking::Change => "Δ prev",
+    }
+}
+
+fn rank_column_value(ranking: Ranking, uri_metric: &UriMetric) -> String {
+    match ranking {
+        Ranking::Busiest | Ranking::Slowest => format!("{:.0}", uri_metric.request_count),
+        Ranking::Errors => format!("{:.1}", uri_metric.errors_per_min),
+        Ranking::Change => uri_metric
+            .change_pct
+            .map_or("-".to_string(), |pct| format!("{:+.0}%", pct)),
+    }
+}
+
+/// Textual latency severity, using the same thresholds as the bar colors.
+fn latency_label(avg_duration_ms: f64) -> &'static str {
+    if avg_duration_ms < 100.0 {
+        "OK"
+    } else if avg_duration_ms < 500.0 {
+        "SLOW"
+    } else {
+        "HIGH"
+    }
+}
+
+/// Panel value with decimals only where they matter (error percentages, small rates).
+fn format_panel_value(value: f64) -> String {
+    if value.abs() < 10.0 && value.fract() != 0.0 {
+        format!("{:.2}", value)
+    } else {
+        format!("{:.0}", value)
+    }
+}
+
+/// Render values as a one-line block-character sparkline scaled to the maximum.
+fn sparkline(values: &[f64]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().fold(0.0_f64, f64::max);
+    values
+        .iter()
+        .map(|&value| {
+            if value <= 0.0 || max <= 0.0 {
+                ' '
+            } else {
+                LEVELS[((value / max) * 7.0).round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// Sparkline scaled between the series' minimum and maximum, so the shape
+/// of a trend stays visible even when it moves little relative to its level.
+fn trend_sparkline(values: &[f64]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = (max - min).max(f64::EPSILON);
+    values
+        .iter()
+        .map(|&value| LEVELS[(((value - min) / span) * 7.0).round() as usize])
+        .collect()
+}
+
+/// Compact age such as "42s", "3m" or "2h".
+pub fn humanize_age(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m", seconds / 60)
+    } else if seconds < 86400 {
+        format!("{}h", seconds / 3600)
+    } else {
+        format!("{}d", seconds / 86400)
+    }
+}
+
+/// Milliseconds as "850ms", or "1.2s" from a second up.
+fn format_ms(ms: f64) -> String {
+    if ms < 1000.0 {
+        format!("{:.0}ms", ms)
+    } else {
+        format!("{:.1}s", ms / 1000.0)
+    }
+}
+
+/// Compact byte count such as "512B", "1.2K" or "3.4M" (powers of 1024).
+pub fn humanize_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+/// Bottom-border label with the age of a source's data, e.g. " metrics 42s old ".
+/// Turns red once the data is older than two refresh intervals, and says
+/// since when the backend has been failing during an outage, and how.
+fn staleness_line(source: &str, last_success: Option<DateTime<Local>>, backoff: &Backoff, state: &AppState) -> Line<'static> {
+    if let Some(since) = backoff.down_since {
+        let retry = backoff
+            .retry_at
+            .map(|at| format!(", retry in {}", humanize_age((at - Local::now()).num_seconds())))
+            .unwrap_or_default();
+        let failing = match backoff.cause {
+            None | Some(ErrorClass::Unreachable) => "disconnected",
+            Some(class) => class.as_str(),
+        };
+        let text = format!(" {} {} since {}{} ", source, failing, since.format("%H:%M:%S"), retry);
+        return Line::from(Span::styled(text, Style::default().fg(Color::Red))).left_aligned();
+    }
+    let stale_after = (state.refresh_interval_seconds * 2) as i64;
+    let (text, stale) = match last_success {
+        Some(at) => {
+            let age = (Local::now() - at).num_seconds();
+            (format!(" {} {} old ", source, humanize_age(age)), age > stale_after)
+        }
+        None => (format!(" {} no data yet ", source), true),
+    };
+    let text = if stale && state.accessible { format!("{}STALE ", text) } else { text };
+    let color = if stale { Color::Red } else { Color::DarkGray };
+    Line::from(Span::styled(text, Style::default().fg(color))).left_aligned()
+}
@@ -0,0 +1,81 @@
+//! Undo and redo of view changes: the log filter or LogQL query, the
+//! metrics time range and the metrics ranking. `u` steps back to what was
+//! shown before the last change and `Ctrl+R` forward again, so a mistyped
+//! filter mid-incident doesn't cost the context built up before it.
+
+use crate::log_actions::LogFilter;
+use crate::prometheus::Ranking;
+use crate::ui::TimeRange;
+
+/// Changes kept to step back through.
+pub const MAX_UNDO: usize = 50;
+
+/// What the view shows, as far as undo goes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewState {
+    pub log_filter: LogFilter,
+    pub time_range: TimeRange,
+    pub ranking: Ranking,
+}
+
+impl ViewState {
+    /// What going from `self` to `to` changed, for the status line.
+    pub fn describe_change(&self, to: &ViewState) -> String {
+        let mut changes = Vec::new();
+        if self.log_filter != to.log_filter {
+            changes.push(match (&to.log_filter.query, to.log_filter.is_active()) {
+                (Some(query), _) => format!("query {}", query),
+                (None, true) => "log filter".to_string(),
+                (None, false) => "no log filter".to_string(),
+            });
+        }
+        if self.time_range != to.time_range {
+            changes.push(format!("time range {}", to.time_range.as_str()));
+        }
+        if self.ranking != to.ranking {
+            changes.push(format!("ranking by {}", to.ranking.as_str()));
+        }
+        changes.join(", ")
+    }
+}
+
+/// Views stepped away from, to undo back to, and undone ones to redo.
+#[derive(Debug, Clone, Default)]
+pub struct ViewHistory {
+    undo: Vec<ViewState>,
+    redo: Vec<ViewState>,
+}
+
+impl ViewHistory {
+    /// Remember `before`, the view a change is leaving. A new change drops
+    /// what could be redone.
+    pub fn record(&mut self, before: ViewState) {
+        if self.undo.len() == MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+        self.redo.clear();
+    }
+
+    /// The view before the last change, leaving `current` to redo.
+    pub fn undo(&mut self, current: ViewState) -> Option<ViewState> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// The view the last undo left, leaving `current` to undo again.
+    pub fn redo(&mut self, current: ViewState) -> Option<ViewState> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
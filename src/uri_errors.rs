@@ -0,0 +1,59 @@
+//! The most recent ERROR log line per endpoint, joined onto the metrics
+//! table so a slow or failing row comes with a likely reason next to it.
+//!
+//! Lines are matched with a regex built from the row's URI: template
+//! placeholders such as `{id}` match any path segment, so
+//! `/api/orders/{id}` finds `POST /api/orders/1234 failed`.
+
+use crate::loki::LogEntry;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// By default the URI must not continue into a longer path, so
+/// `/api/orders` doesn't claim the errors of `/api/orders/{id}`.
+pub const DEFAULT_PATTERN: &str = r"{uri}(?:[^/\w-]|$)";
+
+/// The `[uri_errors]` config section.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UriErrorsConfig {
+    pub enabled: bool,   // Fetch recent errors and show the "Last error" column
+    pub pattern: String, // Regex a line must match, `{uri}` standing for the row's URI
+}
+
+impl Default for UriErrorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pattern: DEFAULT_PATTERN.to_string(),
+        }
+    }
+}
+
+/// Regex source matching lines about `uri` under `pattern`. Placeholders
+/// (`{id}`) match one path segment and a trailing `*` (a merged template
+/// row) the rest of the path.
+pub fn uri_pattern(uri: &str, pattern: &str) -> String {
+    let placeholder = Regex::new(r"\\\{[^}]*\\\}").expect("valid regex");
+    let escaped = regex::escape(uri);
+    let uri_regex = placeholder.replace_all(&escaped, r#"[^/\s"?]+"#);
+    let uri_regex = match uri_regex.strip_suffix(r"\*") {
+        Some(prefix) => format!(r#"{}[^\s"?]*"#, prefix),
+        None => uri_regex.into_owned(),
+    };
+    pattern.replace("{uri}", &uri_regex)
+}
+
+/// For each of `uris`, the newest of `errors` (oldest first) about it.
+/// URIs whose pattern doesn't compile are skipped.
+pub fn latest_errors<'a>(uris: impl Iterator<Item = &'a str>, errors: &[LogEntry], pattern: &str) -> HashMap<String, LogEntry> {
+    let mut latest = HashMap::new();
+    for uri in uris {
+        let Ok(regex) = Regex::new(&uri_pattern(uri, pattern)) else { continue };
+        if let Some(error) = errors.iter().rev().find(|log| regex.is_match(&log.message)) {
+            latest.insert(uri.to_string(), error.clone());
+        }
+    }
+    latest
+}
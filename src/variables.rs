@@ -0,0 +1,220 @@
+//! Template variables (`$`): `[[variables]]` whose values come from a
+//! label, referenced as `$name` or `${name}` in the panels' queries, label
+//! matchers and titles. Picking another value re-runs the panels, so one
+//! dashboard definition serves every namespace or instance.
+//!
+//! A variable's `metric` selector may reference the variables listed before
+//! it (namespace → deployment → pod): values are looked up in order, so a
+//! downstream variable follows its upstream selection.
+//!
+//! A panel with `repeat = "<variable>"` gets a copy per selected value of
+//! the variable, laid out side by side on the panels page.
+
+use crate::panels::{PanelConfig, Preset};
+use crate::prometheus::{PrometheusClient, PrometheusError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What an "All" selection puts in a query; meant for `=~` matchers.
+pub const ALL_REGEX: &str = ".*";
+
+/// One `[[variables]]` entry from the config file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VariableConfig {
+    pub name: String,           // Referenced as `$name` or `${name}`
+    pub label: String,          // Label whose values the variable takes
+    pub metric: Option<String>, // Only the values on these series, e.g. `kube_pod_info{namespace="$namespace"}`; every value of the label otherwise
+    #[serde(default)]
+    pub include_all: bool, // Offer "All", which matches every value
+    pub default: Option<String>, // Value selected at start; the first value (or All) otherwise
+}
+
+/// A variable, its values and the one picked.
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub config: VariableConfig,
+    pub values: Vec<String>,      // Sorted; empty until loaded
+    pub selected: Option<String>, // None selects all values
+    pub error: Option<String>,    // Last lookup failed; the previous values stand
+}
+
+impl Variable {
+    pub fn new(config: VariableConfig) -> Self {
+        Self {
+            selected: config.default.clone(),
+            config,
+            values: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Take freshly looked up values. A selection no longer among them (as
+    /// after an upstream variable changed) falls back to the default, "All"
+    /// or the first value.
+    pub fn set_values(&mut self, mut values: Vec<String>) {
+        values.sort();
+        values.dedup();
+        let offered = |selected: &Option<String>| match selected {
+            Some(value) => values.contains(value),
+            None => self.config.include_all,
+        };
+        if !offered(&self.selected) {
+            self.selected = match &self.config.default {
+                default @ Some(_) if offered(default) => default.clone(),
+                _ if self.config.include_all => None,
+                _ => values.first().cloned(),
+            };
+        }
+        self.values = values;
+        self.error = None;
+    }
+
+    /// What the picker offers: "All" (None) first if enabled, then the values.
+    pub fn choices(&self) -> Vec<Option<&str>> {
+        let all = self.config.include_all.then_some(None);
+        all.into_iter().chain(self.values.iter().map(|value| Some(value.as_str()))).collect()
+    }
+
+    /// The selection as shown: its value or "All".
+    pub fn display(&self) -> &str {
+        self.selected.as_deref().unwrap_or("All")
+    }
+}
+
+/// `text` with the variables it references replaced by their selections,
+/// an "All" selection by [`ALL_REGEX`]. Unknown names are left alone.
+pub fn substitute(text: &str, variables: &[Variable]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(dollar) = rest.find('$') {
+        out.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match variables.iter().find(|variable| !name.is_empty() && variable.config.name == name) {
+            Some(variable) => {
+                out.push_str(variable.selected.as_deref().unwrap_or(ALL_REGEX));
+                rest = &after[len..];
+            }
+            None => {
+                out.push('$');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Whether `text` references a variable with "All" selected.
+fn references_all(text: &str, variables: &[Variable]) -> bool {
+    variables
+        .iter()
+        .filter(|variable| variable.selected.is_none())
+        .any(|variable| substitute(text, std::slice::from_ref(variable)) != text)
+}
+
+/// Equality matchers with the variables substituted; a matcher on a
+/// variable with "All" selected is dropped, as it would match nothing.
+fn substitute_matchers(labels: &BTreeMap<String, String>, variables: &[Variable]) -> BTreeMap<String, String> {
+    labels
+        .iter()
+        .filter(|(_, value)| !references_all(value, variables))
+        .map(|(label, value)| (label.clone(), substitute(value, variables)))
+        .collect()
+}
+
+/// `config` with the variables' selections filled in.
+pub fn resolve(config: &PanelConfig, variables: &[Variable]) -> PanelConfig {
+    let mut resolved = config.clone();
+    if variables.is_empty() {
+        return resolved;
+    }
+    resolved.title = config.title.as_deref().map(|title| substitute(title, variables));
+    match &mut resolved.preset {
+        Preset::Stat { query, .. } | Preset::Gauge { query, .. } | Preset::Table { query, .. } => {
+            *query = substitute(query, variables);
+        }
+        Preset::Counter { labels, .. } | Preset::Heatmap { labels, .. } => {
+            *labels = substitute_matchers(labels, variables);
+        }
+        Preset::QueueLag { .. } | Preset::Ingress { .. } | Preset::Containers { .. } | Preset::Gpu { .. } => {}
+    }
+    resolved
+}
+
+/// The panels to fetch: every `[[panels]]` entry resolved with the
+/// selections, a repeated one once per selected value of its variable
+/// (every value while "All" is selected) along with its entry and value.
+pub fn expand(configs: &[PanelConfig], variables: &[Variable]) -> Vec<(PanelConfig, Option<(usize, String)>)> {
+    let mut panels = Vec::with_capacity(configs.len());
+    for (entry, config) in configs.iter().enumerate() {
+        let repeated = config.repeat.as_ref().and_then(|name| variables.iter().position(|variable| &variable.config.name == name));
+        let Some(repeated) = repeated else {
+            panels.push((resolve(config, variables), None));
+            continue;
+        };
+        let values = match &variables[repeated].selected {
+            Some(value) => vec![value.clone()],
+            None => variables[repeated].values.clone(),
+        };
+        for value in values {
+            let mut selections = variables.to_vec();
+            selections[repeated].selected = Some(value.clone());
+            panels.push((resolve(config, &selections), Some((entry, value))));
+        }
+    }
+    panels
+}
+
+/// The values `config` offers: its label's values on the series of its
+/// metric (with `upstream` variables substituted), or all of them.
+pub async fn fetch_values(client: &PrometheusClient, config: &VariableConfig, upstream: &[Variable]) -> Result<Vec<String>, PrometheusError> {
+    match &config.metric {
+        Some(metric) => Ok(client
+            .get_series(&substitute(metric, upstream))
+            .await?
+            .into_iter()
+            .filter_map(|mut labels| labels.remove(&config.label))
+            .collect()),
+        None => client.get_label_values(&config.label).await,
+    }
+}
+
+/// Look up every variable's values in order, each with the selections of
+/// the ones before it, so a chain settles in one pass.
+pub async fn load_values(client: &PrometheusClient, variables: &mut [Variable]) {
+    for idx in 0..variables.len() {
+        let (upstream, rest) = variables.split_at_mut(idx);
+        let variable = &mut rest[0];
+        match fetch_values(client, &variable.config, upstream).await {
+            Ok(values) => variable.set_values(values),
+            Err(e) => variable.error = Some(e.to_string()),
+        }
+    }
+}
+
+/// The variable picker while it is open.
+#[derive(Debug, Clone, Default)]
+pub struct VariablePicker {
+    pub variable: usize, // Which variable is being picked
+    pub selected: usize, // Cursor in that variable's choices
+}
+
+impl VariablePicker {
+    /// The picker on `variable`, its cursor on the current selection.
+    pub fn open(variables: &[Variable], variable: usize) -> Self {
+        let selected = variables.get(variable).map_or(0, |current| {
+            current.choices().iter().position(|choice| *choice == current.selected.as_deref()).unwrap_or(0)
+        });
+        Self { variable, selected }
+    }
+}
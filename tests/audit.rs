@@ -0,0 +1,108 @@
+use rustdash::admin::AdminAction;
+use rustdash::audit::{AuditConfig, AuditEntry, AuditLog, RECENT_ENTRIES};
+use rustdash::log_actions::LogFilter;
+use rustdash::ui::{AppState, AuditPopup};
+use std::path::PathBuf;
+
+mod common;
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustdash-audit-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir.join("audit.log")
+}
+
+#[test]
+fn entries_are_appended_as_json_lines_and_read_back() {
+    let path = temp_path("append");
+    let mut log = AuditLog::open(path.clone());
+    let delete = AdminAction::LokiDelete { selector: r#"{app="shop"}"#.to_string(), start: 100, end: 200 };
+    let result: Result<String, String> = Ok("Delete request submitted".to_string());
+    log.record(delete.audit_entry("http://loki:3100").outcome(&result)).unwrap();
+    let failed: Result<String, String> = Err("503 Service Unavailable".to_string());
+    log.record(AdminAction::PrometheusSnapshot.audit_entry("http://prometheus:9090").outcome(&failed)).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(first["action"], "loki_delete");
+    assert_eq!(first["target"], "http://loki:3100");
+    assert_eq!(first["params"]["selector"], r#"{app="shop"}"#);
+    assert_eq!(first["params"]["start"], "100");
+    assert_eq!(first["ok"], true);
+    assert!(chrono::DateTime::parse_from_rfc3339(first["time"].as_str().unwrap()).is_ok());
+
+    // A new session appends after what's there and starts with it in the popup
+    let mut log = AuditLog::open(path.clone());
+    let actions: Vec<&str> = log.recent().map(|entry| entry.action.as_str()).collect();
+    assert_eq!(actions, ["prometheus_snapshot", "loki_delete"]);
+    assert!(!log.recent().next().unwrap().ok);
+    log.record(AuditEntry::new("ignore_uri", "").param("uri", "/health")).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn only_the_newest_entries_are_kept_in_memory() {
+    let mut log = AuditLog::default();
+    for i in 0..RECENT_ENTRIES + 5 {
+        log.record(AuditEntry::new("ignore_log", "").param("pattern", i)).unwrap();
+    }
+    assert_eq!(log.len(), RECENT_ENTRIES);
+    assert_eq!(log.recent().next().unwrap().params["pattern"], (RECENT_ENTRIES + 4).to_string());
+    assert!(log.path().is_none());
+}
+
+#[test]
+fn an_unwritable_file_is_reported_and_the_entry_kept() {
+    let path = temp_path("unwritable");
+    std::fs::create_dir_all(&path).unwrap(); // A directory where the file should be
+    let mut state = AppState { audit_log: AuditLog::open(path.clone()), ..AppState::default() };
+    state.audit(AuditEntry::new("ignore_uri", "").param("uri", "/health"));
+    assert!(state.status.starts_with("Audit log not written"), "{}", state.status);
+    assert_eq!(state.audit_log.len(), 1);
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn disabling_the_audit_file_keeps_entries_in_memory() {
+    let config = AuditConfig { enabled: false, path: Some("/tmp/audit.log".to_string()) };
+    assert_eq!(config.file(), None);
+    let config = AuditConfig { path: Some("/var/log/rustdash/audit.log".to_string()), ..AuditConfig::default() };
+    assert_eq!(config.file(), Some(PathBuf::from("/var/log/rustdash/audit.log")));
+}
+
+#[test]
+fn log_filter_changes_are_audited() {
+    let mut state = AppState { loki_url: "http://loki:3100".to_string(), ..AppState::default() };
+    state.set_log_filter(LogFilter {
+        stream: Some(r#"{app="shop"}"#.to_string()),
+        pattern: Some("timeout".to_string()),
+        ..LogFilter::default()
+    });
+    state.set_log_filter(LogFilter::default());
+
+    let entries: Vec<&AuditEntry> = state.audit_log.recent().collect();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].params.is_empty(), "clearing the filter has no params");
+    assert_eq!(entries[1].action, "log_filter");
+    assert_eq!(entries[1].target, "http://loki:3100");
+    assert_eq!(entries[1].params["pattern"], "timeout");
+}
+
+#[test]
+fn recent_actions_popup_lists_the_newest_first() {
+    let mut state = AppState { audit_popup: Some(AuditPopup::default()), ..AppState::default() };
+    let failed: Result<String, String> = Err("connection refused".to_string());
+    state.audit(AdminAction::PrometheusCleanTombstones.audit_entry("http://prometheus:9090").outcome(&failed));
+    state.audit(AuditEntry::new("ignore_uri", "").param("uri", "/health"));
+
+    let screen = common::screen(&state, 140, 30);
+    let rows: Vec<&str> = screen.lines().collect();
+    assert!(screen.contains("Recent actions (2)"), "{}", screen);
+    assert!(screen.contains("Not written to a file"));
+    let row_of = |needle: &str| rows.iter().position(|row| row.contains(needle)).unwrap();
+    assert!(row_of("uri=/health") < row_of("prometheus_clean_tombstones"));
+    assert!(rows[row_of("prometheus_clean_tombstones")].contains("on http://prometheus:9090 - FAILED: connection refused"));
+}
@@ -0,0 +1,23 @@
+use chrono::{Duration, Local};
+use rustdash::backoff::Backoff;
+
+#[test]
+fn retries_back_off_exponentially_up_to_a_minute() {
+    let now = Local::now();
+    let mut backoff = Backoff::default();
+    assert!(backoff.ready(now) && !backoff.is_down());
+
+    let mut delays = Vec::new();
+    for _ in 0..6 {
+        backoff.failure(now, 5);
+        delays.push((backoff.retry_at.unwrap() - now).num_seconds());
+    }
+    assert_eq!(delays, vec![5, 10, 20, 40, 60, 60]);
+    assert!(!backoff.ready(now + Duration::seconds(30)));
+    assert!(backoff.ready(now + Duration::seconds(60)));
+
+    // The outage is dated from its first failure
+    assert_eq!(backoff.down_since, Some(now));
+    assert_eq!(backoff.success(), Some(now));
+    assert!(backoff.ready(now) && !backoff.is_down());
+}
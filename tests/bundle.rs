@@ -0,0 +1,99 @@
+use rustdash::bundle::{Bundle, BUNDLE_VERSION};
+use rustdash::persist::PersistedState;
+use rustdash::saved_views::SavedView;
+use chrono::{Local, TimeZone};
+use rustdash::ui::{AppState, TimeRange};
+use std::fs;
+
+const CONFIG: &str = "# Team setup\n[prometheus]\nbase_url = \"http://prometheus:9090\"\n\n[[panels]]\ntitle = \"Requests\"\npreset = \"stat\"\nquery = \"sum(up)\"\n";
+
+fn view(name: &str, range: TimeRange) -> SavedView {
+    SavedView { time_range: range, ..SavedView::capture(name, &AppState::default()) }
+}
+
+#[test]
+fn bundles_round_trip() {
+    let bundle = Bundle::new(Some(CONFIG.to_string()), vec![view("incident", TimeRange::OneHour)]);
+    let parsed = Bundle::parse(&bundle.to_json().unwrap()).unwrap();
+    assert_eq!(parsed, bundle);
+    assert_eq!(parsed.version, BUNDLE_VERSION);
+    assert!(parsed.config.unwrap().starts_with("# Team setup"), "comments travel too");
+}
+
+#[test]
+fn broken_bundles_are_refused() {
+    assert!(Bundle::parse("{}").is_err());
+    let newer = Bundle { version: BUNDLE_VERSION + 1, ..Bundle::new(None, Vec::new()) };
+    assert!(Bundle::parse(&newer.to_json().unwrap()).unwrap_err().to_string().contains("newer"));
+    let invalid = Bundle::new(Some("[prometheus]\nbase_url = 3".to_string()), Vec::new());
+    let error = Bundle::parse(&invalid.to_json().unwrap()).unwrap_err().to_string();
+    assert!(error.starts_with("Invalid config in the bundle"), "{}", error);
+}
+
+#[test]
+fn installing_keeps_the_previous_config() {
+    let dir = std::env::temp_dir().join(format!("rustdash-bundle-{}", std::process::id()));
+    let config_path = dir.join("rustdash").join("config.toml");
+    let bundle = Bundle::new(Some(CONFIG.to_string()), vec![view("incident", TimeRange::OneDay), view("deploys", TimeRange::OneMin)]);
+
+    let mut state = PersistedState { saved_views: vec![view("incident", TimeRange::FiveMin), view("mine", TimeRange::All)], ..PersistedState::default() };
+    let now = Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let installed = bundle.install(&config_path, &mut state, now).unwrap();
+    assert!(installed.config);
+    assert_eq!(installed.backup, None, "nothing to back up yet");
+    assert_eq!(fs::read_to_string(&config_path).unwrap(), CONFIG);
+    let names: Vec<_> = state.saved_views.iter().map(|view| view.name.as_str()).collect();
+    assert_eq!(names, ["incident", "mine", "deploys"]);
+    assert_eq!(state.saved_views[0].time_range, TimeRange::OneDay, "replaced by the bundle's");
+
+    fs::write(&config_path, "# mine\n").unwrap();
+    let installed = bundle.install(&config_path, &mut state, now).unwrap();
+    let backup = installed.backup.unwrap();
+    assert_eq!(backup, dir.join("rustdash").join("config.toml.20240501-120000.bak"));
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "# mine\n");
+
+    // A backup of the same name is never overwritten
+    fs::write(&config_path, "# changed\n").unwrap();
+    assert!(bundle.install(&config_path, &mut state, now).unwrap_err().to_string().contains("already exists"));
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "# mine\n");
+    assert_eq!(fs::read_to_string(&config_path).unwrap(), "# changed\n");
+
+    bundle.install(&config_path, &mut state, now + chrono::Duration::seconds(1)).unwrap();
+
+    // A bundle without a config leaves the file alone
+    Bundle::new(None, Vec::new()).install(&config_path, &mut state, now).unwrap();
+    assert_eq!(fs::read_to_string(&config_path).unwrap(), CONFIG);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn review_lists_the_commands_and_hosts_a_config_uses() {
+    let config = r#"
+[prometheus]
+ssh_host = "bastion.example.com"
+
+[loki.auth]
+bearer_token = { command = "pass show loki" }
+
+[profiles.staging.prometheus_auth]
+username = "me"
+password = { command = "cat ~/.staging" }
+
+[log_actions]
+commands = [
+    { name = "pager", command = "${PAGER:-less}" },
+    { name = "upload", command = "curl -T - https://paste.example.com" },
+]
+"#;
+    let review = Bundle::new(Some(config.to_string()), Vec::new()).review().unwrap();
+    assert_eq!(
+        review,
+        [
+            "runs `pass show loki` for loki.auth.bearer_token",
+            "runs `cat ~/.staging` for profiles.staging.prometheus_auth.password",
+            "runs `curl -T - https://paste.example.com` as log command \"upload\"",
+            "connects to SSH host bastion.example.com for prometheus",
+        ]
+    );
+    assert!(Bundle::new(Some(CONFIG.to_string()), Vec::new()).review().unwrap().is_empty());
+}
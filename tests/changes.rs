@@ -0,0 +1,99 @@
+use chrono::{DateTime, Local, TimeZone};
+use rustdash::changes::{diff, ChangeKind, MetricChange, MetricChanges};
+use rustdash::prometheus::MetricsData;
+use rustdash::ui::AppState;
+
+mod common;
+
+fn at(seconds: i64) -> DateTime<Local> {
+    Local.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+}
+
+#[test]
+fn finds_new_vanished_and_moved_rows() {
+    let before = [common::metric("/api/orders", 100.0, 10.0), common::metric("/api/cart", 40.0, 10.0), common::metric("/api/old", 10.0, 10.0), common::metric("/health", 2.0, 10.0)];
+    let after = [common::metric("/api/orders", 250.0, 10.0), common::metric("/api/cart", 15.0, 10.0), common::metric("/api/new", 30.0, 10.0), common::metric("/health", 4.0, 10.0)];
+    let changes = diff(&before, &after, 50.0);
+    let summary: Vec<(&str, String)> = changes.iter().map(|change| (change.uri.as_str(), change.describe())).collect();
+    assert_eq!(
+        summary,
+        [
+            ("/api/new", "new".to_string()),
+            ("/api/orders", "+150%  100.0ms → 250.0ms".to_string()),
+            ("/api/cart", "-62%  40.0ms → 15.0ms".to_string()),
+            ("/api/old", "gone".to_string()),
+        ],
+        "/health doubled, but by less than {} ms",
+        rustdash::changes::MIN_CHANGE_MS
+    );
+    assert_eq!(changes[1].marker(), ("▲", "slower"));
+    assert_eq!(changes[2].marker(), ("▼", "faster"));
+}
+
+#[test]
+fn small_moves_are_not_changes() {
+    assert!(diff(&[common::metric("/a", 100.0, 10.0)], &[common::metric("/a", 140.0, 10.0)], 50.0).is_empty());
+    assert_eq!(diff(&[common::metric("/a", 100.0, 10.0)], &[common::metric("/a", 140.0, 10.0)], 25.0).len(), 1);
+}
+
+#[test]
+fn compares_refreshes_of_one_time_range() {
+    let mut changes = MetricChanges::default();
+    changes.update(Some(5), &[common::metric("/a", 100.0, 10.0)], at(0), 50.0);
+    assert!(changes.since.is_none(), "nothing to compare the first refresh with");
+
+    changes.update(Some(5), &[common::metric("/a", 100.0, 10.0), common::metric("/b", 5.0, 10.0)], at(5), 50.0);
+    assert_eq!(changes.since, Some(at(0)));
+    assert_eq!(changes.changes, [MetricChange { uri: "/b".to_string(), kind: ChangeKind::New }]);
+
+    changes.update(Some(60), &[common::metric("/a", 900.0, 10.0)], at(10), 50.0);
+    assert!(changes.changes.is_empty(), "a new time range starts over");
+    assert!(changes.since.is_none());
+}
+
+#[test]
+fn table_marks_changed_rows_and_k_lists_them() {
+    let before = vec![common::metric("/api/orders", 100.0, 10.0), common::metric("/api/gone", 20.0, 10.0)];
+    let after = vec![common::metric("/api/orders", 300.0, 10.0)];
+    let mut state = AppState {
+        metrics: Some(MetricsData {
+            http_requests_total: 0.0,
+            uri_metrics: after.clone(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        ..AppState::default()
+    };
+    state.metric_changes.update(Some(5), &before, at(0), 50.0);
+    state.metric_changes.update(Some(5), &after, at(5), 50.0);
+    assert!(common::screen(&state, 120, 30).contains("/api/orders ▲"));
+
+    state.change_markers = false;
+    assert!(!common::screen(&state, 120, 30).contains("▲"));
+
+    state.changes_popup = true;
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains(" Changes since "), "{}", shown);
+    assert!(shown.contains("+200%  100.0ms → 300.0ms"));
+    assert!(shown.contains("- /api/gone"));
+}
+
+#[test]
+fn accessible_mode_spells_the_change_out() {
+    let after = vec![common::metric("/api/orders", 30.0, 10.0)];
+    let mut state = AppState {
+        accessible: true,
+        metrics: Some(MetricsData {
+            http_requests_total: 0.0,
+            uri_metrics: after.clone(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        ..AppState::default()
+    };
+    state.metric_changes.update(Some(5), &[], at(0), 50.0);
+    state.metric_changes.update(Some(5), &after, at(5), 50.0);
+    assert!(common::screen(&state, 120, 30).contains("change: new"));
+}
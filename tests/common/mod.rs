@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests. Each test crate uses its own subset.
+#![allow(dead_code)]
+
+use rustdash::frontend::{Frontend, MemoryFrontend};
+use rustdash::loki::LogEntry;
+use rustdash::prometheus::UriMetric;
+use rustdash::ui::AppState;
+
+/// `state` drawn on a `width`×`height` screen, one line per row.
+pub fn screen(state: &AppState, width: u16, height: u16) -> String {
+    let mut frontend = MemoryFrontend::new(width, height).unwrap();
+    frontend.draw(state).unwrap();
+    frontend.lines().join("\n")
+}
+
+/// A log line at `nanos` with no timestamp text or stream labels.
+pub fn log(nanos: i64, level: &str, message: &str) -> LogEntry {
+    LogEntry {
+        timestamp: String::new(),
+        nanos,
+        message: message.to_string(),
+        level: level.to_string(),
+        is_new: false,
+        stream: String::new(),
+        source: None,
+        local: false,
+    }
+}
+
+/// A metrics row with no errors, change or bandwidth.
+pub fn metric(uri: &str, avg_duration_ms: f64, request_count: f64) -> UriMetric {
+    UriMetric {
+        uri: uri.to_string(),
+        avg_duration_ms,
+        request_count,
+        errors_per_min: 0.0,
+        change_pct: None,
+        bytes_per_sec: None,
+    }
+}
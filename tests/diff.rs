@@ -0,0 +1,42 @@
+use rustdash::diff::{self, Change, Segment};
+
+fn segment(change: Change, text: &str) -> Segment {
+    Segment { change, text: text.to_string() }
+}
+
+#[test]
+fn only_the_differing_field_is_marked() {
+    let segments = diff::diff("timeout after 812ms order=1234", "timeout after 812ms order=1289");
+    assert_eq!(
+        segments,
+        vec![
+            segment(Change::Same, "timeout after 812ms order=12"),
+            segment(Change::Removed, "34"),
+            segment(Change::Added, "89"),
+        ]
+    );
+    assert_eq!(diff::change_count(&segments), 1);
+
+    let old: String = diff::old_side(&segments).map(|s| s.text.as_str()).collect();
+    let new: String = diff::new_side(&segments).map(|s| s.text.as_str()).collect();
+    assert_eq!(old, "timeout after 812ms order=1234");
+    assert_eq!(new, "timeout after 812ms order=1289");
+}
+
+#[test]
+fn insertions_in_the_middle_keep_both_ends() {
+    let segments = diff::diff("GET /api/users 200 5ms", "GET /api/users/7 404 5ms");
+    assert_eq!(diff::old_side(&segments).map(|s| s.text.as_str()).collect::<String>(), "GET /api/users 200 5ms");
+    assert_eq!(diff::new_side(&segments).map(|s| s.text.as_str()).collect::<String>(), "GET /api/users/7 404 5ms");
+    assert_eq!(segments[0], segment(Change::Same, "GET /api/users"));
+    assert_eq!(segments.last().unwrap(), &segment(Change::Same, " 5ms"));
+}
+
+#[test]
+fn identical_lines_have_no_changes() {
+    let segments = diff::diff("same", "same");
+    assert_eq!(segments, vec![segment(Change::Same, "same")]);
+    assert_eq!(diff::change_count(&segments), 0);
+    assert!(diff::diff("", "").is_empty());
+    assert_eq!(diff::diff("", "new"), vec![segment(Change::Added, "new")]);
+}
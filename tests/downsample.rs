@@ -0,0 +1,28 @@
+use rustdash::downsample::{auto_step, lttb};
+
+#[test]
+fn step_targets_one_point_per_column() {
+    // 5m over 100 columns needs 3s, rounded up to 5s
+    assert_eq!(auto_step(300, 100), 5);
+    assert_eq!(auto_step(86_400, 150), 600);
+    assert_eq!(auto_step(60, 1000), 1);
+    // Windows past the largest nice step fall back to the exact step
+    assert_eq!(auto_step(30 * 86_400, 10), 259_200);
+    assert!(86_400 / auto_step(86_400, 150) <= 150);
+}
+
+#[test]
+fn lttb_keeps_endpoints_and_peaks() {
+    let mut points: Vec<(f64, f64)> = (0..10_000).map(|i| (i as f64, (i % 7) as f64)).collect();
+    points[4_321].1 = 500.0;
+
+    let sampled = lttb(&points, 200);
+
+    assert_eq!(sampled.len(), 200);
+    assert_eq!(sampled.first(), points.first());
+    assert_eq!(sampled.last(), points.last());
+    assert!(sampled.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(sampled.contains(&(4_321.0, 500.0)));
+    // Sparse series are returned untouched
+    assert_eq!(lttb(&points[..50], 200), points[..50].to_vec());
+}
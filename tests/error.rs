@@ -0,0 +1,44 @@
+use reqwest::StatusCode;
+use rustdash::error::{self, ErrorClass};
+use rustdash::loki::LokiError;
+use rustdash::prometheus::PrometheusError;
+
+#[test]
+fn statuses_and_messages_map_to_classes() {
+    assert_eq!(error::classify_status(StatusCode::FORBIDDEN, "denied"), ErrorClass::Auth);
+    assert_eq!(error::classify_status(StatusCode::GATEWAY_TIMEOUT, ""), ErrorClass::Timeout);
+    assert_eq!(error::classify_status(StatusCode::SERVICE_UNAVAILABLE, "unavailable"), ErrorClass::Unreachable);
+    assert_eq!(
+        error::classify_status(StatusCode::BAD_REQUEST, "max entries limit per query exceeded, limit > max_entries_limit (10000 > 5000)"),
+        ErrorClass::Limit
+    );
+    assert_eq!(error::classify_status(StatusCode::BAD_REQUEST, "parse error at line 1, col 7"), ErrorClass::Rejected);
+    assert_eq!(error::classify_message("query timed out in expression evaluation"), Some(ErrorClass::Timeout));
+    assert_eq!(error::classify_message("bad_data"), None);
+}
+
+#[test]
+fn auth_failures_get_their_own_variant() {
+    let e = PrometheusError::status(StatusCode::UNAUTHORIZED, " no token \n");
+    assert!(matches!(&e, PrometheusError::Auth { message, .. } if message == "no token"));
+    assert_eq!(e.to_string(), "HTTP 401 Unauthorized: no token");
+    assert!(matches!(LokiError::status(StatusCode::FORBIDDEN, "tenant"), LokiError::Auth { .. }));
+    assert!(matches!(LokiError::status(StatusCode::BAD_REQUEST, "oops"), LokiError::Status { .. }));
+}
+
+#[test]
+fn prometheus_api_errors_are_classed_by_type_and_status() {
+    let api = |status, error_type: &str, message: &str| PrometheusError::Api {
+        status,
+        error_type: error_type.to_string(),
+        message: message.to_string(),
+    };
+    assert_eq!(api(StatusCode::SERVICE_UNAVAILABLE, "timeout", "query timed out").class(), ErrorClass::Timeout);
+    assert_eq!(api(StatusCode::BAD_REQUEST, "bad_data", "invalid parameter \"query\"").class(), ErrorClass::Rejected);
+    assert_eq!(
+        api(StatusCode::UNPROCESSABLE_ENTITY, "execution", "query processing would load too many samples into memory").class(),
+        ErrorClass::Limit
+    );
+    assert_eq!(api(StatusCode::BAD_REQUEST, "bad_data", "x").to_string(), "bad_data: x");
+    assert!(!ErrorClass::Auth.is_transient() && ErrorClass::Timeout.is_transient());
+}
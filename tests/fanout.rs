@@ -0,0 +1,51 @@
+use rustdash::fanout::{merge, Aggregate, ENDPOINT_LABEL};
+use rustdash::prometheus::PrometheusResponse;
+
+fn matrix(series: &[(&str, &[(f64, &str)])]) -> PrometheusResponse {
+    let result: Vec<_> = series
+        .iter()
+        .map(|(topic, values)| serde_json::json!({"metric": {"topic": topic}, "values": values}))
+        .collect();
+    serde_json::from_value(serde_json::json!({
+        "status": "success",
+        "data": {"resultType": "matrix", "result": result},
+    }))
+    .unwrap()
+}
+
+#[test]
+fn separate_tags_each_endpoint() {
+    let merged = merge(
+        vec![
+            ("eu".to_string(), Ok(matrix(&[("orders", &[(60.0, "5")])]))),
+            ("us".to_string(), Ok(matrix(&[("orders", &[(60.0, "7")])]))),
+        ],
+        Aggregate::Separate,
+    )
+    .unwrap();
+
+    let endpoints: Vec<_> = merged.data.result.iter().map(|r| r.metric[ENDPOINT_LABEL].as_str()).collect();
+    assert_eq!(endpoints, ["eu", "us"]);
+}
+
+#[test]
+fn sum_adds_matching_series_and_reports_failures() {
+    let merged = merge(
+        vec![
+            ("eu".to_string(), Ok(matrix(&[("orders", &[(60.0, "5"), (120.0, "6")]), ("audit", &[(60.0, "1")])]))),
+            ("us".to_string(), Ok(matrix(&[("orders", &[(60.0, "7"), (120.0, "8")])]))),
+            ("ap".to_string(), Err(anyhow::anyhow!("connection refused"))),
+        ],
+        Aggregate::Sum,
+    )
+    .unwrap();
+
+    assert_eq!(merged.data.result.len(), 2);
+    let orders = merged.data.result.iter().find(|r| r.metric["topic"] == "orders").unwrap();
+    assert!(!orders.metric.contains_key(ENDPOINT_LABEL));
+    assert_eq!(orders.values.as_ref().unwrap(), &vec![(60.0, "12".to_string()), (120.0, "14".to_string())]);
+    assert_eq!(merged.warnings, ["ap: connection refused"]);
+
+    // Nothing answered: the query fails
+    assert!(merge(vec![("ap".to_string(), Err(anyhow::anyhow!("down")))], Aggregate::Sum).is_err());
+}
@@ -0,0 +1,54 @@
+use rustdash::ui::{ActivePanel, AppState};
+
+mod common;
+
+#[test]
+fn tab_cycles_through_the_panels_with_a_backend() {
+    let mut state = AppState::default();
+    state.cycle_focus(false);
+    assert_eq!(state.active_panel, ActivePanel::Logs);
+    state.cycle_focus(false);
+    assert_eq!(state.active_panel, ActivePanel::Metrics);
+    state.cycle_focus(false);
+    assert_eq!(state.active_panel, ActivePanel::Logs);
+    state.cycle_focus(true);
+    assert_eq!(state.active_panel, ActivePanel::Metrics);
+
+    state.active_panel = ActivePanel::None;
+    state.cycle_focus(true);
+    assert_eq!(state.active_panel, ActivePanel::Metrics);
+
+    let mut state = AppState { loki_enabled: false, ..AppState::default() };
+    state.cycle_focus(false);
+    assert_eq!(state.active_panel, ActivePanel::Metrics);
+    state.cycle_focus(false);
+    assert_eq!(state.active_panel, ActivePanel::Metrics);
+}
+
+#[test]
+fn only_the_focused_panel_gets_a_thick_border() {
+    let screen = |active_panel| {
+        common::screen(&AppState { active_panel, ..AppState::default() }, 120, 40)
+    };
+
+    assert!(!screen(ActivePanel::None).contains('┏'));
+    assert_eq!(screen(ActivePanel::Logs).matches('┏').count(), 1);
+    assert_eq!(screen(ActivePanel::Metrics).matches('┏').count(), 1);
+}
+
+#[test]
+fn zoom_gives_the_focused_panel_the_whole_terminal() {
+    let mut state = AppState { active_panel: ActivePanel::Logs, zoomed: true, ..AppState::default() };
+    assert_eq!(state.zoomed_panel(), Some(ActivePanel::Logs));
+    assert_eq!(state.get_visible_height(40), 37);
+    let screen = common::screen(&state, 120, 40);
+    assert!(screen.starts_with('┏'), "{}", screen);
+    assert!(screen.contains("Loki Logs"));
+    assert!(!screen.contains("API Response Times"));
+
+    state.zoomed = false;
+    assert!(state.get_visible_height(40) < 37);
+    state.active_panel = ActivePanel::None;
+    state.zoomed = true;
+    assert_eq!(state.zoomed_panel(), None);
+}
@@ -0,0 +1,99 @@
+use rustdash::forecast::{describe, linear_fit, time_to_reach};
+use rustdash::panels::{PanelConfig, PanelData, PanelRow, Severity};
+use rustdash::text::Truncation;
+use rustdash::ui::AppState;
+
+mod common;
+
+/// A point a minute for half an hour, moving `per_minute` from `start`.
+fn trend(start: f64, per_minute: f64) -> Vec<(f64, f64)> {
+    (0..30).map(|i| (1_700_000_000.0 + i as f64 * 60.0, start + per_minute * i as f64)).collect()
+}
+
+#[test]
+fn fits_a_line() {
+    let (slope, intercept) = linear_fit(&[(0.0, 1.0), (10.0, 3.0), (20.0, 5.0)]).unwrap();
+    assert_eq!((slope, intercept), (0.2, 1.0));
+    assert_eq!(linear_fit(&[(5.0, 1.0)]), None);
+    assert_eq!(linear_fit(&[(5.0, 1.0), (5.0, 2.0)]), None);
+}
+
+#[test]
+fn rising_and_falling_towards_the_target() {
+    // 0.01% a minute, at 70.29% now: 29.71 points to go
+    let seconds = time_to_reach(&trend(70.0, 0.01), 100.0).unwrap();
+    assert!((seconds - 2971.0 * 60.0).abs() < 1.0, "{}", seconds);
+    assert_eq!(describe(seconds), "full in ~2d");
+
+    // Free space draining to zero
+    let seconds = time_to_reach(&trend(500.0, -5.0), 0.0).unwrap();
+    assert!((seconds - 71.0 * 60.0).abs() < 1.0, "{}", seconds);
+    assert_eq!(describe(seconds), "full in ~1h");
+}
+
+#[test]
+fn no_hint_away_from_flat_or_far_off() {
+    assert_eq!(time_to_reach(&trend(50.0, -0.5), 100.0), None);
+    assert_eq!(time_to_reach(&trend(50.0, 0.0), 100.0), None);
+    assert_eq!(time_to_reach(&trend(50.0, 0.00001), 100.0), None, "years away");
+    assert_eq!(time_to_reach(&trend(101.0, 0.5), 100.0), None, "started past it and moving on");
+}
+
+#[test]
+fn reached_is_now() {
+    let seconds = time_to_reach(&trend(90.0, 0.5), 100.0).unwrap();
+    assert_eq!(seconds, 0.0);
+    assert_eq!(describe(seconds), "full now");
+}
+
+#[test]
+fn almost_there_is_not_now() {
+    // The line is past 100 by its end, but the last sample is still below
+    let mut points = trend(90.0, 0.5);
+    points.last_mut().unwrap().1 = 99.0;
+    let seconds = time_to_reach(&points, 100.0).unwrap();
+    assert!(seconds > 0.0 && seconds < 60.0, "{}", seconds);
+    assert_eq!(describe(seconds), "full in < 1m");
+    assert_eq!(describe(59.0), "full in < 1m");
+    assert_eq!(describe(60.0), "full in ~1m");
+}
+
+#[test]
+fn panels_opt_in() {
+    let config: PanelConfig = toml::from_str("preset = \"containers\"\nforecast = 100").unwrap();
+    assert_eq!(config.forecast, Some(100.0));
+    let config: PanelConfig = toml::from_str("preset = \"containers\"").unwrap();
+    assert_eq!(config.forecast, None);
+}
+
+#[test]
+fn rows_say_when_they_fill_up() {
+    let row = |label: &str, value: f64, full_in: Option<f64>| PanelRow {
+        label: label.to_string(),
+        value,
+        trend: vec![value - 1.0, value],
+        severity: Severity::Ok,
+        full_in,
+    };
+    let panel = PanelData {
+        title: "Disk usage".to_string(),
+        unit: "%".to_string(),
+        warn: f64::INFINITY,
+        critical: f64::INFINITY,
+        rows: vec![row("/var", 81.0, Some(3.0 * 86400.0)), row("/home", 40.0, None)],
+        stat: None,
+        table: None,
+        heatmap: None,
+        error: None,
+        warnings: Vec::new(),
+        truncation: Truncation::End,
+        counter: None,
+        query: String::new(),
+        repeat: None,
+    };
+    let state = AppState { panels: vec![panel], panels_page: Some(Default::default()), ..AppState::default() };
+    let screen = common::screen(&state, 120, 30);
+    let line = |label: &str| screen.lines().find(|line| line.contains(label)).unwrap_or_default();
+    assert!(line("/var").contains("full in ~3d"), "{}", screen);
+    assert!(!line("/home").contains("full in"));
+}
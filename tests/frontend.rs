@@ -0,0 +1,30 @@
+use rustdash::frontend::{Frontend, MemoryFrontend};
+use rustdash::ui::{ActivePanel, AppState};
+
+#[test]
+fn the_dashboard_draws_without_a_terminal() {
+    let mut frontend = MemoryFrontend::new(100, 30).unwrap();
+    frontend.enter().unwrap();
+    assert!(frontend.entered);
+    let state = AppState { active_panel: ActivePanel::Logs, ..AppState::default() };
+    frontend.draw(&state).unwrap();
+    let lines = frontend.lines();
+    assert_eq!(lines.len(), 30);
+    assert!(lines.iter().any(|line| line.contains("Loki Logs")), "{}", lines.join("\n"));
+
+    frontend.bell().unwrap();
+    frontend.release().unwrap();
+    assert!(!frontend.entered);
+    assert_eq!(frontend.bells, 1);
+}
+
+#[test]
+fn frames_follow_the_size() {
+    let mut frontend = MemoryFrontend::new(100, 30).unwrap();
+    frontend.resize(140, 40);
+    assert_eq!((frontend.size().unwrap().width, frontend.size().unwrap().height), (140, 40));
+    frontend.resume().unwrap();
+    frontend.draw(&AppState::default()).unwrap();
+    assert_eq!(frontend.lines().len(), 40);
+    assert_eq!(frontend.lines()[0].chars().count(), 140);
+}
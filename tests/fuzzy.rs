@@ -0,0 +1,30 @@
+use rustdash::fuzzy::{rank, score};
+
+#[test]
+fn characters_must_appear_in_order() {
+    assert!(score("hrqtot", "http_requests_total").is_some());
+    assert!(score("HTTP", "http_requests_total").is_some());
+    assert!(score("toth", "http_requests_total").is_none());
+    assert_eq!(score("", "up").unwrap().score, 0);
+}
+
+#[test]
+fn word_starts_are_matched_over_earlier_letters() {
+    // `r` and `t` come first inside "http", but "requests" and "total" start words
+    let matched = score("hrt", "http_requests_total").unwrap();
+    assert_eq!(matched.positions, vec![0, 5, 14]);
+    // Falls back to plain order when jumping ahead leaves too little
+    assert_eq!(score("htp", "http").unwrap().positions, vec![0, 1, 3]);
+}
+
+#[test]
+fn contiguous_and_prefix_matches_rank_first() {
+    let names = ["process_cpu_seconds_total", "up", "http_requests_total", "prometheus_http_requests_total", "rate"];
+    let ranked: Vec<&str> = rank("req", names, 10).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(ranked, ["http_requests_total", "prometheus_http_requests_total"]);
+
+    let ranked: Vec<&str> = rank("rate", names, 10).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(ranked[0], "rate");
+
+    assert_eq!(rank("p", names, 2).len(), 2);
+}
@@ -0,0 +1,77 @@
+use chrono::{Local, NaiveTime, TimeZone};
+use rustdash::goto::{self, GotoPrompt, GotoTarget};
+use rustdash::text::LineInput;
+use rustdash::ui::{ActivePanel, AppState};
+
+mod common;
+
+/// A line a minute from 12:00 local time today.
+fn state_with_logs(count: usize) -> AppState {
+    let noon = Local::now().date_naive().and_hms_opt(12, 0, 0).unwrap();
+    let start = Local.from_local_datetime(&noon).earliest().unwrap().timestamp_nanos_opt().unwrap();
+    let all_logs = (0..count).map(|i| common::log(start + i as i64 * 60_000_000_000, "INFO", &format!("line {}", i + 1))).collect();
+    let mut state = AppState { all_logs, ..AppState::default() };
+    state.update_visible_logs_with_height(30);
+    state
+}
+
+#[test]
+fn parses_lines_and_times() {
+    assert_eq!(goto::parse("1234"), Ok(GotoTarget::Line(1234)));
+    assert_eq!(goto::parse(":42"), Ok(GotoTarget::Line(42)));
+    assert_eq!(goto::parse("12:05:30"), Ok(GotoTarget::Time(NaiveTime::from_hms_opt(12, 5, 30).unwrap())));
+    assert_eq!(goto::parse(" 9:41 "), Ok(GotoTarget::Time(NaiveTime::from_hms_opt(9, 41, 0).unwrap())));
+    assert!(goto::parse("0").is_err());
+    assert!(goto::parse("").is_err());
+    assert!(goto::parse("25:00").is_err());
+    assert!(goto::parse("soon").is_err());
+}
+
+#[test]
+fn jumps_to_a_line() {
+    let mut state = state_with_logs(200);
+    assert_eq!(state.goto_log(GotoTarget::Line(50), 30), Ok("Line 50 of 200".to_string()));
+    assert_eq!(state.selected_log_index, Some(49));
+    assert_eq!(state.active_panel, ActivePanel::Logs);
+    assert!(state.time_cursor.is_some());
+    assert!(state.logs.iter().any(|log| log.message == "line 50"), "scrolled into view");
+
+    assert!(state.goto_log(GotoTarget::Line(201), 30).is_err());
+    assert_eq!(state.selected_log_index, Some(49), "a failed jump stays put");
+}
+
+#[test]
+fn jumps_to_the_nearest_time() {
+    let mut state = state_with_logs(200);
+    let time = NaiveTime::from_hms_opt(12, 30, 20).unwrap();
+    assert_eq!(state.goto_log(GotoTarget::Time(time), 30), Ok("Line 31 of 200, nearest 12:30:20".to_string()));
+    assert_eq!(state.selected_log_index, Some(30));
+}
+
+#[test]
+fn nothing_to_go_to_without_logs() {
+    let mut state = AppState::default();
+    assert!(state.goto_log(GotoTarget::Line(1), 30).is_err());
+}
+
+#[test]
+fn gutter_numbers_the_buffer() {
+    let mut state = state_with_logs(200);
+    state.show_line_numbers = true;
+    state.goto_log(GotoTarget::Line(50), 30).unwrap();
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains(" 50 [INFO ] line 50"), "{}", shown);
+
+    state.show_line_numbers = false;
+    assert!(common::screen(&state, 120, 30).contains("[INFO ] line 50"));
+    assert!(!common::screen(&state, 120, 30).contains(" 50 [INFO ]"));
+}
+
+#[test]
+fn prompt_shows_why_it_didnt_jump() {
+    let mut state = state_with_logs(3);
+    state.goto_prompt = Some(GotoPrompt { input: LineInput::new("99"), error: Some("There are only 3 lines".to_string()) });
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains(" Go to line or time "), "{}", shown);
+    assert!(shown.contains("There are only 3 lines"));
+}
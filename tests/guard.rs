@@ -0,0 +1,86 @@
+use rustdash::error::ErrorClass;
+use rustdash::guard::{self, GuardConfig, Outcome, SlowQueryGuard};
+use rustdash::query_stats::Backend;
+use std::time::Duration;
+
+const QUERY: &str = "sum by(uri) (rate(http_server_requests_seconds_count[1h]))";
+
+fn guard() -> SlowQueryGuard {
+    SlowQueryGuard::new(GuardConfig { slow_seconds: 2.0, strikes: 3, max_reductions: 2, ..GuardConfig::default() })
+}
+
+#[test]
+fn consecutive_slow_runs_reduce_the_query() {
+    let guard = guard();
+    let slow = Outcome::Finished(Duration::from_secs(3));
+
+    assert_eq!(guard.observe(Backend::Prometheus, QUERY, slow), None);
+    assert_eq!(guard.observe(Backend::Prometheus, QUERY, Outcome::TimedOut), None);
+    assert_eq!(guard.observe(Backend::Prometheus, QUERY, slow), Some(1));
+    assert_eq!(guard.level(Backend::Prometheus, QUERY), 1);
+    let reduction = guard.reduction(Backend::Prometheus, QUERY).unwrap();
+    assert_eq!(reduction.factor(), 2);
+    assert!(reduction.reason.contains("3 times in a row"), "{}", reduction.reason);
+
+    // Capped at max_reductions
+    for _ in 0..9 {
+        guard.observe(Backend::Prometheus, QUERY, slow);
+    }
+    assert_eq!(guard.level(Backend::Prometheus, QUERY), 2);
+    // Other queries and the other backend are unaffected
+    assert_eq!(guard.level(Backend::Loki, QUERY), 0);
+    assert_eq!(guard.reductions().len(), 1);
+}
+
+#[test]
+fn a_fast_run_breaks_the_streak() {
+    let guard = guard();
+    let slow = Outcome::Finished(Duration::from_secs(3));
+    guard.observe(Backend::Prometheus, QUERY, slow);
+    guard.observe(Backend::Prometheus, QUERY, slow);
+    guard.observe(Backend::Prometheus, QUERY, Outcome::Finished(Duration::from_millis(100)));
+    guard.observe(Backend::Prometheus, QUERY, slow);
+    assert_eq!(guard.level(Backend::Prometheus, QUERY), 0);
+}
+
+#[test]
+fn limit_errors_reduce_at_once_and_fast_runs_ease_off() {
+    let guard = guard();
+    let error = "the query time range exceeds the limit (query length: 30m0s, limit: 10m0s)";
+    assert_eq!(guard.observe(Backend::Loki, "{job=\"api\"}", Outcome::Rejected(ErrorClass::Limit, error)), Some(1));
+    // Other errors say nothing about cost
+    assert_eq!(guard.observe(Backend::Loki, "{job=\"api\"}", Outcome::Rejected(ErrorClass::Rejected, "parse error")), None);
+
+    for _ in 0..9 {
+        guard.observe(Backend::Loki, "{job=\"api\"}", Outcome::Finished(Duration::from_millis(50)));
+    }
+    assert_eq!(guard.level(Backend::Loki, "{job=\"api\"}"), 1);
+    guard.observe(Backend::Loki, "{job=\"api\"}", Outcome::Finished(Duration::from_millis(50)));
+    assert_eq!(guard.level(Backend::Loki, "{job=\"api\"}"), 0);
+    assert!(guard.reductions().is_empty());
+}
+
+#[test]
+fn a_disabled_guard_never_reduces() {
+    let guard = SlowQueryGuard::new(GuardConfig { enabled: false, ..GuardConfig::default() });
+    for _ in 0..10 {
+        guard.observe(Backend::Prometheus, QUERY, Outcome::TimedOut);
+    }
+    assert_eq!(guard.level(Backend::Prometheus, QUERY), 0);
+}
+
+#[test]
+fn reductions_narrow_windows_and_raise_steps() {
+    assert_eq!(guard::narrow_ranges(QUERY, 0), QUERY);
+    assert_eq!(
+        guard::narrow_ranges(QUERY, 2),
+        "sum by(uri) (rate(http_server_requests_seconds_count[900s]))"
+    );
+    // Never below a minute, and windows already shorter stay as they are
+    assert_eq!(guard::narrow_ranges("rate(x[5m]) / rate(y[30s])", 4), "rate(x[60s]) / rate(y[30s])");
+    assert_eq!(guard::narrow_seconds(1800, 1), 900);
+
+    assert_eq!(guard::raise_step("60", 1), "120");
+    assert_eq!(guard::raise_step("15s", 2), "60");
+    assert_eq!(guard::raise_step("60", 0), "60");
+}
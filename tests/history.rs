@@ -0,0 +1,39 @@
+use rustdash::history::{MetricHistory, UriTotals};
+use std::collections::HashMap;
+
+fn totals(count: f64, sum_seconds: f64) -> HashMap<String, UriTotals> {
+    HashMap::from([("/api".to_string(), UriTotals { count, sum_seconds })])
+}
+
+#[test]
+fn rates_come_from_counter_deltas() {
+    let mut history = MetricHistory::new("http://prometheus");
+    history.record(0.0, totals(100.0, 10.0));
+    history.record(60.0, totals(220.0, 16.0));
+    // The service restarted: counting starts over from zero
+    history.record(120.0, totals(30.0, 3.0));
+
+    let series = history.uri_history("/api");
+    assert_eq!(series.requests_per_min, vec![(60.0, 120.0), (120.0, 30.0)]);
+    assert_eq!(series.latency_ms, vec![(60.0, 50.0), (120.0, 100.0)]);
+
+    let (latency_ms, requests_per_min) = history.uri_average("/api").unwrap();
+    assert!((latency_ms - 60.0).abs() < 1e-9);
+    assert!((requests_per_min - 75.0).abs() < 1e-9);
+    assert!(history.uri_average("/other").is_none());
+}
+
+#[test]
+fn thinning_keeps_totals_exact() {
+    let mut history = MetricHistory::new("http://prometheus");
+    for i in 0..5000 {
+        history.record(i as f64, totals(i as f64 * 10.0, i as f64));
+    }
+
+    assert!(history.len() <= 2000);
+    assert_eq!(history.since(), Some(0.0));
+    // Fewer, wider intervals, but the same requests over the same time
+    let (latency_ms, requests_per_min) = history.uri_average("/api").unwrap();
+    assert!((latency_ms - 100.0).abs() < 1e-9);
+    assert!((requests_per_min - 600.0).abs() < 1e-9);
+}
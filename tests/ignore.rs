@@ -0,0 +1,42 @@
+use rustdash::ignore::{IgnoreConfig, IgnoreList};
+
+#[test]
+fn config_and_runtime_patterns_hide_matches() {
+    let config = IgnoreConfig {
+        uris: vec!["/health|/metrics".to_string()],
+        logs: vec!["GET /metrics".to_string()],
+    };
+    let mut ignore = IgnoreList::new(&config).unwrap();
+
+    assert!(ignore.hides_uri("/health"));
+    // URI patterns match the whole URI, log patterns anywhere in the line
+    assert!(!ignore.hides_uri("/health/deep"));
+    assert!(ignore.hides_log("10.0.0.1 - GET /metrics 200"));
+
+    ignore.ignore_uri("/api/users/{id}");
+    assert!(ignore.hides_uri("/api/users/{id}"));
+    let pattern = ignore.ignore_log("GET /api/ping 200 in 3ms");
+    assert!(ignore.hides_log("GET /api/ping 200 in 12ms"));
+    assert!(!ignore.hides_log("POST /api/ping 200 in 12ms"));
+    assert_eq!(ignore.added().logs, vec![pattern]);
+    assert_eq!(ignore.added().uris.len(), 1);
+
+    ignore.show_hidden = true;
+    assert!(!ignore.hides_uri("/health") && !ignore.hides_log("GET /metrics"));
+
+    assert!(IgnoreList::new(&IgnoreConfig { uris: vec!["(".to_string()], logs: Vec::new() }).is_err());
+}
+
+#[test]
+fn saved_runtime_patterns_are_restored_once() {
+    let mut ignore = IgnoreList::default();
+    ignore.ignore_uri("/login");
+    let saved = ignore.added().clone();
+
+    let mut restored = IgnoreList::default();
+    restored.extend(saved.clone());
+    restored.extend(saved);
+
+    assert!(restored.hides_uri("/login"));
+    assert_eq!(restored.added().uris, vec!["/login".to_string()]);
+}
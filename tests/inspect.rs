@@ -0,0 +1,101 @@
+use chrono::{Local, TimeZone};
+use rustdash::inspect::{self, Inspection};
+use rustdash::prometheus::{self, MetricsData, SeriesValue, UriHistory, UriMetric};
+use rustdash::ui::{ActivePanel, AppState, UriDrillIn};
+use std::collections::BTreeMap;
+
+mod common;
+
+fn at(minute: u32) -> chrono::DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 5, 1, 12, minute, 0).unwrap()
+}
+
+fn series(instance: &str, requests_per_min: f64) -> SeriesValue {
+    SeriesValue {
+        labels: BTreeMap::from([("instance".to_string(), instance.to_string()), ("uri".to_string(), "/api/orders".to_string())]),
+        at: at(2).timestamp() as f64,
+        requests_per_min,
+        latency_ms: Some(41.25),
+    }
+}
+
+fn state() -> AppState {
+    AppState {
+        metrics: Some(MetricsData {
+            http_requests_total: 10.0,
+            uri_metrics: vec![UriMetric {
+                uri: "/api/orders".to_string(),
+                avg_duration_ms: 45.123456,
+                request_count: 120.5,
+                errors_per_min: 0.0,
+                change_pct: None,
+                bytes_per_sec: None,
+            }],
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        metrics_last_success: Some(at(2)),
+        active_panel: ActivePanel::Metrics,
+        selected_metric_index: Some(0),
+        inspection: Some(Inspection { uri: "/api/orders".to_string(), series: Some(vec![series("10.0.1.12:8080", 72.3), series("10.0.1.13:8080", 48.2)]), ..Inspection::default() }),
+        ..AppState::default()
+    }
+}
+
+#[test]
+fn lookups_follow_the_selection_and_the_refreshes() {
+    let mut inspection = Inspection::default();
+    assert!(inspection.start("/api/orders", Some(at(1)), at(2)));
+    assert!(!inspection.start("/api/orders", Some(at(1)), at(3)), "nothing new to look up");
+    inspection.finish("/api/orders", Ok(vec![series("a", 1.0)]));
+    assert!(inspection.start("/api/orders", Some(at(4)), at(4)), "the metrics refreshed");
+    assert!(inspection.series.is_some(), "the last series stay while the new ones load");
+
+    assert!(inspection.start("/login", Some(at(4)), at(5)));
+    assert!(inspection.series.is_none());
+    inspection.finish("/api/orders", Ok(vec![series("a", 1.0)]));
+    assert!(inspection.series.is_none(), "a lookup for a row no longer selected is dropped");
+    inspection.finish("/login", Err("HTTP 503".to_string()));
+    assert_eq!(inspection.error.as_deref(), Some("HTTP 503"));
+}
+
+#[test]
+fn labels_are_written_as_a_selector() {
+    assert_eq!(inspect::format_labels(&series("10.0.1.12:8080", 1.0).labels), r#"{instance="10.0.1.12:8080", uri="/api/orders"}"#);
+    assert_eq!(inspect::format_labels(&BTreeMap::new()), "{}");
+}
+
+#[test]
+fn row_inspection_shows_exact_values_queries_and_series() {
+    let state = state();
+    let shown = common::screen(&state, 140, 40);
+    assert!(shown.contains(" Inspect "), "{}", shown);
+    assert!(shown.contains("/api/orders at 2024-05-01 12:02:00  latency 45.123456 ms  120.5 req/min"), "{}", shown);
+    let [latency, count] = prometheus::uri_metric_queries(&state.metrics_time_range.to_prometheus_range());
+    assert!(shown.contains(&format!("query: {}", latency)), "{}", shown);
+    assert!(shown.contains(&format!("query: {}", count)), "{}", shown);
+    assert!(shown.contains("2 series at 12:02:00:"), "{}", shown);
+    assert!(shown.contains(r#"72.300 req/min     41.250 ms  {instance="10.0.1.12:8080", uri="/api/orders"}"#), "{}", shown);
+
+    let mut logs = state;
+    logs.active_panel = ActivePanel::Logs;
+    assert!(!common::screen(&logs, 140, 40).contains(" Inspect "), "only while the metrics panel shows");
+}
+
+#[test]
+fn chart_inspection_shows_the_point_under_the_cursor() {
+    let mut state = state();
+    let t = at(1).timestamp() as f64;
+    let mut drill_in = UriDrillIn::new("/api/orders".to_string());
+    drill_in.history = Some(UriHistory {
+        latency_ms: vec![(t, 38.5), (t + 60.0, 52.75)],
+        requests_per_min: vec![(t, 100.0), (t + 60.0, 110.0)],
+        queries: vec!["sum(rate(latency[4m]))".to_string()],
+    });
+    drill_in.cursor = Some(0);
+    state.drill_in = Some(drill_in);
+    let screen = common::screen(&state, 140, 40);
+    assert!(screen.contains("/api/orders at 2024-05-01 12:01:00  latency 38.5 ms  100 req/min"), "{}", screen);
+    assert!(screen.contains("query: sum(rate(latency[4m]))"), "{}", screen);
+}
@@ -0,0 +1,74 @@
+use ratatui::layout::Rect;
+use rustdash::config::Settings;
+use rustdash::layout::{self, CellContent, LayoutConfig};
+use rustdash::ui::AppState;
+
+mod common;
+
+fn screen_lines(state: &AppState) -> Vec<String> {
+    common::screen(state, 120, 36).lines().map(String::from).collect()
+}
+
+#[test]
+fn split_hands_out_minimums_then_weights() {
+    assert_eq!(layout::split(100, &[1, 1], &[0, 0]), [50, 50]);
+    assert_eq!(layout::split(10, &[1, 1, 1], &[0, 0, 0]), [3, 3, 4], "the parts add up");
+    assert_eq!(layout::split(100, &[1, 3], &[40, 0]), [40, 60]);
+    assert_eq!(layout::split(100, &[1, 1], &[40, 0]), [50, 50], "a share above the minimum stands");
+    // Minimums that don't fit give way to the weights
+    assert_eq!(layout::split(20, &[1, 1], &[15, 15]), [10, 10]);
+    assert_eq!(layout::split(20, &[0, 1], &[5, 0]), [5, 15]);
+}
+
+#[test]
+fn cells_span_rows_and_columns() {
+    let config: Settings = toml::from_str(
+        r#"
+        [layout]
+        rows = [1, 1]
+        columns = [2, 1]
+
+        [[layout.cells]]
+        show = "metrics"
+        row_span = 2
+
+        [[layout.cells]]
+        show = { panel = 0 }
+        column = 1
+
+        [[layout.cells]]
+        show = "logs"
+        row = 1
+        column = 1
+        min_height = 15
+        "#,
+    )
+    .unwrap();
+    let layout = config.layout;
+    assert!(layout.is_grid());
+    let areas = layout.areas(Rect::new(0, 0, 90, 30));
+    assert_eq!(areas[0], (CellContent::Metrics, Rect::new(0, 0, 60, 30)));
+    assert_eq!(areas[1], (CellContent::Panel(0), Rect::new(60, 0, 30, 15)));
+    assert_eq!(areas[2], (CellContent::Logs, Rect::new(60, 15, 30, 15)));
+
+    // The logs' row keeps its minimum, the other row gets what is left
+    assert_eq!(layout.area_of(CellContent::Logs, Rect::new(0, 0, 90, 20)), Some(Rect::new(60, 5, 30, 15)));
+    assert!(!LayoutConfig::default().is_grid());
+}
+
+#[test]
+fn metrics_and_logs_side_by_side() {
+    let layout: LayoutConfig = toml::from_str(
+        "[[cells]]\nshow = \"metrics\"\n\n[[cells]]\nshow = \"logs\"\ncolumn = 1\n\n[[cells]]\nshow = { panel = 3 }\nrow = 1\ncolumn_span = 2",
+    )
+    .unwrap();
+    let state = AppState { layout, ..AppState::default() };
+    let lines = screen_lines(&state);
+    let top = lines.iter().position(|line| line.contains("API Response Times")).expect("metrics cell");
+    assert!(lines[top].contains("Loki Logs"), "{}", lines.join("\n"));
+    assert!(lines.iter().any(|line| line.contains(" Panel 3 ")), "{}", lines.join("\n"));
+    assert!(lines.iter().any(|line| line.contains("Loading…")));
+
+    // Row counts follow the cells: both rows share the main area evenly
+    assert_eq!(state.get_visible_height(36), (36 - 11) / 2 - 3);
+}
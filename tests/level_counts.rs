@@ -0,0 +1,46 @@
+use rustdash::loki::{LevelCounts, LogEntry};
+use rustdash::ui::AppState;
+
+mod common;
+
+fn title(state: &AppState) -> String {
+    common::screen(state, 160, 40)
+        .lines()
+        .find(|line| line.contains("Loki Logs"))
+        .map(String::from)
+        .unwrap()
+}
+
+fn logs(levels: &[(&str, usize)]) -> Vec<LogEntry> {
+    levels.iter().flat_map(|&(level, count)| (0..count).map(move |_| common::log(0, level, &format!("something at {}", level)))).collect()
+}
+
+#[test]
+fn counts_each_level() {
+    let counts = LevelCounts::of(&logs(&[("ERROR", 2), ("WARN", 3), ("INFO", 4), ("DEBUG", 1), ("TRACE", 1)]));
+    assert_eq!(counts, LevelCounts { error: 2, warn: 3, info: 5, debug: 1 });
+}
+
+#[test]
+fn title_shows_the_mix() {
+    let all_logs = logs(&[("ERROR", 12), ("WARN", 40), ("INFO", 310)]);
+    let state = AppState { log_level_counts: LevelCounts::of(&all_logs), all_logs, ..AppState::default() };
+    let title = title(&state);
+    assert!(title.contains("[362 entries] E:12 W:40 I:310 "), "{}", title);
+    assert!(!title.contains("D:0"), "debug only shows when there is some");
+}
+
+#[test]
+fn counts_follow_the_buffer() {
+    let all_logs = logs(&[("ERROR", 2), ("DEBUG", 3)]);
+    let mut state = AppState { log_level_counts: LevelCounts::of(&all_logs), all_logs, ..AppState::default() };
+    assert!(title(&state).contains("E:2 W:0 I:0 D:3"));
+
+    state.ignore.ignore_log("something at DEBUG");
+    state.hide_ignored_logs();
+    assert_eq!(state.log_level_counts, LevelCounts { error: 2, ..LevelCounts::default() });
+
+    state.flush_logs();
+    assert_eq!(state.log_level_counts, LevelCounts::default());
+    assert!(!title(&state).contains("E:"), "nothing to count");
+}
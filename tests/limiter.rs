@@ -0,0 +1,61 @@
+use rustdash::config::Settings;
+use rustdash::limiter::QueryLimiter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn caps_queries_in_flight() {
+    let limiter = Arc::new(QueryLimiter::new(2, None));
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for _ in 0..8 {
+        let (limiter, in_flight, peak) = (limiter.clone(), in_flight.clone(), peak.clone());
+        tasks.spawn(async move {
+            let _permit = limiter.acquire().await;
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    while tasks.join_next().await.is_some() {}
+
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn spaces_query_starts_by_the_rate_budget() {
+    let limiter = QueryLimiter::new(8, Some(50.0));
+    let started = Instant::now();
+
+    for _ in 0..5 {
+        drop(limiter.acquire().await);
+    }
+
+    // The first query starts at once, the next four 20ms apart
+    assert!(started.elapsed() >= Duration::from_millis(80), "{:?}", started.elapsed());
+}
+
+#[test]
+fn rates_too_small_to_space_queries_are_refused_in_the_config() {
+    let mut settings = Settings::default();
+    assert!(settings.validate().is_ok());
+    settings.prometheus.max_queries_per_second = Some(0.5);
+    assert!(settings.validate().is_ok());
+    for rate in [1e-30, 0.0, -1.0, f64::NAN] {
+        settings.loki.max_queries_per_second = Some(rate);
+        assert!(settings.validate().is_err(), "{}", rate);
+    }
+    // Built directly, such a rate leaves the starts unspaced instead of panicking
+    QueryLimiter::new(1, Some(1e-30));
+}
+
+#[test]
+fn a_zero_refresh_interval_is_refused() {
+    let mut settings = Settings::default();
+    settings.ui.refresh_interval_seconds = 0;
+    assert!(settings.validate().unwrap_err().to_string().contains("refresh_interval_seconds"));
+}
@@ -0,0 +1,108 @@
+use rustdash::log_actions::{self, LogAction, LogActionsConfig, LogFilter, LogLink};
+use rustdash::loki::LogEntry;
+use std::process::Stdio;
+
+fn entry(message: &str) -> LogEntry {
+    LogEntry {
+        timestamp: String::new(),
+        nanos: 1_700_000_000_000_000_000,
+        message: message.to_string(),
+        level: "INFO".to_string(),
+        is_new: false,
+        stream: r#"{job="api"}"#.to_string(),
+        source: None,
+        local: false,
+    }
+}
+
+#[test]
+fn trace_ids_come_from_fields_and_traceparent() {
+    assert_eq!(
+        log_actions::trace_id("order created trace_id=4bf92f3577b34da6a3ce929d0e0e4736").as_deref(),
+        Some("4bf92f3577b34da6a3ce929d0e0e4736")
+    );
+    assert_eq!(
+        log_actions::trace_id(r#"{"traceId": "A3CE929D0E0E4736"}"#).as_deref(),
+        Some("a3ce929d0e0e4736")
+    );
+    assert_eq!(
+        log_actions::trace_id("traceparent 00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").as_deref(),
+        Some("4bf92f3577b34da6a3ce929d0e0e4736")
+    );
+    assert_eq!(log_actions::trace_id("trace_id=short"), None);
+}
+
+#[test]
+fn menu_offers_what_applies_to_the_line() {
+    let config = LogActionsConfig {
+        trace_url: Some("http://tempo/trace/{trace_id}".to_string()),
+        links: vec![LogLink {
+            name: "search".to_string(),
+            url: "http://search?q={message}&at={nanos}".to_string(),
+        }],
+        ..LogActionsConfig::default()
+    };
+    let log = entry("GET /a b trace_id=4bf92f3577b34da6");
+
+    let actions = LogAction::available(&log, &LogFilter::default(), false, &config);
+    assert!(actions.contains(&LogAction::FilterStream(r#"{job="api"}"#.to_string())));
+    assert!(actions.contains(&LogAction::Bookmark));
+    assert!(actions.contains(&LogAction::OpenUrl {
+        label: "Open trace 4bf92f3577b34da6".to_string(),
+        url: "http://tempo/trace/4bf92f3577b34da6".to_string(),
+    }));
+    assert!(actions.contains(&LogAction::OpenUrl {
+        label: "Open search".to_string(),
+        url: "http://search?q=GET%20%2Fa%20b%20trace_id%3D4bf92f3577b34da6&at=1700000000000000000".to_string(),
+    }));
+    assert_eq!(LogAction::CopyStream.copy_text(&log).as_deref(), Some(r#"{job="api"}"#));
+    let labels: Vec<String> = actions.iter().map(LogAction::label).collect();
+    assert!(labels.contains(&"Pipe line to pager".to_string()) && labels.contains(&"Pipe buffer to editor".to_string()));
+
+    // Undo entries replace the filters already set; no trace, no trace link
+    let filter = LogFilter {
+        stream: Some(r#"{job="api"}"#.to_string()),
+        pattern: Some("x".to_string()),
+        ..LogFilter::default()
+    };
+    let actions = LogAction::available(&entry("plain"), &filter, true, &LogActionsConfig::default());
+    assert!(actions.contains(&LogAction::ClearStreamFilter) && actions.contains(&LogAction::ClearSearch));
+    assert!(actions.contains(&LogAction::RemoveBookmark));
+    assert!(!actions.iter().any(|action| matches!(action, LogAction::OpenUrl { .. })));
+}
+
+#[cfg(unix)]
+#[test]
+fn local_lines_are_checked_against_the_compiled_pattern() {
+    let filter = LogFilter { pattern: Some(r"order \d+".to_string()), ..LogFilter::default() };
+    let pattern = filter.compile_pattern();
+    assert!(filter.matches(&entry("order 42 failed"), pattern.as_ref()));
+    assert!(!filter.matches(&entry("health ok"), pattern.as_ref()));
+
+    let broken = LogFilter { pattern: Some("(".to_string()), ..LogFilter::default() };
+    assert!(broken.compile_pattern().is_none());
+    assert!(!broken.matches(&entry("order 42 failed"), None), "a pattern that doesn't compile hides the lines");
+    assert!(LogFilter::default().matches(&entry("anything"), None));
+}
+
+#[test]
+fn commands_get_the_lines_on_stdin_or_in_a_file() {
+    let dir = std::env::temp_dir().join(format!("rustdash-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let piped = dir.join("piped");
+    let copied = dir.join("copied");
+
+    log_actions::run_command(&format!("tr a-z A-Z > '{}'", piped.display()), "one\ntwo\n", Stdio::null()).unwrap();
+    assert_eq!(std::fs::read_to_string(&piped).unwrap(), "ONE\nTWO\n");
+
+    log_actions::run_command(&format!("cp {{file}} '{}'", copied.display()), "{\"a\":1}\n", Stdio::null()).unwrap();
+    assert_eq!(std::fs::read_to_string(&copied).unwrap(), "{\"a\":1}\n");
+    // Only we may read the lines
+    let mode = dir.join("mode");
+    log_actions::run_command(&format!("ls -l {{file}} | cut -c1-10 > '{}'", mode.display()), "secret\n", Stdio::null()).unwrap();
+    assert_eq!(std::fs::read_to_string(&mode).unwrap(), "-rw-------\n");
+
+    let error = log_actions::run_command("exit 3", "", Stdio::null()).unwrap_err();
+    assert!(error.to_string().contains("exit status: 3"), "{}", error);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
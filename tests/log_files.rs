@@ -0,0 +1,172 @@
+use rustdash::admin::AdminAction;
+use rustdash::fetch::{self, RefreshScope};
+use rustdash::log_actions::{LogAction, LogActionsConfig, LogFilter};
+use rustdash::log_files::{wildcard, LogFiles, LogFilesConfig, BACKLOG_BYTES, MAX_LINE_BYTES, MAX_READ_BYTES};
+use rustdash::ui::AppState;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustdash-log-files-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn append(path: &Path, text: &str) {
+    OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(text.as_bytes()).unwrap();
+}
+
+fn files(paths: &[PathBuf]) -> LogFiles {
+    let paths = paths.iter().map(|path| path.display().to_string()).collect();
+    LogFiles::new(&LogFilesConfig { paths }).unwrap()
+}
+
+fn messages(files: &mut LogFiles) -> Vec<String> {
+    files.poll(100).into_iter().map(|log| log.message).collect()
+}
+
+#[test]
+fn wildcards_match_within_the_file_name() {
+    assert!(wildcard("*.log", "app.log"));
+    assert!(wildcard("app-?.log", "app-1.log"));
+    assert!(wildcard("a*b*c", "aXbYbZc"));
+    assert!(!wildcard("*.log", "app.log.1"));
+    assert!(!wildcard("app-?.log", "app-10.log"));
+    assert!(LogFiles::new(&LogFilesConfig::default()).is_none(), "nothing to follow");
+}
+
+#[test]
+fn appended_lines_are_followed_with_the_filename_as_their_stream() {
+    let dir = temp_dir("follow");
+    let path = dir.join("app.log");
+    append(&path, "2024-05-01T12:00:00Z ERROR boom\nstarted\n");
+    let mut files = files(std::slice::from_ref(&path));
+
+    let logs = files.poll(100);
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0].stream, format!(r#"{{filename="{}"}}"#, path.display()));
+    assert_eq!((logs[0].level.as_str(), logs[0].nanos), ("ERROR", 1_714_564_800_000_000_000));
+    assert_eq!(logs[0].timestamp, "2024-05-01 12:00:00");
+    assert!(logs[1].nanos > logs[0].nanos, "lines without a timestamp keep the file's order");
+
+    // A line is only taken once it is complete
+    append(&path, "half");
+    assert_eq!(messages(&mut files), ["2024-05-01T12:00:00Z ERROR boom", "started"]);
+    append(&path, " done\n");
+    assert_eq!(messages(&mut files).last().unwrap(), "half done");
+    assert_eq!(files.poll(1).len(), 1, "only the newest lines are kept");
+}
+
+#[test]
+fn file_lines_get_no_actions_that_go_to_loki() {
+    let dir = temp_dir("local");
+    let path = dir.join("app.log");
+    append(&path, "2024-05-01T12:00:00Z ERROR boom
+2024-05-01T12:00:05Z ERROR boom again
+");
+    let mut logs = files(std::slice::from_ref(&path)).poll(100);
+    assert!(logs.iter().all(|log| log.local));
+
+    let actions = LogAction::available(&logs[0], &LogFilter::default(), false, &LogActionsConfig::default());
+    assert!(actions.contains(&LogAction::CopyStream));
+    assert!(!actions.iter().any(|action| matches!(action, LogAction::FilterStream(_) | LogAction::SearchSimilar(_))));
+    assert_eq!(AdminAction::delete_stream(&logs, 0), None);
+
+    // Promtail shipping the same file: only Loki's own line is spanned
+    logs.push(rustdash::loki::LogEntry { local: false, nanos: 1_714_564_810_000_000_000, ..logs[0].clone() });
+    let Some(AdminAction::LokiDelete { start, end, .. }) = AdminAction::delete_stream(&logs, 2) else { panic!("no delete") };
+    assert_eq!((start, end), (1_714_564_810, 1_714_564_811));
+}
+
+#[test]
+fn large_files_start_near_their_end_even_when_they_show_up_later() {
+    let dir = temp_dir("backlog");
+    let big = dir.join("big.log");
+    let line = format!("{}\n", "x".repeat(99));
+    append(&big, &line.repeat((BACKLOG_BYTES / 100) as usize + 50));
+    let mut files = files(&[dir.join("*.log")]);
+    let logs = files.poll(usize::MAX);
+    assert!(logs.len() < (BACKLOG_BYTES / 100) as usize + 1);
+    assert!(logs.iter().all(|log| log.message.len() == 99), "the cut-off line is dropped");
+
+    append(&dir.join("new.log"), "first\nsecond\n");
+    let logs = files.poll(usize::MAX);
+    assert!(logs.iter().any(|log| log.message == "first"), "a small file created later is read whole");
+
+    append(&dir.join("later.log"), &line.repeat((BACKLOG_BYTES / 100) as usize + 50));
+    let before = logs.len();
+    let logs = files.poll(usize::MAX);
+    assert!(logs.len() - before < (BACKLOG_BYTES / 100) as usize + 1, "a large one from near its end");
+}
+
+#[test]
+fn reads_and_lines_are_bounded() {
+    let dir = temp_dir("bounded");
+    let path = dir.join("app.log");
+    append(&path, "");
+    let mut files = files(std::slice::from_ref(&path));
+    assert!(files.poll(usize::MAX).is_empty());
+
+    // A burst is read a slice per poll
+    let line = format!("{}\n", "x".repeat(1023));
+    append(&path, &line.repeat(2 * (MAX_READ_BYTES / 1024) as usize));
+    assert_eq!(files.poll(usize::MAX).len(), (MAX_READ_BYTES / 1024) as usize);
+    assert_eq!(files.poll(usize::MAX).len(), 2 * (MAX_READ_BYTES / 1024) as usize);
+
+    // A line without an end is cut, and what follows of it dropped
+    append(&path, &"y".repeat(MAX_LINE_BYTES + 10));
+    assert_eq!(files.poll(usize::MAX).last().unwrap().message.len(), MAX_LINE_BYTES);
+    append(&path, &format!("{}\nnext\n", "y".repeat(MAX_LINE_BYTES)));
+    let logs = files.poll(usize::MAX);
+    let tail: Vec<usize> = logs.iter().rev().take(2).map(|log| log.message.len()).collect();
+    assert_eq!(tail, [4, MAX_LINE_BYTES]);
+}
+
+#[test]
+fn rotation_and_truncation_are_followed() {
+    let dir = temp_dir("rotate");
+    let path = dir.join("app.log");
+    append(&path, "one\n");
+    let mut files = files(&[dir.join("app.log*")]);
+    assert_eq!(messages(&mut files), ["one"]);
+
+    // Renamed away with a last line still written to it, and recreated
+    append(&path, "two\n");
+    fs::rename(&path, dir.join("app.log.1")).unwrap();
+    append(&dir.join("app.log.1"), "three\n");
+    append(&path, "four\n");
+    assert_eq!(messages(&mut files), ["one", "two", "three", "four"]);
+    assert_eq!(messages(&mut files).len(), 4, "the rotated file matching the glob isn't read twice");
+
+    // Truncated in place (copytruncate), noticed once shorter than what was read
+    fs::write(&path, "").unwrap();
+    append(&path, "5\n");
+    assert_eq!(messages(&mut files).last().unwrap(), "5");
+}
+
+#[tokio::test]
+async fn without_loki_files_feed_the_logs_panel_through_the_filter() {
+    let dir = temp_dir("fetch");
+    let (api, worker) = (dir.join("api.log"), dir.join("worker.log"));
+    append(&api, "GET /orders\nGET /health\n");
+    append(&worker, "job done\n");
+    let state = Mutex::new(AppState {
+        loki_enabled: false,
+        log_files: Some(files(&[dir.join("*.log")])),
+        ..AppState::default()
+    });
+    assert!(state.lock().await.logs_enabled());
+
+    fetch::refresh_scope(&state, None, None, 100, &[], RefreshScope::Logs).await;
+    assert_eq!(state.lock().await.all_logs.len(), 3);
+
+    let stream = format!(r#"{{filename="{}"}}"#, api.display());
+    state.lock().await.set_log_filter(LogFilter { stream: Some(stream), pattern: Some("orders".to_string()), query: None });
+    fetch::refresh_scope(&state, None, None, 100, &[], RefreshScope::Logs).await;
+    let state = state.lock().await;
+    let messages: Vec<&str> = state.all_logs.iter().map(|log| log.message.as_str()).collect();
+    assert_eq!(messages, ["GET /orders"]);
+}
@@ -0,0 +1,51 @@
+use rustdash::config::Settings;
+use rustdash::ui::{ActivePanel, AppState};
+
+mod common;
+
+const SECOND: i64 = 1_000_000_000;
+
+/// A quiet stretch of a line a minute, then a burst of 100 lines a second.
+fn state() -> AppState {
+    let start = 1_700_000_000 * SECOND;
+    let quiet = (0..10).map(|i| common::log(start + i * 60 * SECOND, "INFO", ""));
+    let burst = (0..100).map(|i| common::log(start + 600 * SECOND + i * SECOND / 100, "INFO", ""));
+    let mut state = AppState { all_logs: quiet.chain(burst).collect(), ..AppState::default() };
+    state.update_visible_logs_with_height(30);
+    state
+}
+
+#[test]
+fn steps_through_a_burst_in_one_jump() {
+    let mut state = state();
+    // From the newest line, 30s back clears the whole burst
+    assert!(state.jump_logs(-30, 30).ends_with(", 100 lines back"));
+    assert_eq!(state.selected_log_index, Some(9));
+    assert_eq!(state.active_panel, ActivePanel::Logs);
+    assert_eq!(state.time_cursor, Some(state.all_logs[9].nanos as f64 / 1e9));
+
+    // In the quiet stretch it's a line per minute
+    state.jump_logs(-300, 30);
+    assert_eq!(state.selected_log_index, Some(4));
+    state.jump_logs(30, 30);
+    assert_eq!(state.selected_log_index, Some(5), "on to the first line after");
+}
+
+#[test]
+fn stops_at_the_ends() {
+    let mut state = state();
+    state.selected_log_index = Some(0);
+    assert_eq!(state.jump_logs(-300, 30), "No earlier log lines");
+    state.selected_log_index = Some(109);
+    assert_eq!(state.jump_logs(300, 30), "No later log lines");
+    state.jump_logs(-100_000, 30);
+    assert_eq!(state.selected_log_index, Some(0));
+    assert_eq!(AppState::default().jump_logs(30, 30), "No log lines");
+}
+
+#[test]
+fn steps_are_configurable() {
+    assert_eq!(Settings::default().ui.log_jump_steps, ["30s", "5m"]);
+    let settings: Settings = toml::from_str("[ui]\nlog_jump_steps = [\"10s\", \"1h\"]").unwrap();
+    assert_eq!(settings.ui.log_jump_steps, ["10s", "1h"]);
+}
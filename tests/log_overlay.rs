@@ -0,0 +1,29 @@
+use rustdash::log_overlay;
+
+mod common;
+
+#[test]
+fn merge_interleaves_queries_by_time_and_tags_their_lines() {
+    let merged = log_overlay::merge(vec![
+        vec![common::log(10, "INFO", "app 1"), common::log(30, "INFO", "app 2")],
+        vec![common::log(20, "INFO", "ingress 1"), common::log(30, "INFO", "ingress 2"), common::log(40, "INFO", "ingress 3")],
+    ]);
+    let lines: Vec<(&str, Option<usize>)> = merged.iter().map(|log| (log.message.as_str(), log.source)).collect();
+    assert_eq!(
+        lines,
+        [
+            ("app 1", Some(0)),
+            ("ingress 1", Some(1)),
+            // Same instant: the earlier query first
+            ("app 2", Some(0)),
+            ("ingress 2", Some(1)),
+            ("ingress 3", Some(1)),
+        ]
+    );
+}
+
+#[test]
+fn merge_of_nothing_is_empty() {
+    assert!(log_overlay::merge(vec![Vec::new(), Vec::new()]).is_empty());
+    assert!(log_overlay::merge(Vec::new()).is_empty());
+}
@@ -0,0 +1,130 @@
+use ratatui::style::Color;
+use reqwest::StatusCode;
+use rustdash::frontend::{Frontend, MemoryFrontend};
+use rustdash::log_actions::LogFilter;
+use rustdash::log_query::{check, tokenize, LogQueryBar, TokenKind};
+use rustdash::loki::LokiError;
+use rustdash::persist::PersistedState;
+use rustdash::ui::AppState;
+
+/// The text the local check points at, and its message.
+fn error(query: &str) -> (String, String) {
+    let e = check(query).unwrap_err();
+    let at = e.at.clone().unwrap();
+    (query[at].to_string(), e.message)
+}
+
+#[test]
+fn tokens_classify_each_part_of_the_query() {
+    let query = r#"{app="api"} |~ "time(out" | json | status >= 500 | line_format `{{.msg}}`"#;
+    let kinds: Vec<(TokenKind, &str)> = tokenize(query).iter().map(|t| (t.kind, &query[t.start..t.end])).collect();
+    assert_eq!(&kinds[..5], [
+        (TokenKind::Punct, "{"),
+        (TokenKind::Name, "app"),
+        (TokenKind::Operator, "="),
+        (TokenKind::String, r#""api""#),
+        (TokenKind::Punct, "}"),
+    ]);
+    assert!(kinds.contains(&(TokenKind::Operator, "|~")));
+    assert!(kinds.contains(&(TokenKind::Keyword, "json")));
+    assert!(kinds.contains(&(TokenKind::Operator, ">=")));
+    assert!(kinds.contains(&(TokenKind::Number, "500")));
+    assert_eq!(kinds.last(), Some(&(TokenKind::String, "`{{.msg}}`")));
+    assert_eq!(tokenize(r#"{app="api"#).last().unwrap().kind, TokenKind::Unclosed);
+}
+
+#[test]
+fn well_formed_log_queries_pass() {
+    for query in [
+        r#"{app="api"}"#,
+        r#"{app=~"api|web", env!="dev"} |= "GET" != "/health" |~ `(?i)timeout` or "refused""#,
+        r#"{job="nginx"} | logfmt --strict | status >= 500 and duration > 1.5s, method="GET""#,
+        r#"{job="nginx"} | json user="user.id", path | line_format "{{.path}}" | label_format who=user"#,
+        r#"{job="nginx"} | regexp "(?P<ip>\\S+) - -" | drop ip, __error__ | (level="error" or level="fatal")"#,
+        r#"{job="nginx"} | pattern "<ip> - - <_>" | keep ip | decolorize | unpack"#,
+        r#"{job="nginx"} |= ip("10.0.0.0/8")"#,
+    ] {
+        assert_eq!(check(query), Ok(()), "{}", query);
+    }
+}
+
+#[test]
+fn local_check_points_at_what_is_wrong() {
+    assert_eq!(error(r#"{app="api""#).0, "");
+    assert_eq!(error(r#"{app "api"}"#).0, r#""api""#);
+    assert_eq!(error(r#"{}"#), ("{".to_string(), "the stream selector needs at least one matcher".to_string()));
+    assert_eq!(error(r#"{app=~".*"}"#).0, r#"{app=~".*"}"#, "matches every stream");
+    assert_eq!(error(r#"{app="api"} |~ "time(out""#), (r#""time(out""#.to_string(), "invalid regex: unclosed group".to_string()));
+    assert!(error(r#"{app="api"} |= "a\.b""#).1.starts_with(r"invalid escape \."));
+    assert_eq!(error(r#"{app="api"} |= "oops"#), (r#""oops"#.to_string(), "missing closing quote".to_string()));
+    assert_eq!(error(r#"{app="api"} | jsonn"#).0, "jsonn");
+    assert_eq!(error(r#"{app="api"} | status >"#).0, "");
+    assert_eq!(error(r#"{app="api"} | regexp "(\d+)""#).0, r#""(\d+)""#);
+    assert!(error(r#"count_over_time({app="api"}[5m])"#).1.starts_with("metric queries"));
+    assert_eq!(error(r#"{app="api"} "GET""#).0, r#""GET""#);
+}
+
+#[test]
+fn the_bar_starts_from_the_current_filter() {
+    let filter = LogFilter { stream: Some(r#"{job="api"}"#.to_string()), pattern: Some(r"a\d".to_string()), query: None };
+    assert_eq!(filter.logql().as_deref(), Some(r#"{job="api"} |~ "a\\d""#));
+    assert_eq!(LogFilter { pattern: Some("x".to_string()), ..LogFilter::default() }.logql(), None);
+    let filter = LogFilter::query(r#"{job="api"} | json"#);
+    assert_eq!(filter.logql().as_deref(), Some(r#"{job="api"} | json"#));
+    assert_eq!(filter.describe(), r#"{job="api"} | json"#);
+}
+
+#[test]
+fn submitting_checks_locally_then_waits_for_loki() {
+    let mut bar = LogQueryBar::new(r#"  {app="api"} |= "x" | foo"#);
+    assert_eq!(bar.submit(), None);
+    let at = bar.error.as_ref().unwrap().at.clone().unwrap();
+    assert_eq!(&bar.input.text[at], "foo", "positions account for leading spaces");
+
+    bar.input.clear_to_start();
+    r#"{app="api"} | line_format "{{.msg""#.chars().for_each(|c| bar.input.insert(c));
+    bar.edited();
+    assert_eq!(bar.error, None);
+    assert_eq!(bar.submit().as_deref(), Some(r#"{app="api"} | line_format "{{.msg""#));
+    assert!(bar.submitted.is_some());
+
+    let e = LokiError::status(StatusCode::BAD_REQUEST, "parse error at line 1, col 15: template: line:1: unclosed action");
+    bar.reject(&e);
+    let error = bar.error.clone().unwrap();
+    assert_eq!(error.message, "Loki: parse error at line 1, col 15: template: line:1: unclosed action");
+    assert_eq!(&bar.input.text[error.at.unwrap()], "line_format");
+    assert_eq!(bar.submitted, None);
+}
+
+#[test]
+fn the_bar_highlights_syntax_and_shows_the_error_under_the_input() {
+    let mut bar = LogQueryBar::new(r#"{app="api"} |~ "a(""#);
+    bar.submit();
+    let state = AppState { log_query_bar: Some(bar), ..AppState::default() };
+    let mut frontend = MemoryFrontend::new(100, 30).unwrap();
+    frontend.draw(&state).unwrap();
+    let rows = frontend.lines();
+
+    let input_row = rows.iter().position(|row| row.contains(r#"/ {app="api"} |~ "a(""#)).expect("input shown");
+    assert!(rows[input_row + 1].contains("✗ invalid regex: unclosed group"), "{}", rows[input_row + 1]);
+    assert!(rows[input_row - 1].contains("LogQL"));
+    let x = |needle: &str| rows[input_row].find(needle).map(|byte| rows[input_row][..byte].chars().count()).unwrap() as u16;
+    let y = input_row as u16;
+    let buffer = frontend.terminal().backend().buffer();
+    assert_eq!(buffer[(x("app"), y)].fg, Color::Cyan);
+    assert_eq!(buffer[(x("\"api\""), y)].fg, Color::Green);
+    assert_eq!(buffer[(x("|~"), y)].fg, Color::Yellow);
+    assert_eq!(buffer[(x("\"a(\""), y)].fg, Color::Red, "the failing regex is marked");
+}
+
+#[test]
+fn the_filter_and_last_query_survive_a_restart() {
+    let query = r#"{app="api"} |= "timeout""#;
+    let state = AppState { log_filter: LogFilter::query(query), last_log_query: Some(query.to_string()), ..AppState::default() };
+    let json = serde_json::to_string(&PersistedState::capture(&state)).unwrap();
+
+    let mut restored = AppState::default();
+    serde_json::from_str::<PersistedState>(&json).unwrap().apply(&mut restored);
+    assert_eq!(restored.log_filter, LogFilter::query(query));
+    assert_eq!(restored.last_log_query.as_deref(), Some(query));
+}
@@ -0,0 +1,44 @@
+use rustdash::logql::{count_over_time, rate, StreamSelector};
+use rustdash::promql::MatchOp;
+
+#[test]
+fn selector_values_are_escaped() {
+    let selector = StreamSelector::new().eq("app", r#"web" } |= "x"#).re("host", ".+");
+    assert_eq!(selector.to_string(), r#"{app="web\" } |= \"x", host=~".+"}"#);
+    assert_eq!(StreamSelector::new().to_string(), "{}");
+}
+
+#[test]
+fn pipeline_stages_render_in_order() {
+    let query = StreamSelector::new()
+        .eq("service_name", "api")
+        .pipe()
+        .contains("GET")
+        .not_contains("/health")
+        .json()
+        .label("status", MatchOp::Regex, "5..")
+        .not_matches(r"user=\d+");
+    assert_eq!(
+        query.to_string(),
+        r#"{service_name="api"} |= "GET" != "/health" | json | status=~"5.." !~ "user=\\d+""#
+    );
+}
+
+#[test]
+fn error_filter_is_valid_logql() {
+    let query = StreamSelector::new().re("service_name", ".+").pipe().matches("(?i)error");
+    assert_eq!(query.to_string(), r#"{service_name=~".+"} |~ "(?i)error""#);
+}
+
+#[test]
+fn metric_queries_wrap_log_ranges() {
+    let selector = StreamSelector::new().eq("app", "api");
+    assert_eq!(
+        count_over_time(selector.clone(), "1m").sum().to_string(),
+        r#"sum(count_over_time({app="api"}[1m]))"#
+    );
+    assert_eq!(
+        rate(selector.pipe().contains("ERROR"), "5m").sum_by(&["level"]).to_string(),
+        r#"sum by (level) (rate({app="api"} |= "ERROR"[5m]))"#
+    );
+}
@@ -0,0 +1,73 @@
+use rustdash::prometheus::{MetricsData, UriMetric};
+use rustdash::ui::{humanize_bytes, metrics_columns, scrolled_metrics_columns, AppState, MetricsColumn};
+
+mod common;
+
+fn state(uri_metrics: Vec<UriMetric>) -> AppState {
+    AppState {
+        metrics: Some(MetricsData {
+            http_requests_total: 0.0,
+            uri_metrics,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        ..AppState::default()
+    }
+}
+
+#[test]
+fn columns_follow_the_data() {
+    assert_eq!(metrics_columns(&state(vec![common::metric("/a", 10.0, 4321.0)])), [MetricsColumn::Response, MetricsColumn::Rank]);
+    let mut state = state(vec![UriMetric { bytes_per_sec: Some(2048.0), ..common::metric("/a", 10.0, 4321.0) }]);
+    state.uri_error_pattern = Some("ERROR".to_string());
+    assert_eq!(
+        metrics_columns(&state),
+        [MetricsColumn::Response, MetricsColumn::Rank, MetricsColumn::Bandwidth, MetricsColumn::LastError]
+    );
+}
+
+#[test]
+fn the_last_column_never_scrolls_away() {
+    let state = AppState { metrics_column_offset: 7, ..state(vec![UriMetric { bytes_per_sec: Some(2048.0), ..common::metric("/a", 10.0, 4321.0) }]) };
+    assert_eq!(scrolled_metrics_columns(&state), [MetricsColumn::Response, MetricsColumn::Rank]);
+}
+
+#[test]
+fn uri_and_header_stay_while_columns_scroll() {
+    let mut state = state(vec![
+        UriMetric { bytes_per_sec: Some(2048.0), ..common::metric("/api/orders", 1234.5, 4321.0) },
+        UriMetric { bytes_per_sec: Some(512.0), ..common::metric("/health", 3.0, 4321.0) },
+    ]);
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains("Response Time (ms)"));
+    assert!(shown.contains("1234.5"));
+
+    state.metrics_column_offset = 1;
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains("/api/orders"), "{}", shown);
+    assert!(shown.contains("URI"));
+    assert!(!shown.contains("Response Time (ms)"));
+    assert!(!shown.contains("1234.5"));
+    assert!(shown.contains("4321"));
+    assert!(shown.contains("2.0K/s"));
+    assert!(shown.contains("◀ 1 column scrolled"));
+
+    state.metrics_column_offset = 2;
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains("/health"));
+    assert!(!shown.contains("4321"));
+    let health = shown.lines().find(|line| line.contains("/health")).unwrap();
+    assert!(health.contains(&format!("{:>9}", format!("{}/s", humanize_bytes(512.0)))), "{}", shown);
+    assert!(shown.contains("◀ 2 columns scrolled"));
+}
+
+#[test]
+fn header_stays_when_rows_scroll() {
+    let rows = (0..60).map(|i| common::metric(&format!("/api/item/{}", i), 10.0 + i as f64, 4321.0)).collect();
+    let state = AppState { metrics_scroll_offset: 40, ..state(rows) };
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains("Response Time (ms)"));
+    assert!(!shown.contains("/api/item/0 "), "{}", shown);
+    assert!(shown.contains("/api/item/42"), "{}", shown);
+}
@@ -0,0 +1,111 @@
+use rustdash::metrics_export::{ExportFormat, MetricsExport};
+use rustdash::prometheus::{MetricsData, Ranking, UriHistory, UriMetric};
+use rustdash::ui::{AppState, TimeRange, UriDrillIn};
+
+mod common;
+
+fn state() -> AppState {
+    AppState {
+        metrics: Some(MetricsData {
+            http_requests_total: 0.0,
+            uri_metrics: vec![
+                UriMetric { errors_per_min: 1.5, ..common::metric("/api/orders", 120.5, 42.0) },
+                UriMetric { errors_per_min: 1.5, ..common::metric("/search?q=a,b", 8.0, 3.0) },
+            ],
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        prometheus_url: "http://prometheus:9090".to_string(),
+        metrics_time_range: TimeRange::FiveMin,
+        ..AppState::default()
+    }
+}
+
+#[test]
+fn table_to_csv_with_its_queries() {
+    let csv = MetricsExport::capture(&state()).unwrap().to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "# rustdash: table");
+    assert!(lines.contains(&"# prometheus: http://prometheus:9090"));
+    assert!(lines.contains(&"# time_range: 5m"));
+    assert!(lines.contains(&"# ranking: busiest"));
+    assert_eq!(lines.iter().filter(|line| line.starts_with("# query: ") && line.contains("[5m]")).count(), 2);
+
+    let data: Vec<&str> = lines.into_iter().skip_while(|line| line.starts_with('#')).collect();
+    assert_eq!(
+        data,
+        [
+            "uri,avg_duration_ms,requests_per_min,errors_per_min,change_pct,bytes_per_sec",
+            "/api/orders,120.5,42.0,,,",
+            "\"/search?q=a,b\",8.0,3.0,,,",
+        ]
+    );
+}
+
+#[test]
+fn error_rates_only_when_ranked_by_them() {
+    let mut state = state();
+    state.metrics_ranking = Ranking::Errors;
+    let csv = MetricsExport::capture(&state).unwrap().to_csv();
+    assert!(csv.contains("\n/api/orders,120.5,42.0,1.5,,\n"), "{}", csv);
+}
+
+#[test]
+fn table_to_json_records() {
+    let json: serde_json::Value = serde_json::from_str(&MetricsExport::capture(&state()).unwrap().to_json()).unwrap();
+    assert_eq!(json["kind"], "table");
+    assert_eq!(json["time_range"], "5m");
+    assert_eq!(json["queries"].as_array().unwrap().len(), 2);
+    assert_eq!(json["rows"][0]["uri"], "/api/orders");
+    assert_eq!(json["rows"][0]["avg_duration_ms"], 120.5);
+    assert!(json["rows"][0]["change_pct"].is_null());
+    assert!(json.get("uri").is_none());
+}
+
+#[test]
+fn history_chart_exports_its_series() {
+    let mut state = state();
+    let mut drill_in = UriDrillIn::new("/api/orders".to_string());
+    drill_in.history = Some(UriHistory {
+        latency_ms: vec![(1_700_000_000.0, 110.0), (1_700_000_060.0, 130.0)],
+        requests_per_min: vec![(1_700_000_000.0, 40.0)],
+        queries: vec!["latency query".to_string(), "rate query".to_string()],
+    });
+    state.drill_in = Some(drill_in);
+
+    let export = MetricsExport::capture(&state).unwrap();
+    assert_eq!(export.kind, "series");
+    let json: serde_json::Value = serde_json::from_str(&export.to_json()).unwrap();
+    assert_eq!(json["uri"], "/api/orders");
+    assert_eq!(json["queries"][1], "rate query");
+    assert_eq!(json["rows"][1]["unix_seconds"], 1_700_000_060.0);
+    assert_eq!(json["rows"][1]["latency_ms"], 130.0);
+    assert!(json["rows"][1]["requests_per_min"].is_null());
+
+    let csv = export.to_csv();
+    assert!(csv.contains("# uri: /api/orders\n"));
+    assert!(csv.contains("time,unix_seconds,latency_ms,requests_per_min\n"));
+}
+
+#[test]
+fn nothing_to_export_yet() {
+    assert!(MetricsExport::capture(&AppState::default()).is_none());
+    let mut state = state();
+    state.drill_in = Some(UriDrillIn::new("/api/orders".to_string()));
+    assert!(MetricsExport::capture(&state).is_none(), "the chart hasn't loaded");
+}
+
+#[test]
+fn writes_a_file_per_format() {
+    let dir = std::env::temp_dir().join(format!("rustdash-export-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let export = MetricsExport::capture(&state()).unwrap();
+    let csv = export.write(ExportFormat::Csv, &dir).unwrap();
+    let json = export.write(ExportFormat::Json, &dir).unwrap();
+    assert!(csv.file_name().unwrap().to_string_lossy().starts_with("rustdash-table-"));
+    assert_eq!(csv.extension().unwrap(), "csv");
+    assert_eq!(json.extension().unwrap(), "json");
+    assert_eq!(std::fs::read_to_string(&csv).unwrap(), export.to_csv());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
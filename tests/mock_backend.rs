@@ -0,0 +1,1373 @@
+use chrono::Local;
+use reqwest::{Client, StatusCode};
+use rustdash::fanout::Aggregate;
+use rustdash::fetch::{self, RefreshScope};
+use rustdash::guard::{GuardConfig, SlowQueryGuard};
+use rustdash::admin::{self, AdminAction, AdminMenu};
+use rustdash::breaker::{BreakerConfig, CircuitBreaker};
+use rustdash::config::BandwidthConfig;
+use rustdash::error::ErrorClass;
+use rustdash::exchanges::ExchangeLog;
+use rustdash::ignore::{IgnoreConfig, IgnoreList};
+use rustdash::log_actions::{LogAction, LogActionsConfig, LogFilter};
+use rustdash::log_files::{LogFiles, LogFilesConfig};
+use rustdash::log_overlay::LogQuery;
+use rustdash::log_query::LogQueryBar;
+use rustdash::loki::{LokiClient, LokiError};
+use rustdash::mock::MockBackend;
+use rustdash::panels::{self, CounterView, PanelConfig, PanelData, Preset, Severity, StatStyle};
+use rustdash::prometheus::{PrometheusClient, PrometheusError, Ranking};
+use rustdash::query_editor::{self, EditorRequest, Lookup, QueryEditor};
+use rustdash::query_stats::{Backend, QueryStats};
+use rustdash::silence::{SilenceAlarm, SilenceRule};
+use rustdash::slo::{Slo, SloConfig};
+use rustdash::status_pages::{self, Indicator, StatusPageConfig};
+use rustdash::synthetic::{self, CheckConfig, SyntheticCheck};
+use rustdash::templates::{UriTemplate, UriTemplates};
+use rustdash::text::Truncation;
+use rustdash::ui::{self, AppState, TimeRange, UriDrillIn};
+use rustdash::uri_errors;
+use rustdash::variables::{self, Variable, VariableConfig};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod common;
+
+async fn clients() -> (MockBackend, PrometheusClient, LokiClient) {
+    let backend = MockBackend::start().await.unwrap();
+    let prometheus = PrometheusClient::new(backend.base_url.clone(), Client::new());
+    let loki = LokiClient::new(backend.base_url.clone(), Client::new());
+    (backend, prometheus, loki)
+}
+
+#[tokio::test]
+async fn prometheus_client_reads_uri_metrics() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let metrics = prometheus.get_metrics("5m", Ranking::Busiest).await.unwrap();
+
+    assert!(metrics.errors.is_empty(), "{:?}", metrics.errors);
+    assert!(metrics.http_requests_total > 0.0);
+    assert_eq!(metrics.uri_metrics.len(), 8);
+    assert!(metrics.uri_metrics.iter().all(|m| m.avg_duration_ms > 0.0 && m.request_count > 0.0));
+    // Sorted busiest first
+    assert!(metrics
+        .uri_metrics
+        .windows(2)
+        .all(|pair| pair[0].request_count >= pair[1].request_count));
+}
+
+#[tokio::test]
+async fn loki_client_reads_streams_and_volume() {
+    let (_backend, _, loki) = clients().await;
+
+    let logs = loki.get_recent_logs(50).await.unwrap();
+    assert_eq!(logs.len(), 50);
+    assert!(logs.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    assert!(logs.iter().all(|log| log.message.contains(&format!("[{}]", log.level))));
+
+    // Matrix results from count_over_time
+    let volume = loki.get_log_volume(30, 1).await.unwrap();
+    assert_eq!(volume.len(), 30);
+    assert!(volume.iter().sum::<u64>() > 0);
+}
+
+#[tokio::test]
+async fn refresh_populates_state() {
+    let (_backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState::default());
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+
+    let state = state.lock().await;
+    assert!(state.has_initial_fetch);
+    assert_eq!(state.all_logs.len(), 100);
+    assert!(!state.logs.is_empty());
+    assert!(state.metrics_last_success.is_some());
+    assert!(state.logs_last_success.is_some());
+    // Nothing is highlighted on the first fetch
+    assert!(state.all_logs.iter().all(|log| !log.is_new));
+}
+
+#[tokio::test]
+async fn overlaid_log_queries_merge_into_one_timeline() {
+    let (_backend, prometheus, loki) = clients().await;
+    let log_queries = vec![
+        LogQuery { name: "app".to_string(), query: r#"{service_name="demo"}"#.to_string() },
+        LogQuery { name: "ingress".to_string(), query: r#"{job="ingress-nginx"}"#.to_string() },
+        LogQuery { name: "missing".to_string(), query: r#"{job="nothing-here"}"#.to_string() },
+    ];
+    let state = Mutex::new(AppState { log_queries, ..AppState::default() });
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 50, &[]).await;
+
+    let state = state.lock().await;
+    assert!(state.logs_last_success.is_some());
+    // Each query contributes its own newest lines, in one chronological list
+    let from = |source: usize| state.all_logs.iter().filter(|log| log.source == Some(source)).count();
+    assert_eq!((from(0), from(1), from(2)), (50, 50, 0));
+    assert!(state.all_logs.windows(2).all(|pair| pair[0].nanos <= pair[1].nanos));
+    assert!(state.all_logs.iter().any(|log| log.source == Some(1) && log.message.contains("HTTP/1.1")));
+
+    let screen = common::screen(&state, 160, 40);
+    assert!(screen.contains(" ingress "), "{}", screen);
+    assert!(screen.contains(" app     "), "{}", screen);
+}
+
+#[tokio::test]
+async fn refresh_reports_unreachable_backends() {
+    // Nothing listens on port 9 (discard) on the test machine
+    let prometheus = PrometheusClient::new("http://127.0.0.1:9".to_string(), Client::new());
+    let loki = LokiClient::new("http://127.0.0.1:9".to_string(), Client::new());
+    let state = Mutex::new(AppState::default());
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+
+    let state = state.lock().await;
+    assert!(state.metrics_last_success.is_none());
+    assert!(state.logs_last_success.is_none());
+    assert!(!state.metrics.as_ref().unwrap().errors.is_empty());
+}
+
+#[tokio::test]
+async fn rankings_issue_their_own_queries() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let slowest = prometheus.get_metrics("5m", Ranking::Slowest).await.unwrap();
+    assert!(slowest
+        .uri_metrics
+        .windows(2)
+        .all(|pair| pair[0].avg_duration_ms >= pair[1].avg_duration_ms));
+
+    let errors = prometheus.get_metrics("5m", Ranking::Errors).await.unwrap();
+    assert!(errors.errors.is_empty(), "{:?}", errors.errors);
+    assert!(errors.uri_metrics.iter().all(|m| m.errors_per_min > 0.0 && m.errors_per_min < m.request_count));
+    assert!(errors
+        .uri_metrics
+        .windows(2)
+        .all(|pair| pair[0].errors_per_min >= pair[1].errors_per_min));
+
+    let change = prometheus.get_metrics("5m", Ranking::Change).await.unwrap();
+    assert!(change.uri_metrics.iter().all(|m| m.change_pct.is_some()));
+    let all_time = prometheus.get_metrics("all", Ranking::Change).await.unwrap();
+    assert!(all_time.uri_metrics.iter().all(|m| m.change_pct.is_none()));
+}
+
+#[tokio::test]
+async fn service_graph_lists_edges() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let edges = prometheus.get_service_graph("5m").await.unwrap();
+    assert_eq!(edges.len(), 6);
+    assert!(edges.iter().all(|e| e.requests_per_min > 0.0 && e.p95_ms.is_some()));
+    assert!(edges.iter().all(|e| e.error_ratio() > 0.0 && e.error_ratio() < 1.0));
+    // Grouped by caller
+    assert!(edges.windows(2).all(|pair| pair[0].client <= pair[1].client));
+}
+
+#[tokio::test]
+async fn queue_lag_panel_rows_follow_thresholds() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config = MockBackend::demo_panels().remove(0);
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    assert_eq!(panel.rows.len(), 4);
+    assert!(panel.rows.iter().all(|row| row.label.contains(" / ") && !row.trend.is_empty()));
+    // Worst first, and judged against the preset thresholds
+    assert!(panel.rows.windows(2).all(|pair| pair[0].value >= pair[1].value));
+    assert_ne!(panel.rows[0].severity, Severity::Ok);
+    assert_eq!(panel.rows[3].severity, Severity::Ok);
+}
+
+#[tokio::test]
+async fn ingress_preset_works_from_one_config_line() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config: PanelConfig = toml::from_str(r#"preset = "ingress""#).unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    assert_eq!(panel.title, "Ingress 5xx rate");
+    assert_eq!(panel.unit, "%");
+    assert_eq!(panel.rows.len(), 4);
+    assert!(panel.rows.iter().all(|row| row.label.contains(" / ")));
+    assert_eq!(panel.rows[0].label, "platform / auth");
+    assert_eq!(panel.rows[0].severity, Severity::Critical);
+
+    let config: PanelConfig = toml::from_str("preset = \"ingress\"\nflavor = \"envoy\"\nsignal = \"p95_latency\"").unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    assert_eq!(panel.unit, "ms");
+    assert_eq!(panel.rows[0].label, "platform_auth");
+}
+
+#[tokio::test]
+async fn container_preset_colors_by_share_of_limit() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config: PanelConfig = toml::from_str(r#"preset = "containers""#).unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    assert_eq!(panel.title, "Container memory (% of limit)");
+    assert_eq!(panel.rows[0].label, "shop / checkout-7d9f8-x2x1q");
+    let severities: Vec<_> = panel.rows.iter().map(|row| row.severity).collect();
+    assert_eq!(severities, [Severity::Critical, Severity::Warn, Severity::Ok, Severity::Ok]);
+
+    let config: PanelConfig = toml::from_str("preset = \"containers\"\nsignal = \"cpu\"").unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert_eq!(panel.rows[0].label, "shop / storefront-5c6b7-lk2p9");
+    assert_eq!(panel.rows[0].severity, Severity::Warn);
+
+    let config: PanelConfig = toml::from_str("preset = \"containers\"\nsignal = \"restarts\"").unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert_eq!(panel.rows[0].value, 4.0);
+    assert_eq!(panel.rows[0].severity, Severity::Critical);
+}
+
+#[tokio::test]
+async fn gauge_preset_shows_one_value_against_its_thresholds() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config: PanelConfig = toml::from_str(
+        "preset = \"gauge\"\nquery = \"slo:error_budget_remaining:percent\"\nunit = \"%\"\nwarn = 25\ncritical = 10",
+    )
+    .unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    assert_eq!(panel.title, "slo:error_budget_remaining:percent");
+    assert_eq!(panel.unit, "%");
+    assert_eq!(panel.stat, Some(StatStyle::Gauge { min: 0.0, max: 100.0 }));
+    assert_eq!(panel.rows.len(), 1);
+    let row = &panel.rows[0];
+    assert!((10.0..=100.0).contains(&row.value));
+    // Lower is worse here, and the budget never drops below 10%
+    assert_ne!(row.severity, Severity::Critical);
+
+    let mut state = AppState { panels: vec![panel], panels_page: Some(Default::default()), ..AppState::default() };
+    let screen = common::screen(&state, 120, 30);
+    assert!(screen.contains("(warn ≤ 25, critical ≤ 10)"), "{}", screen);
+    assert!(screen.contains("┏━┓"), "{}", screen);
+    assert!(screen.contains("% of 0–100"), "{}", screen);
+    state.accessible = true;
+    let screen = common::screen(&state, 120, 30);
+    assert!(!screen.contains("┏━┓"), "{}", screen);
+
+    let config: PanelConfig = toml::from_str("preset = \"stat\"\nquery = \"sum(rate(http_server_requests_seconds_count[5m]))\"\ntitle = \"Requests\"").unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert_eq!(panel.title, "Requests");
+    assert_eq!(panel.stat, Some(StatStyle::Number));
+    assert_eq!(panel.rows.len(), 1);
+    assert_eq!(panel.rows[0].severity, Severity::Ok);
+    assert!(panel.warnings.is_empty());
+}
+
+#[tokio::test]
+async fn table_preset_lists_series_by_label_columns() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config = MockBackend::demo_panels()
+        .into_iter()
+        .find(|config| matches!(config.preset, Preset::Table { .. }))
+        .unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    let table = panel.table.clone().unwrap();
+    assert_eq!(table.columns, ["namespace", "pod"]);
+    assert_eq!(table.rows.len(), 4);
+    // Value descending by default
+    let rows = table.sorted_rows(table.sort);
+    assert_eq!(rows[0].labels, ["shop", "checkout-7d9f8-x2x1q"]);
+    assert_eq!(rows[0].severity, Severity::Critical);
+    assert_eq!(rows[3].labels, ["platform", "grafana-0"]);
+
+    let mut state = AppState { panels: vec![panel], panels_page: Some(Default::default()), ..AppState::default() };
+    let screen = common::screen(&state, 120, 30);
+    assert!(screen.contains("namespace"), "{}", screen);
+    assert!(screen.contains("value ▼"), "{}", screen);
+    assert!(screen.find("checkout-7d9f8-x2x1q").unwrap() < screen.find("grafana-0").unwrap());
+
+    // `s` moves the sort to the namespace column, `d` flips it
+    state.panels_page.as_mut().unwrap().sort_shift = 1;
+    state.panels_page.as_mut().unwrap().sort_reversed = true;
+    let screen = common::screen(&state, 120, 30);
+    assert!(screen.contains("namespace ▲"), "{}", screen);
+    assert!(screen.find("grafana-0").unwrap() < screen.find("checkout-7d9f8-x2x1q").unwrap());
+}
+
+#[tokio::test]
+async fn counter_panel_switches_between_rate_increase_and_raw() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config = MockBackend::demo_panels()
+        .into_iter()
+        .find(|config| matches!(config.preset, Preset::Counter { .. }))
+        .unwrap();
+    let value = |panel: &PanelData, uri: &str| panel.rows.iter().find(|row| row.label == uri).unwrap().value;
+
+    let rate = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(rate.error.is_none(), "{:?}", rate.error);
+    assert_eq!(rate.counter, Some(CounterView::Rate));
+    assert_eq!(rate.unit, "/s");
+    assert_eq!(rate.rows.len(), 8);
+
+    let increase = panels::fetch_panel(&prometheus, &config, Some(CounterView::Increase)).await;
+    assert_eq!(increase.counter, Some(CounterView::Increase));
+    assert_eq!(increase.unit, "per 5m");
+
+    let raw = panels::fetch_panel(&prometheus, &config, Some(CounterView::Raw)).await;
+    assert_eq!(raw.counter, Some(CounterView::Raw));
+    assert_eq!(raw.unit, "");
+    assert!(value(&raw, "/api/orders") > value(&increase, "/api/orders"));
+    // The trend of a counter only grows
+    let trend = &raw.rows.iter().find(|row| row.label == "/api/orders").unwrap().trend;
+    assert!(trend.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    // Each mode is named in the panel header and its values carry its unit
+    for (panel, mode, unit) in [(&rate, "rate", "/s"), (&increase, "increase", "per 5m"), (&raw, "raw", "")] {
+        let state = AppState { panels: vec![panel.clone()], panels_page: Some(Default::default()), ..AppState::default() };
+        let screen = common::screen(&state, 120, 30);
+        assert!(screen.contains(&format!("▶ Requests by URI [{}]", mode)), "{}", screen);
+        let orders = value(panel, "/api/orders");
+        let shown = if orders < 10.0 { format!("{:.2}", orders) } else { format!("{:.0}", orders) };
+        let row = screen.lines().find(|line| line.contains("  /api/orders  ")).unwrap();
+        assert!(row.contains(format!("{} {}", shown, unit).trim_end()), "{}", row);
+        for other in ["/s", "per 5m"].into_iter().filter(|other| *other != unit) {
+            assert!(!row.contains(other), "{}", row);
+        }
+    }
+
+    // The marker follows the selection off the panel
+    let mut state = AppState { panels: vec![increase], panels_page: Some(Default::default()), ..AppState::default() };
+    state.panels_page.as_mut().unwrap().selected = 1;
+    let screen = common::screen(&state, 120, 30);
+    assert!(!screen.contains("▶ Requests by URI"), "{}", screen);
+}
+
+#[tokio::test]
+async fn heatmap_preset_shows_both_latency_modes() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config: PanelConfig = toml::from_str("preset = \"heatmap\"\nmetric = \"http_server_requests_seconds\"").unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    assert!(panel.rows.is_empty());
+    let heatmap = panel.heatmap.unwrap();
+    assert_eq!(heatmap.buckets.first().map(String::as_str), Some("≤ 5ms"));
+    assert!(heatmap.buckets.contains(&"≤ 1s".to_string()));
+    assert!(heatmap.times.len() > 1);
+    assert!(heatmap.rates.iter().all(|row| row.len() == heatmap.times.len()));
+
+    // Cache hits around 20ms and misses around 800ms, little in between
+    let shares = heatmap.shares();
+    let share = |label: &str| shares[heatmap.buckets.iter().position(|bucket| bucket == label).unwrap()];
+    assert!(share("≤ 25ms") > share("≤ 100ms"));
+    assert!(share("≤ 1s") > share("≤ 250ms"));
+}
+
+#[tokio::test]
+async fn gpu_preset_reads_dcgm_per_gpu() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let config: PanelConfig = toml::from_str(r#"preset = "gpu""#).unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(panel.error.is_none(), "{:?}", panel.error);
+    assert_eq!(panel.title, "GPU utilization");
+    assert_eq!(panel.rows.len(), 4);
+    assert!(panel.rows.iter().all(|row| row.severity == Severity::Ok));
+    assert!(panel.rows[0].label.starts_with("trainer-0:9400 / "));
+
+    let config: PanelConfig = toml::from_str("preset = \"gpu\"\nsignal = \"memory\"").unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert_eq!(panel.rows[0].label, "trainer-1:9400 / 0");
+    assert_eq!(panel.rows[0].severity, Severity::Critical);
+
+    let config: PanelConfig = toml::from_str("preset = \"gpu\"\nsignal = \"temperature\"").unwrap();
+    let panel = panels::fetch_panel(&prometheus, &config, None).await;
+    assert_eq!(panel.unit, "°C");
+    assert_eq!(panel.rows[0].severity, Severity::Warn);
+}
+
+#[tokio::test]
+async fn all_range_charts_recorded_history() {
+    let (_backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState {
+        metrics_time_range: TimeRange::All,
+        drill_in: Some(UriDrillIn::new("/api/orders".to_string())),
+        ..AppState::default()
+    });
+
+    for _ in 0..3 {
+        fetch::refresh(&state, Some(&prometheus), Some(&loki), 10, &[]).await;
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+
+    let state = state.lock().await;
+    assert_eq!(state.history.len(), 3);
+    let history = state.drill_in.as_ref().unwrap().history.as_ref().unwrap();
+    assert_eq!(history.requests_per_min.len(), 2);
+    assert!(history.latency_ms.iter().all(|&(_, ms)| ms > 0.0));
+    // The table shows the observed rate, not the lifetime counter
+    let orders = state.metrics.as_ref().unwrap().uri_metrics.iter().find(|m| m.uri == "/api/orders").unwrap();
+    assert!(orders.request_count > 0.0 && orders.request_count < 10_000.0, "{}", orders.request_count);
+}
+
+#[tokio::test]
+async fn uri_history_fits_the_chart_width() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let history = prometheus.get_uri_history("/api/orders", 1440, 150).await.unwrap();
+
+    // 24h at a 10m step rather than thousands of points
+    assert!(history.latency_ms.len() > 100 && history.latency_ms.len() <= 150);
+    assert!(history.requests_per_min.len() <= 150);
+}
+
+#[tokio::test]
+async fn uri_series_add_up_to_the_row() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let series = prometheus.get_uri_series("/api/orders", "5m").await.unwrap();
+    let metrics = prometheus.get_metrics("5m", Ranking::Busiest).await.unwrap();
+
+    // One series per instance, busiest first, with its full label set
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].labels["instance"], "10.0.1.12:8080");
+    assert!(series[0].labels.contains_key("job") && !series[0].labels.contains_key("__name__"));
+    assert!(series[0].requests_per_min > series[1].requests_per_min);
+    assert!(series.iter().all(|value| value.latency_ms.is_some_and(|latency| latency > 0.0) && value.at > 0.0));
+    let row = metrics.uri_metrics.iter().find(|metric| metric.uri == "/api/orders").unwrap();
+    let total: f64 = series.iter().map(|value| value.requests_per_min).sum();
+    assert!((total - row.request_count).abs() / row.request_count < 0.2, "{} vs {}", total, row.request_count);
+}
+
+#[tokio::test]
+async fn clients_keep_the_last_exchange_of_each_query() {
+    let backend = MockBackend::start().await.unwrap();
+    let log = Arc::new(ExchangeLog::default());
+    let prometheus = PrometheusClient::new(backend.base_url.clone(), Client::new()).with_exchange_log(log.clone());
+    let loki = LokiClient::new(backend.base_url.clone(), Client::new()).with_exchange_log(log.clone());
+
+    prometheus.query("sum(up)").await.unwrap();
+    loki.get_recent_logs(10).await.unwrap();
+
+    let exchange = log.get(Backend::Prometheus, "sum(up)").unwrap();
+    assert_eq!(exchange.status, Some(200));
+    assert_eq!(exchange.endpoint(), format!("{}/api/v1/query", backend.base_url));
+    assert!(exchange.params().contains(&("query".to_string(), "sum(up)".to_string())));
+    assert!(exchange.body.contains(r#""status":"success""#), "{}", exchange.body);
+    let logs = log.newest(Some(Backend::Loki));
+    assert!(!logs.is_empty());
+    let exchange = log.get(Backend::Loki, &logs[0].1).unwrap();
+    assert!(exchange.url.contains("/loki/api/v1/query_range") && exchange.body.contains("streams"), "{}", exchange.url);
+
+    // A rejected query keeps the error body it got
+    backend.set_authorized(false);
+    assert!(prometheus.query("sum(up)").await.is_err());
+    let rejected = log.get(Backend::Prometheus, "sum(up)").unwrap();
+    assert_eq!(rejected.status, Some(401));
+    assert!(rejected.body.contains("no valid credentials"), "{}", rejected.body);
+}
+
+#[tokio::test]
+async fn refresh_skips_a_missing_backend() {
+    let (_backend, prometheus, loki) = clients().await;
+
+    // Prometheus only: no log fetch, nothing to report for Loki
+    let state = Mutex::new(AppState { loki_enabled: false, ..AppState::default() });
+    fetch::refresh(&state, Some(&prometheus), None, 100, &[]).await;
+    let state = state.lock().await;
+    assert!(state.metrics_last_success.is_some());
+    assert!(state.logs_last_success.is_none());
+    assert!(state.all_logs.is_empty());
+    assert_eq!(state.status, "Connected");
+    drop(state);
+
+    // Loki only
+    let state = Mutex::new(AppState { prometheus_enabled: false, ..AppState::default() });
+    fetch::refresh(&state, None, Some(&loki), 100, &[]).await;
+    let state = state.lock().await;
+    assert!(state.metrics.is_none());
+    assert!(!state.metrics_loading);
+    assert_eq!(state.all_logs.len(), 100);
+}
+
+#[tokio::test]
+async fn empty_loki_returns_no_placeholder_logs() {
+    let (_backend, _, loki) = clients().await;
+
+    // With a zero limit every selector comes back without lines
+    let logs = loki.get_recent_logs(0).await.unwrap();
+
+    assert!(logs.is_empty(), "{:?}", logs);
+}
+
+#[tokio::test]
+async fn panel_fans_out_across_endpoints() {
+    let (eu, prometheus, _) = clients().await;
+    let us = MockBackend::start().await.unwrap();
+    let mut config = MockBackend::demo_panels().remove(0);
+    config.endpoints = [("eu", &eu.base_url), ("us", &us.base_url)]
+        .into_iter()
+        .map(|(name, url)| (name.to_string(), url.clone()))
+        .collect();
+
+    let separate = panels::fetch_panel(&prometheus, &config, None).await;
+    assert!(separate.error.is_none(), "{:?}", separate.error);
+    assert_eq!(separate.rows.len(), 8);
+    assert_eq!(separate.rows.iter().filter(|row| row.label.starts_with("[eu] ")).count(), 4);
+
+    config.aggregate = Aggregate::Sum;
+    let summed = panels::fetch_panel(&prometheus, &config, None).await;
+    assert_eq!(summed.rows.len(), 4);
+    assert!(summed.rows.iter().all(|row| !row.label.starts_with('[')));
+
+    // A dead endpoint degrades to a warning
+    config.endpoints.insert("ap".to_string(), "http://127.0.0.1:9".to_string());
+    let partial = panels::fetch_panel(&prometheus, &config, None).await;
+    assert_eq!(partial.rows.len(), 4);
+    assert_eq!(partial.warnings.len(), 1);
+    assert!(partial.warnings[0].starts_with("ap: "));
+}
+
+#[tokio::test]
+async fn static_data_is_cached_and_revalidated() {
+    let (_backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState::default());
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+    assert_eq!(state.lock().await.prometheus_version.as_deref(), Some("2.53.0"));
+    assert_eq!(state.lock().await.loki_version.as_deref(), Some("3.1.0"));
+    let stats = prometheus.static_cache().stats();
+    assert_eq!((stats.fetched, stats.hits, stats.revalidated), (1, 1, 0));
+
+    // A manual refresh sends conditional requests, answered with 304
+    state.lock().await.refresh_static = true;
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+    assert!(!state.lock().await.refresh_static);
+    assert_eq!(prometheus.static_cache().stats().revalidated, 1);
+    assert_eq!(prometheus.static_cache().stats().fetched, 1);
+    assert!(loki.static_cache().stats().revalidated >= 1);
+    assert_eq!(loki.get_label_names().await.unwrap(), vec!["job", "service_name"]);
+}
+
+#[tokio::test]
+async fn metrics_table_shows_a_page_indicator() {
+    let (_backend, prometheus, _) = clients().await;
+    let state = Mutex::new(AppState::default());
+    fetch::refresh(&state, Some(&prometheus), None, 100, &[]).await;
+    let mut state = state.lock().await;
+
+    // 40 rows tall: the shared layout has room for five of the eight URIs
+    assert_eq!(state.metrics_visible_rows(40), 5);
+    state.metrics_scroll_offset = 3;
+    let screen = common::screen(&state, 120, 40);
+    assert!(screen.contains("rows 4–8 of 8"), "{}", screen);
+
+    // Without Loki the table takes the whole screen and fits
+    state.loki_enabled = false;
+    assert!(state.metrics_visible_rows(40) >= 8);
+}
+
+#[tokio::test]
+async fn uri_templates_merge_rows() {
+    let (_backend, prometheus, _) = clients().await;
+    let ungrouped = prometheus.get_metrics("5m", Ranking::Busiest).await.unwrap().uri_metrics;
+    let templates = UriTemplates::new(&[UriTemplate {
+        pattern: "/api/orders.*".to_string(),
+        template: "/api/orders*".to_string(),
+    }])
+    .unwrap();
+    let prometheus = prometheus.with_uri_templates(templates);
+
+    let grouped = prometheus.get_metrics("5m", Ranking::Busiest).await.unwrap().uri_metrics;
+
+    assert_eq!(grouped.len(), ungrouped.len() - 1);
+    let orders = grouped.iter().find(|m| m.uri == "/api/orders*").unwrap();
+    let raw: f64 = ungrouped.iter().filter(|m| m.uri.starts_with("/api/orders")).map(|m| m.request_count).sum();
+    assert!((orders.request_count - raw).abs() / raw < 0.05);
+    let totals = prometheus.get_uri_totals().await.unwrap();
+    assert!(totals.contains_key("/api/orders*") && !totals.contains_key("/api/orders"));
+    // The drill-in covers every raw path behind the row
+    let history = prometheus.get_uri_history("/api/orders*", 30, 60).await.unwrap();
+    assert!(!history.requests_per_min.is_empty());
+}
+
+#[tokio::test]
+async fn ignore_list_hides_uris_and_logs() {
+    let (_backend, prometheus, loki) = clients().await;
+    let ignore = IgnoreList::new(&IgnoreConfig {
+        uris: vec!["/health".to_string()],
+        logs: vec![r"\[DEBUG\]".to_string()],
+    })
+    .unwrap();
+    let state = Mutex::new(AppState { ignore, ..AppState::default() });
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+    {
+        let state = state.lock().await;
+        let uris = &state.metrics.as_ref().unwrap().uri_metrics;
+        assert_eq!(uris.len(), 7);
+        assert!(uris.iter().all(|m| m.uri != "/health"));
+        assert_eq!(state.hidden_uri_count, 1);
+        assert!(state.all_logs.iter().all(|log| !log.message.contains("[DEBUG]")));
+        assert!(state.hidden_log_count > 0);
+        assert_eq!(state.all_logs.len() + state.hidden_log_count, 100);
+    }
+
+    state.lock().await.ignore.show_hidden = true;
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+    let state = state.lock().await;
+    assert_eq!(state.metrics.as_ref().unwrap().uri_metrics.len(), 8);
+    assert_eq!((state.hidden_uri_count, state.hidden_log_count), (0, 0));
+    // The refetched lines show again; older ones stay as they were
+    assert!(state.all_logs.len() >= 100);
+    assert!(state.all_logs.iter().rev().take(100).any(|log| log.message.contains("[DEBUG]")));
+}
+
+#[tokio::test]
+async fn scoped_refresh_fetches_only_its_sources() {
+    let (_backend, prometheus, loki) = clients().await;
+
+    let state = Mutex::new(AppState::default());
+    fetch::refresh_scope(&state, Some(&prometheus), Some(&loki), 100, &[], RefreshScope::Logs).await;
+    {
+        let state = state.lock().await;
+        assert!(state.metrics.is_none() && state.metrics_last_success.is_none());
+        assert_eq!(state.all_logs.len(), 100);
+    }
+
+    let state = Mutex::new(AppState { metrics_loading: true, ..AppState::default() });
+    fetch::refresh_scope(&state, Some(&prometheus), Some(&loki), 100, &[], RefreshScope::Metrics).await;
+    let state = state.lock().await;
+    assert!(state.metrics_last_success.is_some());
+    assert!(!state.metrics_loading);
+    assert!(state.all_logs.is_empty() && state.logs_last_success.is_none());
+}
+
+#[tokio::test]
+async fn fetch_flags_clear_once_each_source_arrives() {
+    let (_backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState::default());
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+
+    let state = state.lock().await;
+    assert!(state.metrics_fetch_started.is_none() && state.logs_fetch_started.is_none());
+    assert!(!state.is_fetching());
+    assert!(state.metrics_last_success.is_some() && state.logs_last_success.is_some());
+}
+
+#[tokio::test]
+async fn outage_keeps_data_and_backfills_the_gap() {
+    let (backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState::default());
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 1, &[]).await;
+
+    backend.set_available(false);
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 1, &[]).await;
+    {
+        let state = state.lock().await;
+        // The last good data stays on screen while both backends back off
+        assert!(state.metrics_backoff.is_down() && state.logs_backoff.is_down());
+        assert_eq!(state.metrics.as_ref().unwrap().uri_metrics.len(), 8);
+        assert_eq!(state.all_logs.len(), 1);
+        assert_eq!(state.due_scope(Local::now()), None);
+    }
+
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    backend.set_available(true);
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 1, &[]).await;
+    let state = state.lock().await;
+    assert!(!state.metrics_backoff.is_down() && !state.logs_backoff.is_down());
+    assert_eq!(state.due_scope(Local::now()), Some(RefreshScope::All));
+    // Counters were sampled across the gap, not only before and after it
+    assert!(state.history.len() > 2, "{}", state.history.len());
+    // Every line logged in the meantime was fetched
+    let (first, last) = (state.all_logs[0].nanos, state.all_logs[state.all_logs.len() - 1].nanos);
+    let expected = loki.get_logs_between(first - 1, last + 1, 1000).await.unwrap();
+    assert_eq!(state.all_logs.len(), expected.len());
+}
+
+#[tokio::test]
+async fn clock_skew_is_measured_and_flagged() {
+    let (backend, prometheus, loki) = clients().await;
+    backend.set_clock_offset(120);
+    let state = Mutex::new(AppState::default());
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+
+    let state = state.lock().await;
+    let prometheus_skew = state.prometheus_clock_skew.unwrap();
+    let loki_skew = state.loki_clock_skew.unwrap();
+    assert!((prometheus_skew - 120.0).abs() < 1.0, "{}", prometheus_skew);
+    assert!((loki_skew - 120.0).abs() < 1.5, "{}", loki_skew);
+    // Recent logs are asked for relative to Loki's clock
+    assert_eq!(state.all_logs.len(), 100);
+
+    let screen = common::screen(&state, 160, 40);
+    assert!(screen.contains("clock +120s"));
+}
+
+#[tokio::test]
+async fn log_ages_tick_without_refetching() {
+    let (_backend, _, loki) = clients().await;
+    let state = Mutex::new(AppState { show_log_ages: true, ..AppState::default() });
+    fetch::refresh(&state, None, Some(&loki), 100, &[]).await;
+    let state = state.lock().await;
+
+    let newest = state.all_logs.last().unwrap();
+    assert!((0..120).contains(&ui::log_age_seconds(newest, &state)));
+    // The oldest line is safely in the past, so its age isn't clamped at zero
+    let oldest = state.all_logs.first().unwrap();
+    let age = ui::log_age_seconds(oldest, &state);
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert!(ui::log_age_seconds(oldest, &state) > age);
+
+    let screen = common::screen(&state, 120, 40);
+    let aged_line = regex::Regex::new(r"\d+[smhd] \[(INFO |WARN |ERROR|DEBUG)\]").unwrap();
+    assert!(aged_line.is_match(&screen), "{}", screen);
+}
+
+#[tokio::test]
+async fn silent_streams_raise_a_banner_and_the_bell() {
+    let (_backend, _, loki) = clients().await;
+    let rule = |name: &str, selector: &str| SilenceRule {
+        name: Some(name.to_string()),
+        selector: selector.to_string(),
+        minutes: 5,
+    };
+    let state = Mutex::new(AppState {
+        silence_alarms: vec![
+            SilenceAlarm::new(rule("demo", r#"{service_name="demo"}"#)),
+            SilenceAlarm::new(rule("checkout", r#"{service_name="checkout"}"#)),
+        ],
+        ..AppState::default()
+    });
+
+    fetch::refresh(&state, None, Some(&loki), 100, &[]).await;
+
+    let mut state = state.lock().await;
+    assert!(!state.silence_alarms[0].is_firing());
+    assert!(state.silence_alarms[1].is_firing());
+    assert!(std::mem::take(&mut state.bell));
+
+    let screen = common::screen(&state, 120, 40);
+    assert!(screen.contains("SILENT checkout: no logs for 5m+"), "{}", screen);
+}
+
+#[tokio::test]
+async fn slo_burn_rates_come_from_each_window() {
+    let (_backend, prometheus, _) = clients().await;
+    let config = SloConfig {
+        name: "Checkout availability".to_string(),
+        errors: "checkout_requests_failed_total".to_string(),
+        total: "checkout_requests_total".to_string(),
+        ..SloConfig::default()
+    };
+    let state = Mutex::new(AppState { slos: vec![Slo::new(config).unwrap()], ..AppState::default() });
+
+    fetch::refresh(&state, Some(&prometheus), None, 100, &[]).await;
+
+    let state = state.lock().await;
+    let slo = &state.slos[0];
+    assert!(slo.error.is_none(), "{:?}", slo.error);
+    let rates: Vec<f64> = slo.burn_rates.iter().map(|rate| rate.unwrap()).collect();
+    // Never below the steady trickle; a burst shows most in the short windows
+    assert!(rates.iter().all(|&rate| rate > 0.49), "{:?}", rates);
+    assert!(rates.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", rates);
+}
+
+#[tokio::test]
+async fn bandwidth_column_and_egress_sparkline() {
+    let (_backend, prometheus, loki) = clients().await;
+    let prometheus = prometheus.with_bandwidth(BandwidthConfig {
+        metric: Some("http_server_response_size_bytes_sum".to_string()),
+        ..BandwidthConfig::default()
+    });
+    let state = Mutex::new(AppState::default());
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+
+    let state = state.lock().await;
+    let metrics = state.metrics.as_ref().unwrap();
+    assert!(metrics.errors.is_empty(), "{:?}", metrics.errors);
+    let export = metrics.uri_metrics.iter().find(|m| m.uri == "/api/reports/export").unwrap();
+    assert!(export.bytes_per_sec.unwrap() > 500_000.0);
+    assert_eq!(metrics.egress_bytes_per_sec.len(), 31);
+    assert!(metrics.egress_bytes_per_sec.iter().all(|&bytes| bytes > 0.0));
+
+    let screen = common::screen(&state, 160, 40);
+    assert!(screen.contains("BW"));
+    assert!(screen.contains("Egress "), "{}", screen);
+}
+
+#[tokio::test]
+async fn bandwidth_is_off_without_a_metric() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let metrics = prometheus.get_metrics("5m", Ranking::Busiest).await.unwrap();
+
+    assert!(metrics.uri_metrics.iter().all(|m| m.bytes_per_sec.is_none()));
+    assert!(metrics.egress_bytes_per_sec.is_empty());
+}
+
+#[tokio::test]
+async fn prometheus_admin_actions_post_to_the_tsdb_api() {
+    let (_backend, prometheus, _) = clients().await;
+
+    let message = admin::run(&AdminAction::PrometheusSnapshot, Some(&prometheus), None).await.unwrap();
+    assert!(message.starts_with("Snapshot created: snapshots/"), "{}", message);
+    assert!(message.ends_with("-mock"));
+    let message = admin::run(&AdminAction::PrometheusCleanTombstones, Some(&prometheus), None).await.unwrap();
+    assert_eq!(message, "Tombstones cleaned");
+
+    // Nothing is offered unless admin is switched on
+    assert!(AdminAction::available(false).is_empty());
+    assert!(admin::run(&AdminAction::PrometheusSnapshot, None, None).await.is_err());
+}
+
+#[test]
+fn admin_confirmation_names_the_action_and_server() {
+    let mut menu = AdminMenu::new(AdminAction::available(true));
+    menu.selected = 1;
+    menu.confirming = true;
+    let state = AppState {
+        prometheus_url: "http://prometheus.internal:9090".to_string(),
+        admin_menu: Some(menu),
+        ..AppState::default()
+    };
+
+    let screen = common::screen(&state, 120, 40);
+    assert!(screen.contains("Prometheus: clean tombstones?"));
+    assert!(screen.contains("Server: http://prometheus.internal:9090"));
+    assert!(screen.contains("y: run it"));
+}
+
+#[tokio::test]
+async fn loki_delete_request_covers_the_selected_stream() {
+    let (_backend, _, loki) = clients().await;
+    let logs = loki.get_recent_logs(50).await.unwrap();
+
+    let action = AdminAction::delete_stream(&logs, 10).unwrap();
+    let AdminAction::LokiDelete { selector, start, end } = &action else { panic!("{:?}", action) };
+    assert_eq!(selector, r#"{job="rustdash-mock", service_name="demo"}"#);
+    assert!(*start <= logs[0].nanos / 1_000_000_000);
+    assert!(*end > logs[49].nanos / 1_000_000_000);
+    assert!(action.is_destructive());
+    assert!(action.warning().contains(selector.as_str()));
+
+    let message = admin::run(&action, None, Some(&loki)).await.unwrap();
+    assert_eq!(message, format!("Delete request for {} submitted", selector));
+    assert!(admin::run(&action, None, None).await.is_err());
+}
+
+#[tokio::test]
+async fn flushed_logs_stay_out_and_later_lines_count_as_new() {
+    let (_backend, _, loki) = clients().await;
+    let state = Mutex::new(AppState::default());
+    fetch::refresh(&state, None, Some(&loki), 100, &[]).await;
+
+    let cutoff = {
+        let mut state = state.lock().await;
+        let newest = state.all_logs.last().unwrap().nanos;
+        state.selected_log_index = Some(3);
+        state.flush_logs();
+        assert!(state.all_logs.is_empty() && state.logs.is_empty());
+        assert_eq!(state.selected_log_index, None);
+        newest
+    };
+
+    // Enough time for the mock to log a few more lines
+    tokio::time::sleep(std::time::Duration::from_millis(3000)).await;
+    fetch::refresh(&state, None, Some(&loki), 100, &[]).await;
+
+    let state = state.lock().await;
+    assert!(state.all_logs.len() < 100);
+    assert!(state.all_logs.iter().all(|log| log.nanos > cutoff && log.is_new));
+}
+
+#[tokio::test]
+async fn log_filter_narrows_the_next_fetch_to_similar_lines() {
+    let (_backend, _, loki) = clients().await;
+    let app_state = Mutex::new(AppState::default());
+    fetch::refresh_scope(&app_state, None, Some(&loki), 100, &[], RefreshScope::Logs).await;
+
+    let pattern = {
+        let mut state = app_state.lock().await;
+        let index = state.all_logs.iter().position(|log| log.message.contains("Published event")).unwrap();
+        let log = state.all_logs[index].clone();
+        let actions = LogAction::available(&log, &state.log_filter, false, &LogActionsConfig::default());
+        let Some(LogAction::SearchSimilar(pattern)) = actions.into_iter().find(|a| matches!(a, LogAction::SearchSimilar(_))) else {
+            panic!("no search action");
+        };
+        state.set_log_filter(LogFilter { pattern: Some(pattern.clone()), ..LogFilter::default() });
+        assert!(state.all_logs.is_empty());
+        pattern
+    };
+    fetch::refresh_scope(&app_state, None, Some(&loki), 100, &[], RefreshScope::Logs).await;
+
+    let state = app_state.lock().await;
+    let regex = regex::Regex::new(&pattern).unwrap();
+    assert!(state.all_logs.len() > 1);
+    assert!(state.all_logs.iter().all(|log| regex.is_match(&log.message)));
+}
+
+#[tokio::test]
+async fn query_bar_queries_replace_the_filter_only_once_loki_accepts_them() {
+    let (_backend, _, loki) = clients().await;
+    let app_state = Mutex::new(AppState::default());
+    fetch::refresh_scope(&app_state, None, Some(&loki), 100, &[], RefreshScope::Logs).await;
+    let shown = app_state.lock().await.all_logs.len();
+    assert!(shown > 0);
+
+    // Loki can't parse the template: the error goes under the bar and the panel keeps its lines
+    let query = r#"{service_name="demo"} | line_format "{{.msg""#;
+    let mut bar = LogQueryBar::new(query);
+    assert!(bar.submit().is_some(), "passes the local check");
+    app_state.lock().await.log_query_bar = Some(bar);
+    fetch::refresh_scope(&app_state, None, Some(&loki), 100, &[], RefreshScope::Logs).await;
+    {
+        let state = app_state.lock().await;
+        let bar = state.log_query_bar.as_ref().expect("bar stays open");
+        let error = bar.error.clone().unwrap();
+        assert!(error.message.starts_with("Loki: parse error at line 1, col 25"), "{}", error.message);
+        assert_eq!(&query[error.at.unwrap()], "line_format");
+        assert_eq!(state.log_filter, LogFilter::default());
+        assert!(state.all_logs.len() >= shown);
+    }
+
+    let query = r#"{service_name="demo"} |~ "Published""#;
+    app_state.lock().await.log_query_bar = Some(LogQueryBar::new(query));
+    app_state.lock().await.log_query_bar.as_mut().unwrap().submit();
+    fetch::refresh_scope(&app_state, None, Some(&loki), 100, &[], RefreshScope::Logs).await;
+    let state = app_state.lock().await;
+    assert!(state.log_query_bar.is_none(), "closed once accepted");
+    assert_eq!(state.log_filter, LogFilter::query(query));
+    assert_eq!(state.last_log_query.as_deref(), Some(query));
+    assert!(!state.all_logs.is_empty());
+    assert!(state.all_logs.iter().all(|log| log.message.contains("Published")));
+    assert_eq!(state.audit_log.recent().next().unwrap().params["query"], query);
+}
+
+#[tokio::test]
+async fn localized_uris_are_cut_by_display_width() {
+    let (_backend, prometheus, _) = clients().await;
+    let state = Mutex::new(AppState {
+        uri_truncation: Truncation::Middle,
+        ..AppState::default()
+    });
+    fetch::refresh(&state, Some(&prometheus), None, 100, &[]).await;
+    let mut state = state.lock().await;
+    let metrics = state.metrics.as_mut().unwrap();
+    metrics.uri_metrics[0].uri = "/api/주문/목록/상세정보/배송조회/반품신청/환불내역/details".to_string();
+    metrics.uri_metrics[1].uri = "/api/🚀/launch".to_string();
+
+    let screen = common::screen(&state, 120, 40);
+    assert!(screen.contains("/…/details"), "{}", screen);
+}
+
+#[tokio::test]
+async fn uri_rows_show_their_last_error() {
+    let (_backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState {
+        uri_error_pattern: Some(uri_errors::DEFAULT_PATTERN.to_string()),
+        ..AppState::default()
+    });
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+
+    let state = state.lock().await;
+    assert!(!state.error_logs.is_empty());
+    assert!(state.error_logs.iter().all(|log| log.level == "ERROR"));
+    assert!(!state.uri_errors.is_empty());
+    for (uri, log) in &state.uri_errors {
+        assert!(log.message.contains("Unhandled exception in "), "{}: {}", uri, log.message);
+        let regex = regex::Regex::new(&uri_errors::uri_pattern(uri, uri_errors::DEFAULT_PATTERN)).unwrap();
+        assert!(regex.is_match(&log.message));
+    }
+
+    let screen = common::screen(&state, 160, 40);
+    assert!(screen.contains("Last error"), "{}", screen);
+    assert!(screen.contains("Unhandled exception"), "{}", screen);
+}
+
+#[tokio::test]
+async fn launch_backfill_fills_history_and_logs_for_the_window() {
+    let (_backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState::default());
+
+    fetch::backfill(&state, Some(&prometheus), Some(&loki), 2 * 3600).await;
+
+    {
+        let state = state.lock().await;
+        assert!(state.history.len() > 100, "{}", state.history.len());
+        let span = state.history.latest().unwrap() - state.history.since().unwrap();
+        assert!(span > 6000.0, "{}", span);
+        assert!(state.all_logs.len() > 100, "{}", state.all_logs.len());
+        let oldest = state.all_logs[0].nanos / 1_000_000_000;
+        assert!(Local::now().timestamp() - oldest > 3600);
+        assert!(state.status.starts_with("Backfilled the last 2h"), "{}", state.status);
+    }
+
+    // Live refreshes carry on from there
+    let lines = state.lock().await.all_logs.len();
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 100, &[]).await;
+    let state = state.lock().await;
+    assert!(state.all_logs.len() >= lines);
+    assert!(state.history.latest().unwrap() >= Local::now().timestamp() as f64 - 5.0);
+}
+
+#[tokio::test]
+async fn about_popup_shows_server_details() {
+    let (_backend, prometheus, loki) = clients().await;
+
+    let info = prometheus.get_server_info().await.unwrap();
+    assert_eq!(info.version.as_deref(), Some("2.53.0"));
+    assert_eq!(info.storage_retention.as_deref(), Some("15d"));
+    assert!(info.head_series.is_some_and(|series| series > 0));
+    assert!(info.wal_bytes.is_some_and(|bytes| bytes > 1024.0 * 1024.0));
+    assert!(info.start_time.is_some_and(|start| start < chrono::Utc::now()));
+
+    // Only fetched while the popup is open
+    let state = Mutex::new(AppState::default());
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 10, &[]).await;
+    assert!(state.lock().await.about.is_none());
+    state.lock().await.about = Some(ui::AboutBackends::default());
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 10, &[]).await;
+    let state = state.lock().await;
+    let fetched = state.about.as_ref().unwrap().prometheus.as_ref().unwrap();
+    assert_eq!((&fetched.version, fetched.head_series), (&info.version, info.head_series));
+
+    let screen = common::screen(&state, 120, 40);
+    assert!(screen.contains("About backends"), "{}", screen);
+    assert!(screen.contains("2.53.0 (1f5c37a9d8b0)"), "{}", screen);
+    assert!(screen.contains("Retention       15d"), "{}", screen);
+    assert!(screen.contains("3.1.0"), "{}", screen);
+}
+
+#[tokio::test]
+async fn query_timings_are_recorded_with_server_time() {
+    let (_backend, prometheus, loki) = clients().await;
+    let stats = Arc::new(QueryStats::default());
+    let prometheus = prometheus.with_query_stats(stats.clone());
+    let loki = loki.with_query_stats(stats.clone());
+    let state = Mutex::new(AppState { query_stats: stats.clone(), ..AppState::default() });
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 50, &[]).await;
+
+    let timings = stats.by_cost();
+    assert!(timings.iter().any(|t| t.backend == Backend::Prometheus));
+    assert!(timings.iter().any(|t| t.backend == Backend::Loki));
+    // Both mock servers report how long they took
+    assert!(timings.iter().all(|t| t.runs >= 1 && t.last_server_ms.is_some()), "{:?}", timings);
+    assert!(timings.windows(2).all(|pair| pair[0].cost_ms() >= pair[1].cost_ms()));
+
+    let mut state = state.lock().await;
+    state.query_stats_popup = Some(ui::QueryStatsPopup::default());
+    let screen = common::screen(&state, 160, 40);
+    assert!(screen.contains(&format!("Query timings ({} queries)", timings.len())), "{}", screen);
+    assert!(screen.contains("PromQL") && screen.contains("LogQL"), "{}", screen);
+}
+
+#[tokio::test]
+async fn client_errors_carry_their_class() {
+    let (backend, prometheus, loki) = clients().await;
+    let stream = "{job=\"rustdash-mock\"}";
+
+    backend.set_available(false);
+    assert_eq!(prometheus.query("up").await.unwrap_err().class(), ErrorClass::Unreachable);
+    assert_eq!(loki.get_filtered_logs(Some(stream), None, 20).await.unwrap_err().class(), ErrorClass::Unreachable);
+    backend.set_available(true);
+
+    backend.set_authorized(false);
+    let e = prometheus.query("up").await.unwrap_err();
+    assert!(matches!(e, PrometheusError::Auth { status, .. } if status == StatusCode::UNAUTHORIZED), "{:?}", e);
+    assert!(matches!(loki.get_build_info().await, Err(LokiError::Auth { .. })));
+    backend.set_authorized(true);
+
+    backend.set_max_query_length(600);
+    let e = loki.get_filtered_logs(Some(stream), None, 20).await.unwrap_err();
+    assert_eq!(e.class(), ErrorClass::Limit);
+    assert!(e.to_string().contains("exceeds the limit"), "{}", e);
+
+    // No stream has been picked yet
+    assert!(matches!(loki.get_logs_since(60, 10).await, Err(LokiError::NoStream)));
+}
+
+#[tokio::test]
+async fn revoked_credentials_are_told_apart_from_an_outage() {
+    let (backend, prometheus, loki) = clients().await;
+    let state = Mutex::new(AppState::default());
+    backend.set_authorized(false);
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 10, &[]).await;
+
+    let state = state.lock().await;
+    assert_eq!(state.metrics_backoff.cause, Some(ErrorClass::Auth));
+    assert_eq!(state.logs_backoff.cause, Some(ErrorClass::Auth));
+    assert!(state.status.contains("rejected the credentials"), "{}", state.status);
+    let screen = common::screen(&state, 160, 40);
+    assert!(screen.contains("logs unauthorized since"), "{}", screen);
+}
+
+#[tokio::test]
+async fn retries_ride_out_a_brief_outage() {
+    let (backend, prometheus, _) = clients().await;
+    let breaker = Arc::new(CircuitBreaker::new(BreakerConfig { retries: 2, retry_delay_ms: 300, ..BreakerConfig::default() }));
+    let prometheus = prometheus.with_breaker(breaker.clone());
+    let backend = Arc::new(backend);
+
+    backend.set_available(false);
+    let restore = backend.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        restore.set_available(true);
+    });
+    assert!(prometheus.query("up").await.is_ok());
+    assert!(breaker.open_circuits().is_empty());
+}
+
+#[tokio::test]
+async fn failing_endpoints_cool_down_on_their_own() {
+    let (backend, prometheus, loki) = clients().await;
+    let config = BreakerConfig { retries: 0, failures: 3, cooldown_seconds: 1, ..BreakerConfig::default() };
+    let breaker = Arc::new(CircuitBreaker::new(config));
+    let (prometheus, loki) = (prometheus.with_breaker(breaker.clone()), loki.with_breaker(breaker.clone()));
+
+    backend.set_available(false);
+    for _ in 0..3 {
+        assert!(matches!(prometheus.query("up").await, Err(PrometheusError::Api { .. })));
+    }
+    backend.set_available(true);
+    // Turned away without asking the server, which is back already
+    let e = prometheus.query("up").await.unwrap_err();
+    assert!(matches!(e, PrometheusError::CircuitOpen(_)), "{:?}", e);
+    assert_eq!(e.class(), ErrorClass::Unreachable);
+    // Other endpoints and the other backend are unaffected
+    assert!(prometheus.query_range("up", "0", "60", "15").await.is_ok());
+    assert!(loki.get_build_info().await.is_ok());
+
+    let open = breaker.open_circuits();
+    assert_eq!(open.len(), 1);
+    assert_eq!((open[0].backend, open[0].path().as_str(), open[0].failures), (Backend::Prometheus, "/api/v1/query", 3));
+    let state = AppState { circuit_breaker: breaker.clone(), ..AppState::default() };
+    let screen = common::screen(&state, 200, 40);
+    assert!(screen.contains("Prometheus /api/v1/query cooling down 1s"), "{}", screen);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert!(prometheus.query("up").await.is_ok());
+    assert!(breaker.open_circuits().is_empty());
+}
+
+#[tokio::test]
+async fn only_idempotent_requests_are_retried() {
+    let (backend, prometheus, _) = clients().await;
+    let breaker = Arc::new(CircuitBreaker::new(BreakerConfig { retries: 3, retry_delay_ms: 2_000, ..BreakerConfig::default() }));
+    let prometheus = prometheus.with_breaker(breaker);
+    backend.set_available(false);
+
+    let started = std::time::Instant::now();
+    assert!(prometheus.clean_tombstones().await.is_err());
+    assert!(started.elapsed() < std::time::Duration::from_secs(1), "{:?}", started.elapsed());
+}
+
+#[tokio::test]
+async fn loki_limit_errors_narrow_the_log_window() {
+    let (backend, _, loki) = clients().await;
+    let guard = Arc::new(SlowQueryGuard::new(GuardConfig::default()));
+    let loki = loki.with_guard(guard.clone());
+    let stream = "{job=\"rustdash-mock\"}";
+    backend.set_max_query_length(600);
+
+    // 30m and then 15m exceed the limit; each rejection halves the window
+    assert!(loki.get_filtered_logs(Some(stream), None, 20).await.is_err());
+    assert!(loki.get_filtered_logs(Some(stream), None, 20).await.is_err());
+    let logs = loki.get_filtered_logs(Some(stream), None, 20).await.unwrap();
+    assert!(!logs.is_empty());
+
+    let reductions = guard.reductions();
+    assert_eq!(reductions.len(), 1);
+    assert_eq!(reductions[0].factor(), 4);
+    assert!(reductions[0].reason.contains("exceeds the limit"), "{}", reductions[0].reason);
+
+    let state = AppState { query_guard: guard, ..AppState::default() };
+    let screen = common::screen(&state, 160, 40);
+    assert!(screen.contains("1 slow query reduced (S)"), "{}", screen);
+}
+
+#[tokio::test]
+async fn query_editor_looks_up_completions_lazily_and_runs_queries() {
+    let (_backend, prometheus, _) = clients().await;
+    let state = Mutex::new(AppState { query_editor: Some(QueryEditor::default()), ..AppState::default() });
+    let mut editor = QueryEditor::default();
+    for c in "kafk".chars() {
+        editor.insert(c);
+    }
+    let lookup = editor.update_completions().unwrap();
+    state.lock().await.query_editor = Some(editor);
+
+    assert_eq!(query_editor::serve(EditorRequest::Lookup(lookup), &prometheus, &state).await, None);
+    {
+        let mut state = state.lock().await;
+        let editor = state.query_editor.as_mut().unwrap();
+        assert_eq!(editor.completions[0].0, "kafka_consumergroup_lag");
+        editor.accept();
+        "{topic=\"".chars().for_each(|c| editor.insert(c));
+        assert_eq!(editor.update_completions(), Some(Lookup::Series("kafka_consumergroup_lag".to_string())));
+    }
+    let lookup = Lookup::Series("kafka_consumergroup_lag".to_string());
+    query_editor::serve(EditorRequest::Lookup(lookup), &prometheus, &state).await;
+    {
+        let state = state.lock().await;
+        let values: Vec<&str> = state.query_editor.as_ref().unwrap().completions.iter().map(|(v, _)| v.as_str()).collect();
+        assert_eq!(values, ["events", "orders", "catalog", "payments"]);
+    }
+
+    // Label values across all series, for a selector without a metric
+    let lookup = Lookup::LabelValues("namespace".to_string());
+    query_editor::serve(EditorRequest::Lookup(lookup), &prometheus, &state).await;
+    let values = state.lock().await.query_editor.as_ref().unwrap().catalog.label_values["namespace"].clone();
+    assert_eq!(values, ["platform", "shop"]);
+
+    query_editor::serve(EditorRequest::Run("kafka_consumergroup_lag".to_string()), &prometheus, &state).await;
+    let rows = state.lock().await.query_editor.as_ref().unwrap().result.clone().unwrap().unwrap();
+    assert_eq!(rows.len(), 4);
+    assert!(rows.iter().any(|row| row.series == r#"{consumergroup="billing", topic="payments"}"#), "{:?}", rows);
+
+    // Scalars come back as a single row
+    query_editor::serve(EditorRequest::Run("time()".to_string()), &prometheus, &state).await;
+    let rows = state.lock().await.query_editor.as_ref().unwrap().result.clone().unwrap().unwrap();
+    assert_eq!((rows.len(), rows[0].series.as_str()), (1, "{}"));
+}
+
+#[tokio::test]
+async fn followed_log_files_merge_with_lokis_lines() {
+    let (_backend, _prometheus, loki) = clients().await;
+    let path = std::env::temp_dir().join(format!("rustdash-mock-files-{}.log", std::process::id()));
+    std::fs::write(&path, "written to disk only\n").unwrap();
+    let log_files = LogFiles::new(&LogFilesConfig { paths: vec![path.display().to_string()] });
+    let state = Mutex::new(AppState { log_files, ..AppState::default() });
+
+    fetch::refresh_scope(&state, None, Some(&loki), 50, &[], RefreshScope::Logs).await;
+
+    let state = state.lock().await;
+    let file_line = state.all_logs.iter().find(|log| log.message == "written to disk only").expect("file line merged");
+    assert_eq!(file_line.stream, format!(r#"{{filename="{}"}}"#, path.display()));
+    assert!(state.all_logs.len() > 1, "Loki's lines are kept");
+    assert!(state.all_logs.windows(2).all(|pair| pair[0].nanos <= pair[1].nanos));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn status_pages_are_read_and_unreadable_ones_reported() {
+    let backend = MockBackend::start().await.unwrap();
+    let client = Client::new();
+    let pages = vec![
+        StatusPageConfig { name: "Payments".to_string(), url: backend.base_url.clone() },
+        StatusPageConfig { name: "Gone".to_string(), url: format!("{}/missing.json", backend.base_url) },
+    ];
+    let statuses = status_pages::check_all(&client, &pages).await;
+    assert_eq!(statuses[0].indicator, Indicator::Minor);
+    assert_eq!(statuses[0].incidents, ["Elevated card authorization latency"]);
+    assert_eq!(statuses[0].degraded, ["API (degraded performance)"]);
+    assert_eq!(statuses[1].indicator, Indicator::Unknown);
+    assert!(statuses[1].description.starts_with("Couldn't read the status page: HTTP 404"), "{}", statuses[1].description);
+}
+
+#[tokio::test]
+async fn synthetic_checks_assert_on_live_responses() {
+    let backend = MockBackend::start().await.unwrap();
+    let client = Client::new();
+    let config = |path: &str| CheckConfig { name: path.to_string(), url: format!("{}{}", backend.base_url, path), ..CheckConfig::default() };
+
+    let passing = SyntheticCheck::new(CheckConfig { body_regex: Some(r#""version""#.to_string()), ..config("/api/v1/status/buildinfo") }).unwrap();
+    let run = synthetic::run(&client, &passing).await;
+    assert!(run.passed(), "{:?}", run.failures);
+    assert_eq!(run.status, Some(200));
+
+    let missing = SyntheticCheck::new(CheckConfig { body_regex: Some("version".to_string()), ..config("/nope") }).unwrap();
+    let run = synthetic::run(&client, &missing).await;
+    assert_eq!(run.failures, ["status 404 (expected 2xx)", "body doesn't match `version`"]);
+
+    let unreachable = SyntheticCheck::new(CheckConfig { url: "http://127.0.0.1:1/".to_string(), ..config("/") }).unwrap();
+    let run = synthetic::run(&client, &unreachable).await;
+    assert_eq!(run.status, None);
+    assert!(run.failures[0].starts_with("request failed"), "{:?}", run.failures);
+}
+
+#[tokio::test]
+async fn variables_load_their_values_and_narrow_the_panels() {
+    let (_backend, prometheus, loki) = clients().await;
+    let variable: VariableConfig =
+        toml::from_str("name = \"ns\"\nlabel = \"namespace\"\nmetric = \"container_cpu_usage_seconds_total\"").unwrap();
+    let state = Mutex::new(AppState { variables: vec![Variable::new(variable)], ..AppState::default() });
+    let panel: PanelConfig = toml::from_str(
+        "title = \"Memory in $ns\"\npreset = \"stat\"\nquery = 'sum(container_memory_working_set_bytes{namespace=\"${ns}\"})'",
+    )
+    .unwrap();
+
+    fetch::refresh(&state, Some(&prometheus), Some(&loki), 10, &[panel]).await;
+
+    let state = state.lock().await;
+    assert_eq!(state.variables[0].values, ["platform", "shop"]);
+    assert_eq!(state.variables[0].selected.as_deref(), Some("platform"), "the first value without All");
+    assert_eq!(state.panels[0].title, "Memory in platform");
+    assert_eq!(state.panels[0].query, r#"sum(container_memory_working_set_bytes{namespace="platform"})"#);
+}
+
+#[tokio::test]
+async fn downstream_variables_follow_their_upstream_selection() {
+    let (_backend, prometheus, _) = clients().await;
+    let config = |name: &str, metric: &str| VariableConfig {
+        name: name.to_string(),
+        label: name.to_string(),
+        metric: Some(metric.to_string()),
+        include_all: false,
+        default: None,
+    };
+    let mut chain = vec![
+        Variable::new(config("namespace", "container_cpu_usage_seconds_total")),
+        Variable::new(config("pod", r#"container_cpu_usage_seconds_total{namespace="$namespace"}"#)),
+    ];
+
+    variables::load_values(&prometheus, &mut chain).await;
+    assert_eq!(chain[0].display(), "platform");
+    assert_eq!(chain[1].values, ["auth-6f4d2-qq8rt", "grafana-0"]);
+    assert_eq!(chain[1].display(), "auth-6f4d2-qq8rt");
+
+    chain[0].selected = Some("shop".to_string());
+    variables::load_values(&prometheus, &mut chain).await;
+    assert_eq!(chain[1].values, ["checkout-7d9f8-x2x1q", "storefront-5c6b7-lk2p9"]);
+    assert_eq!(chain[1].display(), "checkout-7d9f8-x2x1q", "the pod of the other namespace is gone");
+}
+
+#[tokio::test]
+async fn repeated_panels_get_a_copy_per_value_side_by_side() {
+    let (_backend, prometheus, _) = clients().await;
+    let variable = VariableConfig {
+        name: "ns".to_string(),
+        label: "namespace".to_string(),
+        metric: Some("container_cpu_usage_seconds_total".to_string()),
+        include_all: true,
+        default: None,
+    };
+    let state = Mutex::new(AppState { variables: vec![Variable::new(variable)], ..AppState::default() });
+    let panel: PanelConfig = toml::from_str(
+        "title = \"Memory\"\nrepeat = \"ns\"\npreset = \"stat\"\nquery = 'sum(container_memory_working_set_bytes{namespace=\"$ns\"})'",
+    )
+    .unwrap();
+
+    fetch::refresh(&state, Some(&prometheus), None, 10, &[panel]).await;
+
+    let mut state = state.lock().await;
+    let titles: Vec<&str> = state.panels.iter().map(|panel| panel.title.as_str()).collect();
+    assert_eq!(titles, ["Memory · platform", "Memory · shop"], "one copy per value while All is selected");
+    assert_eq!(state.panels[1].repeat, Some((0, "shop".to_string())));
+    assert_eq!(state.panels[1].query, r#"sum(container_memory_working_set_bytes{namespace="shop"})"#);
+
+    state.panels_page = Some(Default::default());
+    let screen = common::screen(&state, 120, 30);
+    let lines: Vec<&str> = screen.lines().collect();
+    assert!(lines.iter().any(|line| line.contains("Memory · platform") && line.contains("Memory · shop")), "{}", lines.join("\n"));
+    assert_eq!(ui::panels_page_title_line(&state, state.panels_page.as_ref().unwrap(), 1), Some(0), "both copies start on the first line");
+}
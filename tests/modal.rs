@@ -0,0 +1,50 @@
+use ratatui::layout::Rect;
+use rustdash::modal::{self, ModalKind, ModalStack};
+use rustdash::palette::Palette;
+use rustdash::ui::AppState;
+
+#[test]
+fn the_last_opened_overlay_is_on_top() {
+    let mut stack = ModalStack::default();
+    stack.sync(&[ModalKind::Palette]);
+    stack.sync(&[ModalKind::Checks, ModalKind::Palette]);
+    assert_eq!(stack.order(), [ModalKind::Palette, ModalKind::Checks]);
+    assert_eq!(stack.focused(), Some(ModalKind::Checks));
+
+    // Closing the top one hands the keys back to the one under it
+    stack.sync(&[ModalKind::Palette]);
+    assert_eq!(stack.focused(), Some(ModalKind::Palette));
+    stack.sync(&[]);
+    assert_eq!(stack.focused(), None);
+}
+
+#[test]
+fn the_inspector_stays_underneath_without_keys() {
+    let mut stack = ModalStack::default();
+    stack.sync(&[ModalKind::About]);
+    assert_eq!(stack.arrange(&[ModalKind::Inspection, ModalKind::About]), [ModalKind::Inspection, ModalKind::About]);
+    stack.sync(&[ModalKind::Inspection]);
+    assert_eq!(stack.focused(), None);
+}
+
+#[test]
+fn keys_follow_the_stack() {
+    let mut state = AppState { checks_popup: true, ..AppState::default() };
+    assert_eq!(state.focused_modal(), Some(ModalKind::Checks));
+    state.palette = Some(Palette::default());
+    assert_eq!(state.focused_modal(), Some(ModalKind::Palette));
+
+    state.dismiss(ModalKind::Palette);
+    assert_eq!(state.focused_modal(), Some(ModalKind::Checks));
+    state.palette = Some(Palette::default());
+    state.close_views();
+    assert_eq!(state.open_modals(), [ModalKind::Palette], "what is being typed stays");
+}
+
+#[test]
+fn boxes_are_centered_and_fit() {
+    let area = Rect::new(10, 5, 100, 30);
+    assert_eq!(modal::centered(area, 40, 10), Rect::new(40, 15, 40, 10));
+    assert_eq!(modal::centered(area, 200, 50), area);
+    assert_eq!(modal::near_top(area, 40, 40, 2), Rect::new(40, 7, 40, 28));
+}
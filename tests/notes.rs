@@ -0,0 +1,61 @@
+use chrono::{Local, TimeZone};
+use rustdash::log_actions::LogFilter;
+use rustdash::notes::{self, Note, NotesPad};
+use rustdash::text::LineInput;
+use rustdash::ui::{AppState, TimeRange, UriDrillIn};
+
+mod common;
+
+fn note(text: &str, context: &[&str]) -> Note {
+    Note {
+        at: Local.with_ymd_and_hms(2024, 5, 1, 12, 3, 4).unwrap(),
+        text: text.to_string(),
+        context: context.iter().map(|item| item.to_string()).collect(),
+    }
+}
+
+#[test]
+fn notes_are_annotated_with_what_the_dashboard_shows() {
+    let mut state = AppState { metrics_time_range: TimeRange::OneHour, ..AppState::default() };
+    assert_eq!(notes::context(&state), ["range 1h"]);
+
+    state.log_filter = LogFilter::query(r#"{app="api"} |= "timeout""#);
+    state.drill_in = Some(UriDrillIn::new("/api/orders".to_string()));
+    state.time_cursor = Some(Local.with_ymd_and_hms(2024, 5, 1, 11, 58, 0).unwrap().timestamp() as f64);
+    assert_eq!(
+        notes::context(&state),
+        ["range 1h", "cursor 2024-05-01 11:58:00", r#"logs {app="api"} |= "timeout""#, "uri /api/orders"]
+    );
+}
+
+#[test]
+fn notes_export_as_a_markdown_timeline() {
+    let markdown = notes::to_markdown(&[note("p95 doubles on /api/orders", &["range 1h", "uri /api/orders"]), note("rolled back", &[])]);
+    assert_eq!(
+        markdown,
+        "## Incident notes\n\n\
+         - **2024-05-01 12:03:04** p95 doubles on /api/orders\n  - `range 1h`, `uri /api/orders`\n\
+         - **2024-05-01 12:03:04** rolled back\n"
+    );
+    assert!(notes::to_markdown(&[]).contains("_No notes taken._"));
+
+    let dir = std::env::temp_dir().join(format!("rustdash-notes-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = notes::export(&[note("rolled back", &[])], &dir).unwrap();
+    assert!(path.file_name().unwrap().to_string_lossy().starts_with("incident-notes-"));
+    assert!(std::fs::read_to_string(&path).unwrap().contains("rolled back"));
+}
+
+#[test]
+fn pad_lists_notes_above_the_input() {
+    let state = AppState {
+        notes: vec![note("p95 doubles", &["range 1h"]), note("rolled back", &[])],
+        notes_pad: Some(NotesPad { input: LineInput::new("db is fine"), scroll_offset: 0 }),
+        ..AppState::default()
+    };
+    let screen = common::screen(&state, 120, 36);
+    assert!(screen.contains("Incident notes (2)"), "{}", screen);
+    assert!(screen.contains("12:03:04 p95 doubles"));
+    assert!(screen.contains("range 1h"));
+    assert!(screen.find("rolled back").unwrap() < screen.find("> db is fine").unwrap(), "{}", screen);
+}
@@ -0,0 +1,88 @@
+use rustdash::log_overlay::LogQuery;
+use rustdash::palette::{self, Entry, Palette, Target, VISIBLE_ENTRIES};
+use rustdash::prometheus::MetricsData;
+use rustdash::ui::AppState;
+
+mod common;
+
+fn state() -> AppState {
+    AppState {
+        log_queries: vec![LogQuery { name: "checkout orders".to_string(), query: r#"{app="checkout"} |= "order""#.to_string() }],
+        metrics: Some(MetricsData {
+            http_requests_total: 10.0,
+            uri_metrics: vec![common::metric("/api/orders", 10.0, 5.0), common::metric("/api/users/{id}", 10.0, 5.0)],
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        ..AppState::default()
+    }
+}
+
+fn labels(palette: &Palette) -> Vec<&str> {
+    palette.matches.iter().map(|(idx, _)| palette.entries[*idx].label.as_str()).collect()
+}
+
+#[test]
+fn entries_cover_actions_views_queries_and_uris() {
+    let entries = palette::entries(&state());
+    let find = |label: &str| entries.iter().find(|entry| entry.label == label).map(|entry| (entry.kind, &entry.target));
+    assert_eq!(find("Service map"), Some(("view", &Target::Key('m'))));
+    assert_eq!(find("Open the LogQL query bar"), Some(("action", &Target::Key('/'))));
+    assert_eq!(find("checkout orders"), Some(("query", &Target::LogQuery(0))));
+    assert_eq!(find("/api/users/{id}"), Some(("uri", &Target::Uri(1))));
+    assert_eq!(find("Open the admin menu"), None, "no admin actions configured");
+
+    // Without Prometheus its views and editor aren't offered
+    let entries = palette::entries(&AppState { prometheus_enabled: false, ..state() });
+    assert!(!entries.iter().any(|entry| entry.label == "Service map" || entry.label == "Open the PromQL editor"));
+    assert!(entries.iter().any(|entry| entry.label == "Open the LogQL query bar"));
+}
+
+#[test]
+fn typing_ranks_fuzzy_matches_best_first() {
+    let mut palette = Palette::new(palette::entries(&state()));
+    assert_eq!(palette.matches.len(), palette.entries.len(), "nothing typed lists everything");
+    assert_eq!(palette.chosen().unwrap().label, "Refresh all sources");
+
+    "orders".chars().for_each(|c| palette.input.insert(c));
+    palette.update();
+    // Equally good matches keep the entries' order
+    assert_eq!(labels(&palette)[..2], ["checkout orders", "/api/orders"]);
+
+    palette.input.clear_to_start();
+    "svcmap".chars().for_each(|c| palette.input.insert(c));
+    palette.update();
+    assert_eq!(labels(&palette), ["Service map"]);
+    assert_eq!(palette.chosen().unwrap().target, Target::Key('m'));
+
+    "zzz".chars().for_each(|c| palette.input.insert(c));
+    palette.update();
+    assert!(palette.chosen().is_none());
+}
+
+#[test]
+fn selection_wraps_and_scrolls_into_view() {
+    let entries: Vec<Entry> = palette::entries(&state()).into_iter().cycle().take(VISIBLE_ENTRIES + 5).collect();
+    let mut palette = Palette::new(entries);
+    palette.select(-1);
+    assert_eq!(palette.selected, VISIBLE_ENTRIES + 4);
+    assert_eq!(palette.scroll_offset, 5);
+    palette.select(1);
+    assert_eq!((palette.selected, palette.scroll_offset), (0, 0));
+}
+
+#[test]
+fn palette_lists_entries_with_their_kind_and_key() {
+    let mut state = state();
+    let mut palette = Palette::new(palette::entries(&state));
+    "map".chars().for_each(|c| palette.input.insert(c));
+    palette.update();
+    state.palette = Some(palette);
+
+    let screen = common::screen(&state, 100, 30);
+    assert!(screen.contains("> map"), "{}", screen);
+    assert!(screen.contains("Commands (1/"));
+    let row = screen.lines().find(|row| row.contains("Service map")).unwrap();
+    assert!(row.contains("m   view│"), "{}", row);
+}
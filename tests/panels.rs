@@ -0,0 +1,141 @@
+use rustdash::panels::{self, CounterView, Heatmap, PanelConfig, Severity, StatStyle, Table, TableRow, TableSort, ValueFormat};
+use rustdash::promql::Selector;
+
+fn series(le: &str, points: &[(f64, f64)]) -> (String, Vec<(f64, f64)>) {
+    (le.to_string(), points.to_vec())
+}
+
+#[test]
+fn heatmap_decumulates_sorted_buckets() {
+    let heatmap = panels::heatmap(
+        vec![
+            series("+Inf", &[(60.0, 10.0), (120.0, 12.0)]),
+            series("0.1", &[(60.0, 6.0), (120.0, 2.0)]),
+            series("0.025", &[(60.0, 5.0), (120.0, 2.0)]),
+        ],
+        true,
+    );
+    assert_eq!(
+        heatmap,
+        Heatmap {
+            buckets: vec!["≤ 25ms".to_string(), "≤ 100ms".to_string(), "≤ +Inf".to_string()],
+            times: vec![60.0, 120.0],
+            rates: vec![vec![5.0, 2.0], vec![1.0, 0.0], vec![4.0, 10.0]],
+        }
+    );
+    assert_eq!(heatmap.max_rate(), 10.0);
+    assert_eq!(heatmap.shares(), vec![7.0 / 22.0, 1.0 / 22.0, 14.0 / 22.0]);
+}
+
+#[test]
+fn heatmap_trims_empty_edge_buckets_and_fills_gaps() {
+    let heatmap = panels::heatmap(
+        vec![
+            series("1", &[(60.0, 0.0), (120.0, 0.0)]),
+            series("2.5", &[(60.0, 3.0)]),
+            series("5", &[(60.0, 4.0), (120.0, 1.0)]),
+            series("10", &[(60.0, 4.0), (120.0, 1.0)]),
+        ],
+        false,
+    );
+    assert_eq!(heatmap.buckets, ["≤ 2.5", "≤ 5"]);
+    // A missing point counts as nothing above the bucket below
+    assert_eq!(heatmap.rates, vec![vec![3.0, 0.0], vec![1.0, 1.0]]);
+}
+
+#[test]
+fn heatmap_of_no_observations_is_empty() {
+    let heatmap = panels::heatmap(vec![series("0.5", &[(60.0, 0.0)]), series("not-a-bound", &[(60.0, 1.0)])], true);
+    assert!(heatmap.buckets.is_empty());
+    assert!(heatmap.rates.is_empty());
+    assert_eq!(heatmap.shares(), Vec::<f64>::new());
+}
+
+#[test]
+fn thresholds_below_warn_mean_lower_is_worse() {
+    assert_eq!(panels::severity(5.0, 1.0, 10.0), Severity::Warn);
+    assert_eq!(panels::severity(10.0, 1.0, 10.0), Severity::Critical);
+    // Error budget remaining: warn at 25%, critical at 10%
+    assert_eq!(panels::severity(60.0, 25.0, 10.0), Severity::Ok);
+    assert_eq!(panels::severity(25.0, 25.0, 10.0), Severity::Warn);
+    assert_eq!(panels::severity(3.0, 25.0, 10.0), Severity::Critical);
+    assert_eq!(panels::severity(1e9, f64::INFINITY, f64::INFINITY), Severity::Ok);
+}
+
+#[test]
+fn gauge_ratio_is_clamped_to_its_range() {
+    let gauge = StatStyle::Gauge { min: 50.0, max: 150.0 };
+    assert_eq!(gauge.ratio(100.0), 0.5);
+    assert_eq!(gauge.ratio(10.0), 0.0);
+    assert_eq!(gauge.ratio(200.0), 1.0);
+    assert_eq!(StatStyle::Gauge { min: 1.0, max: 1.0 }.ratio(1.0), 0.0);
+}
+
+fn table() -> Table {
+    let row = |node: &str, cpu: &str, value: f64| TableRow {
+        labels: vec![node.to_string(), cpu.to_string()],
+        value,
+        severity: Severity::Ok,
+    };
+    Table {
+        columns: vec!["node".to_string(), "cpu".to_string()],
+        rows: vec![row("b", "10", 3.0), row("a", "9", 1.0), row("c", "2", 2.0)],
+        format: ValueFormat::Number,
+        sort: TableSort { column: 2, descending: true },
+        limit: 2,
+    }
+}
+
+#[test]
+fn table_sorts_by_any_column_up_to_its_limit() {
+    let table = table();
+    let nodes = |sort: TableSort| -> Vec<String> { table.sorted_rows(sort).iter().map(|row| row.labels[0].clone()).collect() };
+
+    assert_eq!(nodes(table.sort), ["b", "c"]);
+    assert_eq!(nodes(TableSort { column: 0, descending: false }), ["a", "b"]);
+    // Numeric labels sort as numbers
+    assert_eq!(nodes(TableSort { column: 1, descending: false }), ["c", "a"]);
+}
+
+#[test]
+fn table_sort_shifts_wrap_around_the_columns() {
+    let table = table();
+    assert_eq!(table.sort_shifted(0, false), table.sort);
+    assert_eq!(table.sort_shifted(1, false), TableSort { column: 0, descending: true });
+    assert_eq!(table.sort_shifted(5, true), TableSort { column: 1, descending: false });
+}
+
+#[test]
+fn counter_views_cycle_and_rewrite_the_query() {
+    assert_eq!(CounterView::Rate.next(), CounterView::Increase);
+    assert_eq!(CounterView::Increase.next(), CounterView::Raw);
+    assert_eq!(CounterView::Raw.next(), CounterView::Rate);
+
+    let counter = || Selector::new("http_requests_total").eq("job", "api");
+    assert_eq!(
+        panels::counter_expr(counter(), "5m", &["uri"], CounterView::Rate).to_string(),
+        r#"sum by (uri) (rate(http_requests_total{job="api"}[5m]))"#
+    );
+    assert_eq!(
+        panels::counter_expr(counter(), "5m", &["uri"], CounterView::Increase).to_string(),
+        r#"sum by (uri) (increase(http_requests_total{job="api"}[5m]))"#
+    );
+    assert_eq!(
+        panels::counter_expr(counter(), "5m", &["uri"], CounterView::Raw).to_string(),
+        r#"sum by (uri) (http_requests_total{job="api"})"#
+    );
+}
+
+#[test]
+fn only_counter_panels_have_a_view() {
+    let panel = |toml: &str| toml::from_str::<PanelConfig>(toml).unwrap();
+    assert_eq!(panel("preset = \"counter\"\nmetric = \"jobs_total\"").counter_view(), Some(CounterView::Rate));
+    assert_eq!(
+        panel("preset = \"counter\"\nmetric = \"jobs_total\"\nview = \"raw\"").counter_view(),
+        Some(CounterView::Raw)
+    );
+    assert_eq!(panel("preset = \"ingress\"\nsignal = \"request_rate\"").counter_view(), Some(CounterView::Rate));
+    assert_eq!(panel("preset = \"containers\"\nsignal = \"restarts\"").counter_view(), Some(CounterView::Increase));
+    assert_eq!(panel("preset = \"ingress\"").counter_view(), None);
+    assert_eq!(panel("preset = \"stat\"\nquery = \"up\"").counter_view(), None);
+}
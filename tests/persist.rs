@@ -0,0 +1,62 @@
+use rustdash::log_actions::LogFilter;
+use rustdash::loki::LogEntry;
+use rustdash::persist::{LogAnchor, PersistedState};
+use rustdash::ui::AppState;
+
+mod common;
+
+fn line(nanos: i64, stream: &str) -> LogEntry {
+    LogEntry { stream: stream.to_string(), ..common::log(nanos, "INFO", stream) }
+}
+
+#[test]
+fn the_selected_line_comes_back_among_lines_of_the_same_second() {
+    let logs = || {
+        vec![
+            line(1_000_000_000, r#"{app="a"}"#),
+            line(1_000_000_500, r#"{app="a"}"#),
+            line(1_000_000_500, r#"{app="b"}"#),
+            line(2_000_000_000, r#"{app="a"}"#),
+        ]
+    };
+    let state = AppState { all_logs: logs(), selected_log_index: Some(2), log_scroll_offset: 1, ..AppState::default() };
+    let saved = PersistedState::capture(&state);
+    assert_eq!(saved.selected_log_line, Some(LogAnchor { nanos: 1_000_000_500, stream: r#"{app="b"}"#.to_string() }));
+
+    let mut restored = AppState::default();
+    serde_json::from_str::<PersistedState>(&serde_json::to_string(&saved).unwrap()).unwrap().apply(&mut restored);
+    restored.all_logs = logs();
+    assert!(restored.resolve_restore_anchors());
+    assert_eq!(restored.selected_log_index, Some(2));
+    assert_eq!(restored.log_scroll_offset, 1);
+}
+
+#[test]
+fn a_line_gone_from_the_fetch_falls_to_the_next_one() {
+    let mut state = AppState {
+        all_logs: vec![line(1_000, "{}"), line(3_000, "{}")],
+        restore_selected_anchor: Some(LogAnchor { nanos: 2_000, stream: "{}".to_string() }),
+        ..AppState::default()
+    };
+    assert!(state.resolve_restore_anchors());
+    assert_eq!(state.selected_log_index, Some(1));
+}
+
+#[test]
+fn a_profile_forgets_what_belongs_to_the_last_endpoints() {
+    let state = AppState {
+        log_filter: LogFilter::query(r#"{app="shop"}"#),
+        last_log_query: Some(r#"{app="shop"} |= "timeout""#.to_string()),
+        all_logs: vec![line(1_000, "{}")],
+        selected_log_index: Some(0),
+        ..AppState::default()
+    };
+    let mut saved = PersistedState::capture(&state);
+    saved.forget_endpoints();
+
+    let mut restored = AppState::default();
+    saved.apply(&mut restored);
+    assert_eq!(restored.log_filter, LogFilter::default());
+    assert_eq!(restored.last_log_query, None);
+    assert_eq!(restored.restore_selected_anchor, None);
+}
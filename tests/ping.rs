@@ -0,0 +1,52 @@
+use rustdash::ping::{parse_rtt, PingConfig, PingTarget, HISTORY};
+use rustdash::ui::AppState;
+
+mod common;
+
+#[test]
+fn round_trips_are_read_from_each_platforms_ping() {
+    let linux = "PING example.com (93.184.216.34) 56(84) bytes of data.\n64 bytes from 93.184.216.34: icmp_seq=1 ttl=56 time=12.3 ms\n";
+    assert_eq!(parse_rtt(linux), Some(12.3));
+    let macos = "64 bytes from 127.0.0.1: icmp_seq=0 ttl=64 time=0.061 ms";
+    assert_eq!(parse_rtt(macos), Some(0.061));
+    let windows = "Reply from 10.0.0.1: bytes=32 time<1ms TTL=64";
+    assert_eq!(parse_rtt(windows), Some(1.0));
+    assert_eq!(parse_rtt("Request timeout for icmp_seq 0"), None);
+}
+
+#[test]
+fn hosts_that_look_like_options_are_refused() {
+    assert!(PingConfig { hosts: vec!["10.0.0.1".to_string(), "db.internal".to_string()], ..PingConfig::default() }.validate().is_ok());
+    assert!(PingConfig { hosts: vec!["-f".to_string()], ..PingConfig::default() }.validate().is_err());
+    assert!(PingConfig { hosts: vec![" ".to_string()], ..PingConfig::default() }.validate().is_err());
+}
+
+#[test]
+fn loss_and_average_cover_the_kept_samples() {
+    let mut target = PingTarget::new("db");
+    assert_eq!((target.last(), target.loss_pct(), target.average_ms()), (None, None, None));
+    for rtt in [Some(10.0), None, Some(20.0), None] {
+        target.record(rtt);
+    }
+    assert_eq!(target.last(), Some(None));
+    assert_eq!(target.loss_pct(), Some(50.0));
+    assert_eq!(target.average_ms(), Some(15.0));
+    for _ in 0..HISTORY {
+        target.record(Some(1.0));
+    }
+    assert_eq!((target.samples.len(), target.loss_pct()), (HISTORY, Some(0.0)));
+}
+
+#[test]
+fn popup_charts_round_trips_and_marks_lost_pings() {
+    let mut target = PingTarget::new("10.0.0.1");
+    for rtt in [Some(5.0), None, Some(40.0)] {
+        target.record(rtt);
+    }
+    let state = AppState { ping_targets: vec![target, PingTarget::new("db")], ping_popup: true, ..AppState::default() };
+    let screen = common::screen(&state, 120, 36);
+    assert!(screen.contains("Network latency (2 hosts)"), "{}", screen);
+    assert!(screen.contains("40.0 ms  avg 22.5 ms  loss 33% of 3"), "{}", screen);
+    assert!(screen.contains("▂×█"));
+    assert!(screen.contains("waiting for a reply"));
+}
@@ -0,0 +1,81 @@
+use rustdash::promql::{histogram_quantile, increase, parse_duration, quote, rate, Expr, Selector};
+
+#[test]
+fn label_values_are_escaped() {
+    assert_eq!(quote(r#"a"b\c"#), r#""a\"b\\c""#);
+    assert_eq!(quote("line\nbreak"), r#""line\nbreak""#);
+
+    let selector = Selector::new("up").eq("job", r#"api" or vector(1) #"#);
+    assert_eq!(selector.to_string(), r#"up{job="api\" or vector(1) #"}"#);
+}
+
+#[test]
+fn matchers_render_in_order() {
+    let selector = Selector::new("http_requests_total")
+        .eq("application", "shop")
+        .ne("status", "200")
+        .re("uri", "/api/.*")
+        .not_re("method", "OPTIONS|HEAD");
+    assert_eq!(
+        selector.to_string(),
+        r#"http_requests_total{application="shop", status!="200", uri=~"/api/.*", method!~"OPTIONS|HEAD"}"#
+    );
+    assert_eq!(Selector::new("up").to_string(), "up");
+}
+
+#[test]
+fn aggregations_wrap_expressions() {
+    let total = Expr::from(Selector::new("http_requests_total")).sum();
+    assert_eq!(total.to_string(), "sum(http_requests_total)");
+
+    let by_uri = increase(Selector::new("http_requests_total").range("1h")).sum_by(&["uri", "method"]);
+    assert_eq!(by_uri.to_string(), "sum by (uri, method) (increase(http_requests_total[1h]))");
+
+    let top = rate(Selector::new("errors_total").range("5m")).topk(3);
+    assert_eq!(top.to_string(), "topk(3, rate(errors_total[5m]))");
+}
+
+#[test]
+fn average_latency_query() {
+    let series = |metric: &str| Selector::new(metric).eq("application", "shop");
+    let query = rate(series("http_server_requests_seconds_sum").range("5m")).sum_by(&["uri"])
+        / rate(series("http_server_requests_seconds_count").range("5m")).sum_by(&["uri"]);
+    assert_eq!(
+        query.to_string(),
+        r#"sum by (uri) (rate(http_server_requests_seconds_sum{application="shop"}[5m])) / sum by (uri) (rate(http_server_requests_seconds_count{application="shop"}[5m]))"#
+    );
+}
+
+#[test]
+fn histogram_quantile_over_buckets() {
+    let buckets = rate(Selector::new("http_server_requests_seconds_bucket").range("5m")).sum_by(&["le", "uri"]);
+    assert_eq!(
+        histogram_quantile(0.95, buckets).to_string(),
+        "histogram_quantile(0.95, sum by (le, uri) (rate(http_server_requests_seconds_bucket[5m])))"
+    );
+}
+
+#[test]
+fn offset_shifts_range_vectors() {
+    let previous = rate(Selector::new("http_requests_total").range("5m").offset("5m")).sum();
+    assert_eq!(previous.to_string(), "sum(rate(http_requests_total[5m] offset 5m))");
+}
+
+#[test]
+fn sums_are_parenthesized() {
+    let series = |metric: &str| Expr::from(Selector::new(metric));
+    let share = series("used") / (series("used") + series("free"));
+    assert_eq!(share.to_string(), "used / (used + free)");
+}
+
+#[test]
+fn durations_parse_to_seconds() {
+    assert_eq!(parse_duration("90s"), Some(90));
+    assert_eq!(parse_duration("2h"), Some(7200));
+    assert_eq!(parse_duration("1h30m"), Some(5400));
+    assert_eq!(parse_duration("1d"), Some(86_400));
+    assert_eq!(parse_duration("2"), None);
+    assert_eq!(parse_duration("2 hours"), None);
+    assert_eq!(parse_duration("0m"), None);
+    assert_eq!(parse_duration(""), None);
+}
@@ -0,0 +1,121 @@
+use rustdash::query_editor::{context, Completing, Found, Lookup, QueryEditor};
+use rustdash::text::LineInput;
+use std::collections::HashMap;
+
+fn completing(input: &str) -> Option<Completing> {
+    context(input, input.len()).map(|context| context.completing)
+}
+
+fn type_text(editor: &mut QueryEditor, text: &str) -> Option<Lookup> {
+    text.chars().for_each(|c| editor.insert(c));
+    editor.update_completions()
+}
+
+fn series(sets: &[&[(&str, &str)]]) -> Found {
+    Found::Series(
+        sets.iter()
+            .map(|set| set.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>())
+            .collect(),
+    )
+}
+
+#[test]
+fn the_cursor_position_decides_what_completes() {
+    assert_eq!(completing("http_req"), Some(Completing::Metric));
+    assert_eq!(completing("sum(rate(http_req"), Some(Completing::Metric));
+    assert_eq!(
+        completing("up{"),
+        Some(Completing::LabelName { metric: Some("up".to_string()), in_selector: true })
+    );
+    assert_eq!(
+        completing(r#"up{job="api", inst"#),
+        Some(Completing::LabelName { metric: Some("up".to_string()), in_selector: true })
+    );
+    assert_eq!(
+        completing(r#"rate(http_requests_total{uri=~"/api"#),
+        Some(Completing::LabelValue { metric: Some("http_requests_total".to_string()), label: "uri".to_string(), regex: true })
+    );
+    assert_eq!(
+        completing(r#"{job!=""#),
+        Some(Completing::LabelValue { metric: None, label: "job".to_string(), regex: false })
+    );
+    assert_eq!(
+        completing("sum by (ur"),
+        Some(Completing::LabelName { metric: None, in_selector: false })
+    );
+
+    // Durations, numbers, closed strings and fresh selectors' values complete nothing
+    assert_eq!(completing("rate(up[5"), None);
+    assert_eq!(completing("up > 10"), None);
+    assert_eq!(completing(r#"up{job="api""#), None);
+    assert_eq!(completing("up{job="), None);
+
+    let context = context(r#"up{job="ap"#, 10).unwrap();
+    assert_eq!((context.start, context.prefix.as_str()), (8, "ap"));
+}
+
+#[test]
+fn completions_wait_for_a_lookup_asked_for_once() {
+    let mut editor = QueryEditor::default();
+    assert_eq!(type_text(&mut editor, "hrt"), Some(Lookup::MetricNames));
+    assert_eq!(type_text(&mut editor, "o"), None, "already pending");
+    assert!(editor.completions.is_empty());
+
+    let names = vec!["http_requests_total".to_string(), "up".to_string(), "http_server_requests_seconds_count".to_string()];
+    editor.catalog.store(&Lookup::MetricNames, Ok(Found::Names(names)));
+    assert_eq!(editor.update_completions(), None);
+    let completions: Vec<&str> = editor.completions.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(completions, ["http_requests_total", "http_server_requests_seconds_count"]);
+}
+
+#[test]
+fn failed_lookups_complete_to_nothing_until_reopened() {
+    let mut editor = QueryEditor::default();
+    type_text(&mut editor, "up");
+    editor.catalog.store(&Lookup::MetricNames, Err("HTTP 503".to_string()));
+    assert_eq!(editor.update_completions(), None);
+    assert_eq!(editor.catalog.error.as_deref(), Some("HTTP 503"));
+    // Functions still complete
+    editor.input = LineInput::default();
+    type_text(&mut editor, "histq");
+    assert_eq!(editor.completions[0].0, "histogram_quantile");
+}
+
+#[test]
+fn accepting_fills_in_metric_label_and_quoted_value() {
+    let mut editor = QueryEditor::default();
+    type_text(&mut editor, "rate(hrt");
+    editor.catalog.store(&Lookup::MetricNames, Ok(Found::Names(vec!["http_requests_total".to_string()])));
+    editor.update_completions();
+    assert_eq!(editor.accept(), None);
+    assert_eq!(editor.input.text, "rate(http_requests_total");
+
+    // Opening the selector asks for the metric's series
+    assert_eq!(type_text(&mut editor, "{"), Some(Lookup::Series("http_requests_total".to_string())));
+    editor.catalog.store(
+        &Lookup::Series("http_requests_total".to_string()),
+        Ok(series(&[&[("__name__", "http_requests_total"), ("uri", "/api/orders/{id}"), ("job", "shop")], &[("uri", "/health"), ("job", "shop")]])),
+    );
+    editor.update_completions();
+    let labels: Vec<&str> = editor.completions.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(labels, ["job", "uri"]);
+
+    // A label gets an empty matcher with the cursor inside, where values complete
+    editor.select(1);
+    editor.accept();
+    assert_eq!(editor.input.text, r#"rate(http_requests_total{uri="""#);
+    assert_eq!(editor.input.cursor, editor.input.text.len() - 1);
+    let values: Vec<&str> = editor.completions.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(values, ["/health", "/api/orders/{id}"]);
+
+    type_text(&mut editor, "ord");
+    editor.accept();
+    assert_eq!(editor.input.text, r#"rate(http_requests_total{uri="/api/orders/{id}""#);
+    assert_eq!(editor.input.cursor, editor.input.text.len());
+
+    // Under a regex matcher the value is escaped twice: for the regex, then the string
+    let mut editor = QueryEditor { catalog: editor.catalog.clone(), ..QueryEditor::default() };
+    type_text(&mut editor, r#"http_requests_total{uri=~"ord"#);
+    editor.accept();
+    assert_eq!(editor.input.text, r#"http_requests_total{uri=~"/api/orders/\\{id\\}""#);
+}
@@ -0,0 +1,32 @@
+use rustdash::query_stats::{Backend, QueryStats};
+use std::time::Duration;
+
+#[test]
+fn queries_are_ranked_by_server_time_then_end_to_end() {
+    let stats = QueryStats::default();
+    stats.record(Backend::Prometheus, "up", Duration::from_millis(40), Some(0.001));
+    stats.record(Backend::Prometheus, "up", Duration::from_millis(20), Some(0.002));
+    stats.record(Backend::Prometheus, "sum(rate(x[5m]))", Duration::from_millis(90), Some(0.08));
+    // No server time reported: its end-to-end time stands in
+    stats.record(Backend::Loki, "{job=\"api\"}", Duration::from_millis(30), None);
+
+    let timings = stats.by_cost();
+    let order: Vec<&str> = timings.iter().map(|t| t.query.as_str()).collect();
+    assert_eq!(order, ["sum(rate(x[5m]))", "{job=\"api\"}", "up"]);
+
+    let up = &timings[2];
+    assert_eq!(up.runs, 2);
+    assert!((up.last_ms - 20.0).abs() < 1e-9);
+    assert!((up.max_ms - 40.0).abs() < 1e-9);
+    assert!((up.average_ms() - 30.0).abs() < 1e-9);
+    assert!((up.cost_ms() - 3.0).abs() < 1e-9);
+    assert_eq!(up.last_server_ms, Some(2.0));
+}
+
+#[test]
+fn the_same_text_on_both_backends_is_two_queries() {
+    let stats = QueryStats::default();
+    stats.record(Backend::Prometheus, "up", Duration::from_millis(1), None);
+    stats.record(Backend::Loki, "up", Duration::from_millis(1), None);
+    assert_eq!(stats.len(), 2);
+}
@@ -0,0 +1,99 @@
+use chrono::{Local, TimeZone};
+use rustdash::history::UriTotals;
+use rustdash::loki::LogEntry;
+use rustdash::notes::Note;
+use rustdash::prometheus::MetricsData;
+use rustdash::report;
+use rustdash::silence::{SilenceAlarm, SilenceRule};
+use rustdash::synthetic::{CheckConfig, CheckRun, SyntheticCheck};
+use rustdash::ui::AppState;
+use std::collections::HashMap;
+
+mod common;
+
+fn at(minute: u32) -> chrono::DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 5, 1, 12, minute, 0).unwrap()
+}
+
+fn state() -> AppState {
+    let mut check = SyntheticCheck::new(CheckConfig { name: "orders".to_string(), ..CheckConfig::default() }).unwrap();
+    for (minute, failures) in [(1, vec![]), (2, vec!["status 503 (expected 2xx)".to_string()]), (3, vec!["timed out".to_string()]), (4, vec![])] {
+        check.record(CheckRun { at: at(minute), latency_ms: 10.0, status: Some(200), failures });
+    }
+    let mut alarm = SilenceAlarm::new(SilenceRule { name: Some("checkout".to_string()), selector: r#"{app="checkout"}"#.to_string(), minutes: 5 });
+    alarm.silent_since = Some(at(0));
+
+    let mut state = AppState {
+        synthetic_checks: vec![check],
+        silence_alarms: vec![alarm],
+        all_logs: (0..8)
+            .map(|i| LogEntry {
+                timestamp: format!("12:00:{:02}", i),
+                stream: r#"{app="api"}"#.to_string(),
+                ..common::log(i, if i % 2 == 0 { "ERROR" } else { "INFO" }, &format!("order {} failed", i))
+            })
+            .collect(),
+        metrics: Some(MetricsData {
+            http_requests_total: 10.0,
+            uri_metrics: vec![common::metric("/api/orders", 300.0, 120.0)],
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        notes: vec![Note { at: at(5), text: "rolled back".to_string(), context: vec!["range 1h".to_string()] }],
+        ..AppState::default()
+    };
+    state.bookmarks.insert(4);
+    // 100 ms a request at 60 requests a minute
+    for (i, count) in [0.0, 60.0, 120.0].into_iter().enumerate() {
+        let totals = HashMap::from([("/api/orders".to_string(), UriTotals { count, sum_seconds: count * 0.1 })]);
+        state.history.record(1_714_564_800.0 + 60.0 * i as f64, totals);
+    }
+    state
+}
+
+#[test]
+fn timeline_lists_alerts_firing_and_clearing_in_order() {
+    let events: Vec<String> = report::alert_timeline(&state()).into_iter().map(|(at, event)| format!("{} {}", at.format("%H:%M"), event)).collect();
+    assert_eq!(
+        events,
+        [
+            "12:00 checkout went silent (5 min without logs)",
+            "12:02 Check orders failing: status 503 (expected 2xx)",
+            "12:04 Check orders recovered",
+        ]
+    );
+}
+
+#[test]
+fn report_covers_errors_endpoints_bookmarks_and_notes() {
+    let report = report::build(&state());
+    assert!(report.starts_with("# Incident report - "));
+    assert!(report.contains("## Alerts\n\n- **2024-05-01 12:00:00** checkout went silent"), "{}", report);
+    assert!(report.contains("- 4x\n  ```text\n  order 0 failed\n  ```"), "{}", report);
+    assert!(report.contains("| `/api/orders` | 100.0 ms | 300.0 ms | +200% | 60 | 120 |"), "{}", report);
+    // The bookmarked line, marked, between the two lines either side of it
+    assert!(
+        report.contains(
+            "  ```text\n    12:00:02 ERROR {app=\"api\"} order 2 failed\n    12:00:03 INFO  {app=\"api\"} order 3 failed\n  > 12:00:04 ERROR {app=\"api\"} order 4 failed\n"
+        ),
+        "{}",
+        report
+    );
+    assert!(report.contains("    12:00:06 ERROR {app=\"api\"} order 6 failed\n  ```"));
+    assert!(report.contains("## Incident notes\n\n- **2024-05-01 12:05:00** rolled back"));
+
+    let empty = report::build(&AppState::default());
+    for placeholder in ["_No alerts fired._", "_No errors in the loaded logs._", "_No metrics._", "_No bookmarks._", "_No notes taken._"] {
+        assert!(empty.contains(placeholder), "{}", placeholder);
+    }
+}
+
+#[test]
+fn report_is_written_to_a_markdown_file() {
+    let dir = std::env::temp_dir().join(format!("rustdash-report-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = report::export(&state(), &dir).unwrap();
+    assert!(path.file_name().unwrap().to_string_lossy().starts_with("incident-report-"));
+    assert!(std::fs::read_to_string(path).unwrap().contains("## Worst endpoints"));
+}
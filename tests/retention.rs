@@ -0,0 +1,46 @@
+use rustdash::history::{MetricHistory, UriTotals};
+use rustdash::loki::LogEntry;
+use rustdash::retention::{self, RetentionConfig};
+use std::collections::HashMap;
+
+mod common;
+
+#[test]
+fn the_oldest_lines_go_beyond_the_line_count() {
+    let logs: Vec<LogEntry> = (0..10).map(|i| common::log(0, "INFO", &format!("line {}", i))).collect();
+    let limit = RetentionConfig { log_lines: 4, ..RetentionConfig::default() }.log_limit();
+    assert_eq!(retention::logs_over_limit(&logs, limit), 6);
+    assert_eq!(retention::logs_over_limit(&logs, RetentionConfig::default().log_limit()), 0);
+}
+
+#[test]
+fn the_memory_cap_drops_lines_until_the_rest_fit() {
+    let logs: Vec<LogEntry> = (0..100).map(|_| common::log(0, "INFO", &"x".repeat(10 * 1024))).collect();
+    let limit = RetentionConfig { log_memory_mb: Some(0.5), ..RetentionConfig::default() }.log_limit();
+
+    let dropped = retention::logs_over_limit(&logs, limit);
+
+    let kept = &logs[dropped..];
+    assert!(retention::logs_bytes(kept) <= 512 * 1024);
+    assert!(retention::logs_bytes(&logs[dropped - 1..]) > 512 * 1024);
+    // A single line over the cap is still shown
+    let tiny = RetentionConfig { log_memory_mb: Some(0.001), ..RetentionConfig::default() }.log_limit();
+    assert_eq!(retention::logs_over_limit(&logs, tiny), 99);
+}
+
+#[test]
+fn history_is_thinned_to_its_memory_cap() {
+    let retention = RetentionConfig { history_memory_mb: Some(0.1), ..RetentionConfig::default() };
+    let mut history = MetricHistory::new("http://prometheus").with_limit(retention.history_limit());
+    for i in 0..2000 {
+        let totals = (0..20)
+            .map(|uri| (format!("/api/endpoint/{}", uri), UriTotals { count: i as f64, sum_seconds: i as f64 }))
+            .collect::<HashMap<_, _>>();
+        history.record(i as f64, totals);
+    }
+
+    assert!(history.bytes() <= (0.1 * 1024.0 * 1024.0) as usize, "{}", history.bytes());
+    assert!(history.len() < 2000);
+    assert_eq!(history.since(), Some(0.0));
+    assert_eq!(history.latest(), Some(1999.0));
+}
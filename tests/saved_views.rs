@@ -0,0 +1,98 @@
+use rustdash::fetch::RefreshScope;
+use rustdash::log_actions::LogFilter;
+use rustdash::persist::PersistedState;
+use rustdash::prometheus::Ranking;
+use rustdash::saved_views::{self, SavedView, SavedViewsPopup};
+use rustdash::text::LineInput;
+use rustdash::ui::{ActivePanel, AppState, TimeRange};
+use rustdash::variables::{Variable, VariableConfig};
+
+mod common;
+
+fn state_with_variable() -> AppState {
+    let mut variable = Variable::new(VariableConfig {
+        name: "ns".to_string(),
+        label: "namespace".to_string(),
+        metric: None,
+        include_all: true,
+        default: None,
+    });
+    variable.set_values(vec!["shop".to_string(), "auth".to_string()]);
+    AppState { variables: vec![variable], ..AppState::default() }
+}
+
+#[test]
+fn a_saved_view_comes_back_as_one_undo() {
+    let mut state = state_with_variable();
+    state.active_panel = ActivePanel::Logs;
+    state.set_log_filter(LogFilter::query(r#"{app="shop"} |= "timeout""#));
+    state.set_time_range(TimeRange::OneHour);
+    state.variables[0].selected = Some("shop".to_string());
+    let saved = SavedView::capture("checkout timeouts", &state);
+
+    state.set_log_filter(LogFilter::default());
+    state.set_time_range(TimeRange::FiveMin);
+    state.set_ranking(Ranking::Errors);
+    state.variables[0].selected = None;
+    state.active_panel = ActivePanel::Metrics;
+
+    assert_eq!(saved.apply(&mut state), Some(RefreshScope::All));
+    assert_eq!(state.log_filter.query.as_deref(), Some(r#"{app="shop"} |= "timeout""#));
+    assert_eq!(state.metrics_time_range, TimeRange::OneHour);
+    assert_eq!(state.metrics_ranking, Ranking::Busiest);
+    assert_eq!(state.variables[0].display(), "shop");
+    assert_eq!(state.active_panel, ActivePanel::Logs);
+    assert_eq!(saved.apply(&mut state), None, "already showing");
+
+    state.undo_view();
+    assert_eq!(state.metrics_ranking, Ranking::Errors);
+    assert!(state.log_filter.query.is_none());
+}
+
+#[test]
+fn the_selected_uri_waits_for_the_metrics() {
+    let mut state = AppState::default();
+    let saved = SavedView { selected_uri: Some("/api/orders".to_string()), ..SavedView::capture("orders", &state) };
+    assert_eq!(saved.apply(&mut state), None);
+    assert_eq!(state.restore_selected_uri.as_deref(), Some("/api/orders"));
+    assert_eq!(state.selected_metric_index, None);
+}
+
+#[test]
+fn saving_under_a_name_replaces_it() {
+    let state = AppState::default();
+    let mut views = Vec::new();
+    assert_eq!(saved_views::save(&mut views, SavedView::capture("a", &state)), 0);
+    assert_eq!(saved_views::save(&mut views, SavedView::capture("b", &state)), 1);
+    let replaced = SavedView { time_range: TimeRange::OneDay, ..SavedView::capture("a", &state) };
+    assert_eq!(saved_views::save(&mut views, replaced), 0);
+    assert_eq!(views.len(), 2);
+    assert_eq!(views[0].time_range, TimeRange::OneDay);
+}
+
+#[test]
+fn views_are_kept_in_the_state_file() {
+    let mut state = state_with_variable();
+    state.set_log_filter(LogFilter { pattern: Some("timeout".to_string()), ..LogFilter::default() });
+    state.saved_views.push(SavedView::capture("timeouts", &state));
+    let json = serde_json::to_string(&PersistedState::capture(&state)).unwrap();
+
+    let mut restored = AppState::default();
+    serde_json::from_str::<PersistedState>(&json).unwrap().apply(&mut restored);
+    assert_eq!(restored.saved_views, state.saved_views);
+    assert_eq!(restored.saved_views[0].variables.get("ns"), Some(&None));
+}
+
+#[test]
+fn popup_lists_the_views() {
+    let mut state = state_with_variable();
+    state.set_time_range(TimeRange::OneHour);
+    state.saved_views.push(SavedView::capture("incident", &state));
+    state.saved_views_popup = Some(SavedViewsPopup::default());
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains(" Saved views (1) "), "{}", shown);
+    assert!(shown.contains(" incident  1h · by busiest · ns=All"));
+
+    state.saved_views_popup = Some(SavedViewsPopup { selected: 0, naming: Some(LineInput::new("after deploy")) });
+    assert!(common::screen(&state, 120, 30).contains("Save as: after deploy"));
+}
@@ -0,0 +1,71 @@
+use reqwest::header::AUTHORIZATION;
+use rustdash::config::{AuthConfig, Settings};
+use rustdash::http;
+use rustdash::secrets::{self, Secret};
+
+fn command(command: &str) -> Secret {
+    Secret { command: Some(command.to_string()), ..Secret::default() }
+}
+
+#[test]
+fn secrets_come_from_env_commands_and_env_files() {
+    std::env::set_var("RUSTDASH_TEST_TOKEN", "s3cret");
+    let env = Secret { env: Some("RUSTDASH_TEST_TOKEN".to_string()), ..Secret::default() };
+    assert_eq!(env.resolve().unwrap(), "s3cret");
+
+    // Like `pass show`: the secret on the first line, metadata after it
+    assert_eq!(command("printf 'hunter2\\nurl: grafana.example.com\\n'").resolve().unwrap(), "hunter2");
+
+    let path = std::env::temp_dir().join(format!("rustdash-secrets-{}.env", std::process::id()));
+    std::fs::write(&path, "# monitoring\nexport LOKI_TOKEN=\"abc def\"\nPROM_PASSWORD='p=w'\n\n").unwrap();
+    let file = path.to_str().unwrap();
+    assert_eq!(secrets::read_env_file(file, "LOKI_TOKEN").unwrap(), "abc def");
+    assert_eq!(secrets::read_env_file(file, "PROM_PASSWORD").unwrap(), "p=w");
+    assert!(secrets::read_env_file(file, "MISSING").is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn a_secret_needs_exactly_one_working_source() {
+    assert!(Secret::default().resolve().is_err());
+    let both = Secret { env: Some("HOME".to_string()), command: Some("echo x".to_string()), ..Secret::default() };
+    assert!(both.resolve().is_err());
+    assert!(Secret { env: Some("RUSTDASH_TEST_UNSET".to_string()), ..Secret::default() }.resolve().is_err());
+    assert!(Secret { env_file: Some("/nonexistent".to_string()), ..Secret::default() }.resolve().is_err());
+    assert!(command("exit 1").resolve().is_err());
+    assert!(command("true").resolve().is_err());
+}
+
+#[test]
+fn auth_turns_into_a_sensitive_authorization_header() {
+    assert!(http::auth_headers(&AuthConfig::default()).unwrap().is_empty());
+
+    let basic = AuthConfig { username: Some("grafana".to_string()), password: Some(command("echo open-sesame")), bearer_token: None };
+    let headers = http::auth_headers(&basic).unwrap();
+    let value = &headers[AUTHORIZATION];
+    assert_eq!(value.to_str().unwrap(), "Basic Z3JhZmFuYTpvcGVuLXNlc2FtZQ==");
+    assert!(value.is_sensitive());
+    assert!(!format!("{:?}", headers).contains("Z3JhZmFuYT"));
+
+    let bearer = AuthConfig { bearer_token: Some(command("echo tok")), ..basic };
+    assert_eq!(http::auth_headers(&bearer).unwrap()[AUTHORIZATION], "Bearer tok");
+}
+
+#[test]
+fn profiles_pick_their_own_credentials() {
+    let mut settings: Settings = toml::from_str(
+        r#"
+        [prometheus.auth]
+        username = "admin"
+        password = { env = "PROM_PASSWORD" }
+
+        [profiles.staging]
+        prometheus_url = "http://prometheus.staging:9090"
+        loki_auth = { bearer_token = { command = "pass show staging/loki" } }
+        "#,
+    )
+    .unwrap();
+    settings.apply_profile("staging").unwrap();
+    assert_eq!(settings.prometheus.auth.username.as_deref(), Some("admin"));
+    assert_eq!(settings.loki.auth.bearer_token, Some(command("pass show staging/loki")));
+}
@@ -0,0 +1,71 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use rustdash::frontend::{Frontend, MemoryFrontend};
+use rustdash::share::{self, ShareServer};
+use rustdash::ui::AppState;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Read from a viewer's connection until `expected` arrives.
+async fn read_until(stream: &mut TcpStream, expected: &str) -> String {
+    let mut received = Vec::new();
+    let mut chunk = [0; 4096];
+    while !text(&received).contains(expected) {
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut chunk)).await.unwrap().unwrap();
+        assert!(read > 0, "connection closed before {:?}", expected);
+        received.extend_from_slice(&chunk[..read]);
+    }
+    text(&received)
+}
+
+#[test]
+fn bare_ports_share_on_localhost() {
+    assert_eq!(share::address("7000"), "127.0.0.1:7000");
+    assert_eq!(share::address("0.0.0.0:7000"), "0.0.0.0:7000");
+    assert_eq!(share::address("db-1:7000"), "db-1:7000");
+}
+
+#[test]
+fn frames_redraw_the_whole_screen() {
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 20, 2));
+    buffer.set_string(0, 1, "hello", ratatui::style::Style::default());
+    let frame = text(&share::encode(&buffer));
+    assert!(frame.starts_with("\x1b[?2026h"), "{:?}", frame);
+    assert!(frame.contains("\x1b[2J"), "the screen is cleared first");
+    assert!(frame.contains("hello"));
+    assert!(frame.ends_with("\x1b[?2026l"));
+}
+
+#[tokio::test]
+async fn viewers_get_the_latest_frame_and_then_each_new_one() {
+    let share = ShareServer::start("127.0.0.1:0").await.unwrap();
+    let mut frontend = MemoryFrontend::new(200, 30).unwrap();
+    let state = AppState { status: "first frame".to_string(), ..AppState::default() };
+    share.publish(frontend.draw(&state).unwrap().buffer);
+
+    let mut viewer = TcpStream::connect(share.address).await.unwrap();
+    read_until(&mut viewer, "first frame").await;
+    assert_eq!(share.viewers(), 1);
+
+    let state = AppState { status: "second frame".to_string(), share: Some(share), ..AppState::default() };
+    state.share.as_ref().unwrap().publish(frontend.draw(&state).unwrap().buffer);
+    let screen = frontend.lines().join("\n");
+    assert!(screen.contains("(1 viewer)"), "the footer counts viewers: {}", screen);
+    read_until(&mut viewer, "second frame").await;
+
+    drop(viewer);
+    let share = state.share.as_ref().unwrap();
+    for _ in 0..50 {
+        share.publish(&Buffer::empty(Rect::new(0, 0, 1, 1)));
+        if share.viewers() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(share.viewers(), 0, "a viewer that left is no longer counted");
+}
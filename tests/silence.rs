@@ -0,0 +1,24 @@
+use chrono::{Duration, Local};
+use rustdash::silence::{SilenceAlarm, SilenceRule};
+
+#[test]
+fn alarm_goes_off_once_per_silence() {
+    let rule = SilenceRule {
+        name: None,
+        selector: r#"{service_name="checkout"}"#.to_string(),
+        minutes: 5,
+    };
+    assert_eq!(rule.label(), r#"{service_name="checkout"}"#);
+    let mut alarm = SilenceAlarm::new(rule);
+    let now = Local::now();
+
+    assert!(!alarm.update(true, now));
+    assert!(alarm.update(false, now));
+    // Still silent: the alarm keeps its start and doesn't go off again
+    assert!(!alarm.update(false, now + Duration::seconds(5)));
+    assert_eq!(alarm.silent_since, Some(now));
+
+    assert!(!alarm.update(true, now + Duration::seconds(10)));
+    assert!(!alarm.is_firing());
+    assert!(alarm.update(false, now + Duration::seconds(15)));
+}
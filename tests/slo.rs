@@ -0,0 +1,104 @@
+use chrono::{DateTime, Local, TimeZone};
+use rustdash::panels::Severity;
+use rustdash::slo::{BurnRates, Slo, SloConfig};
+use rustdash::triage::Alert;
+use rustdash::ui::AppState;
+
+mod common;
+
+fn slo() -> Slo {
+    Slo::new(SloConfig {
+        name: "checkout".to_string(),
+        errors: r#"http_requests_total{status=~"5.."}"#.to_string(),
+        total: "http_requests_total".to_string(),
+        ..SloConfig::default()
+    })
+    .unwrap()
+}
+
+fn at(seconds: i64) -> DateTime<Local> {
+    Local.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+}
+
+/// Burn rates over 5m, 30m, 1h, 2h, 6h, 1d and 3d.
+fn rates(rates: [f64; 7]) -> BurnRates {
+    rates.map(Some)
+}
+
+#[test]
+fn config_defaults_and_queries() {
+    let config: SloConfig = toml::from_str("name = \"api\"\nerrors = \"errors_total\"\ntotal = \"requests_total\"").unwrap();
+    assert_eq!(config.objective, 99.9);
+    assert!((config.error_budget() - 0.001).abs() < 1e-12);
+    assert_eq!(config.error_ratio_query("30m"), "sum(rate(errors_total[30m])) / sum(rate(requests_total[30m]))");
+}
+
+#[test]
+fn rejects_unusable_slos() {
+    let config = SloConfig { name: "api".to_string(), errors: "e".to_string(), total: "t".to_string(), ..SloConfig::default() };
+    assert!(Slo::new(SloConfig { objective: 100.0, ..config.clone() }).is_err());
+    assert!(Slo::new(SloConfig { total: String::new(), ..config.clone() }).is_err());
+    assert!(Slo::new(config).is_ok());
+}
+
+#[test]
+fn both_windows_must_burn() {
+    let mut slo = slo();
+    slo.update(rates([40.0, 2.0, 3.0, 0.5, 0.5, 0.5, 0.5]), at(0));
+    assert_eq!(slo.firing(), None, "a blip in the short window alone");
+
+    slo.update(rates([20.0, 8.0, 15.0, 2.0, 7.0, 2.0, 0.5]), at(0));
+    assert_eq!(slo.severity(), Severity::Critical);
+    assert_eq!(slo.describe_firing().unwrap(), "burning 15.0× over 1h, 20.0× over 5m (page)");
+
+    slo.update(rates([3.0, 8.0, 4.0, 2.0, 7.0, 2.0, 0.5]), at(0));
+    assert_eq!(slo.severity(), Severity::Critical);
+    assert_eq!(slo.describe_firing().unwrap(), "burning 7.0× over 6h, 8.0× over 30m (page)");
+
+    slo.update(rates([1.0, 2.0, 2.0, 4.0, 2.0, 3.5, 0.5]), at(0));
+    assert_eq!(slo.severity(), Severity::Warn);
+    assert_eq!(slo.describe_firing().unwrap(), "burning 3.5× over 1d, 4.0× over 2h (ticket)");
+
+    slo.update(rates([0.5, 0.8, 1.5, 1.5, 1.5, 1.2, 1.2]), at(0));
+    assert_eq!(slo.severity(), Severity::Warn);
+    assert_eq!(slo.describe_firing().unwrap(), "burning 1.2× over 3d, 1.5× over 6h (ticket)");
+
+    slo.update([None, None, None, Some(4.0), Some(2.0), Some(4.0), Some(2.0)], at(0));
+    assert_eq!(slo.firing().unwrap().long, "1d", "no traffic in the short windows");
+}
+
+#[test]
+fn rings_when_an_alert_fires_or_escalates() {
+    let mut slo = slo();
+    assert!(!slo.update(rates([1.0; 7]), at(0)));
+    assert!(slo.update(rates([2.0; 7]), at(10)), "ticket");
+    assert!(!slo.update(rates([2.0; 7]), at(20)), "still the same ticket");
+    assert!(!slo.update(rates([4.0; 7]), at(30)), "a faster ticket is still a ticket");
+    assert!(slo.update(rates([7.0; 7]), at(40)), "now a page");
+    assert!(!slo.update(rates([20.0; 7]), at(50)), "a faster page is still a page");
+    assert!(!slo.update(rates([4.0; 7]), at(60)), "calming down to a ticket");
+    assert_eq!(slo.burning_since, Some(at(10)));
+    assert!(!slo.update(rates([0.5; 7]), at(70)));
+    assert_eq!(slo.burning_since, None);
+}
+
+#[test]
+fn burning_slos_join_the_banner_and_the_popup() {
+    let mut burning = slo();
+    burning.update(rates([20.0, 8.0, 15.0, 2.0, 7.0, 2.0, 0.5]), at(0));
+    let mut state = AppState { slos: vec![burning], ..AppState::default() };
+    let alerts = state.firing_alerts();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].0, Alert::Burning("checkout".to_string()));
+    assert_eq!(alerts[0].0.to_string(), "BURNING checkout");
+
+    state.slo_popup = true;
+    let screen = common::screen(&state, 120, 30);
+    assert!(screen.contains(" SLO burn rates (1, 1 burning) "), "{}", screen);
+    assert!(screen.contains("objective 99.9%, error budget 0.100%"));
+    assert!(screen.contains("5m 20.0×"));
+    assert!(screen.contains("3d 0.5×"));
+    assert!(screen.contains("CRIT: burning 15.0× over 1h"));
+    assert!(screen.contains("page: 1h and 5m > 14.4×, 6h and 30m > 6×"), "{}", screen);
+    assert!(screen.contains("ticket: 1d and 2h > 3×, 3d and 6h > 1×"), "{}", screen);
+}
@@ -0,0 +1,68 @@
+use ratatui::style::Color;
+use rustdash::frontend::{Frontend, MemoryFrontend};
+use rustdash::status_pages::{parse_summary, DependencyStatus, Indicator, StatusPageConfig};
+use rustdash::ui::{AboutBackends, AppState};
+
+mod common;
+
+const SUMMARY: &str = r#"{
+    "page": {"id": "x", "name": "Stripe"},
+    "status": {"indicator": "major", "description": "Partial System Outage"},
+    "components": [
+        {"name": "API", "status": "partial_outage", "group": false},
+        {"name": "Checkout", "status": "operational"},
+        {"name": "Europe", "status": "partial_outage", "group": true}
+    ],
+    "incidents": [{"name": "Increased API error rates", "status": "identified"}]
+}"#;
+
+fn page(name: &str, url: &str) -> StatusPageConfig {
+    StatusPageConfig { name: name.to_string(), url: url.to_string() }
+}
+
+#[test]
+fn summary_url_is_derived_from_the_page() {
+    assert_eq!(page("GitHub", "https://www.githubstatus.com/").summary_url(), "https://www.githubstatus.com/api/v2/summary.json");
+    assert_eq!(page("x", "https://status.example.com/api/v2/status.json").summary_url(), "https://status.example.com/api/v2/status.json");
+}
+
+#[test]
+fn summaries_report_incidents_and_degraded_components() {
+    let status = parse_summary("Stripe", SUMMARY).unwrap();
+    assert_eq!(status.indicator, Indicator::Major);
+    assert_eq!(status.description, "Partial System Outage");
+    assert_eq!(status.incidents, ["Increased API error rates"]);
+    assert_eq!(status.degraded, ["API (partial outage)"], "groups repeat their components");
+    assert!(status.checked.is_some());
+
+    // status.json has no components or incidents
+    let status = parse_summary("AWS", r#"{"status": {"indicator": "none", "description": "All Systems Operational"}}"#).unwrap();
+    assert_eq!((status.indicator, status.incidents.len()), (Indicator::Operational, 0));
+    assert!(parse_summary("AWS", "<html>").is_err());
+}
+
+#[test]
+fn endpoints_bar_summarizes_each_dependency() {
+    let deps = vec![
+        parse_summary("Stripe", SUMMARY).unwrap(),
+        DependencyStatus { indicator: Indicator::Operational, ..DependencyStatus::pending(&page("AWS", "")) },
+        DependencyStatus::pending(&page("GitHub", "")),
+    ];
+    let state = AppState { external_deps: deps.clone(), ..AppState::default() };
+    let mut frontend = MemoryFrontend::new(140, 40).unwrap();
+    frontend.draw(&state).unwrap();
+    let rows = frontend.lines();
+    let (y, row) = rows.iter().enumerate().find(|(_, row)| row.contains("External:")).expect("widget shown");
+    assert!(row.contains("Stripe ▲ major AWS ✓ GitHub ?"), "{}", row);
+    let x = row[..row.find("Stripe").unwrap()].chars().count() as u16;
+    assert_eq!(frontend.terminal().backend().buffer()[(x, y as u16)].fg, Color::Red);
+
+    let shown = common::screen(&AppState { external_deps: deps.clone(), accessible: true, ..AppState::default() }, 140, 40);
+    assert!(shown.contains("Stripe major AWS ok GitHub unknown"));
+
+    // The details are in the backends popup
+    let popup = common::screen(&AppState { external_deps: deps, about: Some(AboutBackends::default()), ..AppState::default() }, 140, 40);
+    assert!(popup.contains("External dependencies"), "{}", popup);
+    assert!(popup.contains("Incident: Increased API error rates"));
+    assert!(popup.contains("Not checked yet"));
+}
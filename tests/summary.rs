@@ -0,0 +1,28 @@
+use chrono::{Local, TimeZone};
+use rustdash::silence::{SilenceAlarm, SilenceRule};
+use rustdash::slo::{Slo, SloConfig};
+use rustdash::summary::build_summary;
+use rustdash::ui::AppState;
+
+#[test]
+fn lists_the_firing_alerts() {
+    assert!(build_summary(&AppState::default()).contains("\nFiring alerts\n  (none)\n"));
+
+    let since = Local.with_ymd_and_hms(2024, 5, 1, 12, 3, 0).unwrap();
+    let mut alarm = SilenceAlarm::new(SilenceRule { name: Some("checkout".to_string()), selector: r#"{app="checkout"}"#.to_string(), minutes: 5 });
+    alarm.silent_since = Some(since);
+    let mut slo = Slo::new(SloConfig {
+        name: "orders".to_string(),
+        errors: "errors_total".to_string(),
+        total: "requests_total".to_string(),
+        ..SloConfig::default()
+    })
+    .unwrap();
+    slo.update([20.0, 8.0, 15.0, 2.0, 7.0, 2.0, 0.5].map(Some), since);
+    let state = AppState { silence_alarms: vec![alarm], slos: vec![slo], ..AppState::default() };
+
+    let summary = build_summary(&state);
+    assert!(summary.contains("  SILENT checkout  since 12:03:00  no logs for 5m+\n"), "{}", summary);
+    assert!(summary.contains("  BURNING orders  since 12:03:00  burning 15.0× over 1h, 20.0× over 5m (page)\n"), "{}", summary);
+    assert!(summary.find("Firing alerts") < summary.find("Worst latencies"));
+}
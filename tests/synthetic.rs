@@ -0,0 +1,80 @@
+use chrono::{Duration, Local};
+use rustdash::synthetic::{CheckConfig, CheckRun, SyntheticCheck, HISTORY};
+use rustdash::ui::AppState;
+
+mod common;
+
+fn check(config: CheckConfig) -> SyntheticCheck {
+    SyntheticCheck::new(CheckConfig { name: "api".to_string(), url: "http://api/health".to_string(), ..config }).unwrap()
+}
+
+fn run(latency_ms: f64, failures: &[&str]) -> CheckRun {
+    CheckRun {
+        at: Local::now(),
+        latency_ms,
+        status: Some(200),
+        failures: failures.iter().map(|failure| failure.to_string()).collect(),
+    }
+}
+
+#[test]
+fn assertions_cover_status_latency_and_body() {
+    let plain = check(CheckConfig::default());
+    assert!(plain.assert(204, 5000.0, "").is_empty(), "any 2xx passes without other assertions");
+    assert_eq!(plain.assert(503, 10.0, ""), ["status 503 (expected 2xx)"]);
+
+    let strict = check(CheckConfig {
+        expect_status: Some(301),
+        max_latency_ms: Some(200),
+        body_regex: Some(r#""status":\s*"UP""#.to_string()),
+        ..CheckConfig::default()
+    });
+    assert!(strict.assert(301, 150.0, r#"{"status": "UP"}"#).is_empty());
+    assert_eq!(strict.assert(200, 812.4, "{}"), [
+        "status 200 (expected 301)",
+        "took 812 ms (budget 200 ms)",
+        r#"body doesn't match `"status":\s*"UP"`"#,
+    ]);
+}
+
+#[test]
+fn invalid_checks_are_rejected_at_startup() {
+    let error = SyntheticCheck::new(CheckConfig { name: "x".to_string(), body_regex: Some("(".to_string()), ..CheckConfig::default() });
+    assert!(error.unwrap_err().to_string().contains("Invalid body_regex '(' in synthetic check 'x'"));
+    assert!(SyntheticCheck::new(CheckConfig { method: "GE T".to_string(), ..CheckConfig::default() }).is_err());
+}
+
+#[test]
+fn failure_streaks_ring_once_and_history_is_bounded() {
+    let mut check = check(CheckConfig::default());
+    assert!(!check.record(run(10.0, &[])));
+    assert!(check.record(run(10.0, &["status 500 (expected 2xx)"])), "a new failure streak rings");
+    let since = check.failing_since.unwrap();
+    assert!(!check.record(run(10.0, &["timed out"])), "and only once");
+    assert_eq!(check.failing_since, Some(since));
+    assert!(!check.record(run(10.0, &[])));
+    assert!(!check.is_failing());
+    assert_eq!(check.uptime_pct(), Some(50.0));
+
+    for _ in 0..HISTORY {
+        check.record(run(10.0, &[]));
+    }
+    assert_eq!(check.runs.len(), HISTORY);
+    assert_eq!(check.uptime_pct(), Some(100.0));
+}
+
+#[test]
+fn failing_checks_join_the_banner_and_chart_in_the_popup() {
+    let mut failing = check(CheckConfig { max_latency_ms: Some(100), ..CheckConfig::default() });
+    failing.record(run(40.0, &[]));
+    failing.record(CheckRun { at: Local::now() - Duration::seconds(5), ..run(350.0, &["took 350 ms (budget 100 ms)"]) });
+    let state = AppState { synthetic_checks: vec![failing], ..AppState::default() };
+    let screen_text = common::screen(&state, 120, 36);
+    assert!(screen_text.contains("FAILING api: took 350 ms (budget 100 ms) (since "), "{}", screen_text);
+
+    let screen_text = common::screen(&AppState { checks_popup: true, ..state }, 120, 36);
+    assert!(screen_text.contains("Synthetic checks (1, 1 failing)"), "{}", screen_text);
+    assert!(screen_text.contains("GET http://api/health"));
+    assert!(screen_text.contains("✗ took 350 ms (budget 100 ms)  uptime 50.0% of 2 runs"));
+    assert!(screen_text.contains("▂█"), "latency bars: {}", screen_text);
+}
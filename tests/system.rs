@@ -0,0 +1,55 @@
+use rustdash::system::{ProcessInfo, ProcessSort, SystemSampler, SystemSnapshot, SystemView};
+use rustdash::ui::AppState;
+
+mod common;
+
+fn process(pid: u32, name: &str, cpu_pct: f32, memory_bytes: u64) -> ProcessInfo {
+    ProcessInfo { pid, name: name.to_string(), cpu_pct, memory_bytes }
+}
+
+fn snapshot() -> SystemSnapshot {
+    SystemSnapshot {
+        host: Some("db-1".to_string()),
+        load: [1.5, 0.75, 0.25],
+        cpu_count: 4,
+        cpu_pct: 50.0,
+        memory_used: 6 << 30,
+        memory_total: 8 << 30,
+        processes: vec![process(10, "postgres", 12.0, 3 << 30), process(20, "backup", 95.0, 1 << 20), process(30, "sshd", 0.0, 4 << 20)],
+        ..SystemSnapshot::default()
+    }
+}
+
+#[test]
+fn processes_sort_by_cpu_or_memory_heaviest_first() {
+    let snapshot = snapshot();
+    let pids = |sort| snapshot.sorted(sort).iter().map(|process| process.pid).collect::<Vec<_>>();
+    assert_eq!(pids(ProcessSort::Cpu), [20, 10, 30]);
+    assert_eq!(pids(ProcessSort::Memory), [10, 30, 20]);
+    assert_eq!(ProcessSort::default().toggle(), ProcessSort::Memory);
+    assert_eq!(ProcessSort::Memory.toggle(), ProcessSort::Cpu);
+}
+
+#[test]
+fn view_shows_gauges_load_and_the_process_table() {
+    let draw = |view: SystemView| {
+        common::screen(&AppState { system_view: Some(view), ..AppState::default() }, 120, 36)
+    };
+    assert!(draw(SystemView::default()).contains("Sampling the host..."));
+
+    let screen = draw(SystemView { snapshot: Some(snapshot()), sort: ProcessSort::Memory, scroll_offset: 0 });
+    assert!(screen.contains("Local system [by memory]"), "{}", screen);
+    assert!(screen.contains("db-1  load 1.50 0.75 0.25 (4 CPUs)"), "{}", screen);
+    assert!(screen.contains("6.0G / 8.0G"), "{}", screen);
+    let postgres = screen.find("postgres").unwrap();
+    assert!(postgres < screen.find("sshd").unwrap() && postgres < screen.find("backup").unwrap(), "{}", screen);
+    assert!(screen.contains("37.5"), "postgres uses 37.5% of the memory\n{}", screen);
+}
+
+#[test]
+fn sampling_reads_this_host() {
+    let snapshot = SystemSampler::default().sample();
+    assert!(snapshot.memory_total > 0);
+    assert!(snapshot.cpu_count > 0);
+    assert!(snapshot.processes.iter().any(|process| process.pid == std::process::id()), "this test's own process is listed");
+}
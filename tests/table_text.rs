@@ -0,0 +1,74 @@
+use rustdash::prometheus::{MetricsData, Ranking, UriMetric};
+use rustdash::table_text::{metrics_table, TableFormat};
+use rustdash::ui::{AppState, TimeRange};
+
+mod common;
+
+fn state(uri_metrics: Vec<UriMetric>) -> AppState {
+    AppState {
+        metrics: Some(MetricsData {
+            http_requests_total: 0.0,
+            uri_metrics,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        metrics_time_range: TimeRange::FiveMin,
+        ..AppState::default()
+    }
+}
+
+/// The table without its heading, whose time varies.
+fn table(state: &AppState, format: TableFormat) -> Vec<String> {
+    let text = metrics_table(state, format).unwrap();
+    let mut lines = text.lines().map(str::to_string);
+    assert!(lines.next().unwrap().starts_with("API response times, 5m average by busiest, "));
+    assert_eq!(lines.next().as_deref(), Some(""));
+    lines.collect()
+}
+
+#[test]
+fn text_columns_line_up() {
+    let state = state(vec![common::metric("/api/orders", 1250.25, 42.0), common::metric("/health", 3.0, 1200.0)]);
+    assert_eq!(
+        table(&state, TableFormat::Text),
+        [
+            "URI          Avg (ms)  Req/min",
+            "-----------  --------  -------",
+            "/api/orders    1250.2       42",
+            "/health           3.0     1200",
+        ]
+    );
+}
+
+#[test]
+fn markdown_is_a_pipe_table() {
+    let state = state(vec![common::metric("/search|all", 80.0, 7.0)]);
+    assert_eq!(
+        table(&state, TableFormat::Markdown),
+        [
+            "| URI | Avg (ms) | Req/min |",
+            "| --- | ---: | ---: |",
+            r"| /search\|all | 80.0 | 7 |",
+        ]
+    );
+}
+
+#[test]
+fn columns_follow_the_ranking_and_bandwidth() {
+    let mut errors = common::metric("/api/pay", 300.0, 10.0);
+    errors.errors_per_min = 2.5;
+    errors.bytes_per_sec = Some(2048.0);
+    let mut state = state(vec![errors, common::metric("/api/cart", 20.0, 5.0)]);
+    state.metrics_ranking = Ranking::Errors;
+    let text = metrics_table(&state, TableFormat::Markdown).unwrap();
+    assert!(text.contains("| URI | Avg (ms) | Req/min | Err/min | BW |"), "{}", text);
+    assert!(text.contains("| /api/pay | 300.0 | 10 | 2.5 | 2.0K/s |"));
+    assert!(text.contains("| /api/cart | 20.0 | 5 | 0.0 | - |"));
+}
+
+#[test]
+fn nothing_to_copy_before_metrics() {
+    assert_eq!(metrics_table(&AppState::default(), TableFormat::Text), None);
+    assert_eq!(metrics_table(&state(Vec::new()), TableFormat::Markdown), None);
+}
@@ -0,0 +1,59 @@
+use rustdash::system::{SystemSnapshot, SystemView};
+use rustdash::tcp::{self, PortConnections, Socket};
+use rustdash::ui::AppState;
+
+mod common;
+
+// Listening on 8080 (IPv4, 2 of 128 waiting to be accepted) and 5432, with
+// connections to both and one outgoing from an ephemeral port
+const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1F90 00000000:0000 0A 00000080:00000002 00:00000000 00000000  1000        0 1 1 0 100 0 0 10 0
+   1: 0100007F:1538 00000000:0000 0A 00000000:00000000 00:00000000 00000000   999        0 2 1 0 100 0 0 10 0
+   2: 0100007F:1F90 0100007F:D431 01 00000000:00000000 00:00000000 00000000  1000        0 3 1 0 20 4 30 10 -1
+   3: 0100007F:1F90 0100007F:D432 01 00000000:00000000 00:00000000 00000000  1000        0 4 1 0 20 4 30 10 -1
+   4: 0100007F:1F90 0100007F:D433 08 00000000:00000000 00:00000000 00000000  1000        0 5 1 0 20 4 30 10 -1
+   5: 0100007F:1538 0100007F:D434 06 00000000:00000000 03:00000F3C 00000000     0        0 0 3 0
+   6: 0100007F:D435 5DB8D822:01BB 01 00000000:00000000 00:00000000 00000000  1000        0 6 1 0 20 4 30 10 -1
+";
+
+const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:1F90 00000000000000000000000000000000:0000 0A 00000080:00000001 00:00000000 00000000  1000        0 7 1 0 100 0 0 10 0
+   1: 0000000000000000FFFF00000100007F:1F90 0000000000000000FFFF00000100007F:D436 03 00000000:00000000 00:00000000 00000000  1000        0 0 1 0 20 4 30 10 -1
+";
+
+#[test]
+fn sockets_are_read_from_proc_net_tables() {
+    let sockets = tcp::parse(TCP);
+    assert_eq!(sockets.len(), 7);
+    assert_eq!(sockets[0], Socket { local_port: 8080, state: 0x0A, tx_queue: 128, rx_queue: 2 });
+    assert_eq!(sockets[5].state, 0x06);
+    assert_eq!(tcp::parse(TCP6)[1].local_port, 8080);
+    assert!(tcp::parse("header\ngarbage line\n").is_empty());
+}
+
+#[test]
+fn connections_are_counted_per_listening_port_across_ip_versions() {
+    let mut sockets = tcp::parse(TCP);
+    sockets.extend(tcp::parse(TCP6));
+    let ports = tcp::summarize(&sockets);
+    assert_eq!(
+        ports,
+        [
+            PortConnections { port: 5432, time_wait: 1, ..Default::default() },
+            PortConnections { port: 8080, established: 2, syn_recv: 1, close_wait: 1, accept_queue: 3, backlog: 128, ..Default::default() },
+        ],
+        "the outgoing connection isn't to a listening port"
+    );
+    assert!(!ports[1].queue_full());
+    assert!(PortConnections { accept_queue: 128, backlog: 128, ..Default::default() }.queue_full());
+}
+
+#[test]
+fn ports_are_listed_beside_the_processes() {
+    let ports = tcp::summarize(&tcp::parse(TCP));
+    let snapshot = SystemSnapshot { tcp: Some(ports), ..SystemSnapshot::default() };
+    let state = AppState { system_view: Some(SystemView { snapshot: Some(snapshot), ..SystemView::default() }), ..AppState::default() };
+    let screen = common::screen(&state, 120, 36);
+    assert!(screen.contains("TCP by listening port (2)"), "{}", screen);
+    assert!(screen.contains("  8080      2       0       0       1      0   2/128"), "{}", screen);
+}
@@ -0,0 +1,55 @@
+use rustdash::prometheus::UriMetric;
+use rustdash::templates::{UriTemplate, UriTemplates};
+
+mod common;
+
+fn rules(rules: &[(&str, &str)]) -> UriTemplates {
+    let rules: Vec<UriTemplate> = rules
+        .iter()
+        .map(|(pattern, template)| UriTemplate {
+            pattern: pattern.to_string(),
+            template: template.to_string(),
+        })
+        .collect();
+    UriTemplates::new(&rules).unwrap()
+}
+
+#[test]
+fn first_matching_rule_templates_the_whole_path() {
+    let templates = rules(&[
+        (r"/api/users/\d+", "/api/users/{id}"),
+        (r"/(\w+)/[0-9a-f-]{36}", "/$1/{uuid}"),
+    ]);
+
+    assert_eq!(templates.apply("/api/users/1234"), "/api/users/{id}");
+    assert_eq!(templates.apply("/orders/0b7c6a4e-5f0d-4d47-9f43-2b1f8a1d6c3e"), "/orders/{uuid}");
+    // Patterns are anchored: a prefix match is not enough
+    assert_eq!(templates.apply("/api/users/1234/avatar"), "/api/users/1234/avatar");
+    assert_eq!(templates.members_pattern("/api/users/{id}").as_deref(), Some(r"/api/users/\d+"));
+    assert!(templates.members_pattern("/health").is_none());
+
+    assert!(UriTemplates::new(&[UriTemplate { pattern: "(".to_string(), template: "x".to_string() }]).is_err());
+}
+
+#[test]
+fn grouped_rows_weight_latency_by_traffic() {
+    let templates = rules(&[(r"/api/users/\d+", "/api/users/{id}")]);
+
+    let grouped = templates.group_metrics(
+        [
+            common::metric("/api/users/1", 100.0, 30.0),
+            common::metric("/health", 2.0, 5.0),
+            common::metric("/api/users/2", 20.0, 10.0),
+        ]
+        .into_iter()
+        .map(|metric| UriMetric { errors_per_min: 1.0, ..metric })
+        .collect(),
+    );
+
+    assert_eq!(grouped.len(), 2);
+    assert_eq!(grouped[0].uri, "/api/users/{id}");
+    assert_eq!(grouped[0].request_count, 40.0);
+    assert_eq!(grouped[0].avg_duration_ms, 80.0);
+    assert_eq!(grouped[0].errors_per_min, 2.0);
+    assert_eq!(grouped[1].uri, "/health");
+}
@@ -0,0 +1,26 @@
+use rustdash::text::{self, Truncation};
+
+#[test]
+fn truncation_counts_columns_not_bytes() {
+    // Hangul takes two columns per character
+    assert_eq!(text::width("/주문/상세"), 10);
+    assert_eq!(text::truncate("/주문/상세", 10, Truncation::End), "/주문/상세");
+    assert_eq!(text::truncate("/주문/상세", 8, Truncation::End), "/주문/…");
+    // A wide character that would straddle the cut is left out whole
+    assert_eq!(text::truncate("/주문/상세", 7, Truncation::End), "/주문/…");
+    assert_eq!(text::truncate("/🚀🚀🚀", 5, Truncation::End), "/🚀…");
+    assert_eq!(text::fit("/주문", 8, Truncation::End), "/주문   ");
+    assert_eq!(text::width(&text::fit("/주문/상세정보", 9, Truncation::End)), 9);
+}
+
+#[test]
+fn middle_truncation_drops_whole_path_segments_first() {
+    let uri = "/api/v1/orders/{id}/details";
+    assert_eq!(text::truncate(uri, 14, Truncation::Middle), "/api/…/details");
+    assert_eq!(text::truncate(uri, 20, Truncation::Middle), "/api/v1/…/details");
+    assert_eq!(text::truncate(uri, 22, Truncation::Middle), "/api/v1/…/{id}/details");
+    assert_eq!(text::truncate("/api/주문/목록/상세", 15, Truncation::Middle), "/api/…/상세");
+    // Too narrow for the first and last segment: cut characters around the middle
+    assert_eq!(text::truncate(uri, 9, Truncation::Middle), "/api…ails");
+    assert_eq!(text::truncate("/averyveryverylongsegment", 9, Truncation::Middle), "/ave…ment");
+}
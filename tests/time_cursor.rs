@@ -0,0 +1,74 @@
+use rustdash::prometheus::UriHistory;
+use rustdash::ui::{self, AppState, UriDrillIn};
+
+mod common;
+
+const NOW: f64 = 1_700_000_000.0;
+
+/// A line every 10 seconds over the 30 minutes before NOW.
+fn state_with_logs() -> AppState {
+    let all_logs = (0..180).map(|i| common::log(((NOW - 1800.0 + i as f64 * 10.0) * 1e9) as i64, "INFO", &format!("line {}", i))).collect();
+    AppState { all_logs, ..AppState::default() }
+}
+
+fn history() -> UriHistory {
+    let samples: Vec<(f64, f64)> = (0..30).map(|i| (NOW - 1800.0 + i as f64 * 60.0, 100.0 + i as f64)).collect();
+    UriHistory { latency_ms: samples.clone(), requests_per_min: samples, queries: Vec::new() }
+}
+
+#[test]
+fn nearest_log_picks_the_closest_line_on_either_side() {
+    let logs = vec![common::log(10_000_000_000, "INFO", "a"), common::log(20_000_000_000, "INFO", "b"), common::log(40_000_000_000, "INFO", "c")];
+    assert_eq!(ui::nearest_log(&logs, 0.0), Some(0));
+    assert_eq!(ui::nearest_log(&logs, 14.0), Some(0));
+    assert_eq!(ui::nearest_log(&logs, 16.0), Some(1));
+    assert_eq!(ui::nearest_log(&logs, 35.0), Some(2));
+    assert_eq!(ui::nearest_log(&logs, 99.0), Some(2));
+    assert_eq!(ui::nearest_log(&[], 10.0), None);
+}
+
+#[test]
+fn setting_the_time_cursor_selects_and_centers_the_nearest_log() {
+    let mut state = state_with_logs();
+    state.set_time_cursor(Some(NOW - 900.0 + 3.0), 40);
+    assert_eq!(state.selected_log_index, Some(90));
+    let visible = state.get_visible_height(40);
+    assert_eq!(state.log_scroll_offset, 90 - visible / 2);
+    assert!(state.logs.iter().any(|log| log.message == "line 90"));
+
+    // Following the latest again goes back to the newest lines
+    state.set_time_cursor(None, 40);
+    assert_eq!(state.selected_log_index, None);
+    assert_eq!(state.logs.last().map(|log| log.message.as_str()), Some("line 179"));
+}
+
+#[test]
+fn drill_in_cursor_and_time_cursor_follow_each_other() {
+    let mut drill_in = UriDrillIn::new("/api/orders".to_string());
+    drill_in.history = Some(history());
+    assert_eq!(drill_in.time_cursor(), None);
+
+    drill_in.move_cursor(-2);
+    assert_eq!(drill_in.time_cursor(), Some(NOW - 1800.0 + 27.0 * 60.0));
+
+    let mut state = AppState { drill_in: Some(drill_in), ..state_with_logs() };
+    state.set_time_cursor(Some(NOW - 1800.0 + 10.0 * 60.0 + 20.0), 40);
+    assert_eq!(state.drill_in.as_ref().unwrap().cursor, Some(10));
+    assert_eq!(state.selected_log_index, Some(62));
+
+    state.set_time_cursor(None, 40);
+    assert_eq!(state.drill_in.as_ref().unwrap().cursor, None);
+}
+
+#[test]
+fn drill_in_shows_the_logs_at_the_cursor_beneath_the_chart() {
+    let mut drill_in = UriDrillIn::new("/api/orders".to_string());
+    drill_in.history = Some(history());
+    let mut state = AppState { drill_in: Some(drill_in), ..state_with_logs() };
+    state.set_time_cursor(Some(NOW - 900.0), 40);
+
+    let screen = common::screen(&state, 120, 40);
+    assert!(screen.contains(&format!("Logs at {}", ui::format_time(NOW - 900.0, "%H:%M:%S"))), "{}", screen);
+    assert!(screen.contains("line 90"));
+    assert!(!screen.contains("line 179"));
+}
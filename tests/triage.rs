@@ -0,0 +1,80 @@
+use chrono::{Local, TimeZone};
+use rustdash::log_actions::LogAction;
+use rustdash::synthetic::{CheckConfig, CheckRun, SyntheticCheck};
+use rustdash::triage::{Alert, Triage, TriagePopup, TriageState};
+use rustdash::ui::AppState;
+
+mod common;
+
+fn at(minute: u32) -> chrono::DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 5, 1, 12, minute, 0).unwrap()
+}
+
+fn failing_state() -> AppState {
+    let mut check = SyntheticCheck::new(CheckConfig { name: "orders".to_string(), ..CheckConfig::default() }).unwrap();
+    check.record(CheckRun { at: at(1), latency_ms: 10.0, status: Some(503), failures: vec!["status 503 (expected 2xx)".to_string()] });
+    AppState { synthetic_checks: vec![check], ..AppState::default() }
+}
+
+#[test]
+fn acknowledging_quiets_one_firing_while_ignoring_lasts_the_session() {
+    let mut triage = Triage::default();
+    let alert = Alert::Failing("orders".to_string());
+    assert!(triage.nags(&alert, at(1)));
+
+    triage.set_alert(alert.clone(), at(1), Some(TriageState::Acknowledged));
+    assert!(!triage.nags(&alert, at(1)));
+    assert!(triage.nags(&alert, at(7)), "firing again surfaces it");
+
+    triage.set_alert(alert.clone(), at(1), Some(TriageState::Investigating));
+    assert_eq!(triage.alert_state(&alert, at(1)), Some(TriageState::Investigating));
+    assert!(triage.nags(&alert, at(1)), "still shown while investigated");
+
+    triage.set_alert(alert.clone(), at(1), Some(TriageState::Ignored));
+    assert!(!triage.nags(&alert, at(7)));
+    triage.set_alert(alert.clone(), at(1), None);
+    assert!(triage.nags(&alert, at(1)));
+    assert_eq!(Alert::Silent("checkout".to_string()).to_string(), "SILENT checkout");
+}
+
+#[test]
+fn log_patterns_cover_lines_shaped_alike() {
+    let mut triage = Triage::default();
+    let pattern = triage.set_log("timeout after 30s calling db-7", Some(TriageState::Ignored));
+    assert_eq!(triage.log_state("timeout after 12s calling db-3"), Some(TriageState::Ignored));
+    assert_eq!(triage.log_state("connection refused"), None);
+
+    triage.set_log("timeout after 1s calling db-1", Some(TriageState::Acknowledged));
+    assert_eq!(triage.patterns.len(), 1, "the same pattern is triaged once");
+    assert_eq!(triage.patterns[0].pattern, pattern);
+    assert_eq!(triage.log_state("timeout after 30s calling db-7"), Some(TriageState::Acknowledged));
+    triage.clear_pattern(0);
+    assert_eq!(triage.log_state("timeout after 30s calling db-7"), None);
+
+    assert_eq!(LogAction::triage(None).len(), 3);
+    let offered = LogAction::triage(Some(TriageState::Ignored));
+    assert!(offered.contains(&LogAction::Triage(None)) && !offered.contains(&LogAction::Triage(Some(TriageState::Ignored))));
+}
+
+#[test]
+fn banner_leaves_out_acknowledged_alerts_and_tones_down_investigated_ones() {
+    let mut state = failing_state();
+    assert!(common::screen(&state, 120, 36).contains("FAILING orders: status 503 (expected 2xx) (since 12:01:00)"));
+
+    state.triage.set_alert(Alert::Failing("orders".to_string()), at(1), Some(TriageState::Investigating));
+    assert!(common::screen(&state, 120, 36).contains("FAILING orders: status 503 (expected 2xx) (since 12:01:00) [investigating]"));
+
+    state.triage.set_alert(Alert::Failing("orders".to_string()), at(1), Some(TriageState::Acknowledged));
+    assert!(!common::screen(&state, 120, 36).contains("FAILING"), "the endpoints bar is back");
+}
+
+#[test]
+fn popup_lists_firing_alerts_and_triaged_patterns() {
+    let mut state = failing_state();
+    state.triage.set_log("slow query 120ms", Some(TriageState::Investigating));
+    state.triage_popup = Some(TriagePopup::default());
+    let screen = common::screen(&state, 120, 36);
+    assert!(screen.contains(" Triage "), "{}", screen);
+    assert!(screen.contains("new           FAILING orders"), "{}", screen);
+    assert!(screen.contains(r"investigating slow query \d+ms"), "{}", screen);
+}
@@ -0,0 +1,14 @@
+use rustdash::config::Settings;
+
+#[test]
+fn an_ssh_host_that_looks_like_an_option_is_refused() {
+    let mut settings = Settings::default();
+    settings.loki.ssh_host = Some("bastion.example.com".to_string());
+    assert!(settings.validate().is_ok());
+
+    settings.loki.ssh_host = Some("-oProxyCommand=touch /tmp/x".to_string());
+    assert!(settings.validate().unwrap_err().to_string().contains("loki.ssh_host"));
+    settings.loki.ssh_host = None;
+    settings.prometheus.ssh_host = Some(" ".to_string());
+    assert!(settings.validate().is_err());
+}
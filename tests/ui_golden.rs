@@ -0,0 +1,133 @@
+//! Renders draw_ui for fixture states and compares the screen with the
+//! golden files in tests/golden. After an intended layout change, rerun
+//! with `UPDATE_GOLDEN=1 cargo test --test ui_golden` and review the diff.
+
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use regex::Regex;
+use rustdash::bench::synthetic_state;
+use rustdash::loki::{LevelCounts, LogEntry};
+use rustdash::prometheus::MetricsData;
+use rustdash::text::{self, Truncation};
+use rustdash::ui::{self, ActivePanel, AppState};
+use std::path::PathBuf;
+
+mod common;
+
+/// The screen as text, one line per row with trailing blanks cut. A wide
+/// character's second cell is left out, and clock times, which vary
+/// between runs, are masked.
+fn render(state: &AppState, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|frame| ui::draw_ui(frame, state)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let mut screen = String::new();
+    for y in 0..height {
+        let mut row = String::new();
+        let mut x = 0;
+        while x < width {
+            let symbol = buffer[(x, y)].symbol();
+            row.push_str(symbol);
+            x += text::width(symbol).max(1) as u16;
+        }
+        screen.push_str(row.trim_end());
+        screen.push('\n');
+    }
+    let clock = Regex::new(r"\d{2}:\d{2}:\d{2}").unwrap();
+    clock.replace_all(&screen, "hh:mm:ss").into_owned()
+}
+
+fn assert_golden(name: &str, screen: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", &format!("{}.txt", name)].iter().collect();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, screen).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("{} is missing; create it with UPDATE_GOLDEN=1", path.display()));
+    if golden != screen {
+        let row = golden.lines().zip(screen.lines()).position(|(want, got)| want != got).unwrap_or(0);
+        panic!(
+            "{} differs from {} at row {}:\nwant: {:?}\n got: {:?}\n\n{}",
+            name,
+            path.display(),
+            row,
+            golden.lines().nth(row).unwrap_or_default(),
+            screen.lines().nth(row).unwrap_or_default(),
+            screen
+        );
+    }
+}
+
+/// Default state, with the clock in the header pinned.
+fn fixture() -> AppState {
+    AppState { last_update: "2025-01-01 12:00:00".to_string(), ..AppState::default() }
+}
+
+#[test]
+fn empty_state() {
+    assert_golden("empty", &render(&fixture(), 100, 30));
+}
+
+#[test]
+fn terminal_below_the_minimum_size() {
+    assert_golden("tiny_terminal", &render(&fixture(), 60, 20));
+}
+
+#[test]
+fn huge_log_buffer_scrolled_to_a_selection() {
+    let mut state = AppState { last_update: fixture().last_update, ..synthetic_state(100_000, 500) };
+    state.selected_log_index = Some(49_996);
+    state.log_scroll_offset = 49_990;
+    state.expanded_log_index = Some(49_994);
+    state.update_visible_logs_with_height(40);
+    assert_golden("huge_logs_middle", &render(&state, 120, 40));
+
+    // Scrolling past the end clamps to the newest lines, here with the
+    // markers spelled out as in accessible mode
+    state.accessible = true;
+    state.selected_log_index = Some(99_999);
+    state.log_scroll_offset = usize::MAX;
+    state.expanded_log_index = None;
+    state.update_visible_logs_with_height(40);
+    assert_golden("huge_logs_end", &render(&state, 120, 40));
+}
+
+#[test]
+fn unicode_is_cut_by_display_width() {
+    let all_logs = [
+        ("INFO", "주문 처리 완료 order=1042 고객=김철수 배송지=서울특별시 강남구 테헤란로 427 위워크타워 10층 결제수단=카드 금액=₩129,000"),
+        ("WARN", "🍜 ramen-service 応答が遅い: p95=870ms しきい値=500ms 再試行 3/5 上流=inventory.internal:8443 トレースID=4bf92f3577b34da6"),
+        ("ERROR", "Ошибка оплаты: карта отклонена банком-эмитентом (код 05), пользователь ivan@example.ru, попытка 2 из 3"),
+        ("DEBUG", "e\u{301}te\u{301} combining marks and ｆｕｌｌｗｉｄｔｈ ｌｅｔｔｅｒｓ cut by columns, not bytes or chars, at the panel's right edge"),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, &(level, message))| LogEntry {
+        timestamp: format!("2025-01-01 12:00:{:02}", i),
+        stream: r#"{service_name="shop"}"#.to_string(),
+        ..common::log(1_735_732_800_000_000_000 + i as i64 * 1_000_000_000, level, message)
+    })
+    .collect::<Vec<_>>();
+    let mut state = AppState {
+        log_level_counts: LevelCounts::of(&all_logs),
+        all_logs,
+        metrics: Some(MetricsData {
+            http_requests_total: 420.0,
+            uri_metrics: vec![
+                common::metric("/api/상품/{id}/리뷰/목록/페이지/{page}/정렬/최신순", 125.0, 90.0),
+                common::metric("/検索/ラーメン/🍜/おすすめ", 48.5, 60.0),
+                common::metric("/api/orders", 12.0, 30.0),
+            ],
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            egress_bytes_per_sec: Vec::new(),
+        }),
+        uri_truncation: Truncation::Middle,
+        active_panel: ActivePanel::Logs,
+        selected_log_index: Some(1),
+        ..fixture()
+    };
+    state.update_visible_logs_with_height(30);
+    assert_golden("unicode", &render(&state, 100, 30));
+}
@@ -0,0 +1,63 @@
+use rustdash::fetch::RefreshScope;
+use rustdash::log_actions::LogFilter;
+use rustdash::prometheus::Ranking;
+use rustdash::ui::{AppState, TimeRange};
+use rustdash::undo::{ViewHistory, ViewState, MAX_UNDO};
+
+fn view(range: TimeRange) -> ViewState {
+    ViewState { log_filter: LogFilter::default(), time_range: range, ranking: Ranking::Busiest }
+}
+
+#[test]
+fn undo_and_redo_step_through_the_changes() {
+    let mut state = AppState::default();
+    state.set_log_filter(LogFilter::query(r#"{app="shop"}"#));
+    state.set_time_range(TimeRange::OneHour);
+    state.set_ranking(Ranking::Errors);
+
+    assert_eq!(state.undo_view(), Some(RefreshScope::Metrics));
+    assert_eq!(state.metrics_ranking, Ranking::Busiest);
+    assert_eq!(state.status, "Undid: ranking by busiest");
+    state.undo_view();
+    state.undo_view();
+    assert_eq!(state.view(), AppState::default().view());
+    assert_eq!(state.undo_view(), None, "nothing left to undo");
+
+    assert_eq!(state.redo_view(), Some(RefreshScope::Logs));
+    assert_eq!(state.log_filter.query.as_deref(), Some(r#"{app="shop"}"#));
+    assert_eq!(state.status, r#"Redid: query {app="shop"}"#);
+}
+
+#[test]
+fn a_new_change_drops_the_redo() {
+    let mut state = AppState::default();
+    state.set_time_range(TimeRange::OneHour);
+    state.undo_view();
+    state.set_time_range(TimeRange::OneDay);
+    assert_eq!(state.redo_view(), None);
+
+    // Setting what is already shown isn't a change
+    state.set_time_range(TimeRange::OneDay);
+    state.set_log_filter(LogFilter::default());
+    state.undo_view();
+    assert_eq!(state.metrics_time_range, AppState::default().metrics_time_range);
+    assert!(!state.view_history.can_undo());
+}
+
+#[test]
+fn history_keeps_the_latest_changes() {
+    let mut history = ViewHistory::default();
+    history.record(view(TimeRange::OneMin));
+    for _ in 1..MAX_UNDO {
+        history.record(view(TimeRange::OneHour));
+    }
+    history.record(view(TimeRange::OneDay));
+    let mut undone = 0;
+    let mut last = None;
+    while let Some(previous) = history.undo(view(TimeRange::All)) {
+        undone += 1;
+        last = Some(previous.time_range);
+    }
+    assert_eq!(undone, MAX_UNDO);
+    assert_eq!(last, Some(TimeRange::OneHour), "the oldest went first");
+}
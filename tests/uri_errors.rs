@@ -0,0 +1,47 @@
+use rustdash::loki::LogEntry;
+use rustdash::uri_errors::{self, DEFAULT_PATTERN};
+
+fn error(nanos: i64, message: &str) -> LogEntry {
+    LogEntry {
+        timestamp: String::new(),
+        nanos,
+        level: "ERROR".to_string(),
+        message: message.to_string(),
+        is_new: false,
+        stream: String::new(),
+        source: None,
+        local: false,
+    }
+}
+
+#[test]
+fn placeholders_match_one_path_segment() {
+    let regex = regex::Regex::new(&uri_errors::uri_pattern("/api/orders/{id}", DEFAULT_PATTERN)).unwrap();
+    assert!(regex.is_match("POST /api/orders/1234 failed"));
+    assert!(regex.is_match(r#"{"uri":"/api/orders/ab-12","status":500}"#));
+    assert!(!regex.is_match("GET /api/orders/1234/items 500"));
+    assert!(!regex.is_match("GET /api/orders 500"));
+}
+
+#[test]
+fn merged_rows_match_the_rest_of_the_path() {
+    let regex = regex::Regex::new(&uri_errors::uri_pattern("/static/*", DEFAULT_PATTERN)).unwrap();
+    assert!(regex.is_match("GET /static/js/app.js 500"));
+    assert!(!regex.is_match("GET /api/static 500"));
+}
+
+#[test]
+fn the_newest_matching_error_wins() {
+    let errors = [
+        error(1, "Unhandled exception in /api/orders/7: old"),
+        error(2, "Unhandled exception in /api/orders: list"),
+        error(3, "Unhandled exception in /api/orders/9: new"),
+    ];
+    let uris = ["/api/orders", "/api/orders/{id}", "/api/users"];
+
+    let latest = uri_errors::latest_errors(uris.into_iter(), &errors, DEFAULT_PATTERN);
+
+    assert_eq!(latest["/api/orders"].nanos, 2);
+    assert_eq!(latest["/api/orders/{id}"].nanos, 3);
+    assert!(!latest.contains_key("/api/users"));
+}
@@ -0,0 +1,135 @@
+use rustdash::panels::{PanelConfig, Preset};
+use rustdash::ui::{AppState, PanelsPage};
+use rustdash::variables::{self, Variable, VariableConfig, VariablePicker};
+
+mod common;
+
+fn variable(name: &str, include_all: bool, selected: Option<&str>) -> Variable {
+    let mut variable = Variable::new(VariableConfig {
+        name: name.to_string(),
+        label: name.to_string(),
+        metric: None,
+        include_all,
+        default: None,
+    });
+    variable.selected = selected.map(str::to_string);
+    variable
+}
+
+#[test]
+fn substitution_replaces_known_names_only() {
+    let vars = [variable("ns", false, Some("shop")), variable("instance", true, None)];
+    assert_eq!(
+        variables::substitute(r#"up{namespace="$ns", instance=~"${instance}"}"#, &vars),
+        r#"up{namespace="shop", instance=~".*"}"#
+    );
+    // Names end at the first character that can't be in one
+    assert_eq!(variables::substitute("$ns_total $nsx $ns.", &vars), "$ns_total $nsx shop.");
+    assert_eq!(variables::substitute("cost in $ and ${missing", &vars), "cost in $ and ${missing");
+}
+
+#[test]
+fn values_pick_the_default_or_the_first() {
+    let config = VariableConfig {
+        name: "ns".to_string(),
+        label: "namespace".to_string(),
+        metric: None,
+        include_all: false,
+        default: Some("shop".to_string()),
+    };
+    let mut with_default = Variable::new(config.clone());
+    with_default.set_values(vec!["shop".to_string(), "auth".to_string(), "shop".to_string()]);
+    assert_eq!(with_default.values, ["auth", "shop"]);
+    assert_eq!(with_default.display(), "shop");
+
+    let mut first = Variable::new(VariableConfig { default: None, ..config.clone() });
+    first.set_values(vec!["shop".to_string(), "auth".to_string()]);
+    assert_eq!(first.display(), "auth");
+
+    let mut all = Variable::new(VariableConfig { default: None, include_all: true, ..config });
+    all.set_values(vec!["shop".to_string()]);
+    assert_eq!(all.display(), "All");
+    assert_eq!(all.choices(), [None, Some("shop")]);
+    assert_eq!(VariablePicker::open(&[first], 0).selected, 0);
+}
+
+#[test]
+fn resolving_drops_matchers_on_all() {
+    let config: PanelConfig = toml::from_str(
+        "title = \"Requests of $job\"\npreset = \"counter\"\nmetric = \"http_requests_total\"\nlabels = { job = \"$job\", uri = \"$uri\" }",
+    )
+    .unwrap();
+    let resolved = variables::resolve(&config, &[variable("job", false, Some("shop")), variable("uri", true, None)]);
+    assert_eq!(resolved.title.as_deref(), Some("Requests of shop"));
+    let Preset::Counter { labels, .. } = resolved.preset else { panic!("still a counter") };
+    assert_eq!(labels.into_iter().collect::<Vec<_>>(), [("job".to_string(), "shop".to_string())]);
+}
+
+#[test]
+fn picker_lists_the_variables_and_values() {
+    let mut ns = variable("ns", true, Some("shop"));
+    ns.set_values(vec!["shop".to_string(), "platform".to_string()]);
+    let mut state = AppState {
+        variables: vec![ns, variable("instance", false, None)],
+        panels_page: Some(PanelsPage::default()),
+        ..AppState::default()
+    };
+    assert!(common::screen(&state, 120, 30).contains("Panels ns=shop instance=All ["));
+
+    state.variable_picker = Some(VariablePicker { variable: 0, selected: 2 });
+    let shown = common::screen(&state, 120, 30);
+    assert!(shown.contains(" Variables "), "{}", shown);
+    assert!(shown.contains("▶ $ns = shop"));
+    assert!(shown.contains("  $instance = All"));
+    assert!(shown.contains("Values of ns"));
+    assert!(shown.contains("    All"));
+    assert!(shown.contains("  ● shop"));
+}
+
+#[test]
+fn a_selection_gone_from_the_values_falls_back() {
+    let config = VariableConfig {
+        name: "pod".to_string(),
+        label: "pod".to_string(),
+        metric: Some(r#"kube_pod_info{namespace="$namespace"}"#.to_string()),
+        include_all: false,
+        default: Some("api-0".to_string()),
+    };
+    let mut pod = Variable::new(config.clone());
+    pod.set_values(vec!["api-0".to_string(), "api-1".to_string()]);
+    pod.selected = Some("api-1".to_string());
+    pod.set_values(vec!["api-1".to_string(), "api-2".to_string()]);
+    assert_eq!(pod.display(), "api-1", "still offered");
+
+    // The upstream namespace changed: the default isn't there either
+    pod.set_values(vec!["web-0".to_string(), "web-1".to_string()]);
+    assert_eq!(pod.display(), "web-0");
+    pod.set_values(vec!["api-0".to_string(), "web-0".to_string()]);
+    assert_eq!(pod.display(), "web-0");
+
+    let mut all = Variable::new(VariableConfig { include_all: true, default: None, ..config });
+    all.selected = Some("api-0".to_string());
+    all.set_values(vec!["web-0".to_string()]);
+    assert_eq!(all.display(), "All");
+}
+
+#[test]
+fn repeated_panels_follow_the_selection() {
+    let config: PanelConfig = toml::from_str("repeat = \"region\"\npreset = \"stat\"\nquery = 'up{region=\"$region\"}'").unwrap();
+    let plain: PanelConfig = toml::from_str("preset = \"stat\"\nquery = \"up\"").unwrap();
+    let mut region = variable("region", true, None);
+    region.set_values(vec!["eu".to_string(), "us".to_string()]);
+
+    let panels = variables::expand(&[plain, config.clone()], std::slice::from_ref(&region));
+    let repeats: Vec<_> = panels.iter().map(|(_, repeat)| repeat.clone()).collect();
+    assert_eq!(repeats, [None, Some((1, "eu".to_string())), Some((1, "us".to_string()))]);
+    let Preset::Stat { query, .. } = &panels[2].0.preset else { panic!("still a stat") };
+    assert_eq!(query, r#"up{region="us"}"#);
+
+    region.selected = Some("eu".to_string());
+    assert_eq!(variables::expand(std::slice::from_ref(&config), std::slice::from_ref(&region)).len(), 1);
+    // An unknown variable leaves the panel as one
+    let unknown = variables::expand(&[config], &[]);
+    assert_eq!(unknown.len(), 1);
+    assert!(unknown[0].1.is_none());
+}